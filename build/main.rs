@@ -1,7 +1,8 @@
 use std::{
-    env, io,
+    env, fs, io,
     path::{Path, PathBuf},
     process, str,
+    time::{SystemTime, UNIX_EPOCH},
 };
 
 use zip_extensions::zip_extract;
@@ -9,9 +10,33 @@ use zip_extensions::zip_extract;
 // Path to PROS release zip (relative to project root)
 const PROS_ZIP_STR: &str = "build/kernel@3.7.2.zip";
 
+// Path to PROS 4 release zip, used with the `pros-4` feature (relative to
+// project root)
+const PROS_4_ZIP_STR: &str = "build/kernel@4.1.0.zip";
+
 // Path to PROS wrapper.h (relative to project root)
 const PROS_WRAPPER_STR: &str = "build/wrapper.h";
 
+// Flags used to link the cold package, matching the PROS makefile
+const COLD_LINK_FLAGS: &[&str] = &[
+    "-mcpu=cortex-a9",
+    "-mfpu=neon-fp16",
+    "-mfloat-abi=softfp",
+    "-nostdlib",
+    "-Wl,--gc-keep-exported,--whole-archive,-lpros,-lstdc++,--no-whole-archive",
+    "-Wl,-Tv5.ld,--gc-sections,--start-group,-lpros,-lc,-lm,-lgcc,-lstdc++,--end-group,-Tv5-common.ld",
+];
+
+// Symbols which the hot image must provide itself, so are removed from the cold
+// package
+const HOT_SYMBOLS: &[&str] = &[
+    "install_hot_table",
+    "__libc_init_array",
+    "_PROS_COMPILE_DIRECTORY",
+    "_PROS_COMPILE_TIMESTAMP",
+    "_PROS_COMPILE_TIMESTAMP_INT",
+];
+
 // Types to be included by bindgen
 const WHITELISTED_TYPES: &[&str] = &[];
 
@@ -24,6 +49,7 @@ const WHITELISTED_FUNCS: &[&str] = &[
     "battery_get_current",
     "battery_get_temperature",
     "battery_get_voltage",
+    "competition_get_status",
     "controller_clear",
     "controller_clear_line",
     "controller_get_analog",
@@ -55,6 +81,22 @@ const WHITELISTED_FUNCS: &[&str] = &[
     "ext_adi_ultrasonic_get",
     "ext_adi_ultrasonic_init",
     "ext_adi_ultrasonic_shutdown",
+    "fclose",
+    "ferror",
+    "fflush",
+    "fopen",
+    "fread",
+    "fwrite",
+    "gps_get_error",
+    "gps_get_heading",
+    "gps_get_rotation",
+    "gps_get_status",
+    "gps_initialize_full",
+    "gps_set_data_rate",
+    "gps_set_offset",
+    "gps_set_position",
+    "gps_set_rotation",
+    "gps_tare_rotation",
     "imu_reset",
     "imu_get_rotation",
     "imu_get_heading",
@@ -79,6 +121,10 @@ const WHITELISTED_FUNCS: &[&str] = &[
     "imu_set_pitch",
     "imu_set_roll",
     "imu_set_yaw",
+    "imu_set_data_rate",
+    "lcd_initialize",
+    "lcd_is_initialized",
+    "lcd_set_text",
     "micros",
     "motor_get_actual_velocity",
     "motor_get_brake_mode",
@@ -128,11 +174,20 @@ const WHITELISTED_FUNCS: &[&str] = &[
     "rotation_reverse",
     "rotation_set_position",
     "rotation_set_reversed",
+    "screen_copy_area",
+    "screen_draw_line",
+    "screen_erase_rect",
+    "screen_get_pen",
+    "screen_print",
+    "screen_print_at",
+    "screen_set_pen",
+    "screen_touch_status",
     "sem_create",
     "sem_delete",
     "sem_get_count",
     "sem_post",
     "sem_wait",
+    "serctl",
     "serial_enable",
     "serial_flush",
     "serial_get_read_avail",
@@ -147,21 +202,37 @@ const WHITELISTED_FUNCS: &[&str] = &[
     "task_delay",
     "task_delete",
     "task_get_by_name",
+    "task_get_count",
     "task_get_current",
     "task_get_name",
     "task_get_priority",
     "task_get_state",
     "task_notify",
     "task_notify_take",
+    "task_set_priority",
+    "usd_is_installed",
+    "vision_clear_led",
+    "vision_get_by_sig",
+    "vision_get_by_size",
+    "vision_get_exposure",
+    "vision_get_object_count",
+    "vision_set_exposure",
+    "vision_set_led",
+    "vision_set_zero_point",
 ];
 
 // Variables to be included by bindgen
 const WHITELISTED_VARS: &[&str] = &[
+    "COMPETITION_AUTONOMOUS",
+    "COMPETITION_CONNECTED",
+    "COMPETITION_DISABLED",
     "INTERNAL_ADI_PORT",
     "PROS_ERR_",
     "PROS_ERR_F_",
+    "SERCTL_DISABLE_COBS",
     "TASK_PRIORITY_DEFAULT",
     "TASK_PRIORITY_MAX",
+    "TASK_PRIORITY_MIN",
     "TASK_STACK_DEPTH_DEFAULT",
 ];
 
@@ -170,16 +241,38 @@ fn main() -> Result<(), io::Error> {
     println!("cargo:rerun-if-changed=build/main.rs");
     println!("cargo:rerun-if-changed={}", PROS_ZIP_STR);
     println!("cargo:rerun-if-changed={}", PROS_WRAPPER_STR);
-
-    // define input paths
-    let pros_zip_path = PathBuf::from(PROS_ZIP_STR);
-    let wrapper_h_path = PathBuf::from(PROS_WRAPPER_STR);
+    if env::var_os("CARGO_FEATURE_PROS_4").is_some() {
+        println!("cargo:rerun-if-changed={}", PROS_4_ZIP_STR);
+    }
 
     // define output paths
     let out_dir_path = PathBuf::from(env::var("OUT_DIR").unwrap());
     let pros_extract_path = out_dir_path.join("pros");
     let bindings_gen_path = out_dir_path.join("bindings.rs");
 
+    capture_build_info(&out_dir_path);
+
+    // the simulation backend replaces the bindings entirely
+    if env::var_os("CARGO_FEATURE_SIM").is_some() {
+        return Ok(());
+    }
+
+    // define input paths
+    let pros_zip_path = if env::var_os("CARGO_FEATURE_PROS_4").is_some() {
+        let path = PathBuf::from(PROS_4_ZIP_STR);
+        if !path.is_file() {
+            panic!(
+                "the PROS 4 kernel is not bundled; download kernel@4.1.0.zip from \
+                 https://github.com/purduesigbots/pros/releases to {}",
+                PROS_4_ZIP_STR
+            );
+        }
+        path
+    } else {
+        PathBuf::from(PROS_ZIP_STR)
+    };
+    let wrapper_h_path = PathBuf::from(PROS_WRAPPER_STR);
+
     // extract pros firmware
     zip_extract(&pros_zip_path, &pros_extract_path)?;
 
@@ -192,6 +285,10 @@ fn main() -> Result<(), io::Error> {
     let args = get_args(&pros_extract_path);
     generate_bindings(&args, &wrapper_h_path, &bindings_gen_path)?;
 
+    if env::var_os("CARGO_FEATURE_HOT_COLD").is_some() {
+        link_cold_package(&pros_extract_path.join("firmware"), &out_dir_path)?;
+    }
+
     Ok(())
 }
 
@@ -268,3 +365,140 @@ fn generate_bindings(
 
     Ok(())
 }
+
+/// Links the PROS kernel into the cold package, generates a linker script
+/// which resolves symbols in the hot image against it, and copies the package
+/// binary next to the build artifacts for the upload script.
+fn link_cold_package(firmware_path: &Path, out_dir_path: &Path) -> Result<(), io::Error> {
+    let elf_path = out_dir_path.join("cold.package.elf");
+    let bin_path = out_dir_path.join("cold.package.bin");
+
+    run(process::Command::new("arm-none-eabi-gcc")
+        .arg(format!("-L{}", firmware_path.display()))
+        .args(COLD_LINK_FLAGS)
+        .arg("-o")
+        .arg(&elf_path));
+    run(process::Command::new("arm-none-eabi-objcopy")
+        .args(HOT_SYMBOLS.iter().map(|s| format!("--strip-symbol={}", s)))
+        .arg(&elf_path));
+    run(process::Command::new("arm-none-eabi-objcopy")
+        .args(["-O", "binary", "-R", ".hot_init"])
+        .arg(&elf_path)
+        .arg(&bin_path));
+
+    // Weak symbols are left out, so that the hot image can override them.
+    let output = run(process::Command::new("arm-none-eabi-nm")
+        .args(["--defined-only", "--extern-only"])
+        .arg(&elf_path));
+    let mut script = String::from("/* Generated by vex-rt: symbols in the cold package. */\n");
+    for line in str::from_utf8(&output).unwrap().lines() {
+        if let [address, "T" | "D" | "B" | "R", name] =
+            line.split_whitespace().collect::<Vec<_>>()[..]
+        {
+            script.push_str(&format!("{} = 0x{};\n", name, address));
+        }
+    }
+    fs::write(firmware_path.join("v5-cold.ld"), script)?;
+
+    // OUT_DIR is <target>/<profile>/build/<package>/out.
+    if let Some(profile_path) = out_dir_path.ancestors().nth(3) {
+        fs::copy(&bin_path, profile_path.join("cold.package.bin"))?;
+    }
+
+    Ok(())
+}
+
+/// Captures the compile timestamp, and the git commit of the project being
+/// built if it is in a repository.
+///
+/// The project is found from the output directory, which is inside its target
+/// directory. The script is rerun when the commit changes, so the timestamp is
+/// that of the first build of each commit.
+fn capture_build_info(out_dir_path: &Path) {
+    let (timestamp, local) = compile_timestamp();
+    println!("cargo:rustc-env=VEX_RT_COMPILE_TIMESTAMP={}", local);
+    println!("cargo:rustc-env=VEX_RT_COMPILE_TIMESTAMP_INT={}", timestamp);
+
+    let git = |args: &[&str]| {
+        let output = process::Command::new("git")
+            .arg("-C")
+            .arg(out_dir_path)
+            .args(args)
+            .output()
+            .ok()?;
+        let stdout = String::from_utf8(output.stdout).ok()?;
+        output.status.success().then(|| stdout.trim().to_string())
+    };
+
+    if let Some(hash) = git(&["rev-parse", "--short", "HEAD"]) {
+        // mark the hash if tracked files had uncommitted changes; like the
+        // timestamp, this is only updated when the script reruns below
+        let dirty = git(&["status", "--porcelain", "--untracked-files=no"])
+            .map_or(false, |status| !status.is_empty());
+        let suffix = if dirty { "-dirty" } else { "" };
+        println!("cargo:rustc-env=VEX_RT_GIT_HASH={}{}", hash, suffix);
+    }
+    if let Some(git_dir) = git(&["rev-parse", "--absolute-git-dir"]).map(PathBuf::from) {
+        let mut watched = vec![git_dir.join("HEAD"), git_dir.join("packed-refs")];
+        if let Some(head_ref) = git(&["symbolic-ref", "-q", "HEAD"]) {
+            watched.push(git_dir.join(head_ref));
+        }
+        for path in watched.iter().filter(|path| path.is_file()) {
+            println!("cargo:rerun-if-changed={}", path.display());
+        }
+    }
+}
+
+/// Runs a toolchain command, panicking if it fails, and returns its output.
+fn run(command: &mut process::Command) -> Vec<u8> {
+    let output = command
+        .output()
+        .expect("failed to execute the arm-none-eabi toolchain. is it installed?");
+    if !output.status.success() {
+        panic!(
+            "{:?} failed:\n{}",
+            command,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    output.stdout
+}
+
+/// Gets the current UNIX timestamp, along with the same time formatted as by
+/// the C `__DATE__` and `__TIME__` macros.
+fn compile_timestamp() -> (u64, String) {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let (days, seconds) = (timestamp / 86400, timestamp % 86400);
+
+    // Converts days since the epoch to a civil date; see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days.
+    let z = days + 719468;
+    let era = z / 146097;
+    let doe = z % 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+
+    (
+        timestamp,
+        format!(
+            "{} {:2} {} {:02}:{:02}:{:02}",
+            MONTHS[month as usize - 1],
+            day,
+            year,
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        ),
+    )
+}
@@ -0,0 +1,93 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::vec;
+use core::time::Duration;
+use vex_rt::prelude::*;
+
+struct DrivetrainBot {
+    controller: Controller,
+    drivetrain: DifferentialDrivetrain,
+    path: Path,
+}
+
+impl Robot for DrivetrainBot {
+    fn new(p: Peripherals) -> Self {
+        let left = MotorGroup::new(vec![
+            p.port01
+                .into_motor(Gearset::EighteenToOne, EncoderUnits::Degrees, false)
+                .unwrap(),
+            p.port02
+                .into_motor(Gearset::EighteenToOne, EncoderUnits::Degrees, false)
+                .unwrap(),
+        ]);
+        let right = MotorGroup::new(vec![
+            p.port09
+                .into_motor(Gearset::EighteenToOne, EncoderUnits::Degrees, true)
+                .unwrap(),
+            p.port10
+                .into_motor(Gearset::EighteenToOne, EncoderUnits::Degrees, true)
+                .unwrap(),
+        ]);
+
+        // 4" wheels, 30cm apart, driven directly by the motors.
+        let config = DrivetrainConfig::new(
+            Conversions::new(Inches(4.0)).gearset(Gearset::EighteenToOne),
+            Millimeters(300.0),
+        );
+
+        DrivetrainBot {
+            controller: p.master_controller,
+            drivetrain: DifferentialDrivetrain::new(left, right, config)
+                .with_imu(p.port11.into_imu()),
+            // Generate the autonomous path up front, rather than during the
+            // match.
+            path: Path::quintic(
+                &[
+                    Pose2d::new(Vec2::new(Inches(24.0), Millimeters(0.0)), Degrees(90.0)),
+                    Pose2d::new(
+                        Vec2::new(Millimeters(900.0), Millimeters(900.0)),
+                        Degrees(0.0),
+                    ),
+                    Pose2d::new(
+                        Vec2::new(Millimeters(900.0), Millimeters(1500.0)),
+                        Degrees(0.0),
+                    ),
+                ],
+                Millimeters(20.0),
+            ),
+        }
+    }
+
+    fn autonomous(&mut self, ctx: Context) {
+        self.drivetrain.drive_distance(&ctx, Inches(24.0)).unwrap();
+        self.drivetrain
+            .turn_to_heading(&ctx, Degrees(90.0))
+            .unwrap();
+        self.drivetrain
+            .follow_path(&ctx, &self.path, Millimeters(200.0))
+            .unwrap();
+    }
+
+    fn opcontrol(&mut self, ctx: Context) {
+        let mut l = Loop::new(Duration::from_millis(10));
+        loop {
+            self.drivetrain
+                .arcade(
+                    self.controller.left_stick.get_y().unwrap(),
+                    self.controller.right_stick.get_x().unwrap(),
+                )
+                .unwrap();
+            self.drivetrain.update_odometry().unwrap();
+
+            select! {
+                _ = ctx.done() => break,
+                _ = l.select() => continue,
+            }
+        }
+    }
+}
+
+entry!(DrivetrainBot);
@@ -22,7 +22,10 @@ impl Robot for UltrasonicBot {
                 Ok(r) => {
                     println!("{}", r);
                 }
-                Err(AdiUltrasonicError::NoReading) => {
+                Err(DeviceError {
+                    kind: DeviceErrorKind::NoReading,
+                    ..
+                }) => {
                     println!("<no reading>");
                 }
                 e => {
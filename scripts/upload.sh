@@ -1,6 +1,21 @@
 NAME="${1##*/}"
 BIN="${1}.bin"
-arm-none-eabi-objcopy -O binary -R .hot_init "${1}" "${BIN}"
+
+# Programs linked against a cold package start in hot memory.
+ENTRY="$(arm-none-eabi-readelf -h "${1}" | sed -n 's/.*Entry point address: *//p')"
+if [ $((ENTRY)) -ge $((0x07800000)) ]; then
+    DIR="$(dirname "${1}")"
+    COLD="${DIR}/cold.package.bin"
+    [ -f "${COLD}" ] || COLD="$(dirname "${DIR}")/cold.package.bin"
+    arm-none-eabi-objcopy -O binary "${1}" "${BIN}"
+    METADATA="\"cold_addr\": \"58720256\",
+                    \"cold_output\": \"${COLD}\",
+                    \"hot_addr\": \"125829120\",
+                    \"hot_output\": \"${BIN}\","
+else
+    arm-none-eabi-objcopy -O binary -R .hot_init "${1}" "${BIN}"
+    METADATA=""
+fi
 
 cat > project.pros << EOL
 {
@@ -12,6 +27,7 @@ cat > project.pros << EOL
             "kernel": {
                 "location": "",
                 "metadata": {
+                    ${METADATA}
                     "origin": "pros-mainline",
                     "output": "${BIN}"
                 },
@@ -1,6 +1,6 @@
 use crate::{
-    bindings,
-    error::{get_errno, Error},
+    bindings, diagnostics,
+    error::{DeviceError, DevicePort},
     rtos::DataSource,
 };
 
@@ -19,16 +19,17 @@ impl AdiAnalog {
     /// mutable references to the same ADI analog reader. You likely want to
     /// implement [`Robot::new()`](crate::robot::Robot::new()) instead.
     pub unsafe fn new(port: u8, expander_port: u8) -> Result<Self, AdiAnalogError> {
+        let device = Self {
+            port,
+            expander_port,
+        };
         match bindings::ext_adi_port_set_config(
             expander_port,
             port,
             bindings::adi_port_config_e_E_ADI_ANALOG_IN,
         ) {
-            bindings::PROS_ERR_ => Err(AdiAnalogError::from_errno()),
-            _ => Ok(Self {
-                port,
-                expander_port,
-            }),
+            bindings::PROS_ERR_ => Err(device.error()),
+            _ => Ok(device),
         }
     }
 
@@ -48,7 +49,7 @@ impl AdiAnalog {
     /// Returns: The average sensor value computed by this function.
     pub fn calibrate(&mut self) -> Result<i32, AdiAnalogError> {
         match unsafe { bindings::ext_adi_analog_calibrate(self.expander_port, self.port) } {
-            bindings::PROS_ERR_ => Err(AdiAnalogError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             x => Ok(x),
         }
     }
@@ -64,7 +65,7 @@ impl AdiAnalog {
     /// reflects an input voltage of nearly 5 V
     pub fn read(&self) -> Result<i32, AdiAnalogError> {
         match unsafe { bindings::ext_adi_analog_read(self.expander_port, self.port) } {
-            bindings::PROS_ERR_ => Err(AdiAnalogError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             x => Ok(x),
         }
     }
@@ -82,7 +83,7 @@ impl AdiAnalog {
     /// from -4095 to 4095.
     pub fn read_calibrated(&self) -> Result<i32, AdiAnalogError> {
         match unsafe { bindings::ext_adi_analog_read_calibrated(self.expander_port, self.port) } {
-            bindings::PROS_ERR_ => Err(AdiAnalogError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             x => Ok(x),
         }
     }
@@ -106,10 +107,20 @@ impl AdiAnalog {
     pub fn read_calibrated_hr(&self) -> Result<i32, AdiAnalogError> {
         match unsafe { bindings::ext_adi_analog_read_calibrated_HR(self.expander_port, self.port) }
         {
-            bindings::PROS_ERR_ => Err(AdiAnalogError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             x => Ok(x),
         }
     }
+
+    fn error(&self) -> DeviceError {
+        diagnostics::record_error(DeviceError::from_errno(
+            DevicePort::Adi {
+                port: self.port,
+                expander_port: self.expander_port,
+            },
+            "an ADI analog input",
+        ))
+    }
 }
 
 impl DataSource for AdiAnalog {
@@ -122,35 +133,33 @@ impl DataSource for AdiAnalog {
     }
 }
 
-/// Represents possible errors for ADI analog operations.
-#[derive(Debug)]
-pub enum AdiAnalogError {
-    /// Ports are out of range (1-8).
-    PortsOutOfRange,
-    /// Ports cannot be configured as an ADI Analog input.
-    PortsNotAnalogInput,
-    /// Unknown error.
-    Unknown(i32),
+/// The analog-to-digital converter of the ADI ports, for use with
+/// [`embedded_hal::adc::OneShot`]. Conversions read the raw 12-bit value of an
+/// [`AdiAnalog`] port, as [`AdiAnalog::read()`] does.
+#[cfg(feature = "embedded-hal")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AdiAdc;
+
+#[cfg(feature = "embedded-hal")]
+impl embedded_hal::adc::Channel<AdiAdc> for AdiAnalog {
+    // ADI ports are chosen at runtime, so the channel is identified by the
+    // `AdiAnalog` value rather than by its type.
+    type ID = ();
+
+    #[inline]
+    fn channel() -> Self::ID {}
 }
 
-impl AdiAnalogError {
-    fn from_errno() -> Self {
-        match get_errno() {
-            libc::ENXIO => Self::PortsOutOfRange,
-            libc::EADDRINUSE => Self::PortsNotAnalogInput,
-            x => Self::Unknown(x),
-        }
-    }
-}
+#[cfg(feature = "embedded-hal")]
+impl embedded_hal::adc::OneShot<AdiAdc, u16, AdiAnalog> for AdiAdc {
+    type Error = AdiAnalogError;
 
-impl From<AdiAnalogError> for Error {
-    fn from(err: AdiAnalogError) -> Self {
-        match err {
-            AdiAnalogError::PortsOutOfRange => Error::Custom("ports out of range".into()),
-            AdiAnalogError::PortsNotAnalogInput => {
-                Error::Custom("ports not an adi analog input".into())
-            }
-            AdiAnalogError::Unknown(n) => Error::System(n),
-        }
+    #[inline]
+    fn read(&mut self, pin: &mut AdiAnalog) -> nb::Result<u16, Self::Error> {
+        Ok(AdiAnalog::read(pin)? as u16)
     }
 }
+
+/// Represents possible errors for ADI analog input operations. This is an alias
+/// of [`DeviceError`].
+pub type AdiAnalogError = DeviceError;
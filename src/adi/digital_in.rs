@@ -1,6 +1,6 @@
 use crate::{
-    bindings,
-    error::{get_errno, Error},
+    bindings, diagnostics,
+    error::{DeviceError, DevicePort},
     prelude::DataSource,
 };
 
@@ -19,27 +19,38 @@ impl AdiDigitalInput {
     /// mutable references to the same ADI digital input. You likely want to
     /// implement [`Robot::new()`](crate::robot::Robot::new()) instead.
     pub unsafe fn new(port: u8, expander_port: u8) -> Result<Self, AdiDigitalInputError> {
+        let device = Self {
+            port,
+            expander_port,
+        };
         match bindings::ext_adi_port_set_config(
             expander_port,
             port,
             bindings::adi_port_config_e_E_ADI_DIGITAL_IN,
         ) {
-            bindings::PROS_ERR_ => Err(AdiDigitalInputError::from_errno()),
-            _ => Ok(Self {
-                port,
-                expander_port,
-            }),
+            bindings::PROS_ERR_ => Err(device.error()),
+            _ => Ok(device),
         }
     }
 
     /// Gets the digital value (true or false) of the input.
     pub fn read(&self) -> Result<bool, AdiDigitalInputError> {
         match unsafe { bindings::ext_adi_digital_read(self.expander_port, self.port) } {
-            bindings::PROS_ERR_ => Err(AdiDigitalInputError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             0 => Ok(false),
             _ => Ok(true),
         }
     }
+
+    fn error(&self) -> DeviceError {
+        diagnostics::record_error(DeviceError::from_errno(
+            DevicePort::Adi {
+                port: self.port,
+                expander_port: self.expander_port,
+            },
+            "an ADI digital input",
+        ))
+    }
 }
 
 impl DataSource for AdiDigitalInput {
@@ -52,35 +63,21 @@ impl DataSource for AdiDigitalInput {
     }
 }
 
-/// Represents possible errors for ADI digital input operations.
-#[derive(Debug)]
-pub enum AdiDigitalInputError {
-    /// Port is out of range (1-8).
-    PortsOutOfRange,
-    /// Port cannot be configured as an ADI digital input.
-    PortsNotDigitalInput,
-    /// Unknown error.
-    Unknown(i32),
-}
+#[cfg(feature = "embedded-hal")]
+impl embedded_hal::digital::v2::InputPin for AdiDigitalInput {
+    type Error = AdiDigitalInputError;
 
-impl AdiDigitalInputError {
-    fn from_errno() -> Self {
-        match get_errno() {
-            libc::ENXIO => Self::PortsOutOfRange,
-            libc::EADDRINUSE => Self::PortsNotDigitalInput,
-            x => Self::Unknown(x),
-        }
+    #[inline]
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        AdiDigitalInput::read(self)
     }
-}
 
-impl From<AdiDigitalInputError> for Error {
-    fn from(err: AdiDigitalInputError) -> Self {
-        match err {
-            AdiDigitalInputError::PortsOutOfRange => Error::Custom("port is out of range".into()),
-            AdiDigitalInputError::PortsNotDigitalInput => {
-                Error::Custom("port is not an ADI digital input".into())
-            }
-            AdiDigitalInputError::Unknown(n) => Error::System(n),
-        }
+    #[inline]
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        Ok(!AdiDigitalInput::read(self)?)
     }
 }
+
+/// Represents possible errors for ADI digital input operations. This is an
+/// alias of [`DeviceError`].
+pub type AdiDigitalInputError = DeviceError;
@@ -1,6 +1,6 @@
 use crate::{
-    bindings,
-    error::{get_errno, Error},
+    bindings, diagnostics,
+    error::{DeviceError, DevicePort},
 };
 
 /// A struct which represents a V5 ADI port configured as an ADI digital output.
@@ -18,57 +18,54 @@ impl AdiDigitalOutput {
     /// mutable references to the same ADI digital output. You likely want to
     /// implement [`Robot::new()`](crate::robot::Robot::new()) instead.
     pub unsafe fn new(port: u8, expander_port: u8) -> Result<Self, AdiDigitalOutputError> {
+        let device = Self {
+            port,
+            expander_port,
+        };
         match bindings::ext_adi_port_set_config(
             expander_port,
             port,
             bindings::adi_port_config_e_E_ADI_DIGITAL_OUT,
         ) {
-            bindings::PROS_ERR_ => Err(AdiDigitalOutputError::from_errno()),
-            _ => Ok(Self {
-                port,
-                expander_port,
-            }),
+            bindings::PROS_ERR_ => Err(device.error()),
+            _ => Ok(device),
         }
     }
 
     /// Sets the digital value (true or false) of the output.
     pub fn write(&mut self, value: bool) -> Result<(), AdiDigitalOutputError> {
         match unsafe { bindings::ext_adi_digital_write(self.expander_port, self.port, value) } {
-            bindings::PROS_ERR_ => Err(AdiDigitalOutputError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
-}
 
-/// Represents possible errors for ADI digital output operations.
-#[derive(Debug)]
-pub enum AdiDigitalOutputError {
-    /// Port is out of range (1-8).
-    PortsOutOfRange,
-    /// Port cannot be configured as an ADI digital output.
-    PortsNotDigitalOutput,
-    /// Unknown error.
-    Unknown(i32),
+    fn error(&self) -> DeviceError {
+        diagnostics::record_error(DeviceError::from_errno(
+            DevicePort::Adi {
+                port: self.port,
+                expander_port: self.expander_port,
+            },
+            "an ADI digital output",
+        ))
+    }
 }
 
-impl AdiDigitalOutputError {
-    fn from_errno() -> Self {
-        match get_errno() {
-            libc::ENXIO => Self::PortsOutOfRange,
-            libc::EADDRINUSE => Self::PortsNotDigitalOutput,
-            x => Self::Unknown(x),
-        }
+#[cfg(feature = "embedded-hal")]
+impl embedded_hal::digital::v2::OutputPin for AdiDigitalOutput {
+    type Error = AdiDigitalOutputError;
+
+    #[inline]
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.write(false)
     }
-}
 
-impl From<AdiDigitalOutputError> for Error {
-    fn from(err: AdiDigitalOutputError) -> Self {
-        match err {
-            AdiDigitalOutputError::PortsOutOfRange => Error::Custom("port is out of range".into()),
-            AdiDigitalOutputError::PortsNotDigitalOutput => {
-                Error::Custom("port is not an adi digital output".into())
-            }
-            AdiDigitalOutputError::Unknown(n) => Error::System(n),
-        }
+    #[inline]
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.write(true)
     }
 }
+
+/// Represents possible errors for ADI digital output operations. This is an
+/// alias of [`DeviceError`].
+pub type AdiDigitalOutputError = DeviceError;
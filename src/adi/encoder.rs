@@ -1,12 +1,15 @@
 use crate::{
     bindings,
-    error::{get_errno, Error},
+    device::RotarySensor,
+    diagnostics,
+    error::{DeviceError, DevicePort},
     rtos::DataSource,
 };
 
 /// A struct which represents a V5 ADI port configured as an ADI encoder.
 pub struct AdiEncoder {
     port: bindings::ext_adi_encoder_t,
+    device_port: DevicePort,
 }
 
 impl AdiEncoder {
@@ -23,9 +26,19 @@ impl AdiEncoder {
         reverse: bool,
         extender_port: u8,
     ) -> Result<AdiEncoder, AdiEncoderError> {
+        let device_port = DevicePort::Adi {
+            port: top_port,
+            expander_port: extender_port,
+        };
         match bindings::ext_adi_encoder_init(extender_port, top_port, bottom_port, reverse) {
-            bindings::PROS_ERR_ => Err(AdiEncoderError::from_errno()),
-            x => Ok(AdiEncoder { port: x }),
+            bindings::PROS_ERR_ => Err(diagnostics::record_error(DeviceError::from_errno(
+                device_port,
+                "an ADI encoder",
+            ))),
+            x => Ok(AdiEncoder {
+                port: x,
+                device_port,
+            }),
         }
     }
 
@@ -34,7 +47,7 @@ impl AdiEncoder {
     /// necessary to call this method before stopping or starting an encoder.
     pub fn reset(&mut self) -> Result<(), AdiEncoderError> {
         match unsafe { bindings::ext_adi_encoder_reset(self.port) } {
-            bindings::PROS_ERR_ => Err(AdiEncoderError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
@@ -43,10 +56,14 @@ impl AdiEncoder {
     /// There are 360 ticks in one revolution.
     pub fn get(&self) -> Result<i32, AdiEncoderError> {
         match unsafe { bindings::ext_adi_encoder_get(self.port) } {
-            bindings::PROS_ERR_ => Err(AdiEncoderError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             x => Ok(x),
         }
     }
+
+    fn error(&self) -> DeviceError {
+        diagnostics::record_error(DeviceError::from_errno(self.device_port, "an ADI encoder"))
+    }
 }
 
 impl DataSource for AdiEncoder {
@@ -59,49 +76,27 @@ impl DataSource for AdiEncoder {
     }
 }
 
-impl Drop for AdiEncoder {
-    fn drop(&mut self) {
-        if let bindings::PROS_ERR_ = unsafe { bindings::ext_adi_encoder_shutdown(self.port) } {
-            panic!(
-                "failed to shutdown ADI encoder: {:?}",
-                AdiEncoderError::from_errno()
-            );
-        }
+impl RotarySensor for AdiEncoder {
+    #[inline]
+    fn position(&self) -> Result<f64, DeviceError> {
+        // There are 360 ticks in one revolution.
+        Ok(self.get()? as f64)
     }
-}
 
-/// Represents possible errors for ADI encoder operations.
-#[derive(Debug)]
-pub enum AdiEncoderError {
-    /// Ports are out of range (1-8).
-    PortsOutOfRange,
-    /// Ports cannot be configured as an ADI encoder.
-    PortsNotAdiEncoder,
-    /// Ports are from non matching extenders.
-    PortNonMatchingExtenders,
-    /// Unknown error.
-    Unknown(i32),
-}
-
-impl AdiEncoderError {
-    fn from_errno() -> Self {
-        match get_errno() {
-            libc::ENXIO => Self::PortsOutOfRange,
-            libc::ENODEV => Self::PortsNotAdiEncoder,
-            x => Self::Unknown(x),
-        }
+    #[inline]
+    fn reset_position(&mut self) -> Result<(), DeviceError> {
+        self.reset()
     }
 }
 
-impl From<AdiEncoderError> for Error {
-    fn from(err: AdiEncoderError) -> Self {
-        match err {
-            AdiEncoderError::PortsOutOfRange => Error::Custom("ports out of range".into()),
-            AdiEncoderError::PortsNotAdiEncoder => Error::Custom("ports not an adi encoder".into()),
-            AdiEncoderError::PortNonMatchingExtenders => {
-                Error::Custom("ports from non-matching extenders".into())
-            }
-            AdiEncoderError::Unknown(n) => Error::System(n),
+impl Drop for AdiEncoder {
+    fn drop(&mut self) {
+        if let bindings::PROS_ERR_ = unsafe { bindings::ext_adi_encoder_shutdown(self.port) } {
+            panic!("failed to shutdown ADI encoder: {:?}", self.error());
         }
     }
 }
+
+/// Represents possible errors for ADI encoder operations. This is an alias of
+/// [`DeviceError`].
+pub type AdiEncoderError = DeviceError;
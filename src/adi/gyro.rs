@@ -1,14 +1,13 @@
-use alloc::string::ToString;
-
 use crate::{
-    bindings,
-    error::{get_errno, Error},
+    bindings, diagnostics,
+    error::{DeviceError, DevicePort},
     rtos::DataSource,
 };
 
 /// A struct which represents a V5 ADI port configured to be an ADI gyro.
 pub struct AdiGyro {
     port: bindings::ext_adi_gyro_t,
+    device_port: DevicePort,
 }
 impl AdiGyro {
     /// Initializes a gyroscope on the given port.
@@ -25,16 +24,26 @@ impl AdiGyro {
         multiplier: f64,
         extender_port: u8,
     ) -> Result<Self, AdiGyroError> {
+        let device_port = DevicePort::Adi {
+            port: adi_port,
+            expander_port: extender_port,
+        };
         match bindings::ext_adi_gyro_init(extender_port, adi_port, multiplier) {
-            bindings::PROS_ERR_ => Err(AdiGyroError::from_errno()),
-            x => Ok(Self { port: x }),
+            bindings::PROS_ERR_ => Err(diagnostics::record_error(DeviceError::from_errno(
+                device_port,
+                "an ADI gyro",
+            ))),
+            x => Ok(Self {
+                port: x,
+                device_port,
+            }),
         }
     }
 
     /// Resets the gyroscope value to zero.
     pub fn reset(&mut self) -> Result<(), AdiGyroError> {
         match unsafe { bindings::ext_adi_gyro_reset(self.port) } {
-            bindings::PROS_ERR_ => Err(AdiGyroError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
@@ -47,11 +56,15 @@ impl AdiGyro {
     pub fn get(&self) -> Result<f64, AdiGyroError> {
         let out = unsafe { bindings::ext_adi_gyro_get(self.port) };
         if out == bindings::PROS_ERR_F_ {
-            Err(AdiGyroError::from_errno())
+            Err(self.error())
         } else {
             Ok(out)
         }
     }
+
+    fn error(&self) -> DeviceError {
+        diagnostics::record_error(DeviceError::from_errno(self.device_port, "an ADI gyro"))
+    }
 }
 
 impl DataSource for AdiGyro {
@@ -67,39 +80,11 @@ impl DataSource for AdiGyro {
 impl Drop for AdiGyro {
     fn drop(&mut self) {
         if let bindings::PROS_ERR_ = unsafe { bindings::ext_adi_gyro_shutdown(self.port) } {
-            panic!(
-                "failed to shutdown ADI gyro: {:?}",
-                AdiGyroError::from_errno()
-            );
+            panic!("failed to shutdown ADI gyro: {:?}", self.error());
         }
     }
 }
 
-/// Represents possible errors for ADI gyro operations.
-#[derive(Debug)]
-pub enum AdiGyroError {
-    /// Port is out of range (1-8).
-    PortOutOfRange,
-    /// Port cannot be configured as an ADI encoder.
-    PortNotAdiEncoder,
-    /// Unknown error.
-    Unknown(i32),
-}
-impl AdiGyroError {
-    fn from_errno() -> Self {
-        match get_errno() {
-            libc::ENXIO => Self::PortOutOfRange,
-            libc::EADDRINUSE => Self::PortNotAdiEncoder,
-            x => Self::Unknown(x),
-        }
-    }
-}
-impl From<AdiGyroError> for Error {
-    fn from(err: AdiGyroError) -> Self {
-        match err {
-            AdiGyroError::PortOutOfRange => Error::Custom("port out of range".to_string()),
-            AdiGyroError::PortNotAdiEncoder => Error::Custom("port not an adi gyro".to_string()),
-            AdiGyroError::Unknown(n) => Error::System(n),
-        }
-    }
-}
+/// Represents possible errors for ADI gyro operations. This is an alias of
+/// [`DeviceError`].
+pub type AdiGyroError = DeviceError;
@@ -6,7 +6,10 @@ use super::{
     AdiUltrasonicError,
 };
 
-use crate::bindings;
+use crate::{
+    bindings,
+    error::{DeviceError, DeviceErrorKind, DevicePort},
+};
 
 /// A struct which represents an unconfigured ADI port.
 pub struct AdiPort {
@@ -39,6 +42,15 @@ impl AdiPort {
         }
     }
 
+    #[inline]
+    /// Gets the port number and expander port of this port.
+    pub fn device_port(&self) -> DevicePort {
+        DevicePort::Adi {
+            port: self.port,
+            expander_port: self.expander_port,
+        }
+    }
+
     /// Turns this port into an ADI analog input.
     #[inline]
     pub fn into_adi_analog(self) -> Result<AdiAnalog, AdiAnalogError> {
@@ -109,7 +121,10 @@ impl TryFrom<(AdiPort, AdiPort)> for AdiEncoder {
     /// [`AdiEncoder`](crate::adi::AdiEncoder).
     fn try_from(ports: (AdiPort, AdiPort)) -> Result<Self, Self::Error> {
         if ports.0.expander_port != ports.1.expander_port {
-            return Err(AdiEncoderError::PortNonMatchingExtenders);
+            return Err(DeviceError::new(
+                DeviceErrorKind::NonMatchingExpanders,
+                ports.0.device_port(),
+            ));
         }
 
         let top_port;
@@ -127,11 +142,19 @@ impl TryFrom<(AdiPort, AdiPort)> for AdiEncoder {
                 bottom_port = ports.0;
                 reversed = true;
             }
-            Ordering::Equal => return Err(AdiEncoderError::PortsOutOfRange),
+            Ordering::Equal => {
+                return Err(DeviceError::new(
+                    DeviceErrorKind::PortOutOfRange,
+                    ports.1.device_port(),
+                ))
+            }
         }
 
         if bottom_port.port - top_port.port != 1 || bottom_port.port % 2 != 0 {
-            return Err(AdiEncoderError::PortsOutOfRange);
+            return Err(DeviceError::new(
+                DeviceErrorKind::PortOutOfRange,
+                bottom_port.device_port(),
+            ));
         }
 
         unsafe {
@@ -159,7 +182,10 @@ impl TryFrom<(AdiPort, AdiPort)> for AdiUltrasonic {
 
     fn try_from(ports: (AdiPort, AdiPort)) -> Result<Self, Self::Error> {
         if ports.0.expander_port != ports.1.expander_port {
-            Err(AdiUltrasonicError::PortNonMatchingExtenders)
+            Err(DeviceError::new(
+                DeviceErrorKind::NonMatchingExpanders,
+                ports.1.device_port(),
+            ))
         } else {
             unsafe { AdiUltrasonic::new(ports.0.port, ports.1.port, ports.0.expander_port) }
         }
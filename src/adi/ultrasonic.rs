@@ -1,12 +1,15 @@
 use crate::{
     bindings,
-    error::{get_errno, Error},
+    device::DistanceLike,
+    diagnostics,
+    error::{DeviceError, DeviceErrorKind, DevicePort},
     rtos::DataSource,
 };
 
 /// Represents a V5 ADI port pair configured as an ultrasonic sensor.
 pub struct AdiUltrasonic {
     port: bindings::ext_adi_ultrasonic_t,
+    device_port: DevicePort,
 }
 
 impl AdiUltrasonic {
@@ -21,20 +24,37 @@ impl AdiUltrasonic {
         in_port: u8,
         smart_port: u8,
     ) -> Result<Self, AdiUltrasonicError> {
+        let device_port = DevicePort::Adi {
+            port: out_port,
+            expander_port: smart_port,
+        };
         match bindings::ext_adi_ultrasonic_init(smart_port, out_port, in_port) {
-            bindings::PROS_ERR_ => Err(AdiUltrasonicError::from_errno()),
-            port => Ok(Self { port }),
+            bindings::PROS_ERR_ => Err(diagnostics::record_error(DeviceError::from_errno(
+                device_port,
+                "an ADI ultrasonic sensor",
+            ))),
+            port => Ok(Self { port, device_port }),
         }
     }
 
-    /// Gets the current value of the ultrasonic sensor.
+    /// Gets the distance to the nearest object in tenths of a millimetre.
     pub fn get(&self) -> Result<u32, AdiUltrasonicError> {
         match unsafe { bindings::ext_adi_ultrasonic_get(self.port) } {
-            bindings::PROS_ERR_ => Err(AdiUltrasonicError::from_errno()),
-            r if r < 0 => Err(AdiUltrasonicError::NoReading),
+            bindings::PROS_ERR_ => Err(self.error()),
+            r if r < 0 => Err(DeviceError::new(
+                DeviceErrorKind::NoReading,
+                self.device_port,
+            )),
             r => Ok(r as u32),
         }
     }
+
+    fn error(&self) -> DeviceError {
+        diagnostics::record_error(DeviceError::from_errno(
+            self.device_port,
+            "an ADI ultrasonic sensor",
+        ))
+    }
 }
 
 impl DataSource for AdiUltrasonic {
@@ -47,54 +67,21 @@ impl DataSource for AdiUltrasonic {
     }
 }
 
-impl Drop for AdiUltrasonic {
-    fn drop(&mut self) {
-        if unsafe { bindings::ext_adi_ultrasonic_shutdown(self.port) } == bindings::PROS_ERR_ {
-            panic!(
-                "failed to shutdown ADI ultrasonic: {:?}",
-                AdiUltrasonicError::from_errno()
-            )
-        }
+impl DistanceLike for AdiUltrasonic {
+    #[inline]
+    fn distance(&self) -> Result<f64, DeviceError> {
+        Ok(self.get()? as f64 / 10.0)
     }
 }
 
-/// Represents possible errors for ADI ultrasonic operations.
-#[derive(Debug)]
-pub enum AdiUltrasonicError {
-    /// Ports are out of range (1-8).
-    PortsOutOfRange,
-    /// Ports cannot be configured as an ADI encoder.
-    PortsNotAdiUltrasonic,
-    /// Ports are from non matching extenders
-    PortNonMatchingExtenders,
-    /// Sensor did not hear an echo.
-    NoReading,
-    /// Unknown error.
-    Unknown(i32),
-}
-
-impl AdiUltrasonicError {
-    fn from_errno() -> Self {
-        match get_errno() {
-            libc::ENXIO => Self::PortsOutOfRange,
-            libc::EADDRINUSE => Self::PortsNotAdiUltrasonic,
-            x => Self::Unknown(x),
+impl Drop for AdiUltrasonic {
+    fn drop(&mut self) {
+        if unsafe { bindings::ext_adi_ultrasonic_shutdown(self.port) } == bindings::PROS_ERR_ {
+            panic!("failed to shutdown ADI ultrasonic: {:?}", self.error())
         }
     }
 }
 
-impl From<AdiUltrasonicError> for Error {
-    fn from(err: AdiUltrasonicError) -> Self {
-        match err {
-            AdiUltrasonicError::PortsOutOfRange => Error::Custom("ports out of range".into()),
-            AdiUltrasonicError::PortsNotAdiUltrasonic => {
-                Error::Custom("ports not an adi ultrasonic".into())
-            }
-            AdiUltrasonicError::PortNonMatchingExtenders => {
-                Error::Custom("ports from non-matching extenders".into())
-            }
-            AdiUltrasonicError::NoReading => Error::Custom("sensor did not hear an echo".into()),
-            AdiUltrasonicError::Unknown(n) => Error::System(n),
-        }
-    }
-}
+/// Represents possible errors for ADI ultrasonic operations. This is an alias
+/// of [`DeviceError`].
+pub type AdiUltrasonicError = DeviceError;
@@ -0,0 +1,282 @@
+//! # Driver Alerts.
+//!
+//! The controller has only three lines of text and a rumble motor with which
+//! to tell the driver something, and several subsystems may want to use them
+//! at once. [`Alerts`] multiplexes prioritised notifications onto them: the
+//! most important active alerts are shown on the controller screen, and an
+//! alert's rumble pattern is played when it is raised.
+//!
+//! Each alert has a key, so raising an alert which is already active updates
+//! it rather than showing it twice or rumbling again, and an optional
+//! duration after which it expires.
+//!
+//! # Example
+//!
+//! ```
+//! let alerts = Alerts::new(ControllerId::Master, 0, 2);
+//!
+//! // In the intake subsystem, with a clone of `alerts`:
+//! alerts.raise(Alert::new("jam", "INTAKE JAMMED").with_level(AlertLevel::Warning));
+//! // ...and once it clears:
+//! alerts.clear("jam");
+//!
+//! // In the autonomous selector:
+//! alerts.raise(Alert::new("auton", "auton: skills").with_duration(Duration::from_secs(3)));
+//! ```
+
+use alloc::{
+    format,
+    string::String,
+    sync::{Arc, Weak},
+    vec::Vec,
+};
+use core::{cmp::Reverse, time::Duration};
+
+use crate::{
+    bindings,
+    controller::{ControllerId, Screen},
+    error::Error,
+    rtos::{time_since_start, Instant, Loop, Mutex, Task},
+};
+
+/// The number of characters which fit on a line of the controller screen.
+const LINE_WIDTH: usize = 19;
+
+/// The interval at which the controller screen is updated.
+const UPDATE_PERIOD: Duration = Duration::from_millis(100);
+
+/// Represents the importance of an [`Alert`]. More important alerts are shown
+/// first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AlertLevel {
+    /// Information for the driver, e.g. the selected autonomous routine.
+    #[default]
+    Info,
+    /// A problem which the driver should know about, e.g. an overheating
+    /// motor.
+    Warning,
+    /// A problem which needs immediate attention, e.g. a disconnected
+    /// drivetrain motor.
+    Critical,
+}
+
+/// A notification for the driver. See the [module-level
+/// documentation](self).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Alert {
+    key: String,
+    text: String,
+    level: AlertLevel,
+    rumble: Option<String>,
+    duration: Option<Duration>,
+}
+
+impl Alert {
+    /// Creates a new alert at [`AlertLevel::Info`] which does not rumble or
+    /// expire. Alerts with the same `key` replace each other; `text` is
+    /// truncated to the width of the controller screen.
+    pub fn new(key: &str, text: &str) -> Self {
+        Self {
+            key: key.into(),
+            text: text.into(),
+            level: AlertLevel::Info,
+            rumble: None,
+            duration: None,
+        }
+    }
+
+    #[inline]
+    /// Sets the importance of the alert.
+    pub fn with_level(mut self, level: AlertLevel) -> Self {
+        self.level = level;
+        self
+    }
+
+    #[inline]
+    /// Rumbles the controller with the given pattern when the alert is raised,
+    /// or when it is raised again at a higher level. See
+    /// [`Screen::rumble()`](crate::controller::Screen::rumble()) for the
+    /// pattern format.
+    pub fn with_rumble(mut self, pattern: &str) -> Self {
+        self.rumble = Some(pattern.into());
+        self
+    }
+
+    #[inline]
+    /// Clears the alert automatically once `duration` has passed since it was
+    /// last raised.
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    #[inline]
+    /// Gets the key of the alert.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    #[inline]
+    /// Gets the text of the alert.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    #[inline]
+    /// Gets the importance of the alert.
+    pub fn level(&self) -> AlertLevel {
+        self.level
+    }
+}
+
+/// Shows [`Alert`]s on a controller from a background task. Clones share the
+/// same set of alerts, so one can be given to each subsystem; the task exits
+/// when every clone has been dropped.
+///
+/// The lines of the controller screen used for alerts should not also be
+/// written through [`Controller::screen`](crate::controller::Controller).
+#[derive(Clone)]
+pub struct Alerts(Arc<Mutex<AlertsData>>);
+
+impl Alerts {
+    #[inline]
+    /// Creates a new alert service which shows up to `count` alerts on the
+    /// given controller, starting at line `first` (0-2), and spawns its task.
+    /// Panics on failure; see [`Alerts::try_new()`].
+    pub fn new(id: ControllerId, first: u8, count: u8) -> Self {
+        Self::try_new(id, first, count)
+            .unwrap_or_else(|err| panic!("failed to create alert service: {:?}", err))
+    }
+
+    /// Creates a new alert service which shows up to `count` alerts on the
+    /// given controller, starting at line `first` (0-2), and spawns its task.
+    pub fn try_new(id: ControllerId, first: u8, count: u8) -> Result<Self, Error> {
+        let first = first.min(2);
+        let count = count.clamp(1, 3 - first) as usize;
+        let data = Arc::new(Mutex::try_new(AlertsData {
+            active: Vec::new(),
+            rumble: None,
+        })?);
+        let weak: Weak<Mutex<AlertsData>> = Arc::downgrade(&data);
+        let mut screen = Screen::new(id.into());
+        Task::spawn_ext(
+            "alerts",
+            bindings::TASK_PRIORITY_MIN,
+            Task::DEFAULT_STACK_DEPTH,
+            move || {
+                let mut l = Loop::new(UPDATE_PERIOD);
+                let mut shown: Vec<Option<String>> = Vec::from([None, None, None]);
+                while let Some(data) = weak.upgrade() {
+                    let (lines, rumble) = data.lock().update(count);
+                    drop(data);
+                    if let Some(pattern) = rumble {
+                        screen.rumble(&pattern);
+                    }
+                    for (i, shown) in shown.iter_mut().take(count).enumerate() {
+                        let line = lines.get(i).cloned();
+                        if line != *shown {
+                            match &line {
+                                Some(text) => screen.print(first + i as u8, 0, text),
+                                None => screen.clear_line(first + i as u8),
+                            }
+                            *shown = line;
+                        }
+                    }
+                    l.delay();
+                }
+            },
+        )?;
+        Ok(Self(data))
+    }
+
+    /// Raises an alert, replacing any active alert with the same key.
+    pub fn raise(&self, alert: Alert) {
+        let now = time_since_start();
+        let expires = alert.duration.map(|d| now + d);
+        let mut data = self.0.lock();
+        let rumble = match data.active.iter_mut().find(|a| a.alert.key == alert.key) {
+            Some(active) => {
+                let escalated = alert.level > active.alert.level;
+                if escalated {
+                    active.raised = now;
+                }
+                active.expires = expires;
+                active.alert = alert.clone();
+                escalated
+            }
+            None => {
+                data.active.push(ActiveAlert {
+                    alert: alert.clone(),
+                    raised: now,
+                    expires,
+                });
+                true
+            }
+        };
+        if let (true, Some(pattern)) = (rumble, alert.rumble) {
+            if !matches!(&data.rumble, Some((level, _)) if *level > alert.level) {
+                data.rumble = Some((alert.level, pattern));
+            }
+        }
+    }
+
+    /// Clears the alert with the given key, returning whether it was active.
+    pub fn clear(&self, key: &str) -> bool {
+        let mut data = self.0.lock();
+        let len = data.active.len();
+        data.active.retain(|a| a.alert.key != key);
+        data.active.len() != len
+    }
+
+    /// Clears every alert.
+    pub fn clear_all(&self) {
+        self.0.lock().active.clear();
+    }
+
+    /// Gets the active alerts, most important first.
+    pub fn active(&self) -> Vec<Alert> {
+        let mut data = self.0.lock();
+        data.expire(time_since_start());
+        data.active.iter().map(|a| a.alert.clone()).collect()
+    }
+}
+
+struct AlertsData {
+    active: Vec<ActiveAlert>,
+    /// The rumble pattern waiting to be played, and the level of its alert.
+    rumble: Option<(AlertLevel, String)>,
+}
+
+impl AlertsData {
+    /// Removes expired alerts, returning the text of the `count` most
+    /// important alerts to show and any rumble pattern to play.
+    fn update(&mut self, count: usize) -> (Vec<String>, Option<String>) {
+        self.expire(time_since_start());
+        let lines = self
+            .active
+            .iter()
+            .take(count)
+            .map(|a| {
+                let text: String = a.alert.text.chars().take(LINE_WIDTH).collect();
+                // Pad the line to overwrite any longer text shown before.
+                format!("{:<width$}", text, width = LINE_WIDTH)
+            })
+            .collect();
+        (lines, self.rumble.take().map(|(_, pattern)| pattern))
+    }
+
+    /// Removes expired alerts and sorts the rest, most important and most
+    /// recent first.
+    fn expire(&mut self, now: Instant) {
+        self.active
+            .retain(|a| !matches!(a.expires, Some(expires) if expires <= now));
+        self.active
+            .sort_by_key(|a| (Reverse(a.alert.level), Reverse(a.raised)));
+    }
+}
+
+struct ActiveAlert {
+    alert: Alert,
+    raised: Instant,
+    expires: Option<Instant>,
+}
@@ -1,10 +1,380 @@
-use core::alloc::Layout;
-use newlib_alloc::Alloc;
+//! # Global Allocator.
+//!
+//! By default, Rust allocations are served by newlib's `malloc`, which shares
+//! its heap with PROS. Over a long practice session, many differently-sized
+//! allocations can fragment that heap until an allocation fails, even though
+//! enough memory is free in total.
+//!
+//! Enabling one of the following features instead serves Rust allocations from
+//! a separate static pool of [`POOL_SIZE`] bytes:
+//!
+//! * `alloc-tlsf`: a TLSF allocator, which allocates and frees in constant time
+//!   and keeps fragmentation low.
+//! * `alloc-linked-list`: a first-fit linked-list allocator, which has less
+//!   overhead for small pools.
+//!
+//! The pool size defaults to 8 MiB and can be changed by setting the
+//! `VEX_RT_POOL_SIZE` environment variable, in bytes, at build time.
+//!
+//! Whichever allocator is used, the bytes and blocks in use are counted (see
+//! [`stats()`]), and every allocation can be reported to a hook (see
+//! [`set_hook()`]). With the `sim` feature, the host allocator is used and
+//! nothing is counted.
+//!
+//! # Example
+//!
+//! ```
+//! static LARGE: AtomicUsize = AtomicUsize::new(0);
+//!
+//! fn count_large(event: AllocEvent) {
+//!     if let AllocEvent::Alloc { layout, .. } = event {
+//!         if layout.size() >= 4096 {
+//!             LARGE.fetch_add(1, Ordering::Relaxed);
+//!         }
+//!     }
+//! }
+//!
+//! allocator::set_hook(Some(count_large));
+//! // Later:
+//! println!("peak heap usage: {} bytes", allocator::stats().peak_bytes);
+//! ```
 
+#[cfg(not(feature = "sim"))]
+use core::alloc::GlobalAlloc;
+use core::{
+    alloc::Layout,
+    ptr,
+    sync::atomic::{AtomicPtr, AtomicUsize, Ordering},
+};
+
+#[cfg(all(feature = "alloc-tlsf", feature = "alloc-linked-list"))]
+compile_error!("only one of the `alloc-tlsf` and `alloc-linked-list` features may be enabled");
+
+/// The size in bytes of the static pool used by the `alloc-tlsf` and
+/// `alloc-linked-list` allocators.
+pub const POOL_SIZE: usize = match option_env!("VEX_RT_POOL_SIZE") {
+    Some(size) => parse_size(size),
+    None => 8 * 1024 * 1024,
+};
+
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+static PEAK_BYTES: AtomicUsize = AtomicUsize::new(0);
+static BLOCKS: AtomicUsize = AtomicUsize::new(0);
+static FAILURES: AtomicUsize = AtomicUsize::new(0);
+static HOOK: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+
+/// Statistics about heap usage by Rust code.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AllocStats {
+    /// The number of bytes currently allocated.
+    pub bytes: usize,
+    /// The largest number of bytes which have been allocated at once.
+    pub peak_bytes: usize,
+    /// The number of blocks currently allocated.
+    pub blocks: usize,
+    /// The number of allocations which have failed.
+    pub failures: usize,
+}
+
+/// An allocator event reported to the hook set with [`set_hook()`].
+#[derive(Clone, Copy, Debug)]
+pub enum AllocEvent {
+    /// A block was allocated.
+    Alloc {
+        /// The address of the block.
+        ptr: *mut u8,
+        /// The requested layout of the block.
+        layout: Layout,
+    },
+    /// A block was freed.
+    Dealloc {
+        /// The address of the block.
+        ptr: *mut u8,
+        /// The layout the block was allocated with.
+        layout: Layout,
+    },
+    /// An allocation failed.
+    Failed {
+        /// The requested layout.
+        layout: Layout,
+    },
+}
+
+/// Gets statistics about heap usage by Rust code.
+pub fn stats() -> AllocStats {
+    AllocStats {
+        bytes: BYTES.load(Ordering::Relaxed),
+        peak_bytes: PEAK_BYTES.load(Ordering::Relaxed),
+        blocks: BLOCKS.load(Ordering::Relaxed),
+        failures: FAILURES.load(Ordering::Relaxed),
+    }
+}
+
+/// Sets a function to be called on every allocation, deallocation and failed
+/// allocation, or clears it if `hook` is `None`. Reallocations are reported as
+/// a deallocation followed by an allocation.
+///
+/// The hook is called from inside the allocator, so it must not allocate, and
+/// should return quickly.
+pub fn set_hook(hook: Option<fn(AllocEvent)>) {
+    HOOK.store(
+        hook.map_or(ptr::null_mut(), |hook| hook as *mut ()),
+        Ordering::Release,
+    );
+}
+
+#[cfg(not(feature = "sim"))]
+fn record(event: AllocEvent) {
+    match event {
+        AllocEvent::Alloc { layout, .. } => {
+            let bytes = BYTES.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK_BYTES.fetch_max(bytes, Ordering::Relaxed);
+            BLOCKS.fetch_add(1, Ordering::Relaxed);
+        }
+        AllocEvent::Dealloc { layout, .. } => {
+            BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+            BLOCKS.fetch_sub(1, Ordering::Relaxed);
+        }
+        AllocEvent::Failed { .. } => {
+            FAILURES.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    let hook = HOOK.load(Ordering::Acquire);
+    if !hook.is_null() {
+        // SAFETY: the pointer was converted from a `fn(AllocEvent)` by
+        // `set_hook()`.
+        let hook: fn(AllocEvent) = unsafe { core::mem::transmute(hook) };
+        hook(event);
+    }
+}
+
+#[cfg(not(feature = "sim"))]
+fn record_alloc(ptr: *mut u8, layout: Layout) {
+    if ptr.is_null() {
+        record(AllocEvent::Failed { layout });
+    } else {
+        record(AllocEvent::Alloc { ptr, layout });
+    }
+}
+
+const fn parse_size(s: &str) -> usize {
+    let bytes = s.as_bytes();
+    assert!(
+        !bytes.is_empty(),
+        "VEX_RT_POOL_SIZE must be a number of bytes"
+    );
+    let mut size = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        assert!(
+            bytes[i].is_ascii_digit(),
+            "VEX_RT_POOL_SIZE must be a number of bytes"
+        );
+        size = size * 10 + (bytes[i] - b'0') as usize;
+        i += 1;
+    }
+    size
+}
+
+/// Wraps the selected allocator to record allocations.
+#[cfg(not(feature = "sim"))]
+struct Tracking;
+
+#[cfg(not(feature = "sim"))]
 #[global_allocator]
-static ALLOCATOR: Alloc = Alloc;
+static ALLOCATOR: Tracking = Tracking;
+
+#[cfg(not(feature = "sim"))]
+unsafe impl GlobalAlloc for Tracking {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = backend::alloc(layout);
+        record_alloc(ptr, layout);
+        ptr
+    }
 
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        backend::dealloc(ptr, layout);
+        record(AllocEvent::Dealloc { ptr, layout });
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+        let new_ptr = backend::realloc(ptr, layout, new_layout);
+        if !new_ptr.is_null() {
+            record(AllocEvent::Dealloc { ptr, layout });
+        }
+        record_alloc(new_ptr, new_layout);
+        new_ptr
+    }
+}
+
+#[cfg(not(feature = "sim"))]
 #[alloc_error_handler]
 fn handle(layout: Layout) -> ! {
-    panic!("memory allocation failed: {:#?}", layout);
+    let stats = stats();
+    panic!(
+        "memory allocation failed: {:#?} ({} bytes in {} blocks allocated)",
+        layout, stats.bytes, stats.blocks
+    );
+}
+
+#[cfg(not(any(feature = "sim", feature = "alloc-tlsf", feature = "alloc-linked-list")))]
+mod backend {
+    use core::alloc::{GlobalAlloc, Layout};
+
+    use newlib_alloc::Alloc;
+
+    #[inline]
+    pub unsafe fn alloc(layout: Layout) -> *mut u8 {
+        Alloc.alloc(layout)
+    }
+
+    #[inline]
+    pub unsafe fn dealloc(ptr: *mut u8, layout: Layout) {
+        Alloc.dealloc(ptr, layout)
+    }
+
+    #[inline]
+    pub unsafe fn realloc(ptr: *mut u8, layout: Layout, new_layout: Layout) -> *mut u8 {
+        Alloc.realloc(ptr, layout, new_layout.size())
+    }
+}
+
+/// The static pool and the lock which protects it. The lock suspends the
+/// scheduler, since an RTOS mutex would itself need to allocate.
+#[cfg(all(
+    not(feature = "sim"),
+    any(feature = "alloc-tlsf", feature = "alloc-linked-list")
+))]
+mod pool {
+    use core::{cell::UnsafeCell, mem::MaybeUninit};
+
+    extern "C" {
+        fn rtos_suspend_all();
+        fn rtos_resume_all() -> i32;
+    }
+
+    pub struct Pool<T> {
+        memory: UnsafeCell<[MaybeUninit<u8>; super::POOL_SIZE]>,
+        state: UnsafeCell<Option<T>>,
+    }
+
+    unsafe impl<T> Sync for Pool<T> {}
+
+    impl<T> Pool<T> {
+        pub const fn new() -> Self {
+            Self {
+                memory: UnsafeCell::new([MaybeUninit::uninit(); super::POOL_SIZE]),
+                state: UnsafeCell::new(None),
+            }
+        }
+
+        /// Runs `f` on the allocator with the scheduler suspended, creating
+        /// the allocator from the pool memory with `init` the first time.
+        pub fn with<R>(
+            &'static self,
+            init: impl FnOnce(&'static mut [MaybeUninit<u8>]) -> T,
+            f: impl FnOnce(&mut T) -> R,
+        ) -> R {
+            unsafe {
+                rtos_suspend_all();
+                let state = &mut *self.state.get();
+                let allocator = match state {
+                    Some(allocator) => allocator,
+                    None => state.insert(init(&mut *self.memory.get())),
+                };
+                let result = f(allocator);
+                rtos_resume_all();
+                result
+            }
+        }
+    }
+}
+
+#[cfg(all(not(feature = "sim"), feature = "alloc-tlsf"))]
+mod backend {
+    use core::{alloc::Layout, mem::MaybeUninit, ptr::NonNull};
+
+    use rlsf::Tlsf;
+
+    use super::pool::Pool;
+
+    /// Supports blocks of up to 256 MiB, with 16 size classes per power of
+    /// two.
+    type Allocator = Tlsf<'static, u32, u16, 24, 16>;
+
+    static POOL: Pool<Allocator> = Pool::new();
+
+    fn init(memory: &'static mut [MaybeUninit<u8>]) -> Allocator {
+        let mut tlsf = Allocator::new();
+        tlsf.insert_free_block(memory);
+        tlsf
+    }
+
+    #[inline]
+    pub unsafe fn alloc(layout: Layout) -> *mut u8 {
+        POOL.with(init, |tlsf| {
+            tlsf.allocate(layout)
+                .map_or(core::ptr::null_mut(), NonNull::as_ptr)
+        })
+    }
+
+    #[inline]
+    pub unsafe fn dealloc(ptr: *mut u8, layout: Layout) {
+        POOL.with(init, |tlsf| {
+            tlsf.deallocate(NonNull::new_unchecked(ptr), layout.align())
+        })
+    }
+
+    #[inline]
+    pub unsafe fn realloc(ptr: *mut u8, _layout: Layout, new_layout: Layout) -> *mut u8 {
+        POOL.with(init, |tlsf| {
+            tlsf.reallocate(NonNull::new_unchecked(ptr), new_layout)
+                .map_or(core::ptr::null_mut(), NonNull::as_ptr)
+        })
+    }
+}
+
+#[cfg(all(
+    not(feature = "sim"),
+    feature = "alloc-linked-list",
+    not(feature = "alloc-tlsf")
+))]
+mod backend {
+    use core::{alloc::Layout, mem::MaybeUninit, ptr::NonNull};
+
+    use linked_list_allocator::Heap;
+
+    use super::pool::Pool;
+
+    static POOL: Pool<Heap> = Pool::new();
+
+    fn init(memory: &'static mut [MaybeUninit<u8>]) -> Heap {
+        Heap::from_slice(memory)
+    }
+
+    #[inline]
+    pub unsafe fn alloc(layout: Layout) -> *mut u8 {
+        POOL.with(init, |heap| {
+            heap.allocate_first_fit(layout)
+                .map_or(core::ptr::null_mut(), NonNull::as_ptr)
+        })
+    }
+
+    #[inline]
+    pub unsafe fn dealloc(ptr: *mut u8, layout: Layout) {
+        POOL.with(init, |heap| {
+            heap.deallocate(NonNull::new_unchecked(ptr), layout)
+        })
+    }
+
+    pub unsafe fn realloc(ptr: *mut u8, layout: Layout, new_layout: Layout) -> *mut u8 {
+        let new_ptr = alloc(new_layout);
+        if !new_ptr.is_null() {
+            core::ptr::copy_nonoverlapping(ptr, new_ptr, layout.size().min(new_layout.size()));
+            dealloc(ptr, layout);
+        }
+        new_ptr
+    }
 }
@@ -0,0 +1,114 @@
+//! # Robot Assertions.
+//!
+//! The [`robot_assert!`](crate::robot_assert!) and
+//! [`robot_debug_assert!`](crate::robot_debug_assert!) macros check safety
+//! conditions which should not bring the robot down during a match. A failed
+//! assertion is always reported: it is printed to standard error, shown on the
+//! brain screen, recorded in the [trace journal](crate::trace), and logged
+//! through the [`log`] crate, so that it reaches any [logger](crate::logger)
+//! sinks such as the SD card. Whether it then panics is decided by the
+//! [`AssertPolicy`].
+//!
+//! Both macros evaluate to whether the condition held, so that the caller can
+//! fall back to a safe behaviour.
+//!
+//! # Example
+//!
+//! ```
+//! assertion::set_policy(AssertPolicy::PanicUnlessCompeting);
+//!
+//! let position = lift.position()?;
+//! if !robot_assert!(position < 90.0, "lift past limit: {}", position) {
+//!     lift.stop()?;
+//!     return Ok(());
+//! }
+//! ```
+
+use alloc::format;
+use core::{
+    fmt::Arguments,
+    sync::atomic::{AtomicU32, AtomicU8, Ordering},
+};
+
+use cstring_interop::with_cstring;
+
+use crate::{bindings, io::eprintln, robot::CompetitionStatus, trace};
+
+/// The brain screen line, in small text, on which failures are shown.
+const SCREEN_LINE: i16 = 11;
+
+static POLICY: AtomicU8 = AtomicU8::new(AssertPolicy::PanicUnlessCompeting as u8);
+static FAILURES: AtomicU32 = AtomicU32::new(0);
+
+/// Determines what happens when a robot assertion fails, after it has been
+/// reported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum AssertPolicy {
+    /// Always panic.
+    Panic,
+    /// Never panic; only report the failure.
+    Report,
+    /// Panic only when not connected to competition control, so that failures
+    /// are caught in practice but do not disable the robot during a match.
+    PanicUnlessCompeting,
+}
+
+#[inline]
+/// Sets the policy for failed robot assertions. The default is
+/// [`AssertPolicy::PanicUnlessCompeting`].
+pub fn set_policy(policy: AssertPolicy) {
+    POLICY.store(policy as u8, Ordering::Relaxed);
+}
+
+/// Gets the policy for failed robot assertions.
+pub fn policy() -> AssertPolicy {
+    match POLICY.load(Ordering::Relaxed) {
+        0 => AssertPolicy::Panic,
+        1 => AssertPolicy::Report,
+        _ => AssertPolicy::PanicUnlessCompeting,
+    }
+}
+
+#[inline]
+/// Gets the number of robot assertions which have failed since the program
+/// started.
+pub fn failures() -> u32 {
+    FAILURES.load(Ordering::Relaxed)
+}
+
+#[doc(hidden)]
+pub fn fail(file: &'static str, line: u32, condition: &'static str, message: Option<Arguments>) {
+    FAILURES.fetch_add(1, Ordering::Relaxed);
+    let text = match message {
+        Some(message) => format!(
+            "assertion failed at {}:{}: {} ({})",
+            file, line, message, condition
+        ),
+        None => format!("assertion failed at {}:{}: {}", file, line, condition),
+    };
+
+    eprintln!("{}", text);
+    log::error!("{}", text);
+    trace::record("assertion failed", &[line as f64]);
+    with_cstring(text.clone(), |text| unsafe {
+        let pen = bindings::screen_get_pen();
+        bindings::screen_set_pen(0x00FF_0000);
+        bindings::screen_print(
+            bindings::text_format_e_t_E_TEXT_SMALL,
+            SCREEN_LINE,
+            b"%s\0".as_ptr().cast(),
+            text.into_raw(),
+        );
+        bindings::screen_set_pen(pen);
+    });
+
+    let panic = match policy() {
+        AssertPolicy::Panic => true,
+        AssertPolicy::Report => false,
+        AssertPolicy::PanicUnlessCompeting => !CompetitionStatus::get().connected,
+    };
+    if panic {
+        panic!("{}", text);
+    }
+}
@@ -0,0 +1,217 @@
+//! # Autonomous Selection.
+//!
+//! An [`AutonSelector`] keeps track of which autonomous routine will run, and
+//! for which [`Alliance`]. The choice is saved to a file on the SD card
+//! whenever it changes and restored when the selector is created, so that a
+//! brain reboot before a match does not silently reset the routine to the
+//! default.
+//!
+//! The selection is saved by name, so adding or reordering routines does not
+//! select a different one; if the saved routine no longer exists, the first
+//! routine is selected.
+//!
+//! # Example
+//!
+//! ```
+//! let mut selector =
+//!     AutonSelector::new(&["left", "right", "skills"]).with_persistence("/usd/auton.cfg");
+//!
+//! // In `opcontrol` or `disabled`:
+//! if controller.right.pressed() {
+//!     selector.next();
+//! }
+//! if controller.a.pressed() {
+//!     selector.toggle_alliance();
+//! }
+//!
+//! // In `autonomous`:
+//! match selector.selected_name() {
+//!     "left" => run_left(selector.alliance()),
+//!     "right" => run_right(selector.alliance()),
+//!     _ => run_skills(),
+//! }
+//! ```
+
+use alloc::{format, string::String, vec::Vec};
+use core::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use crate::{
+    config::Config,
+    io::eprintln,
+    sd::{File, SdError},
+};
+
+/// Represents the alliance a robot is playing on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Alliance {
+    /// The red alliance.
+    #[default]
+    Red,
+    /// The blue alliance.
+    Blue,
+}
+
+impl Alliance {
+    #[inline]
+    /// Gets the opposing alliance.
+    pub fn opponent(self) -> Self {
+        match self {
+            Alliance::Red => Alliance::Blue,
+            Alliance::Blue => Alliance::Red,
+        }
+    }
+}
+
+impl Display for Alliance {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Alliance::Red => "red",
+            Alliance::Blue => "blue",
+        })
+    }
+}
+
+impl FromStr for Alliance {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "red" => Ok(Alliance::Red),
+            "blue" => Ok(Alliance::Blue),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Selects an autonomous routine from a list of named routines. See the
+/// [module-level documentation](self).
+#[derive(Debug)]
+pub struct AutonSelector {
+    routines: Vec<&'static str>,
+    selected: usize,
+    alliance: Alliance,
+    path: Option<String>,
+}
+
+impl AutonSelector {
+    /// Creates a new selector for the given routines, with the first routine
+    /// and the red alliance selected. Panics if `routines` is empty.
+    pub fn new(routines: &[&'static str]) -> Self {
+        assert!(
+            !routines.is_empty(),
+            "auton selector needs at least one routine"
+        );
+        Self {
+            routines: routines.into(),
+            selected: 0,
+            alliance: Alliance::Red,
+            path: None,
+        }
+    }
+
+    /// Saves the selection to the given file on the SD card whenever it
+    /// changes, first restoring any selection previously saved there.
+    pub fn with_persistence(mut self, path: &str) -> Self {
+        let mut config = Config::load(path);
+        let routine: String = config.get("routine", String::new());
+        if let Some(index) = self.routines.iter().position(|r| *r == routine) {
+            self.selected = index;
+        }
+        self.alliance = config.get("alliance", self.alliance);
+        self.path = Some(path.into());
+        self
+    }
+
+    #[inline]
+    /// Gets the names of the routines.
+    pub fn routines(&self) -> &[&'static str] {
+        &self.routines
+    }
+
+    #[inline]
+    /// Gets the index of the selected routine.
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    #[inline]
+    /// Gets the name of the selected routine.
+    pub fn selected_name(&self) -> &'static str {
+        self.routines[self.selected]
+    }
+
+    #[inline]
+    /// Gets the selected alliance.
+    pub fn alliance(&self) -> Alliance {
+        self.alliance
+    }
+
+    /// Selects the routine at the given index. Out-of-range indices are
+    /// ignored.
+    pub fn select(&mut self, index: usize) {
+        if index < self.routines.len() {
+            self.selected = index;
+            self.persist();
+        }
+    }
+
+    /// Selects the routine with the given name, returning whether it exists.
+    pub fn select_name(&mut self, name: &str) -> bool {
+        match self.routines.iter().position(|r| *r == name) {
+            Some(index) => {
+                self.select(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Selects the next routine, wrapping around to the first.
+    pub fn next(&mut self) {
+        self.select((self.selected + 1) % self.routines.len());
+    }
+
+    /// Selects the previous routine, wrapping around to the last.
+    pub fn previous(&mut self) {
+        self.select((self.selected + self.routines.len() - 1) % self.routines.len());
+    }
+
+    /// Selects the alliance.
+    pub fn set_alliance(&mut self, alliance: Alliance) {
+        self.alliance = alliance;
+        self.persist();
+    }
+
+    /// Switches to the opposing alliance.
+    pub fn toggle_alliance(&mut self) {
+        self.set_alliance(self.alliance.opponent());
+    }
+
+    /// Saves the selection to the SD card, if persistence is enabled (see
+    /// [`with_persistence()`](Self::with_persistence())).
+    ///
+    /// This is done automatically whenever the selection changes; failures
+    /// are then printed to standard error rather than returned.
+    pub fn save(&self) -> Result<(), SdError> {
+        match &self.path {
+            Some(path) => File::create(path)?.write_all(
+                format!(
+                    "routine = \"{}\"\nalliance = {}\n",
+                    self.selected_name(),
+                    self.alliance
+                )
+                .as_bytes(),
+            ),
+            None => Ok(()),
+        }
+    }
+
+    fn persist(&self) {
+        if let Err(err) = self.save() {
+            eprintln!("failed to save auton selection: {:?}", err);
+        }
+    }
+}
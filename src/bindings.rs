@@ -4,4 +4,93 @@
 #![allow(non_snake_case)]
 #![allow(dead_code)]
 
+#[cfg(not(feature = "pros-4"))]
 include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+
+// PROS 4 renamed and retyped some functions. The generated bindings are
+// re-exported, and the functions below, which take precedence over the glob
+// import, adapt them to the PROS 3 signatures used by the rest of the crate.
+
+#[cfg(feature = "pros-4")]
+mod raw {
+    include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+}
+
+#[cfg(feature = "pros-4")]
+pub use raw::*;
+
+/// Motor functions take a signed port in PROS 4, which is negated to reverse
+/// the motor; the crate always passes a positive port.
+#[cfg(feature = "pros-4")]
+macro_rules! signed_motor_port {
+    ($(fn $name:ident($($arg:ident: $ty:ty),*) -> $ret:ty;)*) => {
+        $(
+            #[inline]
+            pub unsafe fn $name(port: u8 $(, $arg: $ty)*) -> $ret {
+                raw::$name(port as i8 $(, $arg)*)
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "pros-4")]
+signed_motor_port! {
+    fn motor_get_actual_velocity() -> f64;
+    fn motor_get_brake_mode() -> motor_brake_mode_e_t;
+    fn motor_get_current_draw() -> i32;
+    fn motor_get_current_limit() -> i32;
+    fn motor_get_direction() -> i32;
+    fn motor_get_efficiency() -> f64;
+    fn motor_get_encoder_units() -> motor_encoder_units_e_t;
+    fn motor_get_gearing() -> motor_gearset_e_t;
+    fn motor_get_position() -> f64;
+    fn motor_get_power() -> f64;
+    fn motor_get_target_position() -> f64;
+    fn motor_get_target_velocity() -> i32;
+    fn motor_get_temperature() -> f64;
+    fn motor_get_torque() -> f64;
+    fn motor_get_voltage() -> i32;
+    fn motor_get_voltage_limit() -> i32;
+    fn motor_is_over_current() -> i32;
+    fn motor_is_over_temp() -> i32;
+    fn motor_is_reversed() -> i32;
+    fn motor_modify_profiled_velocity(velocity: i32) -> i32;
+    fn motor_move(voltage: i32) -> i32;
+    fn motor_move_absolute(position: f64, velocity: i32) -> i32;
+    fn motor_move_relative(position: f64, velocity: i32) -> i32;
+    fn motor_move_velocity(velocity: i32) -> i32;
+    fn motor_move_voltage(voltage: i32) -> i32;
+    fn motor_set_brake_mode(mode: motor_brake_mode_e_t) -> i32;
+    fn motor_set_current_limit(limit: i32) -> i32;
+    fn motor_set_encoder_units(units: motor_encoder_units_e_t) -> i32;
+    fn motor_set_gearing(gearset: motor_gearset_e_t) -> i32;
+    fn motor_set_reversed(reverse: bool) -> i32;
+    fn motor_set_voltage_limit(limit: i32) -> i32;
+    fn motor_set_zero_position(position: f64) -> i32;
+    fn motor_tare_position() -> i32;
+}
+
+// The LCD emulator moved out of the kernel into the separate LVGL template in
+// PROS 4, so its text is printed directly to the screen instead.
+
+#[cfg(feature = "pros-4")]
+#[inline]
+pub unsafe fn lcd_initialize() -> bool {
+    true
+}
+
+#[cfg(feature = "pros-4")]
+#[inline]
+pub unsafe fn lcd_is_initialized() -> bool {
+    true
+}
+
+#[cfg(feature = "pros-4")]
+pub unsafe fn lcd_set_text(line: i16, text: *const libc::c_char) -> bool {
+    raw::screen_print(
+        text_format_e_t_E_TEXT_MEDIUM,
+        line,
+        b"%s\0".as_ptr().cast(),
+        text,
+    ) != PROS_ERR_ as u32
+}
@@ -0,0 +1,263 @@
+//! # Match Black-Box Recorder.
+//!
+//! A [`BlackBox`] continuously records the competition state, controller
+//! inputs, and a set of named values (such as motor commands and sensor
+//! readings) into an in-memory ring buffer, which is periodically written to a
+//! CSV file on the SD card. The buffer is also written whenever the
+//! competition state changes, e.g. at the end of a match.
+//!
+//! Sampled channels are read by the recorder itself; inputs are instead set by
+//! robot code as it runs, and the most recent value is recorded.
+//!
+//! # Example
+//!
+//! ```
+//! let recorder = BlackBox::new(Duration::from_millis(20))
+//!     .channel("battery", || Battery::get_voltage().unwrap_or(0) as f64)
+//!     .input("intake")
+//!     .start("/usd/match")?;
+//!
+//! // In opcontrol:
+//! let data = self.controller.read()?;
+//! recorder.record_controller(data);
+//! recorder.set_input("intake", intake_voltage);
+//! ```
+
+use alloc::{boxed::Box, collections::VecDeque, string::String, sync::Arc, vec::Vec};
+use core::{fmt::Write, time::Duration};
+
+use crate::{
+    bindings,
+    controller::ControllerData,
+    csv::{escape, next_path},
+    error::Error,
+    escalation,
+    robot::CompetitionStatus,
+    rtos::{time_since_start, Instant, Loop, Mutex, Task},
+    sd::{File, SdError},
+};
+
+/// The default number of samples held in the ring buffer.
+const DEFAULT_CAPACITY: usize = 500;
+
+const HEADER: &str = "time,disabled,autonomous,connected,left_x,left_y,right_x,right_y,l1,l2,\
+                      r1,r2,up,down,left,right,x,y,a,b";
+
+type Channel = Box<dyn FnMut() -> f64 + Send>;
+
+/// Describes what a black-box recorder should record. Call
+/// [`BlackBox::start()`] to begin recording.
+pub struct BlackBox {
+    period: Duration,
+    capacity: usize,
+    channel_names: Vec<String>,
+    channels: Vec<Channel>,
+    input_names: Vec<String>,
+}
+
+impl BlackBox {
+    #[inline]
+    /// Creates a new recorder which samples every `period`, recording only the
+    /// competition state and controller inputs.
+    pub fn new(period: Duration) -> Self {
+        Self {
+            period,
+            capacity: DEFAULT_CAPACITY,
+            channel_names: Vec::new(),
+            channels: Vec::new(),
+            input_names: Vec::new(),
+        }
+    }
+
+    #[inline]
+    /// Sets the number of samples held in memory. The buffer is written to
+    /// the SD card once it is half full; if writing fails, the most recent
+    /// samples are kept and the oldest are discarded.
+    pub fn capacity(mut self, samples: usize) -> Self {
+        self.capacity = samples.max(2);
+        self
+    }
+
+    /// Adds a channel whose value is sampled by calling `f`.
+    pub fn channel(mut self, name: &str, f: impl FnMut() -> f64 + Send + 'static) -> Self {
+        self.channel_names.push(name.into());
+        self.channels.push(Box::new(f));
+        self
+    }
+
+    /// Adds an input, whose value is set with
+    /// [`BlackBoxRecorder::set_input()`].
+    pub fn input(mut self, name: &str) -> Self {
+        self.input_names.push(name.into());
+        self
+    }
+
+    /// Starts recording to a new file named `{prefix}{n}.csv`, where `n` is
+    /// the smallest number for which no such file exists yet.
+    pub fn start(self, prefix: &str) -> Result<BlackBoxRecorder, Error> {
+        let mut file = File::create(&next_path(prefix)?)?;
+        let mut header = String::from(HEADER);
+        for name in self.channel_names.iter().chain(&self.input_names) {
+            header.push(',');
+            header.push_str(&escape(name));
+        }
+        header.push('\n');
+        file.write_all(header.as_bytes())?;
+
+        let data = Arc::new(Mutex::try_new(BlackBoxData {
+            file,
+            frames: VecDeque::with_capacity(self.capacity),
+            capacity: self.capacity,
+            channels: self.channels,
+            input_names: self.input_names.clone(),
+            inputs: self.input_names.iter().map(|_| f64::NAN).collect(),
+            controller: None,
+            status: CompetitionStatus::get(),
+        })?);
+        let weak = Arc::downgrade(&data);
+        let period = self.period;
+        Task::spawn_ext(
+            "black box",
+            bindings::TASK_PRIORITY_MIN,
+            Task::DEFAULT_STACK_DEPTH,
+            move || {
+                let mut l = Loop::new(period);
+                while let Some(data) = weak.upgrade() {
+                    if let Err(err) = data.lock().sample() {
+                        if escalation::report("black box", &err) {
+                            break;
+                        }
+                    }
+                    drop(data);
+                    l.delay();
+                }
+            },
+        )?;
+        Ok(BlackBoxRecorder(data))
+    }
+}
+
+/// A [`BlackBox`] which is recording in the background. Recording stops, and
+/// any buffered samples are written, when this is dropped.
+pub struct BlackBoxRecorder(Arc<Mutex<BlackBoxData>>);
+
+impl BlackBoxRecorder {
+    #[inline]
+    /// Records the latest controller inputs.
+    pub fn record_controller(&self, data: ControllerData) {
+        self.0.lock().controller = Some(data);
+    }
+
+    /// Sets the value of an input. Has no effect if no input with the given
+    /// name was added.
+    pub fn set_input(&self, name: &str, value: f64) {
+        let mut lock = self.0.lock();
+        if let Some(i) = lock.input_names.iter().position(|n| n == name) {
+            lock.inputs[i] = value;
+        }
+    }
+
+    #[inline]
+    /// Writes all buffered samples to the SD card.
+    pub fn flush(&self) -> Result<(), SdError> {
+        self.0.lock().flush()
+    }
+}
+
+impl Drop for BlackBoxRecorder {
+    fn drop(&mut self) {
+        if let Err(err) = self.flush() {
+            escalation::report("black box", &err);
+        }
+    }
+}
+
+struct Frame {
+    time: Instant,
+    status: CompetitionStatus,
+    controller: Option<ControllerData>,
+    values: Vec<f64>,
+}
+
+struct BlackBoxData {
+    file: File,
+    frames: VecDeque<Frame>,
+    capacity: usize,
+    channels: Vec<Channel>,
+    input_names: Vec<String>,
+    inputs: Vec<f64>,
+    controller: Option<ControllerData>,
+    status: CompetitionStatus,
+}
+
+impl BlackBoxData {
+    fn sample(&mut self) -> Result<(), SdError> {
+        let status = CompetitionStatus::get();
+        let mut values: Vec<f64> = self.channels.iter_mut().map(|f| f()).collect();
+        values.extend_from_slice(&self.inputs);
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(Frame {
+            time: time_since_start(),
+            status,
+            controller: self.controller,
+            values,
+        });
+        if status != self.status {
+            self.status = status;
+            self.flush()
+        } else if self.frames.len() >= self.capacity / 2 {
+            self.flush()
+        } else {
+            Ok(())
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), SdError> {
+        let mut buffer = String::new();
+        for frame in &self.frames {
+            write_frame(&mut buffer, frame);
+        }
+        // Samples are kept in the buffer until they have been written
+        // successfully.
+        self.file.write_all(buffer.as_bytes())?;
+        self.file.flush()?;
+        self.frames.clear();
+        Ok(())
+    }
+}
+
+fn write_frame(buffer: &mut String, frame: &Frame) {
+    // Writing to a string cannot fail.
+    write!(
+        buffer,
+        "{}.{:03},{},{},{}",
+        frame.time.as_millis() / 1000,
+        frame.time.as_millis() % 1000,
+        frame.status.disabled as u8,
+        frame.status.autonomous as u8,
+        frame.status.connected as u8,
+    )
+    .ok();
+    match &frame.controller {
+        Some(c) => {
+            write!(
+                buffer,
+                ",{},{},{},{}",
+                c.left_x, c.left_y, c.right_x, c.right_y
+            )
+            .ok();
+            for pressed in [
+                c.l1, c.l2, c.r1, c.r2, c.up, c.down, c.left, c.right, c.x, c.y, c.a, c.b,
+            ] {
+                write!(buffer, ",{}", pressed as u8).ok();
+            }
+        }
+        None => buffer.push_str(&",".repeat(16)),
+    }
+    for value in &frame.values {
+        write!(buffer, ",{}", value).ok();
+    }
+    buffer.push('\n');
+}
@@ -0,0 +1,108 @@
+//! # Build Information.
+//!
+//! [`build_info()`] identifies the program running on the robot, so that it is
+//! easy to check which code was actually uploaded. The name and version of the
+//! program are recorded by the [`entry!`](crate::entry!) macro, which also
+//! prints them in a banner at startup; the git commit and compile timestamp
+//! are captured by the build script. The build information can also be
+//! printed from the [REPL](crate::repl) with the `version` command.
+//!
+//! The build script only reruns when the git commit or branch changes, not on
+//! every build, so the git information and timestamp describe the first build
+//! at the current commit, rather than the latest build.
+//!
+//! # Example
+//!
+//! ```
+//! let info = vex_rt::build_info();
+//! self.controller
+//!     .screen
+//!     .print(0, 0, info.git_hash.unwrap_or("no git"))?;
+//! ```
+
+use core::fmt::{self, Display, Formatter};
+
+use spin::Once;
+
+use crate::{io::println, repl};
+
+/// The UNIX timestamp at which the program was compiled.
+pub(crate) const COMPILE_TIMESTAMP: u64 = parse_u64(env!("VEX_RT_COMPILE_TIMESTAMP_INT"));
+
+static PROGRAM: Once<(&'static str, &'static str)> = Once::new();
+
+/// Information identifying the program running on the robot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BuildInfo {
+    /// The version of vex-rt the program was built with.
+    pub crate_version: &'static str,
+    /// The name of the program's package, or `"unknown"` if it was not
+    /// started with the [`entry!`](crate::entry!) macro.
+    pub program_name: &'static str,
+    /// The version of the program's package, or `"unknown"` if it was not
+    /// started with the [`entry!`](crate::entry!) macro.
+    pub program_version: &'static str,
+    /// The abbreviated hash of the git commit the program was built from, if
+    /// it was built in a git repository. It is followed by `-dirty` if tracked
+    /// files had uncommitted changes when it was captured, e.g.
+    /// `1a2b3c4-dirty`.
+    pub git_hash: Option<&'static str>,
+    /// The UNIX timestamp at which the program was first compiled at its git
+    /// commit. Later builds at the same commit, including ones with
+    /// uncommitted changes, keep this timestamp.
+    pub compile_timestamp: u64,
+    /// The same time as [`BuildInfo::compile_timestamp`], in UTC and in the
+    /// format `Oct 16 2026 14:05:00`.
+    pub compile_time: &'static str,
+}
+
+impl Display for BuildInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} (", self.program_name, self.program_version)?;
+        if let Some(hash) = self.git_hash {
+            write!(f, "{}, ", hash)?;
+        }
+        write!(
+            f,
+            "compiled {}) on vex-rt {}",
+            self.compile_time, self.crate_version
+        )
+    }
+}
+
+/// Gets the build information of the running program.
+pub fn build_info() -> BuildInfo {
+    let (program_name, program_version) = PROGRAM.get().copied().unwrap_or(("unknown", "unknown"));
+    BuildInfo {
+        crate_version: env!("CARGO_PKG_VERSION"),
+        program_name,
+        program_version,
+        git_hash: option_env!("VEX_RT_GIT_HASH"),
+        compile_timestamp: COMPILE_TIMESTAMP,
+        compile_time: env!("VEX_RT_COMPILE_TIMESTAMP"),
+    }
+}
+
+#[doc(hidden)]
+/// Records the name and version of the program, prints the startup banner and
+/// registers the `version` REPL command. Called by the
+/// [`entry!`](crate::entry!) macro.
+pub fn init(program_name: &'static str, program_version: &'static str) {
+    PROGRAM.call_once(|| (program_name, program_version));
+    println!("{}", build_info());
+    repl::register("version", |_| {
+        println!("{}", build_info());
+        Ok(())
+    });
+}
+
+const fn parse_u64(s: &str) -> u64 {
+    let s = s.as_bytes();
+    let mut value = 0;
+    let mut i = 0;
+    while i < s.len() {
+        value = value * 10 + (s[i] - b'0') as u64;
+        i += 1;
+    }
+    value
+}
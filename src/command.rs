@@ -0,0 +1,297 @@
+//! # Command-Based Scheduling.
+//!
+//! Robot code is divided into subsystems (e.g., a drivetrain or an intake),
+//! each identified by a [`Resource`], and [`Command`]s which use them. Each
+//! command declares the resources it requires; scheduling a command
+//! interrupts any running commands which require the same resources, and
+//! resources which are not in use fall back to their default command.
+//!
+//! The [`Scheduler`] runs all scheduled commands cooperatively, one step at a
+//! time, each time [`Scheduler::run()`] is called.
+//!
+//! # Example
+//!
+//! ```
+//! let intake = Resource::new();
+//! let mut scheduler = Scheduler::new();
+//! scheduler.set_default(
+//!     intake,
+//!     FnCommand::new([intake], move || {
+//!         motor.move_voltage(0).unwrap();
+//!         false
+//!     }),
+//! );
+//! scheduler.schedule(FnCommand::new([intake], move || {
+//!     motor.move_voltage(12000).unwrap();
+//!     sensor.read().unwrap()
+//! }));
+//! scheduler.run_until(&ctx, Duration::from_millis(10));
+//! ```
+
+use alloc::{boxed::Box, collections::BTreeMap, vec::Vec};
+use core::{
+    sync::atomic::{AtomicU32, Ordering},
+    time::Duration,
+};
+
+use crate::{
+    rtos::{Context, Loop},
+    select,
+};
+
+/// Identifies a subsystem which may only be used by one [`Command`] at a time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Resource(u32);
+
+impl Resource {
+    /// Creates a new, unique resource.
+    pub fn new() -> Self {
+        static NEXT: AtomicU32 = AtomicU32::new(0);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl Default for Resource {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An action which runs over time, using a set of [`Resource`]s exclusively.
+///
+/// Commands are run by a [`Scheduler`], which calls
+/// [`initialize()`](Self::initialize()) once when the command starts,
+/// [`execute()`](Self::execute()) on each run of the scheduler until
+/// [`is_finished()`](Self::is_finished()) returns `true` or the command is
+/// interrupted, and then [`end()`](Self::end()) once.
+pub trait Command: Send {
+    /// Gets the resources which this command requires exclusive use of.
+    fn requirements(&self) -> Vec<Resource> {
+        Vec::new()
+    }
+
+    /// Checks whether this command may be interrupted by another command
+    /// which requires the same resources.
+    fn is_interruptible(&self) -> bool {
+        true
+    }
+
+    /// Called once when the command is started.
+    fn initialize(&mut self) {}
+
+    /// Called on each run of the scheduler while the command is running.
+    fn execute(&mut self);
+
+    /// Checks whether the command has finished, after each call to
+    /// [`execute()`](Self::execute()).
+    fn is_finished(&mut self) -> bool {
+        false
+    }
+
+    /// Called once when the command finishes or is interrupted.
+    fn end(&mut self, interrupted: bool) {
+        let _ = interrupted;
+    }
+}
+
+/// A [`Command`] which calls a function on each run of the scheduler, until
+/// the function returns `true`.
+pub struct FnCommand<F: FnMut() -> bool + Send> {
+    requirements: Vec<Resource>,
+    f: F,
+    finished: bool,
+}
+
+impl<F: FnMut() -> bool + Send> FnCommand<F> {
+    /// Creates a new command with the given requirements.
+    pub fn new(requirements: impl IntoIterator<Item = Resource>, f: F) -> Self {
+        Self {
+            requirements: requirements.into_iter().collect(),
+            f,
+            finished: false,
+        }
+    }
+}
+
+impl<F: FnMut() -> bool + Send> Command for FnCommand<F> {
+    fn requirements(&self) -> Vec<Resource> {
+        self.requirements.clone()
+    }
+
+    fn initialize(&mut self) {
+        self.finished = false;
+    }
+
+    fn execute(&mut self) {
+        self.finished = (self.f)();
+    }
+
+    fn is_finished(&mut self) -> bool {
+        self.finished
+    }
+}
+
+/// Identifies a command which has been scheduled on a [`Scheduler`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CommandHandle(u32);
+
+/// Runs [`Command`]s cooperatively, ensuring that each [`Resource`] is used
+/// by at most one command at a time.
+#[derive(Default)]
+pub struct Scheduler {
+    next: u32,
+    running: Vec<Running>,
+    defaults: BTreeMap<Resource, Box<dyn Command>>,
+}
+
+impl Scheduler {
+    #[inline]
+    /// Creates a new scheduler with no commands.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Schedules a command, interrupting any running commands which require
+    /// the same resources.
+    ///
+    /// Returns `None` without scheduling the command if a conflicting command
+    /// cannot be interrupted.
+    pub fn schedule(&mut self, command: impl Command + 'static) -> Option<CommandHandle> {
+        self.schedule_boxed(Box::new(command), false)
+    }
+
+    /// Sets the command which runs whenever no other command requires the
+    /// given resource. The command should require only that resource, and
+    /// should not finish.
+    pub fn set_default(&mut self, resource: Resource, command: impl Command + 'static) {
+        if let Some(index) = self
+            .running
+            .iter()
+            .position(|r| r.default == Some(resource))
+        {
+            self.end(index, true);
+        }
+        self.defaults.insert(resource, Box::new(command));
+    }
+
+    /// Checks whether a command is still running.
+    pub fn is_running(&self, handle: CommandHandle) -> bool {
+        self.running.iter().any(|r| r.handle == handle)
+    }
+
+    /// Interrupts a running command.
+    pub fn cancel(&mut self, handle: CommandHandle) {
+        if let Some(index) = self.running.iter().position(|r| r.handle == handle) {
+            self.end(index, true);
+        }
+    }
+
+    /// Interrupts all running commands, including default commands.
+    pub fn cancel_all(&mut self) {
+        while !self.running.is_empty() {
+            self.end(self.running.len() - 1, true);
+        }
+    }
+
+    /// Runs one step of each scheduled command, ending those which have
+    /// finished, then starts the default commands of any idle resources.
+    pub fn run(&mut self) {
+        let mut index = 0;
+        while index < self.running.len() {
+            let command = &mut self.running[index].command;
+            command.execute();
+            if command.is_finished() {
+                self.end(index, false);
+            } else {
+                index += 1;
+            }
+        }
+
+        let idle: Vec<Resource> = self
+            .defaults
+            .keys()
+            .copied()
+            .filter(|resource| {
+                !self
+                    .running
+                    .iter()
+                    .any(|r| r.requirements.contains(resource))
+            })
+            .collect();
+        for resource in idle {
+            if let Some(command) = self.defaults.remove(&resource) {
+                self.schedule_boxed(command, true);
+            }
+        }
+    }
+
+    /// Runs the scheduler every `period` until `ctx` is cancelled, then
+    /// interrupts all running commands.
+    pub fn run_until(&mut self, ctx: &Context, period: Duration) {
+        let mut l = Loop::new(period);
+        loop {
+            self.run();
+            select! {
+                _ = ctx.done() => break,
+                _ = l.select() => continue,
+            }
+        }
+        self.cancel_all();
+    }
+
+    fn schedule_boxed(
+        &mut self,
+        mut command: Box<dyn Command>,
+        is_default: bool,
+    ) -> Option<CommandHandle> {
+        let requirements = command.requirements();
+        let conflicts = |r: &Running| r.requirements.iter().any(|x| requirements.contains(x));
+        if self
+            .running
+            .iter()
+            .any(|r| conflicts(r) && !r.command.is_interruptible())
+        {
+            if is_default {
+                // Keep the default for when the resource becomes idle.
+                if let Some(&resource) = requirements.first() {
+                    self.defaults.insert(resource, command);
+                }
+            }
+            return None;
+        }
+        while let Some(index) = self.running.iter().position(conflicts) {
+            self.end(index, true);
+        }
+
+        let handle = CommandHandle(self.next);
+        self.next = self.next.wrapping_add(1);
+        command.initialize();
+        self.running.push(Running {
+            handle,
+            requirements: requirements.clone(),
+            default: if is_default {
+                requirements.first().copied()
+            } else {
+                None
+            },
+            command,
+        });
+        Some(handle)
+    }
+
+    fn end(&mut self, index: usize, interrupted: bool) {
+        let mut running = self.running.remove(index);
+        running.command.end(interrupted);
+        if let Some(resource) = running.default {
+            self.defaults.insert(resource, running.command);
+        }
+    }
+}
+
+struct Running {
+    handle: CommandHandle,
+    requirements: Vec<Resource>,
+    default: Option<Resource>,
+    command: Box<dyn Command>,
+}
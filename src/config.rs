@@ -0,0 +1,238 @@
+//! # Configuration Files.
+//!
+//! A [`Config`] holds `key = value` pairs loaded from a simple INI-like file
+//! on the SD card, so that per-robot constants can be changed without
+//! rebuilding the program:
+//!
+//! ```text
+//! # Comments start with `#` or `;`.
+//! name = "Clawbot"
+//!
+//! [drive]
+//! wheel_diameter = 101.6
+//! kp = 0.35
+//! ```
+//!
+//! Keys within a `[section]` are prefixed by the section name and a dot, e.g.
+//! `drive.kp`. Values are parsed when they are read, falling back to a default
+//! if the key is missing or its value is invalid. Any problems are collected,
+//! and can be shown on the brain screen with [`Config::show_errors()`].
+//!
+//! # Example
+//!
+//! ```
+//! let mut config = Config::load("/usd/robot.cfg");
+//! let wheel_diameter = config.get("drive.wheel_diameter", 101.6);
+//! let kp = config.get_in("drive.kp", 0.5, 0.0..=10.0);
+//! config.show_errors();
+//! ```
+
+use alloc::{collections::BTreeMap, format, string::String, vec::Vec};
+use core::{
+    fmt::{self, Display, Formatter},
+    ops::RangeInclusive,
+    str::FromStr,
+};
+
+use cstring_interop::with_cstring;
+
+use crate::{
+    bindings,
+    error::Error,
+    io::eprintln,
+    sd::{File, SdError},
+};
+
+/// The number of lines which fit on the brain screen in small text.
+const SCREEN_LINES: usize = 12;
+
+/// A set of configuration values. See the [module-level
+/// documentation](self) for the file format.
+#[derive(Debug, Default)]
+pub struct Config {
+    values: BTreeMap<String, String>,
+    errors: Vec<ConfigError>,
+}
+
+impl Config {
+    /// Loads a configuration file from the SD card. If the file cannot be
+    /// read, the configuration is empty and the error is recorded.
+    pub fn load(path: &str) -> Self {
+        let mut text = Vec::new();
+        match File::open(path).and_then(|mut file| file.read_to_end(&mut text)) {
+            Ok(_) => Self::parse(&String::from_utf8_lossy(&text)),
+            Err(err) => Self {
+                values: BTreeMap::new(),
+                errors: Vec::from([ConfigError::Sd(err)]),
+            },
+        }
+    }
+
+    /// Parses configuration values from a string. Lines which cannot be parsed
+    /// are recorded as errors and skipped.
+    pub fn parse(text: &str) -> Self {
+        let mut config = Self::default();
+        let mut section = String::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = name.trim().into();
+                continue;
+            }
+            match line.split_once('=') {
+                Some((key, value)) if !key.trim().is_empty() => {
+                    let key = match section.as_str() {
+                        "" => key.trim().into(),
+                        section => format!("{}.{}", section, key.trim()),
+                    };
+                    let value = value.trim();
+                    let value = value
+                        .strip_prefix('"')
+                        .and_then(|v| v.strip_suffix('"'))
+                        .unwrap_or(value);
+                    config.values.insert(key, value.into());
+                }
+                _ => config.errors.push(ConfigError::Syntax { line: i + 1 }),
+            }
+        }
+        config
+    }
+
+    /// Gets the value of a key, or `default` if the key is missing or its
+    /// value cannot be parsed. An invalid value is recorded as an error.
+    pub fn get<T: FromStr>(&mut self, key: &str, default: T) -> T {
+        match self.values.get(key) {
+            Some(value) => match value.parse() {
+                Ok(value) => value,
+                Err(_) => {
+                    self.errors.push(ConfigError::Invalid {
+                        key: key.into(),
+                        value: value.clone(),
+                    });
+                    default
+                }
+            },
+            None => default,
+        }
+    }
+
+    /// Gets the value of a key, as for [`Config::get()`], additionally
+    /// checking that it lies within `range`. A value outside the range is
+    /// recorded as an error, and `default` is returned instead.
+    pub fn get_in<T: FromStr + PartialOrd>(
+        &mut self,
+        key: &str,
+        default: T,
+        range: RangeInclusive<T>,
+    ) -> T {
+        let error = match self.values.get(key) {
+            Some(value) => match value.parse() {
+                Ok(parsed) if range.contains(&parsed) => return parsed,
+                Ok(_) => ConfigError::OutOfRange {
+                    key: key.into(),
+                    value: value.clone(),
+                },
+                Err(_) => ConfigError::Invalid {
+                    key: key.into(),
+                    value: value.clone(),
+                },
+            },
+            None => return default,
+        };
+        self.errors.push(error);
+        default
+    }
+
+    #[inline]
+    /// Checks whether the configuration contains the given key.
+    pub fn contains(&self, key: &str) -> bool {
+        self.values.contains_key(key)
+    }
+
+    /// Iterates over every key and its value as written in the file, in
+    /// alphabetical order of key.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> + '_ {
+        self.values
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    #[inline]
+    /// Gets the errors encountered while loading and reading the
+    /// configuration.
+    pub fn errors(&self) -> &[ConfigError] {
+        &self.errors
+    }
+
+    /// Prints any errors to standard error and shows them on the brain screen.
+    /// Does nothing if there were no errors.
+    pub fn show_errors(&self) {
+        if self.errors.is_empty() {
+            return;
+        }
+        let mut lines = Vec::from([format!("{} config errors:", self.errors.len())]);
+        lines.extend(self.errors.iter().map(|err| format!("  {}", err)));
+        for line in &lines {
+            eprintln!("{}", line);
+        }
+        for (i, line) in lines.iter().take(SCREEN_LINES).enumerate() {
+            with_cstring(line.clone(), |line| unsafe {
+                bindings::screen_print(
+                    bindings::text_format_e_t_E_TEXT_SMALL,
+                    i as i16,
+                    b"%s\0".as_ptr().cast(),
+                    line.into_raw(),
+                );
+            });
+        }
+    }
+}
+
+/// Represents possible errors when loading or reading a [`Config`].
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The file could not be read.
+    Sd(SdError),
+    /// A line is neither a `key = value` pair, a `[section]` header, nor a
+    /// comment.
+    Syntax {
+        /// The line number, starting from 1.
+        line: usize,
+    },
+    /// A value could not be parsed as the requested type.
+    Invalid {
+        /// The key.
+        key: String,
+        /// The value as written in the file.
+        value: String,
+    },
+    /// A value is outside the allowed range.
+    OutOfRange {
+        /// The key.
+        key: String,
+        /// The value as written in the file.
+        value: String,
+    },
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Sd(err) => write!(f, "could not read file: {:?}", err),
+            ConfigError::Syntax { line } => write!(f, "line {}: expected `key = value`", line),
+            ConfigError::Invalid { key, value } => write!(f, "{}: invalid value {:?}", key, value),
+            ConfigError::OutOfRange { key, value } => {
+                write!(f, "{}: value {} out of range", key, value)
+            }
+        }
+    }
+}
+
+impl From<ConfigError> for Error {
+    fn from(err: ConfigError) -> Self {
+        Error::Custom(format!("{}", err))
+    }
+}
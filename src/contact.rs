@@ -0,0 +1,191 @@
+//! # Contact Detection.
+//!
+//! A [`ContactDetector`] recognises when a drivetrain has run into something,
+//! such as a wall or a goal, from two signs: the motors stalling, i.e. drawing
+//! a high current while barely turning, and a sudden jolt measured by an
+//! inertial sensor. This makes it possible to write autonomous steps such as
+//! "drive forwards until touching the goal" without relying on the distance
+//! driven.
+//!
+//! The motors draw a high current while accelerating from rest too, so stalls
+//! are ignored for a short time after detection starts.
+//!
+//! # Example
+//!
+//! ```
+//! chassis.drive_to(goal)?;
+//! select! {
+//!     contact = chassis.contact(ContactConfig::new()) => println!("hit: {:?}", contact?),
+//!     _ = delay(Duration::from_secs(3)) => {},
+//! }
+//! chassis.stop()?;
+//! ```
+
+use core::time::Duration;
+
+use crate::{
+    filter::Debounce,
+    rtos::{time_since_start, GenericSleep, Instant, Selectable},
+};
+
+/// Configures a [`ContactDetector`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContactConfig {
+    /// The average current per motor, in milliamperes, at or above which the
+    /// motors may be stalled.
+    pub current_threshold: f64,
+    /// The average speed of the motors, in RPM, below which they may be
+    /// stalled.
+    pub stall_velocity: f64,
+    /// How long the motors must be stalled before it counts as contact.
+    pub stall_time: Duration,
+    /// The horizontal acceleration, in multiples of standard gravity, at or
+    /// above which a jolt counts as contact, or `None` to ignore acceleration.
+    pub impact_threshold: Option<f64>,
+    /// How long stalls are ignored after detection starts, while the motors
+    /// accelerate.
+    pub startup_time: Duration,
+    /// The interval at which samples are taken by
+    /// [`ContactDetector::select()`].
+    pub period: Duration,
+}
+
+impl ContactConfig {
+    /// Creates a new configuration with default values, which detects a stall
+    /// above 2A per motor and below 20 RPM held for 100ms, or a jolt of 1.5g.
+    pub fn new() -> Self {
+        Self {
+            current_threshold: 2000.0,
+            stall_velocity: 20.0,
+            stall_time: Duration::from_millis(100),
+            impact_threshold: Some(1.5),
+            startup_time: Duration::from_millis(300),
+            period: Duration::from_millis(10),
+        }
+    }
+}
+
+impl Default for ContactConfig {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A reading of the quantities used to detect contact.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ContactSample {
+    /// The average current drawn per motor, in milliamperes.
+    pub current: f64,
+    /// The average speed of the motors, in RPM, regardless of direction.
+    pub velocity: f64,
+    /// The magnitude of the horizontal acceleration, in multiples of standard
+    /// gravity, if an inertial sensor is available.
+    pub acceleration: Option<f64>,
+}
+
+/// Describes how contact was detected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Contact {
+    /// The motors stalled.
+    Stall,
+    /// The inertial sensor measured a jolt.
+    Impact,
+}
+
+/// Detects when a drivetrain has run into something. See the
+/// [module-level documentation](self).
+#[derive(Clone, Debug)]
+pub struct ContactDetector {
+    config: ContactConfig,
+    start: Option<Instant>,
+    stalled: Debounce,
+}
+
+impl ContactDetector {
+    /// Creates a new detector.
+    pub fn new(config: ContactConfig) -> Self {
+        Self {
+            stalled: Debounce::new(config.stall_time),
+            config,
+            start: None,
+        }
+    }
+
+    #[inline]
+    /// Gets the configuration of the detector.
+    pub fn config(&self) -> &ContactConfig {
+        &self.config
+    }
+
+    /// Updates the detector with a new sample, returning how contact was
+    /// detected, if it was.
+    pub fn update(&mut self, sample: ContactSample) -> Option<Contact> {
+        let now = time_since_start();
+        let start = *self.start.get_or_insert(now);
+        if matches!(
+            (self.config.impact_threshold, sample.acceleration),
+            (Some(threshold), Some(acceleration)) if acceleration >= threshold
+        ) {
+            return Some(Contact::Impact);
+        }
+        let stalled = now - start >= self.config.startup_time
+            && sample.current >= self.config.current_threshold
+            && sample.velocity < self.config.stall_velocity;
+        if self.stalled.update(stalled) {
+            Some(Contact::Stall)
+        } else {
+            None
+        }
+    }
+
+    /// Clears the state of the detector, restarting the startup time, e.g.
+    /// before a new movement.
+    pub fn reset(&mut self) {
+        self.start = None;
+        self.stalled.reset(false);
+    }
+
+    /// A [`Selectable`] event which samples the drivetrain with `read` at the
+    /// configured period and occurs once contact is detected, or when `read`
+    /// fails.
+    pub fn select<E>(
+        mut self,
+        read: impl FnMut() -> Result<ContactSample, E>,
+    ) -> impl Selectable<Result<Contact, E>> {
+        struct ContactSelect<F> {
+            detector: ContactDetector,
+            read: F,
+            next: Instant,
+        }
+
+        impl<E, F: FnMut() -> Result<ContactSample, E>> Selectable<Result<Contact, E>>
+            for ContactSelect<F>
+        {
+            fn poll(mut self) -> Result<Result<Contact, E>, Self> {
+                if time_since_start() < self.next {
+                    return Err(self);
+                }
+                self.next += self.detector.config.period;
+                match (self.read)() {
+                    Ok(sample) => match self.detector.update(sample) {
+                        Some(contact) => Ok(Ok(contact)),
+                        None => Err(self),
+                    },
+                    Err(err) => Ok(Err(err)),
+                }
+            }
+
+            fn sleep(&self) -> GenericSleep {
+                GenericSleep::Timestamp(self.next)
+            }
+        }
+
+        self.reset();
+        ContactSelect {
+            detector: self,
+            read,
+            next: time_since_start(),
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use core::{f64::consts::PI, fmt, time::Duration};
+
+#[cfg(not(feature = "sim"))]
+use num_traits::Float;
+
+use crate::{
+    error::Error,
+    rtos::{time_since_start, Context, Instant, Loop},
+    select,
+};
+
+use super::Pid;
+
+const UPDATE_PERIOD: Duration = Duration::from_millis(10);
+
+/// Estimates PID gains for a mechanism using the Åström–Hägglund relay method.
+///
+/// The mechanism is driven with a bang-bang (relay) output around a target
+/// value, which makes it oscillate. The period and amplitude of the
+/// oscillation determine the ultimate gain and period of the system, from
+/// which gains can be suggested with the Ziegler–Nichols rules.
+///
+/// # Example
+///
+/// ```
+/// // Tune a flywheel around 2000RPM, switching 3V either side of 6V.
+/// let result = RelayAutotune::new(2000.0, 3000.0).bias(6000.0).run(
+///     &ctx,
+///     || motors.get_actual_velocity(),
+///     |output| motors.move_voltage(output as i32),
+/// )?;
+/// controller.screen.print(0, 0, &format!("{}", result));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct RelayAutotune {
+    target: f64,
+    amplitude: f64,
+    bias: f64,
+    hysteresis: f64,
+    cycles: usize,
+    timeout: Duration,
+}
+
+impl RelayAutotune {
+    #[inline]
+    /// Creates a new autotuner which oscillates the mechanism around `target`
+    /// by switching the output by `amplitude` either side of zero.
+    pub fn new(target: f64, amplitude: f64) -> Self {
+        Self {
+            target,
+            amplitude,
+            bias: 0.0,
+            hysteresis: 0.0,
+            cycles: 5,
+            timeout: Duration::from_secs(30),
+        }
+    }
+
+    #[inline]
+    /// Sets the output around which the relay switches, e.g. the output
+    /// required to hold the mechanism near the target.
+    pub fn bias(mut self, bias: f64) -> Self {
+        self.bias = bias;
+        self
+    }
+
+    #[inline]
+    /// Sets the error which must be exceeded before the relay switches, to
+    /// reject sensor noise.
+    pub fn hysteresis(mut self, hysteresis: f64) -> Self {
+        self.hysteresis = hysteresis;
+        self
+    }
+
+    #[inline]
+    /// Sets the number of oscillations to measure and average over.
+    pub fn cycles(mut self, cycles: usize) -> Self {
+        self.cycles = cycles.max(1);
+        self
+    }
+
+    #[inline]
+    /// Sets the time after which tuning is abandoned if the mechanism has not
+    /// oscillated enough.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Runs the relay experiment, using `read` to measure the mechanism and
+    /// `write` to drive it. The output is set to zero when the experiment
+    /// finishes.
+    ///
+    /// Blocks until enough oscillations have been measured, the timeout
+    /// expires, or `ctx` is cancelled.
+    pub fn run<E>(
+        &self,
+        ctx: &Context,
+        mut read: impl FnMut() -> Result<f64, E>,
+        mut write: impl FnMut(f64) -> Result<(), E>,
+    ) -> Result<AutotuneResult, AutotuneError<E>> {
+        let result = self.oscillate(ctx, &mut read, &mut write);
+        write(0.0).map_err(AutotuneError::Device)?;
+        result
+    }
+
+    fn oscillate<E>(
+        &self,
+        ctx: &Context,
+        read: &mut impl FnMut() -> Result<f64, E>,
+        write: &mut impl FnMut(f64) -> Result<(), E>,
+    ) -> Result<AutotuneResult, AutotuneError<E>> {
+        let start = time_since_start();
+        let mut l = Loop::new(UPDATE_PERIOD);
+        let mut high = read().map_err(AutotuneError::Device)? < self.target;
+        let mut cycle_start: Option<Instant> = None;
+        let (mut min, mut max) = (f64::INFINITY, f64::NEG_INFINITY);
+        // The first cycle is discarded, since it includes the initial
+        // transient.
+        let mut measured = 0usize;
+        let (mut total_period, mut total_amplitude) = (Duration::ZERO, 0.0);
+
+        loop {
+            let now = time_since_start();
+            if now - start > self.timeout {
+                return Err(AutotuneError::Timeout);
+            }
+
+            let value = read().map_err(AutotuneError::Device)?;
+            min = min.min(value);
+            max = max.max(value);
+            let error = self.target - value;
+            if !high && error > self.hysteresis {
+                high = true;
+                if let Some(last) = cycle_start.replace(now) {
+                    if measured > 0 {
+                        total_period += now - last;
+                        total_amplitude += (max - min) / 2.0;
+                    }
+                    measured += 1;
+                    if measured > self.cycles {
+                        break;
+                    }
+                }
+                min = f64::INFINITY;
+                max = f64::NEG_INFINITY;
+            } else if high && error < -self.hysteresis {
+                high = false;
+            }
+
+            let output = if high {
+                self.bias + self.amplitude
+            } else {
+                self.bias - self.amplitude
+            };
+            write(output).map_err(AutotuneError::Device)?;
+
+            select! {
+                _ = ctx.done() => return Err(AutotuneError::Cancelled),
+                _ = l.select() => continue,
+            }
+        }
+
+        let amplitude = total_amplitude / self.cycles as f64;
+        let adjusted = (amplitude * amplitude - self.hysteresis * self.hysteresis).sqrt();
+        if adjusted.is_nan() || adjusted <= 0.0 {
+            return Err(AutotuneError::NoOscillation);
+        }
+        Ok(AutotuneResult {
+            ultimate_gain: 4.0 * self.amplitude / (PI * adjusted),
+            ultimate_period: total_period / self.cycles as u32,
+        })
+    }
+}
+
+/// The measured characteristics of a mechanism from a [`RelayAutotune`]
+/// experiment.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AutotuneResult {
+    /// The proportional gain at which the mechanism would oscillate steadily.
+    pub ultimate_gain: f64,
+    /// The period of the steady oscillation.
+    pub ultimate_period: Duration,
+}
+
+impl AutotuneResult {
+    /// Suggests PID gains using the classic Ziegler–Nichols rules.
+    pub fn pid(&self) -> Pid {
+        let (ku, tu) = (self.ultimate_gain, self.ultimate_period.as_secs_f64());
+        Pid::new(0.6 * ku, 1.2 * ku / tu, 0.075 * ku * tu)
+    }
+
+    /// Suggests PI gains using the classic Ziegler–Nichols rules.
+    pub fn pi(&self) -> Pid {
+        let (ku, tu) = (self.ultimate_gain, self.ultimate_period.as_secs_f64());
+        Pid::new(0.45 * ku, 0.54 * ku / tu, 0.0)
+    }
+
+    /// Suggests PID gains which avoid overshoot, using the Ziegler–Nichols "no
+    /// overshoot" rules.
+    pub fn no_overshoot(&self) -> Pid {
+        let (ku, tu) = (self.ultimate_gain, self.ultimate_period.as_secs_f64());
+        Pid::new(0.2 * ku, 0.4 * ku / tu, 0.066 * ku * tu)
+    }
+}
+
+impl fmt::Display for AutotuneResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Ku={:.3} Tu={}ms",
+            self.ultimate_gain,
+            self.ultimate_period.as_millis()
+        )
+    }
+}
+
+/// Represents possible errors for a [`RelayAutotune`] experiment.
+#[derive(Debug)]
+pub enum AutotuneError<E> {
+    /// Reading from or writing to the mechanism failed.
+    Device(E),
+    /// The mechanism did not oscillate enough before the timeout expired.
+    Timeout,
+    /// The measured oscillation was too small to estimate gains from.
+    NoOscillation,
+    /// The experiment was cancelled.
+    Cancelled,
+}
+
+impl<E: Into<Error>> From<AutotuneError<E>> for Error {
+    fn from(err: AutotuneError<E>) -> Self {
+        match err {
+            AutotuneError::Device(err) => err.into(),
+            AutotuneError::Timeout => Error::Custom("autotune timed out".into()),
+            AutotuneError::NoOscillation => Error::Custom("no oscillation measured".into()),
+            AutotuneError::Cancelled => Error::Custom("autotune cancelled".into()),
+        }
+    }
+}
@@ -0,0 +1,34 @@
+//! Feedback controllers.
+
+mod autotune;
+mod pid;
+mod settle;
+mod tbh;
+
+pub use autotune::*;
+pub use pid::*;
+pub use settle::*;
+pub use tbh::*;
+
+/// A common interface for feedback controllers, so that they can be used
+/// interchangeably by subsystems.
+pub trait FeedbackController {
+    /// Runs one iteration of the controller, returning the control output
+    /// given the `target` value and the `actual` (measured) value.
+    fn step(&mut self, target: f64, actual: f64) -> f64;
+
+    /// Clears the accumulated state of the controller.
+    fn reset(&mut self);
+}
+
+impl<C: FeedbackController + ?Sized> FeedbackController for &mut C {
+    #[inline]
+    fn step(&mut self, target: f64, actual: f64) -> f64 {
+        (**self).step(target, actual)
+    }
+
+    #[inline]
+    fn reset(&mut self) {
+        (**self).reset()
+    }
+}
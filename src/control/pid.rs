@@ -0,0 +1,79 @@
+use crate::rtos::{time_since_start, Instant};
+
+use super::FeedbackController;
+
+/// A proportional-integral-derivative controller.
+///
+/// The integral and derivative terms are computed with respect to real time
+/// (in seconds) between successive calls to [`step()`](Self::step()), so the
+/// gains do not depend on how frequently the controller is run.
+#[derive(Clone, Debug)]
+pub struct Pid {
+    kp: f64,
+    ki: f64,
+    kd: f64,
+    integral_limit: f64,
+    integral: f64,
+    last: Option<(f64, Instant)>,
+}
+
+impl Pid {
+    #[inline]
+    /// Creates a new PID controller with the given gains.
+    pub fn new(kp: f64, ki: f64, kd: f64) -> Self {
+        Self {
+            kp,
+            ki,
+            kd,
+            integral_limit: f64::INFINITY,
+            integral: 0.0,
+            last: None,
+        }
+    }
+
+    #[inline]
+    /// Limits the magnitude of the accumulated integral term, to prevent
+    /// integral windup.
+    pub fn with_integral_limit(mut self, limit: f64) -> Self {
+        self.integral_limit = limit;
+        self
+    }
+
+    #[inline]
+    /// Gets the proportional, integral and derivative gains, in that order.
+    pub fn gains(&self) -> (f64, f64, f64) {
+        (self.kp, self.ki, self.kd)
+    }
+
+    #[inline]
+    /// Updates the proportional, integral and derivative gains.
+    pub fn set_gains(&mut self, kp: f64, ki: f64, kd: f64) {
+        self.kp = kp;
+        self.ki = ki;
+        self.kd = kd;
+    }
+}
+
+impl FeedbackController for Pid {
+    fn step(&mut self, target: f64, actual: f64) -> f64 {
+        let error = target - actual;
+        let now = time_since_start();
+        let derivative = match self.last {
+            Some((last_error, last_time)) if now > last_time => {
+                let dt = (now - last_time).as_secs_f64();
+                self.integral =
+                    (self.integral + error * dt).clamp(-self.integral_limit, self.integral_limit);
+                (error - last_error) / dt
+            }
+            _ => 0.0,
+        };
+        self.last = Some((error, now));
+        self.kp * error + self.ki * self.integral + self.kd * derivative
+    }
+
+    #[inline]
+    fn reset(&mut self) {
+        self.integral = 0.0;
+        self.last = None;
+    }
+}
@@ -0,0 +1,205 @@
+use core::time::Duration;
+
+use crate::rtos::{time_since_start, GenericSleep, Instant, Selectable};
+
+/// Detects when a controlled quantity has settled at its target.
+///
+/// The quantity is settled once its error has stayed within a tolerance for a
+/// sustained duration or, if a velocity threshold is set with
+/// [`Settled::with_velocity_threshold()`], once it has stopped moving for that
+/// duration even if it is not within tolerance (e.g. when a mechanism is held
+/// against an obstacle).
+///
+/// Feed it errors with [`Settled::update()`] from a control loop, or wait for
+/// it with [`Settled::select()`].
+///
+/// # Example
+///
+/// ```
+/// let mut settled = Settled::new(1.0, Duration::from_millis(200));
+/// let mut l = Loop::new(Duration::from_millis(10));
+/// loop {
+///     let error = target - motor.get_position()?;
+///     if settled.update(error) {
+///         break;
+///     }
+///     motor.move_voltage(pid.step(target, target - error) as i32)?;
+///     l.delay();
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Settled {
+    tolerance: f64,
+    settle_time: Duration,
+    velocity_threshold: Option<f64>,
+    within_since: Option<Instant>,
+    stopped_since: Option<Instant>,
+    last: Option<(Instant, f64)>,
+    settled: bool,
+}
+
+impl Settled {
+    #[inline]
+    /// Creates a new detector which considers the quantity settled once the
+    /// magnitude of its error has been at most `tolerance` for `settle_time`.
+    pub fn new(tolerance: f64, settle_time: Duration) -> Self {
+        Self {
+            tolerance,
+            settle_time,
+            velocity_threshold: None,
+            within_since: None,
+            stopped_since: None,
+            last: None,
+            settled: false,
+        }
+    }
+
+    #[inline]
+    /// Also considers the quantity settled once the magnitude of its velocity
+    /// has been below `threshold`, in units of error per second, for the settle
+    /// time.
+    ///
+    /// A quantity which has not yet started moving is also below the
+    /// threshold, so this should only be used where movement starts well
+    /// within the settle time.
+    pub fn with_velocity_threshold(mut self, threshold: f64) -> Self {
+        self.velocity_threshold = Some(threshold);
+        self
+    }
+
+    #[inline]
+    /// Gets the error tolerance.
+    pub fn tolerance(&self) -> f64 {
+        self.tolerance
+    }
+
+    #[inline]
+    /// Updates the error tolerance.
+    pub fn set_tolerance(&mut self, tolerance: f64) {
+        self.tolerance = tolerance;
+    }
+
+    #[inline]
+    /// Gets the time for which the quantity must be on target.
+    pub fn settle_time(&self) -> Duration {
+        self.settle_time
+    }
+
+    #[inline]
+    /// Updates the time for which the quantity must be on target.
+    pub fn set_settle_time(&mut self, settle_time: Duration) {
+        self.settle_time = settle_time;
+    }
+
+    /// Updates the detector with the current error, estimating the velocity
+    /// from its rate of change, and returns whether the quantity has settled.
+    pub fn update(&mut self, error: f64) -> bool {
+        let now = time_since_start();
+        let velocity = match self.last {
+            Some((then, last)) if now > then => Some((error - last) / (now - then).as_secs_f64()),
+            _ => None,
+        };
+        self.last = Some((now, error));
+        self.step(now, error.abs() <= self.tolerance, velocity)
+    }
+
+    /// Updates the detector with the current error and a measured velocity, in
+    /// units of error per second, and returns whether the quantity has
+    /// settled.
+    pub fn update_with_velocity(&mut self, error: f64, velocity: f64) -> bool {
+        let now = time_since_start();
+        self.last = Some((now, error));
+        self.step(now, error.abs() <= self.tolerance, Some(velocity))
+    }
+
+    /// Updates the detector with whether the quantity is currently on target,
+    /// for quantities with several errors (e.g. a distance and a heading), and
+    /// returns whether it has been on target for the settle time. The velocity
+    /// threshold is not used.
+    pub fn update_on_target(&mut self, on_target: bool) -> bool {
+        self.step(time_since_start(), on_target, None)
+    }
+
+    #[inline]
+    /// Checks whether the quantity had settled at the last update.
+    pub fn is_settled(&self) -> bool {
+        self.settled
+    }
+
+    /// Clears the state of the detector, e.g. when the target changes.
+    pub fn reset(&mut self) {
+        self.within_since = None;
+        self.stopped_since = None;
+        self.last = None;
+        self.settled = false;
+    }
+
+    /// A [`Selectable`] event which samples the error with `read` every
+    /// `period` and occurs once the quantity has settled, or when `read` fails.
+    pub fn select<'a, E: 'a>(
+        &'a mut self,
+        period: Duration,
+        read: impl FnMut() -> Result<f64, E> + 'a,
+    ) -> impl Selectable<Result<(), E>> + 'a {
+        struct SettledSelect<'a, F> {
+            settled: &'a mut Settled,
+            period: Duration,
+            read: F,
+            next: Instant,
+        }
+
+        impl<'a, E, F: FnMut() -> Result<f64, E>> Selectable<Result<(), E>> for SettledSelect<'a, F> {
+            fn poll(mut self) -> Result<Result<(), E>, Self> {
+                if time_since_start() < self.next {
+                    return Err(self);
+                }
+                self.next += self.period;
+                match (self.read)() {
+                    Ok(error) if self.settled.update(error) => Ok(Ok(())),
+                    Ok(_) => Err(self),
+                    Err(err) => Ok(Err(err)),
+                }
+            }
+
+            fn sleep(&self) -> GenericSleep {
+                GenericSleep::Timestamp(self.next)
+            }
+        }
+
+        SettledSelect {
+            settled: self,
+            period,
+            read,
+            next: time_since_start(),
+        }
+    }
+
+    fn step(&mut self, now: Instant, on_target: bool, velocity: Option<f64>) -> bool {
+        let within = held(&mut self.within_since, now, on_target, self.settle_time);
+        let stopped = match (self.velocity_threshold, velocity) {
+            (Some(threshold), Some(velocity)) => held(
+                &mut self.stopped_since,
+                now,
+                velocity.abs() < threshold,
+                self.settle_time,
+            ),
+            _ => {
+                self.stopped_since = None;
+                false
+            }
+        };
+        self.settled = within || stopped;
+        self.settled
+    }
+}
+
+/// Tracks how long `condition` has held, returning whether it has held for at
+/// least `time`.
+fn held(since: &mut Option<Instant>, now: Instant, condition: bool, time: Duration) -> bool {
+    if condition {
+        now - *since.get_or_insert(now) >= time
+    } else {
+        *since = None;
+        false
+    }
+}
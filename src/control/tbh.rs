@@ -0,0 +1,88 @@
+use crate::rtos::{time_since_start, Instant};
+
+use super::FeedbackController;
+
+/// A take-back-half velocity controller.
+///
+/// The output integrates the error over time, and whenever the error changes
+/// sign the output is set halfway between its current value and its value at
+/// the previous sign change. This converges quickly on the output needed to
+/// hold a velocity, which makes it a classic choice for flywheels.
+#[derive(Clone, Debug)]
+pub struct Tbh {
+    gain: f64,
+    min: f64,
+    max: f64,
+    output: f64,
+    tbh: f64,
+    last: Option<(f64, Instant)>,
+}
+
+impl Tbh {
+    #[inline]
+    /// Creates a new take-back-half controller with the given integral gain.
+    pub fn new(gain: f64) -> Self {
+        Self {
+            gain,
+            min: f64::NEG_INFINITY,
+            max: f64::INFINITY,
+            output: 0.0,
+            tbh: 0.0,
+            last: None,
+        }
+    }
+
+    #[inline]
+    /// Limits the output of the controller to the range `[min, max]`.
+    pub fn with_output_limits(mut self, min: f64, max: f64) -> Self {
+        self.min = min;
+        self.max = max;
+        self
+    }
+
+    #[inline]
+    /// Gets the integral gain.
+    pub fn gain(&self) -> f64 {
+        self.gain
+    }
+
+    #[inline]
+    /// Updates the integral gain.
+    pub fn set_gain(&mut self, gain: f64) {
+        self.gain = gain;
+    }
+
+    #[inline]
+    /// Seeds the output with an estimate of the value needed to hold the
+    /// target, which shortens the time taken to converge.
+    pub fn set_output(&mut self, output: f64) {
+        self.output = output;
+        self.tbh = output;
+    }
+}
+
+impl FeedbackController for Tbh {
+    fn step(&mut self, target: f64, actual: f64) -> f64 {
+        let error = target - actual;
+        let now = time_since_start();
+        if let Some((last_error, last_time)) = self.last {
+            if now > last_time {
+                let dt = (now - last_time).as_secs_f64();
+                self.output = (self.output + self.gain * error * dt).clamp(self.min, self.max);
+            }
+            if (error > 0.0) != (last_error > 0.0) {
+                self.output = (self.output + self.tbh) / 2.0;
+                self.tbh = self.output;
+            }
+        }
+        self.last = Some((error, now));
+        self.output
+    }
+
+    #[inline]
+    fn reset(&mut self) {
+        self.output = 0.0;
+        self.tbh = 0.0;
+        self.last = None;
+    }
+}
@@ -120,7 +120,7 @@ impl Controller {
                 id,
                 button: bindings::controller_digital_e_t_E_CONTROLLER_DIGITAL_A,
             },
-            screen: Screen { id, queue: None },
+            screen: Screen::new(id),
         }
     }
 
@@ -289,6 +289,11 @@ pub struct Screen {
 }
 
 impl Screen {
+    #[inline]
+    pub(crate) fn new(id: bindings::controller_id_e_t) -> Self {
+        Self { id, queue: None }
+    }
+
     /// Clears all of the lines of the controller screen
     pub fn clear(&mut self) {
         self.command(ScreenCommand::Clear);
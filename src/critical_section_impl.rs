@@ -0,0 +1,33 @@
+//! A [`critical_section`] implementation which suspends the FreeRTOS
+//! scheduler for the duration of each critical section.
+//!
+//! Interrupts are left enabled, since user code never runs in an interrupt
+//! handler. Scheduler suspension nests, so critical sections may be nested
+//! too. No task may block while the scheduler is suspended: critical sections
+//! must not delay, take an [`rtos::Mutex`](crate::rtos::Mutex) or wait on any
+//! other RTOS primitive.
+
+#[cfg(not(feature = "sim"))]
+extern "C" {
+    fn rtos_suspend_all();
+    fn rtos_resume_all() -> i32;
+}
+
+#[cfg(feature = "sim")]
+use crate::bindings::{rtos_resume_all, rtos_suspend_all};
+
+struct SchedulerCriticalSection;
+
+critical_section::set_impl!(SchedulerCriticalSection);
+
+unsafe impl critical_section::Impl for SchedulerCriticalSection {
+    #[inline]
+    unsafe fn acquire() -> critical_section::RawRestoreState {
+        rtos_suspend_all();
+    }
+
+    #[inline]
+    unsafe fn release(_: critical_section::RawRestoreState) {
+        rtos_resume_all();
+    }
+}
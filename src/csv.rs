@@ -0,0 +1,230 @@
+//! # CSV Data Logging.
+//!
+//! A [`CsvLogger`] samples a set of named columns at a fixed rate on a
+//! low-priority task, and writes them as rows of a CSV file on the SD card.
+//! Each run of the program gets a new file, so earlier logs are never
+//! overwritten.
+//!
+//! # Example
+//!
+//! ```
+//! let log = CsvLogger::new(Duration::from_millis(20))
+//!     .source("velocity", motor, |data| data.actual_velocity)
+//!     .column("battery", || Battery::get_voltage().unwrap_or(0))
+//!     .start("/usd/flywheel")?;
+//! // Logs to /usd/flywheel0.csv, /usd/flywheel1.csv, etc.
+//! ```
+
+use alloc::{
+    boxed::Box,
+    format,
+    string::{String, ToString},
+    sync::Arc,
+    vec::Vec,
+};
+use core::{fmt::Display, time::Duration};
+
+use crate::{
+    bindings,
+    error::Error,
+    io::eprintln,
+    rtos::{time_since_start, DataSource, Loop, Mutex, Task},
+    sd::{File, SdError},
+};
+
+/// The default number of rows buffered in memory between writes to the SD
+/// card.
+const DEFAULT_BUFFER_ROWS: usize = 50;
+
+type Column = Box<dyn FnMut() -> String + Send>;
+
+/// Describes a set of columns to log to a CSV file. Call
+/// [`CsvLogger::start()`] to begin logging.
+///
+/// Every row begins with a `time` column, giving the time since program start
+/// in seconds.
+pub struct CsvLogger {
+    period: Duration,
+    buffer_rows: usize,
+    names: Vec<String>,
+    columns: Vec<Column>,
+}
+
+impl CsvLogger {
+    #[inline]
+    /// Creates a new logger with no columns, sampling every `period`.
+    pub fn new(period: Duration) -> Self {
+        Self {
+            period,
+            buffer_rows: DEFAULT_BUFFER_ROWS,
+            names: Vec::new(),
+            columns: Vec::new(),
+        }
+    }
+
+    /// Adds a column whose values are produced by calling `f`.
+    pub fn column<T: Display>(
+        mut self,
+        name: &str,
+        mut f: impl FnMut() -> T + Send + 'static,
+    ) -> Self {
+        self.names.push(name.into());
+        self.columns.push(Box::new(move || f().to_string()));
+        self
+    }
+
+    /// Adds a column whose values are read from a data source, converted by
+    /// `f`. Failed reads leave the cell empty.
+    pub fn source<S: DataSource + Send + 'static, T: Display>(
+        self,
+        name: &str,
+        source: S,
+        f: impl Fn(S::Data) -> T + Send + 'static,
+    ) -> Self {
+        self.column(name, move || {
+            source
+                .read()
+                .map(&f)
+                .map_or(String::new(), |v| v.to_string())
+        })
+    }
+
+    #[inline]
+    /// Sets the number of rows buffered in memory between writes to the SD
+    /// card. Larger buffers write less often, at the cost of losing more rows
+    /// if the program stops unexpectedly.
+    pub fn buffer_rows(mut self, rows: usize) -> Self {
+        self.buffer_rows = rows.max(1);
+        self
+    }
+
+    /// Starts logging to a new file named `{prefix}{n}.csv`, where `n` is the
+    /// smallest number for which no such file exists yet.
+    pub fn start(self, prefix: &str) -> Result<RunningCsvLogger, Error> {
+        let path = next_path(prefix)?;
+        let mut file = File::create(&path)?;
+        let mut header = String::from("time");
+        for name in &self.names {
+            header.push(',');
+            header.push_str(&escape(name));
+        }
+        header.push('\n');
+        file.write_all(header.as_bytes())?;
+
+        let data = Arc::new(Mutex::try_new(CsvLoggerData {
+            file,
+            path,
+            columns: self.columns,
+            buffer: String::new(),
+            rows: 0,
+            buffer_rows: self.buffer_rows,
+        })?);
+        let weak = Arc::downgrade(&data);
+        let period = self.period;
+        Task::spawn_ext(
+            "csv logger",
+            bindings::TASK_PRIORITY_MIN,
+            Task::DEFAULT_STACK_DEPTH,
+            move || {
+                let mut l = Loop::new(period);
+                while let Some(data) = weak.upgrade() {
+                    if let Err(err) = data.lock().sample() {
+                        eprintln!("{:?}", err);
+                    }
+                    drop(data);
+                    l.delay();
+                }
+            },
+        )?;
+        Ok(RunningCsvLogger(data))
+    }
+}
+
+/// A [`CsvLogger`] which is sampling in the background. Logging stops, and
+/// any buffered rows are written, when this is dropped.
+pub struct RunningCsvLogger(Arc<Mutex<CsvLoggerData>>);
+
+impl RunningCsvLogger {
+    #[inline]
+    /// Gets the path of the file being logged to.
+    pub fn path(&self) -> String {
+        self.0.lock().path.clone()
+    }
+
+    #[inline]
+    /// Writes any buffered rows to the SD card.
+    pub fn flush(&self) -> Result<(), SdError> {
+        self.0.lock().flush()
+    }
+}
+
+impl Drop for RunningCsvLogger {
+    fn drop(&mut self) {
+        if let Err(err) = self.flush() {
+            eprintln!("{:?}", err);
+        }
+    }
+}
+
+struct CsvLoggerData {
+    file: File,
+    path: String,
+    columns: Vec<Column>,
+    buffer: String,
+    rows: usize,
+    buffer_rows: usize,
+}
+
+impl CsvLoggerData {
+    fn sample(&mut self) -> Result<(), SdError> {
+        let time = time_since_start();
+        self.buffer.push_str(&format!(
+            "{}.{:03}",
+            time.as_millis() / 1000,
+            time.as_millis() % 1000
+        ));
+        for column in &mut self.columns {
+            self.buffer.push(',');
+            self.buffer.push_str(&escape(&column()));
+        }
+        self.buffer.push('\n');
+        self.rows += 1;
+        if self.rows >= self.buffer_rows {
+            self.flush()
+        } else {
+            Ok(())
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), SdError> {
+        if self.rows > 0 {
+            self.rows = 0;
+            let result = self.file.write_all(self.buffer.as_bytes());
+            self.buffer.clear();
+            result?;
+        }
+        self.file.flush()
+    }
+}
+
+/// Finds the first `{prefix}{n}.csv` which does not exist yet.
+pub(crate) fn next_path(prefix: &str) -> Result<String, SdError> {
+    for n in 0.. {
+        let path = format!("{}{}.csv", prefix, n);
+        match File::open(&path) {
+            Ok(_) => continue,
+            Err(SdError::NotFound) => return Ok(path),
+            Err(err) => return Err(err),
+        }
+    }
+    unreachable!()
+}
+
+/// Quotes a cell if it contains characters which are special in CSV.
+pub(crate) fn escape(cell: &str) -> String {
+    if cell.contains([',', '"', '\n']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.into()
+    }
+}
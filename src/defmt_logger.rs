@@ -0,0 +1,92 @@
+//! A [`defmt`] global logger which writes frames to standard output over the
+//! V5 USB serial connection.
+//!
+//! PROS normally wraps standard output in COBS-encoded stream packets, which
+//! `defmt-print` cannot decode, so the logger disables this framing the first
+//! time it is used. Text printed with `println!` is still sent, but between
+//! defmt frames; since frames are delimited, the decoder skips over it.
+
+use core::{
+    cell::UnsafeCell,
+    ptr,
+    sync::atomic::{AtomicBool, AtomicPtr, Ordering},
+};
+
+use crate::{
+    bindings,
+    rtos::{time_since_start, TIMEOUT_MAX},
+};
+
+defmt::timestamp!("{=u64:us}", time_since_start().as_micros());
+
+#[defmt::global_logger]
+struct Logger;
+
+static MUTEX: AtomicPtr<libc::c_void> = AtomicPtr::new(ptr::null_mut());
+static TAKEN: AtomicBool = AtomicBool::new(false);
+static ENCODER: Encoder = Encoder(UnsafeCell::new(defmt::Encoder::new()));
+
+struct Encoder(UnsafeCell<defmt::Encoder>);
+
+// Safety: the encoder is only accessed while the logger is acquired.
+unsafe impl Sync for Encoder {}
+
+impl Encoder {
+    #[allow(clippy::mut_from_ref)]
+    unsafe fn get(&self) -> &mut defmt::Encoder {
+        &mut *self.0.get()
+    }
+}
+
+/// Gets the mutex which serializes frames between tasks, creating it and
+/// disabling stream framing on first use.
+fn mutex() -> bindings::mutex_t {
+    let mutex = MUTEX.load(Ordering::Acquire);
+    if !mutex.is_null() {
+        return mutex;
+    }
+    let new = unsafe { bindings::mutex_recursive_create() };
+    match MUTEX.compare_exchange(ptr::null_mut(), new, Ordering::AcqRel, Ordering::Acquire) {
+        Ok(_) => {
+            unsafe {
+                bindings::serctl(bindings::SERCTL_DISABLE_COBS, ptr::null_mut());
+            }
+            new
+        }
+        Err(existing) => {
+            // Another task won the race to create the mutex.
+            unsafe { bindings::mutex_delete(new) };
+            existing
+        }
+    }
+}
+
+fn write(bytes: &[u8]) {
+    unsafe {
+        libc::write(libc::STDOUT_FILENO, bytes.as_ptr().cast(), bytes.len());
+    }
+}
+
+unsafe impl defmt::Logger for Logger {
+    fn acquire() {
+        unsafe {
+            bindings::mutex_recursive_take(mutex(), TIMEOUT_MAX);
+        }
+        if TAKEN.swap(true, Ordering::Acquire) {
+            panic!("defmt logger taken reentrantly");
+        }
+        unsafe { ENCODER.get().start_frame(write) }
+    }
+
+    unsafe fn flush() {}
+
+    unsafe fn release() {
+        ENCODER.get().end_frame(write);
+        TAKEN.store(false, Ordering::Release);
+        bindings::mutex_recursive_give(mutex());
+    }
+
+    unsafe fn write(bytes: &[u8]) {
+        ENCODER.get().write(bytes, write);
+    }
+}
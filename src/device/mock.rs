@@ -0,0 +1,253 @@
+//! Mock devices for testing control code.
+//!
+//! Each mock reads its values from a [`Script`], timed from when the mock was
+//! created or last [restarted](MockMotor::restart()).
+
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use super::{DistanceLike, MotorLike, RotarySensor};
+use crate::{
+    error::DeviceError,
+    rtos::{time_since_start, Instant},
+};
+
+/// A value which changes over time, described by a sequence of keyframes.
+///
+/// Between keyframes, the value either holds the value of the previous
+/// keyframe or ramps linearly towards the next one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Script {
+    initial: f64,
+    keyframes: Vec<Keyframe>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Keyframe {
+    at: Duration,
+    value: f64,
+    ramp: bool,
+}
+
+impl Script {
+    #[inline]
+    /// Creates a new script which starts at the given value.
+    pub fn new(initial: f64) -> Self {
+        Self {
+            initial,
+            keyframes: Vec::new(),
+        }
+    }
+
+    /// Adds a keyframe at which the value jumps to `value`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `at` is earlier than the previous keyframe.
+    pub fn step(self, at: Duration, value: f64) -> Self {
+        self.keyframe(at, value, false)
+    }
+
+    /// Adds a keyframe at which the value reaches `value`, moving linearly
+    /// from the previous keyframe.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `at` is earlier than the previous keyframe.
+    pub fn ramp(self, at: Duration, value: f64) -> Self {
+        self.keyframe(at, value, true)
+    }
+
+    /// Gets the value of the script at the given time since it started.
+    pub fn value_at(&self, elapsed: Duration) -> f64 {
+        let mut previous = (Duration::ZERO, self.initial);
+        for keyframe in self.keyframes.iter() {
+            if elapsed < keyframe.at {
+                if !keyframe.ramp {
+                    return previous.1;
+                }
+                let span = (keyframe.at - previous.0).as_secs_f64();
+                let t = (elapsed - previous.0).as_secs_f64() / span;
+                return previous.1 + (keyframe.value - previous.1) * t;
+            }
+            previous = (keyframe.at, keyframe.value);
+        }
+        previous.1
+    }
+
+    fn keyframe(mut self, at: Duration, value: f64, ramp: bool) -> Self {
+        assert!(
+            !matches!(self.keyframes.last(), Some(k) if k.at > at),
+            "script keyframes must be in order"
+        );
+        self.keyframes.push(Keyframe { at, value, ramp });
+        self
+    }
+}
+
+impl From<f64> for Script {
+    #[inline]
+    fn from(value: f64) -> Self {
+        Self::new(value)
+    }
+}
+
+/// A command sent to a [`MockMotor`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MockCommand {
+    /// An output voltage in millivolts.
+    Voltage(i32),
+    /// A target velocity in RPM.
+    Velocity(i32),
+}
+
+/// A mock motor, which records the commands sent to it and reports a scripted
+/// position, velocity and current draw.
+pub struct MockMotor {
+    position: Script,
+    velocity: Script,
+    current: Script,
+    start: Instant,
+    offset: f64,
+    commands: Vec<MockCommand>,
+}
+
+impl MockMotor {
+    /// Creates a new mock motor.
+    pub fn new(position: impl Into<Script>, velocity: impl Into<Script>) -> Self {
+        Self {
+            position: position.into(),
+            velocity: velocity.into(),
+            current: Script::new(0.0),
+            start: time_since_start(),
+            offset: 0.0,
+            commands: Vec::new(),
+        }
+    }
+
+    #[inline]
+    /// Sets the script for the current drawn by the motor in milliamperes,
+    /// which is zero by default.
+    pub fn with_current(mut self, current: impl Into<Script>) -> Self {
+        self.current = current.into();
+        self
+    }
+
+    #[inline]
+    /// Gets every command sent to the motor, oldest first.
+    pub fn commands(&self) -> &[MockCommand] {
+        &self.commands
+    }
+
+    #[inline]
+    /// Gets the most recent command sent to the motor.
+    pub fn last_command(&self) -> Option<MockCommand> {
+        self.commands.last().copied()
+    }
+
+    /// Restarts the scripts from the beginning and clears the recorded
+    /// commands and zero position.
+    pub fn restart(&mut self) {
+        self.start = time_since_start();
+        self.offset = 0.0;
+        self.commands.clear();
+    }
+}
+
+impl MotorLike for MockMotor {
+    fn move_voltage(&mut self, voltage: i32) -> Result<(), DeviceError> {
+        self.commands.push(MockCommand::Voltage(voltage));
+        Ok(())
+    }
+
+    fn move_velocity(&mut self, velocity: i32) -> Result<(), DeviceError> {
+        self.commands.push(MockCommand::Velocity(velocity));
+        Ok(())
+    }
+
+    fn position(&self) -> Result<f64, DeviceError> {
+        Ok(self.position.value_at(elapsed(self.start)) - self.offset)
+    }
+
+    fn velocity(&self) -> Result<f64, DeviceError> {
+        Ok(self.velocity.value_at(elapsed(self.start)))
+    }
+
+    fn current_draw(&self) -> Result<i32, DeviceError> {
+        Ok(self.current.value_at(elapsed(self.start)) as i32)
+    }
+
+    fn tare_position(&mut self) -> Result<(), DeviceError> {
+        self.offset = self.position.value_at(elapsed(self.start));
+        Ok(())
+    }
+}
+
+/// A mock rotary sensor, which reports a scripted position in degrees.
+pub struct MockRotarySensor {
+    position: Script,
+    start: Instant,
+    offset: f64,
+}
+
+impl MockRotarySensor {
+    /// Creates a new mock rotary sensor.
+    pub fn new(position: impl Into<Script>) -> Self {
+        Self {
+            position: position.into(),
+            start: time_since_start(),
+            offset: 0.0,
+        }
+    }
+
+    /// Restarts the script from the beginning and clears the zero position.
+    pub fn restart(&mut self) {
+        self.start = time_since_start();
+        self.offset = 0.0;
+    }
+}
+
+impl RotarySensor for MockRotarySensor {
+    fn position(&self) -> Result<f64, DeviceError> {
+        Ok(self.position.value_at(elapsed(self.start)) - self.offset)
+    }
+
+    fn reset_position(&mut self) -> Result<(), DeviceError> {
+        self.offset = self.position.value_at(elapsed(self.start));
+        Ok(())
+    }
+}
+
+/// A mock distance sensor, which reports a scripted distance in millimetres.
+pub struct MockDistanceSensor {
+    distance: Script,
+    start: Instant,
+}
+
+impl MockDistanceSensor {
+    /// Creates a new mock distance sensor.
+    pub fn new(distance: impl Into<Script>) -> Self {
+        Self {
+            distance: distance.into(),
+            start: time_since_start(),
+        }
+    }
+
+    #[inline]
+    /// Restarts the script from the beginning.
+    pub fn restart(&mut self) {
+        self.start = time_since_start();
+    }
+}
+
+impl DistanceLike for MockDistanceSensor {
+    fn distance(&self) -> Result<f64, DeviceError> {
+        Ok(self.distance.value_at(elapsed(self.start)))
+    }
+}
+
+fn elapsed(start: Instant) -> Duration {
+    time_since_start()
+        .checked_sub_instant(start)
+        .unwrap_or_default()
+}
@@ -0,0 +1,201 @@
+//! # Device Traits.
+//!
+//! Traits which abstract over the devices used by control code, so that it can
+//! be written once against the traits and run against either real devices or
+//! the [mocks](mock) in this module, whose readings follow a scripted course
+//! over time.
+//!
+//! Sensor readings are converted to the units fixed by the traits, so that
+//! different sensors measuring the same quantity are interchangeable. A
+//! [`GearedSensor`] converts the reading of a sensor which measures a
+//! mechanism through a gear train to the rotation of the mechanism itself.
+//!
+//! # Example
+//!
+//! ```
+//! fn hold_at<M: MotorLike>(motor: &mut M, target: f64) -> Result<(), DeviceError> {
+//!     let error = target - motor.position()?;
+//!     motor.move_voltage((error * 10.0) as i32)
+//! }
+//!
+//! // On the robot:
+//! hold_at(&mut self.lift, 900.0)?;
+//!
+//! // In a test:
+//! let mut motor = MockMotor::new(Script::new(600.0), Script::new(0.0));
+//! hold_at(&mut motor, 900.0).unwrap();
+//! assert_eq!(motor.last_command(), Some(MockCommand::Voltage(3000)));
+//! ```
+
+use crate::error::DeviceError;
+
+pub mod mock;
+
+/// A motor, or a group of motors driven together.
+pub trait MotorLike {
+    /// Sets the output voltage of the motor from -12000 to 12000 in
+    /// millivolts.
+    fn move_voltage(&mut self, voltage: i32) -> Result<(), DeviceError>;
+
+    /// Sets the target velocity of the motor in RPM.
+    fn move_velocity(&mut self, velocity: i32) -> Result<(), DeviceError>;
+
+    /// Gets the position of the motor in its configured encoder units.
+    fn position(&self) -> Result<f64, DeviceError>;
+
+    /// Gets the actual velocity of the motor in RPM.
+    fn velocity(&self) -> Result<f64, DeviceError>;
+
+    /// Gets the current drawn by the motor in milliamperes.
+    fn current_draw(&self) -> Result<i32, DeviceError>;
+
+    /// Sets the zero position of the motor to its current position.
+    fn tare_position(&mut self) -> Result<(), DeviceError>;
+}
+
+/// A sensor which measures the rotation of a shaft.
+pub trait RotarySensor {
+    /// Gets the accumulated rotation of the shaft in degrees.
+    fn position(&self) -> Result<f64, DeviceError>;
+
+    /// Sets the zero position of the sensor to its current position.
+    fn reset_position(&mut self) -> Result<(), DeviceError>;
+}
+
+/// A [`RotarySensor`] adapter for a sensor which measures a mechanism through
+/// a gear train, reporting the rotation of the mechanism's output shaft.
+///
+/// The output position is `ratio * sensor position + offset`, in degrees.
+///
+/// # Example
+///
+/// ```
+/// // A rotation sensor on a 12-tooth gear driving a 60-tooth arm gear, with
+/// // the arm resting at -30 degrees when the sensor is reset.
+/// let arm = GearedSensor::new(rotation, 12.0 / 60.0).with_offset(-30.0);
+/// ```
+pub struct GearedSensor<S: RotarySensor> {
+    sensor: S,
+    ratio: f64,
+    offset: f64,
+}
+
+impl<S: RotarySensor> GearedSensor<S> {
+    #[inline]
+    /// Creates a new adapter, where `ratio` is the number of turns of the
+    /// output shaft per turn of the sensor. For a compound gear train, this is
+    /// the product of the ratios of each stage; it is negative if the output
+    /// turns in the opposite direction to the sensor.
+    pub fn new(sensor: S, ratio: f64) -> Self {
+        Self {
+            sensor,
+            ratio,
+            offset: 0.0,
+        }
+    }
+
+    #[inline]
+    /// Sets the position of the output shaft when the sensor reads zero, in
+    /// degrees. The default is 0.
+    pub fn with_offset(mut self, offset: f64) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    #[inline]
+    /// Gets the number of turns of the output shaft per turn of the sensor.
+    pub fn ratio(&self) -> f64 {
+        self.ratio
+    }
+
+    #[inline]
+    /// Gets the position of the output shaft when the sensor reads zero, in
+    /// degrees.
+    pub fn offset(&self) -> f64 {
+        self.offset
+    }
+
+    #[inline]
+    /// Gets the underlying sensor.
+    pub fn sensor(&mut self) -> &mut S {
+        &mut self.sensor
+    }
+
+    #[inline]
+    /// Consumes the adapter, returning the underlying sensor.
+    pub fn into_inner(self) -> S {
+        self.sensor
+    }
+}
+
+impl<S: RotarySensor> RotarySensor for GearedSensor<S> {
+    #[inline]
+    fn position(&self) -> Result<f64, DeviceError> {
+        Ok(self.sensor.position()? * self.ratio + self.offset)
+    }
+
+    #[inline]
+    /// Sets the zero position of the underlying sensor to its current
+    /// position, so that the output position then reads the
+    /// [offset](GearedSensor::with_offset()).
+    fn reset_position(&mut self) -> Result<(), DeviceError> {
+        self.sensor.reset_position()
+    }
+}
+
+/// A sensor which measures the distance to an object.
+pub trait DistanceLike {
+    /// Gets the distance to the nearest object in millimetres.
+    fn distance(&self) -> Result<f64, DeviceError>;
+}
+
+impl<M: MotorLike + ?Sized> MotorLike for &mut M {
+    #[inline]
+    fn move_voltage(&mut self, voltage: i32) -> Result<(), DeviceError> {
+        (**self).move_voltage(voltage)
+    }
+
+    #[inline]
+    fn move_velocity(&mut self, velocity: i32) -> Result<(), DeviceError> {
+        (**self).move_velocity(velocity)
+    }
+
+    #[inline]
+    fn position(&self) -> Result<f64, DeviceError> {
+        (**self).position()
+    }
+
+    #[inline]
+    fn velocity(&self) -> Result<f64, DeviceError> {
+        (**self).velocity()
+    }
+
+    #[inline]
+    fn current_draw(&self) -> Result<i32, DeviceError> {
+        (**self).current_draw()
+    }
+
+    #[inline]
+    fn tare_position(&mut self) -> Result<(), DeviceError> {
+        (**self).tare_position()
+    }
+}
+
+impl<S: RotarySensor + ?Sized> RotarySensor for &mut S {
+    #[inline]
+    fn position(&self) -> Result<f64, DeviceError> {
+        (**self).position()
+    }
+
+    #[inline]
+    fn reset_position(&mut self) -> Result<(), DeviceError> {
+        (**self).reset_position()
+    }
+}
+
+impl<S: DistanceLike + ?Sized> DistanceLike for &S {
+    #[inline]
+    fn distance(&self) -> Result<f64, DeviceError> {
+        (**self).distance()
+    }
+}
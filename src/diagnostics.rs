@@ -0,0 +1,289 @@
+//! # Smart Port Diagnostics.
+//!
+//! A [`DiagnosticsPage`] lists all 21 smart ports on the brain screen, with
+//! the type of device detected on each, how long it has been connected, how
+//! many times it has reconnected, and the last error reported by any device on
+//! the port, refreshed live. A device which keeps rebooting, e.g. because of a
+//! damaged cable or static discharge, shows up as a short connection time and
+//! a rising reconnect count, so wiring problems are visible at a glance in the
+//! pit.
+//!
+//! PROS does not expose device firmware versions, so they are not shown.
+//!
+//! Errors are recorded whenever a [`DeviceError`] is generated from `errno`,
+//! so the last error is available whether or not the page is running; see
+//! [`last_error()`].
+//!
+//! # Example
+//!
+//! ```
+//! // In a pit-mode screen:
+//! let page = DiagnosticsPage::new();
+//! // ...
+//! drop(page); // Stops refreshing the screen.
+//! ```
+
+use alloc::{
+    format,
+    string::String,
+    sync::{Arc, Weak},
+    vec::Vec,
+};
+use core::time::Duration;
+
+use cstring_interop::with_cstring;
+
+use crate::{
+    bindings,
+    error::{DeviceError, DeviceErrorKind, DevicePort, Error},
+    rtos::{time_since_start, Instant, Loop, Mutex, Task},
+    screen::SCREEN_WIDTH,
+    smart_port::{smart_port_type, DeviceType},
+};
+
+/// The number of smart ports on the brain.
+const PORT_COUNT: usize = 21;
+
+/// The number of ports listed in each column of the page.
+const ROWS: usize = 11;
+
+/// The height of a line of small text, in pixels.
+const LINE_HEIGHT: i16 = 20;
+
+/// The number of characters which fit in a column of small text.
+const COLUMN_CHARS: usize = 29;
+
+/// The interval at which the page is refreshed.
+const UPDATE_PERIOD: Duration = Duration::from_millis(500);
+
+/// How long an error is highlighted after it occurs.
+const RECENT_ERROR: Duration = Duration::from_secs(5);
+
+const IDLE_COLOR: u32 = 0x0080_8080;
+const OK_COLOR: u32 = 0x0000_FF00;
+const ERROR_COLOR: u32 = 0x00FF_FF00;
+const LOST_COLOR: u32 = 0x00FF_0000;
+
+type LastErrors = [Option<(DeviceErrorKind, Instant)>; PORT_COUNT];
+
+static LAST_ERRORS: spin::Once<Mutex<LastErrors>> = spin::Once::new();
+
+fn last_errors() -> &'static Mutex<LastErrors> {
+    LAST_ERRORS.call_once(|| Mutex::new([None; PORT_COUNT]))
+}
+
+/// Records an error reported by a device, against its smart port or, for an
+/// ADI device on an expander, the expander's port, and returns it. Device
+/// wrappers call this as they report errors.
+///
+/// This never blocks, so that it can be used at any priority: the error is not
+/// recorded if another task is using the record at the same moment.
+pub(crate) fn record_error(err: DeviceError) -> DeviceError {
+    let port = match err.port {
+        DevicePort::Smart(port) => port,
+        DevicePort::Adi {
+            expander_port: DevicePort::INTERNAL_ADI_PORT,
+            ..
+        } => return err,
+        DevicePort::Adi { expander_port, .. } => expander_port,
+    };
+    if let Some(mut errors) = last_errors().poll() {
+        if let Some(slot) = (port as usize)
+            .checked_sub(1)
+            .and_then(|i| errors.get_mut(i))
+        {
+            *slot = Some((err.kind, time_since_start()));
+        }
+    }
+    err
+}
+
+/// Gets the last error reported by a device on the given smart port (1-21),
+/// and when it occurred.
+pub fn last_error(port: u8) -> Option<(DeviceErrorKind, Instant)> {
+    let index = (port as usize).checked_sub(1)?;
+    *last_errors().lock().get(index)?
+}
+
+/// The state of a smart port, as seen by a [`DiagnosticsPage`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PortStatus {
+    /// The port number (1-21).
+    pub port: u8,
+    /// The type of device plugged into the port.
+    pub device: DeviceType,
+    /// When the device was connected, or when the page was created if it was
+    /// already connected, or `None` if nothing is connected.
+    pub connected_since: Option<Instant>,
+    /// The number of times a device has been reconnected to the port after
+    /// being disconnected.
+    pub reconnects: u32,
+    /// Whether a device was connected earlier but is no longer.
+    pub lost: bool,
+    /// The last error reported by a device on the port, and when it occurred.
+    pub last_error: Option<(DeviceErrorKind, Instant)>,
+}
+
+impl PortStatus {
+    fn new(port: u8) -> Self {
+        Self {
+            port,
+            device: DeviceType::None,
+            connected_since: None,
+            reconnects: 0,
+            lost: false,
+            last_error: None,
+        }
+    }
+
+    /// Updates the status with the currently plugged device.
+    fn update(&mut self, device: DeviceType, now: Instant) {
+        let connected = device != DeviceType::None;
+        match (self.connected_since, connected) {
+            (None, true) => {
+                if self.lost {
+                    self.reconnects += 1;
+                }
+                self.connected_since = Some(now);
+                self.lost = false;
+            }
+            (Some(_), false) => {
+                self.connected_since = None;
+                self.lost = true;
+            }
+            _ => {}
+        }
+        self.device = device;
+        self.last_error = last_error(self.port);
+    }
+
+    /// Formats the status as a line of the page.
+    fn line(&self, now: Instant) -> (String, u32) {
+        let mut line = format!("{:>2} {:<8.8}", self.port, format!("{:?}", self.device));
+        let color = match (self.connected_since, self.last_error) {
+            (None, _) if self.lost => {
+                line += " LOST";
+                LOST_COLOR
+            }
+            (None, _) => IDLE_COLOR,
+            (Some(since), error) => {
+                line += &format!(" {}", short_duration(now - since));
+                if self.reconnects > 0 {
+                    line += &format!(" r{}", self.reconnects);
+                }
+                match error {
+                    Some((kind, at)) => {
+                        line += &format!(" {}", kind);
+                        if now - at < RECENT_ERROR {
+                            ERROR_COLOR
+                        } else {
+                            OK_COLOR
+                        }
+                    }
+                    None => OK_COLOR,
+                }
+            }
+        };
+        (line.chars().take(COLUMN_CHARS).collect(), color)
+    }
+}
+
+/// Shows the status of every smart port on the brain screen, from a
+/// background task. See the [module-level documentation](self).
+///
+/// The page uses the whole screen, and the task exits when this is dropped.
+pub struct DiagnosticsPage(Arc<Mutex<Vec<PortStatus>>>);
+
+impl DiagnosticsPage {
+    #[inline]
+    /// Creates a new diagnostics page and spawns its task. Panics on failure;
+    /// see [`DiagnosticsPage::try_new()`].
+    pub fn new() -> Self {
+        Self::try_new().unwrap_or_else(|err| panic!("failed to create diagnostics page: {:?}", err))
+    }
+
+    /// Creates a new diagnostics page and spawns its task.
+    pub fn try_new() -> Result<Self, Error> {
+        let data = Arc::new(Mutex::try_new(
+            (1..=PORT_COUNT as u8).map(PortStatus::new).collect(),
+        )?);
+        let weak: Weak<Mutex<Vec<PortStatus>>> = Arc::downgrade(&data);
+        Task::spawn_ext(
+            "diagnostics",
+            bindings::TASK_PRIORITY_MIN,
+            Task::DEFAULT_STACK_DEPTH,
+            move || {
+                let mut l = Loop::new(UPDATE_PERIOD);
+                unsafe {
+                    bindings::screen_erase_rect(
+                        0,
+                        0,
+                        SCREEN_WIDTH - 1,
+                        ROWS as i16 * LINE_HEIGHT - 1,
+                    )
+                };
+                while let Some(data) = weak.upgrade() {
+                    let now = time_since_start();
+                    let lines: Vec<(String, u32)> = {
+                        let mut data = data.lock();
+                        data.iter_mut()
+                            .map(|status| {
+                                status.update(unsafe { smart_port_type(status.port) }, now);
+                                status.line(now)
+                            })
+                            .collect()
+                    };
+                    drop(data);
+                    draw(&lines);
+                    l.delay();
+                }
+            },
+        )?;
+        Ok(Self(data))
+    }
+
+    #[inline]
+    /// Gets the status of every smart port as of the last refresh.
+    pub fn status(&self) -> Vec<PortStatus> {
+        self.0.lock().clone()
+    }
+}
+
+impl Default for DiagnosticsPage {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn draw(lines: &[(String, u32)]) {
+    let width = SCREEN_WIDTH / 2;
+    unsafe {
+        let pen = bindings::screen_get_pen();
+        for (i, (line, color)) in lines.iter().enumerate() {
+            let x = (i / ROWS) as i16 * width;
+            let y = (i % ROWS) as i16 * LINE_HEIGHT;
+            bindings::screen_erase_rect(x, y, x + width - 1, y + LINE_HEIGHT - 1);
+            bindings::screen_set_pen(*color);
+            with_cstring(line.clone(), |line| {
+                bindings::screen_print_at(
+                    bindings::text_format_e_t_E_TEXT_SMALL,
+                    x,
+                    y,
+                    b"%s\0".as_ptr().cast(),
+                    line.into_raw(),
+                );
+            });
+        }
+        bindings::screen_set_pen(pen);
+    }
+}
+
+/// Formats a duration compactly, e.g. `42s` or `12m`.
+fn short_duration(duration: Duration) -> String {
+    match duration.as_secs() {
+        secs @ 0..=99 => format!("{}s", secs),
+        secs @ 100..=5999 => format!("{}m", secs / 60),
+        secs => format!("{}h", secs / 3600),
+    }
+}
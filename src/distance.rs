@@ -2,7 +2,9 @@
 
 use crate::{
     bindings,
-    error::{get_errno, Error},
+    device::DistanceLike,
+    diagnostics,
+    error::{DeviceError, DevicePort},
     rtos::DataSource,
 };
 
@@ -26,7 +28,7 @@ impl DistanceSensor {
     /// Gets the currently measured distance from the sensor in millimetres.
     pub fn get_distance(&self) -> Result<i32, DistanceSensorError> {
         match unsafe { bindings::distance_get(self.port) } {
-            x if x == bindings::PROS_ERR_ => Err(DistanceSensorError::from_errno()),
+            x if x == bindings::PROS_ERR_ => Err(self.error()),
             x => Ok(x),
         }
     }
@@ -38,7 +40,7 @@ impl DistanceSensor {
     /// distance is > 200mm (the value 10 is returned in this scenario).
     pub fn get_confidence(&self) -> Result<i32, DistanceSensorError> {
         match unsafe { bindings::distance_get_confidence(self.port) } {
-            x if x == bindings::PROS_ERR_ => Err(DistanceSensorError::from_errno()),
+            x if x == bindings::PROS_ERR_ => Err(self.error()),
             x => Ok(x),
         }
     }
@@ -49,7 +51,7 @@ impl DistanceSensor {
     /// return a value of approximately 75 in typical room lighting.
     pub fn get_object_size(&self) -> Result<i32, DistanceSensorError> {
         match unsafe { bindings::distance_get_object_size(self.port) } {
-            x if x == bindings::PROS_ERR_ => Err(DistanceSensorError::from_errno()),
+            x if x == bindings::PROS_ERR_ => Err(self.error()),
             x => Ok(x),
         }
     }
@@ -57,10 +59,17 @@ impl DistanceSensor {
     /// Gets the object velocity in metres per second.
     pub fn get_object_velocity(&self) -> Result<f64, DistanceSensorError> {
         match unsafe { bindings::distance_get_object_velocity(self.port) } {
-            x if x == bindings::PROS_ERR_F_ => Err(DistanceSensorError::from_errno()),
+            x if x == bindings::PROS_ERR_F_ => Err(self.error()),
             x => Ok(x),
         }
     }
+
+    fn error(&self) -> DeviceError {
+        diagnostics::record_error(DeviceError::from_errno(
+            DevicePort::Smart(self.port),
+            "a distance sensor",
+        ))
+    }
 }
 
 impl DataSource for DistanceSensor {
@@ -77,6 +86,13 @@ impl DataSource for DistanceSensor {
     }
 }
 
+impl DistanceLike for DistanceSensor {
+    #[inline]
+    fn distance(&self) -> Result<f64, DeviceError> {
+        Ok(self.get_distance()? as f64)
+    }
+}
+
 /// Represents the data that can be read from a distance sensor.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct DistanceData {
@@ -88,35 +104,6 @@ pub struct DistanceData {
     pub velocity: f64,
 }
 
-/// Represents possible errors for distance sensor operations.
-#[derive(Debug)]
-pub enum DistanceSensorError {
-    /// Port is out of range (1-21).
-    PortOutOfRange,
-    /// Port cannot be configured as a distance sensor.
-    PortNotDistanceSensor,
-    /// Unknown error.
-    Unknown(i32),
-}
-
-impl DistanceSensorError {
-    fn from_errno() -> Self {
-        match get_errno() {
-            libc::ENXIO => Self::PortOutOfRange,
-            libc::ENODEV => Self::PortNotDistanceSensor,
-            x => Self::Unknown(x),
-        }
-    }
-}
-
-impl From<DistanceSensorError> for Error {
-    fn from(err: DistanceSensorError) -> Self {
-        match err {
-            DistanceSensorError::PortOutOfRange => Error::Custom("port out of range".into()),
-            DistanceSensorError::PortNotDistanceSensor => {
-                Error::Custom("port not a distance sensor".into())
-            }
-            DistanceSensorError::Unknown(n) => Error::System(n),
-        }
-    }
-}
+/// Represents possible errors for distance sensor operations. This is an alias
+/// of [`DeviceError`].
+pub type DistanceSensorError = DeviceError;
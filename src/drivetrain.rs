@@ -0,0 +1,1549 @@
+//! # Drivetrain API.
+//!
+//! [`DifferentialDrivetrain`] (and [`Chassis`], which runs one on a background
+//! task) supports tank-style drivetrains with odometry and closed-loop
+//! movement commands, while [`HolonomicDrivetrain`] supports mecanum drives and
+//! X-drives, including odometry and field-centric driving.
+//! [`WheelVelocityController`] controls the speed of a group of driven wheels,
+//! optionally measured by a tracking wheel.
+//!
+//! Headings follow the same convention as the [`InertialSensor`]: clockwise
+//! rotations are positive, and a heading of zero points along the positive y
+//! axis.
+
+use alloc::sync::{Arc, Weak};
+use core::{f64::consts::SQRT_2, time::Duration};
+
+#[cfg(not(feature = "sim"))]
+use num_traits::Float;
+use owner_monad::OwnerMut;
+
+use crate::{
+    adi::AdiEncoder,
+    contact::{Contact, ContactConfig, ContactDetector, ContactSample},
+    control::{FeedbackController, Pid, Settled},
+    error::{DeviceError, Error},
+    escalation,
+    filter::{Ema, Filter, HeadingFilter},
+    geometry::{Pose2d, Rotation2d, Vec2},
+    imu::InertialSensor,
+    motion::{Path, Trajectory},
+    motor::MotorGroup,
+    rotation::RotationSensor,
+    rtos::{
+        handle_event, time_since_start, Context, Event, EventHandle, GenericSleep, Instant, Loop,
+        Mutex, Selectable, Task,
+    },
+    select,
+    units::{
+        Conversions, Degrees, MetersPerSecond, MetersPerSecondSquared, Millimeters, Radians, Rpm,
+    },
+};
+
+const UPDATE_PERIOD: Duration = Duration::from_millis(10);
+
+/// The voltage in millivolts at which the motors reach their free speed, used
+/// for velocity feedforward.
+const NOMINAL_VOLTAGE: f64 = 12000.0;
+
+/// Standard gravity in metres per second squared, for converting inertial
+/// sensor readings.
+const STANDARD_GRAVITY: f64 = 9.80665;
+
+/// A two-sided, tank-style drivetrain with optional inertial sensor and
+/// tracking wheels, providing odometry and closed-loop movement commands.
+pub struct DifferentialDrivetrain {
+    left: MotorGroup,
+    right: MotorGroup,
+    imu: Option<InertialSensor>,
+    tracking: Option<(TrackingWheel, TrackingWheel)>,
+    fusion: Option<HeadingFusion>,
+    traction: Option<TractionState>,
+    config: DrivetrainConfig,
+    distance_pid: Pid,
+    turn_pid: Pid,
+    pose: Pose2d,
+    last: Option<OdometryReading>,
+}
+
+impl DifferentialDrivetrain {
+    /// Constructs a new drivetrain from its left and right motor groups.
+    ///
+    /// Without further configuration, odometry is computed from the motor
+    /// encoders alone. See [`with_imu()`](Self::with_imu()) and
+    /// [`with_tracking_wheels()`](Self::with_tracking_wheels()).
+    pub fn new(left: MotorGroup, right: MotorGroup, config: DrivetrainConfig) -> Self {
+        Self {
+            left,
+            right,
+            imu: None,
+            tracking: None,
+            fusion: None,
+            traction: None,
+            config,
+            distance_pid: Pid::new(30.0, 0.0, 2.0),
+            turn_pid: Pid::new(150.0, 0.0, 10.0),
+            pose: Pose2d::default(),
+            last: None,
+        }
+    }
+
+    #[inline]
+    /// Uses an inertial sensor to measure the heading of the drivetrain.
+    ///
+    /// The sensor should already have finished calibrating.
+    pub fn with_imu(mut self, imu: InertialSensor) -> Self {
+        self.imu = Some(imu);
+        self.last = None;
+        self
+    }
+
+    #[inline]
+    /// Uses a pair of parallel, unpowered tracking wheels to measure the
+    /// distance travelled by each side of the drivetrain.
+    pub fn with_tracking_wheels(mut self, left: TrackingWheel, right: TrackingWheel) -> Self {
+        self.tracking = Some((left, right));
+        self.last = None;
+        self
+    }
+
+    #[inline]
+    /// Fuses the inertial sensor heading with the heading derived from the
+    /// difference between the left and right sides, to counteract gyro drift
+    /// over long periods.
+    ///
+    /// This only has an effect when an inertial sensor is in use (see
+    /// [`with_imu()`](Self::with_imu())), and is most effective with tracking
+    /// wheels, which do not slip like driven wheels.
+    pub fn with_heading_fusion(mut self, process_noise: f64, measurement_noise: f64) -> Self {
+        self.fusion = Some(HeadingFusion {
+            filter: HeadingFilter::new(
+                self.pose.heading.degrees().0,
+                process_noise,
+                measurement_noise,
+            ),
+            encoder_heading: self.pose.heading.degrees().0,
+        });
+        self
+    }
+
+    #[inline]
+    /// Detects wheel slip by comparing the acceleration of the driven wheels
+    /// against the acceleration of the chassis, and temporarily reduces the
+    /// power of the movement commands while slipping.
+    ///
+    /// The acceleration of the chassis is measured by the tracking wheels if
+    /// present, or otherwise by the inertial sensor; without either, this has
+    /// no effect.
+    pub fn with_traction_control(mut self, traction: TractionControl) -> Self {
+        self.traction = Some(TractionState::new(traction));
+        self
+    }
+
+    #[inline]
+    /// Sets the controller used for
+    /// [`drive_distance()`](Self::drive_distance())
+    /// and [`follow_path()`](Self::follow_path()), which maps an error in
+    /// millimetres to an output in millivolts.
+    pub fn with_distance_pid(mut self, pid: Pid) -> Self {
+        self.distance_pid = pid;
+        self
+    }
+
+    #[inline]
+    /// Sets the controller used for turning and heading correction, which maps
+    /// an error in degrees to an output in millivolts.
+    pub fn with_turn_pid(mut self, pid: Pid) -> Self {
+        self.turn_pid = pid;
+        self
+    }
+
+    #[inline]
+    /// Gets the configuration of the drivetrain.
+    pub fn config(&self) -> &DrivetrainConfig {
+        &self.config
+    }
+
+    #[inline]
+    /// Replaces the configuration of the drivetrain.
+    pub fn set_config(&mut self, config: DrivetrainConfig) {
+        self.config = config;
+    }
+
+    #[inline]
+    /// Gets the left motor group.
+    pub fn left(&mut self) -> &mut MotorGroup {
+        &mut self.left
+    }
+
+    #[inline]
+    /// Gets the right motor group.
+    pub fn right(&mut self) -> &mut MotorGroup {
+        &mut self.right
+    }
+
+    #[inline]
+    /// Gets the most recently computed pose of the drivetrain.
+    ///
+    /// The pose is only updated by
+    /// [`update_odometry()`](Self::update_odometry()) and the movement
+    /// commands.
+    pub fn pose(&self) -> Pose2d {
+        self.pose
+    }
+
+    #[inline]
+    /// Overrides the current pose of the drivetrain.
+    pub fn set_pose(&mut self, pose: Pose2d) {
+        self.pose = pose;
+        if let Some(fusion) = &mut self.fusion {
+            fusion.filter.reset(pose.heading.degrees().0);
+            fusion.encoder_heading = pose.heading.degrees().0;
+        }
+    }
+
+    /// Corrects the fused heading using an absolute heading measurement, such
+    /// as a heading from a GPS sensor, with the given variance in square
+    /// degrees. Returns the corrected pose.
+    ///
+    /// This only has an effect when heading fusion is in use (see
+    /// [`with_heading_fusion()`](Self::with_heading_fusion())); see
+    /// [`HeadingFilter::correct()`].
+    pub fn correct_heading(&mut self, heading: impl Into<Rotation2d>, variance: f64) -> Pose2d {
+        if let Some(fusion) = &mut self.fusion {
+            let heading = fusion.filter.correct(heading.into().degrees().0, variance);
+            self.pose.heading = Rotation2d::new(Degrees(heading));
+        }
+        self.pose
+    }
+
+    /// Reads the sensors and integrates the movement since the last update into
+    /// the current pose.
+    ///
+    /// This should be called regularly (e.g., every 10ms) whenever the
+    /// drivetrain is not executing a movement command.
+    pub fn update_odometry(&mut self) -> Result<Pose2d, DrivetrainError> {
+        let reading = self.read_odometry()?;
+        if let Some(last) = self.last.replace(reading) {
+            let (left_offset, right_offset) = self.wheel_offsets();
+            let dl = reading.left - last.left;
+            let dr = reading.right - last.right;
+            let encoder_dtheta = (dl - dr) / (right_offset - left_offset);
+            let dtheta = match (reading.rotation, last.rotation, &mut self.fusion) {
+                (Some(r), Some(l), Some(fusion)) => {
+                    fusion.encoder_heading += encoder_dtheta.to_degrees();
+                    let heading = fusion.filter.update(r - l, fusion.encoder_heading);
+                    (Rotation2d::new(Degrees(heading)) - self.pose.heading)
+                        .radians()
+                        .0
+                }
+                (Some(r), Some(l), None) => (r - l).to_radians(),
+                _ => encoder_dtheta,
+            };
+            let ds = (dl + dr) / 2.0 + (left_offset + right_offset) / 2.0 * dtheta;
+            let mid = self.pose.heading + Radians(dtheta / 2.0).into();
+            self.pose.position += Vec2::from_polar(Millimeters(ds), mid);
+            self.pose.heading += Radians(dtheta).into();
+        }
+        if self.traction.is_some() {
+            self.update_traction(reading)?;
+        }
+        Ok(self.pose)
+    }
+
+    #[inline]
+    /// Reads the motors, and the inertial sensor if there is one, for contact
+    /// detection with a [`ContactDetector`].
+    pub fn contact_sample(&self) -> Result<ContactSample, DrivetrainError> {
+        let motors = self.left.motors().len() + self.right.motors().len();
+        let current = self.left.get_current_draw()? + self.right.get_current_draw()?;
+        let velocity = (self.left.get_actual_velocity()?.abs()
+            + self.right.get_actual_velocity()?.abs())
+            / 2.0;
+        let acceleration = match &self.imu {
+            Some(imu) => {
+                let accel = imu.get_accel()?;
+                Some(accel.x.hypot(accel.y))
+            }
+            None => None,
+        };
+        Ok(ContactSample {
+            current: current as f64 / motors.max(1) as f64,
+            velocity,
+            acceleration,
+        })
+    }
+
+    /// Checks whether the driven wheels are currently slipping, as detected by
+    /// traction control (see
+    /// [`with_traction_control()`](Self::with_traction_control())).
+    pub fn is_slipping(&self) -> bool {
+        matches!(&self.traction, Some(t) if t.slipping)
+    }
+
+    /// Drives from -127 to 127 with a forward component and a turning
+    /// component, as is typical for single-stick or split arcade control.
+    pub fn arcade(&mut self, forward: i8, turn: i8) -> Result<(), DrivetrainError> {
+        self.left.move_i8(clamp_i8(forward as i16 + turn as i16))?;
+        self.right.move_i8(clamp_i8(forward as i16 - turn as i16))?;
+        Ok(())
+    }
+
+    /// Drives each side directly from -127 to 127, as is typical for tank
+    /// control.
+    pub fn tank(&mut self, left: i8, right: i8) -> Result<(), DrivetrainError> {
+        self.left.move_i8(left)?;
+        self.right.move_i8(right)?;
+        Ok(())
+    }
+
+    /// Stops both sides of the drivetrain.
+    pub fn stop(&mut self) -> Result<(), DrivetrainError> {
+        self.left.move_voltage(0)?;
+        self.right.move_voltage(0)?;
+        Ok(())
+    }
+
+    /// Drives in a straight line for the given distance, holding the current
+    /// heading. Negative distances drive backwards.
+    ///
+    /// Blocks until the movement settles or `ctx` is cancelled.
+    pub fn drive_distance(
+        &mut self,
+        ctx: &Context,
+        distance: impl Into<Millimeters>,
+    ) -> Result<(), DrivetrainError> {
+        let distance = distance.into();
+        let start = self.update_odometry()?;
+        self.distance_pid.reset();
+        self.turn_pid.reset();
+        self.run(ctx, |dt, pose| {
+            let travelled = pose.relative_to(start).position.y;
+            let forward = dt.distance_pid.step(distance.0, travelled.0);
+            let turn = dt
+                .turn_pid
+                .step(0.0, -(start.heading - pose.heading).degrees().0);
+            (
+                forward + turn,
+                forward - turn,
+                (distance - travelled).abs() <= dt.config.distance_tolerance,
+            )
+        })
+    }
+
+    /// Turns in place to face the given absolute heading, taking the shortest
+    /// direction.
+    ///
+    /// Blocks until the movement settles or `ctx` is cancelled.
+    pub fn turn_to_heading(
+        &mut self,
+        ctx: &Context,
+        heading: impl Into<Rotation2d>,
+    ) -> Result<(), DrivetrainError> {
+        let command = Command::TurnTo(heading.into());
+        self.start_command();
+        self.run(ctx, |dt, pose| dt.command_output(command, pose))
+    }
+
+    /// Drives forwards to the given point, turning to face it on the way.
+    ///
+    /// Blocks until the movement settles or `ctx` is cancelled.
+    pub fn drive_to_point(
+        &mut self,
+        ctx: &Context,
+        point: impl Into<Vec2>,
+    ) -> Result<(), DrivetrainError> {
+        let command = Command::DriveTo(point.into());
+        self.start_command();
+        self.run(ctx, |dt, pose| dt.command_output(command, pose))
+    }
+
+    /// Follows a [`Path`] using the pure pursuit algorithm, steering towards
+    /// the point on the path `lookahead` ahead of the drivetrain.
+    ///
+    /// Paths can be generated from waypoints with [`Path::quintic()`], or
+    /// from a list of points with [`Path::from_points()`].
+    ///
+    /// Blocks until the drivetrain settles at the final point or `ctx` is
+    /// cancelled.
+    pub fn follow_path(
+        &mut self,
+        ctx: &Context,
+        path: &Path,
+        lookahead: impl Into<Millimeters>,
+    ) -> Result<(), DrivetrainError> {
+        let lookahead = lookahead.into();
+        let path = path.points();
+        let end = match path.last() {
+            Some(end) => end.pose.position,
+            None => return Ok(()),
+        };
+        self.update_odometry()?;
+        self.distance_pid.reset();
+        let mut index = 0;
+        self.run(ctx, |dt, pose| {
+            while index + 1 < path.len()
+                && path[index].pose.position.distance(pose.position) < lookahead
+            {
+                index += 1;
+            }
+            let target = (path[index].pose.position - pose.position).rotate(-pose.heading);
+            let l2 = target.dot(target);
+            let curvature = if l2 > 0.0 { 2.0 * target.x.0 / l2 } else { 0.0 };
+            let remaining = end.distance(pose.position);
+            let speed = dt.distance_pid.step(remaining.0, 0.0);
+            let half_track = dt.config.track_width.0 / 2.0;
+            (
+                speed * (1.0 + curvature * half_track),
+                speed * (1.0 - curvature * half_track),
+                index + 1 == path.len() && remaining <= dt.config.distance_tolerance,
+            )
+        })
+    }
+
+    /// Follows a [`Trajectory`], driving with feedforward from the desired
+    /// velocity and curvature at each point in time, and correcting for
+    /// along-track and heading error with the distance and turn controllers.
+    ///
+    /// Blocks until the trajectory has finished and the drivetrain settles at
+    /// the final point, or `ctx` is cancelled.
+    pub fn follow_trajectory(
+        &mut self,
+        ctx: &Context,
+        trajectory: &Trajectory,
+    ) -> Result<(), DrivetrainError> {
+        let end = match trajectory.states().last() {
+            Some(end) => end.point.pose.position,
+            None => return Ok(()),
+        };
+        self.update_odometry()?;
+        self.distance_pid.reset();
+        self.turn_pid.reset();
+        let start = time_since_start();
+        self.run(ctx, |dt, pose| {
+            let elapsed = time_since_start() - start;
+            let state = trajectory.sample(elapsed);
+            let error = state.point.pose.relative_to(pose);
+            let along = dt.distance_pid.step(error.position.y.0, 0.0);
+            let turn = dt.turn_pid.step(0.0, -error.heading.degrees().0);
+            let half_track = dt.config.track_width.0 / 2.0;
+            let speed = state.velocity / dt.config.conversions.max_speed() * NOMINAL_VOLTAGE;
+            (
+                speed * (1.0 + state.point.curvature * half_track) + along + turn,
+                speed * (1.0 - state.point.curvature * half_track) + along - turn,
+                elapsed >= trajectory.duration()
+                    && end.distance(pose.position) <= dt.config.distance_tolerance,
+            )
+        })
+    }
+
+    fn run(
+        &mut self,
+        ctx: &Context,
+        mut f: impl FnMut(&mut Self, Pose2d) -> (f64, f64, bool),
+    ) -> Result<(), DrivetrainError> {
+        let mut l = Loop::new(UPDATE_PERIOD);
+        let mut settle = Settled::new(0.0, self.config.settle_time);
+        loop {
+            let pose = self.update_odometry()?;
+            let (left, right, on_target) = f(self, pose);
+            if settle.update_on_target(on_target) {
+                return self.stop();
+            }
+            self.drive_limited(left, right)?;
+
+            select! {
+                _ = ctx.done() => {
+                    self.stop()?;
+                    return Err(DrivetrainError::Cancelled);
+                },
+                _ = l.select() => continue,
+            }
+        }
+    }
+
+    fn start_command(&mut self) {
+        self.distance_pid.reset();
+        self.turn_pid.reset();
+    }
+
+    /// Computes the left and right outputs of a command, and whether the
+    /// drivetrain is on target.
+    fn command_output(&mut self, command: Command, pose: Pose2d) -> (f64, f64, bool) {
+        match command {
+            Command::TurnTo(heading) => {
+                let error = (heading - pose.heading).degrees();
+                let turn = self.turn_pid.step(0.0, -error.0);
+                (turn, -turn, error.abs() <= self.config.heading_tolerance)
+            }
+            Command::DriveTo(point) => {
+                let target = (point - pose.position).rotate(-pose.heading);
+                let distance = target.length();
+                // Only drive the component of the distance along the current
+                // heading, and stop steering once close to the point to avoid
+                // spinning around it.
+                let forward = self.distance_pid.step(target.y.0, 0.0);
+                let turn = if distance > self.config.distance_tolerance * 5.0 {
+                    self.turn_pid.step(0.0, -target.angle().degrees().0)
+                } else {
+                    0.0
+                };
+                (
+                    forward + turn,
+                    forward - turn,
+                    distance <= self.config.distance_tolerance,
+                )
+            }
+        }
+    }
+
+    /// Drives both sides with the given voltages, scaled down together to
+    /// respect the maximum voltage.
+    fn drive_limited(&mut self, left: f64, right: f64) -> Result<(), DrivetrainError> {
+        let max = self.config.max_voltage as f64;
+        let scale = (left.abs().max(right.abs()) / max).max(1.0);
+        let traction = self.traction.as_ref().map_or(1.0, TractionState::power);
+        self.left.move_voltage((left / scale * traction) as i32)?;
+        self.right.move_voltage((right / scale * traction) as i32)?;
+        Ok(())
+    }
+
+    fn update_traction(&mut self, reading: OdometryReading) -> Result<(), DrivetrainError> {
+        let chassis = match (&self.tracking, &self.imu) {
+            (Some(_), _) => Motion::Distance((reading.left + reading.right) / 2000.0),
+            (None, Some(imu)) => {
+                let accel = imu.get_accel()?;
+                Motion::Acceleration(accel.x.hypot(accel.y) * STANDARD_GRAVITY)
+            }
+            (None, None) => return Ok(()),
+        };
+        let rpm = (self.left.get_actual_velocity()? + self.right.get_actual_velocity()?) / 2.0;
+        let wheel_speed = self.config.conversions.rpm_to_speed(Rpm(rpm));
+        if let Some(traction) = &mut self.traction {
+            traction.update(wheel_speed, chassis);
+        }
+        Ok(())
+    }
+
+    fn wheel_offsets(&self) -> (f64, f64) {
+        match &self.tracking {
+            Some((left, right)) => (left.offset.0, right.offset.0),
+            None => (
+                -self.config.track_width.0 / 2.0,
+                self.config.track_width.0 / 2.0,
+            ),
+        }
+    }
+
+    fn read_odometry(&self) -> Result<OdometryReading, DrivetrainError> {
+        let (left, right) = match &self.tracking {
+            Some((left, right)) => (left.get_distance()?.0, right.get_distance()?.0),
+            None => (
+                self.config
+                    .conversions
+                    .ticks_to_distance(self.left.get_position()?)
+                    .0,
+                self.config
+                    .conversions
+                    .ticks_to_distance(self.right.get_position()?)
+                    .0,
+            ),
+        };
+        let rotation = match &self.imu {
+            Some(imu) => Some(imu.get_rotation()?),
+            None => None,
+        };
+        Ok(OdometryReading {
+            left,
+            right,
+            rotation,
+        })
+    }
+}
+
+/// A [`DifferentialDrivetrain`] which runs odometry and movement commands on a
+/// background task.
+///
+/// Each command returns a [`Selectable`] which resolves when the movement
+/// settles, so that commands can be combined with timeouts and other events
+/// using [`select!`](crate::select!):
+///
+/// ```
+/// select! {
+///     result = chassis.drive_to(Vec2::new(Millimeters(600.0), Millimeters(600.0))) => result?,
+///     _ = delay(Duration::from_secs(2)) => chassis.stop()?,
+/// }
+/// ```
+///
+/// Starting a new command cancels the current one. The background task stops
+/// the drivetrain and exits when the `Chassis` is dropped.
+pub struct Chassis(Arc<Mutex<ChassisData>>);
+
+impl Chassis {
+    #[inline]
+    /// Moves a drivetrain onto a new background task. Panics on failure; see
+    /// [`Chassis::try_new()`].
+    pub fn new(drivetrain: DifferentialDrivetrain) -> Self {
+        Self::try_new(drivetrain)
+            .unwrap_or_else(|err| panic!("failed to create chassis: {:?}", err))
+    }
+
+    /// Moves a drivetrain onto a new background task.
+    pub fn try_new(drivetrain: DifferentialDrivetrain) -> Result<Self, Error> {
+        let data = Arc::new(Mutex::try_new(ChassisData {
+            drivetrain,
+            command: None,
+            id: 0,
+            settle: Settled::new(0.0, Duration::ZERO),
+            outcome: None,
+            event: Event::new(),
+        })?);
+        let weak = Arc::downgrade(&data);
+        Task::spawn_ext(
+            "chassis",
+            Task::DEFAULT_PRIORITY,
+            Task::DEFAULT_STACK_DEPTH,
+            move || {
+                let mut l = Loop::new(UPDATE_PERIOD);
+                while let Some(data) = weak.upgrade() {
+                    let stop = data.lock().update();
+                    drop(data);
+                    if stop {
+                        return;
+                    }
+                    l.delay();
+                }
+            },
+        )?;
+        Ok(Self(data))
+    }
+
+    /// Turns in place to face the given absolute heading, taking the shortest
+    /// direction.
+    ///
+    /// The result resolves once the movement settles, or with
+    /// [`DrivetrainError::Cancelled`] if another command is started first.
+    pub fn turn_to(
+        &'_ self,
+        heading: impl Into<Rotation2d>,
+    ) -> impl Selectable<Result<(), DrivetrainError>> + '_ {
+        self.start(Command::TurnTo(heading.into()))
+    }
+
+    /// Drives forwards to the given point, turning to face it on the way.
+    ///
+    /// The result resolves once the movement settles, or with
+    /// [`DrivetrainError::Cancelled`] if another command is started first.
+    pub fn drive_to(
+        &'_ self,
+        point: impl Into<Vec2>,
+    ) -> impl Selectable<Result<(), DrivetrainError>> + '_ {
+        self.start(Command::DriveTo(point.into()))
+    }
+
+    /// A [`Selectable`] event which occurs once the drivetrain runs into
+    /// something, as detected by a [`ContactDetector`] with the given
+    /// configuration, e.g. to end a movement at a wall.
+    ///
+    /// The current command is not affected.
+    pub fn contact(
+        &'_ self,
+        config: ContactConfig,
+    ) -> impl Selectable<Result<Contact, DrivetrainError>> + '_ {
+        ContactDetector::new(config).select(move || self.0.lock().drivetrain.contact_sample())
+    }
+
+    /// Cancels the current command and stops the drivetrain.
+    pub fn stop(&self) -> Result<(), DrivetrainError> {
+        let mut lock = self.0.lock();
+        lock.cancel();
+        lock.drivetrain.stop()
+    }
+
+    #[inline]
+    /// Gets the most recently computed pose of the drivetrain.
+    pub fn pose(&self) -> Pose2d {
+        self.0.lock().drivetrain.pose()
+    }
+
+    #[inline]
+    /// Overrides the current pose of the drivetrain.
+    pub fn set_pose(&self, pose: Pose2d) {
+        self.0.lock().drivetrain.set_pose(pose);
+    }
+
+    #[inline]
+    /// Gets the configuration of the drivetrain.
+    pub fn config(&self) -> DrivetrainConfig {
+        self.0.lock().drivetrain.config().clone()
+    }
+
+    #[inline]
+    /// Replaces the configuration of the drivetrain, e.g. to change the
+    /// tolerances or settle time of subsequent commands.
+    pub fn set_config(&self, config: DrivetrainConfig) {
+        self.0.lock().drivetrain.set_config(config);
+    }
+
+    #[inline]
+    pub(crate) fn downgrade(&self) -> WeakChassis {
+        WeakChassis(Arc::downgrade(&self.0))
+    }
+
+    fn start(&'_ self, command: Command) -> ChassisSelect<'_> {
+        let mut lock = self.0.lock();
+        lock.cancel();
+        lock.drivetrain.start_command();
+        lock.command = Some(command);
+        ChassisSelect {
+            chassis: self,
+            id: lock.id,
+            handle: handle_event(ChassisHandle(Arc::downgrade(&self.0))),
+        }
+    }
+}
+
+impl Drop for Chassis {
+    fn drop(&mut self) {
+        if let Err(err) = self.stop() {
+            escalation::report("chassis", &err);
+        }
+    }
+}
+
+/// A handle to a [`Chassis`] which does not keep its task running.
+pub(crate) struct WeakChassis(Weak<Mutex<ChassisData>>);
+
+impl WeakChassis {
+    /// Replaces the pose of the drivetrain with a function of it, returning
+    /// the new pose, or `None` if the chassis has been dropped.
+    ///
+    /// The heading filter is only reset if the heading changes.
+    pub(crate) fn update_pose(&self, f: impl FnOnce(Pose2d) -> Pose2d) -> Option<Pose2d> {
+        let data = self.0.upgrade()?;
+        let drivetrain = &mut data.lock().drivetrain;
+        let pose = f(drivetrain.pose);
+        if pose.heading == drivetrain.pose.heading {
+            drivetrain.pose = pose;
+        } else {
+            drivetrain.set_pose(pose);
+        }
+        Some(pose)
+    }
+
+    /// Corrects the fused heading of the drivetrain, returning the new pose,
+    /// or `None` if the chassis has been dropped. See
+    /// [`DifferentialDrivetrain::correct_heading()`].
+    pub(crate) fn correct_heading(&self, heading: Rotation2d, variance: f64) -> Option<Pose2d> {
+        let data = self.0.upgrade()?;
+        let pose = data.lock().drivetrain.correct_heading(heading, variance);
+        Some(pose)
+    }
+}
+
+struct ChassisData {
+    drivetrain: DifferentialDrivetrain,
+    command: Option<Command>,
+    id: u32,
+    settle: Settled,
+    outcome: Option<Result<(), DrivetrainError>>,
+    event: Event,
+}
+
+impl ChassisData {
+    /// Runs one iteration of the control loop, returning whether the chassis
+    /// should stop, according to its [`ErrorPolicy`](escalation::ErrorPolicy).
+    fn update(&mut self) -> bool {
+        let result = self.drivetrain.update_odometry().and_then(|pose| {
+            let command = match self.command {
+                Some(command) => command,
+                None => return Ok(()),
+            };
+            let (left, right, on_target) = self.drivetrain.command_output(command, pose);
+            self.settle
+                .set_settle_time(self.drivetrain.config.settle_time);
+            if self.settle.update_on_target(on_target) {
+                self.drivetrain.stop()?;
+                self.finish(Ok(()));
+                Ok(())
+            } else {
+                self.drivetrain.drive_limited(left, right)
+            }
+        });
+        let err = match result {
+            Ok(()) => return false,
+            Err(err) => err,
+        };
+        if self.command.is_some() {
+            let stop = match self.drivetrain.stop() {
+                Ok(()) => false,
+                Err(err) => escalation::report("chassis", &err),
+            };
+            self.finish(Err(err));
+            stop
+        } else {
+            let stop = escalation::report("chassis", &err);
+            if stop {
+                self.drivetrain.stop().ok();
+            }
+            stop
+        }
+    }
+
+    fn finish(&mut self, outcome: Result<(), DrivetrainError>) {
+        self.command = None;
+        self.settle.reset();
+        self.outcome = Some(outcome);
+        self.event.notify();
+    }
+
+    fn cancel(&mut self) {
+        self.command = None;
+        self.settle.reset();
+        self.outcome = None;
+        self.id = self.id.wrapping_add(1);
+        self.event.notify();
+    }
+}
+
+struct ChassisHandle(Weak<Mutex<ChassisData>>);
+
+impl OwnerMut<Event> for ChassisHandle {
+    fn with<'a, U>(&'a mut self, f: impl FnOnce(&mut Event) -> U) -> Option<U>
+    where
+        Event: 'a,
+    {
+        Some(f(&mut self.0.upgrade()?.lock().event))
+    }
+}
+
+struct ChassisSelect<'a> {
+    chassis: &'a Chassis,
+    id: u32,
+    handle: EventHandle<ChassisHandle>,
+}
+
+impl<'a> Selectable<Result<(), DrivetrainError>> for ChassisSelect<'a> {
+    fn poll(self) -> Result<Result<(), DrivetrainError>, Self> {
+        let mut lock = self.chassis.0.lock();
+        if lock.id != self.id {
+            Ok(Err(DrivetrainError::Cancelled))
+        } else if let Some(outcome) = lock.outcome.take() {
+            Ok(outcome)
+        } else {
+            drop(lock);
+            Err(self)
+        }
+    }
+
+    #[inline]
+    fn sleep(&self) -> GenericSleep {
+        if self.handle.is_done() {
+            GenericSleep::Timestamp(Instant::from_millis(0))
+        } else {
+            GenericSleep::NotifyTake(None)
+        }
+    }
+}
+
+/// A four-wheeled holonomic drivetrain, such as a mecanum drive or an X-drive,
+/// which can translate in any direction while turning.
+///
+/// With an inertial sensor, the drivetrain can be driven field-centrically
+/// (see [`field_centric()`](Self::field_centric())), so that pushing the
+/// joystick forwards always drives away from the driver, whichever way the
+/// robot is facing.
+pub struct HolonomicDrivetrain {
+    front_left: MotorGroup,
+    front_right: MotorGroup,
+    back_left: MotorGroup,
+    back_right: MotorGroup,
+    imu: Option<InertialSensor>,
+    heading_offset: Degrees,
+    heading_lock: Option<Pid>,
+    turn_deadband: i8,
+    held_heading: Option<Rotation2d>,
+    odometry: Option<HolonomicConfig>,
+    pose: Pose2d,
+    last: Option<HolonomicReading>,
+}
+
+impl HolonomicDrivetrain {
+    /// Constructs a new drivetrain from the motor groups driving each of its
+    /// wheels.
+    pub fn new(
+        front_left: MotorGroup,
+        front_right: MotorGroup,
+        back_left: MotorGroup,
+        back_right: MotorGroup,
+    ) -> Self {
+        Self {
+            front_left,
+            front_right,
+            back_left,
+            back_right,
+            imu: None,
+            heading_offset: Degrees(0.0),
+            heading_lock: None,
+            turn_deadband: 5,
+            held_heading: None,
+            odometry: None,
+            pose: Pose2d::default(),
+            last: None,
+        }
+    }
+
+    #[inline]
+    /// Uses an inertial sensor to measure the heading of the drivetrain, which
+    /// is required for field-centric driving.
+    ///
+    /// The sensor should already have finished calibrating.
+    pub fn with_imu(mut self, imu: InertialSensor) -> Self {
+        self.imu = Some(imu);
+        self.held_heading = None;
+        self.last = None;
+        self
+    }
+
+    #[inline]
+    /// Tracks the position of the drivetrain using the motor encoders of its
+    /// wheels; see [`update_odometry()`](Self::update_odometry()).
+    pub fn with_odometry(mut self, config: HolonomicConfig) -> Self {
+        self.odometry = Some(config);
+        self.last = None;
+        self
+    }
+
+    #[inline]
+    /// Holds the current heading while driving field-centrically without
+    /// turning, correcting for the drift of the drivetrain using the given
+    /// controller, which maps an error in degrees to an output in millivolts.
+    pub fn with_heading_lock(mut self, pid: Pid) -> Self {
+        self.heading_lock = Some(pid);
+        self
+    }
+
+    #[inline]
+    /// Sets the largest turning input which is treated as zero for the
+    /// purposes of the heading lock. The default is 5.
+    pub fn with_turn_deadband(mut self, deadband: i8) -> Self {
+        self.turn_deadband = deadband;
+        self
+    }
+
+    /// Gets the heading of the drivetrain relative to the field, as measured by
+    /// the inertial sensor.
+    pub fn heading(&self) -> Result<Rotation2d, DrivetrainError> {
+        let imu = self.imu.as_ref().ok_or(DrivetrainError::NoImu)?;
+        Ok(Rotation2d::new(
+            Degrees(imu.get_heading()?) - self.heading_offset,
+        ))
+    }
+
+    /// Overrides the current heading of the drivetrain relative to the field,
+    /// e.g., to re-zero field-centric driving when the robot is facing away
+    /// from the driver.
+    pub fn set_heading(&mut self, heading: impl Into<Rotation2d>) -> Result<(), DrivetrainError> {
+        let imu = self.imu.as_ref().ok_or(DrivetrainError::NoImu)?;
+        let heading = heading.into();
+        self.heading_offset = Degrees(imu.get_heading()?) - heading.degrees();
+        self.pose.heading = heading;
+        self.held_heading = None;
+        Ok(())
+    }
+
+    #[inline]
+    /// Gets the most recently computed pose of the drivetrain.
+    ///
+    /// The pose is only updated by
+    /// [`update_odometry()`](Self::update_odometry()).
+    pub fn pose(&self) -> Pose2d {
+        self.pose
+    }
+
+    /// Overrides the current pose of the drivetrain. This also sets the heading
+    /// used for field-centric driving.
+    pub fn set_pose(&mut self, pose: Pose2d) -> Result<(), DrivetrainError> {
+        if let Some(imu) = &self.imu {
+            self.heading_offset = Degrees(imu.get_heading()?) - pose.heading.degrees();
+        }
+        self.pose = pose;
+        self.held_heading = None;
+        Ok(())
+    }
+
+    /// Reads the sensors and integrates the movement since the last update into
+    /// the current pose.
+    ///
+    /// The heading is measured by the inertial sensor if present, and
+    /// otherwise by the wheels. The position is only tracked if the physical
+    /// configuration of the drivetrain is known (see
+    /// [`with_odometry()`](Self::with_odometry())).
+    ///
+    /// This should be called regularly (e.g., every 10ms).
+    pub fn update_odometry(&mut self) -> Result<Pose2d, DrivetrainError> {
+        let reading = self.read_odometry()?;
+        if let Some(last) = self.last.replace(reading) {
+            let (forward, strafe, wheel_dtheta) =
+                match (&self.odometry, reading.wheels, last.wheels) {
+                    (Some(config), Some(wheels), Some(last)) => {
+                        config.forward_kinematics(core::array::from_fn(|i| wheels[i] - last[i]))
+                    }
+                    _ => (0.0, 0.0, 0.0),
+                };
+            let dtheta = match (reading.rotation, last.rotation) {
+                (Some(r), Some(l)) => (r - l).to_radians(),
+                _ => wheel_dtheta,
+            };
+            let mid = self.pose.heading + Radians(dtheta / 2.0).into();
+            self.pose.position += Vec2::new(Millimeters(strafe), Millimeters(forward)).rotate(mid);
+            self.pose.heading += Radians(dtheta).into();
+        }
+        Ok(self.pose)
+    }
+
+    /// Drives from -127 to 127 relative to the robot, with a forward
+    /// component, a strafing component (positive to the right) and a turning
+    /// component (positive clockwise).
+    pub fn robot_centric(
+        &mut self,
+        forward: i8,
+        strafe: i8,
+        turn: i8,
+    ) -> Result<(), DrivetrainError> {
+        self.held_heading = None;
+        self.drive(scale_i8(forward), scale_i8(strafe), scale_i8(turn))
+    }
+
+    /// Drives from -127 to 127 relative to the field, with a forward component
+    /// (along the positive y axis), a strafing component (along the positive x
+    /// axis) and a turning component (positive clockwise).
+    ///
+    /// The translation is rotated by the heading measured by the inertial
+    /// sensor, which must be configured. If a heading lock is configured (see
+    /// [`with_heading_lock()`](Self::with_heading_lock())), the heading is
+    /// held whenever the turning component is within the deadband.
+    pub fn field_centric(
+        &mut self,
+        forward: i8,
+        strafe: i8,
+        turn: i8,
+    ) -> Result<(), DrivetrainError> {
+        let heading = self.heading()?;
+        let turn = match &mut self.heading_lock {
+            Some(pid) if turn.unsigned_abs() <= self.turn_deadband.unsigned_abs() => {
+                let target = match self.held_heading {
+                    Some(target) => target,
+                    None => {
+                        pid.reset();
+                        *self.held_heading.insert(heading)
+                    }
+                };
+                pid.step(0.0, -(target - heading).degrees().0)
+            }
+            _ => {
+                self.held_heading = None;
+                scale_i8(turn)
+            }
+        };
+        // Rotate the field-relative translation anticlockwise by the heading to
+        // make it relative to the robot.
+        let (forward, strafe) = (scale_i8(forward), scale_i8(strafe));
+        let (sin, cos) = (heading.sin(), heading.cos());
+        self.drive(
+            forward * cos + strafe * sin,
+            strafe * cos - forward * sin,
+            turn,
+        )
+    }
+
+    /// Stops all wheels of the drivetrain.
+    pub fn stop(&mut self) -> Result<(), DrivetrainError> {
+        self.held_heading = None;
+        self.front_left.move_voltage(0)?;
+        self.front_right.move_voltage(0)?;
+        self.back_left.move_voltage(0)?;
+        self.back_right.move_voltage(0)?;
+        Ok(())
+    }
+
+    /// Drives the wheels with robot-relative components in millivolts, scaling
+    /// them down together if any wheel would exceed the nominal voltage.
+    fn drive(&mut self, forward: f64, strafe: f64, turn: f64) -> Result<(), DrivetrainError> {
+        let outputs = [
+            forward + strafe + turn,
+            forward - strafe - turn,
+            forward - strafe + turn,
+            forward + strafe - turn,
+        ];
+        let max = outputs
+            .iter()
+            .fold(NOMINAL_VOLTAGE, |max, output| max.max(output.abs()));
+        let [front_left, front_right, back_left, back_right] =
+            outputs.map(|output| (output * NOMINAL_VOLTAGE / max) as i32);
+        self.front_left.move_voltage(front_left)?;
+        self.front_right.move_voltage(front_right)?;
+        self.back_left.move_voltage(back_left)?;
+        self.back_right.move_voltage(back_right)?;
+        Ok(())
+    }
+
+    fn read_odometry(&self) -> Result<HolonomicReading, DrivetrainError> {
+        let wheels = match &self.odometry {
+            Some(config) => {
+                let distance = |motors: &MotorGroup| -> Result<f64, DrivetrainError> {
+                    Ok(config
+                        .conversions
+                        .ticks_to_distance(motors.get_position()?)
+                        .0)
+                };
+                Some([
+                    distance(&self.front_left)?,
+                    distance(&self.front_right)?,
+                    distance(&self.back_left)?,
+                    distance(&self.back_right)?,
+                ])
+            }
+            None => None,
+        };
+        let rotation = match &self.imu {
+            Some(imu) => Some(imu.get_rotation()?),
+            None => None,
+        };
+        Ok(HolonomicReading { wheels, rotation })
+    }
+}
+
+/// Represents the physical configuration of a [`DifferentialDrivetrain`] and
+/// the tolerances used by its movement commands.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DrivetrainConfig {
+    /// Conversions between the motor encoders and the travel of the driven
+    /// wheels.
+    pub conversions: Conversions,
+    /// The distance between the left and right driven wheels.
+    pub track_width: Millimeters,
+    /// The maximum output voltage of the movement commands in millivolts.
+    pub max_voltage: i32,
+    /// The distance within which a movement is considered on target.
+    pub distance_tolerance: Millimeters,
+    /// The angle within which a turn is considered on target.
+    pub heading_tolerance: Degrees,
+    /// How long a movement must remain on target before it is considered
+    /// settled.
+    pub settle_time: Duration,
+}
+
+impl DrivetrainConfig {
+    /// Creates a new configuration with the given drive wheel conversions and
+    /// track width, and default tolerances.
+    pub fn new(conversions: Conversions, track_width: impl Into<Millimeters>) -> Self {
+        Self {
+            conversions,
+            track_width: track_width.into(),
+            max_voltage: 12000,
+            distance_tolerance: Millimeters(10.0),
+            heading_tolerance: Degrees(1.0),
+            settle_time: Duration::from_millis(250),
+        }
+    }
+}
+
+/// Represents the physical configuration of a [`HolonomicDrivetrain`], used
+/// for odometry.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HolonomicConfig {
+    /// Conversions between the motor encoders and the travel of the wheels.
+    pub conversions: Conversions,
+    /// The arrangement of the wheels.
+    pub layout: HolonomicLayout,
+    /// The distance between the left and right wheels.
+    pub track_width: Millimeters,
+    /// The distance between the front and back wheels.
+    pub wheelbase: Millimeters,
+}
+
+impl HolonomicConfig {
+    /// Creates a new configuration with the given wheel conversions, wheel
+    /// layout and wheel spacing.
+    pub fn new(
+        conversions: Conversions,
+        layout: HolonomicLayout,
+        track_width: impl Into<Millimeters>,
+        wheelbase: impl Into<Millimeters>,
+    ) -> Self {
+        Self {
+            conversions,
+            layout,
+            track_width: track_width.into(),
+            wheelbase: wheelbase.into(),
+        }
+    }
+
+    /// Computes the forward and strafing distances and the clockwise rotation
+    /// in radians of the drivetrain, given the distance travelled by each wheel
+    /// (front left, front right, back left, back right).
+    fn forward_kinematics(&self, [fl, fr, bl, br]: [f64; 4]) -> (f64, f64, f64) {
+        // The wheels of an X-drive only measure the component of the movement
+        // along their own direction, at 45° to the frame.
+        let scale = match self.layout {
+            HolonomicLayout::Mecanum => 1.0,
+            HolonomicLayout::XDrive => SQRT_2,
+        };
+        let forward = (fl + fr + bl + br) / 4.0 * scale;
+        let strafe = (fl - fr - bl + br) / 4.0 * scale;
+        let dtheta =
+            (fl - fr + bl - br) / 4.0 * scale / ((self.track_width.0 + self.wheelbase.0) / 2.0);
+        (forward, strafe, dtheta)
+    }
+}
+
+/// Represents the arrangement of the wheels of a [`HolonomicDrivetrain`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HolonomicLayout {
+    /// Four mecanum wheels on parallel axles. Mecanum wheels tend to slip
+    /// while strafing, so sideways movement is measured less accurately than
+    /// forward movement.
+    Mecanum,
+    /// Four omni wheels, one in each corner at 45° to the frame.
+    XDrive,
+}
+
+/// Represents the parameters of traction control on a
+/// [`DifferentialDrivetrain`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct TractionControl {
+    /// The amount by which the acceleration of the driven wheels must exceed
+    /// that of the chassis for the wheels to be considered slipping.
+    pub slip_threshold: MetersPerSecondSquared,
+    /// The fraction of power applied while the wheels are slipping.
+    pub reduction: f64,
+    /// The time over which full power is restored after the wheels stop
+    /// slipping.
+    pub recovery: Duration,
+    /// The smoothing factor of the exponential moving average applied to the
+    /// acceleration difference; see [`Ema::new()`].
+    pub smoothing: f64,
+}
+
+impl TractionControl {
+    /// Creates a new set of traction control parameters with default values.
+    pub fn new() -> Self {
+        Self {
+            slip_threshold: MetersPerSecondSquared(3.0),
+            reduction: 0.6,
+            recovery: Duration::from_millis(250),
+            smoothing: 0.3,
+        }
+    }
+}
+
+impl Default for TractionControl {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Represents an unpowered wheel used to measure distance travelled.
+pub struct TrackingWheel {
+    sensor: TrackingSensor,
+    conversions: Conversions,
+    offset: Millimeters,
+}
+
+impl TrackingWheel {
+    /// Creates a new tracking wheel with the given diameter and lateral offset
+    /// from the centre of rotation of the drivetrain (positive to the right).
+    pub fn new(
+        sensor: impl Into<TrackingSensor>,
+        diameter: impl Into<Millimeters>,
+        offset: impl Into<Millimeters>,
+    ) -> Self {
+        Self {
+            sensor: sensor.into(),
+            conversions: Conversions::new(diameter),
+            offset: offset.into(),
+        }
+    }
+
+    /// Gets the distance travelled by the wheel.
+    pub fn get_distance(&self) -> Result<Millimeters, DrivetrainError> {
+        let degrees = match &self.sensor {
+            TrackingSensor::Encoder(encoder) => encoder.get()? as f64,
+            TrackingSensor::Rotation(rotation) => rotation.get_position()? as f64 / 100.0,
+        };
+        Ok(self.conversions.ticks_to_distance(degrees))
+    }
+}
+
+/// Represents a sensor which can measure the rotation of a [`TrackingWheel`].
+pub enum TrackingSensor {
+    /// An ADI quadrature encoder.
+    Encoder(AdiEncoder),
+    /// A V5 rotation sensor.
+    Rotation(RotationSensor),
+}
+
+impl From<AdiEncoder> for TrackingSensor {
+    fn from(encoder: AdiEncoder) -> Self {
+        Self::Encoder(encoder)
+    }
+}
+
+impl From<RotationSensor> for TrackingSensor {
+    fn from(rotation: RotationSensor) -> Self {
+        Self::Rotation(rotation)
+    }
+}
+
+/// Controls the linear speed of one side of a drivetrain (or any group of
+/// driven wheels), combining a feedforward term with a [`FeedbackController`]
+/// which maps an error in metres per second to an output in millivolts.
+///
+/// By default, the speed is measured by the motor encoders. With
+/// [`with_tracking_wheel()`](Self::with_tracking_wheel()), the loop is closed
+/// on an unpowered tracking wheel instead, which measures the speed of the
+/// robot over the ground even while the driven wheels slip.
+pub struct WheelVelocityController<C: FeedbackController> {
+    motors: MotorGroup,
+    conversions: Conversions,
+    controller: C,
+    tracking: Option<TrackingWheel>,
+    filter: Ema,
+    target: MetersPerSecond,
+    velocity: MetersPerSecond,
+    last: Option<(Instant, Millimeters)>,
+}
+
+impl<C: FeedbackController> WheelVelocityController<C> {
+    /// Creates a new velocity controller for the given motors, with the given
+    /// conversions between the motor encoders and the travel of the driven
+    /// wheels.
+    pub fn new(motors: MotorGroup, conversions: Conversions, controller: C) -> Self {
+        Self {
+            motors,
+            conversions,
+            controller,
+            tracking: None,
+            filter: Ema::new(0.5),
+            target: MetersPerSecond(0.0),
+            velocity: MetersPerSecond(0.0),
+            last: None,
+        }
+    }
+
+    #[inline]
+    /// Measures the speed with an unpowered tracking wheel, which should be
+    /// parallel to the driven wheels, instead of the motor encoders.
+    pub fn with_tracking_wheel(mut self, wheel: TrackingWheel) -> Self {
+        self.tracking = Some(wheel);
+        self.last = None;
+        self
+    }
+
+    #[inline]
+    /// Sets the smoothing factor of the exponential moving average applied to
+    /// the measured speed; see [`Ema::new()`]. The default is 0.5.
+    pub fn with_smoothing(mut self, smoothing: f64) -> Self {
+        self.filter = Ema::new(smoothing);
+        self
+    }
+
+    #[inline]
+    /// Gets the motors.
+    pub fn motors(&mut self) -> &mut MotorGroup {
+        &mut self.motors
+    }
+
+    #[inline]
+    /// Gets the target speed.
+    pub fn target(&self) -> MetersPerSecond {
+        self.target
+    }
+
+    /// Sets the target speed. Call [`update()`](Self::update()) regularly to
+    /// drive the motors towards it.
+    pub fn set_target(&mut self, target: impl Into<MetersPerSecond>) {
+        self.target = target.into();
+    }
+
+    #[inline]
+    /// Gets the most recently measured speed.
+    pub fn velocity(&self) -> MetersPerSecond {
+        self.velocity
+    }
+
+    /// Measures the speed and updates the motor output, returning the measured
+    /// speed.
+    ///
+    /// This should be called regularly (e.g., every 10ms).
+    pub fn update(&mut self) -> Result<MetersPerSecond, DrivetrainError> {
+        let measured = match &self.tracking {
+            Some(wheel) => {
+                let now = time_since_start();
+                let distance = wheel.get_distance()?;
+                match self.last.replace((now, distance)) {
+                    Some((time, last)) if now > time => {
+                        MetersPerSecond((distance - last).0 / 1000.0 / (now - time).as_secs_f64())
+                    }
+                    _ => self.velocity,
+                }
+            }
+            None => self
+                .conversions
+                .rpm_to_speed(Rpm(self.motors.get_actual_velocity()?)),
+        };
+        self.velocity = MetersPerSecond(self.filter.filter(measured.0));
+
+        let feedforward = self.target / self.conversions.max_speed() * NOMINAL_VOLTAGE;
+        let output = feedforward + self.controller.step(self.target.0, self.velocity.0);
+        self.motors
+            .move_voltage(output.clamp(-NOMINAL_VOLTAGE, NOMINAL_VOLTAGE) as i32)?;
+        Ok(self.velocity)
+    }
+
+    /// Stops the motors and resets the controller.
+    pub fn stop(&mut self) -> Result<(), DrivetrainError> {
+        self.target = MetersPerSecond(0.0);
+        self.controller.reset();
+        self.motors.move_voltage(0)?;
+        Ok(())
+    }
+}
+
+/// Represents possible errors for drivetrain operations.
+#[derive(Debug)]
+pub enum DrivetrainError {
+    /// A motor or sensor operation failed.
+    Device(DeviceError),
+    /// The movement was cancelled before it settled.
+    Cancelled,
+    /// The operation requires an inertial sensor, but none is configured.
+    NoImu,
+}
+
+impl From<DeviceError> for DrivetrainError {
+    fn from(err: DeviceError) -> Self {
+        Self::Device(err)
+    }
+}
+
+impl From<DrivetrainError> for Error {
+    fn from(err: DrivetrainError) -> Self {
+        match err {
+            DrivetrainError::Device(err) => err.into(),
+            DrivetrainError::Cancelled => Error::Custom("drivetrain movement cancelled".into()),
+            DrivetrainError::NoImu => {
+                Error::Custom("drivetrain has no inertial sensor configured".into())
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Command {
+    TurnTo(Rotation2d),
+    DriveTo(Vec2),
+}
+
+struct TractionState {
+    config: TractionControl,
+    filter: Ema,
+    last: Option<(Instant, MetersPerSecond, Motion)>,
+    last_chassis_speed: Option<f64>,
+    slipping: bool,
+    slip_end: Option<Instant>,
+}
+
+#[derive(Clone, Copy)]
+enum Motion {
+    /// Distance travelled, in metres.
+    Distance(f64),
+    /// Magnitude of acceleration, in metres per second squared.
+    Acceleration(f64),
+}
+
+impl TractionState {
+    fn new(config: TractionControl) -> Self {
+        Self {
+            filter: Ema::new(config.smoothing),
+            config,
+            last: None,
+            last_chassis_speed: None,
+            slipping: false,
+            slip_end: None,
+        }
+    }
+
+    fn update(&mut self, wheel_speed: MetersPerSecond, chassis: Motion) {
+        let now = time_since_start();
+        let (last_time, last_speed, last_chassis) =
+            match self.last.replace((now, wheel_speed, chassis)) {
+                Some(last) if now > last.0 => last,
+                _ => return,
+            };
+        let dt = (now - last_time).as_secs_f64();
+        let wheel_accel = ((wheel_speed - last_speed).0 / dt).abs();
+        let chassis_accel = match (chassis, last_chassis) {
+            (Motion::Distance(d), Motion::Distance(l)) => {
+                let speed = (d - l) / dt;
+                let accel = self
+                    .last_chassis_speed
+                    .map_or(0.0, |last| (speed - last) / dt);
+                self.last_chassis_speed = Some(speed);
+                accel.abs()
+            }
+            (Motion::Acceleration(a), _) => a,
+            _ => return,
+        };
+        let excess = self.filter.filter(wheel_accel - chassis_accel);
+        let slipping = excess > self.config.slip_threshold.0;
+        if self.slipping && !slipping {
+            self.slip_end = Some(now);
+        }
+        self.slipping = slipping;
+    }
+
+    /// Gets the fraction of power to apply.
+    fn power(&self) -> f64 {
+        if self.slipping {
+            return self.config.reduction;
+        }
+        match self.slip_end {
+            Some(end) if time_since_start() - end < self.config.recovery => {
+                let t =
+                    (time_since_start() - end).as_secs_f64() / self.config.recovery.as_secs_f64();
+                self.config.reduction + (1.0 - self.config.reduction) * t
+            }
+            _ => 1.0,
+        }
+    }
+}
+
+struct HeadingFusion {
+    filter: HeadingFilter,
+    encoder_heading: f64,
+}
+
+#[derive(Clone, Copy)]
+struct OdometryReading {
+    left: f64,
+    right: f64,
+    rotation: Option<f64>,
+}
+
+#[derive(Clone, Copy)]
+struct HolonomicReading {
+    wheels: Option<[f64; 4]>,
+    rotation: Option<f64>,
+}
+
+fn clamp_i8(v: i16) -> i8 {
+    v.clamp(i8::MIN as i16 + 1, i8::MAX as i16) as i8
+}
+
+/// Scales a joystick value from -127 to 127 to millivolts.
+fn scale_i8(v: i8) -> f64 {
+    v as f64 * NOMINAL_VOLTAGE / i8::MAX as f64
+}
@@ -59,6 +59,152 @@ impl Display for Error {
     }
 }
 
+/// Identifies the port to which a device is connected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DevicePort {
+    /// A V5 smart port (1-21).
+    Smart(u8),
+    /// An ADI port (1-8), either on the brain or on an ADI expander.
+    Adi {
+        /// The ADI port number (1-8).
+        port: u8,
+        /// The smart port of the ADI expander, or
+        /// [`INTERNAL_ADI_PORT`](DevicePort::INTERNAL_ADI_PORT) for the brain's
+        /// built-in ADI ports.
+        expander_port: u8,
+    },
+}
+
+impl DevicePort {
+    /// The pseudo smart port number of the brain's built-in ADI ports.
+    pub const INTERNAL_ADI_PORT: u8 = bindings::INTERNAL_ADI_PORT as u8;
+}
+
+impl Display for DevicePort {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match *self {
+            DevicePort::Smart(port) => write!(f, "port {}", port),
+            DevicePort::Adi {
+                port,
+                expander_port: Self::INTERNAL_ADI_PORT,
+            } => write!(f, "ADI port {}", port),
+            DevicePort::Adi {
+                port,
+                expander_port,
+            } => write!(f, "ADI port {} on expander {}", port, expander_port),
+        }
+    }
+}
+
+/// Represents the kinds of error which can occur when using a device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeviceErrorKind {
+    /// The port is out of range.
+    PortOutOfRange,
+    /// The port cannot be configured as the expected type of device, usually
+    /// because a different device is plugged in.
+    WrongDevice {
+        /// A description of the expected device, e.g. `"a motor"`.
+        expected: &'static str,
+    },
+    /// The ports of a multi-port ADI device are on different expanders.
+    NonMatchingExpanders,
+    /// The sensor is already calibrating.
+    AlreadyCalibrating,
+    /// The sensor did not produce a reading.
+    NoReading,
+    /// Another error, with the given `errno` value.
+    System(i32),
+}
+
+impl Display for DeviceErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match *self {
+            DeviceErrorKind::PortOutOfRange => f.write_str("port out of range"),
+            DeviceErrorKind::WrongDevice { expected } => write!(f, "not {}", expected),
+            DeviceErrorKind::NonMatchingExpanders => {
+                f.write_str("ports are on different ADI expanders")
+            }
+            DeviceErrorKind::AlreadyCalibrating => f.write_str("sensor already calibrating"),
+            DeviceErrorKind::NoReading => f.write_str("no reading"),
+            DeviceErrorKind::System(n) => {
+                Display::fmt(unsafe { &from_cstring_raw(libc::strerror(n)) }, f)
+            }
+        }
+    }
+}
+
+/// Represents an error which occurred when using a device, along with the
+/// port to which the device is connected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeviceError {
+    /// The kind of error.
+    pub kind: DeviceErrorKind,
+    /// The port of the device.
+    pub port: DevicePort,
+}
+
+impl DeviceError {
+    #[inline]
+    /// Creates a new device error.
+    pub fn new(kind: DeviceErrorKind, port: DevicePort) -> Self {
+        Self { kind, port }
+    }
+
+    /// Generates a [`DeviceError`] from the value of `errno` for the current
+    /// task, for a device which should be of the type described by
+    /// `expected`.
+    pub fn from_errno(port: DevicePort, expected: &'static str) -> Self {
+        let kind = match get_errno() {
+            libc::ENXIO => DeviceErrorKind::PortOutOfRange,
+            libc::ENODEV => DeviceErrorKind::WrongDevice { expected },
+            n => DeviceErrorKind::System(n),
+        };
+        Self::new(kind, port)
+    }
+}
+
+impl Display for DeviceError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.port, self.kind)
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl embedded_io::Error for Error {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        use embedded_io::ErrorKind;
+        match self {
+            Error::System(libc::ENOENT) => ErrorKind::NotFound,
+            Error::System(libc::EACCES) => ErrorKind::PermissionDenied,
+            Error::System(libc::ENODEV | libc::ENXIO) => ErrorKind::NotConnected,
+            Error::System(libc::EEXIST) => ErrorKind::AlreadyExists,
+            Error::System(libc::EINVAL) => ErrorKind::InvalidInput,
+            Error::System(libc::ETIMEDOUT) => ErrorKind::TimedOut,
+            Error::System(libc::EINTR) => ErrorKind::Interrupted,
+            Error::System(libc::ENOMEM) => ErrorKind::OutOfMemory,
+            Error::System(libc::ENOSYS) => ErrorKind::Unsupported,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+impl From<DeviceError> for Error {
+    /// Converts the error back to the `errno` value which PROS reports for it,
+    /// where there is one, so that the error kind is preserved.
+    fn from(err: DeviceError) -> Self {
+        match err.kind {
+            DeviceErrorKind::PortOutOfRange => Error::System(libc::ENXIO),
+            DeviceErrorKind::WrongDevice { .. } => Error::System(libc::ENODEV),
+            DeviceErrorKind::AlreadyCalibrating => Error::System(libc::EAGAIN),
+            DeviceErrorKind::System(n) => Error::System(n),
+            DeviceErrorKind::NonMatchingExpanders | DeviceErrorKind::NoReading => {
+                Error::Custom(format!("{}", err))
+            }
+        }
+    }
+}
+
 /// Represents a type which has some sentinel values which represent errors.
 ///
 /// Implementations are provided for `i32` and `f64` based on PROS's sentinel
@@ -100,10 +246,14 @@ impl<T> SentinelError for *mut T {
 }
 
 // Need to manually declare until https://github.com/rust-lang/libc/issues/1995 is resolved.
+#[cfg(not(feature = "sim"))]
 extern "C" {
     fn __errno() -> *mut i32;
 }
 
+#[cfg(feature = "sim")]
+use crate::bindings::__errno;
+
 /// Gets the value of `errno` for the current task.
 #[inline]
 pub fn get_errno() -> libc::c_int {
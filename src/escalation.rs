@@ -0,0 +1,176 @@
+//! # Error Escalation.
+//!
+//! The subsystems provided by this crate run their control loops on background
+//! tasks, where there is no caller to return an error to. Instead, they report
+//! it here, and the [`ErrorPolicy`] configured for that subsystem decides what
+//! happens next: the error may be ignored, logged, shown to the driver as an
+//! [alert](crate::alerts), stop the subsystem, or panic. This lets a team
+//! choose to degrade gracefully during a match, e.g. by keeping the drivetrain
+//! running when the flywheel motor is unplugged, while still catching problems
+//! loudly in practice.
+//!
+//! Each subsystem is identified by a name. The provided subsystems use:
+//!
+//! - `"chassis"` for [`Chassis`](crate::drivetrain::Chassis),
+//! - `"flywheel"` for [`Flywheel`](crate::flywheel::Flywheel),
+//! - `"mechanism"` for
+//!   [`PositionMechanism`](crate::mechanism::PositionMechanism),
+//! - `"lights"` for [`StatusLights`](crate::lights::StatusLights),
+//! - `"black box"` for [`BlackBoxRecorder`](crate::blackbox::BlackBoxRecorder),
+//!   and
+//! - `"starvation"` for
+//!   [`StarvationMonitor`](crate::starvation::StarvationMonitor).
+//!
+//! Subsystems without a policy of their own use the default policy, which is
+//! initially [`ErrorPolicy::Log`]. User subsystems may report their own errors
+//! with [`report()`].
+//!
+//! Every reported error is also recorded in the [trace journal](crate::trace),
+//! and panics caused by [`ErrorPolicy::Panic`] have a message of the form
+//! `"<subsystem> error: <error>"`, so that they can be told apart from other
+//! panics.
+//!
+//! # Example
+//!
+//! ```
+//! let alerts = Alerts::new(ControllerId::Master, 0, 2);
+//! escalation::set_alerts(alerts.clone());
+//! escalation::set_default_policy(ErrorPolicy::Panic);
+//! escalation::set_policy("flywheel", ErrorPolicy::Alert(AlertLevel::Warning));
+//! escalation::set_policy("chassis", ErrorPolicy::Stop);
+//! ```
+
+use alloc::{collections::BTreeMap, format, string::String};
+use core::{
+    fmt::Debug,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use crate::{
+    alerts::{Alert, AlertLevel, Alerts},
+    io::eprintln,
+    rtos::Mutex,
+    trace,
+};
+
+static CONFIG: spin::Once<Mutex<Config>> = spin::Once::new();
+static ERRORS: AtomicU32 = AtomicU32::new(0);
+
+struct Config {
+    policies: BTreeMap<String, ErrorPolicy>,
+    default_policy: ErrorPolicy,
+    alerts: Option<Alerts>,
+}
+
+fn config() -> &'static Mutex<Config> {
+    CONFIG.call_once(|| {
+        Mutex::new(Config {
+            policies: BTreeMap::new(),
+            default_policy: ErrorPolicy::Log,
+            alerts: None,
+        })
+    })
+}
+
+/// Determines what happens when a subsystem reports an error.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// Do nothing, and carry on.
+    Ignore,
+    /// Print the error to standard error and log it through the [`log`]
+    /// crate, and carry on.
+    #[default]
+    Log,
+    /// Log the error, and raise an alert at the given level on the alert
+    /// service registered with [`set_alerts()`], if any, and carry on.
+    Alert(AlertLevel),
+    /// Log the error, and stop the subsystem: its outputs, such as motors, are
+    /// stopped, and its task exits. Movements started on a stopped subsystem
+    /// never complete.
+    Stop,
+    /// Log the error, and panic.
+    Panic,
+}
+
+/// Sets the policy for errors reported by the given subsystem, replacing the
+/// default policy for it.
+pub fn set_policy(subsystem: &str, policy: ErrorPolicy) {
+    config().lock().policies.insert(subsystem.into(), policy);
+}
+
+/// Removes the policy for the given subsystem, so that it uses the default
+/// policy again.
+pub fn clear_policy(subsystem: &str) {
+    config().lock().policies.remove(subsystem);
+}
+
+#[inline]
+/// Sets the policy for errors reported by subsystems without a policy of
+/// their own. The default is [`ErrorPolicy::Log`].
+pub fn set_default_policy(policy: ErrorPolicy) {
+    config().lock().default_policy = policy;
+}
+
+/// Gets the policy for errors reported by the given subsystem.
+pub fn policy(subsystem: &str) -> ErrorPolicy {
+    let config = config().lock();
+    match config.policies.get(subsystem) {
+        Some(policy) => *policy,
+        None => config.default_policy,
+    }
+}
+
+#[inline]
+/// Registers the alert service on which [`ErrorPolicy::Alert`] and
+/// [`ErrorPolicy::Stop`] raise alerts, replacing any previously registered
+/// one.
+pub fn set_alerts(alerts: Alerts) {
+    config().lock().alerts = Some(alerts);
+}
+
+#[inline]
+/// Gets the number of errors which have been reported since the program
+/// started, including ignored ones.
+pub fn errors() -> u32 {
+    ERRORS.load(Ordering::Relaxed)
+}
+
+/// Reports an error from the given subsystem, and handles it according to the
+/// subsystem's [`ErrorPolicy`]. Returns whether the subsystem should stop,
+/// i.e., whether the policy is [`ErrorPolicy::Stop`].
+///
+/// # Panics
+///
+/// This function panics if the policy is [`ErrorPolicy::Panic`].
+pub fn report(subsystem: &'static str, error: &impl Debug) -> bool {
+    ERRORS.fetch_add(1, Ordering::Relaxed);
+    trace::record(subsystem, &[]);
+    let policy = policy(subsystem);
+    if let ErrorPolicy::Ignore = policy {
+        return false;
+    }
+
+    let text = format!("{} error: {:?}", subsystem, error);
+    eprintln!("{}", text);
+    log::error!("{}", text);
+    match policy {
+        ErrorPolicy::Ignore | ErrorPolicy::Log => false,
+        ErrorPolicy::Alert(level) => {
+            alert(subsystem, level, "error");
+            false
+        }
+        ErrorPolicy::Stop => {
+            alert(subsystem, AlertLevel::Critical, "stopped");
+            true
+        }
+        ErrorPolicy::Panic => panic!("{}", text),
+    }
+}
+
+fn alert(subsystem: &str, level: AlertLevel, what: &str) {
+    let alerts = config().lock().alerts.clone();
+    if let Some(alerts) = alerts {
+        let key = format!("error.{}", subsystem);
+        alerts.raise(Alert::new(&key, &format!("{} {}", subsystem, what)).with_level(level));
+    }
+}
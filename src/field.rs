@@ -0,0 +1,173 @@
+//! # Alliance Mirroring.
+//!
+//! VRC fields are symmetric between the red and blue alliances, so a routine
+//! written for one alliance's starting tiles can be run from the other's by
+//! mirroring every position and heading. A [`Field`] describes that symmetry,
+//! and transforms any [`Mirror`] type — vectors, headings, poses, paths and
+//! trajectories — for whichever alliance the robot is on, so that one set of
+//! autonomous routines serves both alliances.
+//!
+//! By default the origin is taken to be the centre of the field, matching the
+//! [GPS sensor](crate::gps); use [`Field::with_center()`] if routines are
+//! written in another frame, e.g. with the origin at a corner.
+//!
+//! # Example
+//!
+//! ```
+//! // Routines are written from the red alliance's point of view.
+//! let field = Field::new(Symmetry::MirrorX);
+//! let alliance = selector.alliance();
+//!
+//! let start = Pose2d::new((Millimeters(-1500.0), Millimeters(-600.0)), Degrees(90.0));
+//! let start = field.for_alliance(alliance, start);
+//! let path = field.for_alliance(alliance, Path::cubic(&waypoints, Millimeters(25.0)));
+//! ```
+
+use alloc::vec::Vec;
+
+use crate::{
+    auton::Alliance,
+    geometry::{Pose2d, Rotation2d, Vec2},
+    units::{Degrees, Millimeters},
+};
+
+/// Represents how one alliance's half of the field maps onto the other's.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Symmetry {
+    /// The field is mirrored across the y axis, i.e. x coordinates are negated.
+    MirrorX,
+    /// The field is mirrored across the x axis, i.e. y coordinates are negated.
+    MirrorY,
+    /// The field is rotated by half a turn about its centre.
+    Rotate,
+}
+
+impl Symmetry {
+    #[inline]
+    /// Checks whether the symmetry is a reflection, which reverses the sense
+    /// of rotations and curvatures.
+    pub fn is_reflection(self) -> bool {
+        !matches!(self, Symmetry::Rotate)
+    }
+}
+
+/// A type which can be mirrored to the other alliance's side of the field.
+pub trait Mirror {
+    /// Mirrors the value using the given symmetry about the given centre of
+    /// the field.
+    fn mirror(&self, symmetry: Symmetry, center: Vec2) -> Self;
+}
+
+impl Mirror for Vec2 {
+    fn mirror(&self, symmetry: Symmetry, center: Vec2) -> Self {
+        let offset = *self - center;
+        center
+            + match symmetry {
+                Symmetry::MirrorX => Vec2::new(-offset.x, offset.y),
+                Symmetry::MirrorY => Vec2::new(offset.x, -offset.y),
+                Symmetry::Rotate => -offset,
+            }
+    }
+}
+
+impl Mirror for Rotation2d {
+    fn mirror(&self, symmetry: Symmetry, _center: Vec2) -> Self {
+        // Headings are clockwise from the positive y axis.
+        match symmetry {
+            Symmetry::MirrorX => -*self,
+            Symmetry::MirrorY => Rotation2d::new(Degrees(180.0)) - *self,
+            Symmetry::Rotate => *self + Rotation2d::new(Degrees(180.0)),
+        }
+    }
+}
+
+impl Mirror for Pose2d {
+    fn mirror(&self, symmetry: Symmetry, center: Vec2) -> Self {
+        Pose2d {
+            position: self.position.mirror(symmetry, center),
+            heading: self.heading.mirror(symmetry, center),
+        }
+    }
+}
+
+impl<T: Mirror> Mirror for Vec<T> {
+    fn mirror(&self, symmetry: Symmetry, center: Vec2) -> Self {
+        self.iter().map(|v| v.mirror(symmetry, center)).collect()
+    }
+}
+
+impl<T: Mirror> Mirror for Option<T> {
+    fn mirror(&self, symmetry: Symmetry, center: Vec2) -> Self {
+        self.as_ref().map(|v| v.mirror(symmetry, center))
+    }
+}
+
+/// Describes the symmetry of the field between the two alliances. See the
+/// [module-level documentation](self).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Field {
+    symmetry: Symmetry,
+    center: Vec2,
+    reference: Alliance,
+}
+
+impl Field {
+    #[inline]
+    /// Creates a new field with the given symmetry about the origin, for
+    /// routines written from the red alliance's point of view.
+    pub fn new(symmetry: Symmetry) -> Self {
+        Self {
+            symmetry,
+            center: Vec2::new(Millimeters(0.0), Millimeters(0.0)),
+            reference: Alliance::Red,
+        }
+    }
+
+    #[inline]
+    /// Sets the position of the centre of the field in the routines' frame.
+    pub fn with_center(mut self, center: impl Into<Vec2>) -> Self {
+        self.center = center.into();
+        self
+    }
+
+    #[inline]
+    /// Sets the alliance from whose point of view routines are written.
+    pub fn with_reference(mut self, alliance: Alliance) -> Self {
+        self.reference = alliance;
+        self
+    }
+
+    #[inline]
+    /// Gets the symmetry of the field.
+    pub fn symmetry(&self) -> Symmetry {
+        self.symmetry
+    }
+
+    #[inline]
+    /// Gets the position of the centre of the field.
+    pub fn center(&self) -> Vec2 {
+        self.center
+    }
+
+    #[inline]
+    /// Gets the alliance from whose point of view routines are written.
+    pub fn reference(&self) -> Alliance {
+        self.reference
+    }
+
+    #[inline]
+    /// Mirrors a value to the other alliance's side of the field.
+    pub fn mirror<T: Mirror>(&self, value: &T) -> T {
+        value.mirror(self.symmetry, self.center)
+    }
+
+    /// Transforms a value written from the reference alliance's point of view
+    /// for the given alliance, mirroring it if the alliances differ.
+    pub fn for_alliance<T: Mirror>(&self, alliance: Alliance, value: T) -> T {
+        if alliance == self.reference {
+            value
+        } else {
+            self.mirror(&value)
+        }
+    }
+}
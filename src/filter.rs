@@ -0,0 +1,396 @@
+//! Signal filters for smoothing noisy sensor readings.
+//!
+//! [`Debounce`] and [`EdgeDetector`] process streams of `bool` samples from
+//! any source, such as ADI buttons, controller buttons or whether a vision
+//! target is in view.
+
+use alloc::{collections::VecDeque, vec::Vec};
+use core::time::Duration;
+
+use crate::{
+    rtos::{time_since_start, Instant},
+    units::Degrees,
+};
+
+/// A common interface for filters which process a stream of samples.
+pub trait Filter {
+    /// Processes a new sample, returning the filtered value.
+    fn filter(&mut self, input: f64) -> f64;
+
+    /// Clears any history accumulated by the filter.
+    fn reset(&mut self);
+
+    #[inline]
+    /// Creates a new filter which passes the output of this filter through
+    /// `next`.
+    fn chain<F: Filter>(self, next: F) -> Chain<Self, F>
+    where
+        Self: Sized,
+    {
+        Chain(self, next)
+    }
+}
+
+impl<F: Filter + ?Sized> Filter for &mut F {
+    #[inline]
+    fn filter(&mut self, input: f64) -> f64 {
+        (**self).filter(input)
+    }
+
+    #[inline]
+    fn reset(&mut self) {
+        (**self).reset()
+    }
+}
+
+/// A filter which averages the most recent samples.
+#[derive(Clone, Debug)]
+pub struct MovingAverage {
+    window: VecDeque<f64>,
+    size: usize,
+    sum: f64,
+}
+
+impl MovingAverage {
+    /// Creates a new moving average filter over the given number of samples.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `size` is zero.
+    pub fn new(size: usize) -> Self {
+        assert!(size > 0, "moving average window must not be empty");
+        Self {
+            window: VecDeque::with_capacity(size),
+            size,
+            sum: 0.0,
+        }
+    }
+}
+
+impl Filter for MovingAverage {
+    fn filter(&mut self, input: f64) -> f64 {
+        if self.window.len() == self.size {
+            self.sum -= self.window.pop_front().unwrap_or_default();
+        }
+        self.window.push_back(input);
+        self.sum += input;
+        self.sum / self.window.len() as f64
+    }
+
+    fn reset(&mut self) {
+        self.window.clear();
+        self.sum = 0.0;
+    }
+}
+
+/// An exponential moving average filter.
+#[derive(Clone, Debug)]
+pub struct Ema {
+    alpha: f64,
+    value: Option<f64>,
+}
+
+impl Ema {
+    /// Creates a new exponential moving average filter with the given
+    /// smoothing factor, from 0 to 1. Larger values track the input more
+    /// closely.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `alpha` is not within `(0, 1]`.
+    pub fn new(alpha: f64) -> Self {
+        assert!(
+            alpha > 0.0 && alpha <= 1.0,
+            "smoothing factor must be within (0, 1]"
+        );
+        Self { alpha, value: None }
+    }
+}
+
+impl Filter for Ema {
+    fn filter(&mut self, input: f64) -> f64 {
+        let value = match self.value {
+            Some(v) => v + self.alpha * (input - v),
+            None => input,
+        };
+        self.value = Some(value);
+        value
+    }
+
+    #[inline]
+    fn reset(&mut self) {
+        self.value = None;
+    }
+}
+
+/// A filter which takes the median of the most recent samples, for rejecting
+/// outliers.
+#[derive(Clone, Debug)]
+pub struct Median {
+    window: VecDeque<f64>,
+    size: usize,
+    sorted: Vec<f64>,
+}
+
+impl Median {
+    /// Creates a new median filter over the given number of samples.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `size` is zero.
+    pub fn new(size: usize) -> Self {
+        assert!(size > 0, "median window must not be empty");
+        Self {
+            window: VecDeque::with_capacity(size),
+            size,
+            sorted: Vec::with_capacity(size),
+        }
+    }
+}
+
+impl Filter for Median {
+    fn filter(&mut self, input: f64) -> f64 {
+        if self.window.len() == self.size {
+            self.window.pop_front();
+        }
+        self.window.push_back(input);
+        self.sorted.clear();
+        self.sorted.extend(self.window.iter().copied());
+        self.sorted.sort_unstable_by(f64::total_cmp);
+        let mid = self.sorted.len() / 2;
+        if self.sorted.len() % 2 == 0 {
+            (self.sorted[mid - 1] + self.sorted[mid]) / 2.0
+        } else {
+            self.sorted[mid]
+        }
+    }
+
+    #[inline]
+    fn reset(&mut self) {
+        self.window.clear();
+    }
+}
+
+/// A filter which passes the output of one filter into another. See
+/// [`Filter::chain()`].
+#[derive(Clone, Debug)]
+pub struct Chain<A: Filter, B: Filter>(A, B);
+
+impl<A: Filter, B: Filter> Filter for Chain<A, B> {
+    #[inline]
+    fn filter(&mut self, input: f64) -> f64 {
+        self.1.filter(self.0.filter(input))
+    }
+
+    #[inline]
+    fn reset(&mut self) {
+        self.0.reset();
+        self.1.reset();
+    }
+}
+
+/// A one-dimensional Kalman filter which fuses gyro-integrated heading with an
+/// absolute heading measurement (e.g., from encoders), to counteract gyro
+/// drift.
+///
+/// Headings are in degrees, and the filtered heading is bounded by (-180,
+/// 180].
+#[derive(Clone, Debug)]
+pub struct HeadingFilter {
+    estimate: f64,
+    variance: f64,
+    process_noise: f64,
+    measurement_noise: f64,
+}
+
+impl HeadingFilter {
+    /// Creates a new heading filter with the given initial heading.
+    ///
+    /// `process_noise` is the variance added to the estimate by each gyro
+    /// update and `measurement_noise` is the variance of the absolute heading
+    /// measurement; their ratio determines how strongly the measurement
+    /// corrects the gyro.
+    pub fn new(heading: f64, process_noise: f64, measurement_noise: f64) -> Self {
+        Self {
+            estimate: heading,
+            variance: 0.0,
+            process_noise,
+            measurement_noise,
+        }
+    }
+
+    /// Advances the filter by the heading change measured by the gyro since
+    /// the last update, then corrects it using an absolute heading
+    /// measurement, returning the filtered heading.
+    pub fn update(&mut self, gyro_delta: f64, measured: f64) -> f64 {
+        self.estimate += gyro_delta;
+        self.variance += self.process_noise;
+        self.correct(measured, self.measurement_noise)
+    }
+
+    /// Corrects the filter using an additional absolute heading measurement
+    /// with the given variance, such as a heading from a GPS sensor, without
+    /// advancing it, returning the filtered heading.
+    ///
+    /// A measurement with a large variance relative to `process_noise` only
+    /// moves the estimate slightly, so a noisy sensor can still remove drift
+    /// over a long period. The variance should not be negative; if both it and
+    /// the variance of the estimate are zero, the measurement is ignored.
+    pub fn correct(&mut self, measured: f64, measurement_noise: f64) -> f64 {
+        let total = self.variance + measurement_noise;
+        if total <= 0.0 {
+            return self.estimate;
+        }
+        let gain = self.variance / total;
+        self.estimate = Degrees(self.estimate + gain * Degrees(measured - self.estimate).wrap().0)
+            .wrap()
+            .0;
+        self.variance *= 1.0 - gain;
+        self.estimate
+    }
+
+    #[inline]
+    /// Gets the current filtered heading.
+    pub fn heading(&self) -> f64 {
+        self.estimate
+    }
+
+    #[inline]
+    /// Resets the filter to the given heading.
+    pub fn reset(&mut self, heading: f64) {
+        self.estimate = heading;
+        self.variance = 0.0;
+    }
+}
+
+/// Debounces a stream of `bool` samples: the output only changes once the
+/// input has held its new value for a given time, so that contact bounce or a
+/// flickering reading does not register as several changes.
+#[derive(Clone, Debug)]
+pub struct Debounce {
+    time: Duration,
+    state: bool,
+    pending: Option<Instant>,
+}
+
+impl Debounce {
+    #[inline]
+    /// Creates a new debouncer with an initial output of `false`, which
+    /// changes its output once the input has held a new value for `time`.
+    pub fn new(time: Duration) -> Self {
+        Self {
+            time,
+            state: false,
+            pending: None,
+        }
+    }
+
+    #[inline]
+    /// Sets the initial output. The default is `false`.
+    pub fn with_initial(mut self, state: bool) -> Self {
+        self.state = state;
+        self
+    }
+
+    /// Processes a new sample, returning the debounced output.
+    pub fn update(&mut self, input: bool) -> bool {
+        if input == self.state {
+            self.pending = None;
+        } else {
+            let now = time_since_start();
+            if now - *self.pending.get_or_insert(now) >= self.time {
+                self.state = input;
+                self.pending = None;
+            }
+        }
+        self.state
+    }
+
+    #[inline]
+    /// Gets the debounced output as of the last sample.
+    pub fn state(&self) -> bool {
+        self.state
+    }
+
+    #[inline]
+    /// Sets the output, discarding any pending change.
+    pub fn reset(&mut self, state: bool) {
+        self.state = state;
+        self.pending = None;
+    }
+}
+
+/// Detects changes in a stream of `bool` samples, e.g. to act once when a
+/// button is pressed rather than on every cycle while it is held.
+///
+/// ```
+/// let mut shoot = EdgeDetector::new();
+/// loop {
+///     if shoot.update(controller.r1.is_pressed()?).is_rising() {
+///         catapult.fire();
+///     }
+///     l.delay();
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EdgeDetector {
+    state: bool,
+}
+
+impl EdgeDetector {
+    #[inline]
+    /// Creates a new edge detector with a previous sample of `false`, so that
+    /// an initial `true` sample is a rising edge.
+    pub fn new() -> Self {
+        Self { state: false }
+    }
+
+    #[inline]
+    /// Sets the previous sample. The default is `false`.
+    pub fn with_initial(mut self, state: bool) -> Self {
+        self.state = state;
+        self
+    }
+
+    /// Processes a new sample, returning the change since the previous one.
+    pub fn update(&mut self, input: bool) -> Edge {
+        let edge = match (self.state, input) {
+            (false, true) => Edge::Rising,
+            (true, false) => Edge::Falling,
+            _ => Edge::None,
+        };
+        self.state = input;
+        edge
+    }
+
+    #[inline]
+    /// Gets the previous sample.
+    pub fn state(&self) -> bool {
+        self.state
+    }
+}
+
+/// Represents a change in a stream of `bool` samples. See [`EdgeDetector`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Edge {
+    /// The sample did not change.
+    None,
+    /// The sample changed from `false` to `true`.
+    Rising,
+    /// The sample changed from `true` to `false`.
+    Falling,
+}
+
+impl Edge {
+    #[inline]
+    /// Checks whether this is a rising edge.
+    pub fn is_rising(self) -> bool {
+        self == Edge::Rising
+    }
+
+    #[inline]
+    /// Checks whether this is a falling edge.
+    pub fn is_falling(self) -> bool {
+        self == Edge::Falling
+    }
+}
@@ -0,0 +1,556 @@
+//! # Fixed-Point Control.
+//!
+//! With the `fixed-point` feature enabled, this module provides a Q16.16
+//! [`Fixed`] number type and integer-only versions of the PID controller,
+//! smoothing filters and differential odometry, for hot loops which should
+//! not use floating point, or which must produce bit-identical results from
+//! run to run.
+//!
+//! Floating-point values are only needed to construct constants (e.g., with
+//! [`Fixed::from_f64()`]) and to convert results for use with the rest of the
+//! crate.
+//!
+//! # Example
+//!
+//! ```
+//! let mut pid = FixedPid::new(Fixed::from_f64(0.8), Fixed::ZERO, Fixed::from_f64(0.05));
+//! let mut odometry = FixedOdometry::new(Fixed::from_int(300));
+//!
+//! // Every 10ms:
+//! let pose = odometry.update(left_mm, right_mm);
+//! let power = pid.step(target_y, pose.y).round();
+//! drivetrain.tank(power as i8, power as i8)?;
+//! ```
+
+use alloc::collections::VecDeque;
+use core::{
+    fmt::{self, Display, Formatter},
+    ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign},
+};
+
+use crate::{
+    geometry::{Pose2d, Rotation2d, Vec2},
+    rtos::{time_since_start, Instant},
+    units::{Millimeters, Radians},
+};
+
+/// A signed fixed-point number with 16 integer bits and 16 fractional bits,
+/// giving a range of about ±32768 with a resolution of about 0.000015.
+///
+/// Sums and differences overflow in the same way as `i32` arithmetic, while
+/// out-of-range products and quotients are truncated; the `saturating_*`
+/// methods can be used where overflow is possible.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fixed(i32);
+
+impl Fixed {
+    /// The number of fractional bits.
+    pub const FRAC_BITS: u32 = 16;
+    /// Zero.
+    pub const ZERO: Self = Self(0);
+    /// One.
+    pub const ONE: Self = Self(1 << Self::FRAC_BITS);
+    /// The smallest representable value.
+    pub const MIN: Self = Self(i32::MIN);
+    /// The largest representable value.
+    pub const MAX: Self = Self(i32::MAX);
+    /// Archimedes’ constant (π).
+    pub const PI: Self = Self(205_887);
+    /// π/2.
+    pub const FRAC_PI_2: Self = Self(102_944);
+    /// The full circle constant (τ = 2π).
+    pub const TAU: Self = Self(411_775);
+
+    #[inline]
+    /// Creates a number from its raw representation, i.e. the value multiplied
+    /// by 2<sup>16</sup>.
+    pub const fn from_bits(bits: i32) -> Self {
+        Self(bits)
+    }
+
+    #[inline]
+    /// Gets the raw representation of the number, i.e. the value multiplied by
+    /// 2<sup>16</sup>.
+    pub const fn to_bits(self) -> i32 {
+        self.0
+    }
+
+    #[inline]
+    /// Converts an integer, saturating if it is out of range.
+    pub const fn from_int(n: i32) -> Self {
+        Self(saturate((n as i64) << Self::FRAC_BITS))
+    }
+
+    #[inline]
+    /// Creates the number closest to `numerator / denominator`, saturating if
+    /// it is out of range.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `denominator` is zero.
+    pub const fn from_ratio(numerator: i32, denominator: i32) -> Self {
+        Self(saturate(
+            ((numerator as i64) << Self::FRAC_BITS) / denominator as i64,
+        ))
+    }
+
+    #[inline]
+    /// Converts a floating-point number, saturating if it is out of range.
+    pub fn from_f64(x: f64) -> Self {
+        Self(saturate((x * Self::ONE.0 as f64) as i64))
+    }
+
+    #[inline]
+    /// Converts the number to floating point.
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / Self::ONE.0 as f64
+    }
+
+    #[inline]
+    /// Gets the integer part of the number, rounding towards negative
+    /// infinity.
+    pub const fn floor(self) -> i32 {
+        self.0 >> Self::FRAC_BITS
+    }
+
+    #[inline]
+    /// Rounds the number to the nearest integer, with halves rounded towards
+    /// positive infinity.
+    pub const fn round(self) -> i32 {
+        ((self.0 as i64 + (Self::ONE.0 as i64 >> 1)) >> Self::FRAC_BITS) as i32
+    }
+
+    #[inline]
+    /// Gets the absolute value of the number.
+    pub const fn abs(self) -> Self {
+        Self(self.0.abs())
+    }
+
+    #[inline]
+    /// Adds two numbers, saturating at the bounds of the type.
+    pub const fn saturating_add(self, rhs: Self) -> Self {
+        Self(self.0.saturating_add(rhs.0))
+    }
+
+    #[inline]
+    /// Subtracts two numbers, saturating at the bounds of the type.
+    pub const fn saturating_sub(self, rhs: Self) -> Self {
+        Self(self.0.saturating_sub(rhs.0))
+    }
+
+    #[inline]
+    /// Multiplies two numbers, saturating at the bounds of the type.
+    pub const fn saturating_mul(self, rhs: Self) -> Self {
+        Self(saturate((self.0 as i64 * rhs.0 as i64) >> Self::FRAC_BITS))
+    }
+
+    /// Computes the sine of an angle in radians.
+    pub fn sin(self) -> Self {
+        // Reduce to [-π, π], then to [-π/2, π/2] using sin(π - x) = sin(x).
+        let mut x = self.0 as i64 % Self::TAU.0 as i64;
+        if x > Self::PI.0 as i64 {
+            x -= Self::TAU.0 as i64;
+        } else if x < -Self::PI.0 as i64 {
+            x += Self::TAU.0 as i64;
+        }
+        if x > Self::FRAC_PI_2.0 as i64 {
+            x = Self::PI.0 as i64 - x;
+        } else if x < -Self::FRAC_PI_2.0 as i64 {
+            x = -Self::PI.0 as i64 - x;
+        }
+
+        // Taylor series to the x^9 term, evaluated in Horner form with 32
+        // fractional bits; the error is within a few units of the resolution
+        // of the type.
+        let x = x << Self::FRAC_BITS;
+        let x2 = mul_q32(x, x);
+        let mut sum = (1i64 << 32) / 362_880;
+        sum = mul_q32(sum, x2) - (1i64 << 32) / 5040;
+        sum = mul_q32(sum, x2) + (1i64 << 32) / 120;
+        sum = mul_q32(sum, x2) - (1i64 << 32) / 6;
+        sum = mul_q32(sum, x2) + (1i64 << 32);
+        Self((mul_q32(sum, x) >> Self::FRAC_BITS) as i32)
+    }
+
+    #[inline]
+    /// Computes the cosine of an angle in radians.
+    pub fn cos(self) -> Self {
+        Self(self.0.wrapping_add(Self::FRAC_PI_2.0)).sin()
+    }
+}
+
+const fn saturate(x: i64) -> i32 {
+    if x > i32::MAX as i64 {
+        i32::MAX
+    } else if x < i32::MIN as i64 {
+        i32::MIN
+    } else {
+        x as i32
+    }
+}
+
+/// Multiplies two numbers with 32 fractional bits.
+fn mul_q32(a: i64, b: i64) -> i64 {
+    ((a as i128 * b as i128) >> 32) as i64
+}
+
+impl From<i32> for Fixed {
+    #[inline]
+    fn from(n: i32) -> Self {
+        Self::from_int(n)
+    }
+}
+
+impl From<Fixed> for f64 {
+    #[inline]
+    fn from(x: Fixed) -> Self {
+        x.to_f64()
+    }
+}
+
+impl Add for Fixed {
+    type Output = Self;
+
+    #[inline]
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0 + rhs.0)
+    }
+}
+
+impl AddAssign for Fixed {
+    #[inline]
+    fn add_assign(&mut self, rhs: Self) {
+        self.0 += rhs.0;
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Self;
+
+    #[inline]
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0 - rhs.0)
+    }
+}
+
+impl SubAssign for Fixed {
+    #[inline]
+    fn sub_assign(&mut self, rhs: Self) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Self;
+
+    #[inline]
+    fn neg(self) -> Self {
+        Self(-self.0)
+    }
+}
+
+impl Mul for Fixed {
+    type Output = Self;
+
+    #[inline]
+    fn mul(self, rhs: Self) -> Self {
+        Self(((self.0 as i64 * rhs.0 as i64) >> Self::FRAC_BITS) as i32)
+    }
+}
+
+impl Mul<i32> for Fixed {
+    type Output = Self;
+
+    #[inline]
+    fn mul(self, rhs: i32) -> Self {
+        Self(self.0 * rhs)
+    }
+}
+
+impl Div for Fixed {
+    type Output = Self;
+
+    #[inline]
+    fn div(self, rhs: Self) -> Self {
+        Self((((self.0 as i64) << Self::FRAC_BITS) / rhs.0 as i64) as i32)
+    }
+}
+
+impl Div<i32> for Fixed {
+    type Output = Self;
+
+    #[inline]
+    fn div(self, rhs: i32) -> Self {
+        Self(self.0 / rhs)
+    }
+}
+
+impl Display for Fixed {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        // Round to four decimal places without going through floating point.
+        let magnitude = (self.0 as i64).unsigned_abs();
+        let scaled = (magnitude * 10_000 + (1 << (Self::FRAC_BITS - 1))) >> Self::FRAC_BITS;
+        let sign = if self.0 < 0 && scaled != 0 { "-" } else { "" };
+        write!(f, "{}{}.{:04}", sign, scaled / 10_000, scaled % 10_000)
+    }
+}
+
+/// A fixed-point version of [`Pid`](crate::control::Pid).
+///
+/// Time between successive calls to [`step()`](Self::step()) is measured in
+/// whole milliseconds.
+#[derive(Clone, Debug)]
+pub struct FixedPid {
+    kp: Fixed,
+    ki: Fixed,
+    kd: Fixed,
+    integral_limit: Fixed,
+    integral: Fixed,
+    last: Option<(Fixed, Instant)>,
+}
+
+impl FixedPid {
+    #[inline]
+    /// Creates a new PID controller with the given gains.
+    pub fn new(kp: Fixed, ki: Fixed, kd: Fixed) -> Self {
+        Self {
+            kp,
+            ki,
+            kd,
+            integral_limit: Fixed::MAX,
+            integral: Fixed::ZERO,
+            last: None,
+        }
+    }
+
+    #[inline]
+    /// Limits the magnitude of the accumulated integral term, to prevent
+    /// integral windup.
+    pub fn with_integral_limit(mut self, limit: Fixed) -> Self {
+        self.integral_limit = limit;
+        self
+    }
+
+    #[inline]
+    /// Gets the proportional, integral and derivative gains, in that order.
+    pub fn gains(&self) -> (Fixed, Fixed, Fixed) {
+        (self.kp, self.ki, self.kd)
+    }
+
+    #[inline]
+    /// Updates the proportional, integral and derivative gains.
+    pub fn set_gains(&mut self, kp: Fixed, ki: Fixed, kd: Fixed) {
+        self.kp = kp;
+        self.ki = ki;
+        self.kd = kd;
+    }
+
+    /// Runs one iteration of the controller, returning the control output
+    /// given the `target` value and the `actual` (measured) value.
+    pub fn step(&mut self, target: Fixed, actual: Fixed) -> Fixed {
+        let error = target - actual;
+        let now = time_since_start();
+        let derivative = match self.last {
+            Some((last_error, last_time)) if now.as_millis() > last_time.as_millis() => {
+                let dt = Fixed::from_ratio((now.as_millis() - last_time.as_millis()) as i32, 1000);
+                self.integral = self
+                    .integral
+                    .saturating_add(error * dt)
+                    .clamp(-self.integral_limit, self.integral_limit);
+                (error - last_error) / dt
+            }
+            _ => Fixed::ZERO,
+        };
+        self.last = Some((error, now));
+        self.kp
+            .saturating_mul(error)
+            .saturating_add(self.ki.saturating_mul(self.integral))
+            .saturating_add(self.kd.saturating_mul(derivative))
+    }
+
+    #[inline]
+    /// Clears the accumulated state of the controller.
+    pub fn reset(&mut self) {
+        self.integral = Fixed::ZERO;
+        self.last = None;
+    }
+}
+
+/// A fixed-point version of [`MovingAverage`](crate::filter::MovingAverage).
+#[derive(Clone, Debug)]
+pub struct FixedMovingAverage {
+    window: VecDeque<Fixed>,
+    size: usize,
+    sum: i64,
+}
+
+impl FixedMovingAverage {
+    /// Creates a new moving average filter over the given number of samples.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `size` is zero.
+    pub fn new(size: usize) -> Self {
+        assert!(size > 0, "moving average window must not be empty");
+        Self {
+            window: VecDeque::with_capacity(size),
+            size,
+            sum: 0,
+        }
+    }
+
+    /// Processes a new sample, returning the filtered value.
+    pub fn filter(&mut self, input: Fixed) -> Fixed {
+        if self.window.len() == self.size {
+            self.sum -= self.window.pop_front().unwrap_or_default().0 as i64;
+        }
+        self.window.push_back(input);
+        self.sum += input.0 as i64;
+        Fixed((self.sum / self.window.len() as i64) as i32)
+    }
+
+    #[inline]
+    /// Clears any history accumulated by the filter.
+    pub fn reset(&mut self) {
+        self.window.clear();
+        self.sum = 0;
+    }
+}
+
+/// A fixed-point version of [`Ema`](crate::filter::Ema).
+#[derive(Clone, Debug)]
+pub struct FixedEma {
+    alpha: Fixed,
+    value: Option<Fixed>,
+}
+
+impl FixedEma {
+    /// Creates a new exponential moving average filter with the given
+    /// smoothing factor, from 0 to 1. Larger values track the input more
+    /// closely.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `alpha` is not within `(0, 1]`.
+    pub fn new(alpha: Fixed) -> Self {
+        assert!(
+            alpha > Fixed::ZERO && alpha <= Fixed::ONE,
+            "smoothing factor must be within (0, 1]"
+        );
+        Self { alpha, value: None }
+    }
+
+    /// Processes a new sample, returning the filtered value.
+    pub fn filter(&mut self, input: Fixed) -> Fixed {
+        let value = match self.value {
+            Some(v) => v + self.alpha * (input - v),
+            None => input,
+        };
+        self.value = Some(value);
+        value
+    }
+
+    #[inline]
+    /// Clears any history accumulated by the filter.
+    pub fn reset(&mut self) {
+        self.value = None;
+    }
+}
+
+/// A fixed-point robot pose, following the conventions of
+/// [`Pose2d`](crate::geometry::Pose2d).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FixedPose {
+    /// The x coordinate in millimetres.
+    pub x: Fixed,
+    /// The y coordinate in millimetres.
+    pub y: Fixed,
+    /// The heading in radians, positive clockwise.
+    pub heading: Fixed,
+}
+
+impl From<FixedPose> for Pose2d {
+    fn from(pose: FixedPose) -> Self {
+        Pose2d::new(
+            Vec2::new(Millimeters(pose.x.to_f64()), Millimeters(pose.y.to_f64())),
+            Rotation2d::from(Radians(pose.heading.to_f64())),
+        )
+    }
+}
+
+/// Fixed-point odometry for a differential drivetrain, computed in the same
+/// way as by [`DifferentialDrivetrain`](crate::drivetrain::DifferentialDrivetrain).
+///
+/// Positions are limited to about ±32 metres by the range of [`Fixed`].
+#[derive(Clone, Debug)]
+pub struct FixedOdometry {
+    left_offset: Fixed,
+    right_offset: Fixed,
+    pose: FixedPose,
+    last: Option<(Fixed, Fixed)>,
+}
+
+impl FixedOdometry {
+    #[inline]
+    /// Creates a new odometry tracker for wheels separated by `track_width`
+    /// millimetres, starting at the origin.
+    pub fn new(track_width: Fixed) -> Self {
+        Self::with_wheel_offsets(-track_width / 2, track_width / 2)
+    }
+
+    #[inline]
+    /// Creates a new odometry tracker for wheels at the given lateral offsets
+    /// from the tracking centre in millimetres, positive to the right, as for
+    /// offset tracking wheels.
+    pub fn with_wheel_offsets(left_offset: Fixed, right_offset: Fixed) -> Self {
+        Self {
+            left_offset,
+            right_offset,
+            pose: FixedPose::default(),
+            last: None,
+        }
+    }
+
+    #[inline]
+    /// Gets the current pose.
+    pub fn pose(&self) -> FixedPose {
+        self.pose
+    }
+
+    #[inline]
+    /// Overrides the current pose.
+    pub fn set_pose(&mut self, pose: FixedPose) {
+        self.pose = pose;
+    }
+
+    #[inline]
+    /// Integrates the movement since the last update, given the total distance
+    /// travelled by each wheel in millimetres.
+    pub fn update(&mut self, left: Fixed, right: Fixed) -> FixedPose {
+        self.integrate(left, right, None)
+    }
+
+    #[inline]
+    /// Integrates the movement since the last update, given the total distance
+    /// travelled by each wheel in millimetres and the heading change reported
+    /// by a gyro in radians, which is used in place of the heading change
+    /// computed from the wheels.
+    pub fn update_with_heading(&mut self, left: Fixed, right: Fixed, dtheta: Fixed) -> FixedPose {
+        self.integrate(left, right, Some(dtheta))
+    }
+
+    fn integrate(&mut self, left: Fixed, right: Fixed, dtheta: Option<Fixed>) -> FixedPose {
+        if let Some((last_left, last_right)) = self.last.replace((left, right)) {
+            let dl = left - last_left;
+            let dr = right - last_right;
+            let dtheta =
+                dtheta.unwrap_or_else(|| (dl - dr) / (self.right_offset - self.left_offset));
+            let ds = (dl + dr) / 2 + (self.left_offset + self.right_offset) / 2 * dtheta;
+            let mid = self.pose.heading + dtheta / 2;
+            self.pose.x += ds * mid.sin();
+            self.pose.y += ds * mid.cos();
+            self.pose.heading += dtheta;
+        }
+        self.pose
+    }
+}
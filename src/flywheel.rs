@@ -0,0 +1,262 @@
+//! # Flywheel API.
+
+use alloc::sync::{Arc, Weak};
+use core::time::Duration;
+
+#[cfg(not(feature = "sim"))]
+use num_traits::Float;
+use owner_monad::OwnerMut;
+
+use crate::{
+    control::FeedbackController,
+    error::Error,
+    escalation,
+    filter::{Ema, Filter},
+    motor::MotorGroup,
+    rtos::{
+        handle_event, Event, EventHandle, GenericSleep, Instant, Loop, Mutex, Selectable, Task,
+    },
+    units::Rpm,
+};
+
+const UPDATE_PERIOD: Duration = Duration::from_millis(10);
+
+/// A velocity-controlled flywheel which runs its control loop on a background
+/// task.
+///
+/// The output voltage is the sum of a feedforward term and the output of a
+/// [`FeedbackController`] (such as [`Pid`](crate::control::Pid) or
+/// [`Tbh`](crate::control::Tbh)) acting on the measured velocity of the
+/// motors, as reported by
+/// [`Motor::get_actual_velocity()`](crate::motor::Motor::get_actual_velocity()).
+///
+/// The background task stops the motors and exits when the `Flywheel` is
+/// dropped.
+pub struct Flywheel(Arc<Mutex<FlywheelData>>);
+
+impl Flywheel {
+    #[inline]
+    /// Creates a new flywheel and spawns its control task. Panics on failure;
+    /// see [`Flywheel::try_new()`].
+    pub fn new(
+        motors: MotorGroup,
+        controller: impl FeedbackController + Send + 'static,
+        config: FlywheelConfig,
+    ) -> Self {
+        Self::try_new(motors, controller, config)
+            .unwrap_or_else(|err| panic!("failed to create flywheel: {:?}", err))
+    }
+
+    /// Creates a new flywheel and spawns its control task.
+    pub fn try_new(
+        mut motors: MotorGroup,
+        mut controller: impl FeedbackController + Send + 'static,
+        config: FlywheelConfig,
+    ) -> Result<Self, Error> {
+        let data = Arc::new(Mutex::try_new(FlywheelData {
+            target: Rpm(0.0),
+            velocity: Rpm(0.0),
+            at_speed: false,
+            shots: 0,
+            event: Event::new(),
+        })?);
+        let weak = Arc::downgrade(&data);
+        Task::spawn_ext(
+            "flywheel",
+            Task::DEFAULT_PRIORITY,
+            Task::DEFAULT_STACK_DEPTH,
+            move || {
+                let mut filter = Ema::new(config.smoothing);
+                let mut l = Loop::new(UPDATE_PERIOD);
+                loop {
+                    let data = match weak.upgrade() {
+                        Some(data) => data,
+                        None => {
+                            if let Err(err) = motors.move_voltage(0) {
+                                escalation::report("flywheel", &err);
+                            }
+                            return;
+                        }
+                    };
+                    let target = data.lock().target.0;
+                    let stop = match motors.get_actual_velocity() {
+                        Ok(v) => {
+                            let velocity = filter.filter(v);
+                            let output = if target == 0.0 {
+                                controller.reset();
+                                0.0
+                            } else {
+                                config.kv * target
+                                    + config.ks * target.signum()
+                                    + controller.step(target, velocity)
+                            };
+                            data.lock().update(Rpm(velocity), &config);
+                            match motors.move_voltage(output as i32) {
+                                Ok(()) => false,
+                                Err(err) => escalation::report("flywheel", &err),
+                            }
+                        }
+                        Err(err) => escalation::report("flywheel", &err),
+                    };
+                    drop(data);
+                    if stop {
+                        motors.move_voltage(0).ok();
+                        return;
+                    }
+                    l.delay();
+                }
+            },
+        )?;
+        Ok(Self(data))
+    }
+
+    /// Sets the target velocity of the flywheel. A target of zero lets the
+    /// flywheel coast to a stop.
+    pub fn set_target(&self, velocity: Rpm) {
+        let mut lock = self.0.lock();
+        if lock.target != velocity {
+            lock.target = velocity;
+            lock.at_speed = false;
+        }
+    }
+
+    #[inline]
+    /// Gets the target velocity of the flywheel.
+    pub fn target(&self) -> Rpm {
+        self.0.lock().target
+    }
+
+    #[inline]
+    /// Gets the most recent filtered velocity of the flywheel.
+    pub fn velocity(&self) -> Rpm {
+        self.0.lock().velocity
+    }
+
+    #[inline]
+    /// Checks whether the flywheel is currently within tolerance of its target
+    /// velocity.
+    pub fn is_at_speed(&self) -> bool {
+        self.0.lock().at_speed
+    }
+
+    #[inline]
+    /// Gets the number of shots detected since the flywheel was created.
+    ///
+    /// A shot is counted whenever the velocity dips by more than
+    /// [`FlywheelConfig::shot_drop`] after the flywheel has come up to speed.
+    pub fn shots(&self) -> u32 {
+        self.0.lock().shots
+    }
+
+    /// A [`Selectable`] event which occurs when the flywheel is within
+    /// tolerance of its target velocity.
+    pub fn at_speed(&'_ self) -> impl Selectable + '_ {
+        FlywheelSelect {
+            flywheel: self,
+            handle: handle_event(FlywheelHandle(Arc::downgrade(&self.0))),
+            f: |data: &FlywheelData| data.at_speed.then_some(()),
+        }
+    }
+
+    /// A [`Selectable`] event which occurs when the next shot is detected. The
+    /// result is the total number of shots detected so far.
+    pub fn next_shot(&'_ self) -> impl Selectable<u32> + '_ {
+        let shots = self.shots();
+        FlywheelSelect {
+            flywheel: self,
+            handle: handle_event(FlywheelHandle(Arc::downgrade(&self.0))),
+            f: move |data: &FlywheelData| (data.shots > shots).then_some(data.shots),
+        }
+    }
+}
+
+/// Represents the tuning parameters of a [`Flywheel`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct FlywheelConfig {
+    /// The feedforward gain in millivolts per RPM of target velocity.
+    pub kv: f64,
+    /// The feedforward voltage in millivolts to overcome static friction.
+    pub ks: f64,
+    /// The velocity error within which the flywheel is considered at speed.
+    pub tolerance: Rpm,
+    /// The dip in velocity, after coming up to speed, which is counted as a
+    /// shot.
+    pub shot_drop: Rpm,
+    /// The smoothing factor of the exponential moving average applied to the
+    /// measured velocity; see [`Ema::new()`].
+    pub smoothing: f64,
+}
+
+impl FlywheelConfig {
+    /// Creates a new configuration with the given feedforward gain and default
+    /// tolerances.
+    pub fn new(kv: f64) -> Self {
+        Self {
+            kv,
+            ks: 0.0,
+            tolerance: Rpm(25.0),
+            shot_drop: Rpm(100.0),
+            smoothing: 0.5,
+        }
+    }
+}
+
+struct FlywheelData {
+    target: Rpm,
+    velocity: Rpm,
+    at_speed: bool,
+    shots: u32,
+    event: Event,
+}
+
+impl FlywheelData {
+    fn update(&mut self, velocity: Rpm, config: &FlywheelConfig) {
+        self.velocity = velocity;
+        let error = (self.target - velocity).abs();
+        if self.at_speed {
+            if self.target.0 != 0.0
+                && (self.target - velocity) * self.target.0.signum() > config.shot_drop
+            {
+                self.at_speed = false;
+                self.shots += 1;
+                self.event.notify();
+            }
+        } else if error <= config.tolerance {
+            self.at_speed = true;
+            self.event.notify();
+        }
+    }
+}
+
+struct FlywheelHandle(Weak<Mutex<FlywheelData>>);
+
+impl OwnerMut<Event> for FlywheelHandle {
+    fn with<'a, U>(&'a mut self, f: impl FnOnce(&mut Event) -> U) -> Option<U>
+    where
+        Event: 'a,
+    {
+        Some(f(&mut self.0.upgrade()?.lock().event))
+    }
+}
+
+struct FlywheelSelect<'a, T, F: Fn(&FlywheelData) -> Option<T>> {
+    flywheel: &'a Flywheel,
+    handle: EventHandle<FlywheelHandle>,
+    f: F,
+}
+
+impl<'a, T, F: Fn(&FlywheelData) -> Option<T>> Selectable<T> for FlywheelSelect<'a, T, F> {
+    fn poll(self) -> Result<T, Self> {
+        let result = (self.f)(&self.flywheel.0.lock());
+        result.ok_or(self)
+    }
+
+    #[inline]
+    fn sleep(&self) -> GenericSleep {
+        if self.handle.is_done() {
+            GenericSleep::Timestamp(Instant::from_millis(0))
+        } else {
+            GenericSleep::NotifyTake(None)
+        }
+    }
+}
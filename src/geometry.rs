@@ -0,0 +1,408 @@
+//! # 2D Geometry.
+//!
+//! These types follow the same conventions as the rest of the crate: the
+//! field's y axis points forwards from the origin, the x axis points to the
+//! right, and rotations are positive clockwise, with a heading of zero
+//! pointing along the positive y axis.
+//!
+//! Vectors relative to a robot (e.g., in a [`Transform2d`]) use the same
+//! convention, with y pointing forwards and x pointing to the right of the
+//! robot.
+
+use core::{
+    fmt::{self, Display, Formatter},
+    ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign},
+};
+
+#[cfg(not(feature = "sim"))]
+use num_traits::Float;
+
+use crate::units::{Degrees, Millimeters, Radians};
+
+/// A two-dimensional position or displacement.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vec2 {
+    /// The x component.
+    pub x: Millimeters,
+    /// The y component.
+    pub y: Millimeters,
+}
+
+impl Vec2 {
+    /// The zero vector.
+    pub const ZERO: Self = Self {
+        x: Millimeters(0.0),
+        y: Millimeters(0.0),
+    };
+
+    #[inline]
+    /// Creates a new vector from its components.
+    pub fn new(x: impl Into<Millimeters>, y: impl Into<Millimeters>) -> Self {
+        Self {
+            x: x.into(),
+            y: y.into(),
+        }
+    }
+
+    #[inline]
+    /// Creates a vector of the given length pointing in the given direction.
+    pub fn from_polar(length: impl Into<Millimeters>, direction: Rotation2d) -> Self {
+        let length = length.into();
+        Self::new(length * direction.sin(), length * direction.cos())
+    }
+
+    #[inline]
+    /// Gets the length of the vector.
+    pub fn length(self) -> Millimeters {
+        Millimeters(self.x.0.hypot(self.y.0))
+    }
+
+    #[inline]
+    /// Gets the distance between two points.
+    pub fn distance(self, other: Self) -> Millimeters {
+        (other - self).length()
+    }
+
+    #[inline]
+    /// Gets the direction in which the vector points. The zero vector points
+    /// along the positive y axis.
+    pub fn angle(self) -> Rotation2d {
+        Radians(self.x.0.atan2(self.y.0)).into()
+    }
+
+    #[inline]
+    /// Computes the dot product of two vectors, in square millimetres.
+    pub fn dot(self, other: Self) -> f64 {
+        self.x.0 * other.x.0 + self.y.0 * other.y.0
+    }
+
+    #[inline]
+    /// Computes the z component of the cross product of two vectors, in square
+    /// millimetres. This is positive when `other` is anticlockwise of `self`.
+    pub fn cross(self, other: Self) -> f64 {
+        self.x.0 * other.y.0 - self.y.0 * other.x.0
+    }
+
+    #[inline]
+    /// Rotates the vector clockwise by the given rotation.
+    pub fn rotate(self, rotation: Rotation2d) -> Self {
+        let (sin, cos) = (rotation.sin(), rotation.cos());
+        Self::new(self.x * cos + self.y * sin, self.y * cos - self.x * sin)
+    }
+
+    #[inline]
+    /// Linearly interpolates between two vectors, where `t` is 0 at `self`
+    /// and 1 at `other`.
+    pub fn lerp(self, other: Self, t: f64) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl From<(Millimeters, Millimeters)> for Vec2 {
+    #[inline]
+    fn from((x, y): (Millimeters, Millimeters)) -> Self {
+        Self { x, y }
+    }
+}
+
+impl Display for Vec2 {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+impl Add for Vec2 {
+    type Output = Self;
+
+    #[inline]
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl Sub for Vec2 {
+    type Output = Self;
+
+    #[inline]
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl Neg for Vec2 {
+    type Output = Self;
+
+    #[inline]
+    fn neg(self) -> Self {
+        Self::new(-self.x, -self.y)
+    }
+}
+
+impl Mul<f64> for Vec2 {
+    type Output = Self;
+
+    #[inline]
+    fn mul(self, rhs: f64) -> Self {
+        Self::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl Div<f64> for Vec2 {
+    type Output = Self;
+
+    #[inline]
+    fn div(self, rhs: f64) -> Self {
+        Self::new(self.x / rhs, self.y / rhs)
+    }
+}
+
+impl AddAssign for Vec2 {
+    #[inline]
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Vec2 {
+    #[inline]
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+/// A rotation (or heading), bounded by (-180°, 180°].
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Rotation2d(Degrees);
+
+impl Rotation2d {
+    #[inline]
+    /// Creates a new rotation, wrapping the angle into range.
+    pub fn new(angle: impl Into<Degrees>) -> Self {
+        Self(angle.into().wrap())
+    }
+
+    #[inline]
+    /// Gets the angle of the rotation in degrees.
+    pub fn degrees(self) -> Degrees {
+        self.0
+    }
+
+    #[inline]
+    /// Gets the angle of the rotation in radians.
+    pub fn radians(self) -> Radians {
+        self.0.into()
+    }
+
+    #[inline]
+    /// Computes the sine of the rotation.
+    pub fn sin(self) -> f64 {
+        self.radians().sin()
+    }
+
+    #[inline]
+    /// Computes the cosine of the rotation.
+    pub fn cos(self) -> f64 {
+        self.radians().cos()
+    }
+
+    #[inline]
+    /// Gets the rotation which undoes this one.
+    pub fn inverse(self) -> Self {
+        Self::new(-self.0)
+    }
+
+    #[inline]
+    /// Interpolates between two rotations the shortest way around, where `t`
+    /// is 0 at `self` and 1 at `other`.
+    pub fn lerp(self, other: Self, t: f64) -> Self {
+        Self::new(self.0 + (other - self).0 * t)
+    }
+}
+
+impl From<Degrees> for Rotation2d {
+    #[inline]
+    fn from(angle: Degrees) -> Self {
+        Self::new(angle)
+    }
+}
+
+impl From<Radians> for Rotation2d {
+    #[inline]
+    fn from(angle: Radians) -> Self {
+        Self::new(angle)
+    }
+}
+
+impl From<Rotation2d> for Degrees {
+    #[inline]
+    fn from(rotation: Rotation2d) -> Self {
+        rotation.0
+    }
+}
+
+impl From<Rotation2d> for Radians {
+    #[inline]
+    fn from(rotation: Rotation2d) -> Self {
+        rotation.radians()
+    }
+}
+
+impl Display for Rotation2d {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl Add for Rotation2d {
+    type Output = Self;
+
+    #[inline]
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Rotation2d {
+    type Output = Self;
+
+    #[inline]
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.0 - rhs.0)
+    }
+}
+
+impl Neg for Rotation2d {
+    type Output = Self;
+
+    #[inline]
+    fn neg(self) -> Self {
+        self.inverse()
+    }
+}
+
+impl AddAssign for Rotation2d {
+    #[inline]
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Rotation2d {
+    #[inline]
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+/// Represents a position and heading on the field.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Pose2d {
+    /// The position.
+    pub position: Vec2,
+    /// The heading.
+    pub heading: Rotation2d,
+}
+
+impl Pose2d {
+    #[inline]
+    /// Creates a new pose.
+    pub fn new(position: impl Into<Vec2>, heading: impl Into<Rotation2d>) -> Self {
+        Self {
+            position: position.into(),
+            heading: heading.into(),
+        }
+    }
+
+    #[inline]
+    /// Applies a transformation relative to this pose, e.g. moving a robot by
+    /// a robot-relative displacement.
+    pub fn transform_by(self, transform: Transform2d) -> Self {
+        Self {
+            position: self.position + transform.translation.rotate(self.heading),
+            heading: self.heading + transform.rotation,
+        }
+    }
+
+    #[inline]
+    /// Expresses this pose relative to another pose, i.e. as seen by a robot
+    /// at `origin`.
+    pub fn relative_to(self, origin: Self) -> Self {
+        Self {
+            position: (self.position - origin.position).rotate(-origin.heading),
+            heading: self.heading - origin.heading,
+        }
+    }
+
+    #[inline]
+    /// Linearly interpolates between two poses, where `t` is 0 at `self` and 1
+    /// at `other`.
+    pub fn lerp(self, other: Self, t: f64) -> Self {
+        Self {
+            position: self.position.lerp(other.position, t),
+            heading: self.heading.lerp(other.heading, t),
+        }
+    }
+}
+
+impl Display for Pose2d {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} @ {}", self.position, self.heading)
+    }
+}
+
+impl Add<Transform2d> for Pose2d {
+    type Output = Self;
+
+    #[inline]
+    fn add(self, rhs: Transform2d) -> Self {
+        self.transform_by(rhs)
+    }
+}
+
+impl Sub for Pose2d {
+    type Output = Transform2d;
+
+    /// Gets the transformation which takes `rhs` to `self`.
+    #[inline]
+    fn sub(self, rhs: Self) -> Transform2d {
+        let relative = self.relative_to(rhs);
+        Transform2d::new(relative.position, relative.heading)
+    }
+}
+
+/// A robot-relative change in position and heading.
+///
+/// The translation is applied in the starting frame, before the rotation.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Transform2d {
+    /// The translation, relative to the starting heading.
+    pub translation: Vec2,
+    /// The change in heading.
+    pub rotation: Rotation2d,
+}
+
+impl Transform2d {
+    #[inline]
+    /// Creates a new transformation.
+    pub fn new(translation: impl Into<Vec2>, rotation: impl Into<Rotation2d>) -> Self {
+        Self {
+            translation: translation.into(),
+            rotation: rotation.into(),
+        }
+    }
+
+    #[inline]
+    /// Gets the transformation which undoes this one.
+    pub fn inverse(self) -> Self {
+        Self {
+            translation: (-self.translation).rotate(-self.rotation),
+            rotation: -self.rotation,
+        }
+    }
+
+    #[inline]
+    /// Composes two transformations, applying `self` followed by `other`.
+    pub fn then(self, other: Self) -> Self {
+        Pose2d::default() + self + other - Pose2d::default()
+    }
+}
@@ -0,0 +1,350 @@
+//! # Pose Recording and Ghost Comparison.
+//!
+//! A [`PoseRecorder`] saves the robot's pose to the SD card at a fixed period
+//! during a run, e.g. from a
+//! [`PoseEstimator`](crate::localization::PoseEstimator). The recording can be
+//! loaded as a [`PoseTrace`] and shown as a "ghost" by a [`GhostView`], which
+//! draws it on the brain screen next to the live pose, along with how far the
+//! robot has strayed from where the ghost was at the same point in the run.
+//! This makes it easy to see how consistent an autonomous routine is from one
+//! attempt to the next.
+//!
+//! The view shows the whole field, with the origin at its centre and the
+//! positive y axis pointing up the screen, matching the
+//! [GPS sensor](crate::gps).
+//!
+//! # Example
+//!
+//! ```
+//! // During a good run:
+//! let mut recorder = PoseRecorder::create("/usd/ghost.bin", Duration::from_millis(50))?;
+//! let mut l = Loop::new(recorder.period());
+//! loop {
+//!     recorder.record(&estimator.pose())?;
+//!     l.delay();
+//! }
+//!
+//! // During later runs:
+//! let ghost = GhostView::new(PoseTrace::load("/usd/ghost.bin")?, estimator.listen());
+//! ghost.restart(); // At the start of autonomous.
+//! ```
+
+use alloc::{
+    format,
+    sync::{Arc, Weak},
+    vec::Vec,
+};
+use core::time::Duration;
+
+use cstring_interop::with_cstring;
+
+use crate::{
+    bindings,
+    error::Error,
+    geometry::Pose2d,
+    replay::ReplayError,
+    rtos::{time_since_start, BroadcastListener, Instant, Loop, Mutex, Task},
+    screen::SCREEN_HEIGHT,
+    sd::{File, SdError},
+    units::{Millimeters, Radians},
+};
+
+const MAGIC: &[u8; 4] = b"VXPS";
+const HEADER_LEN: usize = 8;
+const FRAME_LEN: usize = 12;
+
+/// The length of a side of the field, in millimetres.
+const FIELD_SIZE: f64 = 3657.6;
+
+/// The length of a side of the field view, in pixels.
+const VIEW_SIZE: i16 = SCREEN_HEIGHT;
+
+/// The x-coordinate at which the deviation is printed, in pixels.
+const TEXT_X: i16 = VIEW_SIZE + 10;
+
+/// The interval at which the field view is updated.
+const UPDATE_PERIOD: Duration = Duration::from_millis(50);
+
+const FIELD_COLOR: u32 = 0x0040_4040;
+const GHOST_COLOR: u32 = 0x0080_8080;
+const LIVE_COLOR: u32 = 0x0000_FF00;
+const TEXT_COLOR: u32 = 0x00FF_FFFF;
+
+/// Records poses to a file on the SD card.
+///
+/// Poses should be recorded at the period given to [`PoseRecorder::create()`],
+/// e.g. using a [`Loop`].
+pub struct PoseRecorder {
+    file: File,
+    period: Duration,
+}
+
+impl PoseRecorder {
+    /// Creates a new recording at `path`, replacing any existing file, with
+    /// poses taken every `period`.
+    pub fn create(path: &str, period: Duration) -> Result<Self, SdError> {
+        let mut file = File::create(path)?;
+        let mut header = [0u8; HEADER_LEN];
+        header[..4].copy_from_slice(MAGIC);
+        header[4..].copy_from_slice(&(period.as_millis() as u32).to_le_bytes());
+        file.write_all(&header)?;
+        Ok(Self { file, period })
+    }
+
+    #[inline]
+    /// Gets the period at which poses should be recorded.
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Appends a pose to the recording.
+    pub fn record(&mut self, pose: &Pose2d) -> Result<(), SdError> {
+        self.file.write_all(&encode(pose))
+    }
+
+    #[inline]
+    /// Ensures that all recorded poses have been written to the SD card. This
+    /// also happens when the recorder is dropped.
+    pub fn flush(&mut self) -> Result<(), SdError> {
+        self.file.flush()
+    }
+}
+
+/// A recording of poses, loaded into memory.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PoseTrace {
+    period: Duration,
+    poses: Vec<Pose2d>,
+}
+
+impl PoseTrace {
+    /// Creates a trace from poses taken every `period`.
+    pub fn new(period: Duration, poses: Vec<Pose2d>) -> Self {
+        Self { period, poses }
+    }
+
+    /// Loads a recording made by a [`PoseRecorder`] from the SD card.
+    pub fn load(path: &str) -> Result<Self, ReplayError> {
+        let mut buf = Vec::new();
+        File::open(path)?.read_to_end(&mut buf)?;
+        if buf.len() < HEADER_LEN || &buf[..4] != MAGIC {
+            return Err(ReplayError::InvalidFormat);
+        }
+        let period = u32::from_le_bytes([buf[4], buf[5], buf[6], buf[7]]);
+        Ok(Self {
+            period: Duration::from_millis(period.into()),
+            poses: buf[HEADER_LEN..]
+                .chunks_exact(FRAME_LEN)
+                .map(decode)
+                .collect(),
+        })
+    }
+
+    #[inline]
+    /// Gets the period at which the poses were recorded.
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    #[inline]
+    /// Gets the recorded poses.
+    pub fn poses(&self) -> &[Pose2d] {
+        &self.poses
+    }
+
+    #[inline]
+    /// Gets the total length of the recording.
+    pub fn duration(&self) -> Duration {
+        self.period * self.poses.len() as u32
+    }
+
+    /// Gets the pose recorded at the given time since the start of the
+    /// recording, or the last pose if the recording has ended. Returns `None`
+    /// if the recording is empty.
+    pub fn pose_at(&self, elapsed: Duration) -> Option<Pose2d> {
+        let period = self.period.as_micros().max(1);
+        let index = (elapsed.as_micros() / period) as usize;
+        self.poses.get(index).or_else(|| self.poses.last()).copied()
+    }
+
+    /// Gets the largest distance between the poses of two recordings at the
+    /// same time since their starts, up to the end of the shorter recording.
+    pub fn max_deviation(&self, other: &Self) -> Millimeters {
+        let duration = self.duration().min(other.duration());
+        let mut elapsed = Duration::ZERO;
+        let mut max = Millimeters(0.0);
+        while elapsed < duration {
+            if let (Some(a), Some(b)) = (self.pose_at(elapsed), other.pose_at(elapsed)) {
+                max = max.max(a.position.distance(b.position));
+            }
+            elapsed += self.period.min(other.period).max(Duration::from_millis(1));
+        }
+        max
+    }
+}
+
+/// Draws a [`PoseTrace`] as a ghost on the brain screen next to the live pose,
+/// from a background task. See the [module-level documentation](self).
+///
+/// The task exits when this is dropped.
+pub struct GhostView(Arc<Mutex<GhostData>>);
+
+impl GhostView {
+    #[inline]
+    /// Creates a new ghost view, which draws `ghost` and the poses received
+    /// from `live`, and spawns its task. Panics on failure; see
+    /// [`GhostView::try_new()`].
+    pub fn new(ghost: PoseTrace, live: BroadcastListener<Pose2d>) -> Self {
+        Self::try_new(ghost, live)
+            .unwrap_or_else(|err| panic!("failed to create ghost view: {:?}", err))
+    }
+
+    /// Creates a new ghost view, which draws `ghost` and the poses received
+    /// from `live`, and spawns its task.
+    pub fn try_new(ghost: PoseTrace, mut live: BroadcastListener<Pose2d>) -> Result<Self, Error> {
+        let data = Arc::new(Mutex::try_new(GhostData {
+            start: time_since_start(),
+            deviation: Millimeters(0.0),
+            max_deviation: Millimeters(0.0),
+            redraw: true,
+        })?);
+        let weak: Weak<Mutex<GhostData>> = Arc::downgrade(&data);
+        Task::spawn_ext(
+            "ghost view",
+            bindings::TASK_PRIORITY_MIN,
+            Task::DEFAULT_STACK_DEPTH,
+            move || {
+                let mut l = Loop::new(UPDATE_PERIOD);
+                let mut last: Option<(i16, i16)> = None;
+                while let Some(data) = weak.upgrade() {
+                    let (start, redraw) = {
+                        let mut data = data.lock();
+                        (data.start, core::mem::take(&mut data.redraw))
+                    };
+                    if redraw {
+                        draw_ghost(&ghost);
+                        last = None;
+                    }
+                    if let Some(pose) = live.next_value() {
+                        let point = to_screen(&pose);
+                        draw_line(last.unwrap_or(point), point, LIVE_COLOR);
+                        last = Some(point);
+                        if let Some(expected) = ghost.pose_at(time_since_start() - start) {
+                            let mut data = data.lock();
+                            data.deviation = pose.position.distance(expected.position);
+                            data.max_deviation = data.max_deviation.max(data.deviation);
+                            draw_deviation(data.deviation, data.max_deviation);
+                        }
+                    }
+                    drop(data);
+                    l.delay();
+                }
+            },
+        )?;
+        Ok(Self(data))
+    }
+
+    /// Restarts the comparison, e.g. at the start of autonomous: clears the
+    /// live trace and the deviation, and compares subsequent poses with the
+    /// ghost from its start.
+    pub fn restart(&self) {
+        let mut data = self.0.lock();
+        data.start = time_since_start();
+        data.deviation = Millimeters(0.0);
+        data.max_deviation = Millimeters(0.0);
+        data.redraw = true;
+    }
+
+    #[inline]
+    /// Gets the distance between the latest live pose and the ghost's pose at
+    /// the same point in the run.
+    pub fn deviation(&self) -> Millimeters {
+        self.0.lock().deviation
+    }
+
+    #[inline]
+    /// Gets the largest deviation since the comparison was started.
+    pub fn max_deviation(&self) -> Millimeters {
+        self.0.lock().max_deviation
+    }
+}
+
+struct GhostData {
+    start: Instant,
+    deviation: Millimeters,
+    max_deviation: Millimeters,
+    /// Whether the view must be cleared and the ghost drawn again.
+    redraw: bool,
+}
+
+/// Clears the view and draws the field outline and the ghost's path.
+fn draw_ghost(ghost: &PoseTrace) {
+    unsafe { bindings::screen_erase_rect(0, 0, VIEW_SIZE - 1, VIEW_SIZE - 1) };
+    let max = VIEW_SIZE - 1;
+    let corners = [(0, 0), (max, 0), (max, max), (0, max), (0, 0)];
+    for pair in corners.windows(2) {
+        draw_line(pair[0], pair[1], FIELD_COLOR);
+    }
+    let points: Vec<(i16, i16)> = ghost.poses().iter().map(to_screen).collect();
+    for pair in points.windows(2) {
+        draw_line(pair[0], pair[1], GHOST_COLOR);
+    }
+}
+
+fn draw_deviation(deviation: Millimeters, max_deviation: Millimeters) {
+    let lines = [
+        format!("dev: {:>5.0} mm", deviation.0),
+        format!("max: {:>5.0} mm", max_deviation.0),
+    ];
+    unsafe {
+        let pen = bindings::screen_get_pen();
+        bindings::screen_set_pen(TEXT_COLOR);
+        for (i, line) in lines.iter().enumerate() {
+            with_cstring(line.clone(), |line| {
+                bindings::screen_print_at(
+                    bindings::text_format_e_t_E_TEXT_SMALL,
+                    TEXT_X,
+                    10 + 15 * i as i16,
+                    b"%s\0".as_ptr().cast(),
+                    line.into_raw(),
+                );
+            });
+        }
+        bindings::screen_set_pen(pen);
+    }
+}
+
+fn draw_line(from: (i16, i16), to: (i16, i16), color: u32) {
+    unsafe {
+        let pen = bindings::screen_get_pen();
+        bindings::screen_set_pen(color);
+        bindings::screen_draw_line(from.0, from.1, to.0, to.1);
+        bindings::screen_set_pen(pen);
+    }
+}
+
+/// Converts a position on the field to a point in the view.
+fn to_screen(pose: &Pose2d) -> (i16, i16) {
+    let scale = VIEW_SIZE as f64 / FIELD_SIZE;
+    let max = (VIEW_SIZE - 1) as f64;
+    let x = ((pose.position.x.0 + FIELD_SIZE / 2.0) * scale).clamp(0.0, max);
+    let y = ((FIELD_SIZE / 2.0 - pose.position.y.0) * scale).clamp(0.0, max);
+    (x as i16, y as i16)
+}
+
+fn encode(pose: &Pose2d) -> [u8; FRAME_LEN] {
+    let heading: Radians = pose.heading.radians();
+    let mut frame = [0u8; FRAME_LEN];
+    frame[..4].copy_from_slice(&(pose.position.x.0 as f32).to_le_bytes());
+    frame[4..8].copy_from_slice(&(pose.position.y.0 as f32).to_le_bytes());
+    frame[8..].copy_from_slice(&(heading.0 as f32).to_le_bytes());
+    frame
+}
+
+fn decode(frame: &[u8]) -> Pose2d {
+    let read = |i: usize| f32::from_le_bytes([frame[i], frame[i + 1], frame[i + 2], frame[i + 3]]);
+    Pose2d::new(
+        (Millimeters(read(0).into()), Millimeters(read(4).into())),
+        Radians(read(8).into()),
+    )
+}
@@ -0,0 +1,216 @@
+//! # GPS Sensor API.
+//!
+//! Positions are reported in the same frame as the rest of the crate (see
+//! [`geometry`](crate::geometry)), with the origin at the centre of the field,
+//! and headings are clockwise from the positive y axis.
+
+use core::time::Duration;
+
+use crate::{
+    bindings, diagnostics,
+    error::{DeviceError, DevicePort},
+    geometry::{Pose2d, Rotation2d, Vec2},
+    rtos::DataSource,
+    units::{Degrees, Millimeters},
+};
+
+/// Millimetres per metre, the unit used by the sensor.
+const MILLIMETERS_PER_METER: f64 = 1000.0;
+
+/// A struct which represents a V5 smart port configured as a GPS sensor.
+pub struct GpsSensor {
+    port: u8,
+}
+
+impl GpsSensor {
+    /// Constructs a new GPS sensor.
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it allows the user to create multiple
+    /// mutable references to the same GPS sensor. You likely want to implement
+    /// [`Robot::new()`](crate::robot::Robot::new()) instead.
+    pub unsafe fn new(port: u8) -> GpsSensor {
+        GpsSensor { port }
+    }
+
+    /// Sets the initial pose of the robot, and the offset of the sensor from
+    /// the centre of rotation of the robot.
+    pub fn initialize(
+        &mut self,
+        initial: Pose2d,
+        offset: impl Into<Vec2>,
+    ) -> Result<(), GpsSensorError> {
+        let offset = offset.into();
+        match unsafe {
+            bindings::gps_initialize_full(
+                self.port,
+                to_meters(initial.position.x),
+                to_meters(initial.position.y),
+                initial.heading.degrees().0,
+                to_meters(offset.x),
+                to_meters(offset.y),
+            )
+        } {
+            bindings::PROS_ERR_ => Err(self.error()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Sets the offset of the sensor from the centre of rotation of the robot.
+    pub fn set_offset(&mut self, offset: impl Into<Vec2>) -> Result<(), GpsSensorError> {
+        let offset = offset.into();
+        match unsafe {
+            bindings::gps_set_offset(self.port, to_meters(offset.x), to_meters(offset.y))
+        } {
+            bindings::PROS_ERR_ => Err(self.error()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Overrides the pose of the robot as estimated by the sensor.
+    pub fn set_pose(&mut self, pose: Pose2d) -> Result<(), GpsSensorError> {
+        match unsafe {
+            bindings::gps_set_position(
+                self.port,
+                to_meters(pose.position.x),
+                to_meters(pose.position.y),
+                pose.heading.degrees().0,
+            )
+        } {
+            bindings::PROS_ERR_ => Err(self.error()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Sets the interval at which the sensor sends new data, which is rounded
+    /// down to a multiple of 5ms. The minimum is 5ms, and the default is 10ms.
+    pub fn set_data_rate(&mut self, rate: Duration) -> Result<(), GpsSensorError> {
+        match unsafe { bindings::gps_set_data_rate(self.port, rate.as_millis() as u32) } {
+            bindings::PROS_ERR_ => Err(self.error()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Gets the root mean square error of the sensor's position estimate.
+    ///
+    /// This grows when the sensor cannot see the field code, so it indicates
+    /// how far the reported position can be trusted.
+    pub fn get_error(&self) -> Result<Millimeters, GpsSensorError> {
+        match unsafe { bindings::gps_get_error(self.port) } {
+            x if x == bindings::PROS_ERR_F_ => Err(self.error()),
+            x => Ok(Millimeters(x * MILLIMETERS_PER_METER)),
+        }
+    }
+
+    /// Gets the pose of the robot as estimated by the sensor.
+    pub fn get_pose(&self) -> Result<Pose2d, GpsSensorError> {
+        Ok(self.get_status()?.pose)
+    }
+
+    /// Gets the pose, pitch and roll of the robot as estimated by the sensor.
+    pub fn get_status(&self) -> Result<GpsStatus, GpsSensorError> {
+        let status = unsafe { bindings::gps_get_status(self.port) };
+        let (x, y, pitch, roll) = (status.x, status.y, status.pitch, status.roll);
+        if x == bindings::PROS_ERR_F_ {
+            return Err(self.error());
+        }
+        Ok(GpsStatus {
+            pose: Pose2d::new(
+                Vec2::new(
+                    Millimeters(x * MILLIMETERS_PER_METER),
+                    Millimeters(y * MILLIMETERS_PER_METER),
+                ),
+                self.get_heading()?,
+            ),
+            pitch: Degrees(pitch),
+            roll: Degrees(roll),
+        })
+    }
+
+    /// Gets the heading of the robot as estimated by the sensor, bounded by
+    /// [0, 360).
+    pub fn get_heading(&self) -> Result<Rotation2d, GpsSensorError> {
+        match unsafe { bindings::gps_get_heading(self.port) } {
+            x if x == bindings::PROS_ERR_F_ => Err(self.error()),
+            x => Ok(Rotation2d::new(Degrees(x))),
+        }
+    }
+
+    /// Gets the total number of degrees the robot has turned, as measured by
+    /// the sensor's internal gyroscope.
+    ///
+    /// This value is theoretically unbounded. Clockwise rotations are
+    /// represented with positive degree values, while counterclockwise
+    /// rotations are represented with negative ones.
+    pub fn get_rotation(&self) -> Result<Degrees, GpsSensorError> {
+        match unsafe { bindings::gps_get_rotation(self.port) } {
+            x if x == bindings::PROS_ERR_F_ => Err(self.error()),
+            x => Ok(Degrees(x)),
+        }
+    }
+
+    /// Sets the current reading of the rotation to the target value.
+    pub fn set_rotation(&mut self, rotation: impl Into<Degrees>) -> Result<(), GpsSensorError> {
+        match unsafe { bindings::gps_set_rotation(self.port, rotation.into().0) } {
+            bindings::PROS_ERR_ => Err(self.error()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Resets the current reading of the rotation to zero.
+    pub fn reset_rotation(&mut self) -> Result<(), GpsSensorError> {
+        match unsafe { bindings::gps_tare_rotation(self.port) } {
+            bindings::PROS_ERR_ => Err(self.error()),
+            _ => Ok(()),
+        }
+    }
+
+    fn error(&self) -> DeviceError {
+        diagnostics::record_error(DeviceError::from_errno(
+            DevicePort::Smart(self.port),
+            "a GPS sensor",
+        ))
+    }
+}
+
+impl DataSource for GpsSensor {
+    type Data = GpsData;
+
+    type Error = GpsSensorError;
+
+    fn read(&self) -> Result<Self::Data, Self::Error> {
+        Ok(GpsData {
+            status: self.get_status()?,
+            error: self.get_error()?,
+        })
+    }
+}
+
+/// Represents the pose and orientation reported by a GPS sensor.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GpsStatus {
+    /// The pose of the robot.
+    pub pose: Pose2d,
+    /// The pitch of the robot.
+    pub pitch: Degrees,
+    /// The roll of the robot.
+    pub roll: Degrees,
+}
+
+/// Represents the data that can be read from a GPS sensor.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GpsData {
+    /// The pose and orientation of the robot.
+    pub status: GpsStatus,
+    /// The error of the position estimate; see [`GpsSensor::get_error()`].
+    pub error: Millimeters,
+}
+
+/// Represents possible errors for GPS sensor operations. This is an alias of
+/// [`DeviceError`].
+pub type GpsSensorError = DeviceError;
+
+fn to_meters(length: Millimeters) -> f64 {
+    length.0 / MILLIMETERS_PER_METER
+}
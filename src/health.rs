@@ -0,0 +1,306 @@
+//! # Device Health Monitoring.
+//!
+//! A [`HealthMonitor`] periodically checks a set of registered devices for
+//! disconnection, the wrong type of device being plugged in, and overheating
+//! or over-current motors, and publishes the results as a [`HealthReport`].
+//! Devices are registered by port number, so the monitor does not need to own
+//! them.
+//!
+//! # Example
+//!
+//! ```
+//! let monitor = HealthMonitor::new(Duration::from_millis(500))
+//!     .motor("left drive", 1)
+//!     .motor("right drive", 10)
+//!     .device("imu", 11, DeviceType::Imu)
+//!     .check("battery", || {
+//!         (Battery::get_capacity().unwrap_or(0.0) < 20.0).then(|| "low battery".into())
+//!     })
+//!     .rumble(".-.")
+//!     .start()?;
+//!
+//! let mut health = monitor.listen();
+//! ```
+
+use alloc::{
+    boxed::Box,
+    string::{String, ToString},
+    sync::Arc,
+    vec::Vec,
+};
+use core::{
+    fmt::{self, Display, Formatter},
+    time::Duration,
+};
+
+use cstring_interop::with_cstring;
+
+use crate::{
+    bindings,
+    error::Error,
+    rtos::{Broadcast, BroadcastListener, Loop, Task},
+    smart_port::{smart_port_type, DeviceType},
+};
+
+/// The default motor temperature above which a warning is raised, in degrees
+/// Celsius. Motors begin to limit their power at 55°C.
+const DEFAULT_TEMPERATURE_LIMIT: f64 = 50.0;
+
+/// The LLEMU line used to show health alerts on the brain screen.
+const SCREEN_LINE: i16 = 7;
+
+type Check = Box<dyn FnMut() -> Option<String> + Send>;
+
+enum Device {
+    Port(u8, DeviceType),
+    Motor(u8),
+    Check(Check),
+}
+
+/// Describes a problem found by a [`HealthMonitor`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum HealthProblem {
+    /// Nothing is plugged into the device's port.
+    Disconnected,
+    /// A different type of device is plugged into the device's port.
+    WrongDevice(DeviceType),
+    /// The motor's temperature, in degrees Celsius, is above the limit.
+    OverTemperature(f64),
+    /// The motor is drawing more than its current limit.
+    OverCurrent,
+    /// A custom check failed, with the given description.
+    Failed(String),
+}
+
+/// A problem with a particular device.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HealthIssue {
+    /// The name of the device, as registered with the [`HealthMonitor`].
+    pub device: String,
+    /// The problem found.
+    pub problem: HealthProblem,
+}
+
+impl Display for HealthProblem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            HealthProblem::Disconnected => f.write_str("disconnected"),
+            HealthProblem::WrongDevice(t) => write!(f, "wrong device ({:?})", t),
+            HealthProblem::OverTemperature(t) => write!(f, "{:.0}°C", t),
+            HealthProblem::OverCurrent => f.write_str("over current"),
+            HealthProblem::Failed(description) => f.write_str(description),
+        }
+    }
+}
+
+impl Display for HealthIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.device, self.problem)
+    }
+}
+
+/// The result of checking every device registered with a [`HealthMonitor`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HealthReport {
+    /// The problems found, in the order in which the devices were registered.
+    pub issues: Vec<HealthIssue>,
+}
+
+impl HealthReport {
+    #[inline]
+    /// Checks whether no problems were found.
+    pub fn is_healthy(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Describes the devices to be checked by a health monitor. Call
+/// [`HealthMonitor::start()`] to begin monitoring.
+pub struct HealthMonitor {
+    period: Duration,
+    temperature_limit: f64,
+    devices: Vec<(String, Device)>,
+    rumble: Option<String>,
+    screen: bool,
+}
+
+impl HealthMonitor {
+    #[inline]
+    /// Creates a new monitor with no devices, which checks every `period`.
+    pub fn new(period: Duration) -> Self {
+        Self {
+            period,
+            temperature_limit: DEFAULT_TEMPERATURE_LIMIT,
+            devices: Vec::new(),
+            rumble: None,
+            screen: false,
+        }
+    }
+
+    /// Registers a device which should be plugged into the given port.
+    pub fn device(mut self, name: &str, port: u8, device_type: DeviceType) -> Self {
+        self.devices
+            .push((name.into(), Device::Port(port, device_type)));
+        self
+    }
+
+    /// Registers a motor on the given port, which is also checked for
+    /// overheating and excessive current draw.
+    pub fn motor(mut self, name: &str, port: u8) -> Self {
+        self.devices.push((name.into(), Device::Motor(port)));
+        self
+    }
+
+    /// Registers a custom check, which returns a description of the problem
+    /// if one is found.
+    pub fn check(mut self, name: &str, f: impl FnMut() -> Option<String> + Send + 'static) -> Self {
+        self.devices.push((name.into(), Device::Check(Box::new(f))));
+        self
+    }
+
+    #[inline]
+    /// Sets the motor temperature above which a problem is reported, in
+    /// degrees Celsius.
+    pub fn temperature_limit(mut self, limit: f64) -> Self {
+        self.temperature_limit = limit;
+        self
+    }
+
+    #[inline]
+    /// Rumbles the master controller with the given pattern whenever a new
+    /// problem is found. See
+    /// [`Screen::rumble()`](crate::controller::Screen::rumble())
+    /// for the pattern format.
+    pub fn rumble(mut self, pattern: &str) -> Self {
+        self.rumble = Some(pattern.into());
+        self
+    }
+
+    #[inline]
+    /// Shows the first problem found on the bottom line of the brain screen
+    /// console.
+    pub fn screen(mut self) -> Self {
+        self.screen = true;
+        self
+    }
+
+    /// Spawns the task which checks the registered devices.
+    pub fn start(mut self) -> Result<RunningHealthMonitor, Error> {
+        let broadcast = Arc::new(Broadcast::try_new(HealthReport::default())?);
+        let weak = Arc::downgrade(&broadcast);
+        if self.screen {
+            unsafe {
+                if !bindings::lcd_is_initialized() {
+                    bindings::lcd_initialize();
+                }
+            }
+        }
+        Task::spawn_ext(
+            "health monitor",
+            bindings::TASK_PRIORITY_MIN,
+            Task::DEFAULT_STACK_DEPTH,
+            move || {
+                let mut l = Loop::new(self.period);
+                let mut last = HealthReport::default();
+                while let Some(broadcast) = weak.upgrade() {
+                    let report = self.check_all();
+                    if report
+                        .issues
+                        .iter()
+                        .any(|issue| !last.issues.contains(issue))
+                    {
+                        self.alert(&report);
+                    }
+                    if self.screen && report.is_healthy() && !last.is_healthy() {
+                        set_screen_line("");
+                    }
+                    last = report.clone();
+                    broadcast.publish(report);
+                    drop(broadcast);
+                    l.delay();
+                }
+            },
+        )?;
+        Ok(RunningHealthMonitor(broadcast))
+    }
+
+    fn check_all(&mut self) -> HealthReport {
+        let temperature_limit = self.temperature_limit;
+        let issues = self
+            .devices
+            .iter_mut()
+            .filter_map(|(name, device)| {
+                let problem = match device {
+                    Device::Port(port, expected) => check_port(*port, *expected),
+                    Device::Motor(port) => check_port(*port, DeviceType::Motor)
+                        .or_else(|| check_motor(*port, temperature_limit)),
+                    Device::Check(f) => f().map(HealthProblem::Failed),
+                }?;
+                Some(HealthIssue {
+                    device: name.clone(),
+                    problem,
+                })
+            })
+            .collect();
+        HealthReport { issues }
+    }
+
+    fn alert(&self, report: &HealthReport) {
+        if let Some(pattern) = &self.rumble {
+            with_cstring(pattern.clone(), |pattern| unsafe {
+                bindings::controller_rumble(
+                    bindings::controller_id_e_t_E_CONTROLLER_MASTER,
+                    pattern.into_raw(),
+                );
+            });
+        }
+        if self.screen {
+            if let Some(issue) = report.issues.first() {
+                set_screen_line(&issue.to_string());
+            }
+        }
+    }
+}
+
+/// A [`HealthMonitor`] which is checking devices in the background. Monitoring
+/// stops when this is dropped.
+pub struct RunningHealthMonitor(Arc<Broadcast<HealthReport>>);
+
+impl RunningHealthMonitor {
+    #[inline]
+    /// Gets the most recent health report.
+    pub fn report(&self) -> HealthReport {
+        self.0.value()
+    }
+
+    #[inline]
+    /// Creates a listener which receives each new health report.
+    pub fn listen(&self) -> BroadcastListener<HealthReport> {
+        self.0.listen()
+    }
+}
+
+pub(crate) fn check_port(port: u8, expected: DeviceType) -> Option<HealthProblem> {
+    match unsafe { smart_port_type(port) } {
+        t if t == expected => None,
+        DeviceType::None => Some(HealthProblem::Disconnected),
+        t => Some(HealthProblem::WrongDevice(t)),
+    }
+}
+
+fn check_motor(port: u8, temperature_limit: f64) -> Option<HealthProblem> {
+    let temperature = unsafe { bindings::motor_get_temperature(port) };
+    if temperature != bindings::PROS_ERR_F_ && temperature > temperature_limit {
+        Some(HealthProblem::OverTemperature(temperature))
+    } else if unsafe { bindings::motor_is_over_current(port) } == 1 {
+        Some(HealthProblem::OverCurrent)
+    } else {
+        None
+    }
+}
+
+fn set_screen_line(text: &str) {
+    with_cstring(text.into(), |text| unsafe {
+        bindings::lcd_set_text(SCREEN_LINE, text.into_raw());
+    });
+}
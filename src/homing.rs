@@ -0,0 +1,229 @@
+//! # Homing.
+//!
+//! Most mechanisms with a limited range of travel need to be zeroed at startup,
+//! by driving them slowly against one end of their travel until a limit switch
+//! is pressed or they stall against a hard stop, which shows up as a spike in
+//! the motor's current draw. [`home()`] does this for any [`MotorLike`],
+//! stopping the motor and zeroing its position once the [`HomingStop`]
+//! condition is met; [`home_with_sensor()`] also zeroes a separate
+//! [`RotarySensor`], e.g. a rotation sensor on the output shaft.
+//!
+//! Both return a [`Selectable`] which occurs once homing has finished, so that
+//! homing can be bounded with a timeout or run alongside other startup tasks.
+//! The motor is stopped if the event is dropped before it occurs.
+//!
+//! # Example
+//!
+//! ```
+//! // Drive the lift down until it stalls against the hard stop.
+//! let config = HomingConfig::new();
+//! select! {
+//!     result = home(&mut lift_motors, HomingStop::Current(1500), config) => result?,
+//!     _ = ctx.done() => {},
+//! }
+//! ```
+
+use core::time::Duration;
+
+use crate::{
+    adi::AdiDigitalInput,
+    device::{MotorLike, RotarySensor},
+    error::{DeviceError, Error},
+    rtos::{time_since_start, GenericSleep, Instant, Selectable},
+};
+
+/// Describes when a motor being homed has reached the end of its travel.
+pub enum HomingStop {
+    /// The end of travel is reached when the limit switch is pressed.
+    Switch(AdiDigitalInput),
+    /// The end of travel is reached when the current drawn by the motor rises
+    /// to the given threshold in milliamperes, i.e. it has stalled against a
+    /// hard stop.
+    Current(i32),
+}
+
+/// Configures [`home()`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct HomingConfig {
+    /// The voltage at which the motor is driven towards the end of its travel
+    /// in millivolts. The sign of the voltage gives the direction.
+    pub voltage: i32,
+    /// How long the current draw is ignored after the motor starts, so that
+    /// the spike as it accelerates is not mistaken for a stall.
+    pub current_delay: Duration,
+    /// The maximum time to spend homing, if any.
+    pub timeout: Option<Duration>,
+    /// The interval at which the stop condition is checked.
+    pub period: Duration,
+}
+
+impl HomingConfig {
+    /// Creates a new configuration with default values, which drives the motor
+    /// backwards at 3V, with a timeout of 5 seconds.
+    pub fn new() -> Self {
+        Self {
+            voltage: -3000,
+            current_delay: Duration::from_millis(250),
+            timeout: Some(Duration::from_secs(5)),
+            period: Duration::from_millis(10),
+        }
+    }
+}
+
+impl Default for HomingConfig {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Represents possible errors for homing.
+#[derive(Debug)]
+pub enum HomingError {
+    /// A motor or sensor operation failed.
+    Device(DeviceError),
+    /// The end of travel was not reached before the timeout.
+    Timeout,
+}
+
+impl From<DeviceError> for HomingError {
+    fn from(err: DeviceError) -> Self {
+        Self::Device(err)
+    }
+}
+
+impl From<HomingError> for Error {
+    fn from(err: HomingError) -> Self {
+        match err {
+            HomingError::Device(err) => err.into(),
+            HomingError::Timeout => Error::Custom("homing timed out".into()),
+        }
+    }
+}
+
+/// A [`Selectable`] event which drives `motor` towards the end of its travel
+/// until `stop` is met, then stops it and sets its zero position. See the
+/// [module-level documentation](self).
+pub fn home<M: MotorLike>(
+    motor: M,
+    stop: HomingStop,
+    config: HomingConfig,
+) -> impl Selectable<Result<(), HomingError>> {
+    HomingSelect::new(motor, stop, config, || Ok(()))
+}
+
+/// Like [`home()`], but also sets the zero position of `sensor` once the end
+/// of travel is reached.
+pub fn home_with_sensor<M: MotorLike, S: RotarySensor>(
+    motor: M,
+    mut sensor: S,
+    stop: HomingStop,
+    config: HomingConfig,
+) -> impl Selectable<Result<(), HomingError>> {
+    HomingSelect::new(motor, stop, config, move || sensor.reset_position())
+}
+
+struct HomingSelect<M: MotorLike, F> {
+    motor: M,
+    stop: HomingStop,
+    config: HomingConfig,
+    reset: F,
+    /// When the motor was started, or `None` if it has not been started yet.
+    start: Option<Instant>,
+    next: Instant,
+    done: bool,
+}
+
+impl<M: MotorLike, F: FnMut() -> Result<(), DeviceError>> HomingSelect<M, F> {
+    fn new(motor: M, stop: HomingStop, config: HomingConfig, reset: F) -> Self {
+        Self {
+            motor,
+            stop,
+            config,
+            reset,
+            start: None,
+            next: time_since_start(),
+            done: false,
+        }
+    }
+
+    /// Checks the stop condition, starting the motor on the first call.
+    /// Returns `Some` once homing has finished.
+    fn step(&mut self) -> Option<Result<(), HomingError>> {
+        let now = time_since_start();
+        let start = match self.start {
+            Some(start) => start,
+            None => {
+                if let Err(err) = self.motor.move_voltage(self.config.voltage) {
+                    return Some(Err(err.into()));
+                }
+                self.start = Some(now);
+                now
+            }
+        };
+        let reached = match &self.stop {
+            HomingStop::Switch(switch) => switch.read(),
+            HomingStop::Current(threshold) => {
+                if now - start < self.config.current_delay {
+                    Ok(false)
+                } else {
+                    self.motor
+                        .current_draw()
+                        .map(|current| current.abs() >= *threshold)
+                }
+            }
+        };
+        match reached {
+            Ok(true) => Some(self.finish()),
+            Ok(false) if matches!(self.config.timeout, Some(timeout) if now - start >= timeout) => {
+                self.motor.move_voltage(0).ok();
+                Some(Err(HomingError::Timeout))
+            }
+            Ok(false) => None,
+            Err(err) => {
+                self.motor.move_voltage(0).ok();
+                Some(Err(err.into()))
+            }
+        }
+    }
+
+    fn finish(&mut self) -> Result<(), HomingError> {
+        self.motor.move_voltage(0)?;
+        self.motor.tare_position()?;
+        (self.reset)()?;
+        Ok(())
+    }
+}
+
+impl<M: MotorLike, F: FnMut() -> Result<(), DeviceError>> Selectable<Result<(), HomingError>>
+    for HomingSelect<M, F>
+{
+    fn poll(mut self) -> Result<Result<(), HomingError>, Self> {
+        if time_since_start() < self.next {
+            return Err(self);
+        }
+        match self.step() {
+            Some(result) => {
+                self.done = true;
+                Ok(result)
+            }
+            None => {
+                self.next = time_since_start() + self.config.period;
+                Err(self)
+            }
+        }
+    }
+
+    fn sleep(&self) -> GenericSleep {
+        GenericSleep::Timestamp(self.next)
+    }
+}
+
+impl<M: MotorLike, F> Drop for HomingSelect<M, F> {
+    fn drop(&mut self) {
+        // Stop the motor if homing was abandoned part way through.
+        if self.start.is_some() && !self.done {
+            self.motor.move_voltage(0).ok();
+        }
+    }
+}
@@ -1,18 +1,36 @@
 //! # Inertial Sensor API.
-
-use core::fmt;
-
-use alloc::format;
+//!
+//! A sensor which is not mounted upright can be given its
+//! [mounting orientation](InertialSensor::set_orientation()), in which case
+//! all of its readings are converted to the frame of the robot.
+//!
+//! # Example
+//!
+//! ```
+//! // Mounted on its side, with its x-axis pointing up and its y-axis pointing
+//! // backwards.
+//! imu.set_orientation(ImuOrientation::new(SensorAxis::NegY, SensorAxis::PosX));
+//! imu.set_data_rate(Duration::from_millis(5))?;
+//! let heading = imu.get_heading()?;
+//! ```
+
+use core::{fmt, time::Duration};
+
+#[cfg(not(feature = "sim"))]
+use num_traits::Float;
 
 use crate::{
-    bindings,
-    error::{get_errno, Error},
-    rtos::DataSource,
+    bindings, diagnostics,
+    error::{get_errno, DeviceError, DeviceErrorKind, DevicePort},
+    rtos::{DataSource, Mutex},
+    units::Degrees,
 };
 
 /// A struct which represents a V5 smart port configured as a inertial sensor.
 pub struct InertialSensor {
     port: u8,
+    orientation: ImuOrientation,
+    frame: Mutex<RobotFrame>,
 }
 
 impl InertialSensor {
@@ -24,7 +42,11 @@ impl InertialSensor {
     /// mutable references to the same inertial sensor. You likely want to
     /// implement [`Robot::new()`](crate::robot::Robot::new()) instead.
     pub unsafe fn new(port: u8) -> InertialSensor {
-        InertialSensor { port }
+        InertialSensor {
+            port,
+            orientation: ImuOrientation::UPRIGHT,
+            frame: Mutex::new(RobotFrame::default()),
+        }
     }
 
     /// Calibrate IMU.
@@ -33,11 +55,46 @@ impl InertialSensor {
     /// This takes approximately 2 seconds, and is a non-blocking operation.
     pub fn calibrate(&mut self) -> Result<(), InertialSensorError> {
         match unsafe { bindings::imu_reset(self.port) } {
-            bindings::PROS_ERR_ => Err(InertialSensorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
+            _ => {
+                *self.frame.lock() = RobotFrame::default();
+                Ok(())
+            }
+        }
+    }
+
+    /// Sets the interval at which the sensor sends new data, which is rounded
+    /// down to a multiple of 5ms. The minimum is 5ms, and the default is 10ms.
+    ///
+    /// Data is only copied to the brain every 10ms, so a shorter interval does
+    /// not give more frequent readings, but does give more recent ones.
+    pub fn set_data_rate(&mut self, rate: Duration) -> Result<(), InertialSensorError> {
+        match unsafe { bindings::imu_set_data_rate(self.port, rate.as_millis() as u32) } {
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
 
+    #[inline]
+    /// Gets the mounting orientation of the sensor.
+    pub fn orientation(&self) -> ImuOrientation {
+        self.orientation
+    }
+
+    /// Sets the mounting orientation of the sensor. Unless it is
+    /// [`ImuOrientation::UPRIGHT`], all readings are then converted to the
+    /// frame of the robot, and tares and set values are applied in software
+    /// rather than by the sensor. Offsets previously applied in software are
+    /// cleared.
+    ///
+    /// In that case, the robot's rotation is tracked by unwrapping its yaw, so
+    /// the rotation or heading must be read at least once in every half turn
+    /// of the robot for [`InertialSensor::get_rotation()`] to be correct.
+    pub fn set_orientation(&mut self, orientation: ImuOrientation) {
+        self.orientation = orientation;
+        *self.frame.lock() = RobotFrame::default();
+    }
+
     /// Get the total number of degrees the Inertial Sensor has spun about the
     /// z-axis.
     ///
@@ -45,8 +102,13 @@ impl InertialSensor {
     /// represented with positive degree values, while counterclockwise
     /// rotations are represented with negative ones.
     pub fn get_rotation(&self) -> Result<f64, InertialSensorError> {
+        if !self.orientation.is_upright() {
+            return self
+                .robot_rotation()
+                .map(|(rotation, frame)| rotation - frame.rotation_offset);
+        }
         match unsafe { bindings::imu_get_rotation(self.port) } {
-            x if x == bindings::PROS_ERR_F_ => Err(InertialSensorError::from_errno()),
+            x if x == bindings::PROS_ERR_F_ => Err(self.error()),
             x => Ok(x),
         }
     }
@@ -58,16 +120,33 @@ impl InertialSensor {
     /// with positive degree values, while counterclockwise rotations are
     /// represented with negative ones.
     pub fn get_heading(&self) -> Result<f64, InertialSensorError> {
+        if !self.orientation.is_upright() {
+            return self
+                .robot_rotation()
+                .map(|(rotation, frame)| wrap_heading(rotation - frame.heading_offset));
+        }
         match unsafe { bindings::imu_get_heading(self.port) } {
-            x if x == bindings::PROS_ERR_F_ => Err(InertialSensorError::from_errno()),
+            x if x == bindings::PROS_ERR_F_ => Err(self.error()),
             x => Ok(x),
         }
     }
 
+    /// Computes the shortest turn from the Inertial Sensor’s current heading to
+    /// a target heading in degrees.
+    ///
+    /// The result is bounded by (-180,180]; a positive value means the target
+    /// is clockwise of the current heading.
+    pub fn angle_to_heading(&self, target: f64) -> Result<f64, InertialSensorError> {
+        Ok(Degrees(self.get_heading()?).angle_to(Degrees(target)).0)
+    }
+
     /// Get a quaternion representing the Inertial Sensor’s orientation.
     pub fn get_quaternion(&self) -> Result<InertialSensorQuaternion, InertialSensorError> {
+        if !self.orientation.is_upright() {
+            return self.get_euler().map(InertialSensorQuaternion::from);
+        }
         match unsafe { bindings::imu_get_quaternion(self.port) } {
-            x if x.x == bindings::PROS_ERR_F_ => Err(InertialSensorError::from_errno()),
+            x if x.x == bindings::PROS_ERR_F_ => Err(self.error()),
             x => Ok(InertialSensorQuaternion {
                 x: x.x,
                 y: x.y,
@@ -79,8 +158,17 @@ impl InertialSensor {
 
     /// Get the Euler angles representing the Inertial Sensor’s orientation.
     pub fn get_euler(&self) -> Result<InertialSensorEuler, InertialSensorError> {
+        if !self.orientation.is_upright() {
+            let (rotation, frame) = self.robot_rotation()?;
+            let (pitch, roll) = self.robot_tilt()?;
+            return Ok(InertialSensorEuler {
+                pitch: wrap_degrees(pitch - frame.pitch_offset),
+                roll: wrap_degrees(roll - frame.roll_offset),
+                yaw: wrap_degrees(rotation - frame.yaw_offset),
+            });
+        }
         match unsafe { bindings::imu_get_euler(self.port) } {
-            x if x.pitch == bindings::PROS_ERR_F_ => Err(InertialSensorError::from_errno()),
+            x if x.pitch == bindings::PROS_ERR_F_ => Err(self.error()),
             x => Ok(InertialSensorEuler {
                 pitch: x.pitch,
                 roll: x.roll,
@@ -91,24 +179,33 @@ impl InertialSensor {
 
     /// Get the Inertial Sensor’s pitch angle bounded by (-180,180).
     pub fn get_pitch(&self) -> Result<f64, InertialSensorError> {
+        if !self.orientation.is_upright() {
+            return self.get_euler().map(|euler| euler.pitch);
+        }
         match unsafe { bindings::imu_get_pitch(self.port) } {
-            x if x == bindings::PROS_ERR_F_ => Err(InertialSensorError::from_errno()),
+            x if x == bindings::PROS_ERR_F_ => Err(self.error()),
             x => Ok(x),
         }
     }
 
     /// Get the Inertial Sensor’s roll angle bounded by (-180,180).
     pub fn get_roll(&self) -> Result<f64, InertialSensorError> {
+        if !self.orientation.is_upright() {
+            return self.get_euler().map(|euler| euler.roll);
+        }
         match unsafe { bindings::imu_get_roll(self.port) } {
-            x if x == bindings::PROS_ERR_F_ => Err(InertialSensorError::from_errno()),
+            x if x == bindings::PROS_ERR_F_ => Err(self.error()),
             x => Ok(x),
         }
     }
 
     /// Get the Inertial Sensor’s yaw angle bounded by (-180,180).
     pub fn get_yaw(&self) -> Result<f64, InertialSensorError> {
+        if !self.orientation.is_upright() {
+            return self.get_euler().map(|euler| euler.yaw);
+        }
         match unsafe { bindings::imu_get_yaw(self.port) } {
-            x if x == bindings::PROS_ERR_F_ => Err(InertialSensorError::from_errno()),
+            x if x == bindings::PROS_ERR_F_ => Err(self.error()),
             x => Ok(x),
         }
     }
@@ -116,24 +213,24 @@ impl InertialSensor {
     /// Get the Inertial Sensor’s raw gyroscope values.
     pub fn get_gyro_rate(&self) -> Result<InertialSensorRaw, InertialSensorError> {
         match unsafe { bindings::imu_get_gyro_rate(self.port) } {
-            x if x.x == bindings::PROS_ERR_F_ => Err(InertialSensorError::from_errno()),
-            x => Ok(InertialSensorRaw {
+            x if x.x == bindings::PROS_ERR_F_ => Err(self.error()),
+            x => Ok(self.orientation.to_robot(InertialSensorRaw {
                 x: x.x,
                 y: x.y,
                 z: x.z,
-            }),
+            })),
         }
     }
 
     /// Get the Inertial Sensor’s raw accelerometer values.
     pub fn get_accel(&self) -> Result<InertialSensorRaw, InertialSensorError> {
         match unsafe { bindings::imu_get_accel(self.port) } {
-            x if x.x == bindings::PROS_ERR_F_ => Err(InertialSensorError::from_errno()),
-            x => Ok(InertialSensorRaw {
+            x if x.x == bindings::PROS_ERR_F_ => Err(self.error()),
+            x => Ok(self.orientation.to_robot(InertialSensorRaw {
                 x: x.x,
                 y: x.y,
                 z: x.z,
-            }),
+            })),
         }
     }
 
@@ -142,7 +239,7 @@ impl InertialSensor {
         let status = unsafe { bindings::imu_get_status(self.port) };
 
         if status == bindings::imu_status_e_E_IMU_STATUS_ERROR {
-            Err(InertialSensorError::from_errno())
+            Err(self.error())
         } else {
             Ok(InertialSensorStatus(status))
         }
@@ -150,56 +247,83 @@ impl InertialSensor {
 
     /// Resets the current reading of the Inertial Sensor’s rotation to zero.
     pub fn reset_heading(&mut self) -> Result<(), InertialSensorError> {
+        if !self.orientation.is_upright() {
+            return self.set_heading(0.0);
+        }
         match unsafe { bindings::imu_tare_heading(self.port) } {
-            bindings::PROS_ERR_ => Err(InertialSensorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
 
     /// Resets the current reading of the Inertial Sensor’s rotation to zero.
     pub fn reset_rotation(&mut self) -> Result<(), InertialSensorError> {
+        if !self.orientation.is_upright() {
+            return self.set_rotation(0.0);
+        }
         match unsafe { bindings::imu_tare_rotation(self.port) } {
-            bindings::PROS_ERR_ => Err(InertialSensorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
 
     /// Resets the current reading of the Inertial Sensor’s pitch to zero.
     pub fn reset_pitch(&mut self) -> Result<(), InertialSensorError> {
+        if !self.orientation.is_upright() {
+            return self.set_pitch(0.0);
+        }
         match unsafe { bindings::imu_tare_pitch(self.port) } {
-            bindings::PROS_ERR_ => Err(InertialSensorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
 
     /// Resets the current reading of the Inertial Sensor’s roll to zero.
     pub fn reset_roll(&mut self) -> Result<(), InertialSensorError> {
+        if !self.orientation.is_upright() {
+            return self.set_roll(0.0);
+        }
         match unsafe { bindings::imu_tare_roll(self.port) } {
-            bindings::PROS_ERR_ => Err(InertialSensorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
 
     /// Resets the current reading of the Inertial Sensor’s yaw to zero.
     pub fn reset_yaw(&mut self) -> Result<(), InertialSensorError> {
+        if !self.orientation.is_upright() {
+            return self.set_yaw(0.0);
+        }
         match unsafe { bindings::imu_tare_yaw(self.port) } {
-            bindings::PROS_ERR_ => Err(InertialSensorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
 
     /// Reset all 3 euler values of the Inertial Sensor to 0.
     pub fn reset_euler(&mut self) -> Result<(), InertialSensorError> {
+        if !self.orientation.is_upright() {
+            return self.set_zero_euler(InertialSensorEuler {
+                pitch: 0.0,
+                roll: 0.0,
+                yaw: 0.0,
+            });
+        }
         match unsafe { bindings::imu_tare_euler(self.port) } {
-            bindings::PROS_ERR_ => Err(InertialSensorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
 
     /// Resets all 5 values of the Inertial Sensor to 0.
     pub fn reset(&mut self) -> Result<(), InertialSensorError> {
+        if !self.orientation.is_upright() {
+            self.reset_euler()?;
+            self.reset_heading()?;
+            return self.reset_rotation();
+        }
         match unsafe { bindings::imu_tare(self.port) } {
-            bindings::PROS_ERR_ => Err(InertialSensorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
@@ -210,6 +334,15 @@ impl InertialSensor {
         &mut self,
         euler: InertialSensorEuler,
     ) -> Result<(), InertialSensorError> {
+        if !self.orientation.is_upright() {
+            let (rotation, _) = self.robot_rotation()?;
+            let (pitch, roll) = self.robot_tilt()?;
+            let mut frame = self.frame.lock();
+            frame.pitch_offset = pitch - euler.pitch.clamp(-180.0, 180.0);
+            frame.roll_offset = roll - euler.roll.clamp(-180.0, 180.0);
+            frame.yaw_offset = rotation - euler.yaw.clamp(-180.0, 180.0);
+            return Ok(());
+        }
         match unsafe {
             bindings::imu_set_euler(
                 self.port,
@@ -220,7 +353,7 @@ impl InertialSensor {
                 },
             )
         } {
-            bindings::PROS_ERR_ => Err(InertialSensorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
@@ -228,8 +361,13 @@ impl InertialSensor {
     /// Sets the current reading of the Inertial Sensor’s rotation to target
     /// value.
     pub fn set_rotation(&mut self, rotation: f64) -> Result<(), InertialSensorError> {
+        if !self.orientation.is_upright() {
+            let (current, _) = self.robot_rotation()?;
+            self.frame.lock().rotation_offset = current - rotation;
+            return Ok(());
+        }
         match unsafe { bindings::imu_set_rotation(self.port, rotation) } {
-            bindings::PROS_ERR_ => Err(InertialSensorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
@@ -238,8 +376,13 @@ impl InertialSensor {
     /// value Target will default to 360 if above 360 and default to 0 if below
     /// 0.
     pub fn set_heading(&mut self, heading: f64) -> Result<(), InertialSensorError> {
+        if !self.orientation.is_upright() {
+            let (current, _) = self.robot_rotation()?;
+            self.frame.lock().heading_offset = current - heading.clamp(0.0, 360.0);
+            return Ok(());
+        }
         match unsafe { bindings::imu_set_heading(self.port, heading) } {
-            bindings::PROS_ERR_ => Err(InertialSensorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
@@ -247,8 +390,13 @@ impl InertialSensor {
     /// Sets the current reading of the Inertial Sensor’s pitch to target value
     /// Will default to +/- 180 if target exceeds +/- 180.
     pub fn set_pitch(&mut self, pitch: f64) -> Result<(), InertialSensorError> {
+        if !self.orientation.is_upright() {
+            let (current, _) = self.robot_tilt()?;
+            self.frame.lock().pitch_offset = current - pitch.clamp(-180.0, 180.0);
+            return Ok(());
+        }
         match unsafe { bindings::imu_set_pitch(self.port, pitch) } {
-            bindings::PROS_ERR_ => Err(InertialSensorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
@@ -256,8 +404,13 @@ impl InertialSensor {
     /// Sets the current reading of the Inertial Sensor’s roll to target value
     /// Will default to +/- 180 if target exceeds +/- 180.
     pub fn set_roll(&mut self, roll: f64) -> Result<(), InertialSensorError> {
+        if !self.orientation.is_upright() {
+            let (_, current) = self.robot_tilt()?;
+            self.frame.lock().roll_offset = current - roll.clamp(-180.0, 180.0);
+            return Ok(());
+        }
         match unsafe { bindings::imu_set_roll(self.port, roll) } {
-            bindings::PROS_ERR_ => Err(InertialSensorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
@@ -265,11 +418,77 @@ impl InertialSensor {
     /// Sets the current reading of the Inertial Sensor’s yaw to target value
     /// Will default to +/- 180 if target exceeds +/- 180.
     pub fn set_yaw(&mut self, yaw: f64) -> Result<(), InertialSensorError> {
+        if !self.orientation.is_upright() {
+            let (current, _) = self.robot_rotation()?;
+            self.frame.lock().yaw_offset = current - yaw.clamp(-180.0, 180.0);
+            return Ok(());
+        }
         match unsafe { bindings::imu_set_yaw(self.port, yaw) } {
-            bindings::PROS_ERR_ => Err(InertialSensorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
+
+    /// Gets the orientation of the sensor as a rotation matrix in the frame of
+    /// the robot.
+    fn robot_matrix(&self) -> Result<[[f64; 3]; 3], InertialSensorError> {
+        let q = match unsafe { bindings::imu_get_quaternion(self.port) } {
+            x if x.x == bindings::PROS_ERR_F_ => return Err(self.error()),
+            x => x,
+        };
+        let sensor = [
+            [
+                1.0 - 2.0 * (q.y * q.y + q.z * q.z),
+                2.0 * (q.x * q.y - q.w * q.z),
+                2.0 * (q.x * q.z + q.w * q.y),
+            ],
+            [
+                2.0 * (q.x * q.y + q.w * q.z),
+                1.0 - 2.0 * (q.x * q.x + q.z * q.z),
+                2.0 * (q.y * q.z - q.w * q.x),
+            ],
+            [
+                2.0 * (q.x * q.z - q.w * q.y),
+                2.0 * (q.y * q.z + q.w * q.x),
+                1.0 - 2.0 * (q.x * q.x + q.y * q.y),
+            ],
+        ];
+        let axes = self.orientation.axes();
+        let mut robot = [[0.0; 3]; 3];
+        for (i, a) in axes.iter().enumerate() {
+            for (j, b) in axes.iter().enumerate() {
+                robot[i][j] = a.sign() * b.sign() * sensor[a.index()][b.index()];
+            }
+        }
+        Ok(robot)
+    }
+
+    /// Gets the unwrapped yaw of the robot, before any offsets, along with the
+    /// offsets.
+    fn robot_rotation(&self) -> Result<(f64, RobotFrame), InertialSensorError> {
+        let m = self.robot_matrix()?;
+        let yaw = m[1][0].atan2(m[0][0]).to_degrees();
+        let mut frame = self.frame.lock();
+        frame.rotation += wrap_degrees(yaw - frame.rotation);
+        Ok((frame.rotation, *frame))
+    }
+
+    /// Gets the pitch and roll of the robot, before any offsets.
+    fn robot_tilt(&self) -> Result<(f64, f64), InertialSensorError> {
+        let m = self.robot_matrix()?;
+        Ok((
+            (-m[2][0]).clamp(-1.0, 1.0).asin().to_degrees(),
+            m[2][1].atan2(m[2][2]).to_degrees(),
+        ))
+    }
+
+    fn error(&self) -> DeviceError {
+        let port = DevicePort::Smart(self.port);
+        match get_errno() {
+            libc::EAGAIN => DeviceError::new(DeviceErrorKind::AlreadyCalibrating, port),
+            _ => diagnostics::record_error(DeviceError::from_errno(port, "an inertial sensor")),
+        }
+    }
 }
 
 impl DataSource for InertialSensor {
@@ -288,6 +507,150 @@ impl DataSource for InertialSensor {
     }
 }
 
+/// An axis of an inertial sensor, pointing in either direction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SensorAxis {
+    /// The positive x-axis.
+    PosX,
+    /// The negative x-axis.
+    NegX,
+    /// The positive y-axis.
+    PosY,
+    /// The negative y-axis.
+    NegY,
+    /// The positive z-axis.
+    PosZ,
+    /// The negative z-axis.
+    NegZ,
+}
+
+impl SensorAxis {
+    fn index(self) -> usize {
+        match self {
+            Self::PosX | Self::NegX => 0,
+            Self::PosY | Self::NegY => 1,
+            Self::PosZ | Self::NegZ => 2,
+        }
+    }
+
+    fn sign(self) -> f64 {
+        match self {
+            Self::PosX | Self::PosY | Self::PosZ => 1.0,
+            Self::NegX | Self::NegY | Self::NegZ => -1.0,
+        }
+    }
+
+    fn from_parts(index: usize, sign: f64) -> Self {
+        match (index, sign > 0.0) {
+            (0, true) => Self::PosX,
+            (0, false) => Self::NegX,
+            (1, true) => Self::PosY,
+            (1, false) => Self::NegY,
+            (_, true) => Self::PosZ,
+            (_, false) => Self::NegZ,
+        }
+    }
+}
+
+/// The orientation in which an inertial sensor is mounted on the robot, given
+/// by the axes of the sensor which point forward, left and up on the robot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ImuOrientation {
+    forward: SensorAxis,
+    left: SensorAxis,
+    up: SensorAxis,
+}
+
+impl ImuOrientation {
+    /// The orientation of a sensor mounted flat with its x-axis pointing
+    /// forward, in which its readings are used as they are.
+    pub const UPRIGHT: Self = Self {
+        forward: SensorAxis::PosX,
+        left: SensorAxis::PosY,
+        up: SensorAxis::PosZ,
+    };
+
+    /// Creates an orientation from the axes of the sensor which point forward
+    /// and up on the robot.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `forward` and `up` are along the same axis.
+    pub fn new(forward: SensorAxis, up: SensorAxis) -> Self {
+        assert!(
+            forward.index() != up.index(),
+            "forward and up must be different axes"
+        );
+        // left = up × forward
+        let index = 3 - forward.index() - up.index();
+        let cyclic = (up.index() + 1) % 3 == forward.index();
+        let sign = up.sign() * forward.sign() * if cyclic { 1.0 } else { -1.0 };
+        Self {
+            forward,
+            left: SensorAxis::from_parts(index, sign),
+            up,
+        }
+    }
+
+    #[inline]
+    /// Gets the axis of the sensor which points forward on the robot.
+    pub fn forward(self) -> SensorAxis {
+        self.forward
+    }
+
+    #[inline]
+    /// Gets the axis of the sensor which points left on the robot.
+    pub fn left(self) -> SensorAxis {
+        self.left
+    }
+
+    #[inline]
+    /// Gets the axis of the sensor which points up on the robot.
+    pub fn up(self) -> SensorAxis {
+        self.up
+    }
+
+    fn is_upright(self) -> bool {
+        self == Self::UPRIGHT
+    }
+
+    fn axes(self) -> [SensorAxis; 3] {
+        [self.forward, self.left, self.up]
+    }
+
+    fn to_robot(self, raw: InertialSensorRaw) -> InertialSensorRaw {
+        let raw = [raw.x, raw.y, raw.z];
+        let [x, y, z] = self.axes().map(|axis| axis.sign() * raw[axis.index()]);
+        InertialSensorRaw { x, y, z }
+    }
+}
+
+impl Default for ImuOrientation {
+    #[inline]
+    fn default() -> Self {
+        Self::UPRIGHT
+    }
+}
+
+/// The state used to convert readings to the frame of the robot.
+#[derive(Clone, Copy, Default)]
+struct RobotFrame {
+    rotation: f64,
+    rotation_offset: f64,
+    heading_offset: f64,
+    yaw_offset: f64,
+    pitch_offset: f64,
+    roll_offset: f64,
+}
+
+fn wrap_degrees(angle: f64) -> f64 {
+    Degrees(angle).wrap().0
+}
+
+fn wrap_heading(angle: f64) -> f64 {
+    Degrees(angle).wrap_positive().0
+}
+
 /// Represents the data that can be read from an inertial sensor.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct InertialSensorData {
@@ -303,49 +666,9 @@ pub struct InertialSensorData {
     pub accel: InertialSensorRaw,
 }
 
-/// Represents possible errors for inertial sensor operations.
-#[derive(Debug)]
-pub enum InertialSensorError {
-    /// Port is out of range (1-21).
-    PortOutOfRange,
-    /// Port cannot be configured as a inertial sensor.
-    PortNotInertialSensor,
-    /// The sensor is already calibrating.
-    SensorAlreadyCalibrating,
-    /// The sensor returned an unknown status code.
-    UnknownStatusCode(u32),
-    /// Unknown error.
-    Unknown(i32),
-}
-
-impl InertialSensorError {
-    fn from_errno() -> Self {
-        match get_errno() {
-            libc::ENXIO => Self::PortOutOfRange,
-            libc::ENODEV => Self::PortNotInertialSensor,
-            libc::EAGAIN => Self::SensorAlreadyCalibrating,
-            x => Self::Unknown(x),
-        }
-    }
-}
-
-impl From<InertialSensorError> for Error {
-    fn from(err: InertialSensorError) -> Self {
-        match err {
-            InertialSensorError::PortOutOfRange => Error::Custom("port out of range".into()),
-            InertialSensorError::PortNotInertialSensor => {
-                Error::Custom("port not a inertial sensor".into())
-            }
-            InertialSensorError::SensorAlreadyCalibrating => {
-                Error::Custom("sensor already calibrating".into())
-            }
-            InertialSensorError::UnknownStatusCode(n) => {
-                Error::Custom(format!("sensor returned unknown status code {}", n))
-            }
-            InertialSensorError::Unknown(n) => Error::System(n),
-        }
-    }
-}
+/// Represents possible errors for inertial sensor operations. This is an alias
+/// of [`DeviceError`].
+pub type InertialSensorError = DeviceError;
 
 /// Represents raw values returned from an inertial sensor.
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -371,6 +694,20 @@ pub struct InertialSensorQuaternion {
     pub w: f64,
 }
 
+impl From<InertialSensorEuler> for InertialSensorQuaternion {
+    fn from(euler: InertialSensorEuler) -> Self {
+        let (sr, cr) = (euler.roll.to_radians() / 2.0).sin_cos();
+        let (sp, cp) = (euler.pitch.to_radians() / 2.0).sin_cos();
+        let (sy, cy) = (euler.yaw.to_radians() / 2.0).sin_cos();
+        Self {
+            x: sr * cp * cy - cr * sp * sy,
+            y: cr * sp * cy + sr * cp * sy,
+            z: cr * cp * sy - sr * sp * cy,
+            w: cr * cp * cy + sr * sp * sy,
+        }
+    }
+}
+
 /// Represents the set of euler angles returned from an inertial sensor.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct InertialSensorEuler {
@@ -0,0 +1,192 @@
+//! # Intake Indexing.
+//!
+//! Almost every intake is driven the same way: run the rollers until a game
+//! object reaches a sensor, then stop with the object held; later, run them
+//! again until the object has passed the sensor, e.g. into a shooter. An
+//! [`Indexer`] is a [state machine](crate::state_machine!) which does this
+//! with any [`MotorLike`] and any [`DistanceLike`] sensor, treating a reading
+//! closer than a threshold as an object at the sensor. Detections are
+//! debounced, so that a flickering reading does not stop the intake early.
+//!
+//! Each state method returns a [`Promise`](crate::rtos::Promise) which
+//! resolves with the [`IndexOutcome`] once the state finishes.
+//!
+//! # Example
+//!
+//! ```
+//! let indexer = Indexer::new(intake_motor, distance_sensor, IndexerConfig::new());
+//!
+//! // Intake until a ball is held, giving up after 3 seconds.
+//! let loaded = indexer.load_ext(ctx.fork_with_timeout(Duration::from_secs(3)));
+//! select! {
+//!     outcome = loaded.done() => println!("load: {:?}", outcome),
+//!     _ = ctx.done() => {},
+//! }
+//!
+//! // Shoot it.
+//! indexer.feed();
+//! ```
+
+use core::time::Duration;
+
+use crate::{
+    device::{DistanceLike, MotorLike},
+    error::DeviceError,
+    filter::Debounce,
+    machine::StateResult,
+    rtos::{Context, Loop},
+    select, state_machine,
+};
+
+/// Configures an [`Indexer`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndexerConfig {
+    /// The distance below which an object is considered to be at the sensor,
+    /// in millimetres.
+    pub threshold: f64,
+    /// The voltage at which the motor runs while loading an object, in
+    /// millivolts.
+    pub load_voltage: i32,
+    /// The voltage at which the motor runs while feeding an object out, in
+    /// millivolts.
+    pub feed_voltage: i32,
+    /// How long a detection must hold before it is acted on.
+    pub debounce: Duration,
+    /// The interval at which the sensor is read.
+    pub period: Duration,
+}
+
+impl IndexerConfig {
+    /// Creates a new configuration with default values, which detects objects
+    /// within 50mm and runs the motor at full voltage, debounced over 30ms.
+    pub fn new() -> Self {
+        Self {
+            threshold: 50.0,
+            load_voltage: 12000,
+            feed_voltage: 12000,
+            debounce: Duration::from_millis(30),
+            period: Duration::from_millis(10),
+        }
+    }
+}
+
+impl Default for IndexerConfig {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Describes how an [`Indexer`] state finished.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IndexOutcome {
+    /// The object reached, or passed, the sensor.
+    Done,
+    /// The state's context was cancelled first, e.g. by a timeout or a
+    /// transition to another state.
+    Cancelled,
+    /// The motor or sensor failed.
+    Failed(DeviceError),
+}
+
+state_machine! {
+    /// Runs an intake until game objects reach or pass a sensor. See the
+    /// [module-level documentation](crate::indexer).
+    pub Indexer<M: MotorLike + Send + 'static, D: DistanceLike + Send + 'static>(
+        motor: M,
+        sensor: D,
+        config: IndexerConfig,
+    ) {
+        motor: M = motor,
+        sensor: D = sensor,
+        config: IndexerConfig = config,
+    } = idle;
+
+    /// Stops the motor.
+    idle(_ctx) [motor] -> IndexOutcome {
+        match motor.move_voltage(0) {
+            Ok(()) => IndexOutcome::Done,
+            Err(err) => IndexOutcome::Failed(err),
+        }
+    }
+
+    /// Runs the motor until an object is at the sensor, then stops it. Finishes
+    /// immediately if an object is already there.
+    load(ctx) [motor, sensor, config] -> IndexOutcome {
+        let outcome = run_until(&ctx, motor, sensor, config, config.load_voltage, true);
+        return StateResult::Transition(outcome, IndexerState::Idle);
+    }
+
+    /// Runs the motor until no object is at the sensor, then stops it, e.g. to
+    /// feed a held object into a shooter.
+    feed(ctx) [motor, sensor, config] -> IndexOutcome {
+        let outcome = run_until(&ctx, motor, sensor, config, config.feed_voltage, false);
+        return StateResult::Transition(outcome, IndexerState::Idle);
+    }
+
+    /// Runs the motor at the given voltage, in millivolts, regardless of the
+    /// sensor, until another state is entered, e.g. to outtake.
+    run(ctx, voltage: i32) [motor] -> IndexOutcome {
+        if let Err(err) = motor.move_voltage(voltage) {
+            return StateResult::Simple(IndexOutcome::Failed(err));
+        }
+        crate::rtos::select(ctx.done());
+        IndexOutcome::Cancelled
+    }
+}
+
+/// Runs the motor at `voltage` until whether an object is at the sensor
+/// matches `present`, then stops it.
+fn run_until(
+    ctx: &Context,
+    motor: &mut impl MotorLike,
+    sensor: &mut impl DistanceLike,
+    config: &IndexerConfig,
+    voltage: i32,
+    present: bool,
+) -> IndexOutcome {
+    let outcome = wait_until(ctx, motor, sensor, config, voltage, present);
+    match (motor.move_voltage(0), outcome) {
+        (Err(err), IndexOutcome::Done | IndexOutcome::Cancelled) => IndexOutcome::Failed(err),
+        (_, outcome) => outcome,
+    }
+}
+
+fn wait_until(
+    ctx: &Context,
+    motor: &mut impl MotorLike,
+    sensor: &mut impl DistanceLike,
+    config: &IndexerConfig,
+    voltage: i32,
+    present: bool,
+) -> IndexOutcome {
+    let mut detected = Debounce::new(config.debounce);
+    let mut l = Loop::new(config.period);
+    let mut running = false;
+    loop {
+        match sensor.distance() {
+            Ok(distance) => {
+                let detection = distance < config.threshold;
+                if !running {
+                    // Start from the current reading, so that an object which is
+                    // already in place finishes the state straight away.
+                    detected.reset(detection);
+                }
+                if detected.update(detection) == present {
+                    return IndexOutcome::Done;
+                }
+            }
+            Err(err) => return IndexOutcome::Failed(err),
+        }
+        if !running {
+            if let Err(err) = motor.move_voltage(voltage) {
+                return IndexOutcome::Failed(err);
+            }
+            running = true;
+        }
+        select! {
+            _ = ctx.done() => return IndexOutcome::Cancelled,
+            _ = l.select() => continue,
+        }
+    }
+}
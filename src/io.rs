@@ -1,3 +1,166 @@
 //! STDIO/STDERR macros.
+//!
+//! Output printed with [`print!`](crate::print!) and
+//! [`println!`](crate::println!) is collected in a shared buffer, and written
+//! to standard output by a low-priority background task. Each call is added to
+//! the buffer in one piece, so lines printed by different tasks are never
+//! interleaved, and printing does not block while the serial connection is
+//! busy. Call [`flush()`] to write the buffer immediately.
+//!
+//! Output printed to standard error with [`eprint!`] and [`eprintln!`] is
+//! written immediately, and so may appear before buffered standard output
+//! which was printed earlier.
+
+use alloc::{string::String, vec::Vec};
+use core::{
+    fmt::{self, Write},
+    mem,
+    time::Duration,
+};
 
 pub use libc_print::std_name::*;
+use spin::Once;
+
+use crate::{
+    bindings,
+    error::{from_errno, Error},
+    rtos::{Loop, Mutex, Task},
+};
+pub use crate::{print, println};
+
+/// How often the background task writes buffered output.
+const FLUSH_PERIOD: Duration = Duration::from_millis(10);
+
+/// The maximum number of bytes held in the output buffer. Output printed while
+/// the buffer is full is discarded.
+const BUFFER_CAPACITY: usize = 4096;
+
+struct Stdout {
+    buffer: String,
+    dropped: usize,
+}
+
+static STDOUT: Once<Mutex<Stdout>> = Once::new();
+static WRITER: Once<Mutex<()>> = Once::new();
+static FLUSHER: Once<()> = Once::new();
+
+fn stdout() -> &'static Mutex<Stdout> {
+    STDOUT.call_once(|| {
+        Mutex::new(Stdout {
+            buffer: String::new(),
+            dropped: 0,
+        })
+    })
+}
+
+fn writer() -> &'static Mutex<()> {
+    WRITER.call_once(|| Mutex::new(()))
+}
+
+#[doc(hidden)]
+pub fn _print(args: fmt::Arguments) {
+    let mut stdout = stdout().lock();
+    let start = stdout.buffer.len();
+    // Writing to a string cannot fail.
+    stdout.buffer.write_fmt(args).ok();
+    if stdout.buffer.len() > BUFFER_CAPACITY {
+        stdout.buffer.truncate(start);
+        stdout.dropped += 1;
+    }
+    drop(stdout);
+
+    FLUSHER.call_once(|| {
+        if let Err(err) = Task::spawn_ext(
+            "stdout",
+            bindings::TASK_PRIORITY_MIN,
+            Task::DEFAULT_STACK_DEPTH,
+            || {
+                let mut l = Loop::new(FLUSH_PERIOD);
+                loop {
+                    flush();
+                    l.delay();
+                }
+            },
+        ) {
+            eprintln!("failed to start stdout task: {:?}", err);
+        }
+    });
+}
+
+/// Writes all buffered standard output immediately, blocking until it has
+/// been written.
+pub fn flush() {
+    let _writer = writer().lock();
+    let pending = take_pending(&mut stdout().lock());
+    write_pending(pending);
+}
+
+/// Writes all buffered standard output, unless another task is currently
+/// printing or flushing. Used by the panic handler, which must not block.
+pub(crate) fn try_flush() {
+    if let Some(_writer) = writer().poll() {
+        if let Some(mut stdout) = stdout().poll() {
+            let pending = take_pending(&mut stdout);
+            drop(stdout);
+            write_pending(pending);
+        }
+    }
+}
+
+fn take_pending(stdout: &mut Stdout) -> (String, usize) {
+    (
+        mem::take(&mut stdout.buffer),
+        mem::take(&mut stdout.dropped),
+    )
+}
+
+// The buffer is not locked while writing, so that other tasks can continue
+// printing in the meantime.
+fn write_pending((buffer, dropped): (String, usize)) {
+    write_stdout(buffer.as_bytes()).ok();
+    if dropped > 0 {
+        let mut message = String::new();
+        writeln!(message, "[{} messages dropped]", dropped).ok();
+        write_stdout(message.as_bytes()).ok();
+    }
+}
+
+/// Writes bytes directly to standard output, bypassing the buffer, such as a
+/// binary frame which must not be split. Waits for any buffered output being
+/// written by another task, so that the two are not interleaved.
+pub(crate) fn write_raw(bytes: &[u8]) -> Result<(), Error> {
+    let _writer = writer().lock();
+    write_stdout(bytes)
+}
+
+fn write_stdout(mut bytes: &[u8]) -> Result<(), Error> {
+    while !bytes.is_empty() {
+        let n = unsafe { libc::write(libc::STDOUT_FILENO, bytes.as_ptr().cast(), bytes.len()) };
+        match n {
+            n if n < 0 => return Err(from_errno()),
+            0 => return Err(Error::System(libc::EIO)),
+            n => bytes = &bytes[n as usize..],
+        }
+    }
+    Ok(())
+}
+
+/// Reads a line from standard input (the USB serial connection), blocking
+/// until a newline is received. The line ending is not included.
+///
+/// Returns [`None`] if standard input has been closed.
+pub fn read_line() -> Option<String> {
+    let mut line = Vec::new();
+    loop {
+        let mut byte = 0u8;
+        if unsafe { libc::read(libc::STDIN_FILENO, (&mut byte as *mut u8).cast(), 1) } <= 0 {
+            return None;
+        }
+        match byte {
+            b'\n' => break,
+            b'\r' => {}
+            _ => line.push(byte),
+        }
+    }
+    Some(String::from_utf8_lossy(&line).into_owned())
+}
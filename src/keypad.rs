@@ -0,0 +1,310 @@
+//! # Numeric Keypad.
+//!
+//! A [`Keypad`] lets the drive team change numeric [parameters](crate::params)
+//! from the brain screen while the robot is disabled, e.g. the flywheel speed
+//! or the delay before an autonomous routine, without reconnecting a laptop.
+//! The parameters are listed on the left of the screen with their current
+//! values, and a keypad is shown on the right; tapping a parameter selects it,
+//! and pressing `OK` writes the value entered to the global [`Params`] store.
+//!
+//! The keypad only uses the screen while the robot is disabled, and clears it
+//! once the robot is enabled.
+//!
+//! # Example
+//!
+//! ```
+//! let keypad = Keypad::new(&["flywheel.rpm", "auton.delay"]);
+//!
+//! // In autonomous:
+//! let mut delay = Param::new("auton.delay", 0.0);
+//! Task::delay(Duration::from_secs_f64(delay.get()));
+//! ```
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    sync::{Arc, Weak},
+    vec,
+    vec::Vec,
+};
+use core::time::Duration;
+
+use cstring_interop::with_cstring;
+
+use crate::{
+    bindings,
+    error::Error,
+    params::Params,
+    robot::CompetitionStatus,
+    rtos::{Loop, Mutex, Task},
+    screen::{SCREEN_HEIGHT, SCREEN_WIDTH},
+};
+
+/// The maximum number of parameters which can be edited with a keypad.
+pub const MAX_KEYPAD_ENTRIES: usize = 5;
+
+/// The interval at which touches are checked and the screen is refreshed.
+const UPDATE_PERIOD: Duration = Duration::from_millis(20);
+
+/// The width of the list of parameters, in pixels.
+const LIST_WIDTH: i16 = SCREEN_WIDTH / 2;
+
+/// The height of each line of the list of parameters, in pixels.
+const ROW_HEIGHT: i16 = SCREEN_HEIGHT / (MAX_KEYPAD_ENTRIES as i16 + 1);
+
+/// The size of each key, in pixels.
+const KEY_SIZE: i16 = 60;
+
+/// The maximum number of characters which can be entered.
+const MAX_LEN: usize = 12;
+
+/// The label, column, row and width in columns of each key.
+const KEYS: [(&str, i16, i16, i16); 15] = [
+    ("7", 0, 0, 1),
+    ("8", 1, 0, 1),
+    ("9", 2, 0, 1),
+    ("<", 3, 0, 1),
+    ("4", 0, 1, 1),
+    ("5", 1, 1, 1),
+    ("6", 2, 1, 1),
+    ("C", 3, 1, 1),
+    ("1", 0, 2, 1),
+    ("2", 1, 2, 1),
+    ("3", 2, 2, 1),
+    ("-", 3, 2, 1),
+    ("0", 0, 3, 1),
+    (".", 1, 3, 1),
+    ("OK", 2, 3, 2),
+];
+
+const TEXT_COLOR: u32 = 0xFFFFFF;
+const SELECTED_COLOR: u32 = 0xFFFF00;
+const KEY_COLOR: u32 = 0x808080;
+
+/// Shows a numeric keypad for editing parameters on the brain screen while the
+/// robot is disabled, from a background task. See the
+/// [module-level documentation](self).
+///
+/// The keypad uses the whole screen, and the task exits when this is dropped.
+pub struct Keypad(Arc<Mutex<KeypadData>>);
+
+impl Keypad {
+    #[inline]
+    /// Creates a new keypad for editing the given parameters, and spawns its
+    /// task. Panics on failure; see [`Keypad::try_new()`].
+    ///
+    /// # Panics
+    ///
+    /// This function panics if more than [`MAX_KEYPAD_ENTRIES`] parameters are
+    /// given.
+    pub fn new(keys: &[&str]) -> Self {
+        Self::try_new(keys).unwrap_or_else(|err| panic!("failed to create keypad: {:?}", err))
+    }
+
+    /// Creates a new keypad for editing the given parameters, and spawns its
+    /// task.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if more than [`MAX_KEYPAD_ENTRIES`] parameters are
+    /// given.
+    pub fn try_new(keys: &[&str]) -> Result<Self, Error> {
+        assert!(
+            keys.len() <= MAX_KEYPAD_ENTRIES,
+            "a keypad can edit at most {} parameters",
+            MAX_KEYPAD_ENTRIES
+        );
+        let data = Arc::new(Mutex::try_new(KeypadData {
+            keys: keys.iter().map(|key| key.to_string()).collect(),
+            selected: None,
+            buffer: String::new(),
+            message: None,
+        })?);
+        let weak: Weak<Mutex<KeypadData>> = Arc::downgrade(&data);
+        Task::spawn_ext(
+            "keypad",
+            bindings::TASK_PRIORITY_MIN,
+            Task::DEFAULT_STACK_DEPTH,
+            move || {
+                let mut l = Loop::new(UPDATE_PERIOD);
+                let mut active = false;
+                let mut was_pressed = false;
+                let mut shown: Vec<Option<(String, u32)>> = vec![None; MAX_KEYPAD_ENTRIES + 1];
+                while let Some(data) = weak.upgrade() {
+                    let disabled = CompetitionStatus::get().disabled;
+                    if disabled != active {
+                        active = disabled;
+                        clear();
+                        if active {
+                            draw_keys();
+                            shown.iter_mut().for_each(|line| *line = None);
+                        }
+                    }
+                    if active {
+                        let touch = unsafe { bindings::screen_touch_status() };
+                        let pressed = touch.touch_status
+                            == bindings::last_touch_e_t_E_TOUCH_PRESSED
+                            || touch.touch_status == bindings::last_touch_e_t_E_TOUCH_HELD;
+                        let lines = {
+                            let mut data = data.lock();
+                            if pressed && !was_pressed {
+                                data.press(touch.x, touch.y);
+                            }
+                            data.lines()
+                        };
+                        was_pressed = pressed;
+                        for (i, line) in lines.into_iter().enumerate() {
+                            if shown[i].as_ref() != Some(&line) {
+                                draw_row(i, &line.0, line.1);
+                                shown[i] = Some(line);
+                            }
+                        }
+                    }
+                    drop(data);
+                    l.delay();
+                }
+                if active {
+                    clear();
+                }
+            },
+        )?;
+        Ok(Self(data))
+    }
+
+    #[inline]
+    /// Gets the parameter currently selected for editing, if any.
+    pub fn selected(&self) -> Option<String> {
+        let data = self.0.lock();
+        data.selected.map(|i| data.keys[i].clone())
+    }
+}
+
+struct KeypadData {
+    keys: Vec<String>,
+    selected: Option<usize>,
+    buffer: String,
+    message: Option<String>,
+}
+
+impl KeypadData {
+    /// Handles a press at the given point on the screen.
+    fn press(&mut self, x: i16, y: i16) {
+        if x < LIST_WIDTH {
+            let row = (y / ROW_HEIGHT) as usize;
+            if (1..=self.keys.len()).contains(&row) {
+                self.selected = Some(row - 1);
+                self.buffer.clear();
+                self.message = None;
+            }
+            return;
+        }
+        let (col, row) = ((x - LIST_WIDTH) / KEY_SIZE, y / KEY_SIZE);
+        let label = match KEYS
+            .iter()
+            .find(|(_, c, r, w)| *r == row && (*c..*c + *w).contains(&col))
+        {
+            Some((label, ..)) => *label,
+            None => return,
+        };
+        let key = match self.selected {
+            Some(i) => self.keys[i].clone(),
+            None => return,
+        };
+        match label {
+            "<" => {
+                self.buffer.pop();
+            }
+            "C" => self.buffer.clear(),
+            "-" => {
+                if self.buffer.starts_with('-') {
+                    self.buffer.remove(0);
+                } else {
+                    self.buffer.insert(0, '-');
+                }
+            }
+            "." if self.buffer.contains('.') => {}
+            "OK" => {
+                if self.buffer.parse::<f64>().is_ok() {
+                    Params::set_str(&key, &self.buffer);
+                    self.message = Some(format!("set {}", key));
+                    self.selected = None;
+                    self.buffer.clear();
+                } else {
+                    self.message = Some("invalid number".into());
+                }
+                return;
+            }
+            digit if self.buffer.len() < MAX_LEN => self.buffer.push_str(digit),
+            _ => {}
+        }
+        self.message = None;
+    }
+
+    /// Gets the text and colour of the editing line followed by the line of
+    /// each parameter.
+    fn lines(&self) -> Vec<(String, u32)> {
+        let editing = match (&self.message, self.selected) {
+            (Some(message), _) => message.clone(),
+            (None, Some(i)) => format!("{} = {}_", self.keys[i], self.buffer),
+            (None, None) => "tap a parameter to edit".into(),
+        };
+        let mut lines = vec![(editing, SELECTED_COLOR)];
+        lines.extend(self.keys.iter().enumerate().map(|(i, key)| {
+            let value = Params::get(key).unwrap_or_else(|| "-".into());
+            let color = if self.selected == Some(i) {
+                SELECTED_COLOR
+            } else {
+                TEXT_COLOR
+            };
+            (format!("{}: {}", key, value), color)
+        }));
+        lines
+    }
+}
+
+fn clear() {
+    unsafe { bindings::screen_erase_rect(0, 0, SCREEN_WIDTH - 1, SCREEN_HEIGHT - 1) };
+}
+
+fn draw_row(row: usize, text: &str, color: u32) {
+    let y = row as i16 * ROW_HEIGHT;
+    unsafe {
+        bindings::screen_erase_rect(0, y, LIST_WIDTH - 1, y + ROW_HEIGHT - 1);
+    }
+    print_at(4, y + ROW_HEIGHT / 2 - 6, text, color);
+}
+
+fn draw_keys() {
+    for (label, col, row, width) in KEYS.iter() {
+        let (x0, y0) = (LIST_WIDTH + col * KEY_SIZE, row * KEY_SIZE);
+        let (x1, y1) = (x0 + width * KEY_SIZE - 1, y0 + KEY_SIZE - 1);
+        unsafe {
+            let pen = bindings::screen_get_pen();
+            bindings::screen_set_pen(KEY_COLOR);
+            bindings::screen_draw_line(x0, y0, x1, y0);
+            bindings::screen_draw_line(x1, y0, x1, y1);
+            bindings::screen_draw_line(x1, y1, x0, y1);
+            bindings::screen_draw_line(x0, y1, x0, y0);
+            bindings::screen_set_pen(pen);
+        }
+        let text_x = (x0 + x1) / 2 - 5 * label.len() as i16;
+        print_at(text_x, (y0 + y1) / 2 - 8, label, TEXT_COLOR);
+    }
+}
+
+fn print_at(x: i16, y: i16, text: &str, color: u32) {
+    unsafe {
+        let pen = bindings::screen_get_pen();
+        bindings::screen_set_pen(color);
+        with_cstring(text.into(), |text| {
+            bindings::screen_print_at(
+                bindings::text_format_e_t_E_TEXT_SMALL,
+                x,
+                y,
+                b"%s\0".as_ptr().cast(),
+                text.into_raw(),
+            );
+        });
+        bindings::screen_set_pen(pen);
+    }
+}
@@ -1,43 +1,92 @@
 //! A crate for running rust on the VEX V5.
 
 #![no_std]
-#![feature(alloc_error_handler)]
+#![cfg_attr(not(feature = "sim"), feature(alloc_error_handler))]
 #![feature(negative_impls)]
 #![warn(missing_docs)]
 
 extern crate alloc;
+#[cfg(feature = "sim")]
+extern crate std;
 
-use core::panic::PanicInfo;
-
-mod allocator;
+#[cfg(not(feature = "sim"))]
 mod bindings;
+#[cfg(feature = "sim")]
+use sim::bindings;
+#[cfg(feature = "critical-section")]
+mod critical_section_impl;
+#[cfg(feature = "defmt")]
+mod defmt_logger;
 mod error;
 
 pub mod adi;
+pub mod alerts;
+pub mod allocator;
+pub mod assertion;
+pub mod auton;
 pub mod battery;
+pub mod blackbox;
+pub mod build_info;
+pub mod command;
+pub mod config;
+pub mod contact;
+pub mod control;
 pub mod controller;
+pub mod csv;
+pub mod device;
+pub mod diagnostics;
 pub mod distance;
+pub mod drivetrain;
+pub mod escalation;
+pub mod field;
+pub mod filter;
+#[cfg(feature = "fixed-point")]
+pub mod fixed;
+pub mod flywheel;
+pub mod geometry;
+pub mod ghost;
+pub mod gps;
+pub mod health;
+pub mod homing;
 pub mod imu;
+pub mod indexer;
 pub mod io;
+pub mod keypad;
+pub mod lights;
+pub mod localization;
+pub mod logger;
 pub mod machine;
 pub mod macros;
+pub mod mechanism;
+pub mod motion;
 pub mod motor;
+pub mod panic;
+pub mod params;
 pub mod peripherals;
+pub mod power;
 pub mod prelude;
+pub mod profiler;
+pub mod repl;
+pub mod replay;
+pub mod retry;
 pub mod robot;
 pub mod rotation;
+pub mod rpc;
 pub mod rtos;
+pub mod screen;
+pub mod sd;
+pub mod self_check;
 pub mod serial;
+#[cfg(feature = "sim")]
+pub mod sim;
 pub mod smart_port;
+pub mod starvation;
+pub mod telemetry;
+pub mod trace;
+pub mod tunable;
+pub mod units;
+pub mod vision;
 
+pub use build_info::build_info;
 #[doc(hidden)]
 pub use spin::once;
-
-#[panic_handler]
-fn panic(panic_info: &PanicInfo) -> ! {
-    crate::io::eprintln!("panic occurred!: {:#?}", panic_info);
-
-    unsafe {
-        libc::exit(1);
-    }
-}
@@ -0,0 +1,288 @@
+//! # Status Lights.
+//!
+//! [`StatusLights`] shows the state of the robot on a set of [`Lights`], so
+//! that the drive team can tell at a glance whether the robot is disabled,
+//! whether an autonomous routine has been selected, or whether the shooter is
+//! up to speed. The mapping is declared as a table of [`Status`] conditions and
+//! the [`Pattern`] to show for each; the first entry whose condition holds is
+//! shown, so entries should be listed from most to least important.
+//!
+//! The competition period is followed automatically through
+//! [`CompetitionStatus::listen()`], while other conditions are named flags
+//! which the program sets with [`StatusLights::set()`].
+//!
+//! PROS does not provide a driver for addressable LED strips, so [`Lights`] is
+//! implemented here for the vision sensor's LED and for an ADI digital output
+//! driving a single light. Other lights can be supported by implementing the
+//! trait.
+//!
+//! # Example
+//!
+//! ```
+//! let lights = StatusLights::new(
+//!     vision,
+//!     vec![
+//!         (
+//!             Status::Flag("error"),
+//!             Pattern::Blink {
+//!                 color: 0xFF0000,
+//!                 period: Duration::from_millis(250),
+//!             },
+//!         ),
+//!         (
+//!             Status::Disabled,
+//!             Pattern::Pulse {
+//!                 color: 0x0000FF,
+//!                 period: Duration::from_secs(2),
+//!             },
+//!         ),
+//!         (Status::Flag("shooter ready"), Pattern::Solid(0x00FF00)),
+//!         (Status::Opcontrol, Pattern::Solid(0xFFFFFF)),
+//!     ],
+//! );
+//!
+//! // In the shooter loop:
+//! lights.set("shooter ready", flywheel.is_ready());
+//! ```
+
+use alloc::{
+    string::String,
+    sync::{Arc, Weak},
+    vec,
+    vec::Vec,
+};
+use core::time::Duration;
+
+use crate::{
+    adi::AdiDigitalOutput,
+    bindings,
+    error::{DeviceError, Error},
+    escalation,
+    robot::CompetitionStatus,
+    rtos::{time_since_start, Instant, Loop, Mutex, Task},
+    vision::VisionSensor,
+};
+
+/// The interval at which the lights are updated.
+const UPDATE_PERIOD: Duration = Duration::from_millis(20);
+
+/// A set of lights which can each be set to a colour.
+pub trait Lights {
+    /// Gets the number of individually coloured lights.
+    fn pixel_count(&self) -> usize;
+
+    /// Sets the colours of the lights, as 24-bit RGB values. `colors` has one
+    /// entry per light.
+    fn show(&mut self, colors: &[u32]) -> Result<(), DeviceError>;
+}
+
+impl Lights for VisionSensor {
+    #[inline]
+    fn pixel_count(&self) -> usize {
+        1
+    }
+
+    #[inline]
+    fn show(&mut self, colors: &[u32]) -> Result<(), DeviceError> {
+        self.set_led(colors.first().copied().unwrap_or(0))
+    }
+}
+
+impl Lights for AdiDigitalOutput {
+    #[inline]
+    fn pixel_count(&self) -> usize {
+        1
+    }
+
+    #[inline]
+    /// Turns the light on for any colour other than black.
+    fn show(&mut self, colors: &[u32]) -> Result<(), DeviceError> {
+        self.write(matches!(colors.first(), Some(&color) if color != 0))
+    }
+}
+
+/// Represents a pattern of colours shown on a set of [`Lights`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pattern {
+    /// All lights are off.
+    Off,
+    /// All lights show a single colour.
+    Solid(u32),
+    /// All lights flash on and off, spending half of each period on.
+    Blink {
+        /// The colour of the lights while on.
+        color: u32,
+        /// The time between flashes.
+        period: Duration,
+    },
+    /// All lights fade smoothly in and out.
+    Pulse {
+        /// The colour of the lights at full brightness.
+        color: u32,
+        /// The time between peaks of brightness.
+        period: Duration,
+    },
+    /// A single lit light moves along the set.
+    Chase {
+        /// The colour of the lit light.
+        color: u32,
+        /// The time taken to move along the whole set.
+        period: Duration,
+    },
+}
+
+impl Pattern {
+    /// Gets the colour of each of `count` lights at the given time since the
+    /// pattern started.
+    pub fn colors(&self, elapsed: Duration, count: usize) -> Vec<u32> {
+        match *self {
+            Pattern::Off => vec![0; count],
+            Pattern::Solid(color) => vec![color; count],
+            Pattern::Blink { color, period } => {
+                let on = phase(elapsed, period) < 0.5;
+                vec![if on { color } else { 0 }; count]
+            }
+            Pattern::Pulse { color, period } => {
+                let phase = phase(elapsed, period);
+                let brightness = 1.0 - 2.0 * (phase - 0.5).abs();
+                vec![scale(color, brightness); count]
+            }
+            Pattern::Chase { color, period } => {
+                let lit = (phase(elapsed, period) * count as f64) as usize;
+                (0..count)
+                    .map(|i| if i == lit { color } else { 0 })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// A condition under which a [`Pattern`] is shown by [`StatusLights`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    /// The robot is disabled.
+    Disabled,
+    /// The robot is running its autonomous routine.
+    Autonomous,
+    /// The robot is under driver control.
+    Opcontrol,
+    /// The named flag has been set with [`StatusLights::set()`].
+    Flag(&'static str),
+}
+
+/// Shows the state of the robot on a set of [`Lights`], from a background
+/// task. See the [module-level documentation](self).
+///
+/// The lights are turned off and the task exits when this is dropped.
+pub struct StatusLights(Arc<Mutex<Vec<String>>>);
+
+impl StatusLights {
+    #[inline]
+    /// Creates a new status lights service, which shows the pattern of the
+    /// first entry of `table` whose condition holds, or no light if none does,
+    /// and spawns its task. Panics on failure; see [`StatusLights::try_new()`].
+    pub fn new(lights: impl Lights + Send + 'static, table: Vec<(Status, Pattern)>) -> Self {
+        Self::try_new(lights, table)
+            .unwrap_or_else(|err| panic!("failed to create status lights: {:?}", err))
+    }
+
+    /// Creates a new status lights service, which shows the pattern of the
+    /// first entry of `table` whose condition holds, or no light if none does,
+    /// and spawns its task.
+    pub fn try_new(
+        mut lights: impl Lights + Send + 'static,
+        table: Vec<(Status, Pattern)>,
+    ) -> Result<Self, Error> {
+        let data = Arc::new(Mutex::try_new(Vec::new())?);
+        let weak: Weak<Mutex<Vec<String>>> = Arc::downgrade(&data);
+        let mut lifecycle = CompetitionStatus::listen();
+        Task::spawn_ext(
+            "status lights",
+            bindings::TASK_PRIORITY_MIN,
+            Task::DEFAULT_STACK_DEPTH,
+            move || {
+                let mut l = Loop::new(UPDATE_PERIOD);
+                let mut competition = CompetitionStatus::get();
+                let mut current: Option<(Pattern, Instant)> = None;
+                let mut shown: Option<Vec<u32>> = None;
+                while let Some(data) = weak.upgrade() {
+                    if let Some(status) = lifecycle.next_value() {
+                        competition = status;
+                    }
+                    let pattern = {
+                        let flags = data.lock();
+                        table
+                            .iter()
+                            .find(|(status, _)| is_active(status, &competition, &flags))
+                            .map_or(Pattern::Off, |(_, pattern)| *pattern)
+                    };
+                    drop(data);
+                    let now = time_since_start();
+                    // Restart the animation whenever the pattern changes.
+                    let start = match current {
+                        Some((current, start)) if current == pattern => start,
+                        _ => {
+                            current = Some((pattern, now));
+                            now
+                        }
+                    };
+                    let colors = pattern.colors(now - start, lights.pixel_count());
+                    if shown.as_ref() != Some(&colors) {
+                        if let Err(err) = lights.show(&colors) {
+                            if escalation::report("lights", &err) {
+                                break;
+                            }
+                        }
+                        shown = Some(colors);
+                    }
+                    l.delay();
+                }
+                if let Err(err) = lights.show(&vec![0; lights.pixel_count()]) {
+                    escalation::report("lights", &err);
+                }
+            },
+        )?;
+        Ok(Self(data))
+    }
+
+    /// Sets or clears a named flag, for use with [`Status::Flag`].
+    pub fn set(&self, flag: &str, active: bool) {
+        let mut flags = self.0.lock();
+        let index = flags.iter().position(|f| f == flag);
+        match (index, active) {
+            (None, true) => flags.push(flag.into()),
+            (Some(index), false) => {
+                flags.swap_remove(index);
+            }
+            _ => {}
+        }
+    }
+
+    /// Checks whether a named flag is set.
+    pub fn is_set(&self, flag: &str) -> bool {
+        self.0.lock().iter().any(|f| f == flag)
+    }
+}
+
+fn is_active(status: &Status, competition: &CompetitionStatus, flags: &[String]) -> bool {
+    match *status {
+        Status::Disabled => competition.disabled,
+        Status::Autonomous => !competition.disabled && competition.autonomous,
+        Status::Opcontrol => !competition.disabled && !competition.autonomous,
+        Status::Flag(flag) => flags.iter().any(|f| f == flag),
+    }
+}
+
+/// Gets the fraction of the way through the current period.
+fn phase(elapsed: Duration, period: Duration) -> f64 {
+    let period = period.as_micros().max(1);
+    (elapsed.as_micros() % period) as f64 / period as f64
+}
+
+/// Scales the brightness of a 24-bit RGB colour.
+fn scale(color: u32, brightness: f64) -> u32 {
+    [16, 8, 0].iter().fold(0, |scaled, shift| {
+        let channel = ((color >> shift) & 0xFF) as f64 * brightness;
+        scaled | ((channel as u32).min(0xFF) << shift)
+    })
+}
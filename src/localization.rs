@@ -0,0 +1,215 @@
+//! # GPS Pose Estimation.
+//!
+//! A [`PoseEstimator`] corrects the odometry of a [`Chassis`] using a
+//! [`GpsSensor`]. Odometry is smooth but drifts over time, while the GPS
+//! sensor is absolute but noisy, and loses its fix whenever it cannot see the
+//! field code; the estimator nudges the odometry pose towards each trusted GPS
+//! reading, in the manner of a complementary filter.
+//!
+//! The corrected pose is written back to the chassis, so that its movement
+//! commands follow it, and is also published as a [`Broadcast`] for other
+//! consumers such as path followers.
+//!
+//! # Example
+//!
+//! ```
+//! let estimator = PoseEstimator::new(&chassis, gps, GpsFusionConfig::new());
+//! let mut poses = estimator.listen();
+//! loop {
+//!     select! {
+//!         pose = poses.select() => println!("{}", pose),
+//!     }
+//! }
+//! ```
+
+use alloc::sync::Arc;
+use core::time::Duration;
+
+use crate::{
+    drivetrain::{Chassis, WeakChassis},
+    error::Error,
+    geometry::Pose2d,
+    gps::GpsSensor,
+    rtos::{time_since_start, Broadcast, BroadcastListener, Instant, Loop, Mutex, Task},
+    units::Millimeters,
+};
+
+/// Represents the parameters of a [`PoseEstimator`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct GpsFusionConfig {
+    /// The fraction of the distance to each trusted GPS position by which the
+    /// estimate is moved, from 0 to 1.
+    pub position_gain: f64,
+    /// The fraction of the difference to each trusted GPS heading by which the
+    /// estimate is turned, from 0 to 1. This is zero by default, since an
+    /// inertial sensor usually gives a better heading.
+    pub heading_gain: f64,
+    /// The variance, in square degrees, of GPS headings used to correct the
+    /// drift of the drivetrain's fused heading, or `None` to not do so. This
+    /// only has an effect when the drivetrain uses
+    /// [heading fusion](crate::drivetrain::DifferentialDrivetrain::with_heading_fusion()),
+    /// and replaces `heading_gain`, which should then be zero. A large
+    /// variance corrects the heading slowly, so that the GPS noise does not
+    /// reach the heading. Negative variances are ignored, as is a variance of
+    /// zero while the fused heading is also exact.
+    pub heading_variance: Option<f64>,
+    /// The maximum [error](GpsSensor::get_error()) of a GPS reading for its
+    /// heading to be used with `heading_variance`. This is usually stricter
+    /// than `max_error`, as only the best readings should correct the heading.
+    pub heading_max_error: Millimeters,
+    /// The maximum [error](GpsSensor::get_error()) of a GPS reading for it to
+    /// be trusted.
+    pub max_error: Millimeters,
+    /// The maximum distance between a GPS reading and the estimate for the
+    /// reading to be trusted, to reject outliers.
+    pub max_jump: Millimeters,
+    /// The interval between updates of the estimate.
+    pub period: Duration,
+}
+
+impl GpsFusionConfig {
+    /// Creates a new set of fusion parameters with default values.
+    pub fn new() -> Self {
+        Self {
+            position_gain: 0.05,
+            heading_gain: 0.0,
+            heading_variance: None,
+            heading_max_error: Millimeters(10.0),
+            max_error: Millimeters(25.0),
+            max_jump: Millimeters(300.0),
+            period: Duration::from_millis(20),
+        }
+    }
+}
+
+impl Default for GpsFusionConfig {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Fuses the odometry of a [`Chassis`] with readings from a [`GpsSensor`] on a
+/// background task. The task exits when either the estimator or the chassis is
+/// dropped.
+pub struct PoseEstimator(Arc<Mutex<EstimatorData>>);
+
+impl PoseEstimator {
+    #[inline]
+    /// Starts estimating the pose of a chassis. Panics on failure; see
+    /// [`PoseEstimator::try_new()`].
+    pub fn new(chassis: &Chassis, gps: GpsSensor, config: GpsFusionConfig) -> Self {
+        Self::try_new(chassis, gps, config)
+            .unwrap_or_else(|err| panic!("failed to create pose estimator: {:?}", err))
+    }
+
+    /// Starts estimating the pose of a chassis.
+    pub fn try_new(
+        chassis: &Chassis,
+        gps: GpsSensor,
+        config: GpsFusionConfig,
+    ) -> Result<Self, Error> {
+        let period = config.period;
+        let data = Arc::new(Mutex::try_new(EstimatorData {
+            gps,
+            config,
+            broadcast: Broadcast::try_new(chassis.pose())?,
+            last_fix: None,
+        })?);
+        let weak = Arc::downgrade(&data);
+        let chassis = chassis.downgrade();
+        Task::spawn_ext(
+            "pose estimator",
+            Task::DEFAULT_PRIORITY,
+            Task::DEFAULT_STACK_DEPTH,
+            move || {
+                let mut l = Loop::new(period);
+                while let Some(data) = weak.upgrade() {
+                    if data.lock().update(&chassis).is_none() {
+                        break;
+                    }
+                    drop(data);
+                    l.delay();
+                }
+            },
+        )?;
+        Ok(Self(data))
+    }
+
+    #[inline]
+    /// Gets the most recent estimate of the pose.
+    pub fn pose(&self) -> Pose2d {
+        self.0.lock().broadcast.value()
+    }
+
+    #[inline]
+    /// Creates a listener which receives each new estimate of the pose.
+    pub fn listen(&self) -> BroadcastListener<Pose2d> {
+        self.0.lock().broadcast.listen()
+    }
+
+    #[inline]
+    /// Gets the time of the most recent trusted GPS reading, if there has been
+    /// one.
+    pub fn last_fix(&self) -> Option<Instant> {
+        self.0.lock().last_fix
+    }
+
+    #[inline]
+    /// Gets the fusion parameters.
+    pub fn config(&self) -> GpsFusionConfig {
+        self.0.lock().config.clone()
+    }
+
+    #[inline]
+    /// Replaces the fusion parameters. The update period is not changed.
+    pub fn set_config(&self, config: GpsFusionConfig) {
+        self.0.lock().config = config;
+    }
+}
+
+struct EstimatorData {
+    gps: GpsSensor,
+    config: GpsFusionConfig,
+    broadcast: Broadcast<Pose2d>,
+    last_fix: Option<Instant>,
+}
+
+impl EstimatorData {
+    /// Applies the latest GPS reading to the chassis, returning `None` if the
+    /// chassis has been dropped.
+    fn update(&mut self, chassis: &WeakChassis) -> Option<()> {
+        let reading = self
+            .gps
+            .get_error()
+            .and_then(|error| Ok((self.gps.get_pose()?, error)));
+        let config = &self.config;
+        let mut fix = false;
+        let pose = chassis.update_pose(|odometry| match reading {
+            Ok((gps, error))
+                if error <= config.max_error
+                    && odometry.position.distance(gps.position) <= config.max_jump =>
+            {
+                fix = true;
+                Pose2d {
+                    position: odometry.position.lerp(gps.position, config.position_gain),
+                    heading: odometry.heading.lerp(gps.heading, config.heading_gain),
+                }
+            }
+            _ => odometry,
+        })?;
+        if fix {
+            self.last_fix = Some(time_since_start());
+        }
+        let pose = match (reading, config.heading_variance) {
+            (Ok((gps, error)), Some(variance))
+                if fix && error <= config.heading_max_error && variance >= 0.0 =>
+            {
+                chassis.correct_heading(gps.heading, variance)?
+            }
+            _ => pose,
+        };
+        self.broadcast.publish(pose);
+        Some(())
+    }
+}
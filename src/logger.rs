@@ -0,0 +1,531 @@
+//! # Logging.
+//!
+//! A backend for the [`log`] crate. Each record is timestamped with
+//! [`time_since_start()`] and tagged with the name of the task which logged
+//! it, then passed to every registered [`LogSink`] whose level allows it.
+//!
+//! Records are passed to the sinks by a low-priority background task, so that
+//! logging never waits for a slow sink such as the SD card. Records logged
+//! while too many are already waiting are dropped, and the number dropped is
+//! logged at the [`Warn`](Level::Warn) level in their place. Call
+//! [`log::logger()`]`.flush()` to write waiting records immediately.
+//!
+//! # Example
+//!
+//! ```
+//! logger::init(LevelFilter::Info);
+//! logger::add_sink(LevelFilter::Warn, SdSink::append("/usd/log.txt").unwrap());
+//! logger::add_sink(
+//!     LevelFilter::Debug,
+//!     SdSink::rotating("/usd/debug.txt", LogRotation::default()).unwrap(),
+//! );
+//!
+//! log::info!("starting autonomous");
+//! ```
+
+use alloc::{boxed::Box, collections::VecDeque, format, string::String, vec, vec::Vec};
+use core::{
+    mem,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use cstring_interop::with_cstring;
+pub use log::{Level, LevelFilter};
+use log::{Log, Metadata, Record};
+use spin::Once;
+
+use crate::{
+    bindings,
+    io::{eprintln, println},
+    rtos::{time_since_start, Loop, Mutex, Task},
+    sd::{File, SdError},
+};
+
+/// How often the background task writes waiting records to the sinks.
+const WRITE_PERIOD: Duration = Duration::from_millis(20);
+
+/// The maximum number of records waiting to be written. Records logged while
+/// this many are waiting are dropped.
+const MAX_PENDING: usize = 64;
+
+/// The number of lines of small text which fit on the brain screen.
+const SCREEN_LINES: i16 = 12;
+
+/// The height of a line of small text, in pixels.
+const LINE_HEIGHT: i16 = 20;
+
+/// The width of the brain screen, in pixels.
+const SCREEN_WIDTH: i16 = 480;
+
+static SCREEN_ENABLED: AtomicBool = AtomicBool::new(true);
+
+static LOGGER: Once<Logger> = Once::new();
+static WRITER: Once<()> = Once::new();
+
+/// Installs the logger as the backend for the [`log`] crate, printing records
+/// at or above `level` to the serial console.
+///
+/// Calling this function again only changes the level.
+pub fn init(level: LevelFilter) {
+    let mut installed = false;
+    let logger = LOGGER.call_once(|| {
+        installed = true;
+        Logger {
+            pending: Mutex::new(Pending {
+                records: VecDeque::new(),
+                dropped: 0,
+            }),
+            sinks: Mutex::new(vec![(LevelFilter::Trace, Box::new(SerialSink) as _)]),
+        }
+    });
+    if installed && log::set_logger(logger).is_err() {
+        panic!("another logger has already been installed");
+    }
+    log::set_max_level(level);
+}
+
+/// Adds a sink which receives records at or above `level`. Has no effect if
+/// the logger has not been installed with [`init()`].
+pub fn add_sink(level: LevelFilter, sink: impl LogSink + 'static) {
+    if let Some(logger) = LOGGER.get() {
+        logger.sinks.lock().push((level, Box::new(sink)));
+    }
+}
+
+/// Removes all sinks, including the default serial sink.
+pub fn clear_sinks() {
+    if let Some(logger) = LOGGER.get() {
+        logger.sinks.lock().clear();
+    }
+}
+
+/// A destination for log records.
+pub trait LogSink: Send {
+    /// Writes a single formatted record, without a trailing newline.
+    fn write(&mut self, level: Level, line: &str);
+
+    /// Flushes any buffered output. The default implementation does nothing.
+    fn flush(&mut self) {}
+}
+
+/// A [`LogSink`] which prints records to the serial console.
+pub struct SerialSink;
+
+impl LogSink for SerialSink {
+    #[inline]
+    fn write(&mut self, _level: Level, line: &str) {
+        println!("{}", line);
+    }
+}
+
+/// A [`LogSink`] which writes records to a file on the SD card.
+///
+/// The file is flushed to the card after each batch of records written by the
+/// logger's background task, so that the log survives a crash or power loss.
+///
+/// A sink created with [`SdSink::rotating()`] spreads the log over a limited
+/// number of files, so that long practice sessions cannot fill the card or
+/// produce a single unmanageable file.
+pub struct SdSink {
+    file: File,
+    rotation: Option<Rotation>,
+}
+
+impl SdSink {
+    #[inline]
+    /// Creates a sink which writes to a new file at the given path, replacing
+    /// any existing file.
+    pub fn create(path: &str) -> Result<Self, SdError> {
+        Ok(Self {
+            file: File::create(path)?,
+            rotation: None,
+        })
+    }
+
+    #[inline]
+    /// Creates a sink which appends to the file at the given path, creating
+    /// it if it does not exist.
+    pub fn append(path: &str) -> Result<Self, SdError> {
+        Ok(Self {
+            file: File::append(path)?,
+            rotation: None,
+        })
+    }
+
+    /// Creates a sink which starts a new numbered file each time the program
+    /// runs, and whenever the current file reaches the size limit.
+    ///
+    /// The files are named after `path` with a number inserted before the
+    /// extension, e.g. `/usd/log.0.txt`, `/usd/log.1.txt`, and so on, up to
+    /// [`LogRotation::max_files`]; after that, the oldest file is overwritten.
+    /// The number of the next file is kept in a file with the extension
+    /// `.next` (e.g. `/usd/log.next`), so the most recent log is the one before
+    /// it.
+    ///
+    /// The SD card does not support deleting or renaming files, so old logs
+    /// are replaced by reusing their names rather than being removed.
+    pub fn rotating(path: &str, config: LogRotation) -> Result<Self, SdError> {
+        let (stem, extension) = match path.rfind('.') {
+            Some(i) if !path[i..].contains('/') => path.split_at(i),
+            _ => (path, ""),
+        };
+        let mut rotation = Rotation {
+            stem: stem.into(),
+            extension: extension.into(),
+            config,
+            index: 0,
+            written: 0,
+        };
+        if let Ok(mut file) = File::open(&rotation.counter_path()) {
+            let mut buf = Vec::new();
+            file.read_to_end(&mut buf)?;
+            rotation.index = core::str::from_utf8(&buf)
+                .ok()
+                .and_then(|text| text.trim().parse().ok())
+                .unwrap_or(0);
+        }
+        Ok(Self {
+            file: rotation.open()?,
+            rotation: Some(rotation),
+        })
+    }
+
+    fn rotate(&mut self) {
+        if let Some(rotation) = &mut self.rotation {
+            rotation.index = rotation.index.wrapping_add(1);
+            match rotation.open() {
+                Ok(file) => {
+                    self.file = file;
+                    rotation.written = 0;
+                }
+                // Keep writing to the current file rather than losing records.
+                Err(_) => rotation.index = rotation.index.wrapping_sub(1),
+            }
+        }
+    }
+}
+
+impl LogSink for SdSink {
+    fn write(&mut self, _level: Level, line: &str) {
+        let len = line.len() + 1;
+        if let Some(rotation) = &self.rotation {
+            if matches!(rotation.config.max_size, Some(max) if rotation.written > 0 && rotation.written + len > max)
+            {
+                self.rotate();
+            }
+        }
+        if self.file.write_all(line.as_bytes()).is_ok() {
+            self.file.write_all(b"\n").ok();
+        }
+        if let Some(rotation) = &mut self.rotation {
+            rotation.written += len;
+        }
+    }
+
+    #[inline]
+    fn flush(&mut self) {
+        self.file.flush().ok();
+    }
+}
+
+/// Limits on the files written by an [`SdSink`] created with
+/// [`SdSink::rotating()`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LogRotation {
+    /// The size in bytes at which a new file is started, or `None` to only
+    /// start a new file when the program starts. The default is 1MiB.
+    pub max_size: Option<usize>,
+    /// The number of files to keep before overwriting the oldest. The default
+    /// is 8.
+    pub max_files: u32,
+}
+
+impl LogRotation {
+    #[inline]
+    /// Creates the default rotation limits.
+    pub fn new() -> Self {
+        Self {
+            max_size: Some(1024 * 1024),
+            max_files: 8,
+        }
+    }
+}
+
+impl Default for LogRotation {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct Rotation {
+    stem: String,
+    extension: String,
+    config: LogRotation,
+    /// The number of files started so far, including the current one.
+    index: u32,
+    written: usize,
+}
+
+impl Rotation {
+    fn counter_path(&self) -> String {
+        format!("{}.next", self.stem)
+    }
+
+    /// Truncates and opens the file for the current index, then records the
+    /// index of the next file.
+    fn open(&self) -> Result<File, SdError> {
+        let file = File::create(&format!(
+            "{}.{}{}",
+            self.stem,
+            self.index % self.config.max_files.max(1),
+            self.extension
+        ))?;
+        File::create(&self.counter_path())?
+            .write_all(format!("{}\n", self.index.wrapping_add(1)).as_bytes())?;
+        Ok(file)
+    }
+}
+
+/// A [`LogSink`] which shows the most recent records on the brain screen,
+/// colored by severity. Drawing can be turned on and off at runtime with
+/// [`set_screen_enabled()`].
+///
+/// To mirror only warnings and errors to the screen, add the sink with
+/// [`LevelFilter::Warn`]:
+///
+/// ```
+/// logger::add_sink(LevelFilter::Warn, ScreenSink::new());
+/// ```
+pub struct ScreenSink {
+    first_line: i16,
+    capacity: usize,
+    records: VecDeque<(Level, String)>,
+    shown: bool,
+}
+
+impl ScreenSink {
+    #[inline]
+    /// Creates a new screen sink which uses the whole screen.
+    pub fn new() -> Self {
+        Self::lines(0, SCREEN_LINES)
+    }
+
+    /// Creates a new screen sink which uses `count` lines of the screen,
+    /// starting at line `first` (0-11), leaving the rest of the screen free
+    /// for other uses.
+    pub fn lines(first: i16, count: i16) -> Self {
+        let first = first.clamp(0, SCREEN_LINES - 1);
+        let count = count.clamp(1, SCREEN_LINES - first);
+        Self {
+            first_line: first,
+            capacity: count as usize,
+            records: VecDeque::with_capacity(count as usize),
+            shown: false,
+        }
+    }
+
+    fn draw(&self) {
+        unsafe {
+            let pen = bindings::screen_get_pen();
+            for i in 0..self.capacity {
+                let y = (self.first_line + i as i16) * LINE_HEIGHT;
+                bindings::screen_erase_rect(0, y, SCREEN_WIDTH - 1, y + LINE_HEIGHT - 1);
+                if let Some((level, line)) = self.records.get(i) {
+                    bindings::screen_set_pen(level_color(*level));
+                    with_cstring(line.clone(), |line| {
+                        bindings::screen_print_at(
+                            bindings::text_format_e_t_E_TEXT_SMALL,
+                            0,
+                            y,
+                            b"%s\0".as_ptr().cast(),
+                            line.into_raw(),
+                        );
+                    });
+                }
+            }
+            bindings::screen_set_pen(pen);
+        }
+    }
+
+    fn erase(&self) {
+        let y = self.first_line * LINE_HEIGHT;
+        let height = self.capacity as i16 * LINE_HEIGHT;
+        unsafe {
+            bindings::screen_erase_rect(0, y, SCREEN_WIDTH - 1, y + height - 1);
+        }
+    }
+}
+
+impl Default for ScreenSink {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LogSink for ScreenSink {
+    fn write(&mut self, level: Level, line: &str) {
+        if self.records.len() == self.capacity {
+            self.records.pop_front();
+        }
+        self.records.push_back((level, line.into()));
+        if screen_enabled() {
+            self.draw();
+            self.shown = true;
+        } else if self.shown {
+            self.erase();
+            self.shown = false;
+        }
+    }
+}
+
+#[inline]
+/// Turns drawing by every [`ScreenSink`] on or off. Records received while
+/// drawing is off are still kept, and are shown once it is turned back on.
+pub fn set_screen_enabled(enabled: bool) {
+    SCREEN_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+#[inline]
+/// Checks whether drawing by [`ScreenSink`]s is turned on.
+pub fn screen_enabled() -> bool {
+    SCREEN_ENABLED.load(Ordering::Relaxed)
+}
+
+fn level_color(level: Level) -> u32 {
+    match level {
+        Level::Error => 0x00FF_0000,
+        Level::Warn => 0x00FF_FF00,
+        Level::Info => 0x00FF_FFFF,
+        Level::Debug => 0x00D3_D3D3,
+        Level::Trace => 0x0080_8080,
+    }
+}
+
+type Sinks = Vec<(LevelFilter, Box<dyn LogSink>)>;
+
+struct Logger {
+    pending: Mutex<Pending>,
+    sinks: Mutex<Sinks>,
+}
+
+struct Pending {
+    records: VecDeque<(Level, String)>,
+    dropped: usize,
+}
+
+impl Pending {
+    fn take(&mut self) -> (VecDeque<(Level, String)>, usize) {
+        (mem::take(&mut self.records), mem::take(&mut self.dropped))
+    }
+}
+
+impl Logger {
+    /// Writes the waiting records to the sinks, returning whether there were
+    /// any.
+    fn write_pending(&self, sinks: &mut Sinks) -> bool {
+        let pending = self.pending.lock().take();
+        write_records(sinks, pending)
+    }
+}
+
+fn write_records(
+    sinks: &mut Sinks,
+    (records, dropped): (VecDeque<(Level, String)>, usize),
+) -> bool {
+    for (level, line) in &records {
+        for (max, sink) in sinks.iter_mut() {
+            if *level <= *max {
+                sink.write(*level, line);
+            }
+        }
+    }
+    if dropped > 0 {
+        let line = format!("[{} log records dropped]", dropped);
+        for (max, sink) in sinks.iter_mut() {
+            if Level::Warn <= *max {
+                sink.write(Level::Warn, &line);
+            }
+        }
+    }
+    !records.is_empty() || dropped > 0
+}
+
+/// Writes the waiting records to the sinks, unless another task is currently
+/// logging or writing records. Used by the panic handler, which must not
+/// block.
+pub(crate) fn try_flush() {
+    if let Some(logger) = LOGGER.get() {
+        if let Some(mut sinks) = logger.sinks.poll() {
+            if let Some(mut pending) = logger.pending.poll() {
+                let pending = pending.take();
+                write_records(&mut sinks, pending);
+                for (_, sink) in sinks.iter_mut() {
+                    sink.flush();
+                }
+            }
+        }
+    }
+}
+
+impl Log for Logger {
+    #[inline]
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = format!(
+            "[{}] {:<5} [{}] {}: {}",
+            time_since_start(),
+            record.level(),
+            Task::current().name(),
+            record.target(),
+            record.args()
+        );
+        let mut pending = self.pending.lock();
+        if pending.records.len() < MAX_PENDING {
+            pending.records.push_back((record.level(), line));
+        } else {
+            pending.dropped += 1;
+        }
+        drop(pending);
+
+        WRITER.call_once(|| {
+            if let Err(err) = Task::spawn_ext(
+                "logger",
+                bindings::TASK_PRIORITY_MIN,
+                Task::DEFAULT_STACK_DEPTH,
+                || {
+                    let mut l = Loop::new(WRITE_PERIOD);
+                    loop {
+                        if let Some(logger) = LOGGER.get() {
+                            let mut sinks = logger.sinks.lock();
+                            if logger.write_pending(&mut sinks) {
+                                for (_, sink) in sinks.iter_mut() {
+                                    sink.flush();
+                                }
+                            }
+                        }
+                        l.delay();
+                    }
+                },
+            ) {
+                eprintln!("failed to start logger task: {:?}", err);
+            }
+        });
+    }
+
+    fn flush(&self) {
+        let mut sinks = self.sinks.lock();
+        self.write_pending(&mut sinks);
+        for (_, sink) in sinks.iter_mut() {
+            sink.flush();
+        }
+    }
+}
@@ -0,0 +1,59 @@
+#[macro_export]
+/// Checks a condition, reporting a failure according to the
+/// [`AssertPolicy`](crate::assertion::AssertPolicy) instead of always
+/// panicking. Evaluates to whether the condition held. See
+/// [`assertion`](crate::assertion) for details.
+///
+/// # Examples
+///
+/// ```
+/// robot_assert!(voltage.abs() <= 12000);
+/// if !robot_assert!(sensor_ok, "sensor {} failed", port) {
+///     return;
+/// }
+/// ```
+macro_rules! robot_assert {
+    ($condition:expr $(,)?) => {
+        match $condition {
+            true => true,
+            false => {
+                $crate::assertion::fail(
+                    ::core::file!(),
+                    ::core::line!(),
+                    ::core::stringify!($condition),
+                    ::core::option::Option::None,
+                );
+                false
+            }
+        }
+    };
+    ($condition:expr, $($arg:tt)+) => {
+        match $condition {
+            true => true,
+            false => {
+                $crate::assertion::fail(
+                    ::core::file!(),
+                    ::core::line!(),
+                    ::core::stringify!($condition),
+                    ::core::option::Option::Some(::core::format_args!($($arg)+)),
+                );
+                false
+            }
+        }
+    };
+}
+
+#[macro_export]
+/// Like [`robot_assert!`](crate::robot_assert!), but only checked when debug
+/// assertions are enabled. Evaluates to `true` otherwise.
+///
+/// # Examples
+///
+/// ```
+/// robot_debug_assert!(path.len() > 1, "path too short");
+/// ```
+macro_rules! robot_debug_assert {
+    ($($arg:tt)+) => {
+        !::core::cfg!(debug_assertions) || $crate::robot_assert!($($arg)+)
+    };
+}
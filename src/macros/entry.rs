@@ -26,10 +26,12 @@ macro_rules! entry {
 
         #[no_mangle]
         unsafe extern "C" fn initialize() {
+            $crate::build_info::init(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
             ROBOT.call_once(|| {
-                Competition::new($crate::robot::Robot::new(unsafe {
-                    $crate::peripherals::Peripherals::new()
-                }))
+                Competition::new($crate::robot::Robot::new(
+                    $crate::peripherals::Peripherals::take()
+                        .expect("peripherals were taken before initialization"),
+                ))
             });
         }
 
@@ -47,5 +49,79 @@ macro_rules! entry {
         extern "C" fn disabled() {
             ROBOT.wait().disabled();
         }
+
+        $crate::hot_image_info!();
+    };
+}
+
+#[cfg(feature = "hot-cold")]
+#[doc(hidden)]
+#[macro_export]
+/// Defines the build information which the PROS kernel reads from the hot
+/// image when it is installed.
+macro_rules! hot_image_info {
+    () => {
+        #[no_mangle]
+        static _PROS_COMPILE_TIMESTAMP: $crate::macros::CStrPtr =
+            $crate::macros::CStrPtr::new($crate::macros::COMPILE_TIMESTAMP);
+
+        #[no_mangle]
+        static _PROS_COMPILE_TIMESTAMP_INT: i32 = $crate::macros::COMPILE_TIMESTAMP_INT;
+
+        static COMPILE_DIRECTORY: [u8; 23] =
+            $crate::macros::compile_directory(env!("CARGO_MANIFEST_DIR"));
+
+        #[no_mangle]
+        static _PROS_COMPILE_DIRECTORY: $crate::macros::CStrPtr =
+            $crate::macros::CStrPtr::new(&COMPILE_DIRECTORY);
     };
 }
+
+#[cfg(not(feature = "hot-cold"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! hot_image_info {
+    () => {};
+}
+
+#[cfg(feature = "hot-cold")]
+/// A pointer to a null-terminated string, which can be placed in a static.
+#[repr(transparent)]
+pub struct CStrPtr(*const u8);
+
+#[cfg(feature = "hot-cold")]
+impl CStrPtr {
+    /// Creates a new pointer to the start of `s`, which must be
+    /// null-terminated.
+    pub const fn new(s: &'static [u8]) -> Self {
+        Self(s.as_ptr())
+    }
+}
+
+#[cfg(feature = "hot-cold")]
+// SAFETY: the pointer is only ever to immutable static data.
+unsafe impl Sync for CStrPtr {}
+
+#[cfg(feature = "hot-cold")]
+/// The compile timestamp, in the format of the C `__DATE__` and `__TIME__`
+/// macros.
+pub const COMPILE_TIMESTAMP: &[u8] = concat!(env!("VEX_RT_COMPILE_TIMESTAMP"), "\0").as_bytes();
+
+#[cfg(feature = "hot-cold")]
+/// The UNIX timestamp at which the program was compiled.
+pub const COMPILE_TIMESTAMP_INT: i32 = crate::build_info::COMPILE_TIMESTAMP as i32;
+
+#[cfg(feature = "hot-cold")]
+/// Gets the last 22 bytes of a project directory as a null-terminated string,
+/// as the PROS makefile does.
+pub const fn compile_directory(dir: &str) -> [u8; 23] {
+    let dir = dir.as_bytes();
+    let mut buf = [0; 23];
+    let start = if dir.len() > 22 { dir.len() - 22 } else { 0 };
+    let mut i = start;
+    while i < dir.len() {
+        buf[i - start] = dir[i];
+        i += 1;
+    }
+    buf
+}
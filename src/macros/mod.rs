@@ -1,7 +1,13 @@
 #![doc(hidden)]
 
+mod assert;
 mod entry;
+mod print;
+mod profile;
 mod select;
 mod state_machine;
+mod trace;
 
+#[cfg(feature = "hot-cold")]
+pub use entry::{compile_directory, CStrPtr, COMPILE_TIMESTAMP, COMPILE_TIMESTAMP_INT};
 pub use vex_rt_macros::make_state_machine;
@@ -0,0 +1,32 @@
+#[macro_export]
+/// Prints to standard output. Output is buffered and written by a background
+/// task; see [`io`](crate::io) for details.
+///
+/// # Examples
+///
+/// ```
+/// print!("x = {}, ", x);
+/// ```
+macro_rules! print {
+    ($($arg:tt)*) => {
+        $crate::io::_print(::core::format_args!($($arg)*))
+    };
+}
+
+#[macro_export]
+/// Prints to standard output, followed by a newline. Output is buffered and
+/// written by a background task; see [`io`](crate::io) for details.
+///
+/// # Examples
+///
+/// ```
+/// println!("Hello, world!");
+/// ```
+macro_rules! println {
+    () => {
+        $crate::print!("\n")
+    };
+    ($($arg:tt)*) => {
+        $crate::io::_print(::core::format_args!("{}\n", ::core::format_args!($($arg)*)))
+    };
+}
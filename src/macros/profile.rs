@@ -0,0 +1,17 @@
+#[macro_export]
+/// Measures the time taken by the rest of the enclosing scope, recording it
+/// under the given name. See [`profiler`](crate::profiler) for details.
+///
+/// # Examples
+///
+/// ```
+/// fn update(&mut self) {
+///     profile_scope!("update");
+///     // ...
+/// }
+/// ```
+macro_rules! profile_scope {
+    ($name:expr) => {
+        let _profile_scope_guard = $crate::profiler::ScopeGuard::new($name);
+    };
+}
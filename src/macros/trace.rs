@@ -0,0 +1,16 @@
+#[macro_export]
+/// Records an event in the [trace journal](crate::trace), with a static
+/// message and up to three numeric values. Values are converted to [`f64`]
+/// with `as`; no formatting takes place until the journal is dumped.
+///
+/// # Examples
+///
+/// ```
+/// trace!("auton start");
+/// trace!("lift target", target, position);
+/// ```
+macro_rules! trace {
+    ($message:literal $(, $value:expr)* $(,)?) => {
+        $crate::trace::record($message, &[$($value as f64),*])
+    };
+}
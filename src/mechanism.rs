@@ -0,0 +1,452 @@
+//! # Position-Controlled Mechanism API.
+
+use alloc::{
+    boxed::Box,
+    sync::{Arc, Weak},
+};
+use core::time::Duration;
+
+#[cfg(not(feature = "sim"))]
+use num_traits::Float;
+use owner_monad::OwnerMut;
+
+use crate::{
+    adi::AdiDigitalInput,
+    control::{FeedbackController, Settled},
+    error::{DeviceError, Error},
+    escalation,
+    motor::MotorGroup,
+    rotation::RotationSensor,
+    rtos::{
+        handle_event, Event, EventHandle, GenericSleep, Instant, Loop, Mutex, MutexGuard,
+        Selectable, Task,
+    },
+    units::{Degrees, Radians},
+};
+
+const UPDATE_PERIOD: Duration = Duration::from_millis(10);
+
+/// A position-controlled mechanism, such as an arm or lift, which runs its
+/// control loop on a background task.
+///
+/// The output voltage is the sum of a gravity feedforward term and the output
+/// of a [`FeedbackController`] acting on the position of the mechanism. Targets
+/// are clamped to the soft limits in the [`MechanismConfig`], and the
+/// mechanism is never driven further past a limit.
+///
+/// Starting a new movement cancels the current one. The background task stops
+/// the motors and exits when the `PositionMechanism` is dropped.
+pub struct PositionMechanism(Arc<Mutex<MechanismData>>);
+
+impl PositionMechanism {
+    #[inline]
+    /// Creates a new mechanism and spawns its control task. Panics on failure;
+    /// see [`PositionMechanism::try_new()`].
+    pub fn new(
+        motors: MotorGroup,
+        sensor: impl Into<PositionSensor>,
+        controller: impl FeedbackController + Send + 'static,
+        config: MechanismConfig,
+    ) -> Self {
+        Self::try_new(motors, sensor, controller, config)
+            .unwrap_or_else(|err| panic!("failed to create position mechanism: {:?}", err))
+    }
+
+    /// Creates a new mechanism and spawns its control task.
+    ///
+    /// The mechanism starts at the position reported by the sensor, and does
+    /// not move until [`go_to()`](Self::go_to()) or [`home()`](Self::home())
+    /// is called.
+    pub fn try_new(
+        motors: MotorGroup,
+        sensor: impl Into<PositionSensor>,
+        controller: impl FeedbackController + Send + 'static,
+        config: MechanismConfig,
+    ) -> Result<Self, Error> {
+        let settle = Settled::new(config.tolerance.0, config.settle_time);
+        let data = Arc::new(Mutex::try_new(MechanismData {
+            motors,
+            sensor: sensor.into(),
+            limit_switch: None,
+            controller: Box::new(controller),
+            config,
+            offset: Degrees(0.0),
+            position: Degrees(0.0),
+            mode: Mode::Idle,
+            id: 0,
+            settle,
+            done: false,
+            outcome: None,
+            event: Event::new(),
+        })?);
+        let weak = Arc::downgrade(&data);
+        Task::spawn_ext(
+            "mechanism",
+            Task::DEFAULT_PRIORITY,
+            Task::DEFAULT_STACK_DEPTH,
+            move || {
+                let mut l = Loop::new(UPDATE_PERIOD);
+                while let Some(data) = weak.upgrade() {
+                    let stop = data.lock().update();
+                    drop(data);
+                    if stop {
+                        return;
+                    }
+                    l.delay();
+                }
+            },
+        )?;
+        Ok(Self(data))
+    }
+
+    #[inline]
+    /// Adds a limit switch which is pressed when the mechanism is at
+    /// [`MechanismConfig::home_position`], for use by
+    /// [`home()`](Self::home()).
+    pub fn with_limit_switch(self, switch: AdiDigitalInput) -> Self {
+        self.0.lock().limit_switch = Some(switch);
+        self
+    }
+
+    /// Moves the mechanism to the given position, clamped to the soft limits,
+    /// and holds it there.
+    ///
+    /// The result resolves once the mechanism settles within tolerance, or with
+    /// [`MechanismError::Cancelled`] if another movement is started first.
+    pub fn go_to(
+        &'_ self,
+        position: impl Into<Degrees>,
+    ) -> impl Selectable<Result<(), MechanismError>> + '_ {
+        let lock = self.0.lock();
+        let target = lock.config.clamp(position.into());
+        self.start(lock, Mode::Move(target))
+    }
+
+    /// Drives the mechanism towards its limit switch at
+    /// [`MechanismConfig::homing_voltage`] until the switch is pressed, then
+    /// sets the current position to [`MechanismConfig::home_position`] and
+    /// holds it there.
+    ///
+    /// The result resolves once homing is complete, or with
+    /// [`MechanismError::Cancelled`] if another movement is started first.
+    pub fn home(&'_ self) -> impl Selectable<Result<(), MechanismError>> + '_ {
+        let lock = self.0.lock();
+        self.start(lock, Mode::Homing)
+    }
+
+    /// Cancels the current movement and stops the motors.
+    pub fn stop(&self) -> Result<(), MechanismError> {
+        let mut lock = self.0.lock();
+        lock.cancel();
+        lock.mode = Mode::Idle;
+        lock.motors.move_voltage(0)?;
+        Ok(())
+    }
+
+    #[inline]
+    /// Gets the most recently measured position of the mechanism.
+    pub fn position(&self) -> Degrees {
+        self.0.lock().position
+    }
+
+    /// Gets the position the mechanism is moving to or holding, if any.
+    pub fn target(&self) -> Option<Degrees> {
+        match self.0.lock().mode {
+            Mode::Move(target) => Some(target),
+            _ => None,
+        }
+    }
+
+    fn start<'a>(
+        &'a self,
+        mut lock: MutexGuard<'_, MechanismData>,
+        mode: Mode,
+    ) -> MechanismSelect<'a> {
+        lock.cancel();
+        lock.controller.reset();
+        lock.mode = mode;
+        MechanismSelect {
+            mechanism: self,
+            id: lock.id,
+            handle: handle_event(MechanismHandle(Arc::downgrade(&self.0))),
+        }
+    }
+}
+
+impl Drop for PositionMechanism {
+    fn drop(&mut self) {
+        if let Err(err) = self.stop() {
+            escalation::report("mechanism", &err);
+        }
+    }
+}
+
+/// Represents a sensor which can measure the position of a
+/// [`PositionMechanism`].
+pub enum PositionSensor {
+    /// The integrated encoders of the motors, which must be configured to
+    /// report [`EncoderUnits::Degrees`](crate::motor::EncoderUnits::Degrees).
+    Motors,
+    /// A V5 rotation sensor.
+    Rotation(RotationSensor),
+}
+
+impl From<RotationSensor> for PositionSensor {
+    fn from(rotation: RotationSensor) -> Self {
+        Self::Rotation(rotation)
+    }
+}
+
+/// Represents the gravity feedforward of a [`PositionMechanism`], in
+/// millivolts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Gravity {
+    /// No gravity compensation.
+    None,
+    /// A constant voltage, as for a lift or elevator.
+    Constant(f64),
+    /// A voltage proportional to the cosine of the angle from horizontal, as
+    /// for a rotating arm.
+    Arm {
+        /// The voltage required to hold the arm horizontal.
+        kg: f64,
+        /// The position at which the arm is horizontal.
+        horizontal: Degrees,
+    },
+}
+
+impl Gravity {
+    fn feedforward(&self, position: Degrees) -> f64 {
+        match *self {
+            Gravity::None => 0.0,
+            Gravity::Constant(kg) => kg,
+            Gravity::Arm { kg, horizontal } => kg * Radians::from(position - horizontal).cos(),
+        }
+    }
+}
+
+/// Represents the tuning parameters and limits of a [`PositionMechanism`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct MechanismConfig {
+    /// The number of degrees the mechanism moves per degree measured by the
+    /// sensor (i.e., the external gear ratio between the sensor and the
+    /// mechanism).
+    pub ratio: f64,
+    /// The gravity feedforward.
+    pub gravity: Gravity,
+    /// The lowest position the mechanism may be driven to.
+    pub min: Option<Degrees>,
+    /// The highest position the mechanism may be driven to.
+    pub max: Option<Degrees>,
+    /// The maximum output voltage in millivolts.
+    pub max_voltage: i32,
+    /// The position error within which a movement is considered on target.
+    pub tolerance: Degrees,
+    /// How long a movement must remain on target before it is considered
+    /// settled.
+    pub settle_time: Duration,
+    /// The voltage in millivolts applied while homing; its sign determines
+    /// the direction of the limit switch.
+    pub homing_voltage: i32,
+    /// The position of the mechanism when the limit switch is pressed.
+    pub home_position: Degrees,
+}
+
+impl MechanismConfig {
+    /// Creates a new configuration with the given sensor ratio, no gravity
+    /// compensation or soft limits, and default tolerances.
+    pub fn new(ratio: f64) -> Self {
+        Self {
+            ratio,
+            gravity: Gravity::None,
+            min: None,
+            max: None,
+            max_voltage: 12000,
+            tolerance: Degrees(2.0),
+            settle_time: Duration::from_millis(150),
+            homing_voltage: -3000,
+            home_position: Degrees(0.0),
+        }
+    }
+
+    fn clamp(&self, position: Degrees) -> Degrees {
+        let position = self.min.map_or(position, |min| position.max(min));
+        self.max.map_or(position, |max| position.min(max))
+    }
+}
+
+/// Represents possible errors for position mechanism operations.
+#[derive(Debug)]
+pub enum MechanismError {
+    /// A motor or sensor operation failed.
+    Device(DeviceError),
+    /// Homing was requested without a limit switch.
+    NoLimitSwitch,
+    /// The movement was cancelled before it settled.
+    Cancelled,
+}
+
+impl From<DeviceError> for MechanismError {
+    fn from(err: DeviceError) -> Self {
+        Self::Device(err)
+    }
+}
+
+impl From<MechanismError> for Error {
+    fn from(err: MechanismError) -> Self {
+        match err {
+            MechanismError::Device(err) => err.into(),
+            MechanismError::NoLimitSwitch => Error::Custom("no limit switch".into()),
+            MechanismError::Cancelled => Error::Custom("mechanism movement cancelled".into()),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Mode {
+    Idle,
+    Move(Degrees),
+    Homing,
+}
+
+struct MechanismData {
+    motors: MotorGroup,
+    sensor: PositionSensor,
+    limit_switch: Option<AdiDigitalInput>,
+    controller: Box<dyn FeedbackController + Send>,
+    config: MechanismConfig,
+    offset: Degrees,
+    position: Degrees,
+    mode: Mode,
+    id: u32,
+    settle: Settled,
+    done: bool,
+    outcome: Option<Result<(), MechanismError>>,
+    event: Event,
+}
+
+impl MechanismData {
+    /// Runs one iteration of the control loop, returning whether the mechanism
+    /// should stop, according to its [`ErrorPolicy`](escalation::ErrorPolicy).
+    fn update(&mut self) -> bool {
+        let err = match self.step() {
+            Ok(()) => return false,
+            Err(err) => err,
+        };
+        let stop = match self.motors.move_voltage(0) {
+            Ok(()) => false,
+            Err(err) => escalation::report("mechanism", &err),
+        };
+        if let Mode::Idle = self.mode {
+            stop || escalation::report("mechanism", &err)
+        } else {
+            self.mode = Mode::Idle;
+            self.finish(Err(err));
+            stop
+        }
+    }
+
+    fn step(&mut self) -> Result<(), MechanismError> {
+        let raw = match &self.sensor {
+            PositionSensor::Motors => self.motors.get_position()?,
+            PositionSensor::Rotation(rotation) => rotation.get_position()? as f64 / 100.0,
+        };
+        self.position = Degrees(raw * self.config.ratio) + self.offset;
+
+        let output = match self.mode {
+            Mode::Idle => return Ok(()),
+            Mode::Homing => {
+                let pressed = match &self.limit_switch {
+                    Some(switch) => switch.read()?,
+                    None => return Err(MechanismError::NoLimitSwitch),
+                };
+                if pressed {
+                    self.offset += self.config.home_position - self.position;
+                    self.position = self.config.home_position;
+                    self.controller.reset();
+                    self.mode = Mode::Move(self.position);
+                    self.finish(Ok(()));
+                    self.config.gravity.feedforward(self.position)
+                } else {
+                    self.config.homing_voltage as f64
+                }
+            }
+            Mode::Move(target) => {
+                let feedforward = self.config.gravity.feedforward(self.position);
+                let output = feedforward + self.controller.step(target.0, self.position.0);
+                if !self.done && self.settle.update((target - self.position).0) {
+                    self.finish(Ok(()));
+                }
+                // Never drive further past a soft limit than gravity
+                // compensation requires.
+                let below = matches!(self.config.min, Some(min) if self.position <= min);
+                let above = matches!(self.config.max, Some(max) if self.position >= max);
+                if below {
+                    output.max(feedforward)
+                } else if above {
+                    output.min(feedforward)
+                } else {
+                    output
+                }
+            }
+        };
+        let max = self.config.max_voltage as f64;
+        self.motors.move_voltage(output.clamp(-max, max) as i32)?;
+        Ok(())
+    }
+
+    fn finish(&mut self, outcome: Result<(), MechanismError>) {
+        self.settle.reset();
+        self.done = true;
+        self.outcome = Some(outcome);
+        self.event.notify();
+    }
+
+    fn cancel(&mut self) {
+        self.settle.reset();
+        self.done = false;
+        self.outcome = None;
+        self.id = self.id.wrapping_add(1);
+        self.event.notify();
+    }
+}
+
+struct MechanismHandle(Weak<Mutex<MechanismData>>);
+
+impl OwnerMut<Event> for MechanismHandle {
+    fn with<'a, U>(&'a mut self, f: impl FnOnce(&mut Event) -> U) -> Option<U>
+    where
+        Event: 'a,
+    {
+        Some(f(&mut self.0.upgrade()?.lock().event))
+    }
+}
+
+struct MechanismSelect<'a> {
+    mechanism: &'a PositionMechanism,
+    id: u32,
+    handle: EventHandle<MechanismHandle>,
+}
+
+impl<'a> Selectable<Result<(), MechanismError>> for MechanismSelect<'a> {
+    fn poll(self) -> Result<Result<(), MechanismError>, Self> {
+        let mut lock = self.mechanism.0.lock();
+        if lock.id != self.id {
+            Ok(Err(MechanismError::Cancelled))
+        } else if let Some(outcome) = lock.outcome.take() {
+            Ok(outcome)
+        } else {
+            drop(lock);
+            Err(self)
+        }
+    }
+
+    #[inline]
+    fn sleep(&self) -> GenericSleep {
+        if self.handle.is_done() {
+            GenericSleep::Timestamp(Instant::from_millis(0))
+        } else {
+            GenericSleep::NotifyTake(None)
+        }
+    }
+}
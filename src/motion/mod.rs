@@ -0,0 +1,7 @@
+//! Motion planning.
+
+mod spline;
+mod trajectory;
+
+pub use spline::*;
+pub use trajectory::*;
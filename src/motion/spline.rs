@@ -0,0 +1,274 @@
+use alloc::vec::Vec;
+
+#[cfg(not(feature = "sim"))]
+use num_traits::Float;
+
+use crate::{
+    field::{Mirror, Symmetry},
+    geometry::{Pose2d, Rotation2d, Vec2},
+    units::{Millimeters, Radians},
+};
+
+/// The number of parameter steps per spline segment used to integrate arc
+/// length while sampling.
+const INTEGRATION_STEPS: usize = 256;
+
+/// A parametric hermite spline segment between two waypoints, defined for a
+/// parameter `t` in `[0, 1]`.
+///
+/// Each waypoint is a [`Pose2d`], giving the position and the direction of
+/// travel through that position.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HermiteSpline {
+    x: [f64; 6],
+    y: [f64; 6],
+}
+
+impl HermiteSpline {
+    /// Creates a cubic hermite spline between two waypoints.
+    ///
+    /// Cubic splines are continuous in heading but not in curvature across
+    /// waypoints.
+    pub fn cubic(start: Pose2d, end: Pose2d) -> Self {
+        let (x, y) = Self::endpoints(start, end);
+        let cubic = |[p0, m0, p1, m1]: [f64; 4]| {
+            [
+                p0,
+                m0,
+                -3.0 * p0 - 2.0 * m0 + 3.0 * p1 - m1,
+                2.0 * p0 + m0 - 2.0 * p1 + m1,
+                0.0,
+                0.0,
+            ]
+        };
+        Self {
+            x: cubic(x),
+            y: cubic(y),
+        }
+    }
+
+    /// Creates a quintic hermite spline between two waypoints, with zero
+    /// curvature at each end.
+    ///
+    /// Quintic splines are continuous in both heading and curvature across
+    /// waypoints, which produces smoother paths.
+    pub fn quintic(start: Pose2d, end: Pose2d) -> Self {
+        let (x, y) = Self::endpoints(start, end);
+        let quintic = |[p0, v0, p1, v1]: [f64; 4]| {
+            [
+                p0,
+                v0,
+                0.0,
+                -10.0 * p0 - 6.0 * v0 - 4.0 * v1 + 10.0 * p1,
+                15.0 * p0 + 8.0 * v0 + 7.0 * v1 - 15.0 * p1,
+                -6.0 * p0 - 3.0 * v0 - 3.0 * v1 + 6.0 * p1,
+            ]
+        };
+        Self {
+            x: quintic(x),
+            y: quintic(y),
+        }
+    }
+
+    /// Gets the position on the spline at parameter `t`.
+    pub fn position(&self, t: f64) -> Vec2 {
+        Vec2::new(
+            Millimeters(evaluate(&self.x, t)),
+            Millimeters(evaluate(&self.y, t)),
+        )
+    }
+
+    /// Gets the direction of travel along the spline at parameter `t`.
+    pub fn heading(&self, t: f64) -> Rotation2d {
+        let (dx, dy) = (derivative(&self.x, t), derivative(&self.y, t));
+        Radians(dx.atan2(dy)).into()
+    }
+
+    /// Gets the curvature of the spline at parameter `t`, in inverse
+    /// millimetres. Positive values curve clockwise (to the right).
+    pub fn curvature(&self, t: f64) -> f64 {
+        let (dx, dy) = (derivative(&self.x, t), derivative(&self.y, t));
+        let (ddx, ddy) = (second_derivative(&self.x, t), second_derivative(&self.y, t));
+        let speed = dx.hypot(dy);
+        if speed == 0.0 {
+            0.0
+        } else {
+            (ddx * dy - ddy * dx) / (speed * speed * speed)
+        }
+    }
+
+    /// Gets the start position, start tangent, end position and end tangent
+    /// along each axis.
+    fn endpoints(start: Pose2d, end: Pose2d) -> ([f64; 4], [f64; 4]) {
+        // Scaling the tangents by the distance between the waypoints keeps the
+        // shape of the spline independent of its size.
+        let scale = 1.2 * start.position.distance(end.position).0;
+        let (p0, p1) = (start.position, end.position);
+        let (s0, c0) = (start.heading.sin(), start.heading.cos());
+        let (s1, c1) = (end.heading.sin(), end.heading.cos());
+        (
+            [p0.x.0, scale * s0, p1.x.0, scale * s1],
+            [p0.y.0, scale * c0, p1.y.0, scale * c1],
+        )
+    }
+}
+
+/// Represents a sampled point along a [`Path`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PathPoint {
+    /// The position and direction of travel at the point.
+    pub pose: Pose2d,
+    /// The curvature of the path at the point, in inverse millimetres.
+    /// Positive values curve clockwise (to the right).
+    pub curvature: f64,
+    /// The distance along the path from its start to the point.
+    pub distance: Millimeters,
+}
+
+/// A discretized path, suitable for pure pursuit or for generating a
+/// trajectory.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Path(Vec<PathPoint>);
+
+impl Path {
+    #[inline]
+    /// Generates a path through the given waypoints using cubic hermite
+    /// splines, sampled approximately every `spacing` along the path.
+    pub fn cubic(waypoints: &[Pose2d], spacing: impl Into<Millimeters>) -> Self {
+        Self::from_splines(
+            waypoints
+                .windows(2)
+                .map(|w| HermiteSpline::cubic(w[0], w[1])),
+            spacing.into(),
+        )
+    }
+
+    #[inline]
+    /// Generates a path through the given waypoints using quintic hermite
+    /// splines, sampled approximately every `spacing` along the path.
+    pub fn quintic(waypoints: &[Pose2d], spacing: impl Into<Millimeters>) -> Self {
+        Self::from_splines(
+            waypoints
+                .windows(2)
+                .map(|w| HermiteSpline::quintic(w[0], w[1])),
+            spacing.into(),
+        )
+    }
+
+    /// Samples a sequence of connected splines into a path, with points
+    /// approximately every `spacing` along the path.
+    pub fn from_splines(
+        splines: impl IntoIterator<Item = HermiteSpline>,
+        spacing: impl Into<Millimeters>,
+    ) -> Self {
+        let spacing = spacing.into();
+        let mut points = Vec::new();
+        let mut distance = Millimeters(0.0);
+        let mut next = Millimeters(0.0);
+        let mut last = None;
+        let mut end = None;
+        for spline in splines {
+            for i in 0..=INTEGRATION_STEPS {
+                let t = i as f64 / INTEGRATION_STEPS as f64;
+                let position = spline.position(t);
+                if let Some(last) = last {
+                    distance += position.distance(last);
+                }
+                last = Some(position);
+                let point = PathPoint {
+                    pose: Pose2d::new(position, spline.heading(t)),
+                    curvature: spline.curvature(t),
+                    distance,
+                };
+                if distance >= next {
+                    points.push(point);
+                    next = distance + spacing;
+                    end = None;
+                } else {
+                    end = Some(point);
+                }
+            }
+        }
+        // Always finish exactly at the final waypoint.
+        points.extend(end);
+        Self(points)
+    }
+
+    /// Creates a path which passes straight through the given points, with
+    /// headings and curvatures estimated from neighbouring points.
+    pub fn from_points(points: impl IntoIterator<Item = impl Into<Vec2>>) -> Self {
+        let positions: Vec<Vec2> = points.into_iter().map(Into::into).collect();
+        let mut distance = Millimeters(0.0);
+        let points = (0..positions.len())
+            .map(|i| {
+                let position = positions[i];
+                let prev = positions[i.saturating_sub(1)];
+                let next = positions[(i + 1).min(positions.len() - 1)];
+                distance += position.distance(prev);
+                PathPoint {
+                    pose: Pose2d::new(position, (next - prev).angle()),
+                    curvature: three_point_curvature(prev, position, next),
+                    distance,
+                }
+            })
+            .collect();
+        Self(points)
+    }
+
+    #[inline]
+    /// Gets the sampled points along the path.
+    pub fn points(&self) -> &[PathPoint] {
+        &self.0
+    }
+
+    #[inline]
+    /// Gets the total length of the path.
+    pub fn length(&self) -> Millimeters {
+        self.0.last().map_or(Millimeters(0.0), |p| p.distance)
+    }
+}
+
+impl Mirror for PathPoint {
+    fn mirror(&self, symmetry: Symmetry, center: Vec2) -> Self {
+        Self {
+            pose: self.pose.mirror(symmetry, center),
+            curvature: if symmetry.is_reflection() {
+                -self.curvature
+            } else {
+                self.curvature
+            },
+            distance: self.distance,
+        }
+    }
+}
+
+impl Mirror for Path {
+    fn mirror(&self, symmetry: Symmetry, center: Vec2) -> Self {
+        Self(self.0.mirror(symmetry, center))
+    }
+}
+
+fn evaluate(c: &[f64; 6], t: f64) -> f64 {
+    c.iter().rev().fold(0.0, |acc, &c| acc * t + c)
+}
+
+fn derivative(c: &[f64; 6], t: f64) -> f64 {
+    (1..6).rev().fold(0.0, |acc, i| acc * t + i as f64 * c[i])
+}
+
+fn second_derivative(c: &[f64; 6], t: f64) -> f64 {
+    (2..6)
+        .rev()
+        .fold(0.0, |acc, i| acc * t + (i * (i - 1)) as f64 * c[i])
+}
+
+fn three_point_curvature(a: Vec2, b: Vec2, c: Vec2) -> f64 {
+    let (ab, bc) = (b - a, c - b);
+    let product = ab.length().0 * bc.length().0 * a.distance(c).0;
+    if product == 0.0 {
+        0.0
+    } else {
+        // Clockwise turns have a negative cross product.
+        -2.0 * ab.cross(bc) / product
+    }
+}
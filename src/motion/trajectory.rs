@@ -0,0 +1,202 @@
+use alloc::vec::Vec;
+use core::time::Duration;
+
+#[cfg(not(feature = "sim"))]
+use num_traits::Float;
+
+use super::{Path, PathPoint};
+use crate::{
+    field::{Mirror, Symmetry},
+    geometry::Vec2,
+    units::{MetersPerSecond, MetersPerSecondSquared, Millimeters},
+};
+
+/// Represents the limits which a [`Trajectory`] must respect.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrajectoryConstraints {
+    /// The maximum linear speed.
+    pub max_velocity: MetersPerSecond,
+    /// The maximum linear acceleration and deceleration.
+    pub max_acceleration: MetersPerSecondSquared,
+    /// The distance between the left and right wheels, used to slow down so
+    /// that the outer wheel does not exceed `max_velocity` on curves.
+    pub track_width: Option<Millimeters>,
+    /// The maximum centripetal acceleration, used to slow down on tight
+    /// curves.
+    pub max_centripetal_acceleration: Option<MetersPerSecondSquared>,
+}
+
+impl TrajectoryConstraints {
+    /// Creates a new set of constraints with the given velocity and
+    /// acceleration limits, and no curvature-dependent slowdown.
+    pub fn new(max_velocity: MetersPerSecond, max_acceleration: MetersPerSecondSquared) -> Self {
+        Self {
+            max_velocity,
+            max_acceleration,
+            track_width: None,
+            max_centripetal_acceleration: None,
+        }
+    }
+
+    #[inline]
+    /// Sets the track width of a differential drivetrain following the
+    /// trajectory.
+    pub fn track_width(mut self, track_width: impl Into<Millimeters>) -> Self {
+        self.track_width = Some(track_width.into());
+        self
+    }
+
+    #[inline]
+    /// Sets the maximum centripetal acceleration.
+    pub fn max_centripetal_acceleration(mut self, limit: MetersPerSecondSquared) -> Self {
+        self.max_centripetal_acceleration = Some(limit);
+        self
+    }
+
+    /// Gets the maximum speed permitted at a point along a path.
+    fn max_velocity_at(&self, point: &PathPoint) -> f64 {
+        // Curvatures are in inverse millimetres; convert to inverse metres.
+        let curvature = point.curvature.abs() * 1000.0;
+        let mut limit = self.max_velocity.0;
+        if let Some(track_width) = self.track_width {
+            limit /= 1.0 + curvature * track_width.0 / 2000.0;
+        }
+        if let Some(max) = self.max_centripetal_acceleration {
+            if curvature > 0.0 {
+                limit = limit.min((max.0 / curvature).sqrt());
+            }
+        }
+        limit
+    }
+}
+
+/// Represents the desired motion at a point in time along a [`Trajectory`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TrajectoryState {
+    /// The time since the start of the trajectory.
+    pub time: Duration,
+    /// The position along the path.
+    pub point: PathPoint,
+    /// The linear speed.
+    pub velocity: MetersPerSecond,
+    /// The linear acceleration.
+    pub acceleration: MetersPerSecondSquared,
+}
+
+/// A [`Path`] parameterized by time, which starts and ends at rest.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Trajectory(Vec<TrajectoryState>);
+
+impl Trajectory {
+    /// Generates a trajectory along a path which respects the given
+    /// constraints.
+    pub fn generate(path: &Path, constraints: &TrajectoryConstraints) -> Self {
+        let points = path.points();
+        let accel = constraints.max_acceleration.0;
+        let mut velocities: Vec<f64> = points
+            .iter()
+            .map(|p| constraints.max_velocity_at(p))
+            .collect();
+
+        // Limit acceleration from rest at the start, then deceleration to rest
+        // at the end.
+        let n = velocities.len();
+        let ds = |i: usize| (points[i + 1].distance - points[i].distance).0 / 1000.0;
+        if n > 0 {
+            velocities[0] = 0.0;
+            velocities[n - 1] = 0.0;
+        }
+        for i in 1..n {
+            let limit = (velocities[i - 1].powi(2) + 2.0 * accel * ds(i - 1)).sqrt();
+            velocities[i] = velocities[i].min(limit);
+        }
+        for i in (0..n.saturating_sub(1)).rev() {
+            let limit = (velocities[i + 1].powi(2) + 2.0 * accel * ds(i)).sqrt();
+            velocities[i] = velocities[i].min(limit);
+        }
+
+        let mut time = 0.0;
+        let mut states = Vec::with_capacity(n);
+        for i in 0..n {
+            let mut acceleration = 0.0;
+            if i + 1 < n && ds(i) > 0.0 {
+                let (v0, v1) = (velocities[i], velocities[i + 1]);
+                acceleration = (v1 * v1 - v0 * v0) / (2.0 * ds(i));
+            }
+            states.push(TrajectoryState {
+                time: Duration::from_secs_f64(time),
+                point: points[i],
+                velocity: MetersPerSecond(velocities[i]),
+                acceleration: MetersPerSecondSquared(acceleration),
+            });
+            if i + 1 < n && velocities[i] + velocities[i + 1] > 0.0 {
+                time += 2.0 * ds(i) / (velocities[i] + velocities[i + 1]);
+            }
+        }
+        Self(states)
+    }
+
+    #[inline]
+    /// Gets the timed states along the trajectory.
+    pub fn states(&self) -> &[TrajectoryState] {
+        &self.0
+    }
+
+    #[inline]
+    /// Gets the total time taken to follow the trajectory.
+    pub fn duration(&self) -> Duration {
+        self.0.last().map_or(Duration::ZERO, |s| s.time)
+    }
+
+    /// Gets the desired state at the given time since the start of the
+    /// trajectory, interpolating between the generated states.
+    ///
+    /// Times past the end of the trajectory give the final state.
+    pub fn sample(&self, time: Duration) -> TrajectoryState {
+        let states = &self.0;
+        let index = states.partition_point(|s| s.time <= time);
+        if index == 0 {
+            return states.first().copied().unwrap_or_default();
+        }
+        if index == states.len() {
+            return states[index - 1];
+        }
+        let (a, b) = (&states[index - 1], &states[index]);
+        let t = (time - a.time).as_secs_f64();
+        let velocity = a.velocity.0 + a.acceleration.0 * t;
+        let distance =
+            a.point.distance.0 + (a.velocity.0 * t + a.acceleration.0 * t * t / 2.0) * 1000.0;
+        let span = (b.point.distance - a.point.distance).0;
+        let f = if span > 0.0 {
+            ((distance - a.point.distance.0) / span).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let lerp = |a: f64, b: f64| a + (b - a) * f;
+        TrajectoryState {
+            time,
+            point: PathPoint {
+                pose: a.point.pose.lerp(b.point.pose, f),
+                curvature: lerp(a.point.curvature, b.point.curvature),
+                distance: Millimeters(distance),
+            },
+            velocity: MetersPerSecond(velocity),
+            acceleration: a.acceleration,
+        }
+    }
+}
+
+impl Mirror for TrajectoryState {
+    fn mirror(&self, symmetry: Symmetry, center: Vec2) -> Self {
+        Self {
+            point: self.point.mirror(symmetry, center),
+            ..*self
+        }
+    }
+}
+
+impl Mirror for Trajectory {
+    fn mirror(&self, symmetry: Symmetry, center: Vec2) -> Self {
+        Self(self.0.mirror(symmetry, center))
+    }
+}
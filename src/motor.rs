@@ -1,11 +1,42 @@
 //! # Motor API.
 
+use alloc::vec::Vec;
+use core::time::Duration;
+
 use crate::{
     bindings,
-    error::{get_errno, Error},
-    rtos::DataSource,
+    control::Settled,
+    device::MotorLike,
+    diagnostics,
+    error::{DeviceError, DeviceErrorKind, DevicePort},
+    filter::{Ema, Filter},
+    rtos::{Broadcast, BroadcastListener, DataSource, Loop, Mutex, Selectable, Task},
+    smart_port::{smart_port_type, DeviceType},
 };
 
+/// The interval at which [`Motor::settled()`] samples the motor's position.
+const SETTLE_PERIOD: Duration = Duration::from_millis(10);
+
+/// The interval at which motors with soft limits are checked.
+const SOFT_LIMIT_PERIOD: Duration = Duration::from_millis(10);
+
+/// The soft limits of the motor on each smart port, and the direction in which
+/// it was last driven by a voltage or velocity command.
+static SOFT_LIMITS: spin::Once<Mutex<[Option<SoftLimits>; 21]>> = spin::Once::new();
+
+static SOFT_LIMIT_TASK: spin::Once<Task> = spin::Once::new();
+
+fn soft_limits() -> &'static Mutex<[Option<SoftLimits>; 21]> {
+    SOFT_LIMITS.call_once(|| Mutex::new([None; 21]))
+}
+
+#[derive(Clone, Copy)]
+struct SoftLimits {
+    min: f64,
+    max: f64,
+    direction: i32,
+}
+
 /// A struct which represents a V5 smart port configured as a motor.
 pub struct Motor {
     port: u8,
@@ -29,7 +60,7 @@ impl Motor {
         motor.set_reversed(reverse)?;
         motor.set_gearing(gearset)?;
         match unsafe { bindings::motor_set_encoder_units(port, encoder_units.into()) } {
-            bindings::PROS_ERR_ => Err(MotorError::from_errno()),
+            bindings::PROS_ERR_ => Err(motor.error()),
             _ => Ok(motor),
         }
     }
@@ -40,8 +71,9 @@ impl Motor {
     /// stick for simple opcontrol use. The actual behavior of the motor is
     /// analogous to use of [`Motor::move_voltage()`].
     pub fn move_i8(&mut self, voltage: i8) -> Result<(), MotorError> {
-        match unsafe { bindings::motor_move(self.port, voltage as i32) } {
-            bindings::PROS_ERR_ => Err(MotorError::from_errno()),
+        let voltage = self.limit_command(voltage as i32)?;
+        match unsafe { bindings::motor_move(self.port, voltage) } {
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
@@ -55,8 +87,15 @@ impl Motor {
     /// **Note:** This function simply sets the target for the motor, it does
     /// not block program execution until the movement finishes.
     pub fn move_absolute(&mut self, position: f64, velocity: i32) -> Result<(), MotorError> {
+        let position = match self.get_soft_limits() {
+            Some((min, max)) => {
+                self.set_direction(0);
+                position.clamp(min, max)
+            }
+            None => position,
+        };
         match unsafe { bindings::motor_move_absolute(self.port, position, velocity) } {
-            bindings::PROS_ERR_ => Err(MotorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
@@ -71,12 +110,38 @@ impl Motor {
     /// **Note:** This function simply sets the target for the motor, it does
     /// not block program execution until the movement finishes.
     pub fn move_relative(&mut self, position: f64, velocity: i32) -> Result<(), MotorError> {
+        if self.get_soft_limits().is_some() {
+            // Clamp the absolute target instead.
+            return self.move_absolute(self.get_position()? + position, velocity);
+        }
         match unsafe { bindings::motor_move_relative(self.port, position, velocity) } {
-            bindings::PROS_ERR_ => Err(MotorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
 
+    /// A [`Selectable`] event which occurs once the motor has settled at the
+    /// target of the last [`Motor::move_absolute()`] or
+    /// [`Motor::move_relative()`] command, as judged by `settled` from the
+    /// difference between the target and actual positions, in encoder units.
+    ///
+    /// ```
+    /// motor.move_absolute(900.0, 100)?;
+    /// select! {
+    ///     r = motor.settled(&mut Settled::new(5.0, Duration::from_millis(100))) => r?,
+    ///     _ = delay(Duration::from_secs(2)) => println!("lift timed out"),
+    /// }
+    /// ```
+    pub fn settled<'a>(
+        &'a self,
+        settled: &'a mut Settled,
+    ) -> impl Selectable<Result<(), MotorError>> + 'a {
+        settled.reset();
+        settled.select(SETTLE_PERIOD, move || {
+            Ok(self.get_target_position()? - self.get_position()?)
+        })
+    }
+
     /// Sets the velocity for the motor.
     ///
     /// This velocity corresponds to different actual speeds depending on the
@@ -85,8 +150,9 @@ impl Motor {
     /// for [`Gearset::SixToOne`]. The velocity is held with PID to ensure
     /// consistent speed.
     pub fn move_velocity(&mut self, velocity: i32) -> Result<(), MotorError> {
+        let velocity = self.limit_command(velocity)?;
         match unsafe { bindings::motor_move_velocity(self.port, velocity) } {
-            bindings::PROS_ERR_ => Err(MotorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
@@ -94,8 +160,9 @@ impl Motor {
     /// Sets the output voltage for the motor from -12000 to 12000 in
     /// millivolts.
     pub fn move_voltage(&mut self, voltage: i32) -> Result<(), MotorError> {
+        let voltage = self.limit_command(voltage)?;
         match unsafe { bindings::motor_move_voltage(self.port, voltage) } {
-            bindings::PROS_ERR_ => Err(MotorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
@@ -105,7 +172,7 @@ impl Motor {
     /// will have no effect if the motor is not following a profiled movement.
     pub fn modify_profiled_velocity(&mut self, velocity: i32) -> Result<(), MotorError> {
         match unsafe { bindings::motor_modify_profiled_velocity(self.port, velocity) } {
-            bindings::PROS_ERR_ => Err(MotorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
@@ -113,7 +180,7 @@ impl Motor {
     /// Gets the target position set for the motor by the user.
     pub fn get_target_position(&self) -> Result<f64, MotorError> {
         match unsafe { bindings::motor_get_target_position(self.port) } {
-            x if x == bindings::PROS_ERR_F_ => Err(MotorError::from_errno()),
+            x if x == bindings::PROS_ERR_F_ => Err(self.error()),
             x => Ok(x),
         }
     }
@@ -121,7 +188,7 @@ impl Motor {
     /// Gets the velocity commanded to the motor by the user.
     pub fn get_target_velocity(&self) -> Result<i32, MotorError> {
         match unsafe { bindings::motor_get_target_velocity(self.port) } {
-            bindings::PROS_ERR_ => Err(MotorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             x => Ok(x),
         }
     }
@@ -129,7 +196,7 @@ impl Motor {
     /// Gets the actual velocity of the motor.
     pub fn get_actual_velocity(&self) -> Result<f64, MotorError> {
         match unsafe { bindings::motor_get_actual_velocity(self.port) } {
-            x if x == bindings::PROS_ERR_F_ => Err(MotorError::from_errno()),
+            x if x == bindings::PROS_ERR_F_ => Err(self.error()),
             x => Ok(x),
         }
     }
@@ -137,7 +204,7 @@ impl Motor {
     /// Gets the current drawn by the motor in milliamperes.
     pub fn get_current_draw(&self) -> Result<i32, MotorError> {
         match unsafe { bindings::motor_get_current_draw(self.port) } {
-            bindings::PROS_ERR_ => Err(MotorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             x => Ok(x),
         }
     }
@@ -145,7 +212,7 @@ impl Motor {
     /// Gets the direction of movement for the motor.
     pub fn get_direction(&self) -> Result<Direction, MotorError> {
         match unsafe { bindings::motor_get_direction(self.port) } {
-            bindings::PROS_ERR_ => Err(MotorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             1 => Ok(Direction::Positive),
             -1 => Ok(Direction::Negative),
             x => panic!(
@@ -158,7 +225,7 @@ impl Motor {
     /// Gets the efficiency of the motor in percent.
     pub fn get_efficiency(&self) -> Result<f64, MotorError> {
         match unsafe { bindings::motor_get_efficiency(self.port) } {
-            x if x == bindings::PROS_ERR_F_ => Err(MotorError::from_errno()),
+            x if x == bindings::PROS_ERR_F_ => Err(self.error()),
             x => Ok(x),
         }
     }
@@ -166,7 +233,7 @@ impl Motor {
     /// Gets the absolute position of the motor in encoder ticks.
     pub fn get_position(&self) -> Result<f64, MotorError> {
         match unsafe { bindings::motor_get_position(self.port) } {
-            x if x == bindings::PROS_ERR_F_ => Err(MotorError::from_errno()),
+            x if x == bindings::PROS_ERR_F_ => Err(self.error()),
             x => Ok(x),
         }
     }
@@ -174,7 +241,7 @@ impl Motor {
     /// Gets the power drawn by the motor in Watts.
     pub fn get_power(&self) -> Result<f64, MotorError> {
         match unsafe { bindings::motor_get_power(self.port) } {
-            x if x == bindings::PROS_ERR_F_ => Err(MotorError::from_errno()),
+            x if x == bindings::PROS_ERR_F_ => Err(self.error()),
             x => Ok(x),
         }
     }
@@ -182,7 +249,7 @@ impl Motor {
     /// Gets the temperature of the motor in degrees Celsius.
     pub fn get_temperature(&self) -> Result<f64, MotorError> {
         match unsafe { bindings::motor_get_temperature(self.port) } {
-            x if x == bindings::PROS_ERR_F_ => Err(MotorError::from_errno()),
+            x if x == bindings::PROS_ERR_F_ => Err(self.error()),
             x => Ok(x),
         }
     }
@@ -190,7 +257,7 @@ impl Motor {
     /// Gets the torque of the motor in Newton-Meters.
     pub fn get_torque(&self) -> Result<f64, MotorError> {
         match unsafe { bindings::motor_get_torque(self.port) } {
-            x if x == bindings::PROS_ERR_F_ => Err(MotorError::from_errno()),
+            x if x == bindings::PROS_ERR_F_ => Err(self.error()),
             x => Ok(x),
         }
     }
@@ -198,7 +265,7 @@ impl Motor {
     /// Gets the voltage delivered to the motor in millivolts.
     pub fn get_voltage(&self) -> Result<i32, MotorError> {
         match unsafe { bindings::motor_get_voltage(self.port) } {
-            x if x == bindings::PROS_ERR_ => Err(MotorError::from_errno()),
+            x if x == bindings::PROS_ERR_ => Err(self.error()),
             x => Ok(x),
         }
     }
@@ -206,7 +273,7 @@ impl Motor {
     /// Checks if the motor is drawing over its current limit.
     pub fn is_over_current(&self) -> Result<bool, MotorError> {
         match unsafe { bindings::motor_is_over_current(self.port) } {
-            bindings::PROS_ERR_ => Err(MotorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             0 => Ok(false),
             _ => Ok(true),
         }
@@ -215,7 +282,7 @@ impl Motor {
     /// Checks if the motor's temperature is above its limit.
     pub fn is_over_temp(&self) -> Result<bool, MotorError> {
         match unsafe { bindings::motor_is_over_temp(self.port) } {
-            bindings::PROS_ERR_ => Err(MotorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             0 => Ok(false),
             _ => Ok(true),
         }
@@ -227,7 +294,7 @@ impl Motor {
             bindings::motor_brake_mode_e_E_MOTOR_BRAKE_BRAKE => Ok(BrakeMode::Brake),
             bindings::motor_brake_mode_e_E_MOTOR_BRAKE_COAST => Ok(BrakeMode::Coast),
             bindings::motor_brake_mode_e_E_MOTOR_BRAKE_HOLD => Ok(BrakeMode::Hold),
-            bindings::motor_brake_mode_e_E_MOTOR_BRAKE_INVALID => Err(MotorError::from_errno()),
+            bindings::motor_brake_mode_e_E_MOTOR_BRAKE_INVALID => Err(self.error()),
             x => panic!(
                 "bindings::motor_get_brake_mode returned unexpected value: {}.",
                 x
@@ -241,7 +308,7 @@ impl Motor {
     /// be lower if more then 8 motors are competing for power.
     pub fn get_current_limit(&self) -> Result<i32, MotorError> {
         match unsafe { bindings::motor_get_current_limit(self.port) } {
-            bindings::PROS_ERR_ => Err(MotorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             x => Ok(x),
         }
     }
@@ -252,7 +319,7 @@ impl Motor {
             bindings::motor_gearset_e_E_MOTOR_GEARSET_36 => Ok(Gearset::SixToOne),
             bindings::motor_gearset_e_E_MOTOR_GEARSET_18 => Ok(Gearset::EighteenToOne),
             bindings::motor_gearset_e_E_MOTOR_GEARSET_06 => Ok(Gearset::ThirtySixToOne),
-            bindings::motor_gearset_e_E_MOTOR_GEARSET_INVALID => Err(MotorError::from_errno()),
+            bindings::motor_gearset_e_E_MOTOR_GEARSET_INVALID => Err(self.error()),
             x => panic!(
                 "bindings::motor_get_gearing returned unexpected value: {}.",
                 x
@@ -266,7 +333,7 @@ impl Motor {
     /// imposed on the voltage.
     pub fn get_voltage_limit(&self) -> Result<i32, MotorError> {
         match unsafe { bindings::motor_get_voltage_limit(self.port) } {
-            bindings::PROS_ERR_ => Err(MotorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             x => Ok(x),
         }
     }
@@ -276,7 +343,7 @@ impl Motor {
     /// Returns 1 if the motor has been reversed and 0 if the motor was not.
     pub fn is_reversed(&self) -> Result<bool, MotorError> {
         match unsafe { bindings::motor_is_reversed(self.port) } {
-            bindings::PROS_ERR_ => Err(MotorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             0 => Ok(false),
             _ => Ok(true),
         }
@@ -285,7 +352,7 @@ impl Motor {
     /// Gets the brake mode that was set for the motor.
     pub fn set_brake_mode(&mut self, mode: BrakeMode) -> Result<(), MotorError> {
         match unsafe { bindings::motor_set_brake_mode(self.port, mode.into()) } {
-            bindings::PROS_ERR_ => Err(MotorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
@@ -293,7 +360,7 @@ impl Motor {
     /// Sets the current limit for the motor in milliamperes.
     pub fn set_current_limit(&mut self, limit: i32) -> Result<(), MotorError> {
         match unsafe { bindings::motor_set_current_limit(self.port, limit) } {
-            bindings::PROS_ERR_ => Err(MotorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
@@ -301,7 +368,7 @@ impl Motor {
     /// Sets one of [`Gearset`] for the motor.
     pub fn set_gearing(&mut self, gearset: Gearset) -> Result<(), MotorError> {
         match unsafe { bindings::motor_set_gearing(self.port, gearset.into()) } {
-            bindings::PROS_ERR_ => Err(MotorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
@@ -311,7 +378,7 @@ impl Motor {
     /// This will invert its movements and the values returned for its position.
     pub fn set_reversed(&mut self, reverse: bool) -> Result<(), MotorError> {
         match unsafe { bindings::motor_set_reversed(self.port, reverse) } {
-            bindings::PROS_ERR_ => Err(MotorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
@@ -319,7 +386,7 @@ impl Motor {
     /// Sets the voltage limit for the motor in Volts.
     pub fn set_voltage_limit(&mut self, limit: i32) -> Result<(), MotorError> {
         match unsafe { bindings::motor_set_voltage_limit(self.port, limit) } {
-            bindings::PROS_ERR_ => Err(MotorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
@@ -327,7 +394,7 @@ impl Motor {
     /// Sets the "absolute" zero position of the motor.
     pub fn set_zero_position(&mut self, position: f64) -> Result<(), MotorError> {
         match unsafe { bindings::motor_set_zero_position(self.port, position) } {
-            bindings::PROS_ERR_ => Err(MotorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
@@ -335,7 +402,7 @@ impl Motor {
     /// Sets the "absolute" zero position of the motor to its current position.
     pub fn tare_position(&mut self) -> Result<(), MotorError> {
         match unsafe { bindings::motor_tare_position(self.port) } {
-            bindings::PROS_ERR_ => Err(MotorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
@@ -343,7 +410,7 @@ impl Motor {
     /// Sets the [`EncoderUnits`] for the motor.
     pub fn set_encoder_units(&mut self, units: EncoderUnits) -> Result<(), MotorError> {
         match unsafe { bindings::motor_set_encoder_units(self.port, units.into()) } {
-            bindings::PROS_ERR_ => Err(MotorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
@@ -358,12 +425,146 @@ impl Motor {
             bindings::motor_encoder_units_e_E_MOTOR_ENCODER_ROTATIONS => {
                 Ok(EncoderUnits::Rotations)
             }
-            bindings::motor_encoder_units_e_E_MOTOR_ENCODER_INVALID => {
-                Err(MotorError::from_errno())
-            }
+            bindings::motor_encoder_units_e_E_MOTOR_ENCODER_INVALID => Err(self.error()),
             x => panic!("bindings:get_encoder_units returned unexpected value {}", x),
         }
     }
+
+    /// Sets soft limits on the position of the motor, in its encoder units.
+    ///
+    /// While the motor is at or beyond a limit, commands which would drive it
+    /// further out of range are replaced with a command to stop, and the
+    /// targets of profiled movements are clamped to the range. A background
+    /// task also stops the motor if it passes a limit while still being driven
+    /// outwards, e.g. after a single call to [`Motor::move_voltage()`]. This
+    /// protects a mechanism from bugs in the code driving it.
+    ///
+    /// The limits are stored per port, so they apply to every handle to the
+    /// motor, and are relative to the zero position at the time they are
+    /// checked; see [`Motor::set_zero_position()`].
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `min` is greater than `max`, or if the
+    /// background task cannot be created.
+    pub fn set_soft_limits(&mut self, min: f64, max: f64) -> Result<(), MotorError> {
+        assert!(min <= max, "soft limit minimum is greater than maximum");
+        let position = self.get_position()?;
+        if let Some(slot) = soft_limit_slot(&mut soft_limits().lock(), self.port) {
+            *slot = Some(SoftLimits {
+                min,
+                max,
+                direction: 0,
+            });
+        }
+        SOFT_LIMIT_TASK.call_once(|| {
+            Task::spawn_ext(
+                "soft limits",
+                bindings::TASK_PRIORITY_MAX,
+                Task::DEFAULT_STACK_DEPTH,
+                supervise_soft_limits,
+            )
+            .unwrap_or_else(|err| panic!("failed to create soft limit task: {:?}", err))
+        });
+        if position < min || position > max {
+            log::warn!(
+                "motor on port {} is outside its soft limits: {}",
+                self.port,
+                position
+            );
+        }
+        Ok(())
+    }
+
+    /// Removes the soft limits set with [`Motor::set_soft_limits()`].
+    pub fn clear_soft_limits(&mut self) {
+        if let Some(slot) = soft_limit_slot(&mut soft_limits().lock(), self.port) {
+            *slot = None;
+        }
+    }
+
+    /// Gets the soft limits set with [`Motor::set_soft_limits()`], as the
+    /// minimum and maximum positions.
+    pub fn get_soft_limits(&self) -> Option<(f64, f64)> {
+        let limits = (*soft_limits()
+            .lock()
+            .get((self.port as usize).checked_sub(1)?)?)?;
+        Some((limits.min, limits.max))
+    }
+
+    /// Replaces a voltage or velocity command with zero if it would drive the
+    /// motor further beyond a soft limit, and records its direction.
+    fn limit_command(&self, command: i32) -> Result<i32, MotorError> {
+        let (min, max) = match self.get_soft_limits() {
+            Some(limits) => limits,
+            None => return Ok(command),
+        };
+        let position = self.get_position()?;
+        let command = if (command > 0 && position >= max) || (command < 0 && position <= min) {
+            0
+        } else {
+            command
+        };
+        self.set_direction(command.signum());
+        Ok(command)
+    }
+
+    /// Records the direction in which the motor is being driven, for the soft
+    /// limit task, or zero for a profiled movement.
+    fn set_direction(&self, direction: i32) {
+        if let Some(Some(limits)) = soft_limit_slot(&mut soft_limits().lock(), self.port) {
+            limits.direction = direction;
+        }
+    }
+
+    fn error(&self) -> DeviceError {
+        diagnostics::record_error(DeviceError::from_errno(
+            DevicePort::Smart(self.port),
+            "a motor",
+        ))
+    }
+}
+
+fn soft_limit_slot(
+    table: &mut [Option<SoftLimits>; 21],
+    port: u8,
+) -> Option<&mut Option<SoftLimits>> {
+    (port as usize)
+        .checked_sub(1)
+        .and_then(move |i| table.get_mut(i))
+}
+
+/// Stops any motor which has passed one of its soft limits while still being
+/// driven outwards.
+fn supervise_soft_limits() {
+    let mut l = Loop::new(SOFT_LIMIT_PERIOD);
+    loop {
+        let table = *soft_limits().lock();
+        for (i, limits) in table.iter().enumerate() {
+            if !matches!(limits, Some(limits) if limits.direction != 0) {
+                continue;
+            }
+            let port = i as u8 + 1;
+            let position = unsafe { bindings::motor_get_position(port) };
+            if position == bindings::PROS_ERR_F_ {
+                continue;
+            }
+            // Check again under the lock, in case a command has been sent
+            // since the table was copied.
+            let mut guard = soft_limits().lock();
+            if let Some(Some(limits)) = soft_limit_slot(&mut guard, port) {
+                if (limits.direction > 0 && position >= limits.max)
+                    || (limits.direction < 0 && position <= limits.min)
+                {
+                    limits.direction = 0;
+                    unsafe { bindings::motor_move_voltage(port, 0) };
+                    drop(guard);
+                    log::warn!("motor on port {} stopped at its soft limit", port);
+                }
+            }
+        }
+        l.delay();
+    }
 }
 
 impl DataSource for Motor {
@@ -393,6 +594,38 @@ impl DataSource for Motor {
     }
 }
 
+impl MotorLike for Motor {
+    #[inline]
+    fn move_voltage(&mut self, voltage: i32) -> Result<(), DeviceError> {
+        Motor::move_voltage(self, voltage)
+    }
+
+    #[inline]
+    fn move_velocity(&mut self, velocity: i32) -> Result<(), DeviceError> {
+        Motor::move_velocity(self, velocity)
+    }
+
+    #[inline]
+    fn position(&self) -> Result<f64, DeviceError> {
+        self.get_position()
+    }
+
+    #[inline]
+    fn velocity(&self) -> Result<f64, DeviceError> {
+        self.get_actual_velocity()
+    }
+
+    #[inline]
+    fn current_draw(&self) -> Result<i32, DeviceError> {
+        self.get_current_draw()
+    }
+
+    #[inline]
+    fn tare_position(&mut self) -> Result<(), DeviceError> {
+        Motor::tare_position(self)
+    }
+}
+
 /// Represents the data that can be read from a motor.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct MotorData {
@@ -430,37 +663,556 @@ pub struct MotorData {
     pub voltage_limit: i32,
 }
 
-/// Represents possible errors for motor operations.
-#[derive(Debug)]
-pub enum MotorError {
-    /// Port is out of range (1-21).
-    PortOutOfRange,
-    /// Port cannot be configured as a motor.
-    PortNotMotor,
-    /// Unknown error.
-    Unknown(i32),
+/// A struct which represents a group of motors which are commanded together,
+/// such as one side of a drivetrain.
+///
+/// A group can optionally limit its output as its motors heat up; see
+/// [`MotorGroup::with_thermal_limit()`].
+pub struct MotorGroup {
+    motors: Vec<Motor>,
+    thermal: Option<ThermalManager>,
+}
+
+impl MotorGroup {
+    /// Constructs a new motor group from a list of motors.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `motors` is empty.
+    pub fn new(motors: Vec<Motor>) -> Self {
+        assert!(!motors.is_empty(), "motor group must not be empty");
+        Self {
+            motors,
+            thermal: None,
+        }
+    }
+
+    /// Limits the output of the group as its hottest motor approaches the
+    /// temperature at which the motor firmware cuts its power, so that the
+    /// robot slows down gradually rather than losing a motor suddenly.
+    ///
+    /// Every voltage and velocity command is scaled by the output fraction in
+    /// the group's [`ThermalStatus`], which is updated on each command.
+    pub fn with_thermal_limit(mut self, limit: ThermalLimit) -> Self {
+        self.thermal = Some(ThermalManager {
+            filter: Ema::new(limit.smoothing),
+            limit,
+            broadcast: Broadcast::new(ThermalStatus::default()),
+        });
+        self
+    }
+
+    #[inline]
+    /// Gets the thermal status of the group, if a thermal limit has been set.
+    pub fn thermal_status(&self) -> Option<ThermalStatus> {
+        self.thermal.as_ref().map(|t| t.broadcast.value())
+    }
+
+    #[inline]
+    /// Creates a listener which receives each change in the thermal status of
+    /// the group, e.g. to show a warning on the controller screen, if a
+    /// thermal limit has been set.
+    pub fn listen_thermal(&self) -> Option<BroadcastListener<ThermalStatus>> {
+        self.thermal.as_ref().map(|t| t.broadcast.listen())
+    }
+
+    #[inline]
+    /// Gets the motors in the group.
+    pub fn motors(&self) -> &[Motor] {
+        &self.motors
+    }
+
+    #[inline]
+    /// Gets mutable references to the motors in the group.
+    pub fn motors_mut(&mut self) -> &mut [Motor] {
+        &mut self.motors
+    }
+
+    /// Sets the voltage for all motors in the group from -127 to 127.
+    ///
+    /// See [`Motor::move_i8()`].
+    pub fn move_i8(&mut self, voltage: i8) -> Result<(), MotorError> {
+        let voltage = (voltage as f64 * self.derating()?) as i8;
+        self.motors.iter_mut().try_for_each(|m| m.move_i8(voltage))
+    }
+
+    /// Sets the velocity for all motors in the group.
+    ///
+    /// See [`Motor::move_velocity()`].
+    pub fn move_velocity(&mut self, velocity: i32) -> Result<(), MotorError> {
+        let velocity = (velocity as f64 * self.derating()?) as i32;
+        self.motors
+            .iter_mut()
+            .try_for_each(|m| m.move_velocity(velocity))
+    }
+
+    /// Sets the output voltage for all motors in the group from -12000 to 12000
+    /// in millivolts.
+    pub fn move_voltage(&mut self, voltage: i32) -> Result<(), MotorError> {
+        let voltage = (voltage as f64 * self.derating()?) as i32;
+        self.motors
+            .iter_mut()
+            .try_for_each(|m| m.move_voltage(voltage))
+    }
+
+    /// Gets the average position of the motors in the group.
+    pub fn get_position(&self) -> Result<f64, MotorError> {
+        self.average(Motor::get_position)
+    }
+
+    /// Gets the average actual velocity of the motors in the group.
+    pub fn get_actual_velocity(&self) -> Result<f64, MotorError> {
+        self.average(Motor::get_actual_velocity)
+    }
+
+    /// Gets the total current drawn by the motors in the group in
+    /// milliamperes.
+    pub fn get_current_draw(&self) -> Result<i32, MotorError> {
+        self.motors.iter().map(Motor::get_current_draw).sum()
+    }
+
+    /// Sets the brake mode for all motors in the group.
+    pub fn set_brake_mode(&mut self, mode: BrakeMode) -> Result<(), MotorError> {
+        self.motors
+            .iter_mut()
+            .try_for_each(|m| m.set_brake_mode(mode))
+    }
+
+    /// Sets the "absolute" zero position of all motors in the group to their
+    /// current positions.
+    pub fn tare_position(&mut self) -> Result<(), MotorError> {
+        self.motors.iter_mut().try_for_each(Motor::tare_position)
+    }
+
+    /// Updates the thermal status, returning the fraction of each command to
+    /// output.
+    fn derating(&mut self) -> Result<f64, MotorError> {
+        let thermal = match &mut self.thermal {
+            Some(thermal) => thermal,
+            None => return Ok(1.0),
+        };
+        let mut hottest = f64::NEG_INFINITY;
+        for motor in self.motors.iter() {
+            hottest = hottest.max(motor.get_temperature()?);
+        }
+        let temperature = thermal.filter.filter(hottest);
+        let status = ThermalStatus {
+            temperature,
+            output: thermal.limit.output(temperature),
+        };
+        if status != thermal.broadcast.value() {
+            thermal.broadcast.publish(status);
+        }
+        Ok(status.output)
+    }
+
+    fn average(&self, f: impl Fn(&Motor) -> Result<f64, MotorError>) -> Result<f64, MotorError> {
+        let mut sum = 0.0;
+        for motor in self.motors.iter() {
+            sum += f(motor)?;
+        }
+        Ok(sum / self.motors.len() as f64)
+    }
+}
+
+impl MotorLike for MotorGroup {
+    #[inline]
+    fn move_voltage(&mut self, voltage: i32) -> Result<(), DeviceError> {
+        MotorGroup::move_voltage(self, voltage)
+    }
+
+    #[inline]
+    fn move_velocity(&mut self, velocity: i32) -> Result<(), DeviceError> {
+        MotorGroup::move_velocity(self, velocity)
+    }
+
+    #[inline]
+    fn position(&self) -> Result<f64, DeviceError> {
+        self.get_position()
+    }
+
+    #[inline]
+    fn velocity(&self) -> Result<f64, DeviceError> {
+        self.get_actual_velocity()
+    }
+
+    #[inline]
+    fn current_draw(&self) -> Result<i32, DeviceError> {
+        self.get_current_draw()
+    }
+
+    #[inline]
+    fn tare_position(&mut self) -> Result<(), DeviceError> {
+        MotorGroup::tare_position(self)
+    }
+}
+
+impl From<Vec<Motor>> for MotorGroup {
+    fn from(motors: Vec<Motor>) -> Self {
+        Self::new(motors)
+    }
+}
+
+/// Represents the parameters of the thermal limit of a [`MotorGroup`].
+///
+/// The output of the group is scaled down linearly from full output at
+/// `start` to `min_output` at `end`, based on the temperature of its hottest
+/// motor. V5 motors report their temperature in steps of 5°C, so the
+/// temperature is smoothed to avoid sudden changes in output.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ThermalLimit {
+    /// The temperature at which the output starts to be limited, in degrees
+    /// Celsius.
+    pub start: f64,
+    /// The temperature at which the output reaches its minimum, in degrees
+    /// Celsius.
+    pub end: f64,
+    /// The minimum fraction of each command which is output, from 0 to 1.
+    pub min_output: f64,
+    /// The smoothing factor applied to the temperature on each command; see
+    /// [`Ema::new()`].
+    pub smoothing: f64,
 }
 
-impl MotorError {
-    fn from_errno() -> Self {
-        match get_errno() {
-            libc::ENXIO => Self::PortOutOfRange,
-            libc::ENODEV => Self::PortNotMotor,
-            x => Self::Unknown(x),
+impl ThermalLimit {
+    /// Creates a new set of thermal limit parameters with default values,
+    /// which limit the output between 45°C and 55°C, the temperature at which
+    /// the motor firmware starts to limit its current.
+    pub fn new() -> Self {
+        Self {
+            start: 45.0,
+            end: 55.0,
+            min_output: 0.5,
+            smoothing: 0.02,
         }
     }
+
+    /// Gets the fraction of each command to output at the given temperature.
+    fn output(&self, temperature: f64) -> f64 {
+        let t = ((temperature - self.start) / (self.end - self.start)).clamp(0.0, 1.0);
+        1.0 - t * (1.0 - self.min_output)
+    }
+}
+
+impl Default for ThermalLimit {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Represents the thermal status of a [`MotorGroup`] with a thermal limit.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ThermalStatus {
+    /// The smoothed temperature of the hottest motor in the group, in degrees
+    /// Celsius.
+    pub temperature: f64,
+    /// The fraction of each command which is output, from 0 to 1.
+    pub output: f64,
+}
+
+impl ThermalStatus {
+    #[inline]
+    /// Checks whether the output of the group is being limited.
+    pub fn is_limited(&self) -> bool {
+        self.output < 1.0
+    }
+}
+
+impl Default for ThermalStatus {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            temperature: 0.0,
+            output: 1.0,
+        }
+    }
+}
+
+struct ThermalManager {
+    limit: ThermalLimit,
+    filter: Ema,
+    broadcast: Broadcast<ThermalStatus>,
+}
+
+/// A [`Motor`] which tolerates brief disconnections, e.g. from a loose cable.
+///
+/// While the motor is disconnected, commands are dropped and readings return
+/// their last known values rather than failing, so that control code is not
+/// interrupted by a momentary glitch. A motor which loses power forgets its
+/// configuration and resets its encoder, so the brake mode, limits, gearing
+/// and direction are cached and reapplied when the motor is seen again, and
+/// the position continues from its last known value.
+///
+/// Each disconnection and reconnection is logged with the [`log`] crate and
+/// published to listeners created with [`ResilientMotor::listen()`]. A
+/// disconnection is not otherwise reported, so a
+/// [`HealthMonitor`](crate::health::HealthMonitor) should be used to catch
+/// persistent problems.
+pub struct ResilientMotor {
+    motor: Motor,
+    settings: MotorSettings,
+    link: Mutex<MotorLink>,
+    broadcast: Broadcast<bool>,
 }
 
-impl From<MotorError> for Error {
-    fn from(err: MotorError) -> Self {
-        match err {
-            MotorError::PortOutOfRange => Error::Custom("port out of range".into()),
-            MotorError::PortNotMotor => Error::Custom("port not a motor".into()),
-            MotorError::Unknown(n) => Error::System(n),
+impl ResilientMotor {
+    /// Wraps a motor, caching its current configuration. The motor must be
+    /// connected.
+    pub fn new(motor: Motor) -> Result<Self, MotorError> {
+        let settings = MotorSettings {
+            gearset: motor.get_gearing()?,
+            encoder_units: motor.get_encoder_units()?,
+            reversed: motor.is_reversed()?,
+            brake_mode: motor.get_brake_mode()?,
+            current_limit: motor.get_current_limit()?,
+            voltage_limit: motor.get_voltage_limit()?,
+        };
+        let link = MotorLink {
+            connected: true,
+            offset: 0.0,
+            position: motor.get_position()?,
+        };
+        Ok(Self {
+            motor,
+            settings,
+            link: Mutex::new(link),
+            broadcast: Broadcast::new(true),
+        })
+    }
+
+    #[inline]
+    /// Gets the underlying motor.
+    pub fn motor(&self) -> &Motor {
+        &self.motor
+    }
+
+    #[inline]
+    /// Checks whether the motor was connected at the last command or reading.
+    pub fn is_connected(&self) -> bool {
+        self.link.lock().connected
+    }
+
+    #[inline]
+    /// Creates a listener which receives `false` each time the motor is
+    /// disconnected and `true` each time it is reconnected.
+    pub fn listen(&self) -> BroadcastListener<bool> {
+        self.broadcast.listen()
+    }
+
+    /// Sets the voltage for the motor from -127 to 127. See
+    /// [`Motor::move_i8()`].
+    pub fn move_i8(&mut self, voltage: i8) -> Result<(), MotorError> {
+        self.run(|m, _| m.move_i8(voltage)).map(drop)
+    }
+
+    /// Sets the target absolute position for the motor to move to. See
+    /// [`Motor::move_absolute()`].
+    pub fn move_absolute(&mut self, position: f64, velocity: i32) -> Result<(), MotorError> {
+        self.run(|m, offset| m.move_absolute(position - offset, velocity))
+            .map(drop)
+    }
+
+    /// Sets the relative target position for the motor to move to. See
+    /// [`Motor::move_relative()`].
+    pub fn move_relative(&mut self, position: f64, velocity: i32) -> Result<(), MotorError> {
+        self.run(|m, _| m.move_relative(position, velocity))
+            .map(drop)
+    }
+
+    /// Sets the velocity for the motor. See [`Motor::move_velocity()`].
+    pub fn move_velocity(&mut self, velocity: i32) -> Result<(), MotorError> {
+        self.run(|m, _| m.move_velocity(velocity)).map(drop)
+    }
+
+    /// Sets the output voltage for the motor from -12000 to 12000 in
+    /// millivolts.
+    pub fn move_voltage(&mut self, voltage: i32) -> Result<(), MotorError> {
+        self.run(|m, _| m.move_voltage(voltage)).map(drop)
+    }
+
+    /// Gets the position of the motor in its configured encoder units, or the
+    /// last known position if it is disconnected.
+    pub fn get_position(&self) -> Result<f64, MotorError> {
+        match self.run(|m, offset| Ok(m.get_position()? + offset))? {
+            Some(position) => {
+                self.link.lock().position = position;
+                Ok(position)
+            }
+            None => Ok(self.link.lock().position),
+        }
+    }
+
+    /// Gets the actual velocity of the motor in RPM, or zero if it is
+    /// disconnected.
+    pub fn get_actual_velocity(&self) -> Result<f64, MotorError> {
+        Ok(self.run(|m, _| m.get_actual_velocity())?.unwrap_or(0.0))
+    }
+
+    /// Gets the current drawn by the motor in milliamperes, or zero if it is
+    /// disconnected.
+    pub fn get_current_draw(&self) -> Result<i32, MotorError> {
+        Ok(self.run(|m, _| m.get_current_draw())?.unwrap_or(0))
+    }
+
+    /// Sets the brake mode for the motor.
+    pub fn set_brake_mode(&mut self, mode: BrakeMode) -> Result<(), MotorError> {
+        self.settings.brake_mode = mode;
+        self.run(|m, _| m.set_brake_mode(mode)).map(drop)
+    }
+
+    /// Sets the current limit for the motor in milliamperes.
+    pub fn set_current_limit(&mut self, limit: i32) -> Result<(), MotorError> {
+        self.settings.current_limit = limit;
+        self.run(|m, _| m.set_current_limit(limit)).map(drop)
+    }
+
+    /// Sets the voltage limit for the motor in volts.
+    pub fn set_voltage_limit(&mut self, limit: i32) -> Result<(), MotorError> {
+        self.settings.voltage_limit = limit;
+        self.run(|m, _| m.set_voltage_limit(limit)).map(drop)
+    }
+
+    /// Sets the gearset of the motor.
+    pub fn set_gearing(&mut self, gearset: Gearset) -> Result<(), MotorError> {
+        self.settings.gearset = gearset;
+        self.run(|m, _| m.set_gearing(gearset)).map(drop)
+    }
+
+    /// Sets the reverse flag for the motor.
+    pub fn set_reversed(&mut self, reverse: bool) -> Result<(), MotorError> {
+        self.settings.reversed = reverse;
+        self.run(|m, _| m.set_reversed(reverse)).map(drop)
+    }
+
+    /// Sets the zero position of the motor to its current position.
+    pub fn tare_position(&mut self) -> Result<(), MotorError> {
+        if self.run(|m, _| m.tare_position())?.is_some() {
+            let mut link = self.link.lock();
+            link.offset = 0.0;
+            link.position = 0.0;
         }
+        Ok(())
+    }
+
+    /// Runs an operation on the motor with the current position offset,
+    /// returning `None` rather than an error if the motor is disconnected.
+    fn run<T>(
+        &self,
+        f: impl FnOnce(&mut Motor, f64) -> Result<T, MotorError>,
+    ) -> Result<Option<T>, MotorError> {
+        let mut link = self.link.lock();
+        if !link.connected {
+            if unsafe { smart_port_type(self.motor.port) } != DeviceType::Motor {
+                return Ok(None);
+            }
+            self.reconnect(&mut link)?;
+            drop(link);
+            log::info!(
+                "motor on port {} reconnected; configuration restored",
+                self.motor.port
+            );
+            self.broadcast.publish(true);
+            link = self.link.lock();
+        }
+        // The operations used here only need a shared reference in practice,
+        // but the setters take `&mut Motor`, so use a second handle to the
+        // same port.
+        match f(
+            &mut Motor {
+                port: self.motor.port,
+            },
+            link.offset,
+        ) {
+            Ok(value) => Ok(Some(value)),
+            Err(DeviceError {
+                kind: DeviceErrorKind::WrongDevice { .. },
+                ..
+            }) => {
+                link.connected = false;
+                drop(link);
+                log::warn!("motor on port {} disconnected", self.motor.port);
+                self.broadcast.publish(false);
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Reapplies the cached configuration to a reconnected motor, and offsets
+    /// its position to continue from the last known position.
+    fn reconnect(&self, link: &mut MotorLink) -> Result<(), MotorError> {
+        let settings = self.settings;
+        let mut motor = Motor {
+            port: self.motor.port,
+        };
+        motor.set_encoder_units(settings.encoder_units)?;
+        motor.set_gearing(settings.gearset)?;
+        motor.set_reversed(settings.reversed)?;
+        motor.set_brake_mode(settings.brake_mode)?;
+        motor.set_current_limit(settings.current_limit)?;
+        motor.set_voltage_limit(settings.voltage_limit)?;
+        link.offset = link.position - motor.get_position()?;
+        link.connected = true;
+        Ok(())
     }
 }
 
+impl MotorLike for ResilientMotor {
+    #[inline]
+    fn move_voltage(&mut self, voltage: i32) -> Result<(), DeviceError> {
+        ResilientMotor::move_voltage(self, voltage)
+    }
+
+    #[inline]
+    fn move_velocity(&mut self, velocity: i32) -> Result<(), DeviceError> {
+        ResilientMotor::move_velocity(self, velocity)
+    }
+
+    #[inline]
+    fn position(&self) -> Result<f64, DeviceError> {
+        self.get_position()
+    }
+
+    #[inline]
+    fn velocity(&self) -> Result<f64, DeviceError> {
+        self.get_actual_velocity()
+    }
+
+    #[inline]
+    fn current_draw(&self) -> Result<i32, DeviceError> {
+        self.get_current_draw()
+    }
+
+    #[inline]
+    fn tare_position(&mut self) -> Result<(), DeviceError> {
+        ResilientMotor::tare_position(self)
+    }
+}
+
+#[derive(Clone, Copy)]
+struct MotorSettings {
+    gearset: Gearset,
+    encoder_units: EncoderUnits,
+    reversed: bool,
+    brake_mode: BrakeMode,
+    current_limit: i32,
+    voltage_limit: i32,
+}
+
+struct MotorLink {
+    connected: bool,
+    /// The difference between the reported position and that of the motor,
+    /// which changes when the motor's encoder is reset by a power loss.
+    offset: f64,
+    /// The last known position, including the offset.
+    position: f64,
+}
+
+/// Represents possible errors for motor operations. This is an alias of
+/// [`DeviceError`].
+pub type MotorError = DeviceError;
+
 /// Represents possible brake modes for a motor.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum BrakeMode {
@@ -513,7 +1265,7 @@ pub enum Direction {
 }
 
 /// Represents the possible encoder units.
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum EncoderUnits {
     /// The number of ticks of the internal motor encoder.
     /// - 300 ticks/rev with [`Gearset::SixToOne`].
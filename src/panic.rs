@@ -0,0 +1,161 @@
+//! # Panic Handling.
+//!
+//! By default, a panic prints its message, the name of the panicking task,
+//! and a summary of the tasks spawned by this crate, including how much of
+//! each one's stack has remained free, to standard error and the brain screen,
+//! followed by the [trace journal](crate::trace) if any events have been
+//! recorded, and then exits the program. A custom hook, registered with
+//! [`set_hook()`], runs before this, and can be used to put the robot in a
+//! safe state (e.g., stopping motors) or to save diagnostic information.
+//!
+//! With the [`sim`](crate::sim) feature, panics unwind as usual on the host
+//! instead. The tasks and trace journal are still printed after the first
+//! panic, but the custom hook is not run.
+//!
+//! # Example
+//!
+//! ```
+//! panic::set_hook(|_| {
+//!     // Stop the drive motors on ports 1 and 2.
+//!     for port in [1, 2] {
+//!         let gearset = Gearset::EighteenToOne;
+//!         let units = EncoderUnits::Degrees;
+//!         if let Ok(mut motor) = unsafe { Motor::new(port, gearset, units, false) } {
+//!             motor.move_voltage(0).ok();
+//!         }
+//!     }
+//! });
+//! ```
+
+use alloc::{format, string::String, vec};
+use core::{
+    panic::{Location, PanicInfo},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use cstring_interop::with_cstring;
+use spin::Once;
+
+use crate::{
+    bindings,
+    io::{self, eprintln},
+    logger,
+    rtos::{Mutex, Task},
+    trace,
+};
+
+/// The number of lines which fit on the brain screen in small text.
+const SCREEN_LINES: i16 = 12;
+
+type Hook = fn(&PanicInfo);
+
+static HOOK: Once<Mutex<Option<Hook>>> = Once::new();
+static PANICKING: AtomicBool = AtomicBool::new(false);
+
+fn panic_hook() -> &'static Mutex<Option<Hook>> {
+    HOOK.call_once(|| Mutex::new(None))
+}
+
+/// Registers a hook to run when a panic occurs, before the default panic
+/// behaviour. Replaces any previously registered hook.
+///
+/// If the hook itself panics, it is not run again.
+pub fn set_hook(hook: fn(&PanicInfo)) {
+    *panic_hook().lock() = Some(hook);
+}
+
+/// Unregisters the panic hook, returning it if one was registered.
+pub fn take_hook() -> Option<fn(&PanicInfo)> {
+    panic_hook().lock().take()
+}
+
+#[cfg(not(feature = "sim"))]
+#[panic_handler]
+fn panic(panic_info: &PanicInfo) -> ! {
+    // A panic within the hook or the task dump only prints its message.
+    let first = !PANICKING.swap(true, Ordering::SeqCst);
+    if first {
+        // Skip the hook rather than deadlocking if the panic occurred while it
+        // was being replaced.
+        let hook = HOOK.get().and_then(Mutex::poll).and_then(|hook| *hook);
+        if let Some(hook) = hook {
+            hook(panic_info);
+        }
+    }
+
+    // Write any waiting log records and buffered output first, so that they
+    // appear before the panic message.
+    logger::try_flush();
+    io::try_flush();
+    eprintln!("panic occurred!: {:#?}", panic_info);
+    if first {
+        dump_tasks(panic_info.location());
+        trace::dump_on_panic();
+    }
+
+    unsafe {
+        libc::exit(1);
+    }
+}
+
+/// Installs a standard library panic hook which prints the same diagnostics as
+/// the panic handler, after the default message.
+#[cfg(feature = "sim")]
+pub(crate) fn install_sim_hook() {
+    let default = std::panic::take_hook();
+    std::panic::set_hook(alloc::boxed::Box::new(move |info| {
+        logger::try_flush();
+        io::try_flush();
+        default(info);
+        if !PANICKING.swap(true, Ordering::SeqCst) {
+            dump_tasks(info.location());
+            trace::dump_on_panic();
+        }
+    }));
+}
+
+/// Prints the panicking task and a summary of all tasks spawned by this crate,
+/// to help diagnose deadlocks and stack overflows.
+fn dump_tasks(location: Option<&Location>) {
+    let mut lines = vec![
+        format!("panic in task `{}`", Task::current().name()),
+        match location {
+            Some(location) => format!("at {}:{}", location.file(), location.line()),
+            None => String::new(),
+        },
+    ];
+    // Skip the list rather than deadlocking if the panic occurred while it was
+    // being updated.
+    match Task::try_spawned() {
+        Some(tasks) => {
+            lines.push(format!("{} tasks; tasks spawned by vex-rt:", Task::count()));
+            for task in tasks {
+                lines.push(format!(
+                    "  {:<20} {:<10?} priority {:<2} stack free {}",
+                    task.name(),
+                    task.state(),
+                    task.priority(),
+                    task.stack_headroom()
+                ));
+            }
+        }
+        None => lines.push(format!(
+            "{} tasks; tasks spawned by vex-rt unavailable",
+            Task::count()
+        )),
+    }
+
+    for line in &lines {
+        eprintln!("{}", line);
+    }
+    for (i, line) in lines.iter().take(SCREEN_LINES as usize).enumerate() {
+        with_cstring(line.clone(), |line| unsafe {
+            bindings::screen_print(
+                bindings::text_format_e_t_E_TEXT_SMALL,
+                i as i16,
+                b"%s\0".as_ptr().cast(),
+                line.into_raw(),
+            );
+        });
+    }
+}
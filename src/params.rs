@@ -0,0 +1,235 @@
+//! # Parameters.
+//!
+//! [`Params`] is a global store of named parameters, such as PID gains and
+//! thresholds, which subsystems read through typed [`Param`] handles. Values
+//! can be loaded from a [`Config`] file and changed at runtime, either
+//! programmatically with [`Params::set()`] or through the `tune`
+//! [REPL](crate::repl) command, as for a [`Tunable`](crate::tunable::Tunable).
+//!
+//! Reading a parameter which has not changed does not take a lock, so it is
+//! cheap enough to do on every iteration of a control loop. Each change is
+//! also published on a per-parameter [`Broadcast`], so that a subsystem can
+//! react to it, e.g. by resetting a controller.
+//!
+//! # Example
+//!
+//! ```
+//! Params::load(&Config::load("/usd/robot.cfg"));
+//! repl::start()?;
+//!
+//! let mut kp = Param::new("lift.kp", 0.5);
+//! loop {
+//!     let output = kp.get() * error;
+//!     // ...
+//! }
+//! ```
+
+use alloc::{
+    boxed::Box,
+    collections::BTreeMap,
+    string::{String, ToString},
+    sync::Arc,
+    vec::Vec,
+};
+use core::{
+    fmt::Display,
+    str::FromStr,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use spin::Once;
+
+use crate::{
+    config::Config,
+    error::Error,
+    io::eprintln,
+    rtos::{Broadcast, BroadcastListener, Mutex},
+    tunable::{self, Entry},
+};
+
+static STORE: Once<Mutex<BTreeMap<String, Arc<ParamEntry>>>> = Once::new();
+
+fn store() -> &'static Mutex<BTreeMap<String, Arc<ParamEntry>>> {
+    STORE.call_once(|| Mutex::new(BTreeMap::new()))
+}
+
+/// The global parameter store. See the [module-level documentation](self).
+pub struct Params;
+
+impl Params {
+    /// Sets every key in a configuration as a parameter.
+    pub fn load(config: &Config) {
+        for (key, value) in config.iter() {
+            Self::set_str(key, value);
+        }
+    }
+
+    #[inline]
+    /// Sets the value of a parameter, creating it if necessary.
+    pub fn set(key: &str, value: impl Display) {
+        Self::set_str(key, &value.to_string())
+    }
+
+    /// Sets the value of a parameter from its textual form, creating it if
+    /// necessary. The value is parsed by each [`Param`] when it is next read.
+    pub fn set_str(key: &str, value: &str) {
+        let mut store = store().lock();
+        match store.get(key) {
+            Some(entry) => entry.update(value),
+            None => {
+                insert(&mut store, key, value);
+            }
+        }
+    }
+
+    /// Gets the textual value of a parameter, or `None` if it does not exist.
+    pub fn get(key: &str) -> Option<String> {
+        let entry = store().lock().get(key)?.clone();
+        let value = entry.value.lock().clone();
+        Some(value)
+    }
+
+    /// Creates a listener which receives the textual value of a parameter each
+    /// time it is set, or `None` if it does not exist.
+    pub fn listen(key: &str) -> Option<BroadcastListener<String>> {
+        Some(store().lock().get(key)?.broadcast.listen())
+    }
+
+    /// Gets the key and textual value of every parameter, in alphabetical order
+    /// of key.
+    pub fn values() -> Vec<(String, String)> {
+        store()
+            .lock()
+            .iter()
+            .map(|(key, entry)| (key.clone(), entry.value.lock().clone()))
+            .collect()
+    }
+}
+
+/// A typed handle to a parameter in the global [`Params`] store. Clones refer
+/// to the same parameter.
+#[derive(Clone)]
+pub struct Param<T> {
+    key: String,
+    entry: Arc<ParamEntry>,
+    default: T,
+    value: T,
+    version: u32,
+}
+
+impl<T: FromStr + Display + Clone> Param<T> {
+    /// Creates a handle to the parameter with the given key, creating the
+    /// parameter with the value `default` if it does not exist. `default` is
+    /// also used whenever the parameter's value cannot be parsed as a `T`.
+    pub fn new(key: &str, default: T) -> Self {
+        let entry = {
+            let mut store = store().lock();
+            match store.get(key) {
+                Some(entry) => entry.clone(),
+                None => insert(&mut store, key, &default.to_string()),
+            }
+        };
+        let mut param = Self {
+            key: key.into(),
+            entry,
+            value: default.clone(),
+            default,
+            version: 0,
+        };
+        param.refresh();
+        param
+    }
+
+    #[inline]
+    /// Gets the key of the parameter.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    #[inline]
+    /// Gets the current value of the parameter. A lock is taken only if the
+    /// value has changed since it was last read through this handle.
+    pub fn get(&mut self) -> T {
+        self.refresh();
+        self.value.clone()
+    }
+
+    /// Checks whether the value of the parameter has changed since it was last
+    /// read through this handle, without taking a lock.
+    pub fn changed(&self) -> bool {
+        self.entry.version.load(Ordering::Acquire) != self.version
+    }
+
+    #[inline]
+    /// Sets the value of the parameter.
+    pub fn set(&self, value: T) {
+        self.entry.update(&value.to_string());
+    }
+
+    #[inline]
+    /// Creates a listener which receives the textual value of the parameter
+    /// each time it is set.
+    pub fn listen(&self) -> BroadcastListener<String> {
+        self.entry.broadcast.listen()
+    }
+
+    fn refresh(&mut self) {
+        let version = self.entry.version.load(Ordering::Acquire);
+        if version == self.version {
+            return;
+        }
+        self.version = version;
+        let text = self.entry.value.lock().clone();
+        self.value = match text.parse() {
+            Ok(value) => value,
+            Err(_) => {
+                eprintln!("{}: invalid value {:?}", self.key, text);
+                self.default.clone()
+            }
+        };
+    }
+}
+
+struct ParamEntry {
+    /// Incremented each time the value is set, so that handles can tell
+    /// whether their copy is stale without taking a lock.
+    version: AtomicU32,
+    value: Mutex<String>,
+    broadcast: Broadcast<String>,
+}
+
+impl ParamEntry {
+    fn update(&self, value: &str) {
+        *self.value.lock() = value.into();
+        self.version.fetch_add(1, Ordering::AcqRel);
+        self.broadcast.publish(value.into());
+    }
+}
+
+impl Entry for Arc<ParamEntry> {
+    fn get(&self) -> Option<String> {
+        Some(self.value.lock().clone())
+    }
+
+    fn set(&self, value: &str) -> Option<Result<(), Error>> {
+        self.update(value);
+        Some(Ok(()))
+    }
+}
+
+/// Inserts a new parameter into the store and registers it with the `tune`
+/// command.
+fn insert(
+    store: &mut BTreeMap<String, Arc<ParamEntry>>,
+    key: &str,
+    value: &str,
+) -> Arc<ParamEntry> {
+    let entry = Arc::new(ParamEntry {
+        version: AtomicU32::new(1),
+        value: Mutex::new(value.into()),
+        broadcast: Broadcast::new(value.into()),
+    });
+    store.insert(key.into(), entry.clone());
+    tunable::register(key, Box::new(entry.clone()));
+    entry
+}
@@ -1,5 +1,7 @@
 //! Peripherals.
 
+use core::sync::atomic::{AtomicBool, Ordering};
+
 use crate::adi::AdiPort;
 use crate::{
     bindings,
@@ -7,6 +9,8 @@ use crate::{
     smart_port::SmartPort,
 };
 
+static TAKEN: AtomicBool = AtomicBool::new(false);
+
 /// A struct which represents all the peripherals on the V5 brain.
 pub struct Peripherals {
     /// Primary Controller.
@@ -74,6 +78,28 @@ pub struct Peripherals {
 }
 
 impl Peripherals {
+    /// Takes the [`Peripherals`] singleton, returning `None` if it has already
+    /// been taken.
+    ///
+    /// The [`entry!`](crate::entry!) macro takes the peripherals before
+    /// calling [`Robot::new()`](crate::robot::Robot::new()), so this is only
+    /// useful for custom entry points, tests and simulations. With the
+    /// [`sim`](crate::sim) feature, [`sim::reset()`](crate::sim::reset())
+    /// allows the peripherals to be taken again.
+    pub fn take() -> Option<Self> {
+        if TAKEN.swap(true, Ordering::AcqRel) {
+            None
+        } else {
+            Some(unsafe { Self::new() })
+        }
+    }
+
+    /// Allows the peripherals to be taken again.
+    #[cfg(feature = "sim")]
+    pub(crate) fn release() {
+        TAKEN.store(false, Ordering::Release);
+    }
+
     /// Constructs a [`Peripherals`] struct unsafely.
     ///
     /// # Safety
@@ -0,0 +1,273 @@
+//! # Power Management.
+//!
+//! Under heavy load, such as a pushing match at the end of a game, the battery
+//! voltage sags and the brain may brown out, and every motor loses power at
+//! once. A [`PowerManager`] watches the battery voltage and current and, as
+//! they pass configurable thresholds, progressively lowers the current limit of
+//! registered motors which are not part of the drivetrain, so that the
+//! drivetrain keeps enough power to stay responsive. The driver can be
+//! notified with a controller rumble when limiting begins.
+//!
+//! Like the [`HealthMonitor`](crate::health::HealthMonitor), motors are
+//! registered by port number, so the manager does not need to own them. Each
+//! motor's current limit is read when the manager starts, and restored when
+//! the battery recovers or the manager is dropped.
+//!
+//! # Example
+//!
+//! ```
+//! let power = PowerManager::new(Duration::from_millis(50))
+//!     .motor(5) // intake
+//!     .motor(6) // lift
+//!     .rumble("..")
+//!     .start()?;
+//!
+//! if power.status().is_limited() {
+//!     controller.screen.print(0, 0, "LOW POWER");
+//! }
+//! ```
+
+use alloc::{string::String, sync::Arc, vec::Vec};
+use core::time::Duration;
+
+use cstring_interop::with_cstring;
+
+use crate::{
+    battery::Battery,
+    bindings,
+    error::Error,
+    filter::{Ema, Filter},
+    io::eprintln,
+    rtos::{Broadcast, BroadcastListener, Loop, Task},
+};
+
+/// Represents the thresholds at which a [`PowerManager`] limits motor power.
+///
+/// The output of the registered motors is scaled down linearly from full
+/// output to `min_output` as the battery voltage falls from `start_voltage`
+/// to `end_voltage`, or as the battery current rises from `start_current` to
+/// `end_current`, whichever limits more. Both readings are smoothed, so that
+/// brief spikes do not cause the limit to flicker.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PowerLimits {
+    /// The battery voltage below which the output starts to be limited, in
+    /// millivolts.
+    pub start_voltage: f64,
+    /// The battery voltage at which the output reaches its minimum, in
+    /// millivolts.
+    pub end_voltage: f64,
+    /// The battery current above which the output starts to be limited, in
+    /// milliamps.
+    pub start_current: f64,
+    /// The battery current at which the output reaches its minimum, in
+    /// milliamps.
+    pub end_current: f64,
+    /// The minimum fraction of each motor's normal current limit which is
+    /// allowed, from 0 to 1.
+    pub min_output: f64,
+    /// The smoothing factor applied to the readings on each update; see
+    /// [`Ema::new()`].
+    pub smoothing: f64,
+}
+
+impl PowerLimits {
+    /// Creates a new set of thresholds with default values, which limit the
+    /// output between 12V and 11V, or between 16A and 20A, the most the
+    /// battery can supply.
+    pub fn new() -> Self {
+        Self {
+            start_voltage: 12000.0,
+            end_voltage: 11000.0,
+            start_current: 16000.0,
+            end_current: 20000.0,
+            min_output: 0.25,
+            smoothing: 0.2,
+        }
+    }
+
+    /// Gets the fraction of the normal output to allow at the given voltage
+    /// and current.
+    fn output(&self, voltage: f64, current: f64) -> f64 {
+        let by_voltage = (self.start_voltage - voltage) / (self.start_voltage - self.end_voltage);
+        let by_current = (current - self.start_current) / (self.end_current - self.start_current);
+        let t = by_voltage.max(by_current).clamp(0.0, 1.0);
+        1.0 - t * (1.0 - self.min_output)
+    }
+}
+
+impl Default for PowerLimits {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Represents the state of the battery as seen by a [`PowerManager`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PowerStatus {
+    /// The smoothed battery voltage, in millivolts.
+    pub voltage: f64,
+    /// The smoothed battery current, in milliamps.
+    pub current: f64,
+    /// The fraction of their normal current limit allowed to the registered
+    /// motors, from 0 to 1.
+    pub output: f64,
+}
+
+impl PowerStatus {
+    #[inline]
+    /// Checks whether the registered motors are being limited.
+    pub fn is_limited(&self) -> bool {
+        self.output < 1.0
+    }
+}
+
+impl Default for PowerStatus {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            voltage: 0.0,
+            current: 0.0,
+            output: 1.0,
+        }
+    }
+}
+
+/// Describes the motors to be limited by a power manager. Call
+/// [`PowerManager::start()`] to begin managing power.
+pub struct PowerManager {
+    period: Duration,
+    limits: PowerLimits,
+    motors: Vec<u8>,
+    rumble: Option<String>,
+}
+
+impl PowerManager {
+    #[inline]
+    /// Creates a new power manager with no motors, which updates every
+    /// `period`.
+    pub fn new(period: Duration) -> Self {
+        Self {
+            period,
+            limits: PowerLimits::new(),
+            motors: Vec::new(),
+            rumble: None,
+        }
+    }
+
+    /// Registers a motor on the given port whose power is limited when the
+    /// battery is under strain. Drivetrain motors should not be registered.
+    pub fn motor(mut self, port: u8) -> Self {
+        self.motors.push(port);
+        self
+    }
+
+    #[inline]
+    /// Sets the thresholds at which power is limited.
+    pub fn limits(mut self, limits: PowerLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    #[inline]
+    /// Rumbles the master controller with the given pattern whenever limiting
+    /// begins. See [`Screen::rumble()`](crate::controller::Screen::rumble())
+    /// for the pattern format.
+    pub fn rumble(mut self, pattern: &str) -> Self {
+        self.rumble = Some(pattern.into());
+        self
+    }
+
+    /// Spawns the task which watches the battery and limits the registered
+    /// motors.
+    pub fn start(self) -> Result<RunningPowerManager, Error> {
+        let broadcast = Arc::new(Broadcast::try_new(PowerStatus::default())?);
+        let weak = Arc::downgrade(&broadcast);
+        let normal: Vec<(u8, i32)> = self
+            .motors
+            .iter()
+            .map(|&port| (port, unsafe { bindings::motor_get_current_limit(port) }))
+            .filter(|&(_, limit)| limit != bindings::PROS_ERR_)
+            .collect();
+        Task::spawn_ext(
+            "power manager",
+            bindings::TASK_PRIORITY_DEFAULT,
+            Task::DEFAULT_STACK_DEPTH,
+            move || {
+                let mut l = Loop::new(self.period);
+                let mut voltage_filter = Ema::new(self.limits.smoothing);
+                let mut current_filter = Ema::new(self.limits.smoothing);
+                let mut last = PowerStatus::default();
+                while let Some(broadcast) = weak.upgrade() {
+                    let (voltage, current) = match (Battery::get_voltage(), Battery::get_current())
+                    {
+                        (Ok(voltage), Ok(current)) => (
+                            voltage_filter.filter(voltage as f64),
+                            current_filter.filter(current as f64),
+                        ),
+                        (Err(err), _) | (_, Err(err)) => {
+                            eprintln!("failed to read battery: {:?}", err);
+                            drop(broadcast);
+                            l.delay();
+                            continue;
+                        }
+                    };
+                    let status = PowerStatus {
+                        voltage,
+                        current,
+                        output: self.limits.output(voltage, current),
+                    };
+                    if status.output != last.output {
+                        limit(&normal, status.output);
+                    }
+                    if status.is_limited() && !last.is_limited() {
+                        self.alert();
+                    }
+                    last = status;
+                    broadcast.publish(status);
+                    drop(broadcast);
+                    l.delay();
+                }
+                limit(&normal, 1.0);
+            },
+        )?;
+        Ok(RunningPowerManager(broadcast))
+    }
+
+    fn alert(&self) {
+        if let Some(pattern) = &self.rumble {
+            with_cstring(pattern.clone(), |pattern| unsafe {
+                bindings::controller_rumble(
+                    bindings::controller_id_e_t_E_CONTROLLER_MASTER,
+                    pattern.into_raw(),
+                );
+            });
+        }
+    }
+}
+
+/// A [`PowerManager`] which is watching the battery in the background. The
+/// motors' normal current limits are restored when this is dropped.
+pub struct RunningPowerManager(Arc<Broadcast<PowerStatus>>);
+
+impl RunningPowerManager {
+    #[inline]
+    /// Gets the most recent power status.
+    pub fn status(&self) -> PowerStatus {
+        self.0.value()
+    }
+
+    #[inline]
+    /// Creates a listener which receives each new power status.
+    pub fn listen(&self) -> BroadcastListener<PowerStatus> {
+        self.0.listen()
+    }
+}
+
+/// Sets the current limit of each motor to the given fraction of its normal
+/// limit.
+fn limit(normal: &[(u8, i32)], output: f64) {
+    for &(port, limit) in normal {
+        unsafe { bindings::motor_set_current_limit(port, (limit as f64 * output) as i32) };
+    }
+}
@@ -1,19 +1,77 @@
 //! Convenient to `use` common components.
 
 pub use crate::entry;
+pub use crate::profile_scope;
+pub use crate::robot_assert;
+pub use crate::robot_debug_assert;
 pub use crate::select;
+pub use crate::trace;
 
 pub use crate::adi::*;
+pub use crate::alerts::*;
+pub use crate::allocator::{self, AllocEvent, AllocStats};
+pub use crate::assertion::{self, AssertPolicy};
+pub use crate::auton::*;
 pub use crate::battery::*;
+pub use crate::blackbox::*;
+pub use crate::build_info::{build_info, BuildInfo};
+pub use crate::command::*;
+pub use crate::config::*;
+pub use crate::contact::*;
+pub use crate::control::*;
 pub use crate::controller::*;
+pub use crate::csv::*;
+pub use crate::device::{
+    mock::{MockCommand, MockDistanceSensor, MockMotor, MockRotarySensor, Script},
+    DistanceLike, GearedSensor, MotorLike, RotarySensor,
+};
+pub use crate::diagnostics::*;
 pub use crate::distance::*;
+pub use crate::drivetrain::*;
 pub use crate::error::*;
+pub use crate::escalation::{self, ErrorPolicy};
+pub use crate::field::*;
+pub use crate::filter::*;
+#[cfg(feature = "fixed-point")]
+pub use crate::fixed::*;
+pub use crate::flywheel::*;
+pub use crate::geometry::*;
+pub use crate::ghost::*;
+pub use crate::gps::*;
+pub use crate::health::*;
+pub use crate::homing::*;
 pub use crate::imu::*;
+pub use crate::indexer::*;
 pub use crate::io::*;
+pub use crate::keypad::*;
+pub use crate::lights::*;
+pub use crate::localization::*;
+pub use crate::logger::{
+    self, Level, LevelFilter, LogRotation, LogSink, ScreenSink, SdSink, SerialSink,
+};
 pub use crate::machine::*;
+pub use crate::mechanism::*;
+pub use crate::motion::*;
 pub use crate::motor::*;
+pub use crate::panic;
+pub use crate::params::*;
 pub use crate::peripherals::*;
+pub use crate::power::*;
+pub use crate::profiler::{self, ScopeStats, Stopwatch};
+pub use crate::repl;
+pub use crate::replay::*;
+pub use crate::retry::*;
 pub use crate::robot::*;
 pub use crate::rotation::*;
+pub use crate::rpc::*;
 pub use crate::rtos::*;
+pub use crate::screen::*;
+pub use crate::sd::*;
+pub use crate::self_check::*;
 pub use crate::smart_port::*;
+pub use crate::starvation::*;
+pub use crate::telemetry::*;
+pub use crate::trace::TraceEvent;
+pub use crate::tunable::{self, Tunable};
+pub use crate::units::*;
+pub use crate::vision::*;
@@ -0,0 +1,211 @@
+//! # Scope Profiling.
+//!
+//! The [`profile_scope!`](crate::profile_scope!) macro measures how long the
+//! rest of the enclosing scope takes to run, using the microsecond clock, and
+//! accumulates the minimum, average and maximum times for each named scope.
+//! For one-off measurements, a [`Stopwatch`] or [`measure()`] can be used
+//! instead.
+//!
+//! # Example
+//!
+//! ```
+//! profiler::dump_every(Duration::from_secs(5))?;
+//!
+//! let mut l = Loop::new(Duration::from_millis(10));
+//! loop {
+//!     {
+//!         profile_scope!("odometry");
+//!         drivetrain.update_odometry()?;
+//!     }
+//!     profile_scope!("control");
+//!     // ...
+//!     l.delay();
+//! }
+//!
+//! let elapsed = profiler::measure(|| readings.sort_unstable());
+//! println!("sorting took {:?}", elapsed);
+//! ```
+
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use spin::Once;
+
+use crate::{
+    bindings,
+    error::Error,
+    io::println,
+    rtos::{time_since_start, Instant, Loop, Mutex, Task},
+};
+
+static SCOPES: Once<Mutex<Vec<(&'static str, ScopeStats)>>> = Once::new();
+
+fn scopes() -> &'static Mutex<Vec<(&'static str, ScopeStats)>> {
+    SCOPES.call_once(|| Mutex::new(Vec::new()))
+}
+
+/// Accumulated timings for a profiled scope.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScopeStats {
+    /// The number of times the scope has run.
+    pub count: u32,
+    /// The total time spent in the scope.
+    pub total: Duration,
+    /// The shortest time spent in the scope.
+    pub min: Duration,
+    /// The longest time spent in the scope.
+    pub max: Duration,
+}
+
+impl ScopeStats {
+    #[inline]
+    /// Gets the average time spent in the scope.
+    pub fn average(&self) -> Duration {
+        self.total / self.count.max(1)
+    }
+
+    fn record(&mut self, elapsed: Duration) {
+        self.count += 1;
+        self.total += elapsed;
+        self.min = self.min.min(elapsed);
+        self.max = self.max.max(elapsed);
+    }
+}
+
+/// Measures the time until it is dropped, and records it against a named
+/// scope. Usually created with [`profile_scope!`](crate::profile_scope!).
+pub struct ScopeGuard {
+    name: &'static str,
+    start: Instant,
+}
+
+impl ScopeGuard {
+    #[inline]
+    /// Starts measuring a scope with the given name.
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            start: time_since_start(),
+        }
+    }
+}
+
+impl Drop for ScopeGuard {
+    fn drop(&mut self) {
+        let elapsed = time_since_start() - self.start;
+        let mut scopes = scopes().lock();
+        match scopes.iter_mut().find(|(name, _)| *name == self.name) {
+            Some((_, stats)) => stats.record(elapsed),
+            None => scopes.push((
+                self.name,
+                ScopeStats {
+                    count: 1,
+                    total: elapsed,
+                    min: elapsed,
+                    max: elapsed,
+                },
+            )),
+        }
+    }
+}
+
+/// Measures elapsed time using the microsecond clock.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Stopwatch {
+    start: Instant,
+    lap: Instant,
+}
+
+impl Stopwatch {
+    #[inline]
+    /// Creates a new stopwatch, started at the current time.
+    pub fn new() -> Self {
+        let now = time_since_start();
+        Self {
+            start: now,
+            lap: now,
+        }
+    }
+
+    #[inline]
+    /// Gets the time since the stopwatch was started.
+    pub fn elapsed(&self) -> Duration {
+        time_since_start() - self.start
+    }
+
+    /// Gets the time since the previous lap, or since the stopwatch was
+    /// started if this is the first, and begins a new lap.
+    pub fn lap(&mut self) -> Duration {
+        let now = time_since_start();
+        let lap = now - self.lap;
+        self.lap = now;
+        lap
+    }
+
+    /// Restarts the stopwatch, returning the time since it was previously
+    /// started.
+    pub fn restart(&mut self) -> Duration {
+        let elapsed = self.elapsed();
+        *self = Self::new();
+        elapsed
+    }
+}
+
+impl Default for Stopwatch {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Runs a function, returning how long it took.
+pub fn measure(f: impl FnOnce()) -> Duration {
+    let stopwatch = Stopwatch::new();
+    f();
+    stopwatch.elapsed()
+}
+
+/// Gets the timings of every profiled scope, in the order in which they first
+/// ran.
+pub fn report() -> Vec<(&'static str, ScopeStats)> {
+    scopes().lock().clone()
+}
+
+/// Clears the timings of every profiled scope.
+pub fn reset() {
+    scopes().lock().clear();
+}
+
+/// Prints the timings of every profiled scope to standard output.
+pub fn dump() {
+    println!(
+        "{:<20} {:>8} {:>10} {:>10} {:>10}",
+        "scope", "count", "min (us)", "avg (us)", "max (us)"
+    );
+    for (name, stats) in report() {
+        println!(
+            "{:<20} {:>8} {:>10} {:>10} {:>10}",
+            name,
+            stats.count,
+            stats.min.as_micros(),
+            stats.average().as_micros(),
+            stats.max.as_micros()
+        );
+    }
+}
+
+/// Spawns a low-priority task which calls [`dump()`] every `period`.
+pub fn dump_every(period: Duration) -> Result<Task, Error> {
+    Task::spawn_ext(
+        "profiler",
+        bindings::TASK_PRIORITY_MIN,
+        Task::DEFAULT_STACK_DEPTH,
+        move || {
+            let mut l = Loop::new(period);
+            loop {
+                l.delay();
+                dump();
+            }
+        },
+    )
+}
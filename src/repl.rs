@@ -0,0 +1,97 @@
+//! # Serial Command REPL.
+//!
+//! Commands registered with [`register()`] can be run from a terminal
+//! connected over USB, once [`start()`] has been called. Each line of input
+//! is split on whitespace; the first word selects the command, and the
+//! remaining words are passed to its handler as arguments.
+//!
+//! # Example
+//!
+//! ```
+//! let kp = Arc::new(Mutex::new(0.5));
+//! let handler_kp = kp.clone();
+//! repl::register("set_kp", move |args| {
+//!     let value = args
+//!         .first()
+//!         .and_then(|arg| arg.parse().ok())
+//!         .ok_or_else(|| Error::Custom("usage: set_kp <value>".into()))?;
+//!     *handler_kp.lock() = value;
+//!     Ok(())
+//! });
+//! repl::start()?;
+//! ```
+
+use alloc::{collections::BTreeMap, format, string::String, sync::Arc, vec::Vec};
+
+use spin::Once;
+
+use crate::{
+    error::Error,
+    io::{eprintln, println, read_line},
+    rtos::{Mutex, Task},
+};
+
+type Handler = Arc<dyn Fn(&[&str]) -> Result<(), Error> + Send + Sync>;
+
+static COMMANDS: Once<Mutex<BTreeMap<String, Handler>>> = Once::new();
+
+fn commands() -> &'static Mutex<BTreeMap<String, Handler>> {
+    COMMANDS.call_once(|| Mutex::new(BTreeMap::new()))
+}
+
+/// Registers a command, replacing any existing command with the same name.
+pub fn register(
+    name: &str,
+    handler: impl Fn(&[&str]) -> Result<(), Error> + Send + Sync + 'static,
+) {
+    commands().lock().insert(name.into(), Arc::new(handler));
+}
+
+/// Removes a registered command.
+pub fn unregister(name: &str) {
+    commands().lock().remove(name);
+}
+
+/// Runs a single line of input as a command, as if it had been typed into the
+/// terminal.
+pub fn execute(line: &str) -> Result<(), Error> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let (name, args) = match words.split_first() {
+        Some(split) => split,
+        None => return Ok(()),
+    };
+    if *name == "help" {
+        println!("commands:");
+        for name in commands().lock().keys() {
+            println!("  {}", name);
+        }
+        return Ok(());
+    }
+    // The lock is released before running the handler, so that handlers may
+    // themselves register commands.
+    let handler = commands().lock().get(*name).cloned();
+    match handler {
+        Some(handler) => handler(args),
+        None => Err(Error::Custom(format!(
+            "unknown command: {} (try `help`)",
+            name
+        ))),
+    }
+}
+
+/// Spawns a task which reads commands from standard input and runs them,
+/// printing any errors.
+pub fn start() -> Result<Task, Error> {
+    Task::spawn_ext(
+        "repl",
+        Task::DEFAULT_PRIORITY,
+        Task::DEFAULT_STACK_DEPTH,
+        || {
+            while let Some(line) = read_line() {
+                if let Err(err) = execute(&line) {
+                    eprintln!("error: {}", err);
+                }
+            }
+        },
+    )
+}
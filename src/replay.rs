@@ -0,0 +1,207 @@
+//! # Driver Input Recording and Replay.
+//!
+//! A [`Recorder`] saves a sequence of [`ControllerData`] samples to the SD
+//! card during driver control, which a [`Replay`] can later feed back into the
+//! same driving code during autonomous.
+//!
+//! # Example
+//!
+//! ```
+//! // In opcontrol, sampling at the recorder's period:
+//! let data = self.controller.read()?;
+//! self.drive(&data);
+//! recorder.record(&data)?;
+//!
+//! // In autonomous:
+//! Replay::load("/usd/skills.bin")?.play(&ctx, |data| self.drive(data))?;
+//! ```
+
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use crate::{
+    controller::ControllerData,
+    error::Error,
+    rtos::{Context, Loop},
+    sd::{File, SdError},
+    select,
+};
+
+const MAGIC: &[u8; 4] = b"VXRP";
+const HEADER_LEN: usize = 8;
+const FRAME_LEN: usize = 6;
+
+/// Records controller inputs to a file on the SD card.
+///
+/// Samples should be recorded at the period given to [`Recorder::create()`],
+/// e.g. using a [`Loop`].
+pub struct Recorder {
+    file: File,
+    period: Duration,
+}
+
+impl Recorder {
+    /// Creates a new recording at `path`, replacing any existing file, with
+    /// samples taken every `period`.
+    pub fn create(path: &str, period: Duration) -> Result<Self, SdError> {
+        let mut file = File::create(path)?;
+        let mut header = [0u8; HEADER_LEN];
+        header[..4].copy_from_slice(MAGIC);
+        header[4..].copy_from_slice(&(period.as_millis() as u32).to_le_bytes());
+        file.write_all(&header)?;
+        Ok(Self { file, period })
+    }
+
+    #[inline]
+    /// Gets the period at which samples should be recorded.
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Appends a sample to the recording.
+    pub fn record(&mut self, data: &ControllerData) -> Result<(), SdError> {
+        self.file.write_all(&encode(data))
+    }
+
+    #[inline]
+    /// Ensures that all recorded samples have been written to the SD card.
+    /// This also happens when the recorder is dropped.
+    pub fn flush(&mut self) -> Result<(), SdError> {
+        self.file.flush()
+    }
+}
+
+/// A recording of controller inputs, loaded into memory for playback.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Replay {
+    period: Duration,
+    frames: Vec<ControllerData>,
+}
+
+impl Replay {
+    /// Loads a recording made by a [`Recorder`] from the SD card.
+    pub fn load(path: &str) -> Result<Self, ReplayError> {
+        let mut buf = Vec::new();
+        File::open(path)?.read_to_end(&mut buf)?;
+        if buf.len() < HEADER_LEN || &buf[..4] != MAGIC {
+            return Err(ReplayError::InvalidFormat);
+        }
+        let period = u32::from_le_bytes([buf[4], buf[5], buf[6], buf[7]]);
+        Ok(Self {
+            period: Duration::from_millis(period.into()),
+            frames: buf[HEADER_LEN..]
+                .chunks_exact(FRAME_LEN)
+                .map(decode)
+                .collect(),
+        })
+    }
+
+    #[inline]
+    /// Gets the period at which the samples were recorded.
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    #[inline]
+    /// Gets the recorded samples.
+    pub fn frames(&self) -> &[ControllerData] {
+        &self.frames
+    }
+
+    #[inline]
+    /// Gets the total length of the recording.
+    pub fn duration(&self) -> Duration {
+        self.period * self.frames.len() as u32
+    }
+
+    /// Plays back the recording, calling `f` with each sample at the period it
+    /// was recorded at.
+    ///
+    /// Blocks until the recording has finished or `ctx` is cancelled.
+    pub fn play(
+        &self,
+        ctx: &Context,
+        mut f: impl FnMut(&ControllerData),
+    ) -> Result<(), ReplayError> {
+        let mut l = Loop::new(self.period);
+        for frame in &self.frames {
+            f(frame);
+            select! {
+                _ = ctx.done() => return Err(ReplayError::Cancelled),
+                _ = l.select() => continue,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Represents possible errors for recording playback.
+#[derive(Debug)]
+pub enum ReplayError {
+    /// The recording could not be read from the SD card.
+    Sd(SdError),
+    /// The file is not a recording made by a [`Recorder`].
+    InvalidFormat,
+    /// Playback was cancelled before the end of the recording.
+    Cancelled,
+}
+
+impl From<SdError> for ReplayError {
+    fn from(err: SdError) -> Self {
+        Self::Sd(err)
+    }
+}
+
+impl From<ReplayError> for Error {
+    fn from(err: ReplayError) -> Self {
+        match err {
+            ReplayError::Sd(err) => err.into(),
+            ReplayError::InvalidFormat => Error::Custom("invalid recording format".into()),
+            ReplayError::Cancelled => Error::Custom("replay cancelled".into()),
+        }
+    }
+}
+
+fn encode(data: &ControllerData) -> [u8; FRAME_LEN] {
+    let buttons = [
+        data.l1, data.l2, data.r1, data.r2, data.up, data.down, data.left, data.right, data.x,
+        data.y, data.a, data.b,
+    ]
+    .iter()
+    .enumerate()
+    .fold(0u16, |acc, (i, &pressed)| acc | (pressed as u16) << i)
+    .to_le_bytes();
+    [
+        data.left_x as u8,
+        data.left_y as u8,
+        data.right_x as u8,
+        data.right_y as u8,
+        buttons[0],
+        buttons[1],
+    ]
+}
+
+fn decode(frame: &[u8]) -> ControllerData {
+    let buttons = u16::from_le_bytes([frame[4], frame[5]]);
+    let pressed = |i: u32| buttons & (1 << i) != 0;
+    ControllerData {
+        left_x: frame[0] as i8,
+        left_y: frame[1] as i8,
+        right_x: frame[2] as i8,
+        right_y: frame[3] as i8,
+        l1: pressed(0),
+        l2: pressed(1),
+        r1: pressed(2),
+        r2: pressed(3),
+        up: pressed(4),
+        down: pressed(5),
+        left: pressed(6),
+        right: pressed(7),
+        x: pressed(8),
+        y: pressed(9),
+        a: pressed(10),
+        b: pressed(11),
+        battery_level: 0,
+        battery_capacity: 0,
+    }
+}
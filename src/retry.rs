@@ -0,0 +1,169 @@
+//! # Retrying Device Operations.
+//!
+//! Smart devices occasionally fail a single read, e.g. when a cable is jostled
+//! or a sensor has not yet produced a reading, and succeed again a moment
+//! later. [`retry()`] wraps such an operation in a [`Selectable`] event which
+//! retries it on transient errors, waiting longer between each attempt as
+//! described by a [`RetryPolicy`], and gives up on errors which will not go
+//! away by themselves.
+//!
+//! # Example
+//!
+//! ```
+//! let distance = select! {
+//!     d = retry(RetryPolicy::new(), || sensor.get_distance()) => d?,
+//!     _ = ctx.done() => return Ok(()),
+//! };
+//! ```
+
+use core::time::Duration;
+
+use crate::{
+    error::{DeviceError, DeviceErrorKind, Error},
+    rtos::{time_since_start, GenericSleep, Instant, Selectable},
+};
+
+/// Describes how many times, and how often, [`retry()`] attempts an
+/// operation.
+///
+/// The delay before the first retry is `initial_delay`, and each subsequent
+/// delay is `multiplier` times the previous one, up to `max_delay`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts, including the first. At least one
+    /// attempt is always made.
+    pub max_attempts: u32,
+    /// The delay before the first retry.
+    pub initial_delay: Duration,
+    /// The factor by which the delay grows after each retry.
+    pub multiplier: f64,
+    /// The longest delay between attempts.
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Creates a new retry policy with default values, which makes up to 5
+    /// attempts, waiting 10ms before the first retry and doubling the delay
+    /// each time, up to 100ms.
+    pub fn new() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_delay: Duration::from_millis(10),
+            multiplier: 2.0,
+            max_delay: Duration::from_millis(100),
+        }
+    }
+
+    /// Creates a new retry policy which makes up to `max_attempts` attempts
+    /// with a fixed `delay` between them.
+    pub fn fixed(max_attempts: u32, delay: Duration) -> Self {
+        Self {
+            max_attempts,
+            initial_delay: delay,
+            multiplier: 1.0,
+            max_delay: delay,
+        }
+    }
+
+    /// Gets the delay after the given delay.
+    fn next_delay(&self, delay: Duration) -> Duration {
+        delay.mul_f64(self.multiplier).min(self.max_delay)
+    }
+}
+
+impl Default for RetryPolicy {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Represents an error which may be transient, i.e. an operation which failed
+/// with it may succeed if attempted again.
+pub trait Transient {
+    /// Checks whether the error may be transient.
+    fn is_transient(&self) -> bool;
+}
+
+impl Transient for DeviceErrorKind {
+    fn is_transient(&self) -> bool {
+        match *self {
+            // A device reports the wrong type while it is unplugged, so this
+            // is usually a loose cable.
+            DeviceErrorKind::WrongDevice { .. }
+            | DeviceErrorKind::AlreadyCalibrating
+            | DeviceErrorKind::NoReading => true,
+            DeviceErrorKind::PortOutOfRange | DeviceErrorKind::NonMatchingExpanders => false,
+            DeviceErrorKind::System(n) => is_transient_errno(n),
+        }
+    }
+}
+
+impl Transient for DeviceError {
+    #[inline]
+    fn is_transient(&self) -> bool {
+        self.kind.is_transient()
+    }
+}
+
+impl Transient for Error {
+    fn is_transient(&self) -> bool {
+        match *self {
+            Error::System(n) => is_transient_errno(n),
+            Error::Custom(_) => false,
+        }
+    }
+}
+
+fn is_transient_errno(n: i32) -> bool {
+    matches!(
+        n,
+        libc::EAGAIN | libc::EBUSY | libc::EINTR | libc::EIO | libc::ENODEV | libc::ETIMEDOUT
+    )
+}
+
+/// A [`Selectable`] event which attempts `op` immediately, and again after
+/// each transient failure as described by `policy`. It occurs with the result
+/// of the first successful attempt, or the error of the last attempt if the
+/// error is not transient or the attempts run out.
+pub fn retry<T, E: Transient>(
+    policy: RetryPolicy,
+    op: impl FnMut() -> Result<T, E>,
+) -> impl Selectable<Result<T, E>> {
+    struct RetrySelect<F> {
+        policy: RetryPolicy,
+        op: F,
+        attempts: u32,
+        delay: Duration,
+        next: Instant,
+    }
+
+    impl<T, E: Transient, F: FnMut() -> Result<T, E>> Selectable<Result<T, E>> for RetrySelect<F> {
+        fn poll(mut self) -> Result<Result<T, E>, Self> {
+            if time_since_start() < self.next {
+                return Err(self);
+            }
+            self.attempts += 1;
+            match (self.op)() {
+                Err(err) if err.is_transient() && self.attempts < self.policy.max_attempts => {
+                    self.next = time_since_start() + self.delay;
+                    self.delay = self.policy.next_delay(self.delay);
+                    Err(self)
+                }
+                result => Ok(result),
+            }
+        }
+
+        fn sleep(&self) -> GenericSleep {
+            GenericSleep::Timestamp(self.next)
+        }
+    }
+
+    RetrySelect {
+        delay: policy.initial_delay,
+        policy,
+        op,
+        attempts: 0,
+        next: time_since_start(),
+    }
+}
@@ -1,6 +1,20 @@
 //! For use with the [`entry!`](crate::entry!) macro.
 
-use crate::{io::println, peripherals::Peripherals, rtos::Context, state_machine};
+use spin::Once;
+
+use crate::{
+    bindings,
+    io::println,
+    peripherals::Peripherals,
+    rtos::{Broadcast, BroadcastListener, Context},
+    state_machine,
+};
+
+static LIFECYCLE: Once<Broadcast<CompetitionStatus>> = Once::new();
+
+fn lifecycle() -> &'static Broadcast<CompetitionStatus> {
+    LIFECYCLE.call_once(|| Broadcast::new(CompetitionStatus::get()))
+}
 
 /// A trait representing a competition-ready VEX Robot.
 pub trait Robot: Send + Sync + 'static {
@@ -35,6 +49,38 @@ pub trait Robot: Send + Sync + 'static {
     }
 }
 
+/// Describes the competition state, as reported by the field controller or
+/// competition switch.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CompetitionStatus {
+    /// Whether the robot is disabled.
+    pub disabled: bool,
+    /// Whether the robot is in the autonomous period.
+    pub autonomous: bool,
+    /// Whether a field controller or competition switch is connected.
+    pub connected: bool,
+}
+
+impl CompetitionStatus {
+    /// Gets the current competition state.
+    pub fn get() -> Self {
+        let status = unsafe { bindings::competition_get_status() } as u32;
+        Self {
+            disabled: status & bindings::COMPETITION_DISABLED != 0,
+            autonomous: status & bindings::COMPETITION_AUTONOMOUS != 0,
+            connected: status & bindings::COMPETITION_CONNECTED != 0,
+        }
+    }
+
+    #[inline]
+    /// Creates a listener which receives the competition state each time the
+    /// [`Competition`] state machine enters a new period, e.g. when the robot
+    /// is enabled for autonomous.
+    pub fn listen() -> BroadcastListener<Self> {
+        lifecycle().listen()
+    }
+}
+
 state_machine! {
     /// Competition state machine.
     pub Competition<R: Robot>(robot: R) {
@@ -43,21 +89,25 @@ state_machine! {
 
     /// Runs on initialization.
     initialize(ctx) [robot] {
+        lifecycle().publish(CompetitionStatus::get());
         robot.initialize(ctx);
     }
 
     /// Runs during the autonomous period.
     autonomous(ctx) [robot] {
+        lifecycle().publish(CompetitionStatus::get());
         robot.autonomous(ctx);
     }
 
     /// Runs during the opcontrol period.
     opcontrol(ctx) [robot] {
+        lifecycle().publish(CompetitionStatus::get());
         robot.opcontrol(ctx);
     }
 
     /// Runs when the robot is disabled.
     disabled(ctx) [robot] {
+        lifecycle().publish(CompetitionStatus::get());
         robot.disabled(ctx);
     }
 }
@@ -2,10 +2,16 @@
 
 use crate::{
     bindings,
-    error::{get_errno, Error},
+    device::RotarySensor,
+    diagnostics,
+    error::{DeviceError, DevicePort},
     rtos::DataSource,
+    units::Degrees,
 };
 
+/// Centidegrees per degree, the unit used by the sensor.
+const CENTIDEGREES_PER_DEGREE: f64 = 100.0;
+
 /// A struct which represents a V5 smart port configured as a rotation sensor.
 pub struct RotationSensor {
     port: u8,
@@ -31,7 +37,7 @@ impl RotationSensor {
     /// Sensor angle.
     pub fn reset(&mut self) -> Result<(), RotationSensorError> {
         match unsafe { bindings::rotation_reset(self.port) } {
-            bindings::PROS_ERR_ => Err(RotationSensorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
@@ -39,7 +45,7 @@ impl RotationSensor {
     /// Set the Rotation sensor to a desired rotation value in centidegrees.
     pub fn set_position(&mut self, rotation: u32) -> Result<(), RotationSensorError> {
         match unsafe { bindings::rotation_set_position(self.port, rotation) } {
-            bindings::PROS_ERR_ => Err(RotationSensorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
@@ -48,7 +54,7 @@ impl RotationSensor {
     /// Sensor angle.
     pub fn reset_position(&mut self) -> Result<(), RotationSensorError> {
         match unsafe { bindings::rotation_reset_position(self.port) } {
-            bindings::PROS_ERR_ => Err(RotationSensorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
@@ -56,7 +62,7 @@ impl RotationSensor {
     /// Get the Rotation Sensor’s current position in centidegrees.
     pub fn get_position(&self) -> Result<i32, RotationSensorError> {
         match unsafe { bindings::rotation_get_position(self.port) } {
-            x if x == bindings::PROS_ERR_ => Err(RotationSensorError::from_errno()),
+            x if x == bindings::PROS_ERR_ => Err(self.error()),
             x => Ok(x),
         }
     }
@@ -64,7 +70,7 @@ impl RotationSensor {
     /// Get the Rotation Sensor’s current velocity in centidegrees per second.
     pub fn get_velocity(&self) -> Result<i32, RotationSensorError> {
         match unsafe { bindings::rotation_get_velocity(self.port) } {
-            x if x == bindings::PROS_ERR_ => Err(RotationSensorError::from_errno()),
+            x if x == bindings::PROS_ERR_ => Err(self.error()),
             x => Ok(x),
         }
     }
@@ -72,15 +78,54 @@ impl RotationSensor {
     /// Get the Rotation Sensor’s current angle in centidegrees (0-36000).
     pub fn get_angle(&self) -> Result<i32, RotationSensorError> {
         match unsafe { bindings::rotation_get_angle(self.port) } {
-            x if x == bindings::PROS_ERR_ => Err(RotationSensorError::from_errno()),
+            x if x == bindings::PROS_ERR_ => Err(self.error()),
             x => Ok(x),
         }
     }
 
+    /// Get the Rotation Sensor’s current position in degrees.
+    pub fn get_position_degrees(&self) -> Result<f64, RotationSensorError> {
+        Ok(self.get_position()? as f64 / CENTIDEGREES_PER_DEGREE)
+    }
+
+    /// Get the Rotation Sensor’s current position in revolutions.
+    pub fn get_position_revolutions(&self) -> Result<f64, RotationSensorError> {
+        Ok(self.get_position_degrees()? / 360.0)
+    }
+
+    /// Set the Rotation sensor to a desired rotation value in degrees.
+    pub fn set_position_degrees(&mut self, rotation: f64) -> Result<(), RotationSensorError> {
+        // The sensor treats the position as signed, despite the PROS signature.
+        self.set_position((rotation * CENTIDEGREES_PER_DEGREE) as i32 as u32)
+    }
+
+    /// Get the Rotation Sensor’s current velocity in degrees per second.
+    pub fn get_velocity_degrees(&self) -> Result<f64, RotationSensorError> {
+        Ok(self.get_velocity()? as f64 / CENTIDEGREES_PER_DEGREE)
+    }
+
+    /// Get the Rotation Sensor’s current angle in degrees [0-360).
+    pub fn get_angle_degrees(&self) -> Result<f64, RotationSensorError> {
+        Ok(Degrees(self.get_angle()? as f64 / CENTIDEGREES_PER_DEGREE)
+            .wrap_positive()
+            .0)
+    }
+
+    /// Computes the shortest turn from the Rotation Sensor’s current angle to a
+    /// target angle in degrees.
+    ///
+    /// The result is bounded by (-180,180]; a positive value means the target
+    /// is in the sensor's positive direction from the current angle.
+    pub fn angle_to(&self, target: f64) -> Result<f64, RotationSensorError> {
+        Ok(Degrees(self.get_angle_degrees()?)
+            .angle_to(Degrees(target))
+            .0)
+    }
+
     /// Set the rotation direction of the sensor.
     pub fn set_reversed(&mut self, reverse: bool) -> Result<(), RotationSensorError> {
         match unsafe { bindings::rotation_set_reversed(self.port, reverse) } {
-            bindings::PROS_ERR_ => Err(RotationSensorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
@@ -88,7 +133,7 @@ impl RotationSensor {
     /// Reverses the rotational sensor’s direction.
     pub fn reverse(&mut self) -> Result<(), RotationSensorError> {
         match unsafe { bindings::rotation_reverse(self.port) } {
-            bindings::PROS_ERR_ => Err(RotationSensorError::from_errno()),
+            bindings::PROS_ERR_ => Err(self.error()),
             _ => Ok(()),
         }
     }
@@ -96,10 +141,17 @@ impl RotationSensor {
     /// Get the Rotation Sensor’s reversed flag.
     pub fn get_reversed(&self) -> Result<bool, RotationSensorError> {
         match unsafe { bindings::rotation_get_reversed(self.port) } {
-            x if x == bindings::PROS_ERR_ => Err(RotationSensorError::from_errno()),
+            x if x == bindings::PROS_ERR_ => Err(self.error()),
             x => Ok(x != 0),
         }
     }
+
+    fn error(&self) -> DeviceError {
+        diagnostics::record_error(DeviceError::from_errno(
+            DevicePort::Smart(self.port),
+            "a rotation sensor",
+        ))
+    }
 }
 
 impl DataSource for RotationSensor {
@@ -116,6 +168,18 @@ impl DataSource for RotationSensor {
     }
 }
 
+impl RotarySensor for RotationSensor {
+    #[inline]
+    fn position(&self) -> Result<f64, DeviceError> {
+        self.get_position_degrees()
+    }
+
+    #[inline]
+    fn reset_position(&mut self) -> Result<(), DeviceError> {
+        RotationSensor::reset_position(self)
+    }
+}
+
 /// Represents the data that can be read from a rotation sensor.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct RotationSensorData {
@@ -127,35 +191,6 @@ pub struct RotationSensorData {
     pub angle: i32,
 }
 
-/// Represents possible errors for distance sensor operations.
-#[derive(Debug)]
-pub enum RotationSensorError {
-    /// Port is out of range (1-21).
-    PortOutOfRange,
-    /// Port cannot be configured as a distance sensor.
-    PortNotDistanceSensor,
-    /// Unknown error.
-    Unknown(i32),
-}
-
-impl RotationSensorError {
-    fn from_errno() -> Self {
-        match get_errno() {
-            libc::ENXIO => Self::PortOutOfRange,
-            libc::ENODEV => Self::PortNotDistanceSensor,
-            x => Self::Unknown(x),
-        }
-    }
-}
-
-impl From<RotationSensorError> for Error {
-    fn from(err: RotationSensorError) -> Self {
-        match err {
-            RotationSensorError::PortOutOfRange => Error::Custom("port out of range".into()),
-            RotationSensorError::PortNotDistanceSensor => {
-                Error::Custom("port not a rotation sensor".into())
-            }
-            RotationSensorError::Unknown(n) => Error::System(n),
-        }
-    }
-}
+/// Represents possible errors for rotation sensor operations. This is an alias
+/// of [`DeviceError`].
+pub type RotationSensorError = DeviceError;
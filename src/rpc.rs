@@ -0,0 +1,389 @@
+//! # Coprocessor RPC.
+//!
+//! An [`Rpc`] endpoint lets the robot and a coprocessor, such as a Raspberry
+//! Pi or Jetson connected to a smart port, call functions on each other. Either
+//! side may send a request naming a method, which the other side answers with
+//! a response carrying the same ID, or a notification, which is not answered
+//! and suits streams of data such as vision detections.
+//!
+//! Messages are serialized with [`postcard`] and sent in the same
+//! checksummed, COBS-encoded frames as [telemetry](crate::telemetry). Each
+//! frame holds a message kind (0 for a request, 1 for a successful response, 2
+//! for an error response and 3 for a notification), a little-endian `u32`
+//! request ID, the length of the method name as a byte, the method name, and
+//! the serialized body; the body of an error response is the UTF-8 error
+//! message, and responses have an empty method name.
+//!
+//! # Example
+//!
+//! ```
+//! let rpc = Rpc::new(serial);
+//!
+//! // Called by the coprocessor:
+//! rpc.register("intake", move |voltage: i32| {
+//!     intake
+//!         .move_voltage(voltage)
+//!         .map_err(|err| format!("{:?}", err))
+//! });
+//!
+//! // Streamed by the coprocessor into a broadcast for other tasks:
+//! let detections = Arc::new(Broadcast::new(Vec::new()));
+//! let publisher = detections.clone();
+//! rpc.register("detections", move |d: Vec<(f64, f64)>| {
+//!     publisher.publish(d);
+//!     Ok(())
+//! });
+//!
+//! // Called by the robot:
+//! let target: (f64, f64) = select(rpc.call("target", &(), Duration::from_millis(100)))?;
+//! ```
+
+use alloc::{
+    boxed::Box,
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    sync::{Arc, Weak},
+    vec::Vec,
+};
+use core::{marker::PhantomData, time::Duration};
+
+use owner_monad::OwnerMut;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    error::Error,
+    io::eprintln,
+    rtos::{
+        handle_event, time_since_start, Event, EventHandle, GenericSleep, Instant, Loop, Mutex,
+        Selectable, Task, Timeout,
+    },
+    serial::Serial,
+    telemetry::{decode_frame, encode_frame},
+};
+
+/// The interval at which the serial port is checked for incoming frames.
+const POLL_PERIOD: Duration = Duration::from_millis(5);
+
+/// The largest frame which is accepted; longer frames are discarded.
+const MAX_FRAME_LEN: usize = 1024;
+
+const REQUEST: u8 = 0;
+const RESPONSE: u8 = 1;
+const ERROR_RESPONSE: u8 = 2;
+const NOTIFICATION: u8 = 3;
+
+type Handler = Box<dyn FnMut(&[u8]) -> Result<Vec<u8>, String> + Send>;
+
+/// An RPC endpoint on a serial connection to a coprocessor. See the
+/// [module-level documentation](self).
+///
+/// Incoming messages are handled by a background task, which exits when the
+/// endpoint is dropped. Handlers run on that task, one at a time, so they
+/// should return promptly, and must not wait for the result of a call, since
+/// the response would never be received.
+pub struct Rpc(Arc<Mutex<RpcData>>);
+
+impl Rpc {
+    #[inline]
+    /// Creates a new endpoint on a serial port and spawns its task. Panics on
+    /// failure; see [`Rpc::try_new()`].
+    pub fn new(serial: Serial) -> Self {
+        Self::try_new(serial).unwrap_or_else(|err| panic!("failed to create rpc: {:?}", err))
+    }
+
+    /// Creates a new endpoint on a serial port and spawns its task.
+    pub fn try_new(serial: Serial) -> Result<Self, Error> {
+        let data = Arc::new(Mutex::try_new(RpcData {
+            serial,
+            handlers: BTreeMap::new(),
+            pending: BTreeMap::new(),
+            next_id: 0,
+            event: Event::new(),
+        })?);
+        let weak = Arc::downgrade(&data);
+        Task::spawn_ext(
+            "rpc",
+            Task::DEFAULT_PRIORITY,
+            Task::DEFAULT_STACK_DEPTH,
+            move || {
+                let mut l = Loop::new(POLL_PERIOD);
+                let mut frame = Vec::new();
+                let mut buffer = [0; 64];
+                while let Some(data) = weak.upgrade() {
+                    loop {
+                        let count = match data.lock().serial.read(&mut buffer) {
+                            Ok(count) => count,
+                            Err(err) => {
+                                eprintln!("{:?}", err);
+                                0
+                            }
+                        };
+                        if count == 0 {
+                            break;
+                        }
+                        for &byte in &buffer[..count] {
+                            if byte != 0 {
+                                // A frame which grows past the limit is
+                                // discarded once it ends.
+                                if frame.len() <= MAX_FRAME_LEN {
+                                    frame.push(byte);
+                                }
+                            } else {
+                                if frame.len() <= MAX_FRAME_LEN {
+                                    if let Some(message) = decode_frame(&frame) {
+                                        receive(&data, &message);
+                                    }
+                                }
+                                frame.clear();
+                            }
+                        }
+                    }
+                    drop(data);
+                    l.delay();
+                }
+            },
+        )?;
+        Ok(Self(data))
+    }
+
+    /// Registers a handler for requests and notifications of the given method,
+    /// replacing any existing handler. The value returned by the handler is
+    /// sent in response to requests, and discarded for notifications.
+    pub fn register<Req: DeserializeOwned, Resp: Serialize>(
+        &self,
+        method: &str,
+        mut handler: impl FnMut(Req) -> Result<Resp, String> + Send + 'static,
+    ) {
+        let handler: Handler = Box::new(move |body| {
+            let request = postcard::from_bytes(body).map_err(|err| format!("{:?}", err))?;
+            let response = handler(request)?;
+            postcard::to_allocvec(&response).map_err(|err| format!("{:?}", err))
+        });
+        self.0.lock().handlers.insert(method.into(), Some(handler));
+    }
+
+    #[inline]
+    /// Removes the handler for the given method. Requests for it are then
+    /// answered with an error.
+    pub fn unregister(&self, method: &str) {
+        self.0.lock().handlers.remove(method);
+    }
+
+    /// Sends a request to the coprocessor. The [`Selectable`] event returned
+    /// occurs with the response, or with [`RpcError::Timeout`] if none arrives
+    /// within `timeout`. The response is ignored if the event is dropped
+    /// first.
+    ///
+    /// Method names must be no longer than 255 bytes.
+    pub fn call<Req: Serialize, Resp: DeserializeOwned>(
+        &self,
+        method: &str,
+        request: &Req,
+        timeout: Duration,
+    ) -> impl Selectable<Result<Resp, RpcError>> {
+        struct CallSelect<Resp> {
+            data: Arc<Mutex<RpcData>>,
+            id: u32,
+            deadline: Instant,
+            error: Option<RpcError>,
+            _handle: EventHandle<RpcHandle>,
+            _resp: PhantomData<Resp>,
+        }
+
+        impl<Resp: DeserializeOwned> Selectable<Result<Resp, RpcError>> for CallSelect<Resp> {
+            fn poll(mut self) -> Result<Result<Resp, RpcError>, Self> {
+                if let Some(err) = self.error.take() {
+                    return Ok(Err(err));
+                }
+                let response = {
+                    let mut lock = self.data.lock();
+                    match lock.pending.get(&self.id) {
+                        Some(Some(_)) => lock.pending.remove(&self.id).flatten(),
+                        _ => None,
+                    }
+                };
+                match response {
+                    Some(Ok(body)) => Ok(postcard::from_bytes(&body).map_err(RpcError::from)),
+                    Some(Err(message)) => Ok(Err(RpcError::Remote(message))),
+                    None if time_since_start() >= self.deadline => Ok(Err(RpcError::Timeout)),
+                    None => Err(self),
+                }
+            }
+
+            #[inline]
+            fn sleep(&self) -> GenericSleep {
+                if self.error.is_some() {
+                    GenericSleep::Timestamp(Instant::from_millis(0))
+                } else {
+                    GenericSleep::NotifyTake(Some(self.deadline))
+                }
+            }
+        }
+
+        impl<Resp> Drop for CallSelect<Resp> {
+            fn drop(&mut self) {
+                self.data.lock().pending.remove(&self.id);
+            }
+        }
+
+        let deadline = time_since_start() + timeout;
+        let mut lock = self.0.lock();
+        let id = lock.next_id;
+        lock.next_id = lock.next_id.wrapping_add(1);
+        let error = postcard::to_allocvec(request)
+            .map_err(RpcError::from)
+            .and_then(|body| lock.send(REQUEST, id, method, &body))
+            .err();
+        if error.is_none() {
+            lock.pending.insert(id, None);
+        }
+        drop(lock);
+        CallSelect {
+            _handle: handle_event(RpcHandle(Arc::downgrade(&self.0))),
+            data: self.0.clone(),
+            id,
+            deadline,
+            error,
+            _resp: PhantomData,
+        }
+    }
+
+    /// Sends a notification to the coprocessor, which is not answered.
+    ///
+    /// Method names must be no longer than 255 bytes.
+    pub fn notify<T: Serialize>(&self, method: &str, message: &T) -> Result<(), RpcError> {
+        let body = postcard::to_allocvec(message)?;
+        self.0.lock().send(NOTIFICATION, 0, method, &body)
+    }
+}
+
+struct RpcData {
+    serial: Serial,
+    // A handler is taken out while it runs, so that the lock is not held.
+    handlers: BTreeMap<String, Option<Handler>>,
+    pending: BTreeMap<u32, Option<Result<Vec<u8>, String>>>,
+    next_id: u32,
+    event: Event,
+}
+
+impl RpcData {
+    fn send(&mut self, kind: u8, id: u32, method: &str, body: &[u8]) -> Result<(), RpcError> {
+        let method_len = u8::try_from(method.len())
+            .unwrap_or_else(|_| panic!("rpc method name too long: {}", method));
+        let mut message = Vec::with_capacity(6 + method.len() + body.len());
+        message.push(kind);
+        message.extend_from_slice(&id.to_le_bytes());
+        message.push(method_len);
+        message.extend_from_slice(method.as_bytes());
+        message.extend_from_slice(body);
+        let frame = encode_frame(&message);
+        if self.serial.get_write_free().map_err(RpcError::Serial)? < frame.len() {
+            return Err(RpcError::Full);
+        }
+        self.serial.write(&frame).map_err(RpcError::Serial)?;
+        Ok(())
+    }
+}
+
+/// Handles a message received from the coprocessor. Malformed messages are
+/// ignored.
+fn receive(data: &Mutex<RpcData>, message: &[u8]) {
+    if message.len() < 6 {
+        return;
+    }
+    let kind = message[0];
+    let id = u32::from_le_bytes([message[1], message[2], message[3], message[4]]);
+    let method_end = 6 + message[5] as usize;
+    let method = match message
+        .get(6..method_end)
+        .and_then(|method| core::str::from_utf8(method).ok())
+    {
+        Some(method) => method,
+        None => return,
+    };
+    let body = &message[method_end..];
+    match kind {
+        REQUEST | NOTIFICATION => {
+            let handler = data.lock().handlers.get_mut(method).and_then(Option::take);
+            let result = match handler {
+                Some(mut handler) => {
+                    let result = handler(body);
+                    // Only restore the handler if it was not replaced meanwhile.
+                    if let Some(slot @ None) = data.lock().handlers.get_mut(method) {
+                        *slot = Some(handler);
+                    }
+                    result
+                }
+                None => Err(format!("unknown method: {}", method)),
+            };
+            if kind == REQUEST {
+                let sent = match result {
+                    Ok(body) => data.lock().send(RESPONSE, id, "", &body),
+                    Err(message) => data.lock().send(ERROR_RESPONSE, id, "", message.as_bytes()),
+                };
+                if let Err(err) = sent {
+                    eprintln!("{:?}", Error::from(err));
+                }
+            }
+        }
+        RESPONSE | ERROR_RESPONSE => {
+            let mut lock = data.lock();
+            if let Some(slot @ None) = lock.pending.get_mut(&id) {
+                *slot = Some(if kind == RESPONSE {
+                    Ok(body.to_vec())
+                } else {
+                    Err(String::from_utf8_lossy(body).to_string())
+                });
+                lock.event.notify();
+            }
+        }
+        _ => {}
+    }
+}
+
+struct RpcHandle(Weak<Mutex<RpcData>>);
+
+impl OwnerMut<Event> for RpcHandle {
+    fn with<'a, U>(&'a mut self, f: impl FnOnce(&mut Event) -> U) -> Option<U>
+    where
+        Event: 'a,
+    {
+        Some(f(&mut self.0.upgrade()?.lock().event))
+    }
+}
+
+/// Represents possible errors for RPC operations.
+#[derive(Debug)]
+pub enum RpcError {
+    /// The coprocessor did not respond in time.
+    Timeout,
+    /// The coprocessor responded with an error message.
+    Remote(String),
+    /// The output buffer of the serial port does not have room for the
+    /// message.
+    Full,
+    /// A message could not be serialized or deserialized.
+    Serialize(postcard::Error),
+    /// An error occurred writing to the serial port.
+    Serial(Error),
+}
+
+impl From<postcard::Error> for RpcError {
+    #[inline]
+    fn from(err: postcard::Error) -> Self {
+        Self::Serialize(err)
+    }
+}
+
+impl From<RpcError> for Error {
+    fn from(err: RpcError) -> Self {
+        match err {
+            RpcError::Timeout => Timeout.into(),
+            RpcError::Remote(message) => Error::Custom(format!("remote error: {}", message)),
+            RpcError::Full => Error::Custom("serial output buffer full".into()),
+            RpcError::Serialize(err) => Error::Custom(format!("{:?}", err)),
+            RpcError::Serial(err) => err,
+        }
+    }
+}
@@ -21,6 +21,7 @@ impl<T: Clone> Broadcast<T> {
     pub fn try_new(data: T) -> Result<Self, Error> {
         Ok(Self(Arc::new(Mutex::try_new(BroadcastData {
             data: Arc::new(data),
+            count: 1,
             event: Event::new(),
         })?)))
     }
@@ -30,16 +31,33 @@ impl<T: Clone> Broadcast<T> {
         (*self.0.lock().data).clone()
     }
 
+    #[inline]
+    /// Gets the number of values which have been published, counting the
+    /// initial value.
+    pub fn count(&self) -> u64 {
+        self.0.lock().count
+    }
+
     #[inline]
     /// Creates a new listener for the broadcast event.
     pub fn listen(&self) -> BroadcastListener<T> {
-        BroadcastListener::new(Weak::new(), Arc::downgrade(&self.0))
+        let count = self.0.lock().count;
+        BroadcastListener::new(
+            ListenerState {
+                value: Weak::new(),
+                seen: count - 1,
+                missed: 0,
+                total_missed: 0,
+            },
+            Arc::downgrade(&self.0),
+        )
     }
 
     /// Publishes a new value for the broadcast event.
     pub fn publish(&self, data: T) {
         let mut lock = self.0.lock();
         lock.data = Arc::new(data);
+        lock.count += 1;
         lock.event.notify();
     }
 }
@@ -47,20 +65,36 @@ impl<T: Clone> Broadcast<T> {
 #[derive(Clone)]
 /// Provides a means of listening to updates from a [`Broadcast`] event.
 pub struct BroadcastListener<T: Clone> {
-    value: Weak<T>,
+    state: ListenerState<T>,
     data: Weak<Mutex<BroadcastData<T>>>,
 }
 
 impl<T: Clone> BroadcastListener<T> {
     #[inline]
-    fn new(value: Weak<T>, data: Weak<Mutex<BroadcastData<T>>>) -> Self {
-        Self { value, data }
+    fn new(state: ListenerState<T>, data: Weak<Mutex<BroadcastData<T>>>) -> Self {
+        Self { state, data }
     }
 
     #[inline]
     /// Get the latest unprocessed value from the event, if there is one.
     pub fn next_value(&mut self) -> Option<T> {
-        Self::next_value_impl(&mut self.value, &self.data)
+        Self::next_value_impl(&mut self.state, &self.data)
+    }
+
+    #[inline]
+    /// Gets the number of published values which were skipped over because a
+    /// newer value was published before they were received, between the two
+    /// most recently received values. A listener which keeps up always
+    /// reports zero.
+    pub fn missed(&self) -> u64 {
+        self.state.missed
+    }
+
+    #[inline]
+    /// Gets the total number of published values which have been skipped over
+    /// since the listener was created; see [`BroadcastListener::missed()`].
+    pub fn total_missed(&self) -> u64 {
+        self.state.total_missed
     }
 
     #[inline]
@@ -68,16 +102,16 @@ impl<T: Clone> BroadcastListener<T> {
     /// underlying [`Broadcast`] event.
     pub fn select(&'_ mut self) -> impl Selectable<T> + '_ {
         struct BroadcastSelect<'b, T: Clone> {
-            value: &'b mut Weak<T>,
+            state: &'b mut ListenerState<T>,
             handle: EventHandle<&'b Weak<Mutex<BroadcastData<T>>>>,
         }
 
         impl<'b, T: Clone> Selectable<T> for BroadcastSelect<'b, T> {
             #[inline]
             fn poll(mut self) -> Result<T, Self> {
-                let value = &mut self.value;
+                let state = &mut self.state;
                 self.handle
-                    .with(|data| BroadcastListener::next_value_impl(value, *data))
+                    .with(|data| BroadcastListener::next_value_impl(state, *data))
                     .flatten()
                     .ok_or(self)
             }
@@ -88,24 +122,39 @@ impl<T: Clone> BroadcastListener<T> {
         }
 
         BroadcastSelect {
-            value: &mut self.value,
+            state: &mut self.state,
             handle: handle_event(&self.data),
         }
     }
 
-    fn next_value_impl(value: &mut Weak<T>, data: &Weak<Mutex<BroadcastData<T>>>) -> Option<T> {
+    fn next_value_impl(
+        state: &mut ListenerState<T>,
+        data: &Weak<Mutex<BroadcastData<T>>>,
+    ) -> Option<T> {
         let data = data.upgrade()?;
         let lock = data.lock();
-        match value.upgrade() {
+        match state.value.upgrade() {
             Some(arc) if Arc::ptr_eq(&arc, &lock.data) => None,
             _ => {
-                *value = Arc::downgrade(&lock.data);
+                state.value = Arc::downgrade(&lock.data);
+                state.missed = lock.count - state.seen - 1;
+                state.total_missed += state.missed;
+                state.seen = lock.count;
                 Some((*lock.data).clone())
             }
         }
     }
 }
 
+#[derive(Clone)]
+struct ListenerState<T> {
+    value: Weak<T>,
+    /// The publish count of the most recently received value.
+    seen: u64,
+    missed: u64,
+    total_missed: u64,
+}
+
 /// Describes an object which is a source of data, such as a sensor.
 ///
 /// Used to facilitate broadcasting readings via [`IntoBroadcast`].
@@ -194,5 +243,6 @@ impl<T> OwnerMut<Event> for &Weak<Mutex<BroadcastData<T>>> {
 
 struct BroadcastData<T> {
     data: Arc<T>,
+    count: u64,
     event: Event,
 }
@@ -0,0 +1,235 @@
+use alloc::{collections::VecDeque, sync::Arc};
+use core::time::Duration;
+use owner_monad::OwnerMut;
+
+use super::{
+    delay, handle_event, Event, EventHandle, GenericSleep, Instant, Mutex, Selectable, Timeout,
+};
+use crate::{error::Error, select};
+
+/// Represents the sending end of a buffered channel.
+pub struct BufferedSendChannel<T>(Arc<Mutex<BufferedData<T>>>);
+
+impl<T> BufferedSendChannel<T> {
+    /// A [`Selectable`] event which resolves when `value` has been placed in
+    /// the channel's buffer, which is immediately unless the buffer is full.
+    /// If the event is dropped before it occurs, the value is not sent.
+    pub fn select(&self, value: T) -> impl '_ + Selectable {
+        struct SendSelect<'b, T> {
+            value: T,
+            data: &'b Mutex<BufferedData<T>>,
+            _handle: EventHandle<SendWrapper<'b, T>>,
+        }
+
+        impl<'b, T> Selectable for SendSelect<'b, T> {
+            fn poll(self) -> Result<(), Self> {
+                let value = match self.data.lock().push(self.value) {
+                    Ok(()) => return Ok(()),
+                    Err(value) => value,
+                };
+                Err(Self { value, ..self })
+            }
+
+            fn sleep(&self) -> GenericSleep {
+                if self.data.lock().is_full() {
+                    GenericSleep::NotifyTake(None)
+                } else {
+                    GenericSleep::Timestamp(Instant::from_millis(0))
+                }
+            }
+        }
+
+        SendSelect {
+            value,
+            data: &self.0,
+            _handle: handle_event(SendWrapper(&self.0)),
+        }
+    }
+
+    #[inline]
+    /// Places `value` in the channel's buffer if there is room, without
+    /// blocking; otherwise, gives it back.
+    pub fn try_send(&self, value: T) -> Result<(), T> {
+        self.0.lock().push(value)
+    }
+
+    /// Sends `value` on the channel, blocking while the buffer is full, until
+    /// `timeout` has passed. If the timeout expires, the value is dropped.
+    pub fn send_timeout(&self, value: T, timeout: Duration) -> Result<(), Timeout> {
+        select! {
+            _ = self.select(value) => Ok(()),
+            _ = delay(timeout) => Err(Timeout),
+        }
+    }
+
+    #[inline]
+    /// Gets the number of values waiting in the channel's buffer.
+    pub fn len(&self) -> usize {
+        self.0.lock().buffer.len()
+    }
+
+    #[inline]
+    /// Checks whether the channel's buffer is empty.
+    pub fn is_empty(&self) -> bool {
+        self.0.lock().buffer.is_empty()
+    }
+}
+
+impl<T> Clone for BufferedSendChannel<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+/// Represents the receive end of a buffered channel.
+pub struct BufferedReceiveChannel<T>(Arc<Mutex<BufferedData<T>>>);
+
+impl<T> BufferedReceiveChannel<T> {
+    /// A [`Selectable`] event which resolves with the oldest value in the
+    /// channel's buffer, which is immediately unless the buffer is empty.
+    pub fn select(&self) -> impl '_ + Selectable<T> {
+        struct ReceiveSelect<'b, T> {
+            data: &'b Mutex<BufferedData<T>>,
+            _handle: EventHandle<ReceiveWrapper<'b, T>>,
+        }
+
+        impl<'b, T> Selectable<T> for ReceiveSelect<'b, T> {
+            fn poll(self) -> Result<T, Self> {
+                let value = self.data.lock().pop();
+                value.ok_or(self)
+            }
+
+            fn sleep(&self) -> GenericSleep {
+                if self.data.lock().buffer.is_empty() {
+                    GenericSleep::NotifyTake(None)
+                } else {
+                    GenericSleep::Timestamp(Instant::from_millis(0))
+                }
+            }
+        }
+
+        ReceiveSelect {
+            data: &self.0,
+            _handle: handle_event(ReceiveWrapper(&self.0)),
+        }
+    }
+
+    #[inline]
+    /// Receives the oldest value in the channel's buffer, if there is one,
+    /// without blocking.
+    pub fn try_recv(&self) -> Option<T> {
+        self.0.lock().pop()
+    }
+
+    /// Receives a value from the channel, blocking while the buffer is empty,
+    /// until `timeout` has passed.
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, Timeout> {
+        select! {
+            value = self.select() => Ok(value),
+            _ = delay(timeout) => Err(Timeout),
+        }
+    }
+
+    #[inline]
+    /// Gets the number of values waiting in the channel's buffer.
+    pub fn len(&self) -> usize {
+        self.0.lock().buffer.len()
+    }
+
+    #[inline]
+    /// Checks whether the channel's buffer is empty.
+    pub fn is_empty(&self) -> bool {
+        self.0.lock().buffer.is_empty()
+    }
+}
+
+impl<T> Clone for BufferedReceiveChannel<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+/// Creates a new send-receive pair together representing a buffered channel,
+/// which holds up to `capacity` values. Unlike a rendez-vous
+/// [`channel()`](super::channel()), sending only blocks while the buffer is
+/// full, and receiving only while it is empty. Both ends may be cloned, and
+/// each value is received once. Panics on failure; see
+/// [`try_buffered_channel`].
+///
+/// # Panics
+///
+/// This function panics if `capacity` is zero.
+pub fn buffered_channel<T>(capacity: usize) -> (BufferedSendChannel<T>, BufferedReceiveChannel<T>) {
+    try_buffered_channel(capacity).unwrap_or_else(|err| panic!("failed to create channel: {}", err))
+}
+
+/// Creates a new send-receive pair together representing a buffered channel,
+/// which holds up to `capacity` values. See [`buffered_channel()`].
+///
+/// # Panics
+///
+/// This function panics if `capacity` is zero.
+pub fn try_buffered_channel<T>(
+    capacity: usize,
+) -> Result<(BufferedSendChannel<T>, BufferedReceiveChannel<T>), Error> {
+    assert!(capacity > 0, "buffered channel capacity must be nonzero");
+    let data = Arc::new(Mutex::try_new(BufferedData {
+        buffer: VecDeque::with_capacity(capacity),
+        capacity,
+        send_event: Event::new(),
+        receive_event: Event::new(),
+    })?);
+    let send = BufferedSendChannel(data.clone());
+    let receive = BufferedReceiveChannel(data);
+    Ok((send, receive))
+}
+
+struct BufferedData<T> {
+    buffer: VecDeque<T>,
+    capacity: usize,
+    send_event: Event,
+    receive_event: Event,
+}
+
+impl<T> BufferedData<T> {
+    fn is_full(&self) -> bool {
+        self.buffer.len() >= self.capacity
+    }
+
+    fn push(&mut self, value: T) -> Result<(), T> {
+        if self.is_full() {
+            return Err(value);
+        }
+        self.buffer.push_back(value);
+        self.receive_event.notify();
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        let value = self.buffer.pop_front()?;
+        self.send_event.notify();
+        Some(value)
+    }
+}
+
+struct SendWrapper<'b, T>(&'b Mutex<BufferedData<T>>);
+
+impl<'b, T> OwnerMut<Event> for SendWrapper<'b, T> {
+    fn with<'a, U>(&'a mut self, f: impl FnOnce(&mut Event) -> U) -> Option<U>
+    where
+        Event: 'a,
+    {
+        Some(f(&mut self.0.lock().send_event))
+    }
+}
+
+struct ReceiveWrapper<'b, T>(&'b Mutex<BufferedData<T>>);
+
+impl<'b, T> OwnerMut<Event> for ReceiveWrapper<'b, T> {
+    fn with<'a, U>(&'a mut self, f: impl FnOnce(&mut Event) -> U) -> Option<U>
+    where
+        Event: 'a,
+    {
+        Some(f(&mut self.0.lock().receive_event))
+    }
+}
@@ -0,0 +1,153 @@
+use alloc::format;
+use core::time::Duration;
+
+use crate::{
+    error::Error,
+    rtos::{time_since_start, DataSource, Instant, Mutex},
+};
+
+/// Wraps a [`DataSource`] to limit how often it is read, so that a slow device
+/// such as a vision or GPS sensor can be shared between several tasks.
+///
+/// A reading is reused for every call to [`Cached::read()`] until `interval`
+/// has passed since the device was last read, after which the next call reads
+/// the device again. If that read fails, the last reading is still returned as
+/// long as it is no older than `max_age`; beyond that, [`CachedError::Stale`]
+/// is returned instead, so that callers never silently act on outdated data.
+/// Failed reads are rate-limited too: the device is not read again until
+/// `interval` has passed since the failed attempt, unless there is no earlier
+/// reading to fall back on.
+///
+/// # Example
+///
+/// ```
+/// let gps = Cached::new(gps, Duration::from_millis(20), Duration::from_millis(200));
+/// // From any number of tasks:
+/// let status = gps.read()?;
+/// ```
+pub struct Cached<D: DataSource> {
+    source: D,
+    interval: Duration,
+    max_age: Duration,
+    cache: Mutex<CacheData<D::Data>>,
+}
+
+impl<D: DataSource> Cached<D> {
+    #[inline]
+    /// Wraps a data source, reading it at most once every `interval` and
+    /// serving readings up to `max_age` old when a read fails.
+    pub fn new(source: D, interval: Duration, max_age: Duration) -> Self {
+        Self {
+            source,
+            interval,
+            max_age,
+            cache: Mutex::new(CacheData {
+                reading: None,
+                attempted: None,
+            }),
+        }
+    }
+
+    /// Gets a reading, from the cache if it is recent enough or otherwise from
+    /// the data source.
+    pub fn read(&self) -> Result<D::Data, CachedError<D::Error>> {
+        let mut cache = self.cache.lock();
+        let now = time_since_start();
+        if let (Some(reading), Some(attempted)) = (&cache.reading, cache.attempted) {
+            if now - attempted < self.interval {
+                return self.fallback(reading, now);
+            }
+        }
+        cache.attempted = Some(now);
+        match self.source.read() {
+            Ok(data) => {
+                cache.reading = Some((data.clone(), now));
+                Ok(data)
+            }
+            Err(err) => match &cache.reading {
+                Some(reading) => self.fallback(reading, now),
+                None => Err(CachedError::Source(err)),
+            },
+        }
+    }
+
+    /// Gets the cached reading, unless it is older than `max_age`.
+    fn fallback(
+        &self,
+        (data, at): &(D::Data, Instant),
+        now: Instant,
+    ) -> Result<D::Data, CachedError<D::Error>> {
+        if now - *at <= self.max_age {
+            Ok(data.clone())
+        } else {
+            Err(CachedError::Stale(now - *at))
+        }
+    }
+
+    /// Gets the age of the most recent successful reading, if there is one.
+    pub fn age(&self) -> Option<Duration> {
+        let cache = self.cache.lock();
+        cache
+            .reading
+            .as_ref()
+            .map(|(_, at)| time_since_start() - *at)
+    }
+
+    #[inline]
+    /// Discards the cached reading, so that the next call to
+    /// [`Cached::read()`] reads the data source.
+    pub fn invalidate(&self) {
+        let mut cache = self.cache.lock();
+        cache.reading = None;
+        cache.attempted = None;
+    }
+
+    #[inline]
+    /// Gets a reference to the wrapped data source.
+    pub fn inner(&self) -> &D {
+        &self.source
+    }
+
+    #[inline]
+    /// Unwraps the data source.
+    pub fn into_inner(self) -> D {
+        self.source
+    }
+}
+
+impl<D: DataSource> DataSource for Cached<D> {
+    type Data = D::Data;
+
+    type Error = CachedError<D::Error>;
+
+    #[inline]
+    fn read(&self) -> Result<Self::Data, Self::Error> {
+        Cached::read(self)
+    }
+}
+
+struct CacheData<T> {
+    /// The most recent successful reading, and when it was taken.
+    reading: Option<(T, Instant)>,
+    /// When the data source was last read, successfully or not.
+    attempted: Option<Instant>,
+}
+
+/// Represents possible errors when reading a [`Cached`] data source.
+#[derive(Debug)]
+pub enum CachedError<E> {
+    /// The data source failed and there is no earlier reading.
+    Source(E),
+    /// The data source failed and the last reading, of the given age, is too
+    /// old to be used.
+    Stale(Duration),
+}
+
+impl<E: Into<Error>> From<CachedError<E>> for Error {
+    fn from(err: CachedError<E>) -> Self {
+        match err {
+            CachedError::Source(err) => err.into(),
+            CachedError::Stale(age) => Error::Custom(format!("reading is stale ({:?} old)", age)),
+        }
+    }
+}
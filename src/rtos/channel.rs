@@ -3,10 +3,10 @@ use core::time::Duration;
 use owner_monad::OwnerMut;
 
 use super::{
-    handle_event, Event, EventHandle, GenericSleep, Instant, Mutex, Selectable, Semaphore,
-    TIMEOUT_MAX,
+    delay, handle_event, Event, EventHandle, GenericSleep, Instant, Mutex, Selectable, Semaphore,
+    Timeout, TIMEOUT_MAX,
 };
-use crate::error::Error;
+use crate::{error::Error, select};
 
 /// Represents the sending end of a rendez-vous channel.
 pub struct SendChannel<T>(Arc<ChannelShared<T>>);
@@ -74,6 +74,15 @@ impl<T> SendChannel<T> {
             handle: handle_event(SendWrapper(&*self.0)),
         }
     }
+
+    /// Sends `value` on the channel, blocking until it is received or until
+    /// `timeout` has passed. If the timeout expires, the value is dropped.
+    pub fn send_timeout(&self, value: T, timeout: Duration) -> Result<(), Timeout> {
+        select! {
+            _ = self.select(value) => Ok(()),
+            _ = delay(timeout) => Err(Timeout),
+        }
+    }
 }
 
 impl<T> Clone for SendChannel<T> {
@@ -145,6 +154,15 @@ impl<T> ReceiveChannel<T> {
             seq: lock.seq,
         }
     }
+
+    /// Receives a value from the channel, blocking until one is sent or until
+    /// `timeout` has passed.
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, Timeout> {
+        select! {
+            value = self.select() => Ok(value),
+            _ = delay(timeout) => Err(Timeout),
+        }
+    }
 }
 
 impl<T> Clone for ReceiveChannel<T> {
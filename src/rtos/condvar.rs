@@ -0,0 +1,132 @@
+use alloc::collections::VecDeque;
+use core::time::Duration;
+
+use crate::{
+    bindings,
+    error::Error,
+    rtos::{time_since_start, GenericSleep, Instant, Mutex, MutexGuard, Task},
+};
+
+/// Represents a condition variable, which lets tasks sleep until the state
+/// protected by a [`Mutex`] is changed by another task.
+///
+/// # Example
+///
+/// ```
+/// let state = Mutex::new(Vec::new());
+/// let ready = Condvar::new();
+///
+/// // In the vision task:
+/// state.lock().push(target);
+/// ready.notify_one();
+///
+/// // In autonomous:
+/// let targets = ready.wait_while(state.lock(), |targets| targets.is_empty());
+/// ```
+///
+/// # Behaviour
+///
+/// Waiting releases the mutex while the task sleeps and takes it again before
+/// returning. Since [`Mutex`] is recursive, the guard passed in must be the
+/// only one held by the current task for that mutex; otherwise the mutex stays
+/// held while the task sleeps, and no other task can change the state.
+///
+/// Tasks are woken in the order in which they started waiting. Unlike some
+/// condition variables, waits do not end spuriously, but another task may
+/// still change the state between the notification and the mutex being taken
+/// again, so the condition should be checked afterwards, as
+/// [`Condvar::wait_while()`] does.
+pub struct Condvar(Mutex<VecDeque<Task>>);
+
+impl Condvar {
+    #[inline]
+    /// Creates a new condition variable with no waiting tasks. Panics on
+    /// failure; see [`Condvar::try_new()`].
+    pub fn new() -> Self {
+        Self::try_new().unwrap_or_else(|err| panic!("failed to create condvar: {:?}", err))
+    }
+
+    #[inline]
+    /// Creates a new condition variable with no waiting tasks.
+    pub fn try_new() -> Result<Self, Error> {
+        Ok(Self(Mutex::try_new(VecDeque::new())?))
+    }
+
+    #[inline]
+    /// Releases the mutex held by `guard` and blocks until the current task is
+    /// notified, then takes the mutex again.
+    pub fn wait<'a, T: ?Sized>(&self, guard: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+        self.wait_until(guard, None).0
+    }
+
+    /// Releases the mutex held by `guard` and blocks until the current task is
+    /// notified or `timeout` elapses, then takes the mutex again. The flag
+    /// returned is `true` if the timeout elapsed without a notification.
+    pub fn wait_timeout<'a, T: ?Sized>(
+        &self,
+        guard: MutexGuard<'a, T>,
+        timeout: Duration,
+    ) -> (MutexGuard<'a, T>, bool) {
+        self.wait_until(guard, Some(time_since_start() + timeout))
+    }
+
+    /// Blocks until `condition` no longer holds for the state protected by the
+    /// mutex, waiting for a notification each time it is found to hold.
+    pub fn wait_while<'a, T: ?Sized>(
+        &self,
+        mut guard: MutexGuard<'a, T>,
+        mut condition: impl FnMut(&mut T) -> bool,
+    ) -> MutexGuard<'a, T> {
+        while condition(&mut guard) {
+            guard = self.wait(guard);
+        }
+        guard
+    }
+
+    /// Wakes the task which has been waiting the longest, if any.
+    pub fn notify_one(&self) {
+        if let Some(task) = self.0.lock().pop_front() {
+            unsafe { bindings::task_notify(task.0) };
+        }
+    }
+
+    /// Wakes all of the waiting tasks.
+    pub fn notify_all(&self) {
+        for task in self.0.lock().drain(..) {
+            unsafe { bindings::task_notify(task.0) };
+        }
+    }
+
+    fn wait_until<'a, T: ?Sized>(
+        &self,
+        guard: MutexGuard<'a, T>,
+        deadline: Option<Instant>,
+    ) -> (MutexGuard<'a, T>, bool) {
+        let mutex = guard.0;
+        let task = Task::current();
+        self.0.lock().push_back(task.clone());
+        drop(guard);
+        let timed_out = loop {
+            GenericSleep::NotifyTake(deadline).sleep();
+            // A notifying task removes this task from the queue; anything else
+            // which woke it is ignored until the deadline.
+            let mut waiters = self.0.lock();
+            match waiters.iter().position(|t| *t == task) {
+                None => break false,
+                Some(index) if matches!(deadline, Some(d) if time_since_start() >= d) => {
+                    waiters.remove(index);
+                    break true;
+                }
+                Some(_) => {}
+            }
+        };
+        (mutex.lock(), timed_out)
+    }
+}
+
+impl Default for Condvar {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
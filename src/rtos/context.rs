@@ -1,18 +1,23 @@
 use alloc::{
+    string::String,
     sync::{Arc, Weak},
     vec::Vec,
 };
 use by_address::ByAddress;
-use core::{cmp::min, time::Duration};
+use core::{
+    cmp::min,
+    fmt::{self, Debug, Formatter},
+    time::Duration,
+};
 use owner_monad::OwnerMut;
 use raii_map::set::{insert, Set, SetHandle};
 
 use super::{
     handle_event, time_since_start, Event, EventHandle, GenericSleep, Instant, Mutex, Selectable,
 };
-use crate::select_merge;
+use crate::{io::println, select_merge};
 
-type ContextValue = (Option<Instant>, Mutex<Option<ContextData>>);
+type ContextValue = (Option<Instant>, Mutex<Option<ContextData>>, ContextTag);
 
 #[derive(Clone)]
 #[repr(transparent)]
@@ -33,6 +38,13 @@ type ContextValue = (Option<Instant>, Mutex<Option<ContextData>>);
 ///
 /// A context can be "forked", which creates a new child context. This new
 /// context can optionally be created with a deadline.
+///
+/// # Introspection
+///
+/// A context can be given a name with [`Context::name()`], and its live
+/// children enumerated with [`Context::children()`], so that the tree of
+/// contexts can be printed with [`Context::dump()`] to find out what a stuck
+/// task is waiting for.
 pub struct Context(Arc<ContextValue>);
 
 impl Context {
@@ -49,6 +61,76 @@ impl Context {
         cancel(&self.0.as_ref().1);
     }
 
+    /// Checks whether the context has been cancelled, or its deadline has
+    /// passed, without blocking.
+    pub fn is_cancelled(&self) -> bool {
+        self.0 .1.lock().is_none() || matches!(self.0 .0, Some(d) if d <= time_since_start())
+    }
+
+    /// Tags the context with a name, which is shown by [`Context::dump()`]
+    /// and the [`Debug`] implementation. The name is shared by all copies of
+    /// the context.
+    pub fn name(self, name: &str) -> Self {
+        *self.0 .2.name.lock() = Some(name.into());
+        self
+    }
+
+    /// Gets the name of the context, if one has been set with
+    /// [`Context::name()`].
+    pub fn get_name(&self) -> Option<String> {
+        self.0 .2.name.lock().clone()
+    }
+
+    #[inline]
+    /// Gets the deadline of the context, if it has one.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.0 .0
+    }
+
+    #[cfg(debug_assertions)]
+    #[inline]
+    /// Gets the time at which the context was created. Only available in debug
+    /// builds.
+    pub fn created(&self) -> Instant {
+        self.0 .2.created
+    }
+
+    /// Gets the number of tasks currently waiting for the context to be
+    /// cancelled, e.g. in [`Context::done()`]. This is zero once the context
+    /// has been cancelled.
+    pub fn waiter_count(&self) -> usize {
+        self.0
+             .1
+            .lock()
+            .as_ref()
+            .map_or(0, |data| data.event.task_count())
+    }
+
+    /// Gets the live children of the context, i.e. those forked from it which
+    /// have not been cancelled. This is empty once the context has been
+    /// cancelled.
+    pub fn children(&self) -> Vec<Context> {
+        self.0 .1.lock().as_ref().map_or_else(Vec::new, |data| {
+            data.children
+                .iter()
+                .map(|child| Context(child.0.clone()))
+                .collect()
+        })
+    }
+
+    /// Prints the context and its live descendants to standard output, one per
+    /// line, indented by depth.
+    pub fn dump(&self) {
+        self.dump_at(0);
+    }
+
+    fn dump_at(&self, depth: usize) {
+        println!("{:width$}{:?}", "", self, width = depth * 2);
+        for child in self.children() {
+            child.dump_at(depth + 1);
+        }
+    }
+
     /// A [`Selectable`] event which occurs when the context is
     /// cancelled. The sleep amount takes the context deadline into
     /// consideration.
@@ -97,7 +179,15 @@ impl Context {
             .filter_map(|parent| parent.0 .0)
             .min()
             .map_or(deadline, |d1| Some(deadline.map_or(d1, |d2| min(d1, d2))));
-        let ctx = Self(Arc::new((deadline, Mutex::new(None))));
+        let ctx = Self(Arc::new((
+            deadline,
+            Mutex::new(None),
+            ContextTag {
+                name: Mutex::new(None),
+                #[cfg(debug_assertions)]
+                created: time_since_start(),
+            },
+        )));
         let mut parent_handles = Vec::new();
         parent_handles.reserve_exact(parents.len());
         for parent in parents {
@@ -119,6 +209,19 @@ impl Context {
     }
 }
 
+impl Debug for Context {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut s = f.debug_struct("Context");
+        s.field("name", &self.get_name())
+            .field("deadline", &self.deadline());
+        #[cfg(debug_assertions)]
+        s.field("created", &self.created());
+        s.field("cancelled", &self.is_cancelled())
+            .field("waiters", &self.waiter_count())
+            .finish()
+    }
+}
+
 /// Describes an object from which a child context can be created. Implemented
 /// for contexts and for slices of contexts.
 pub trait ParentContext {
@@ -162,6 +265,12 @@ impl ParentContext for [&Context] {
     }
 }
 
+struct ContextTag {
+    name: Mutex<Option<String>>,
+    #[cfg(debug_assertions)]
+    created: Instant,
+}
+
 struct ContextData {
     _parents: Vec<SetHandle<ByAddress<Arc<ContextValue>>, ContextHandle>>,
     event: Event,
@@ -0,0 +1,146 @@
+use alloc::sync::Arc;
+
+use owner_monad::OwnerMut;
+
+use super::{handle_event, Event, EventHandle, GenericSleep, Instant, Mutex, Selectable};
+use crate::error::Error;
+
+/// A shared set of 32 flags which tasks can set, clear and wait on, like a
+/// FreeRTOS event group. Clones refer to the same set of flags.
+///
+/// This makes it easy to wait for several conditions at once, where separate
+/// semaphores would otherwise be needed for each. Each waiting task is woken
+/// whenever flags are set, and checks its own condition.
+///
+/// # Example
+///
+/// ```
+/// const INTAKE_HOMED: u32 = 1 << 0;
+/// const LIFT_HOMED: u32 = 1 << 1;
+///
+/// let homed = EventFlags::new();
+///
+/// // In the intake and lift tasks:
+/// homed.set(INTAKE_HOMED);
+/// homed.set(LIFT_HOMED);
+///
+/// // In autonomous:
+/// select(homed.wait_all(INTAKE_HOMED | LIFT_HOMED));
+/// ```
+pub struct EventFlags(Arc<Mutex<EventFlagsData>>);
+
+impl EventFlags {
+    #[inline]
+    /// Creates a new set of flags, all initially clear. Panics on failure; see
+    /// [`EventFlags::try_new()`].
+    pub fn new() -> Self {
+        Self::try_new().unwrap_or_else(|err| panic!("failed to create event flags: {:?}", err))
+    }
+
+    /// Creates a new set of flags, all initially clear.
+    pub fn try_new() -> Result<Self, Error> {
+        Ok(Self(Arc::new(Mutex::try_new(EventFlagsData {
+            flags: 0,
+            event: Event::new(),
+        })?)))
+    }
+
+    #[inline]
+    /// Gets the current value of the flags.
+    pub fn get(&self) -> u32 {
+        self.0.lock().flags
+    }
+
+    /// Sets the flags in `mask`, notifying any tasks waiting on the flags.
+    /// Returns the value of the flags after setting them.
+    pub fn set(&self, mask: u32) -> u32 {
+        let mut lock = self.0.lock();
+        lock.flags |= mask;
+        lock.event.notify();
+        lock.flags
+    }
+
+    /// Clears the flags in `mask`. Returns the value of the flags before
+    /// clearing them.
+    pub fn clear(&self, mask: u32) -> u32 {
+        let mut lock = self.0.lock();
+        let flags = lock.flags;
+        lock.flags &= !mask;
+        flags
+    }
+
+    /// A [`Selectable`] event which occurs with the value of the flags as soon
+    /// as any of the flags in `mask` are set, which may be immediately.
+    pub fn wait_any(&'_ self, mask: u32) -> impl Selectable<u32> + '_ {
+        self.select(move |flags| flags & mask != 0)
+    }
+
+    /// A [`Selectable`] event which occurs with the value of the flags as soon
+    /// as all of the flags in `mask` are set, which may be immediately.
+    pub fn wait_all(&'_ self, mask: u32) -> impl Selectable<u32> + '_ {
+        self.select(move |flags| flags & mask == mask)
+    }
+
+    fn select<'a>(&'a self, ready: impl Fn(u32) -> bool + 'a) -> impl Selectable<u32> + 'a {
+        struct EventFlagsSelect<'b, F> {
+            data: &'b Mutex<EventFlagsData>,
+            ready: F,
+            _handle: EventHandle<EventFlagsHandle<'b>>,
+        }
+
+        impl<'b, F: Fn(u32) -> bool> Selectable<u32> for EventFlagsSelect<'b, F> {
+            fn poll(self) -> Result<u32, Self> {
+                let flags = self.data.lock().flags;
+                if (self.ready)(flags) {
+                    Ok(flags)
+                } else {
+                    Err(self)
+                }
+            }
+
+            fn sleep(&self) -> GenericSleep {
+                if (self.ready)(self.data.lock().flags) {
+                    GenericSleep::Timestamp(Instant::from_millis(0))
+                } else {
+                    GenericSleep::NotifyTake(None)
+                }
+            }
+        }
+
+        EventFlagsSelect {
+            data: &self.0,
+            ready,
+            _handle: handle_event(EventFlagsHandle(&self.0)),
+        }
+    }
+}
+
+impl Clone for EventFlags {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl Default for EventFlags {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct EventFlagsData {
+    flags: u32,
+    event: Event,
+}
+
+struct EventFlagsHandle<'a>(&'a Mutex<EventFlagsData>);
+
+impl<'a> OwnerMut<Event> for EventFlagsHandle<'a> {
+    fn with<'b, U>(&'b mut self, f: impl FnOnce(&mut Event) -> U) -> Option<U>
+    where
+        Event: 'b,
+    {
+        Some(f(&mut self.0.lock().event))
+    }
+}
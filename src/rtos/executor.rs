@@ -0,0 +1,202 @@
+use alloc::{boxed::Box, sync::Arc, task::Wake, vec::Vec};
+use core::{
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context as TaskContext, Poll, Waker},
+};
+
+use crate::{
+    bindings,
+    rtos::{GenericSleep, Instant, Mutex, Selectable, Task},
+};
+
+/// The earliest time at which each task running an executor must next poll
+/// its futures, as requested by pending [`SelectableFuture`]s.
+static DEADLINES: spin::Once<Mutex<Vec<(Task, Instant)>>> = spin::Once::new();
+
+fn deadlines() -> &'static Mutex<Vec<(Task, Instant)>> {
+    DEADLINES.call_once(|| Mutex::new(Vec::new()))
+}
+
+/// Runs a future to completion on the current task, sleeping until it is woken
+/// between polls, and returns its output.
+///
+/// Wakers notify the current task, so any future which wakes its waker
+/// correctly can be run; [`Selectable`] events can be awaited with [`wait()`].
+///
+/// # Example
+///
+/// ```
+/// async fn score(chassis: &Chassis, intake: &Indexer<Motor, DistanceSensor>) {
+///     wait(chassis.drive_to(goal)).await.unwrap();
+///     wait(intake.feed().done()).await;
+/// }
+///
+/// block_on(score(&chassis, &intake));
+/// ```
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = Box::pin(future);
+    let waker = Waker::from(Arc::new(TaskWaker(Task::current())));
+    let mut cx = TaskContext::from_waker(&waker);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            take_deadline();
+            return output;
+        }
+        GenericSleep::NotifyTake(take_deadline()).sleep();
+    }
+}
+
+/// Runs several futures concurrently on the current task.
+///
+/// All of the futures are polled each time the task is woken, which keeps the
+/// executor small at the cost of some unnecessary polls; it is intended for a
+/// handful of concurrent routines, such as the mechanisms of an autonomous
+/// routine.
+///
+/// # Example
+///
+/// ```
+/// let mut executor = Executor::new();
+/// executor.spawn(async {
+///     wait(chassis.drive_to(goal)).await.unwrap();
+/// });
+/// executor.spawn(async {
+///     wait(lift.move_to(Degrees(90.0))).await.unwrap();
+/// });
+/// executor.run();
+/// ```
+pub struct Executor<'a> {
+    futures: Vec<Pin<Box<dyn Future<Output = ()> + 'a>>>,
+}
+
+impl<'a> Executor<'a> {
+    #[inline]
+    /// Creates a new executor with no futures.
+    pub fn new() -> Self {
+        Self {
+            futures: Vec::new(),
+        }
+    }
+
+    #[inline]
+    /// Adds a future to be run by [`Executor::run()`].
+    pub fn spawn(&mut self, future: impl Future<Output = ()> + 'a) {
+        self.futures.push(Box::pin(future));
+    }
+
+    #[inline]
+    /// Gets the number of futures which have not yet completed.
+    pub fn len(&self) -> usize {
+        self.futures.len()
+    }
+
+    #[inline]
+    /// Checks whether all futures have completed.
+    pub fn is_empty(&self) -> bool {
+        self.futures.is_empty()
+    }
+
+    /// Runs all of the futures on the current task until they have completed.
+    pub fn run(&mut self) {
+        let waker = Waker::from(Arc::new(TaskWaker(Task::current())));
+        let mut cx = TaskContext::from_waker(&waker);
+        while !self.futures.is_empty() {
+            self.futures
+                .retain_mut(|future| future.as_mut().poll(&mut cx).is_pending());
+            if self.futures.is_empty() {
+                take_deadline();
+            } else {
+                GenericSleep::NotifyTake(take_deadline()).sleep();
+            }
+        }
+    }
+}
+
+impl<'a> Default for Executor<'a> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Creates a [`Future`] which resolves with the result of a [`Selectable`]
+/// event, for use with `.await`.
+///
+/// The event must be awaited on the task which created it, as is the case
+/// within [`block_on()`] or [`Executor::run()`], so that the notifications it
+/// relies on reach the executor.
+pub fn wait<T, S: Selectable<T>>(event: S) -> SelectableFuture<T, S> {
+    SelectableFuture {
+        event: Some(event),
+        _t: PhantomData,
+    }
+}
+
+/// A [`Future`] which resolves with the result of a [`Selectable`] event. See
+/// [`wait()`].
+pub struct SelectableFuture<T, S: Selectable<T>> {
+    event: Option<S>,
+    _t: PhantomData<T>,
+}
+
+// The event is only ever moved, never pinned.
+impl<T, S: Selectable<T>> Unpin for SelectableFuture<T, S> {}
+
+impl<T, S: Selectable<T>> Future for SelectableFuture<T, S> {
+    type Output = T;
+
+    fn poll(mut self: Pin<&mut Self>, _cx: &mut TaskContext<'_>) -> Poll<T> {
+        let event = self
+            .event
+            .take()
+            .expect("SelectableFuture polled after completion");
+        match event.poll() {
+            Ok(output) => Poll::Ready(output),
+            Err(event) => {
+                // Notifications wake the executor directly; a timestamp must be
+                // left for it to sleep until.
+                if let Some(deadline) = event.sleep().timeout() {
+                    wake_at(deadline);
+                }
+                self.event = Some(event);
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// A waker which notifies the task running an executor.
+struct TaskWaker(Task);
+
+impl Wake for TaskWaker {
+    #[inline]
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    #[inline]
+    fn wake_by_ref(self: &Arc<Self>) {
+        unsafe { bindings::task_notify(self.0 .0) };
+    }
+}
+
+/// Requests that the executor on the current task polls its futures again no
+/// later than `deadline`.
+fn wake_at(deadline: Instant) {
+    let task = Task::current();
+    let mut deadlines = deadlines().lock();
+    match deadlines.iter_mut().find(|(t, _)| *t == task) {
+        Some((_, at)) => *at = (*at).min(deadline),
+        None => deadlines.push((task, deadline)),
+    }
+}
+
+/// Takes the deadline requested for the executor on the current task, if any.
+fn take_deadline() -> Option<Instant> {
+    let task = Task::current();
+    let mut deadlines = deadlines().lock();
+    let index = deadlines.iter().position(|(t, _)| *t == task)?;
+    Some(deadlines.swap_remove(index).1)
+}
@@ -0,0 +1,199 @@
+use core::{
+    ptr,
+    sync::atomic::{AtomicPtr, Ordering},
+    time::Duration,
+};
+
+use super::{Loop, Task};
+use crate::{bindings, io::eprintln};
+
+/// The interval at which the tick hook is called.
+const TICK_PERIOD: Duration = Duration::from_millis(1);
+
+static STACK_OVERFLOW_HOOK: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+static MALLOC_FAILED_HOOK: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+static IDLE_HOOK: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+static TICK_HOOK: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+static TICKER: spin::Once<()> = spin::Once::new();
+
+/// Sets a function to be called with the name of the offending task when
+/// FreeRTOS detects that a task has overflowed its stack, or clears it if
+/// `hook` is `None`.
+///
+/// Whether or not a hook is set, the overflow is then reported on standard
+/// output and the brain screen, and the program halts; the hook cannot recover
+/// from the overflow.
+///
+/// The hook is called from inside the scheduler's context switch, with a
+/// corrupted stack, so it must not block, allocate, take a
+/// [`Mutex`](super::Mutex) or wait on any other RTOS primitive, and should do
+/// as little as possible (e.g., stopping motors through the raw bindings).
+///
+/// With the [`sim`](crate::sim) feature, the hook is never called.
+pub fn set_stack_overflow_hook(hook: Option<fn(&str)>) {
+    store(&STACK_OVERFLOW_HOOK, hook);
+}
+
+/// Sets a function to be called with the name of the current task when an
+/// allocation from the FreeRTOS heap fails (e.g., when creating a task, queue
+/// or mutex), or clears it if `hook` is `None`.
+///
+/// Whether or not a hook is set, the failure is then reported on standard
+/// output and the brain screen, and the program halts.
+///
+/// The hook is called from inside the FreeRTOS allocator with the scheduler
+/// suspended, so it is subject to the same constraints as the hook set with
+/// [`set_stack_overflow_hook()`].
+///
+/// Failures of Rust allocations do not call this hook; see
+/// [`allocator`](crate::allocator) instead.
+///
+/// With the [`sim`](crate::sim) feature, the hook is never called.
+pub fn set_malloc_failed_hook(hook: Option<fn(&str)>) {
+    store(&MALLOC_FAILED_HOOK, hook);
+}
+
+/// Sets a function to be called repeatedly by the FreeRTOS idle task, or
+/// clears it if `hook` is `None`.
+///
+/// The idle task only runs when no other task is ready, so the hook is a
+/// convenient place for low-priority bookkeeping, but is not called at all
+/// while any task is busy. It is also responsible for freeing the memory of
+/// deleted tasks, so the hook must return quickly, and must never block: it
+/// must not delay, take a [`Mutex`](super::Mutex) or wait on any other RTOS
+/// primitive. The idle task has a small stack, so the hook should not allocate
+/// or use much stack space either.
+///
+/// With the [`sim`](crate::sim) feature, the hook is never called.
+pub fn set_idle_hook(hook: Option<fn()>) {
+    IDLE_HOOK.store(
+        hook.map_or(ptr::null_mut(), |hook| hook as *mut ()),
+        Ordering::Release,
+    );
+}
+
+/// Sets a function to be called every millisecond, or clears it if `hook` is
+/// `None`, e.g. to blink a heartbeat LED or kick a watchdog.
+///
+/// PROS does not enable the FreeRTOS tick hook, so the hook is called from a
+/// task at the highest priority, which is started the first time a hook is
+/// set; this pre-empts every other task, so the hook must return well within a
+/// millisecond, and must never block: it must not delay, take a
+/// [`Mutex`](super::Mutex) or wait on any other RTOS primitive.
+pub fn set_tick_hook(hook: Option<fn()>) {
+    TICK_HOOK.store(
+        hook.map_or(ptr::null_mut(), |hook| hook as *mut ()),
+        Ordering::Release,
+    );
+    if hook.is_some() {
+        TICKER.call_once(|| {
+            if let Err(err) = Task::spawn_ext(
+                "tick hook",
+                bindings::TASK_PRIORITY_MAX,
+                Task::DEFAULT_STACK_DEPTH,
+                || {
+                    let mut l = Loop::new(TICK_PERIOD);
+                    loop {
+                        call(&TICK_HOOK);
+                        l.delay();
+                    }
+                },
+            ) {
+                eprintln!("failed to start tick hook task: {:?}", err);
+            }
+        });
+    }
+}
+
+fn store(slot: &AtomicPtr<()>, hook: Option<fn(&str)>) {
+    slot.store(
+        hook.map_or(ptr::null_mut(), |hook| hook as *mut ()),
+        Ordering::Release,
+    );
+}
+
+/// Calls the hook stored in `slot`, if any.
+fn call(slot: &AtomicPtr<()>) {
+    let hook = slot.load(Ordering::Acquire);
+    if !hook.is_null() {
+        // SAFETY: the pointer was converted from a `fn()` by `set_idle_hook()`
+        // or `set_tick_hook()`.
+        let hook: fn() = unsafe { core::mem::transmute(hook) };
+        hook();
+    }
+}
+
+// Overrides of the hooks defined by PROS, whose failure hooks print a message
+// to the serial port and freeze without showing anything on the brain screen.
+// The program is linked with `--allow-multiple-definition`, so these
+// definitions take precedence.
+#[cfg(not(feature = "sim"))]
+mod handlers {
+    use core::{
+        ffi::CStr,
+        sync::atomic::{AtomicPtr, Ordering},
+    };
+
+    use libc::{c_char, c_void};
+
+    use super::{call, IDLE_HOOK, MALLOC_FAILED_HOOK, STACK_OVERFLOW_HOOK};
+    use crate::bindings;
+
+    extern "C" {
+        fn vexSerialWriteBuffer(channel: u32, data: *const u8, len: u32) -> i32;
+        fn vexDisplayString(line: i32, format: *const c_char, ...);
+        fn vexBackgroundProcessing();
+        fn ulPortSetInterruptMask() -> u32;
+    }
+
+    #[no_mangle]
+    extern "C" fn vApplicationIdleHook() {
+        call(&IDLE_HOOK);
+    }
+
+    #[no_mangle]
+    extern "C" fn vApplicationStackOverflowHook(_task: *mut c_void, name: *const c_char) {
+        fail(&STACK_OVERFLOW_HOOK, name, b"stack overflow in task `\0");
+    }
+
+    #[no_mangle]
+    extern "C" fn vApplicationMallocFailedHook() {
+        fail(
+            &MALLOC_FAILED_HOOK,
+            unsafe { bindings::task_get_name(bindings::task_get_current()) },
+            b"RTOS allocation failed in task `\0",
+        );
+    }
+
+    /// Calls the registered hook, then reports the failure and halts, without
+    /// allocating.
+    fn fail(hook: &AtomicPtr<()>, name: *const c_char, message: &[u8]) -> ! {
+        let name = if name.is_null() {
+            b"?\0".as_ptr().cast()
+        } else {
+            name
+        };
+        let hook = hook.load(Ordering::Acquire);
+        if !hook.is_null() {
+            // SAFETY: the pointer was converted from a `fn(&str)` by `store()`.
+            let hook: fn(&str) = unsafe { core::mem::transmute(hook) };
+            hook(unsafe { CStr::from_ptr(name) }.to_str().unwrap_or("?"));
+        }
+
+        unsafe {
+            let name_bytes = CStr::from_ptr(name).to_bytes();
+            let message_bytes = &message[..message.len() - 1];
+            for part in [message_bytes, name_bytes, b"`\n"] {
+                vexSerialWriteBuffer(1, part.as_ptr(), part.len() as u32);
+            }
+            vexDisplayString(2, b"%s%s`\0".as_ptr().cast(), message.as_ptr(), name);
+
+            // Keep servicing the serial port and screen so that the message
+            // is actually sent, as PROS does.
+            ulPortSetInterruptMask();
+            loop {
+                vexBackgroundProcessing();
+            }
+        }
+    }
+}
@@ -0,0 +1,264 @@
+use alloc::{
+    boxed::Box,
+    string::String,
+    sync::{Arc, Weak},
+    vec::Vec,
+};
+use core::time::Duration;
+
+use crate::{
+    bindings,
+    error::Error,
+    io::eprintln,
+    rtos::{time_since_start, GenericSleep, Instant, Mutex, Task},
+};
+
+/// Identifies a job registered with a [`JobRunner`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct JobId(u32);
+
+/// Statistics for a job registered with a [`JobRunner`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct JobStats {
+    /// The number of times the job has run.
+    pub runs: u32,
+    /// The number of runs which took longer than the job's budget.
+    pub overruns: u32,
+    /// The duration of the most recent run.
+    pub last_time: Duration,
+    /// The duration of the longest run.
+    pub max_time: Duration,
+}
+
+/// Runs many small periodic jobs on a single task, so that lightweight work
+/// such as telemetry, LED animation and health checks does not each need its
+/// own task and stack.
+///
+/// Jobs are run round-robin whenever they are due. Scheduling is cooperative:
+/// a job runs to completion and holds up every other job while it does, so
+/// jobs should be short and must not block. Each job may be given a budget;
+/// runs which exceed it are counted in the job's [`JobStats`] and reported,
+/// but are not interrupted.
+///
+/// Clones share the same set of jobs; the task exits when every clone has been
+/// dropped.
+///
+/// # Example
+///
+/// ```
+/// let jobs = JobRunner::new();
+/// jobs.add("leds", Duration::from_millis(20), move || leds.step());
+/// jobs.add_with_budget(
+///     "telemetry",
+///     Duration::from_millis(100),
+///     Duration::from_millis(2),
+///     move || telemetry.send(),
+/// );
+/// ```
+#[derive(Clone)]
+pub struct JobRunner(Arc<JobRunnerShared>);
+
+impl JobRunner {
+    #[inline]
+    /// Creates a new job runner with no jobs and spawns its task. Panics on
+    /// failure; see [`JobRunner::try_new()`].
+    pub fn new() -> Self {
+        Self::try_new().unwrap_or_else(|err| panic!("failed to create job runner: {:?}", err))
+    }
+
+    /// Creates a new job runner with no jobs and spawns its task.
+    pub fn try_new() -> Result<Self, Error> {
+        let shared = Arc::new(JobRunnerShared {
+            data: Mutex::try_new(JobRunnerData {
+                jobs: Vec::new(),
+                next_id: 0,
+                cursor: 0,
+            })?,
+            task: spin::Once::new(),
+        });
+        let weak: Weak<JobRunnerShared> = Arc::downgrade(&shared);
+        let task = Task::spawn_ext(
+            "jobs",
+            bindings::TASK_PRIORITY_DEFAULT,
+            Task::DEFAULT_STACK_DEPTH,
+            move || {
+                while let Some(shared) = weak.upgrade() {
+                    let sleep = shared.run_next();
+                    drop(shared);
+                    if let Some(sleep) = sleep {
+                        sleep.sleep();
+                    }
+                }
+            },
+        )?;
+        shared.task.call_once(|| task);
+        Ok(Self(shared))
+    }
+
+    #[inline]
+    /// Registers a job which runs every `period`, starting as soon as
+    /// possible, with no budget.
+    pub fn add(&self, name: &str, period: Duration, f: impl FnMut() + Send + 'static) -> JobId {
+        self.insert(name, period, None, Box::new(f))
+    }
+
+    #[inline]
+    /// Registers a job which runs every `period`, starting as soon as
+    /// possible, and is expected to take no longer than `budget` each time.
+    pub fn add_with_budget(
+        &self,
+        name: &str,
+        period: Duration,
+        budget: Duration,
+        f: impl FnMut() + Send + 'static,
+    ) -> JobId {
+        self.insert(name, period, Some(budget), Box::new(f))
+    }
+
+    /// Unregisters a job, returning whether it was registered. If the job is
+    /// currently running, it finishes its current run.
+    pub fn remove(&self, id: JobId) -> bool {
+        let mut data = self.0.data.lock();
+        let len = data.jobs.len();
+        data.jobs.retain(|job| job.id != id);
+        data.jobs.len() != len
+    }
+
+    /// Gets the statistics of a job, or `None` if it is not registered.
+    pub fn stats(&self, id: JobId) -> Option<JobStats> {
+        let data = self.0.data.lock();
+        data.jobs
+            .iter()
+            .find(|job| job.id == id)
+            .map(|job| job.stats)
+    }
+
+    /// Gets the names and statistics of every registered job.
+    pub fn all_stats(&self) -> Vec<(String, JobStats)> {
+        let data = self.0.data.lock();
+        data.jobs
+            .iter()
+            .map(|job| (job.name.clone(), job.stats))
+            .collect()
+    }
+
+    fn insert(
+        &self,
+        name: &str,
+        period: Duration,
+        budget: Option<Duration>,
+        f: Box<dyn FnMut() + Send>,
+    ) -> JobId {
+        let mut data = self.0.data.lock();
+        let id = JobId(data.next_id);
+        data.next_id += 1;
+        data.jobs.push(Job {
+            id,
+            name: name.into(),
+            period,
+            budget,
+            next: time_since_start(),
+            f: Some(f),
+            stats: JobStats::default(),
+        });
+        drop(data);
+        // Wake the task in case it is sleeping until a later job is due.
+        if let Some(task) = self.0.task.get() {
+            unsafe { bindings::task_notify(task.0) };
+        }
+        id
+    }
+}
+
+impl Default for JobRunner {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct JobRunnerShared {
+    data: Mutex<JobRunnerData>,
+    task: spin::Once<Task>,
+}
+
+impl JobRunnerShared {
+    /// Runs the next due job, if any, returning how long to sleep before
+    /// checking again, or `None` if another job may already be due.
+    fn run_next(&self) -> Option<GenericSleep> {
+        let now = time_since_start();
+        let mut data = self.data.lock();
+        let count = data.jobs.len();
+        let index = (0..count)
+            .map(|i| (data.cursor + i) % count)
+            .find(|&i| data.jobs[i].next <= now);
+        let index = match index {
+            Some(index) => index,
+            None => {
+                let next = data.jobs.iter().map(|job| job.next).min();
+                return Some(GenericSleep::NotifyTake(next));
+            }
+        };
+        data.cursor = index + 1;
+        let id = data.jobs[index].id;
+        // Run the job without holding the lock, so that it may add or remove
+        // jobs itself.
+        let mut f = data.jobs[index].f.take()?;
+        drop(data);
+
+        let start = time_since_start();
+        f();
+        let end = time_since_start();
+        let time = end - start;
+
+        let mut data = self.data.lock();
+        // The job may have been removed, or others added, while it ran.
+        if let Some(job) = data.jobs.iter_mut().find(|job| job.id == id) {
+            job.f = Some(f);
+            job.stats.runs += 1;
+            job.stats.last_time = time;
+            job.stats.max_time = job.stats.max_time.max(time);
+            if let Some(budget) = job.budget.filter(|&budget| time > budget) {
+                job.stats.overruns += 1;
+                eprintln!(
+                    "job '{}' overran its budget: {:?} > {:?}",
+                    job.name, time, budget
+                );
+            }
+            job.next += job.period;
+            if job.next < end {
+                // The job has fallen behind; skip the missed runs rather than
+                // running it back-to-back to catch up.
+                job.next = end + job.period;
+            }
+        }
+        None
+    }
+}
+
+impl Drop for JobRunnerShared {
+    fn drop(&mut self) {
+        // Wake the task so that it notices the runner is gone and exits.
+        if let Some(task) = self.task.get() {
+            unsafe { bindings::task_notify(task.0) };
+        }
+    }
+}
+
+struct JobRunnerData {
+    jobs: Vec<Job>,
+    next_id: u32,
+    /// The index from which to look for the next due job.
+    cursor: usize,
+}
+
+struct Job {
+    id: JobId,
+    name: String,
+    period: Duration,
+    budget: Option<Duration>,
+    next: Instant,
+    /// The job's closure, or `None` while it is running.
+    f: Option<Box<dyn FnMut() + Send>>,
+    stats: JobStats,
+}
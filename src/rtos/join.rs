@@ -0,0 +1,125 @@
+use alloc::sync::Arc;
+
+use owner_monad::OwnerMut;
+
+use super::{
+    handle_event, select, Event, EventHandle, GenericSleep, Instant, Mutex, Selectable, Task,
+};
+use crate::error::Error;
+
+/// A handle to a task spawned with [`Task::spawn_joinable()`] or
+/// [`Task::spawn_joinable_ext()`], which gives the value returned by the task
+/// once it finishes.
+///
+/// Dropping the handle does not stop the task; its return value is then
+/// discarded.
+///
+/// # Example
+///
+/// ```
+/// let handle = Task::spawn_joinable(|| vision.find_goal())?;
+/// chassis.turn_to_heading(Degrees(0.0))?;
+/// let goal = handle.join();
+/// ```
+pub struct JoinHandle<T> {
+    task: Task,
+    data: Arc<Mutex<JoinData<T>>>,
+}
+
+impl<T: Send + 'static> JoinHandle<T> {
+    pub(super) fn spawn(
+        name: &str,
+        priority: u32,
+        stack_depth: u16,
+        f: impl FnOnce() -> T + Send + 'static,
+    ) -> Result<Self, Error> {
+        let data = Arc::new(Mutex::try_new(JoinData {
+            result: None,
+            finished: false,
+            event: Event::new(),
+        })?);
+        let task_data = data.clone();
+        let task = Task::spawn_ext(name, priority, stack_depth, move || {
+            let result = f();
+            let mut lock = task_data.lock();
+            lock.result = Some(result);
+            lock.finished = true;
+            lock.event.notify();
+        })?;
+        Ok(Self { task, data })
+    }
+
+    #[inline]
+    /// Gets the task.
+    pub fn task(&self) -> &Task {
+        &self.task
+    }
+
+    #[inline]
+    /// Checks whether the task has finished.
+    pub fn is_finished(&self) -> bool {
+        self.data.lock().finished
+    }
+
+    /// A [`Selectable`] event which occurs when the task has finished.
+    pub fn done(&'_ self) -> impl Selectable + '_ {
+        struct JoinSelect<'a, T> {
+            data: &'a Mutex<JoinData<T>>,
+            _handle: EventHandle<JoinHandleOwner<'a, T>>,
+        }
+
+        impl<'a, T> Selectable for JoinSelect<'a, T> {
+            fn poll(self) -> Result<(), Self> {
+                if self.data.lock().finished {
+                    Ok(())
+                } else {
+                    Err(self)
+                }
+            }
+
+            fn sleep(&self) -> GenericSleep {
+                if self.data.lock().finished {
+                    GenericSleep::Timestamp(Instant::from_millis(0))
+                } else {
+                    GenericSleep::NotifyTake(None)
+                }
+            }
+        }
+
+        JoinSelect {
+            data: &self.data,
+            _handle: handle_event(JoinHandleOwner(&self.data)),
+        }
+    }
+
+    /// Blocks until the task has finished, and returns the value it returned.
+    pub fn join(self) -> T {
+        select(self.done());
+        let result = self.data.lock().result.take();
+        result.expect("joined task finished without a result")
+    }
+
+    /// Gets the value returned by the task if it has finished, or otherwise
+    /// gives the handle back. Does not block.
+    pub fn poll(self) -> Result<T, Self> {
+        let result = self.data.lock().result.take();
+        result.ok_or(self)
+    }
+}
+
+struct JoinData<T> {
+    result: Option<T>,
+    finished: bool,
+    event: Event,
+}
+
+struct JoinHandleOwner<'a, T>(&'a Mutex<JoinData<T>>);
+
+impl<'a, T> OwnerMut<Event> for JoinHandleOwner<'a, T> {
+    fn with<'b, U>(&'b mut self, f: impl FnOnce(&mut Event) -> U) -> Option<U>
+    where
+        Event: 'b,
+    {
+        Some(f(&mut self.0.lock().event))
+    }
+}
@@ -1,6 +1,6 @@
 //! Multitasking primitives.
 
-use alloc::{boxed::Box, format, string::String};
+use alloc::{boxed::Box, format, string::String, vec::Vec};
 use core::{
     cmp::min,
     convert::TryInto,
@@ -17,7 +17,39 @@ use crate::{
     error::{Error, SentinelError},
 };
 
-const TIMEOUT_MAX: u32 = 0xffffffff;
+pub(crate) const TIMEOUT_MAX: u32 = 0xffffffff;
+
+// Part of the FreeRTOS kernel within PROS, but not declared in its headers.
+#[cfg(not(feature = "sim"))]
+extern "C" {
+    fn uxTaskGetStackHighWaterMark(task: bindings::task_t) -> u32;
+    fn uxTaskGetSystemState(status: *mut TaskStatus, size: u32, total_run_time: *mut u32) -> u32;
+}
+
+#[cfg(feature = "sim")]
+use crate::bindings::{uxTaskGetStackHighWaterMark, uxTaskGetSystemState};
+
+/// The state of a task as reported by `uxTaskGetSystemState`, laid out as the
+/// kernel's `TaskStatus_t`.
+#[repr(C)]
+pub(crate) struct TaskStatus {
+    pub(crate) handle: bindings::task_t,
+    pub(crate) name: *const libc::c_char,
+    pub(crate) number: u32,
+    pub(crate) state: u8,
+    pub(crate) current_priority: u32,
+    pub(crate) base_priority: u32,
+    pub(crate) run_time: u32,
+    pub(crate) stack_base: *mut c_void,
+    pub(crate) stack_high_water_mark: u16,
+}
+
+/// Tasks spawned with [`Task::spawn_ext()`] which have not yet finished.
+static SPAWNED: spin::Once<Mutex<Vec<Task>>> = spin::Once::new();
+
+fn spawned_tasks() -> &'static Mutex<Vec<Task>> {
+    SPAWNED.call_once(|| Mutex::new(Vec::new()))
+}
 
 /// Represents a time on a monotonically increasing clock (i.e., time since
 /// program start).
@@ -271,7 +303,10 @@ impl Task {
     {
         extern "C" fn run<F: FnOnce()>(arg: *mut libc::c_void) {
             let cb_box: Box<F> = unsafe { Box::from_raw(arg as *mut F) };
-            cb_box()
+            spawned_tasks().lock().push(Task::current());
+            cb_box();
+            let current = Task::current();
+            spawned_tasks().lock().retain(|task| *task != current);
         }
 
         let cb = Box::new(f);
@@ -287,6 +322,35 @@ impl Task {
         }
     }
 
+    #[inline]
+    /// Spawns a new task with no name and the default priority and stack
+    /// depth, returning a [`JoinHandle`] through which the value returned by
+    /// `f` can be retrieved.
+    pub fn spawn_joinable<T, F>(f: F) -> Result<JoinHandle<T>, Error>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        Self::spawn_joinable_ext("", Self::DEFAULT_PRIORITY, Self::DEFAULT_STACK_DEPTH, f)
+    }
+
+    #[inline]
+    /// Spawns a new task with the specified name, priority and stack depth,
+    /// returning a [`JoinHandle`] through which the value returned by `f` can
+    /// be retrieved.
+    pub fn spawn_joinable_ext<T, F>(
+        name: &str,
+        priority: u32,
+        stack_depth: u16,
+        f: F,
+    ) -> Result<JoinHandle<T>, Error>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        JoinHandle::spawn(name, priority, stack_depth, f)
+    }
+
     #[inline]
     /// Spawns a new task from a C function pointer and an arbitrary data
     /// pointer.
@@ -309,6 +373,57 @@ impl Task {
         })
     }
 
+    /// Gets the tasks spawned with [`Task::spawn()`] or [`Task::spawn_ext()`]
+    /// which have not yet finished. Tasks created by PROS itself are not
+    /// included.
+    pub fn spawned() -> Vec<Self> {
+        spawned_tasks().lock().clone()
+    }
+
+    /// Gets the tasks spawned by this crate, like [`Task::spawned()`], unless
+    /// another task is currently updating the list. Used by the panic handler,
+    /// which must not block.
+    pub(crate) fn try_spawned() -> Option<Vec<Self>> {
+        match SPAWNED.get() {
+            Some(spawned) => Some(spawned.poll()?.clone()),
+            None => Some(Vec::new()),
+        }
+    }
+
+    #[inline]
+    /// Gets the total number of tasks which currently exist, including those
+    /// created by PROS.
+    pub fn count() -> u32 {
+        unsafe { bindings::task_get_count() }
+    }
+
+    /// Gets every task which currently exists, including those created by
+    /// PROS, along with its run-time counter: the processor time it has used
+    /// since it was created, in ticks of the kernel's run-time statistics
+    /// timer. The counter wraps around on overflow.
+    pub(crate) fn run_times() -> Vec<(Self, u32)> {
+        // Leave room for tasks created in the meantime; the kernel fills in
+        // nothing if there is not enough.
+        let mut capacity = Self::count() as usize + 4;
+        loop {
+            let mut status: Vec<TaskStatus> = Vec::with_capacity(capacity);
+            let len = unsafe {
+                uxTaskGetSystemState(status.as_mut_ptr(), capacity as u32, core::ptr::null_mut())
+            };
+            if len > 0 {
+                unsafe { status.set_len(len as usize) };
+                return status
+                    .into_iter()
+                    .filter(|status| {
+                        status.state != bindings::task_state_e_t_E_TASK_STATE_DELETED as u8
+                    })
+                    .map(|status| (Self(status.handle), status.run_time))
+                    .collect();
+            }
+            capacity *= 2;
+        }
+    }
+
     #[inline]
     /// Gets the name of the task.
     pub fn name(&self) -> String {
@@ -321,6 +436,21 @@ impl Task {
         unsafe { bindings::task_get_priority(self.0) }
     }
 
+    #[inline]
+    /// Sets the priority of the task. A task's priority should be between
+    /// [`bindings::TASK_PRIORITY_MIN`] and [`bindings::TASK_PRIORITY_MAX`].
+    pub fn set_priority(&self, priority: u32) {
+        unsafe { bindings::task_set_priority(self.0, priority) }
+    }
+
+    #[inline]
+    /// Gets the least amount of stack space, in words, which has been free
+    /// since the task started, i.e., its stack high-water mark. A value close
+    /// to zero means the task has nearly overflowed its stack.
+    pub fn stack_headroom(&self) -> u32 {
+        unsafe { uxTaskGetStackHighWaterMark(self.0) }
+    }
+
     #[inline]
     /// Gets the state of the task.
     pub fn state(&self) -> TaskState {
@@ -346,6 +476,7 @@ impl Task {
     /// unwinds (i.e., that destructors are called, memory is freed and other
     /// resources are released).
     pub unsafe fn delete(&self) {
+        spawned_tasks().lock().retain(|task| task != self);
         bindings::task_delete(self.0)
     }
 }
@@ -364,6 +495,7 @@ unsafe impl Send for Task {}
 unsafe impl Sync for Task {}
 
 /// Represents the state of a [`Task`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum TaskState {
     /// The task is actively executing.
     Running,
@@ -576,22 +708,101 @@ pub fn delay_until(timestamp: Instant) -> impl Selectable {
     DelaySelect(timestamp)
 }
 
+/// Runs `f` at the highest task priority, so that a group of device writes,
+/// such as setting the left and right drive motors, are issued back-to-back,
+/// with as little skew between them as possible.
+///
+/// The current task's priority is raised to
+/// [`TASK_PRIORITY_MAX`](bindings::TASK_PRIORITY_MAX) while `f` runs, and then
+/// restored. On the robot a panic aborts the program, so a panic in `f` never
+/// returns here. The scheduler is not suspended, since device functions take a
+/// mutex for their port, and no task may block while the scheduler is
+/// suspended; a task holding one of these mutexes inherits the raised priority
+/// until it releases it, so `f` is not held up for long. `f` should be short,
+/// and must not delay or wait on an event, or other tasks will be starved.
+///
+/// This is not atomic with respect to everything else: interrupts, including
+/// the kernel's tick hook, and other tasks which run at `TASK_PRIORITY_MAX`,
+/// such as the [soft limit](crate::motor::Motor::set_soft_limits()) supervisor
+/// and the [starvation monitor](crate::starvation::StarvationMonitor), can
+/// still run in the middle of `f`.
+///
+/// # Example
+///
+/// ```
+/// rtos::atomically(|| -> Result<(), DeviceError> {
+///     left.move_voltage(x)?;
+///     right.move_voltage(y)
+/// })?;
+/// ```
+pub fn atomically<T>(f: impl FnOnce() -> T) -> T {
+    struct RestorePriority(Task, u32);
+
+    impl Drop for RestorePriority {
+        fn drop(&mut self) {
+            self.0.set_priority(self.1);
+        }
+    }
+
+    let task = Task::current();
+    let _restore = RestorePriority(task.clone(), task.priority());
+    task.set_priority(bindings::TASK_PRIORITY_MAX);
+    f()
+}
+
+/// Represents the expiry of the timeout of a blocking operation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Timeout;
+
+impl Display for Timeout {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("operation timed out")
+    }
+}
+
+impl From<Timeout> for Error {
+    fn from(err: Timeout) -> Self {
+        Error::Custom(format!("{}", err))
+    }
+}
+
 mod broadcast;
+mod buffered_channel;
+mod cached;
 mod channel;
+mod condvar;
 mod context;
 mod event;
+mod event_flags;
+mod executor;
+mod hooks;
+mod jobs;
+mod join;
 mod r#loop;
 mod mutex;
 mod promise;
 mod queue;
+mod rwlock;
 mod semaphore;
+mod watch;
 
 pub use broadcast::*;
+pub use buffered_channel::*;
+pub use cached::*;
 pub use channel::*;
+pub use condvar::*;
 pub use context::*;
 pub use event::*;
+pub use event_flags::*;
+pub use executor::*;
+pub use hooks::*;
+pub use jobs::*;
+pub use join::*;
 pub use mutex::*;
 pub use promise::*;
 pub use queue::*;
 pub use r#loop::*;
+pub use rwlock::*;
 pub use semaphore::*;
+pub use watch::*;
@@ -1,7 +1,10 @@
+use alloc::sync::Arc;
 use core::{
     cell::UnsafeCell,
     fmt::{self, Debug, Display, Formatter},
+    mem::ManuallyDrop,
     ops::{Deref, DerefMut},
+    ptr,
 };
 
 use crate::{bindings, error::*};
@@ -34,9 +37,26 @@ impl<T> Mutex<T> {
             mutex: unsafe { bindings::mutex_recursive_create() }.check()?,
         })
     }
+
+    /// Consumes the mutex, returning the object it protected. No locking is
+    /// needed, since the mutex is owned.
+    pub fn into_inner(self) -> T {
+        let this = ManuallyDrop::new(self);
+        unsafe {
+            bindings::mutex_delete(this.mutex);
+            ptr::read(&this.data).into_inner()
+        }
+    }
 }
 
 impl<T: ?Sized> Mutex<T> {
+    #[inline]
+    /// Gets a mutable reference to the object protected by the mutex. No
+    /// locking is needed, since the mutex is borrowed exclusively.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.data.get_mut()
+    }
+
     #[inline]
     /// Obtains a [`MutexGuard`] giving access to the object protected by the
     /// mutex. Blocks until access can be obtained. Panics on failure; see
@@ -69,6 +89,29 @@ impl<T: ?Sized> Mutex<T> {
         }
     }
 
+    #[inline]
+    /// Obtains an [`OwnedMutexGuard`] giving access to the object protected by
+    /// the mutex, which keeps the mutex alive rather than borrowing it. Blocks
+    /// until access can be obtained. Panics on failure; see
+    /// [`Mutex::try_lock_owned()`].
+    pub fn lock_owned(self: Arc<Self>) -> OwnedMutexGuard<T> {
+        self.try_lock_owned()
+            .unwrap_or_else(|err| panic!("Failed to lock mutex: {:?}", err))
+    }
+
+    #[inline]
+    /// Obtains an [`OwnedMutexGuard`] giving access to the object protected by
+    /// the mutex, which keeps the mutex alive rather than borrowing it. Blocks
+    /// until access can be obtained; see [`Mutex::lock()`] for a more thorough
+    /// behavioural description.
+    pub fn try_lock_owned(self: Arc<Self>) -> Result<OwnedMutexGuard<T>, Error> {
+        if unsafe { bindings::mutex_recursive_take(self.mutex, TIMEOUT_MAX) } {
+            Ok(OwnedMutexGuard(self))
+        } else {
+            Err(from_errno())
+        }
+    }
+
     #[inline]
     /// Obtains a [`MutexGuard`] giving access to the object protected by the
     /// mutex, if it is available immediately. Does not block.
@@ -134,7 +177,7 @@ impl<T> From<T> for Mutex<T> {
 /// out of scope. Rust's object and reference lifetime rules prevent safe code
 /// from retaining access to the [`Mutex`] object's internal data beyond the
 /// lifetime of the guard object.
-pub struct MutexGuard<'a, T: ?Sized>(&'a Mutex<T>);
+pub struct MutexGuard<'a, T: ?Sized>(pub(super) &'a Mutex<T>);
 
 impl<T: ?Sized> Deref for MutexGuard<'_, T> {
     type Target = T;
@@ -178,3 +221,55 @@ impl<T: ?Sized + Display> Display for MutexGuard<'_, T> {
 impl<T: ?Sized> !Send for MutexGuard<'_, T> {}
 
 unsafe impl<T: ?Sized + Sync> Sync for MutexGuard<'_, T> {}
+
+/// Provides exclusive access to an object controlled by a [`Mutex`] via the
+/// RAII pattern, like a [`MutexGuard`], but holds a reference-counted pointer
+/// to the mutex rather than borrowing it, so it can be stored without a
+/// lifetime. Created by [`Mutex::lock_owned()`].
+///
+/// Like a [`MutexGuard`], this object represents the current task's ownership
+/// of the mutex, so it cannot be transferred to another task.
+pub struct OwnedMutexGuard<T: ?Sized>(Arc<Mutex<T>>);
+
+impl<T: ?Sized> Deref for OwnedMutexGuard<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.0.data.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for OwnedMutexGuard<T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.0.data.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for OwnedMutexGuard<T> {
+    #[inline]
+    fn drop(&mut self) {
+        if !unsafe { bindings::mutex_recursive_give(self.0.mutex) } {
+            panic!("failed to return mutex: {:?}", from_errno());
+        }
+    }
+}
+
+impl<T: ?Sized + Debug> Debug for OwnedMutexGuard<T> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized + Display> Display for OwnedMutexGuard<T> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: ?Sized> !Send for OwnedMutexGuard<T> {}
+
+unsafe impl<T: ?Sized + Sync> Sync for OwnedMutexGuard<T> {}
@@ -4,13 +4,18 @@ use alloc::sync::{Arc, Weak};
 use owner_monad::OwnerMut;
 
 use super::{
-    handle_event, select, Context, Event, EventHandle, GenericSleep, Instant, Mutex, Selectable,
-    Task,
+    handle_event, select, Context, Event, EventHandle, GenericSleep, Instant, Mutex, ParentContext,
+    Selectable, Task,
 };
 use crate::{error::Error, select};
 
 /// Represents an ongoing operation which produces a result.
-pub struct Promise<T: 'static = ()>(Arc<Mutex<PromiseData<T>>>);
+///
+/// A promise created with [`Promise::new_cancellable()`] or
+/// [`Promise::spawn_cancellable()`] also gives the producer a [`Context`] which
+/// is cancelled once every copy of the promise has been dropped, so that the
+/// producer can stop working on a result which nobody is waiting for.
+pub struct Promise<T: 'static = ()>(Arc<Mutex<PromiseData<T>>>, Option<Context>);
 
 impl<T: Send + Sync + 'static> Promise<T> {
     /// Creates a new lightweight promise and an associated resolve function.
@@ -32,7 +37,7 @@ impl<T: Send + Sync + 'static> Promise<T> {
     /// ```
     pub fn new() -> (Self, impl FnOnce(T) + Send) {
         let data = Arc::new(Mutex::new(PromiseData::Incomplete(Event::new())));
-        let promise = Self(data.clone());
+        let promise = Self(data.clone(), None);
         let resolve = move |r: T| {
             let mut l = data.lock();
             if let Some(e) = l.event() {
@@ -43,6 +48,31 @@ impl<T: Send + Sync + 'static> Promise<T> {
         (promise, resolve)
     }
 
+    /// Creates a new lightweight promise, an associated resolve function, and a
+    /// context which is cancelled once every copy of the promise has been
+    /// dropped.
+    ///
+    /// # Example
+    /// ```
+    /// let (promise, ctx, resolve) = Promise::<u32>::new_cancellable();
+    /// Task::spawn(move || {
+    ///     let mut n = 0;
+    ///     while !ctx.is_cancelled() && n < 1000 {
+    ///         n += search_step();
+    ///     }
+    ///     resolve(n);
+    /// })
+    /// .unwrap();
+    /// drop(promise); // Stops the search.
+    /// ```
+    pub fn new_cancellable() -> (Self, Context, impl FnOnce(T) + Send) {
+        let (mut promise, resolve) = Self::new();
+        let interest = Context::new_global();
+        let ctx = interest.fork();
+        promise.1 = Some(interest);
+        (promise, ctx, resolve)
+    }
+
     /// A [`Selectable`] event which occurs when the promise is resolved.
     pub fn done(&'_ self) -> impl Selectable<&'_ T> + '_ {
         struct PromiseSelect<'a, T: 'static> {
@@ -96,6 +126,27 @@ impl<T: Send + Sync + 'static> Promise<T> {
         Ok(promise)
     }
 
+    #[inline]
+    /// Spawns a task to run the given function and returns a [`Promise`] that
+    /// resolves with the result when it returns. The function is given a
+    /// context which is cancelled once every copy of the promise has been
+    /// dropped. Panics on failure; see [`Promise::try_spawn_cancellable()`].
+    pub fn spawn_cancellable(f: impl FnOnce(Context) -> T + Send + 'static) -> Self {
+        Self::try_spawn_cancellable(f).unwrap()
+    }
+
+    /// Spawns a task to run the given function and returns a [`Promise`] that
+    /// resolves with the result when it returns. The function is given a
+    /// context which is cancelled once every copy of the promise has been
+    /// dropped.
+    pub fn try_spawn_cancellable(
+        f: impl FnOnce(Context) -> T + Send + 'static,
+    ) -> Result<Self, Error> {
+        let (promise, ctx, resolve) = Self::new_cancellable();
+        Task::spawn(|| resolve(f(ctx)))?;
+        Ok(promise)
+    }
+
     #[inline]
     /// Spawns a new promise which, upon the completion of `self`, runs `f`.
     pub fn then<U: Send + Sync + 'static>(
@@ -138,7 +189,7 @@ impl<T: Send + Sync + 'static> Promise<T> {
 
 impl<T: 'static> Clone for Promise<T> {
     fn clone(&self) -> Self {
-        Self(self.0.clone())
+        Self(self.0.clone(), self.1.clone())
     }
 }
 
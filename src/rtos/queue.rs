@@ -1,4 +1,4 @@
-use alloc::sync::Arc;
+use alloc::{sync::Arc, vec::Vec};
 use owner_monad::OwnerMut;
 use queue_model::QueueModel;
 
@@ -11,6 +11,13 @@ pub struct SendQueue<T>(Arc<dyn QueueShared<T> + Send + Sync>);
 impl<T> SendQueue<T> {
     #[inline]
     /// Attempts to send an item on a queue.
+    ///
+    /// This never waits: an item rejected by the queue model is discarded.
+    /// There is no selectable send event, since a rejected item cannot be
+    /// recovered to try again; to wait until there is room instead, use a
+    /// [`buffered_channel()`](super::buffered_channel()), whose
+    /// [`select()`](super::BufferedSendChannel::select()) keeps the value
+    /// until it has been sent.
     pub fn send(&self, item: T) -> bool {
         self.0.send(item)
     }
@@ -26,8 +33,31 @@ impl<T> Clone for SendQueue<T> {
 pub struct ReceiveQueue<T>(Arc<dyn QueueShared<T> + Send + Sync>);
 
 impl<T> ReceiveQueue<T> {
+    #[inline]
+    /// Receives an item from the queue if one is available, without blocking.
+    pub fn try_recv(&self) -> Option<T> {
+        self.0.receive()
+    }
+
+    #[inline]
+    /// Iterates over items received from the queue, without blocking; the
+    /// iterator ends as soon as the queue is empty. Items sent while iterating
+    /// are also received.
+    pub fn try_iter(&self) -> impl '_ + Iterator<Item = T> {
+        core::iter::from_fn(move || self.0.receive())
+    }
+
+    #[inline]
+    /// Removes every item currently in the queue at once, without blocking,
+    /// and iterates over them. Unlike [`ReceiveQueue::try_iter()`], items sent
+    /// afterwards are left in the queue.
+    pub fn drain(&self) -> impl Iterator<Item = T> {
+        self.0.drain().into_iter()
+    }
+
     /// A [`Selectable`] event which resolves when a value is received on the
-    /// message-passing queue.
+    /// message-passing queue. This is the selectable counterpart of
+    /// [`ReceiveQueue::try_recv()`], so there is no separate `select_recv()`.
     pub fn select(&self) -> impl '_ + Selectable<T> {
         struct ReceiveSelect<'b, T> {
             data: &'b dyn QueueShared<T>,
@@ -96,6 +126,11 @@ pub fn try_queue<Q: 'static + QueueModel + Send + Sync>(queue: Q) -> Result<Queu
             self.0.lock().queue.dequeue()
         }
 
+        fn drain(&self) -> Vec<Q::Item> {
+            let mut lock = self.0.lock();
+            core::iter::from_fn(|| lock.queue.dequeue()).collect()
+        }
+
         fn is_empty(&self) -> bool {
             self.0.lock().queue.is_empty()
         }
@@ -119,9 +154,64 @@ pub fn try_queue<Q: 'static + QueueModel + Send + Sync>(queue: Q) -> Result<Queu
     Ok((send, receive))
 }
 
+/// A [`QueueModel`] which holds at most one item, where sending overwrites any
+/// item which has not yet been received. Sending therefore always succeeds,
+/// and the receiver always gets the most recent value; this suits sharing
+/// e.g. the latest sensor snapshot between tasks without blocking either side.
+///
+/// See [`mailbox()`] to create a queue using this model.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mailbox<T>(Option<T>);
+
+impl<T> Mailbox<T> {
+    #[inline]
+    /// Creates a new, empty mailbox.
+    pub fn new() -> Self {
+        Self(None)
+    }
+}
+
+impl<T> Default for Mailbox<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> QueueModel for Mailbox<T> {
+    type Item = T;
+
+    fn enqueue(&mut self, item: T) -> bool {
+        self.0 = Some(item);
+        true
+    }
+
+    fn dequeue(&mut self) -> Option<T> {
+        self.0.take()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_none()
+    }
+}
+
+#[inline]
+/// Creates a new send-receive pair together representing a [`Mailbox`].
+/// Panics on failure; see [`try_mailbox`].
+pub fn mailbox<T: 'static + Send + Sync>() -> QueuePair<Mailbox<T>> {
+    queue(Mailbox::new())
+}
+
+#[inline]
+/// Creates a new send-receive pair together representing a [`Mailbox`].
+pub fn try_mailbox<T: 'static + Send + Sync>() -> Result<QueuePair<Mailbox<T>>, Error> {
+    try_queue(Mailbox::new())
+}
+
 trait QueueShared<T> {
     fn send(&self, item: T) -> bool;
     fn receive(&self) -> Option<T>;
+    fn drain(&self) -> Vec<T>;
     fn is_empty(&self) -> bool;
     fn with_event<'a>(&'a self, f: &'a mut dyn FnMut(&mut Event));
 }
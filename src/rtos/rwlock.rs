@@ -0,0 +1,273 @@
+use core::{
+    cell::UnsafeCell,
+    fmt::{self, Debug, Display, Formatter},
+    ops::{Deref, DerefMut},
+    time::Duration,
+};
+
+use crate::error::Error;
+
+use super::{Mutex, MutexGuard, Semaphore, TIMEOUT_MAX};
+
+/// Represents an object which may be read by any number of tasks at once, or
+/// written by a single task, built on FreeRTOS mutexes and semaphores.
+///
+/// Unlike a [`Mutex`], readers do not block each other, which suits state that
+/// is written by one task and read by several, such as odometry read by
+/// telemetry tasks.
+///
+/// # Behaviour
+///
+/// Writers take priority: once a task is waiting to write, tasks which then
+/// ask to read wait until it has finished, so that a steady stream of readers
+/// cannot starve a writer. Tasks waiting to write are served in priority order,
+/// and a task holding the lock inherits the priority of a higher-priority
+/// writer waiting for it (though not of waiting readers).
+///
+/// A task must not ask to write while it holds either kind of guard for the
+/// same lock, nor ask to read while it already holds a read guard if another
+/// task may be waiting to write; either would deadlock.
+pub struct RwLock<T: ?Sized> {
+    turnstile: Mutex<()>,
+    readers: Mutex<usize>,
+    room: Semaphore,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: ?Sized + Send> Send for RwLock<T> {}
+
+unsafe impl<T: ?Sized + Send + Sync> Sync for RwLock<T> {}
+
+impl<T> RwLock<T> {
+    #[inline]
+    /// Creates a new lock which wraps the given object. Panics on failure; see
+    /// [`RwLock::try_new()`].
+    pub fn new(data: T) -> Self {
+        Self::try_new(data).unwrap_or_else(|err| panic!("failed to create rwlock: {:?}", err))
+    }
+
+    /// Creates a new lock which wraps the given object.
+    pub fn try_new(data: T) -> Result<Self, Error> {
+        Ok(Self {
+            turnstile: Mutex::try_new(())?,
+            readers: Mutex::try_new(0)?,
+            room: Semaphore::try_new(1, 1)?,
+            data: UnsafeCell::new(data),
+        })
+    }
+
+    #[inline]
+    /// Consumes the lock, returning the object it protected. No locking is
+    /// needed, since the lock is owned.
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+}
+
+impl<T: ?Sized> RwLock<T> {
+    #[inline]
+    /// Gets a mutable reference to the object protected by the lock. No
+    /// locking is needed, since the lock is borrowed exclusively.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.data.get_mut()
+    }
+
+    #[inline]
+    /// Obtains an [`RwLockReadGuard`] giving shared access to the object
+    /// protected by the lock. Blocks until no task is writing or waiting to
+    /// write. Panics on failure; see [`RwLock::try_read()`].
+    pub fn read(&'_ self) -> RwLockReadGuard<'_, T> {
+        self.try_read()
+            .unwrap_or_else(|err| panic!("Failed to lock rwlock for reading: {:?}", err))
+    }
+
+    /// Obtains an [`RwLockReadGuard`] giving shared access to the object
+    /// protected by the lock. Blocks until no task is writing or waiting to
+    /// write.
+    pub fn try_read(&'_ self) -> Result<RwLockReadGuard<'_, T>, Error> {
+        let _turnstile = self.turnstile.try_lock()?;
+        let mut readers = self.readers.try_lock()?;
+        if *readers == 0 {
+            self.room.wait(Duration::from_millis(TIMEOUT_MAX.into()))?;
+        }
+        *readers += 1;
+        Ok(RwLockReadGuard(self))
+    }
+
+    /// Obtains an [`RwLockReadGuard`] giving shared access to the object
+    /// protected by the lock, if it is available immediately. Does not block.
+    pub fn poll_read(&'_ self) -> Option<RwLockReadGuard<'_, T>> {
+        let _turnstile = self.turnstile.poll()?;
+        let mut readers = self.readers.poll()?;
+        if *readers == 0 {
+            self.room.wait(Duration::ZERO).ok()?;
+        }
+        *readers += 1;
+        Some(RwLockReadGuard(self))
+    }
+
+    #[inline]
+    /// Obtains an [`RwLockWriteGuard`] giving exclusive access to the object
+    /// protected by the lock. Blocks until all other readers and writers have
+    /// finished. Panics on failure; see [`RwLock::try_write()`].
+    pub fn write(&'_ self) -> RwLockWriteGuard<'_, T> {
+        self.try_write()
+            .unwrap_or_else(|err| panic!("Failed to lock rwlock for writing: {:?}", err))
+    }
+
+    /// Obtains an [`RwLockWriteGuard`] giving exclusive access to the object
+    /// protected by the lock. Blocks until all other readers and writers have
+    /// finished.
+    pub fn try_write(&'_ self) -> Result<RwLockWriteGuard<'_, T>, Error> {
+        let turnstile = self.turnstile.try_lock()?;
+        self.room.wait(Duration::from_millis(TIMEOUT_MAX.into()))?;
+        Ok(RwLockWriteGuard {
+            lock: self,
+            _turnstile: turnstile,
+        })
+    }
+
+    /// Obtains an [`RwLockWriteGuard`] giving exclusive access to the object
+    /// protected by the lock, if it is available immediately. Does not block.
+    pub fn poll_write(&'_ self) -> Option<RwLockWriteGuard<'_, T>> {
+        let turnstile = self.turnstile.poll()?;
+        self.room.wait(Duration::ZERO).ok()?;
+        Some(RwLockWriteGuard {
+            lock: self,
+            _turnstile: turnstile,
+        })
+    }
+}
+
+impl<T: ?Sized + Debug> Debug for RwLock<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.poll_read() {
+            Some(guard) => f.debug_struct("RwLock").field("data", &&*guard).finish(),
+            None => {
+                struct LockedPlaceholder;
+                impl Debug for LockedPlaceholder {
+                    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                        f.write_str("<locked>")
+                    }
+                }
+
+                f.debug_struct("RwLock")
+                    .field("data", &LockedPlaceholder)
+                    .finish()
+            }
+        }
+    }
+}
+
+impl<T: Default> Default for RwLock<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Default::default())
+    }
+}
+
+impl<T> From<T> for RwLock<T> {
+    #[inline]
+    fn from(data: T) -> Self {
+        Self::new(data)
+    }
+}
+
+/// Provides shared access to an object controlled by an [`RwLock`] via the
+/// RAII pattern. Any number of these may exist at once, but none while an
+/// [`RwLockWriteGuard`] exists.
+pub struct RwLockReadGuard<'a, T: ?Sized>(&'a RwLock<T>);
+
+impl<T: ?Sized> Deref for RwLockReadGuard<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.0.data.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for RwLockReadGuard<'_, T> {
+    fn drop(&mut self) {
+        let mut readers = self.0.readers.lock();
+        *readers -= 1;
+        if *readers == 0 {
+            if let Err(err) = self.0.room.post() {
+                panic!("failed to return rwlock: {:?}", err);
+            }
+        }
+    }
+}
+
+impl<T: ?Sized + Debug> Debug for RwLockReadGuard<'_, T> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized + Display> Display for RwLockReadGuard<'_, T> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: ?Sized> !Send for RwLockReadGuard<'_, T> {}
+
+unsafe impl<T: ?Sized + Sync> Sync for RwLockReadGuard<'_, T> {}
+
+/// Provides exclusive access to an object controlled by an [`RwLock`] via the
+/// RAII pattern.
+///
+/// Like a [`MutexGuard`], this object represents the current task's ownership
+/// of the lock, so it cannot be transferred to another task.
+pub struct RwLockWriteGuard<'a, T: ?Sized> {
+    lock: &'a RwLock<T>,
+    // Released after the room, so that waiting readers cannot slip in ahead of
+    // the next waiting writer.
+    _turnstile: MutexGuard<'a, ()>,
+}
+
+impl<T: ?Sized> Deref for RwLockWriteGuard<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for RwLockWriteGuard<'_, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for RwLockWriteGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        if let Err(err) = self.lock.room.post() {
+            panic!("failed to return rwlock: {:?}", err);
+        }
+    }
+}
+
+impl<T: ?Sized + Debug> Debug for RwLockWriteGuard<'_, T> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized + Display> Display for RwLockWriteGuard<'_, T> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: ?Sized> !Send for RwLockWriteGuard<'_, T> {}
+
+unsafe impl<T: ?Sized + Sync> Sync for RwLockWriteGuard<'_, T> {}
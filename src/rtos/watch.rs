@@ -0,0 +1,155 @@
+use alloc::sync::Arc;
+
+use owner_monad::OwnerMut;
+
+use super::{handle_event, Event, EventHandle, GenericSleep, Instant, Mutex, Selectable};
+use crate::error::Error;
+
+/// A shared cell holding a single value, which notifies waiting tasks each
+/// time the value is set. Clones refer to the same cell.
+///
+/// This is lighter than a [`Broadcast`](super::Broadcast), as there are no
+/// listeners to keep track of: tasks simply read the current value, or wait
+/// for it to change. It suits simple shared state such as a "flywheel ready"
+/// flag.
+///
+/// # Example
+///
+/// ```
+/// let ready = Watch::new(false);
+///
+/// // In the flywheel task:
+/// ready.set(flywheel.at_speed());
+///
+/// // In autonomous:
+/// select(ready.wait_for(|ready| *ready));
+/// indexer.feed();
+/// ```
+pub struct Watch<T>(Arc<Mutex<WatchData<T>>>);
+
+impl<T: Clone> Watch<T> {
+    #[inline]
+    /// Creates a new cell with the given initial value. Panics on failure; see
+    /// [`Watch::try_new()`].
+    pub fn new(value: T) -> Self {
+        Self::try_new(value).unwrap_or_else(|err| panic!("failed to create watch: {:?}", err))
+    }
+
+    /// Creates a new cell with the given initial value.
+    pub fn try_new(value: T) -> Result<Self, Error> {
+        Ok(Self(Arc::new(Mutex::try_new(WatchData {
+            value,
+            version: 0,
+            event: Event::new(),
+        })?)))
+    }
+
+    #[inline]
+    /// Gets a copy of the current value.
+    pub fn get(&self) -> T {
+        self.0.lock().value.clone()
+    }
+
+    /// Sets the value, notifying any tasks waiting for it to change.
+    pub fn set(&self, value: T) {
+        let mut lock = self.0.lock();
+        lock.value = value;
+        lock.version = lock.version.wrapping_add(1);
+        lock.event.notify();
+    }
+
+    /// Sets the value only if it differs from the current value, returning
+    /// whether it was set.
+    pub fn set_if_changed(&self, value: T) -> bool
+    where
+        T: PartialEq,
+    {
+        let mut lock = self.0.lock();
+        if lock.value == value {
+            return false;
+        }
+        lock.value = value;
+        lock.version = lock.version.wrapping_add(1);
+        lock.event.notify();
+        true
+    }
+
+    #[inline]
+    /// Gets the number of times the value has been set.
+    pub fn version(&self) -> u64 {
+        self.0.lock().version
+    }
+
+    /// A [`Selectable`] event which occurs with the new value the next time
+    /// the value is set after this call.
+    pub fn changed(&'_ self) -> impl Selectable<T> + '_ {
+        let version = self.version();
+        self.select(move |data| data.version != version)
+    }
+
+    /// A [`Selectable`] event which occurs with the value as soon as it
+    /// satisfies `predicate`, which may be immediately.
+    pub fn wait_for<'a>(&'a self, predicate: impl Fn(&T) -> bool + 'a) -> impl Selectable<T> + 'a {
+        self.select(move |data| predicate(&data.value))
+    }
+
+    fn select<'a>(&'a self, ready: impl Fn(&WatchData<T>) -> bool + 'a) -> impl Selectable<T> + 'a {
+        struct WatchSelect<'b, T, F> {
+            data: &'b Mutex<WatchData<T>>,
+            ready: F,
+            _handle: EventHandle<WatchHandle<'b, T>>,
+        }
+
+        impl<'b, T: Clone, F: Fn(&WatchData<T>) -> bool> Selectable<T> for WatchSelect<'b, T, F> {
+            fn poll(self) -> Result<T, Self> {
+                let value = {
+                    let lock = self.data.lock();
+                    if (self.ready)(&lock) {
+                        Some(lock.value.clone())
+                    } else {
+                        None
+                    }
+                };
+                value.ok_or(self)
+            }
+
+            fn sleep(&self) -> GenericSleep {
+                if (self.ready)(&self.data.lock()) {
+                    GenericSleep::Timestamp(Instant::from_millis(0))
+                } else {
+                    GenericSleep::NotifyTake(None)
+                }
+            }
+        }
+
+        WatchSelect {
+            data: &self.0,
+            ready,
+            _handle: handle_event(WatchHandle(&self.0)),
+        }
+    }
+}
+
+impl<T> Clone for Watch<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+struct WatchData<T> {
+    value: T,
+    version: u64,
+    event: Event,
+}
+
+struct WatchHandle<'a, T>(&'a Mutex<WatchData<T>>);
+
+impl<'a, T> OwnerMut<Event> for WatchHandle<'a, T> {
+    fn with<'b, U>(&'b mut self, f: impl FnOnce(&mut Event) -> U) -> Option<U>
+    where
+        Event: 'b,
+    {
+        Some(f(&mut self.0.lock().event))
+    }
+}
@@ -0,0 +1,383 @@
+//! # Screen Sprites.
+//!
+//! A [`Sprite`] is a bitmap, or a sequence of bitmaps forming an animation,
+//! drawn at a position on the brain screen. Sprites are added to a
+//! [`SpriteCanvas`], which runs a background task to advance their animations
+//! and redraw them. Only the areas of the screen which have changed are
+//! redrawn, so status icons and small animations do not require redrawing the
+//! whole screen on each frame.
+//!
+//! Sprites are opaque: each is drawn as a filled rectangle, over anything
+//! drawn beneath it.
+//!
+//! # Example
+//!
+//! ```
+//! let canvas = SpriteCanvas::new();
+//! let red = Bitmap::filled(16, 16, 0xFF0000);
+//! let dark = Bitmap::filled(16, 16, 0x400000);
+//! let heartbeat = canvas
+//!     .add(Sprite::animated(vec![red, dark], Duration::from_millis(500)).with_position(456, 8));
+//!
+//! // Later, e.g. to hide it when disabled:
+//! canvas.set_visible(heartbeat, false);
+//! ```
+
+use alloc::{
+    sync::{Arc, Weak},
+    vec,
+    vec::Vec,
+};
+use core::time::Duration;
+
+use crate::{
+    bindings,
+    error::Error,
+    rtos::{time_since_start, Instant, Loop, Mutex, Task},
+};
+
+/// The width of the brain screen, in pixels.
+pub const SCREEN_WIDTH: i16 = 480;
+
+/// The height of the brain screen, in pixels.
+pub const SCREEN_HEIGHT: i16 = 240;
+
+/// The interval at which a [`SpriteCanvas`] redraws the screen.
+const FRAME_PERIOD: Duration = Duration::from_millis(20);
+
+/// A rectangular image, with the colour of each pixel as `0xRRGGBB`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bitmap {
+    width: i16,
+    height: i16,
+    pixels: Vec<u32>,
+}
+
+impl Bitmap {
+    /// Creates a new bitmap of the given size from its pixels, row by row.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of pixels does not match the size.
+    pub fn new(width: i16, height: i16, pixels: Vec<u32>) -> Self {
+        assert_eq!(
+            pixels.len(),
+            width as usize * height as usize,
+            "bitmap has the wrong number of pixels"
+        );
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    #[inline]
+    /// Creates a new bitmap of the given size filled with a single colour.
+    pub fn filled(width: i16, height: i16, color: u32) -> Self {
+        Self::new(width, height, vec![color; width as usize * height as usize])
+    }
+
+    #[inline]
+    /// Gets the width of the bitmap, in pixels.
+    pub fn width(&self) -> i16 {
+        self.width
+    }
+
+    #[inline]
+    /// Gets the height of the bitmap, in pixels.
+    pub fn height(&self) -> i16 {
+        self.height
+    }
+
+    #[inline]
+    /// Gets the pixels of the bitmap, row by row.
+    pub fn pixels(&self) -> &[u32] {
+        &self.pixels
+    }
+}
+
+/// A bitmap, or an animated sequence of bitmaps, at a position on the brain
+/// screen.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sprite {
+    frames: Vec<Bitmap>,
+    frame_time: Duration,
+    x: i16,
+    y: i16,
+    visible: bool,
+}
+
+impl Sprite {
+    #[inline]
+    /// Creates a new sprite showing a single bitmap at the top-left corner of
+    /// the screen.
+    pub fn new(bitmap: Bitmap) -> Self {
+        Self::animated(vec![bitmap], Duration::ZERO)
+    }
+
+    /// Creates a new sprite at the top-left corner of the screen which shows
+    /// each of the given bitmaps in turn for `frame_time`, looping forever.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `frames` is empty.
+    pub fn animated(frames: Vec<Bitmap>, frame_time: Duration) -> Self {
+        assert!(!frames.is_empty(), "sprite must have at least one frame");
+        Self {
+            frames,
+            frame_time,
+            x: 0,
+            y: 0,
+            visible: true,
+        }
+    }
+
+    #[inline]
+    /// Sets the position of the top-left corner of the sprite, in pixels.
+    pub fn with_position(mut self, x: i16, y: i16) -> Self {
+        self.x = x;
+        self.y = y;
+        self
+    }
+
+    #[inline]
+    /// Sets whether the sprite is initially visible. The default is `true`.
+    pub fn with_visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+}
+
+/// Identifies a sprite which has been added to a [`SpriteCanvas`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SpriteId(usize);
+
+/// Draws a set of [`Sprite`]s on the brain screen from a background task,
+/// redrawing only the areas which have changed. The task exits when the
+/// canvas is dropped.
+///
+/// Other drawing on the screen is not affected, except where it is covered by
+/// a sprite or by an area a sprite has left.
+pub struct SpriteCanvas(Arc<Mutex<CanvasData>>);
+
+impl SpriteCanvas {
+    #[inline]
+    /// Creates a new, empty canvas and spawns its drawing task. Panics on
+    /// failure; see [`SpriteCanvas::try_new()`].
+    pub fn new() -> Self {
+        Self::try_new().unwrap_or_else(|err| panic!("failed to create sprite canvas: {:?}", err))
+    }
+
+    /// Creates a new, empty canvas and spawns its drawing task.
+    pub fn try_new() -> Result<Self, Error> {
+        let data = Arc::new(Mutex::try_new(CanvasData {
+            sprites: Vec::new(),
+            dirty: Vec::new(),
+        })?);
+        let weak: Weak<Mutex<CanvasData>> = Arc::downgrade(&data);
+        Task::spawn_ext(
+            "sprite canvas",
+            bindings::TASK_PRIORITY_MIN,
+            Task::DEFAULT_STACK_DEPTH,
+            move || {
+                let mut l = Loop::new(FRAME_PERIOD);
+                while let Some(data) = weak.upgrade() {
+                    data.lock().update();
+                    drop(data);
+                    l.delay();
+                }
+            },
+        )?;
+        Ok(Self(data))
+    }
+
+    /// Adds a sprite to the canvas, drawing it on the next frame.
+    pub fn add(&self, sprite: Sprite) -> SpriteId {
+        let state = SpriteState {
+            sprite,
+            frame: 0,
+            frame_start: time_since_start(),
+            drawn: None,
+        };
+        let mut data = self.0.lock();
+        data.dirty.extend(state.bounds());
+        let index = match data.sprites.iter().position(Option::is_none) {
+            Some(index) => {
+                data.sprites[index] = Some(state);
+                index
+            }
+            None => {
+                data.sprites.push(Some(state));
+                data.sprites.len() - 1
+            }
+        };
+        SpriteId(index)
+    }
+
+    /// Removes a sprite from the canvas, erasing it on the next frame.
+    pub fn remove(&self, id: SpriteId) -> Option<Sprite> {
+        let mut data = self.0.lock();
+        let state = data.sprites.get_mut(id.0)?.take()?;
+        data.dirty.extend(state.drawn);
+        Some(state.sprite)
+    }
+
+    /// Moves a sprite so that its top-left corner is at the given position.
+    pub fn set_position(&self, id: SpriteId, x: i16, y: i16) {
+        self.modify(id, |sprite| {
+            sprite.sprite.x = x;
+            sprite.sprite.y = y;
+        });
+    }
+
+    /// Shows or hides a sprite.
+    pub fn set_visible(&self, id: SpriteId, visible: bool) {
+        self.modify(id, |sprite| sprite.sprite.visible = visible);
+    }
+
+    /// Shows the given frame of a sprite, restarting its animation from there.
+    /// Out-of-range frames are ignored.
+    pub fn set_frame(&self, id: SpriteId, frame: usize) {
+        self.modify(id, |sprite| {
+            if frame < sprite.sprite.frames.len() {
+                sprite.frame = frame;
+                sprite.frame_start = time_since_start();
+            }
+        });
+    }
+
+    /// Replaces the bitmaps of a sprite, restarting its animation.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `frames` is empty.
+    pub fn set_frames(&self, id: SpriteId, frames: Vec<Bitmap>, frame_time: Duration) {
+        assert!(!frames.is_empty(), "sprite must have at least one frame");
+        self.modify(id, |sprite| {
+            sprite.sprite.frames = frames;
+            sprite.sprite.frame_time = frame_time;
+            sprite.frame = 0;
+            sprite.frame_start = time_since_start();
+        });
+    }
+
+    /// Applies a change to a sprite, marking its old and new areas for redraw.
+    fn modify(&self, id: SpriteId, f: impl FnOnce(&mut SpriteState)) {
+        let mut data = self.0.lock();
+        let data = &mut *data;
+        if let Some(Some(sprite)) = data.sprites.get_mut(id.0) {
+            f(sprite);
+            data.dirty.extend(sprite.drawn);
+            data.dirty.extend(sprite.bounds());
+        }
+    }
+}
+
+impl Default for SpriteCanvas {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct CanvasData {
+    sprites: Vec<Option<SpriteState>>,
+    /// The areas of the screen which need to be redrawn.
+    dirty: Vec<Rect>,
+}
+
+impl CanvasData {
+    /// Advances the animations and redraws the areas which have changed.
+    fn update(&mut self) {
+        let now = time_since_start();
+        for sprite in self.sprites.iter_mut().flatten() {
+            if sprite.advance(now) {
+                self.dirty.extend(sprite.drawn);
+                self.dirty.extend(sprite.bounds());
+            }
+        }
+        if self.dirty.is_empty() {
+            return;
+        }
+
+        for rect in &self.dirty {
+            unsafe { bindings::screen_erase_rect(rect.x0, rect.y0, rect.x1, rect.y1) };
+        }
+        for sprite in self.sprites.iter_mut().flatten() {
+            let bounds = match sprite.bounds() {
+                Some(bounds) if self.dirty.iter().any(|rect| rect.intersects(&bounds)) => bounds,
+                _ => continue,
+            };
+            let bitmap = &sprite.sprite.frames[sprite.frame];
+            unsafe {
+                // The screen only reads from the buffer.
+                bindings::screen_copy_area(
+                    bounds.x0,
+                    bounds.y0,
+                    bounds.x1,
+                    bounds.y1,
+                    bitmap.pixels.as_ptr() as *mut u32,
+                    bitmap.width as i32,
+                )
+            };
+            sprite.drawn = Some(bounds);
+        }
+        self.dirty.clear();
+    }
+}
+
+struct SpriteState {
+    sprite: Sprite,
+    frame: usize,
+    frame_start: Instant,
+    /// The area the sprite was last drawn in, if it is on the screen.
+    drawn: Option<Rect>,
+}
+
+impl SpriteState {
+    /// Gets the area the sprite should be drawn in, if it is visible.
+    fn bounds(&self) -> Option<Rect> {
+        let bitmap = &self.sprite.frames[self.frame];
+        if !self.sprite.visible || bitmap.width <= 0 || bitmap.height <= 0 {
+            return None;
+        }
+        Some(Rect {
+            x0: self.sprite.x,
+            y0: self.sprite.y,
+            x1: self.sprite.x + bitmap.width - 1,
+            y1: self.sprite.y + bitmap.height - 1,
+        })
+    }
+
+    /// Moves on to the next frame of the animation if it is due, returning
+    /// whether the frame changed.
+    fn advance(&mut self, now: Instant) -> bool {
+        let frames = self.sprite.frames.len();
+        if frames < 2 || self.sprite.frame_time.is_zero() {
+            return false;
+        }
+        let mut changed = false;
+        while now - self.frame_start >= self.sprite.frame_time {
+            self.frame_start += self.sprite.frame_time;
+            self.frame = (self.frame + 1) % frames;
+            changed = true;
+        }
+        changed
+    }
+}
+
+/// A rectangle on the screen, with inclusive bounds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Rect {
+    x0: i16,
+    y0: i16,
+    x1: i16,
+    y1: i16,
+}
+
+impl Rect {
+    fn intersects(&self, other: &Rect) -> bool {
+        self.x0 <= other.x1 && other.x0 <= self.x1 && self.y0 <= other.y1 && other.y0 <= self.y1
+    }
+}
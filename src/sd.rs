@@ -0,0 +1,195 @@
+//! # SD Card API.
+//!
+//! Files on the SD card are accessed through paths beginning with `/usd/`,
+//! e.g. `/usd/auton.bin`.
+
+use core::fmt;
+
+use cstring_interop::with_cstring;
+
+use crate::{
+    bindings,
+    error::{get_errno, Error},
+};
+
+/// A struct which represents the V5 Brain's SD card slot.
+pub struct SdCard {}
+
+impl SdCard {
+    /// Checks whether an SD card is inserted.
+    pub fn is_installed() -> bool {
+        unsafe { bindings::usd_is_installed() == 1 }
+    }
+}
+
+/// Represents an open file on the SD card. The file is closed when dropped.
+pub struct File(*mut bindings::FILE);
+
+impl File {
+    #[inline]
+    /// Opens an existing file for reading.
+    pub fn open(path: &str) -> Result<Self, SdError> {
+        Self::open_with(path, b"rb\0")
+    }
+
+    #[inline]
+    /// Creates a file for writing, truncating it if it already exists.
+    pub fn create(path: &str) -> Result<Self, SdError> {
+        Self::open_with(path, b"wb\0")
+    }
+
+    #[inline]
+    /// Opens a file for writing at its end, creating it if it does not exist.
+    pub fn append(path: &str) -> Result<Self, SdError> {
+        Self::open_with(path, b"ab\0")
+    }
+
+    /// Reads up to `buf.len()` bytes from the file, returning the number of
+    /// bytes read. A result of zero indicates the end of the file.
+    pub fn read(&mut self, buf: &mut [u8]) -> Result<usize, SdError> {
+        let n = unsafe { bindings::fread(buf.as_mut_ptr().cast(), 1, buf.len() as _, self.0) };
+        if n == 0 && unsafe { bindings::ferror(self.0) } != 0 {
+            Err(SdError::from_errno())
+        } else {
+            Ok(n as usize)
+        }
+    }
+
+    /// Reads the remainder of the file into `buf`, returning the number of
+    /// bytes read.
+    pub fn read_to_end(&mut self, buf: &mut alloc::vec::Vec<u8>) -> Result<usize, SdError> {
+        let start = buf.len();
+        let mut chunk = [0u8; 512];
+        loop {
+            match self.read(&mut chunk)? {
+                0 => return Ok(buf.len() - start),
+                n => buf.extend_from_slice(&chunk[..n]),
+            }
+        }
+    }
+
+    /// Writes all of `buf` to the file.
+    pub fn write_all(&mut self, buf: &[u8]) -> Result<(), SdError> {
+        let n = unsafe { bindings::fwrite(buf.as_ptr().cast(), 1, buf.len() as _, self.0) };
+        if (n as usize) < buf.len() {
+            Err(SdError::from_errno())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Flushes any buffered writes to the SD card.
+    pub fn flush(&mut self) -> Result<(), SdError> {
+        match unsafe { bindings::fflush(self.0) } {
+            0 => Ok(()),
+            _ => Err(SdError::from_errno()),
+        }
+    }
+
+    fn open_with(path: &str, mode: &'static [u8]) -> Result<Self, SdError> {
+        if !SdCard::is_installed() {
+            return Err(SdError::NotInstalled);
+        }
+        let file = with_cstring(path.into(), |path| unsafe {
+            bindings::fopen(path.into_raw(), mode.as_ptr().cast())
+        });
+        if file.is_null() {
+            Err(SdError::from_errno())
+        } else {
+            Ok(Self(file))
+        }
+    }
+}
+
+impl fmt::Write for File {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write_all(s.as_bytes()).map_err(|_| fmt::Error)
+    }
+}
+
+impl Drop for File {
+    fn drop(&mut self) {
+        unsafe {
+            bindings::fclose(self.0);
+        }
+    }
+}
+
+unsafe impl Send for File {}
+
+#[cfg(feature = "embedded-io")]
+impl embedded_io::ErrorType for File {
+    type Error = SdError;
+}
+
+#[cfg(feature = "embedded-io")]
+impl embedded_io::Read for File {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        File::read(self, buf)
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl embedded_io::Write for File {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        let n = unsafe { bindings::fwrite(buf.as_ptr().cast(), 1, buf.len() as _, self.0) };
+        if n == 0 && !buf.is_empty() {
+            Err(SdError::from_errno())
+        } else {
+            Ok(n as usize)
+        }
+    }
+
+    #[inline]
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        File::flush(self)
+    }
+}
+
+/// Represents possible errors for SD card operations.
+#[derive(Debug)]
+pub enum SdError {
+    /// No SD card is inserted.
+    NotInstalled,
+    /// The file does not exist.
+    NotFound,
+    /// The SD card is full.
+    Full,
+    /// Unknown error.
+    Unknown(i32),
+}
+
+impl SdError {
+    fn from_errno() -> Self {
+        match get_errno() {
+            libc::ENOENT => Self::NotFound,
+            libc::ENOSPC => Self::Full,
+            x => Self::Unknown(x),
+        }
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl embedded_io::Error for SdError {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        match self {
+            SdError::NotInstalled => embedded_io::ErrorKind::NotConnected,
+            SdError::NotFound => embedded_io::ErrorKind::NotFound,
+            SdError::Full => embedded_io::ErrorKind::WriteZero,
+            SdError::Unknown(n) => embedded_io::Error::kind(&Error::System(*n)),
+        }
+    }
+}
+
+impl From<SdError> for Error {
+    fn from(err: SdError) -> Self {
+        match err {
+            SdError::NotInstalled => Error::Custom("no sd card installed".into()),
+            SdError::NotFound => Error::Custom("file not found".into()),
+            SdError::Full => Error::Custom("sd card full".into()),
+            SdError::Unknown(n) => Error::System(n),
+        }
+    }
+}
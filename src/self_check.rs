@@ -0,0 +1,343 @@
+//! # Pre-Match Self-Check.
+//!
+//! A [`SelfCheck`] exercises a set of registered devices once, e.g. from the
+//! `disabled` period or an autonomous selector before a match: motors are
+//! spun briefly and must be seen to move, sensors must report a changing
+//! reading while the motors spin, and the battery and controller are checked.
+//! The results are collected in a [`SelfCheckReport`], which can be shown as
+//! a pass/fail list on the brain screen.
+//!
+//! Like the [`HealthMonitor`](crate::health::HealthMonitor), devices are
+//! registered by port number, so the self-check does not need to own them.
+//!
+//! **Motors are driven during the check**, so the robot should be on a stand
+//! or otherwise free to move.
+//!
+//! # Example
+//!
+//! ```
+//! let mut check = SelfCheck::new()
+//!     .motor("left drive", 1)
+//!     .motor("right drive", 10)
+//!     .sensor("left tracking", 3, DeviceType::Rotation, || unsafe {
+//!         RotationSensor::new(3, false)
+//!             .ok()?
+//!             .get_position()
+//!             .ok()
+//!             .map(f64::from)
+//!     })
+//!     .device("imu", 11, DeviceType::Imu)
+//!     .battery(50.0)
+//!     .controller(ControllerId::Master);
+//!
+//! // In `disabled`:
+//! if controller.x.pressed() {
+//!     check.run().show();
+//! }
+//! ```
+
+use alloc::{boxed::Box, format, string::String, vec, vec::Vec};
+use core::{
+    fmt::{self, Display, Formatter},
+    time::Duration,
+};
+
+use cstring_interop::with_cstring;
+
+use crate::{
+    battery::Battery,
+    bindings,
+    controller::ControllerId,
+    health::{check_port, HealthProblem},
+    io::println,
+    rtos::{time_since_start, Loop, Task},
+    smart_port::DeviceType,
+};
+
+/// The number of lines which fit on the brain screen in small text.
+const SCREEN_LINES: usize = 12;
+
+/// The size of the brain screen in pixels.
+const SCREEN_WIDTH: i16 = 480;
+const SCREEN_HEIGHT: i16 = 240;
+
+/// The interval between sensor readings while the motors spin.
+const SAMPLE_PERIOD: Duration = Duration::from_millis(20);
+
+type Check = Box<dyn FnMut() -> Option<String>>;
+
+type Reader = Box<dyn FnMut() -> Option<f64>>;
+
+enum Item {
+    Port(u8, DeviceType),
+    Motor(u8),
+    Sensor(u8, DeviceType, Reader),
+    Battery(f64),
+    Controller(bindings::controller_id_e_t),
+    Check(Check),
+}
+
+/// The result of checking one device in a [`SelfCheck`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SelfCheckResult {
+    /// The name of the device, as registered with the [`SelfCheck`].
+    pub device: String,
+    /// The problem found, or `None` if the device passed.
+    pub problem: Option<HealthProblem>,
+}
+
+impl SelfCheckResult {
+    #[inline]
+    /// Checks whether the device passed.
+    pub fn passed(&self) -> bool {
+        self.problem.is_none()
+    }
+}
+
+impl Display for SelfCheckResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.problem {
+            None => write!(f, "PASS {}", self.device),
+            Some(problem) => write!(f, "FAIL {}: {}", self.device, problem),
+        }
+    }
+}
+
+/// The results of a [`SelfCheck`], in the order in which the devices were
+/// registered.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SelfCheckReport {
+    /// The result for each device.
+    pub results: Vec<SelfCheckResult>,
+}
+
+impl SelfCheckReport {
+    #[inline]
+    /// Checks whether every device passed.
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(SelfCheckResult::passed)
+    }
+
+    /// Iterates over the devices which failed.
+    pub fn failures(&self) -> impl Iterator<Item = &SelfCheckResult> {
+        self.results.iter().filter(|result| !result.passed())
+    }
+
+    /// Prints the results to standard output and shows them on the brain
+    /// screen, failures first.
+    pub fn show(&self) {
+        let failures = self.failures().count();
+        let mut lines = Vec::from([format!(
+            "self-check: {}/{} passed",
+            self.results.len() - failures,
+            self.results.len()
+        )]);
+        lines.extend(self.failures().map(|result| format!("{}", result)));
+        lines.extend(
+            self.results
+                .iter()
+                .filter(|result| result.passed())
+                .map(|result| format!("{}", result)),
+        );
+        for line in &lines {
+            println!("{}", line);
+        }
+        unsafe { bindings::screen_erase_rect(0, 0, SCREEN_WIDTH - 1, SCREEN_HEIGHT - 1) };
+        for (i, line) in lines.iter().take(SCREEN_LINES).enumerate() {
+            with_cstring(line.clone(), |line| unsafe {
+                bindings::screen_print(
+                    bindings::text_format_e_t_E_TEXT_SMALL,
+                    i as i16,
+                    b"%s\0".as_ptr().cast(),
+                    line.into_raw(),
+                );
+            });
+        }
+    }
+}
+
+/// Describes the devices to be exercised by a self-check. Call
+/// [`SelfCheck::run()`] to perform the check.
+pub struct SelfCheck {
+    spin_time: Duration,
+    spin_voltage: i32,
+    items: Vec<(String, Item)>,
+}
+
+impl SelfCheck {
+    #[inline]
+    /// Creates a new self-check with no devices.
+    pub fn new() -> Self {
+        Self {
+            spin_time: Duration::from_millis(500),
+            spin_voltage: 4000,
+            items: Vec::new(),
+        }
+    }
+
+    /// Registers a device which should be plugged into the given port.
+    pub fn device(mut self, name: &str, port: u8, device_type: DeviceType) -> Self {
+        self.items
+            .push((name.into(), Item::Port(port, device_type)));
+        self
+    }
+
+    /// Registers a motor on the given port, which is spun briefly and must be
+    /// seen to move.
+    pub fn motor(mut self, name: &str, port: u8) -> Self {
+        self.items.push((name.into(), Item::Motor(port)));
+        self
+    }
+
+    /// Registers a sensor on the given port, whose reading must change while
+    /// the registered motors spin. `read` returns `None` if the sensor cannot
+    /// be read.
+    pub fn sensor(
+        mut self,
+        name: &str,
+        port: u8,
+        device_type: DeviceType,
+        read: impl FnMut() -> Option<f64> + 'static,
+    ) -> Self {
+        self.items
+            .push((name.into(), Item::Sensor(port, device_type, Box::new(read))));
+        self
+    }
+
+    /// Checks that the battery has at least the given capacity, in percent.
+    pub fn battery(mut self, min_capacity: f64) -> Self {
+        self.items
+            .push(("battery".into(), Item::Battery(min_capacity)));
+        self
+    }
+
+    /// Checks that the given controller is connected.
+    pub fn controller(mut self, id: ControllerId) -> Self {
+        let name = match id {
+            ControllerId::Master => "master controller",
+            ControllerId::Partner => "partner controller",
+        };
+        self.items.push((name.into(), Item::Controller(id.into())));
+        self
+    }
+
+    /// Registers a custom check, which returns a description of the problem
+    /// if one is found.
+    pub fn check(mut self, name: &str, f: impl FnMut() -> Option<String> + 'static) -> Self {
+        self.items.push((name.into(), Item::Check(Box::new(f))));
+        self
+    }
+
+    #[inline]
+    /// Sets how long the motors are spun for. The default is 500ms.
+    pub fn spin_time(mut self, time: Duration) -> Self {
+        self.spin_time = time;
+        self
+    }
+
+    #[inline]
+    /// Sets the voltage at which the motors are spun, in millivolts. The
+    /// default is 4000mV.
+    pub fn spin_voltage(mut self, voltage: i32) -> Self {
+        self.spin_voltage = voltage;
+        self
+    }
+
+    /// Performs the check, blocking while the motors spin.
+    pub fn run(&mut self) -> SelfCheckReport {
+        // Check that everything is plugged in before driving any motors.
+        let mut problems: Vec<Option<HealthProblem>> = self
+            .items
+            .iter_mut()
+            .map(|(_, item)| match item {
+                Item::Port(port, expected) | Item::Sensor(port, expected, _) => {
+                    check_port(*port, *expected)
+                }
+                Item::Motor(port) => check_port(*port, DeviceType::Motor),
+                Item::Battery(min) => match Battery::get_capacity() {
+                    Ok(capacity) if capacity >= *min => None,
+                    Ok(capacity) => Some(HealthProblem::Failed(format!("{:.0}%", capacity))),
+                    Err(_) => Some(HealthProblem::Failed("cannot read".into())),
+                },
+                Item::Controller(id) => match unsafe { bindings::controller_is_connected(*id) } {
+                    1 => None,
+                    _ => Some(HealthProblem::Disconnected),
+                },
+                Item::Check(f) => f().map(HealthProblem::Failed),
+            })
+            .collect();
+
+        // Spin the connected motors, watching the sensors for changes.
+        let mut start = Vec::with_capacity(self.items.len());
+        for ((_, item), problem) in self.items.iter_mut().zip(&mut problems) {
+            start.push(match (item, &problem) {
+                (Item::Motor(port), None) => unsafe {
+                    let position = bindings::motor_get_position(*port);
+                    bindings::motor_move_voltage(*port, self.spin_voltage);
+                    Some(position)
+                },
+                (Item::Sensor(_, _, read), None) => {
+                    let value = read();
+                    if value.is_none() {
+                        *problem = Some(HealthProblem::Failed("cannot read".into()));
+                    }
+                    value
+                }
+                _ => None,
+            });
+        }
+        let mut changed = vec![false; self.items.len()];
+        let begin = time_since_start();
+        let mut l = Loop::new(SAMPLE_PERIOD);
+        while time_since_start() - begin < self.spin_time {
+            l.delay();
+            for (((_, item), start), changed) in self.items.iter_mut().zip(&start).zip(&mut changed)
+            {
+                if let (Item::Sensor(_, _, read), Some(start)) = (item, start) {
+                    if matches!(read(), Some(value) if value != *start) {
+                        *changed = true;
+                    }
+                }
+            }
+        }
+        for (((_, item), start), problem) in self.items.iter().zip(&start).zip(&mut problems) {
+            if let (Item::Motor(port), Some(start)) = (item, start) {
+                let position = unsafe {
+                    bindings::motor_move_voltage(*port, 0);
+                    bindings::motor_get_position(*port)
+                };
+                if position == bindings::PROS_ERR_F_ || position == *start {
+                    *problem = Some(HealthProblem::Failed("did not move".into()));
+                }
+            }
+        }
+        for (((_, item), changed), problem) in self.items.iter().zip(&changed).zip(&mut problems) {
+            if let Item::Sensor(..) = item {
+                if problem.is_none() && !*changed {
+                    *problem = Some(HealthProblem::Failed("reading did not change".into()));
+                }
+            }
+        }
+        // Let the motors come to a stop before the robot is used.
+        Task::delay(Duration::from_millis(100));
+
+        SelfCheckReport {
+            results: self
+                .items
+                .iter()
+                .zip(problems)
+                .map(|((name, _), problem)| SelfCheckResult {
+                    device: name.clone(),
+                    problem,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Default for SelfCheck {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,13 +1,21 @@
 //! API for using smart ports as generic serial ports.
 
 use core::convert::TryInto;
+#[cfg(feature = "embedded-io")]
+use core::time::Duration;
 
+#[cfg(feature = "embedded-io")]
+use crate::rtos::Task;
 use crate::{
     bindings,
     error::{Error, SentinelError},
     smart_port::{smart_port_type, DeviceType},
 };
 
+/// How often a blocking read or write checks the buffers of the serial port.
+#[cfg(feature = "embedded-io")]
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
 /// Represents the generic serial interface of a smart port.
 pub struct Serial(u8);
 
@@ -103,3 +111,60 @@ impl Serial {
         Ok(())
     }
 }
+
+#[cfg(feature = "embedded-io")]
+impl embedded_io::ErrorType for Serial {
+    type Error = Error;
+}
+
+#[cfg(feature = "embedded-io")]
+impl embedded_io::Read for Serial {
+    /// Reads at least one byte into the given buffer, waiting for input if
+    /// none is available yet.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        loop {
+            match Serial::read(self, buf)? {
+                0 if !buf.is_empty() => Task::delay(POLL_INTERVAL),
+                n => return Ok(n),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl embedded_io::ReadReady for Serial {
+    #[inline]
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.get_read_avail()? > 0)
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl embedded_io::Write for Serial {
+    /// Writes at least one byte from the given buffer, waiting for space in the
+    /// output buffer if it is full.
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        loop {
+            match Serial::write(self, buf)? {
+                0 if !buf.is_empty() => Task::delay(POLL_INTERVAL),
+                n => return Ok(n),
+            }
+        }
+    }
+
+    /// Does nothing: bytes in the output buffer are sent without further
+    /// action. Note that this differs from [`Serial::flush()`], which discards
+    /// them.
+    #[inline]
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl embedded_io::WriteReady for Serial {
+    #[inline]
+    fn write_ready(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.get_write_free()? > 0)
+    }
+}
@@ -0,0 +1,1651 @@
+//! A host-side implementation of the subset of the PROS API used by this
+//! crate, with the same names and signatures as the generated bindings.
+//!
+//! Functions report errors as PROS does, by setting `errno` and returning a
+//! sentinel value.
+
+#![allow(clippy::all)]
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(missing_docs)]
+
+use alloc::{string::String, vec::Vec};
+use core::ptr;
+use std::ffi::{CStr, CString};
+
+use libc::{c_char, c_int, c_uint, c_void};
+
+use super::{
+    rtos, world, MotorCommand, SimAdi, SimButton, SimController, SimDevice, SimImage, SimImu,
+    SimLine, SimMotor, SimSerial, SimText, SimVisionObject,
+};
+use crate::vision::{VISION_FOV_HEIGHT, VISION_FOV_WIDTH};
+
+pub const COMPETITION_DISABLED: u32 = 1;
+pub const COMPETITION_AUTONOMOUS: u32 = 2;
+pub const COMPETITION_CONNECTED: u32 = 4;
+pub const INTERNAL_ADI_PORT: u32 = 22;
+pub const PROS_ERR_: i32 = i32::MAX;
+pub const PROS_ERR_F_: f64 = f64::INFINITY;
+pub const SERCTL_DISABLE_COBS: u32 = 15;
+pub const TASK_PRIORITY_MAX: u32 = 16;
+pub const TASK_PRIORITY_MIN: u32 = 1;
+pub const TASK_PRIORITY_DEFAULT: u32 = 8;
+pub const TASK_STACK_DEPTH_DEFAULT: u32 = 8192;
+
+pub type adi_port_config_e = c_uint;
+pub type adi_port_config_e_t = adi_port_config_e;
+pub const adi_port_config_e_E_ADI_ANALOG_IN: adi_port_config_e = 0;
+pub const adi_port_config_e_E_ADI_DIGITAL_IN: adi_port_config_e = 2;
+pub const adi_port_config_e_E_ADI_DIGITAL_OUT: adi_port_config_e = 3;
+pub const adi_port_config_e_E_ADI_LEGACY_GYRO: adi_port_config_e = 10;
+pub const adi_port_config_e_E_ADI_LEGACY_ENCODER: adi_port_config_e = 14;
+pub const adi_port_config_e_E_ADI_LEGACY_ULTRASONIC: adi_port_config_e = 15;
+
+pub type controller_id_e_t = c_uint;
+pub const controller_id_e_t_E_CONTROLLER_MASTER: controller_id_e_t = 0;
+pub const controller_id_e_t_E_CONTROLLER_PARTNER: controller_id_e_t = 1;
+
+pub type controller_analog_e_t = c_uint;
+pub const controller_analog_e_t_E_CONTROLLER_ANALOG_LEFT_X: controller_analog_e_t = 0;
+pub const controller_analog_e_t_E_CONTROLLER_ANALOG_LEFT_Y: controller_analog_e_t = 1;
+pub const controller_analog_e_t_E_CONTROLLER_ANALOG_RIGHT_X: controller_analog_e_t = 2;
+pub const controller_analog_e_t_E_CONTROLLER_ANALOG_RIGHT_Y: controller_analog_e_t = 3;
+
+pub type controller_digital_e_t = c_uint;
+pub const controller_digital_e_t_E_CONTROLLER_DIGITAL_L1: controller_digital_e_t = 6;
+pub const controller_digital_e_t_E_CONTROLLER_DIGITAL_L2: controller_digital_e_t = 7;
+pub const controller_digital_e_t_E_CONTROLLER_DIGITAL_R1: controller_digital_e_t = 8;
+pub const controller_digital_e_t_E_CONTROLLER_DIGITAL_R2: controller_digital_e_t = 9;
+pub const controller_digital_e_t_E_CONTROLLER_DIGITAL_UP: controller_digital_e_t = 10;
+pub const controller_digital_e_t_E_CONTROLLER_DIGITAL_DOWN: controller_digital_e_t = 11;
+pub const controller_digital_e_t_E_CONTROLLER_DIGITAL_LEFT: controller_digital_e_t = 12;
+pub const controller_digital_e_t_E_CONTROLLER_DIGITAL_RIGHT: controller_digital_e_t = 13;
+pub const controller_digital_e_t_E_CONTROLLER_DIGITAL_X: controller_digital_e_t = 14;
+pub const controller_digital_e_t_E_CONTROLLER_DIGITAL_B: controller_digital_e_t = 15;
+pub const controller_digital_e_t_E_CONTROLLER_DIGITAL_Y: controller_digital_e_t = 16;
+pub const controller_digital_e_t_E_CONTROLLER_DIGITAL_A: controller_digital_e_t = 17;
+
+pub type imu_status_e = c_uint;
+pub type imu_status_e_t = imu_status_e;
+pub const imu_status_e_E_IMU_STATUS_READY: imu_status_e = 0;
+pub const imu_status_e_E_IMU_STATUS_CALIBRATING: imu_status_e = 1;
+pub const imu_status_e_E_IMU_STATUS_ERROR: imu_status_e = 255;
+
+pub type motor_brake_mode_e = c_uint;
+pub type motor_brake_mode_e_t = motor_brake_mode_e;
+pub const motor_brake_mode_e_E_MOTOR_BRAKE_COAST: motor_brake_mode_e = 0;
+pub const motor_brake_mode_e_E_MOTOR_BRAKE_BRAKE: motor_brake_mode_e = 1;
+pub const motor_brake_mode_e_E_MOTOR_BRAKE_HOLD: motor_brake_mode_e = 2;
+pub const motor_brake_mode_e_E_MOTOR_BRAKE_INVALID: motor_brake_mode_e = i32::MAX as u32;
+
+pub type motor_encoder_units_e = c_uint;
+pub type motor_encoder_units_e_t = motor_encoder_units_e;
+pub const motor_encoder_units_e_E_MOTOR_ENCODER_DEGREES: motor_encoder_units_e = 0;
+pub const motor_encoder_units_e_E_MOTOR_ENCODER_ROTATIONS: motor_encoder_units_e = 1;
+pub const motor_encoder_units_e_E_MOTOR_ENCODER_COUNTS: motor_encoder_units_e = 2;
+pub const motor_encoder_units_e_E_MOTOR_ENCODER_INVALID: motor_encoder_units_e = i32::MAX as u32;
+
+pub type motor_gearset_e = c_uint;
+pub type motor_gearset_e_t = motor_gearset_e;
+pub const motor_gearset_e_E_MOTOR_GEARSET_36: motor_gearset_e = 0;
+pub const motor_gearset_e_E_MOTOR_GEARSET_18: motor_gearset_e = 1;
+pub const motor_gearset_e_E_MOTOR_GEARSET_06: motor_gearset_e = 2;
+pub const motor_gearset_e_E_MOTOR_GEARSET_INVALID: motor_gearset_e = i32::MAX as u32;
+
+pub type task_state_e_t = c_uint;
+pub const task_state_e_t_E_TASK_STATE_RUNNING: task_state_e_t = 0;
+pub const task_state_e_t_E_TASK_STATE_READY: task_state_e_t = 1;
+pub const task_state_e_t_E_TASK_STATE_BLOCKED: task_state_e_t = 2;
+pub const task_state_e_t_E_TASK_STATE_SUSPENDED: task_state_e_t = 3;
+pub const task_state_e_t_E_TASK_STATE_DELETED: task_state_e_t = 4;
+pub const task_state_e_t_E_TASK_STATE_INVALID: task_state_e_t = 5;
+
+pub type text_format_e_t = c_uint;
+pub const text_format_e_t_E_TEXT_SMALL: text_format_e_t = 0;
+
+pub type last_touch_e_t = c_uint;
+pub const last_touch_e_t_E_TOUCH_RELEASED: last_touch_e_t = 0;
+pub const last_touch_e_t_E_TOUCH_PRESSED: last_touch_e_t = 1;
+pub const last_touch_e_t_E_TOUCH_HELD: last_touch_e_t = 2;
+pub const last_touch_e_t_E_TOUCH_ERROR: last_touch_e_t = 3;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct screen_touch_status_s {
+    pub touch_status: last_touch_e_t,
+    pub x: i16,
+    pub y: i16,
+    pub press_count: i32,
+    pub release_count: i32,
+}
+pub type screen_touch_status_s_t = screen_touch_status_s;
+
+pub type v5_device_e = c_uint;
+pub type v5_device_e_t = v5_device_e;
+pub const v5_device_e_E_DEVICE_NONE: v5_device_e = 0;
+pub const v5_device_e_E_DEVICE_MOTOR: v5_device_e = 2;
+pub const v5_device_e_E_DEVICE_ROTATION: v5_device_e = 4;
+pub const v5_device_e_E_DEVICE_IMU: v5_device_e = 6;
+pub const v5_device_e_E_DEVICE_DISTANCE: v5_device_e = 7;
+pub const v5_device_e_E_DEVICE_RADIO: v5_device_e = 8;
+pub const v5_device_e_E_DEVICE_VISION: v5_device_e = 11;
+pub const v5_device_e_E_DEVICE_ADI: v5_device_e = 12;
+pub const v5_device_e_E_DEVICE_OPTICAL: v5_device_e = 16;
+pub const v5_device_e_E_DEVICE_GPS: v5_device_e = 20;
+pub const v5_device_e_E_DEVICE_GENERIC: v5_device_e = 129;
+pub const v5_device_e_E_DEVICE_UNDEFINED: v5_device_e = 255;
+
+pub type vision_object_type = c_uint;
+pub type vision_object_type_e_t = vision_object_type;
+pub const vision_object_type_E_VISION_OBJECT_NORMAL: vision_object_type = 0;
+
+pub type vision_zero = c_uint;
+pub type vision_zero_e_t = vision_zero;
+pub const vision_zero_E_VISION_ZERO_TOPLEFT: vision_zero = 0;
+pub const vision_zero_E_VISION_ZERO_CENTER: vision_zero = 1;
+
+pub type ext_adi_encoder_t = i32;
+pub type ext_adi_gyro_t = i32;
+pub type ext_adi_ultrasonic_t = i32;
+pub type mutex_t = *mut c_void;
+pub type sem_t = *mut c_void;
+pub type task_t = *mut c_void;
+pub type task_fn_t = Option<unsafe extern "C" fn(arg1: *mut c_void)>;
+pub type FILE = libc::FILE;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct euler_s {
+    pub pitch: f64,
+    pub roll: f64,
+    pub yaw: f64,
+}
+pub type euler_s_t = euler_s;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct quaternion_s {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub w: f64,
+}
+pub type quaternion_s_t = quaternion_s;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct imu_raw_s {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+pub type imu_gyro_s_t = imu_raw_s;
+pub type imu_accel_s_t = imu_raw_s;
+
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+pub struct gps_status_s {
+    pub x: f64,
+    pub y: f64,
+    pub pitch: f64,
+    pub roll: f64,
+    pub yaw: f64,
+}
+pub type gps_status_s_t = gps_status_s;
+
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+pub struct vision_object {
+    pub signature: u16,
+    pub type_: vision_object_type_e_t,
+    pub left_coord: i16,
+    pub top_coord: i16,
+    pub width: i16,
+    pub height: i16,
+    pub angle: u16,
+    pub x_middle_coord: i16,
+    pub y_middle_coord: i16,
+}
+pub type vision_object_s_t = vision_object;
+
+/// The object returned by the vision functions on failure.
+const VISION_OBJECT_ERR: vision_object_s_t = vision_object {
+    signature: 255,
+    type_: vision_object_type_E_VISION_OBJECT_NORMAL,
+    left_coord: 0,
+    top_coord: 0,
+    width: 0,
+    height: 0,
+    angle: 0,
+    x_middle_coord: 0,
+    y_middle_coord: 0,
+};
+
+/// The height of a line of text on the brain screen, in pixels.
+const LINE_HEIGHT: i16 = 20;
+
+pub unsafe fn __errno() -> *mut c_int {
+    #[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+    {
+        libc::__error()
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "ios", target_os = "freebsd")))]
+    {
+        libc::__errno_location()
+    }
+}
+
+pub(crate) unsafe fn set_errno(errno: c_int) {
+    *__errno() = errno;
+}
+
+/// Sets `errno` and returns the given sentinel value.
+fn fail<T>(errno: c_int, error: T) -> T {
+    unsafe { set_errno(errno) };
+    error
+}
+
+fn smart<D, T: Copy>(
+    port: u8,
+    error: T,
+    select: fn(&mut SimDevice) -> Option<&mut D>,
+    f: impl FnOnce(&mut D) -> T,
+) -> T {
+    let mut world = world();
+    let slot = match port
+        .checked_sub(1)
+        .and_then(|i| world.ports.get_mut(i as usize))
+    {
+        Some(slot) => slot,
+        None => return fail(libc::ENXIO, error),
+    };
+    match slot.as_mut().and_then(select) {
+        Some(device) => f(device),
+        None => fail(libc::ENODEV, error),
+    }
+}
+
+fn motor<T: Copy>(port: u8, error: T, f: impl FnOnce(&mut SimMotor) -> T) -> T {
+    smart(port, error, SimDevice::as_motor, |motor| {
+        motor.update(super::micros());
+        f(motor)
+    })
+}
+
+fn imu<T: Copy>(port: u8, error: T, f: impl FnOnce(&mut SimImu) -> T) -> T {
+    smart(port, error, SimDevice::as_imu, |imu| {
+        if imu.calibrating {
+            fail(libc::EAGAIN, error)
+        } else {
+            f(imu)
+        }
+    })
+}
+
+fn adi<T: Copy>(
+    smart_port: u8,
+    adi_port: u8,
+    error: T,
+    f: impl FnOnce(&mut SimAdi, usize) -> T,
+) -> T {
+    if !(1..=8).contains(&adi_port) {
+        return fail(libc::ENXIO, error);
+    }
+    let index = adi_port as usize - 1;
+    if smart_port == INTERNAL_ADI_PORT as u8 {
+        f(&mut world().internal_adi, index)
+    } else {
+        smart(smart_port, error, SimDevice::as_adi, |adi| f(adi, index))
+    }
+}
+
+/// Accesses an ADI port which must have been configured with the given type.
+fn adi_as<T: Copy>(
+    smart_port: u8,
+    adi_port: u8,
+    config: adi_port_config_e_t,
+    error: T,
+    f: impl FnOnce(&mut SimAdi, usize) -> T,
+) -> T {
+    adi(smart_port, adi_port, error, |adi, index| {
+        if adi.configs[index] == Some(config) {
+            f(adi, index)
+        } else {
+            fail(libc::EADDRINUSE, error)
+        }
+    })
+}
+
+/// Configures a pair of ADI ports for a legacy device, returning a handle.
+fn adi_pair(smart_port: u8, adi_port: u8, other: u8, config: adi_port_config_e_t) -> i32 {
+    if adi_port % 2 == 0 || other != adi_port + 1 {
+        return fail(libc::ENXIO, PROS_ERR_);
+    }
+    adi(smart_port, adi_port, PROS_ERR_, |adi, index| {
+        adi.configs[index] = Some(config);
+        adi.configs[index + 1] = Some(config);
+        adi.offsets[index] = adi.values[index];
+        handle(smart_port, adi_port)
+    })
+}
+
+fn handle(smart_port: u8, adi_port: u8) -> i32 {
+    (smart_port as i32) << 8 | adi_port as i32
+}
+
+fn from_handle(handle: i32) -> (u8, u8) {
+    ((handle >> 8) as u8, handle as u8)
+}
+
+fn controller<T: Copy>(
+    id: controller_id_e_t,
+    error: T,
+    f: impl FnOnce(&mut SimController) -> T,
+) -> T {
+    match world().controllers.get_mut(id as usize) {
+        Some(controller) => f(controller),
+        None => fail(libc::EINVAL, error),
+    }
+}
+
+unsafe fn string(text: *const c_char) -> String {
+    CStr::from_ptr(text).to_string_lossy().into_owned()
+}
+
+/// Draws text on the brain screen. Only the `"%s"` format, as used within this
+/// crate, is supported; any other format is drawn literally.
+unsafe fn draw(x: i16, y: i16, format: *const c_char, arg: *const c_char) -> u32 {
+    let text = match CStr::from_ptr(format).to_bytes() {
+        b"%s" => string(arg),
+        _ => string(format),
+    };
+    let mut world = world();
+    let screen = &mut world.screen;
+    let color = screen.pen;
+    screen.text.retain(|text| (text.x, text.y) != (x, y));
+    screen.text.push(SimText { x, y, color, text });
+    1
+}
+
+// Battery
+
+pub unsafe fn battery_get_capacity() -> f64 {
+    world().battery.capacity
+}
+
+pub unsafe fn battery_get_current() -> i32 {
+    world().battery.current
+}
+
+pub unsafe fn battery_get_voltage() -> i32 {
+    world().battery.voltage
+}
+
+// Competition
+
+pub unsafe fn competition_get_status() -> u8 {
+    let status = world().competition;
+    let mut bits = 0;
+    if status.disabled {
+        bits |= COMPETITION_DISABLED;
+    }
+    if status.autonomous {
+        bits |= COMPETITION_AUTONOMOUS;
+    }
+    if status.connected {
+        bits |= COMPETITION_CONNECTED;
+    }
+    bits as u8
+}
+
+// Controllers
+
+pub unsafe fn controller_clear(id: controller_id_e_t) -> i32 {
+    controller(id, PROS_ERR_, |controller| {
+        controller.lines = Default::default();
+        1
+    })
+}
+
+pub unsafe fn controller_clear_line(id: controller_id_e_t, line: u8) -> i32 {
+    controller(id, PROS_ERR_, |controller| {
+        match controller.lines.get_mut(line as usize) {
+            Some(text) => {
+                text.clear();
+                1
+            }
+            None => fail(libc::EINVAL, PROS_ERR_),
+        }
+    })
+}
+
+pub unsafe fn controller_get_analog(id: controller_id_e_t, channel: controller_analog_e_t) -> i32 {
+    controller(id, PROS_ERR_, |controller| match channel {
+        controller_analog_e_t_E_CONTROLLER_ANALOG_LEFT_X => controller.left.0 as i32,
+        controller_analog_e_t_E_CONTROLLER_ANALOG_LEFT_Y => controller.left.1 as i32,
+        controller_analog_e_t_E_CONTROLLER_ANALOG_RIGHT_X => controller.right.0 as i32,
+        controller_analog_e_t_E_CONTROLLER_ANALOG_RIGHT_Y => controller.right.1 as i32,
+        _ => fail(libc::EINVAL, PROS_ERR_),
+    })
+}
+
+pub unsafe fn controller_get_battery_capacity(id: controller_id_e_t) -> i32 {
+    controller(id, PROS_ERR_, |controller| controller.battery_capacity)
+}
+
+pub unsafe fn controller_get_battery_level(id: controller_id_e_t) -> i32 {
+    controller(id, PROS_ERR_, |controller| controller.battery_level)
+}
+
+pub unsafe fn controller_get_digital(id: controller_id_e_t, button: controller_digital_e_t) -> i32 {
+    controller(id, PROS_ERR_, |controller| {
+        match SimButton::from_raw(button) {
+            Some(button) => controller.pressed.contains(&button) as i32,
+            None => fail(libc::EINVAL, PROS_ERR_),
+        }
+    })
+}
+
+pub unsafe fn controller_is_connected(id: controller_id_e_t) -> i32 {
+    controller(id, PROS_ERR_, |controller| controller.connected as i32)
+}
+
+pub unsafe fn controller_rumble(id: controller_id_e_t, rumble_pattern: *const c_char) -> i32 {
+    let pattern = string(rumble_pattern);
+    controller(id, PROS_ERR_, |controller| {
+        controller.rumble = pattern;
+        1
+    })
+}
+
+pub unsafe fn controller_set_text(
+    id: controller_id_e_t,
+    line: u8,
+    col: u8,
+    str: *const c_char,
+) -> i32 {
+    let text = string(str);
+    controller(id, PROS_ERR_, |controller| {
+        match controller.lines.get_mut(line as usize) {
+            Some(line) => {
+                let mut chars: Vec<char> = line.chars().collect();
+                if chars.len() < col as usize {
+                    chars.resize(col as usize, ' ');
+                }
+                for (i, c) in text.chars().enumerate() {
+                    match chars.get_mut(col as usize + i) {
+                        Some(old) => *old = c,
+                        None => chars.push(c),
+                    }
+                }
+                *line = chars.into_iter().collect();
+                1
+            }
+            None => fail(libc::EINVAL, PROS_ERR_),
+        }
+    })
+}
+
+// Distance sensors
+
+pub unsafe fn distance_get(port: u8) -> i32 {
+    smart(port, PROS_ERR_, SimDevice::as_distance, |d| d.distance)
+}
+
+pub unsafe fn distance_get_confidence(port: u8) -> i32 {
+    smart(port, PROS_ERR_, SimDevice::as_distance, |d| d.confidence)
+}
+
+pub unsafe fn distance_get_object_size(port: u8) -> i32 {
+    smart(port, PROS_ERR_, SimDevice::as_distance, |d| d.object_size)
+}
+
+pub unsafe fn distance_get_object_velocity(port: u8) -> f64 {
+    smart(port, PROS_ERR_F_, SimDevice::as_distance, |d| {
+        d.object_velocity
+    })
+}
+
+// ADI
+
+pub unsafe fn ext_adi_port_set_config(
+    smart_port: u8,
+    adi_port: u8,
+    r#type: adi_port_config_e_t,
+) -> i32 {
+    adi(smart_port, adi_port, PROS_ERR_, |adi, index| {
+        adi.configs[index] = Some(r#type);
+        1
+    })
+}
+
+pub unsafe fn ext_adi_analog_calibrate(smart_port: u8, adi_port: u8) -> i32 {
+    let config = adi_port_config_e_E_ADI_ANALOG_IN;
+    adi_as(smart_port, adi_port, config, PROS_ERR_, |adi, index| {
+        adi.offsets[index] = adi.values[index];
+        adi.values[index]
+    })
+}
+
+pub unsafe fn ext_adi_analog_read(smart_port: u8, adi_port: u8) -> i32 {
+    let config = adi_port_config_e_E_ADI_ANALOG_IN;
+    adi_as(smart_port, adi_port, config, PROS_ERR_, |adi, index| {
+        adi.values[index]
+    })
+}
+
+pub unsafe fn ext_adi_analog_read_calibrated(smart_port: u8, adi_port: u8) -> i32 {
+    let config = adi_port_config_e_E_ADI_ANALOG_IN;
+    adi_as(smart_port, adi_port, config, PROS_ERR_, |adi, index| {
+        adi.values[index] - adi.offsets[index]
+    })
+}
+
+pub unsafe fn ext_adi_analog_read_calibrated_HR(smart_port: u8, adi_port: u8) -> i32 {
+    let config = adi_port_config_e_E_ADI_ANALOG_IN;
+    adi_as(smart_port, adi_port, config, PROS_ERR_, |adi, index| {
+        (adi.values[index] - adi.offsets[index]) * 16
+    })
+}
+
+pub unsafe fn ext_adi_digital_read(smart_port: u8, adi_port: u8) -> i32 {
+    let config = adi_port_config_e_E_ADI_DIGITAL_IN;
+    adi_as(smart_port, adi_port, config, PROS_ERR_, |adi, index| {
+        (adi.values[index] != 0) as i32
+    })
+}
+
+pub unsafe fn ext_adi_digital_write(smart_port: u8, adi_port: u8, value: bool) -> i32 {
+    let config = adi_port_config_e_E_ADI_DIGITAL_OUT;
+    adi_as(smart_port, adi_port, config, PROS_ERR_, |adi, index| {
+        adi.values[index] = value as i32;
+        1
+    })
+}
+
+pub unsafe fn ext_adi_encoder_init(
+    smart_port: u8,
+    adi_port_top: u8,
+    adi_port_bottom: u8,
+    _reverse: bool,
+) -> ext_adi_encoder_t {
+    let config = adi_port_config_e_E_ADI_LEGACY_ENCODER;
+    adi_pair(smart_port, adi_port_top, adi_port_bottom, config)
+}
+
+pub unsafe fn ext_adi_encoder_get(enc: ext_adi_encoder_t) -> i32 {
+    let (smart_port, adi_port) = from_handle(enc);
+    let config = adi_port_config_e_E_ADI_LEGACY_ENCODER;
+    adi_as(smart_port, adi_port, config, PROS_ERR_, |adi, index| {
+        adi.values[index] - adi.offsets[index]
+    })
+}
+
+pub unsafe fn ext_adi_encoder_reset(enc: ext_adi_encoder_t) -> i32 {
+    let (smart_port, adi_port) = from_handle(enc);
+    let config = adi_port_config_e_E_ADI_LEGACY_ENCODER;
+    adi_as(smart_port, adi_port, config, PROS_ERR_, |adi, index| {
+        adi.offsets[index] = adi.values[index];
+        1
+    })
+}
+
+pub unsafe fn ext_adi_encoder_shutdown(enc: ext_adi_encoder_t) -> i32 {
+    let (smart_port, adi_port) = from_handle(enc);
+    let config = adi_port_config_e_E_ADI_LEGACY_ENCODER;
+    adi_as(smart_port, adi_port, config, PROS_ERR_, |adi, index| {
+        adi.configs[index] = None;
+        adi.configs[index + 1] = None;
+        1
+    })
+}
+
+pub unsafe fn ext_adi_gyro_init(smart_port: u8, adi_port: u8, _multiplier: f64) -> ext_adi_gyro_t {
+    adi(smart_port, adi_port, PROS_ERR_, |adi, index| {
+        adi.configs[index] = Some(adi_port_config_e_E_ADI_LEGACY_GYRO);
+        adi.offsets[index] = adi.values[index];
+        handle(smart_port, adi_port)
+    })
+}
+
+pub unsafe fn ext_adi_gyro_get(gyro: ext_adi_gyro_t) -> f64 {
+    let (smart_port, adi_port) = from_handle(gyro);
+    let config = adi_port_config_e_E_ADI_LEGACY_GYRO;
+    adi_as(smart_port, adi_port, config, PROS_ERR_F_, |adi, index| {
+        (adi.values[index] - adi.offsets[index]) as f64
+    })
+}
+
+pub unsafe fn ext_adi_gyro_reset(gyro: ext_adi_gyro_t) -> i32 {
+    let (smart_port, adi_port) = from_handle(gyro);
+    let config = adi_port_config_e_E_ADI_LEGACY_GYRO;
+    adi_as(smart_port, adi_port, config, PROS_ERR_, |adi, index| {
+        adi.offsets[index] = adi.values[index];
+        1
+    })
+}
+
+pub unsafe fn ext_adi_gyro_shutdown(gyro: ext_adi_gyro_t) -> i32 {
+    let (smart_port, adi_port) = from_handle(gyro);
+    let config = adi_port_config_e_E_ADI_LEGACY_GYRO;
+    adi_as(smart_port, adi_port, config, PROS_ERR_, |adi, index| {
+        adi.configs[index] = None;
+        1
+    })
+}
+
+pub unsafe fn ext_adi_ultrasonic_init(
+    smart_port: u8,
+    adi_port_ping: u8,
+    adi_port_echo: u8,
+) -> ext_adi_ultrasonic_t {
+    let config = adi_port_config_e_E_ADI_LEGACY_ULTRASONIC;
+    adi_pair(smart_port, adi_port_ping, adi_port_echo, config)
+}
+
+pub unsafe fn ext_adi_ultrasonic_get(ult: ext_adi_ultrasonic_t) -> i32 {
+    let (smart_port, adi_port) = from_handle(ult);
+    let config = adi_port_config_e_E_ADI_LEGACY_ULTRASONIC;
+    adi_as(smart_port, adi_port, config, PROS_ERR_, |adi, index| {
+        adi.values[index]
+    })
+}
+
+pub unsafe fn ext_adi_ultrasonic_shutdown(ult: ext_adi_ultrasonic_t) -> i32 {
+    let (smart_port, adi_port) = from_handle(ult);
+    let config = adi_port_config_e_E_ADI_LEGACY_ULTRASONIC;
+    adi_as(smart_port, adi_port, config, PROS_ERR_, |adi, index| {
+        adi.configs[index] = None;
+        adi.configs[index + 1] = None;
+        1
+    })
+}
+
+// Files
+
+pub unsafe fn usd_is_installed() -> i32 {
+    world().sd_root.is_some() as i32
+}
+
+pub unsafe fn fopen(path: *const c_char, mode: *const c_char) -> *mut FILE {
+    let path = string(path);
+    let root = match (world().sd_root.clone(), path.strip_prefix("/usd/")) {
+        (Some(root), Some(relative)) => root.join(relative),
+        (None, Some(_)) => return fail(libc::ENXIO, ptr::null_mut()),
+        (_, None) => return fail(libc::ENOENT, ptr::null_mut()),
+    };
+    match CString::new(root.to_string_lossy().into_owned()) {
+        Ok(path) => libc::fopen(path.as_ptr(), mode),
+        Err(_) => fail(libc::EINVAL, ptr::null_mut()),
+    }
+}
+
+pub unsafe fn fclose(f: *mut FILE) -> c_int {
+    libc::fclose(f)
+}
+
+pub unsafe fn ferror(f: *mut FILE) -> c_int {
+    libc::ferror(f)
+}
+
+pub unsafe fn fflush(f: *mut FILE) -> c_int {
+    libc::fflush(f)
+}
+
+pub unsafe fn fread(p: *mut c_void, s: c_uint, n: c_uint, f: *mut FILE) -> c_uint {
+    libc::fread(p, s as usize, n as usize, f) as c_uint
+}
+
+pub unsafe fn fwrite(p: *const c_void, s: c_uint, n: c_uint, f: *mut FILE) -> c_uint {
+    libc::fwrite(p, s as usize, n as usize, f) as c_uint
+}
+
+// GPS sensors
+
+pub unsafe fn gps_initialize_full(
+    port: u8,
+    x_initial: f64,
+    y_initial: f64,
+    heading_initial: f64,
+    x_offset: f64,
+    y_offset: f64,
+) -> i32 {
+    smart(port, PROS_ERR_, SimDevice::as_gps, |gps| {
+        gps.x = x_initial;
+        gps.y = y_initial;
+        gps.rotation = heading_initial;
+        gps.offset = (x_offset, y_offset);
+        1
+    })
+}
+
+pub unsafe fn gps_set_offset(port: u8, x_offset: f64, y_offset: f64) -> i32 {
+    smart(port, PROS_ERR_, SimDevice::as_gps, |gps| {
+        gps.offset = (x_offset, y_offset);
+        1
+    })
+}
+
+pub unsafe fn gps_set_position(
+    port: u8,
+    x_initial: f64,
+    y_initial: f64,
+    heading_initial: f64,
+) -> i32 {
+    smart(port, PROS_ERR_, SimDevice::as_gps, |gps| {
+        gps.x = x_initial;
+        gps.y = y_initial;
+        gps.rotation = heading_initial;
+        1
+    })
+}
+
+pub unsafe fn gps_set_data_rate(port: u8, rate: u32) -> i32 {
+    smart(port, PROS_ERR_, SimDevice::as_gps, |gps| {
+        gps.data_rate = (rate / 5 * 5).max(5);
+        1
+    })
+}
+
+pub unsafe fn gps_get_error(port: u8) -> f64 {
+    smart(port, PROS_ERR_F_, SimDevice::as_gps, |gps| gps.error)
+}
+
+pub unsafe fn gps_get_status(port: u8) -> gps_status_s_t {
+    let error = gps_status_s {
+        x: PROS_ERR_F_,
+        y: PROS_ERR_F_,
+        pitch: PROS_ERR_F_,
+        roll: PROS_ERR_F_,
+        yaw: PROS_ERR_F_,
+    };
+    smart(port, error, SimDevice::as_gps, |gps| gps_status_s {
+        x: gps.x,
+        y: gps.y,
+        pitch: gps.pitch,
+        roll: gps.roll,
+        yaw: (gps.rotation + 180.0).rem_euclid(360.0) - 180.0,
+    })
+}
+
+pub unsafe fn gps_get_heading(port: u8) -> f64 {
+    smart(port, PROS_ERR_F_, SimDevice::as_gps, |gps| {
+        gps.rotation.rem_euclid(360.0)
+    })
+}
+
+pub unsafe fn gps_get_rotation(port: u8) -> f64 {
+    smart(port, PROS_ERR_F_, SimDevice::as_gps, |gps| gps.rotation)
+}
+
+pub unsafe fn gps_set_rotation(port: u8, target: f64) -> i32 {
+    smart(port, PROS_ERR_, SimDevice::as_gps, |gps| {
+        gps.rotation = target;
+        1
+    })
+}
+
+pub unsafe fn gps_tare_rotation(port: u8) -> i32 {
+    gps_set_rotation(port, 0.0)
+}
+
+// Inertial sensors
+
+pub unsafe fn imu_reset(port: u8) -> i32 {
+    imu(port, PROS_ERR_, |imu| {
+        imu.rotation = 0.0;
+        imu.pitch = 0.0;
+        imu.roll = 0.0;
+        1
+    })
+}
+
+pub unsafe fn imu_set_data_rate(port: u8, rate: u32) -> i32 {
+    imu(port, PROS_ERR_, |imu| {
+        imu.data_rate = (rate / 5 * 5).max(5);
+        1
+    })
+}
+
+pub unsafe fn imu_get_rotation(port: u8) -> f64 {
+    imu(port, PROS_ERR_F_, |imu| imu.rotation)
+}
+
+pub unsafe fn imu_get_heading(port: u8) -> f64 {
+    imu(port, PROS_ERR_F_, |imu| imu.heading())
+}
+
+pub unsafe fn imu_get_pitch(port: u8) -> f64 {
+    imu(port, PROS_ERR_F_, |imu| imu.pitch)
+}
+
+pub unsafe fn imu_get_roll(port: u8) -> f64 {
+    imu(port, PROS_ERR_F_, |imu| imu.roll)
+}
+
+pub unsafe fn imu_get_yaw(port: u8) -> f64 {
+    imu(port, PROS_ERR_F_, |imu| imu.yaw())
+}
+
+pub unsafe fn imu_get_euler(port: u8) -> euler_s_t {
+    let error = euler_s_t {
+        pitch: PROS_ERR_F_,
+        roll: PROS_ERR_F_,
+        yaw: PROS_ERR_F_,
+    };
+    imu(port, error, |imu| euler_s_t {
+        pitch: imu.pitch,
+        roll: imu.roll,
+        yaw: imu.yaw(),
+    })
+}
+
+pub unsafe fn imu_get_quaternion(port: u8) -> quaternion_s_t {
+    let error = quaternion_s_t {
+        x: PROS_ERR_F_,
+        y: PROS_ERR_F_,
+        z: PROS_ERR_F_,
+        w: PROS_ERR_F_,
+    };
+    imu(port, error, |imu| {
+        let (sr, cr) = (imu.roll.to_radians() / 2.0).sin_cos();
+        let (sp, cp) = (imu.pitch.to_radians() / 2.0).sin_cos();
+        let (sy, cy) = (imu.yaw().to_radians() / 2.0).sin_cos();
+        quaternion_s_t {
+            x: sr * cp * cy - cr * sp * sy,
+            y: cr * sp * cy + sr * cp * sy,
+            z: cr * cp * sy - sr * sp * cy,
+            w: cr * cp * cy + sr * sp * sy,
+        }
+    })
+}
+
+pub unsafe fn imu_get_gyro_rate(port: u8) -> imu_gyro_s_t {
+    let error = imu_raw_s {
+        x: PROS_ERR_F_,
+        y: PROS_ERR_F_,
+        z: PROS_ERR_F_,
+    };
+    imu(port, error, |imu| {
+        let [x, y, z] = imu.gyro_rate;
+        imu_raw_s { x, y, z }
+    })
+}
+
+pub unsafe fn imu_get_accel(port: u8) -> imu_accel_s_t {
+    let error = imu_raw_s {
+        x: PROS_ERR_F_,
+        y: PROS_ERR_F_,
+        z: PROS_ERR_F_,
+    };
+    imu(port, error, |imu| {
+        let [x, y, z] = imu.accel;
+        imu_raw_s { x, y, z }
+    })
+}
+
+pub unsafe fn imu_get_status(port: u8) -> imu_status_e_t {
+    let error = imu_status_e_E_IMU_STATUS_ERROR;
+    smart(port, error, SimDevice::as_imu, |imu| {
+        if imu.calibrating {
+            imu_status_e_E_IMU_STATUS_CALIBRATING
+        } else {
+            imu_status_e_E_IMU_STATUS_READY
+        }
+    })
+}
+
+pub unsafe fn imu_tare(port: u8) -> i32 {
+    imu_tare_euler(port)
+}
+
+pub unsafe fn imu_tare_euler(port: u8) -> i32 {
+    imu(port, PROS_ERR_, |imu| {
+        imu.rotation = 0.0;
+        imu.pitch = 0.0;
+        imu.roll = 0.0;
+        1
+    })
+}
+
+pub unsafe fn imu_tare_heading(port: u8) -> i32 {
+    imu_set_rotation(port, 0.0)
+}
+
+pub unsafe fn imu_tare_rotation(port: u8) -> i32 {
+    imu_set_rotation(port, 0.0)
+}
+
+pub unsafe fn imu_tare_yaw(port: u8) -> i32 {
+    imu_set_rotation(port, 0.0)
+}
+
+pub unsafe fn imu_tare_pitch(port: u8) -> i32 {
+    imu_set_pitch(port, 0.0)
+}
+
+pub unsafe fn imu_tare_roll(port: u8) -> i32 {
+    imu_set_roll(port, 0.0)
+}
+
+pub unsafe fn imu_set_euler(port: u8, target: euler_s_t) -> i32 {
+    imu(port, PROS_ERR_, |imu| {
+        imu.pitch = target.pitch;
+        imu.roll = target.roll;
+        imu.rotation = target.yaw;
+        1
+    })
+}
+
+pub unsafe fn imu_set_heading(port: u8, target: f64) -> i32 {
+    imu_set_rotation(port, target)
+}
+
+pub unsafe fn imu_set_yaw(port: u8, target: f64) -> i32 {
+    imu_set_rotation(port, target)
+}
+
+pub unsafe fn imu_set_rotation(port: u8, target: f64) -> i32 {
+    imu(port, PROS_ERR_, |imu| {
+        imu.rotation = target;
+        1
+    })
+}
+
+pub unsafe fn imu_set_pitch(port: u8, target: f64) -> i32 {
+    imu(port, PROS_ERR_, |imu| {
+        imu.pitch = target;
+        1
+    })
+}
+
+pub unsafe fn imu_set_roll(port: u8, target: f64) -> i32 {
+    imu(port, PROS_ERR_, |imu| {
+        imu.roll = target;
+        1
+    })
+}
+
+// LLEMU
+
+pub unsafe fn lcd_initialize() -> bool {
+    world().screen.lcd_initialized = true;
+    true
+}
+
+pub unsafe fn lcd_is_initialized() -> bool {
+    world().screen.lcd_initialized
+}
+
+pub unsafe fn lcd_set_text(line: i16, text: *const c_char) -> bool {
+    let text = string(text);
+    let mut world = world();
+    let screen = &mut world.screen;
+    if !screen.lcd_initialized {
+        return fail(libc::ENXIO, false);
+    }
+    match usize::try_from(line)
+        .ok()
+        .and_then(|line| screen.lcd.get_mut(line))
+    {
+        Some(line) => {
+            *line = text;
+            true
+        }
+        None => fail(libc::EINVAL, false),
+    }
+}
+
+// Motors
+
+pub unsafe fn motor_move(port: u8, voltage: i32) -> i32 {
+    motor_move_voltage(port, voltage.clamp(-127, 127) * 12000 / 127)
+}
+
+pub unsafe fn motor_move_voltage(port: u8, voltage: i32) -> i32 {
+    motor(port, PROS_ERR_, |motor| {
+        motor.command = MotorCommand::Voltage(voltage);
+        1
+    })
+}
+
+pub unsafe fn motor_move_velocity(port: u8, velocity: i32) -> i32 {
+    motor(port, PROS_ERR_, |motor| {
+        motor.command = MotorCommand::Velocity(velocity);
+        1
+    })
+}
+
+pub unsafe fn motor_move_absolute(port: u8, position: f64, velocity: i32) -> i32 {
+    motor(port, PROS_ERR_, |motor| {
+        motor.command = MotorCommand::Position(motor.to_degrees(position), velocity);
+        1
+    })
+}
+
+pub unsafe fn motor_move_relative(port: u8, position: f64, velocity: i32) -> i32 {
+    motor(port, PROS_ERR_, |motor| {
+        let target = motor.position + motor.to_degrees(position);
+        motor.command = MotorCommand::Position(target, velocity);
+        1
+    })
+}
+
+pub unsafe fn motor_modify_profiled_velocity(port: u8, velocity: i32) -> i32 {
+    motor(port, PROS_ERR_, |motor| {
+        if let MotorCommand::Position(target, _) = motor.command {
+            motor.command = MotorCommand::Position(target, velocity);
+        }
+        1
+    })
+}
+
+pub unsafe fn motor_get_actual_velocity(port: u8) -> f64 {
+    motor(port, PROS_ERR_F_, |motor| motor.velocity)
+}
+
+pub unsafe fn motor_get_brake_mode(port: u8) -> motor_brake_mode_e_t {
+    motor(port, motor_brake_mode_e_E_MOTOR_BRAKE_INVALID, |motor| {
+        motor.brake_mode
+    })
+}
+
+pub unsafe fn motor_get_current_draw(port: u8) -> i32 {
+    motor(port, PROS_ERR_, |motor| motor.current)
+}
+
+pub unsafe fn motor_get_current_limit(port: u8) -> i32 {
+    motor(port, PROS_ERR_, |motor| motor.current_limit)
+}
+
+pub unsafe fn motor_get_direction(port: u8) -> i32 {
+    motor(
+        port,
+        PROS_ERR_,
+        |motor| {
+            if motor.velocity < 0.0 {
+                -1
+            } else {
+                1
+            }
+        },
+    )
+}
+
+pub unsafe fn motor_get_efficiency(port: u8) -> f64 {
+    motor(port, PROS_ERR_F_, |motor| motor.efficiency)
+}
+
+pub unsafe fn motor_get_encoder_units(port: u8) -> motor_encoder_units_e_t {
+    motor(
+        port,
+        motor_encoder_units_e_E_MOTOR_ENCODER_INVALID,
+        |motor| motor.units,
+    )
+}
+
+pub unsafe fn motor_get_gearing(port: u8) -> motor_gearset_e_t {
+    motor(port, motor_gearset_e_E_MOTOR_GEARSET_INVALID, |motor| {
+        motor.gearset
+    })
+}
+
+pub unsafe fn motor_get_position(port: u8) -> f64 {
+    motor(port, PROS_ERR_F_, |motor| motor.to_units(motor.position))
+}
+
+pub unsafe fn motor_get_power(port: u8) -> f64 {
+    motor(port, PROS_ERR_F_, |motor| {
+        motor.voltage() as f64 * motor.current as f64 / 1_000_000.0
+    })
+}
+
+pub unsafe fn motor_get_target_position(port: u8) -> f64 {
+    motor(port, PROS_ERR_F_, |motor| match motor.command {
+        MotorCommand::Position(target, _) => motor.to_units(target),
+        _ => 0.0,
+    })
+}
+
+pub unsafe fn motor_get_target_velocity(port: u8) -> i32 {
+    motor(port, PROS_ERR_, |motor| match motor.command {
+        MotorCommand::Velocity(velocity) => velocity,
+        _ => 0,
+    })
+}
+
+pub unsafe fn motor_get_temperature(port: u8) -> f64 {
+    motor(port, PROS_ERR_F_, |motor| motor.temperature)
+}
+
+pub unsafe fn motor_get_torque(port: u8) -> f64 {
+    motor(port, PROS_ERR_F_, |motor| motor.torque)
+}
+
+pub unsafe fn motor_get_voltage(port: u8) -> i32 {
+    motor(port, PROS_ERR_, |motor| motor.voltage())
+}
+
+pub unsafe fn motor_get_voltage_limit(port: u8) -> i32 {
+    motor(port, PROS_ERR_, |motor| motor.voltage_limit)
+}
+
+pub unsafe fn motor_is_over_current(port: u8) -> i32 {
+    motor(port, PROS_ERR_, |motor| {
+        (motor.current >= motor.current_limit) as i32
+    })
+}
+
+pub unsafe fn motor_is_over_temp(port: u8) -> i32 {
+    motor(port, PROS_ERR_, |motor| (motor.temperature >= 55.0) as i32)
+}
+
+pub unsafe fn motor_is_reversed(port: u8) -> i32 {
+    motor(port, PROS_ERR_, |motor| motor.reversed as i32)
+}
+
+pub unsafe fn motor_set_brake_mode(port: u8, mode: motor_brake_mode_e_t) -> i32 {
+    motor(port, PROS_ERR_, |motor| {
+        motor.brake_mode = mode;
+        1
+    })
+}
+
+pub unsafe fn motor_set_current_limit(port: u8, limit: i32) -> i32 {
+    motor(port, PROS_ERR_, |motor| {
+        motor.current_limit = limit;
+        1
+    })
+}
+
+pub unsafe fn motor_set_encoder_units(port: u8, units: motor_encoder_units_e_t) -> i32 {
+    motor(port, PROS_ERR_, |motor| {
+        motor.units = units;
+        1
+    })
+}
+
+pub unsafe fn motor_set_gearing(port: u8, gearset: motor_gearset_e_t) -> i32 {
+    motor(port, PROS_ERR_, |motor| {
+        motor.gearset = gearset;
+        1
+    })
+}
+
+pub unsafe fn motor_set_reversed(port: u8, reverse: bool) -> i32 {
+    motor(port, PROS_ERR_, |motor| {
+        motor.reversed = reverse;
+        1
+    })
+}
+
+pub unsafe fn motor_set_voltage_limit(port: u8, limit: i32) -> i32 {
+    motor(port, PROS_ERR_, |motor| {
+        motor.voltage_limit = limit;
+        1
+    })
+}
+
+pub unsafe fn motor_set_zero_position(port: u8, position: f64) -> i32 {
+    motor(port, PROS_ERR_, |motor| {
+        motor.position -= motor.to_degrees(position);
+        1
+    })
+}
+
+pub unsafe fn motor_tare_position(port: u8) -> i32 {
+    motor(port, PROS_ERR_, |motor| {
+        motor.position = 0.0;
+        1
+    })
+}
+
+// Device registry
+
+pub unsafe fn registry_get_plugged_type(port: u8) -> v5_device_e_t {
+    match world().ports.get(port as usize) {
+        Some(Some(device)) => device.device_type(),
+        Some(None) => v5_device_e_E_DEVICE_NONE,
+        None => fail(libc::ENXIO, PROS_ERR_ as v5_device_e_t),
+    }
+}
+
+// Rotation sensors
+
+pub unsafe fn rotation_get_angle(port: u8) -> i32 {
+    smart(port, PROS_ERR_, SimDevice::as_rotation, |r| r.angle())
+}
+
+pub unsafe fn rotation_get_position(port: u8) -> i32 {
+    smart(port, PROS_ERR_, SimDevice::as_rotation, |r| r.position)
+}
+
+pub unsafe fn rotation_get_reversed(port: u8) -> i32 {
+    smart(port, PROS_ERR_, SimDevice::as_rotation, |r| {
+        r.reversed as i32
+    })
+}
+
+pub unsafe fn rotation_get_velocity(port: u8) -> i32 {
+    smart(port, PROS_ERR_, SimDevice::as_rotation, |r| r.velocity)
+}
+
+pub unsafe fn rotation_reset(port: u8) -> i32 {
+    smart(port, PROS_ERR_, SimDevice::as_rotation, |r| {
+        r.position = r.angle();
+        1
+    })
+}
+
+pub unsafe fn rotation_reset_position(port: u8) -> i32 {
+    rotation_set_position(port, 0)
+}
+
+pub unsafe fn rotation_reverse(port: u8) -> i32 {
+    smart(port, PROS_ERR_, SimDevice::as_rotation, |r| {
+        r.reversed = !r.reversed;
+        1
+    })
+}
+
+pub unsafe fn rotation_set_position(port: u8, position: u32) -> i32 {
+    smart(port, PROS_ERR_, SimDevice::as_rotation, |r| {
+        r.position = position as i32;
+        1
+    })
+}
+
+pub unsafe fn rotation_set_reversed(port: u8, value: bool) -> i32 {
+    smart(port, PROS_ERR_, SimDevice::as_rotation, |r| {
+        r.reversed = value;
+        1
+    })
+}
+
+// Screen
+
+pub unsafe fn screen_copy_area(
+    x0: i16,
+    y0: i16,
+    x1: i16,
+    y1: i16,
+    buf: *mut u32,
+    stride: i32,
+) -> u32 {
+    let (width, height) = (x1 - x0 + 1, y1 - y0 + 1);
+    let mut pixels = Vec::with_capacity(width as usize * height as usize);
+    for row in 0..height as usize {
+        let start = buf.add(row * stride as usize);
+        pixels.extend_from_slice(core::slice::from_raw_parts(start, width as usize));
+    }
+    let mut world = world();
+    let screen = &mut world.screen;
+    screen.images.retain(|image| (image.x, image.y) != (x0, y0));
+    screen.images.push(SimImage {
+        x: x0,
+        y: y0,
+        width,
+        height,
+        pixels,
+    });
+    1
+}
+
+pub unsafe fn screen_draw_line(x0: i16, y0: i16, x1: i16, y1: i16) -> u32 {
+    let mut world = world();
+    let color = world.screen.pen;
+    world.screen.lines.push(SimLine {
+        x0,
+        y0,
+        x1,
+        y1,
+        color,
+    });
+    1
+}
+
+pub unsafe fn screen_erase_rect(x0: i16, y0: i16, x1: i16, y1: i16) -> u32 {
+    let mut world = world();
+    let screen = &mut world.screen;
+    let inside = |x: i16, y: i16| (x0..=x1).contains(&x) && (y0..=y1).contains(&y);
+    screen.text.retain(|text| !inside(text.x, text.y));
+    screen.images.retain(|image| !inside(image.x, image.y));
+    screen
+        .lines
+        .retain(|line| !(inside(line.x0, line.y0) && inside(line.x1, line.y1)));
+    1
+}
+
+pub unsafe fn screen_get_pen() -> u32 {
+    world().screen.pen
+}
+
+pub unsafe fn screen_set_pen(color: u32) -> u32 {
+    world().screen.pen = color;
+    1
+}
+
+pub unsafe fn screen_print(
+    _txt_fmt: text_format_e_t,
+    line: i16,
+    text: *const c_char,
+    arg: *const c_char,
+) -> u32 {
+    draw(0, line * LINE_HEIGHT, text, arg)
+}
+
+pub unsafe fn screen_print_at(
+    _txt_fmt: text_format_e_t,
+    x: i16,
+    y: i16,
+    text: *const c_char,
+    arg: *const c_char,
+) -> u32 {
+    draw(x, y, text, arg)
+}
+
+pub unsafe fn screen_touch_status() -> screen_touch_status_s_t {
+    let world = world();
+    let (touch_status, (x, y)) = match world.screen.touch {
+        Some(point) => (last_touch_e_t_E_TOUCH_PRESSED, point),
+        None => (last_touch_e_t_E_TOUCH_RELEASED, (0, 0)),
+    };
+    screen_touch_status_s_t {
+        touch_status,
+        x,
+        y,
+        press_count: 0,
+        release_count: 0,
+    }
+}
+
+// Serial
+
+pub unsafe fn serctl(_action: u32, _extra_arg: *mut c_void) -> i32 {
+    0
+}
+
+pub unsafe fn serial_enable(port: u8) -> i32 {
+    let mut world = world();
+    match port
+        .checked_sub(1)
+        .and_then(|i| world.ports.get_mut(i as usize))
+    {
+        Some(slot @ None) => {
+            *slot = Some(SimDevice::Serial(SimSerial::default()));
+            1
+        }
+        Some(Some(SimDevice::Serial(_))) => 1,
+        Some(Some(_)) => fail(libc::EADDRINUSE, PROS_ERR_),
+        None => fail(libc::ENXIO, PROS_ERR_),
+    }
+}
+
+pub unsafe fn serial_set_baudrate(port: u8, baudrate: i32) -> i32 {
+    smart(port, PROS_ERR_, SimDevice::as_serial, |serial| {
+        serial.baudrate = baudrate;
+        1
+    })
+}
+
+pub unsafe fn serial_flush(port: u8) -> i32 {
+    smart(port, PROS_ERR_, SimDevice::as_serial, |serial| {
+        serial.input.clear();
+        1
+    })
+}
+
+pub unsafe fn serial_get_read_avail(port: u8) -> i32 {
+    smart(port, PROS_ERR_, SimDevice::as_serial, |serial| {
+        serial.input.len() as i32
+    })
+}
+
+pub unsafe fn serial_get_write_free(port: u8) -> i32 {
+    smart(port, PROS_ERR_, SimDevice::as_serial, |_| 1024)
+}
+
+pub unsafe fn serial_peek_byte(port: u8) -> i32 {
+    smart(port, PROS_ERR_, SimDevice::as_serial, |serial| {
+        serial.input.front().map_or(-1, |&byte| byte as i32)
+    })
+}
+
+pub unsafe fn serial_read_byte(port: u8) -> i32 {
+    smart(port, PROS_ERR_, SimDevice::as_serial, |serial| {
+        serial.input.pop_front().map_or(-1, |byte| byte as i32)
+    })
+}
+
+pub unsafe fn serial_read(port: u8, buffer: *mut u8, length: i32) -> i32 {
+    smart(port, PROS_ERR_, SimDevice::as_serial, |serial| {
+        let n = serial.input.len().min(length.max(0) as usize);
+        for (i, byte) in serial.input.drain(..n).enumerate() {
+            *buffer.add(i) = byte;
+        }
+        n as i32
+    })
+}
+
+pub unsafe fn serial_write(port: u8, buffer: *mut u8, length: i32) -> i32 {
+    let bytes = core::slice::from_raw_parts(buffer, length.max(0) as usize);
+    smart(port, PROS_ERR_, SimDevice::as_serial, |serial| {
+        serial.output.extend_from_slice(bytes);
+        length
+    })
+}
+
+pub unsafe fn serial_write_byte(port: u8, buffer: u8) -> i32 {
+    smart(port, PROS_ERR_, SimDevice::as_serial, |serial| {
+        serial.output.push(buffer);
+        1
+    })
+}
+
+// Vision sensors
+
+pub unsafe fn vision_get_object_count(port: u8) -> i32 {
+    smart(port, PROS_ERR_, SimDevice::as_vision, |vision| {
+        vision.objects.len() as i32
+    })
+}
+
+pub unsafe fn vision_get_by_size(port: u8, size_id: u32) -> vision_object_s_t {
+    vision_object(port, size_id, |_| true)
+}
+
+pub unsafe fn vision_get_by_sig(port: u8, size_id: u32, sig_id: u32) -> vision_object_s_t {
+    if !(1..=7).contains(&sig_id) {
+        return fail(libc::EINVAL, VISION_OBJECT_ERR);
+    }
+    vision_object(port, size_id, |object| object.signature as u32 == sig_id)
+}
+
+/// Gets the `size_id`th largest object matching `filter`.
+fn vision_object(
+    port: u8,
+    size_id: u32,
+    filter: impl Fn(&SimVisionObject) -> bool,
+) -> vision_object_s_t {
+    smart(port, VISION_OBJECT_ERR, SimDevice::as_vision, |vision| {
+        let mut objects: Vec<&SimVisionObject> =
+            vision.objects.iter().filter(|o| filter(o)).collect();
+        objects.sort_by_key(|o| core::cmp::Reverse(o.width as i32 * o.height as i32));
+        let object = match objects.get(size_id as usize) {
+            Some(object) => object,
+            None => return fail(libc::EDOM, VISION_OBJECT_ERR),
+        };
+        let (x0, y0) = if vision.centered {
+            (VISION_FOV_WIDTH / 2, VISION_FOV_HEIGHT / 2)
+        } else {
+            (0, 0)
+        };
+        vision_object {
+            signature: object.signature,
+            type_: vision_object_type_E_VISION_OBJECT_NORMAL,
+            left_coord: object.left - x0,
+            top_coord: object.top - y0,
+            width: object.width,
+            height: object.height,
+            angle: 0,
+            x_middle_coord: object.left + object.width / 2 - x0,
+            y_middle_coord: object.top + object.height / 2 - y0,
+        }
+    })
+}
+
+pub unsafe fn vision_set_zero_point(port: u8, zero_point: vision_zero_e_t) -> i32 {
+    smart(port, PROS_ERR_, SimDevice::as_vision, |vision| {
+        vision.centered = zero_point == vision_zero_E_VISION_ZERO_CENTER;
+        1
+    })
+}
+
+pub unsafe fn vision_get_exposure(port: u8) -> i32 {
+    smart(port, PROS_ERR_, SimDevice::as_vision, |vision| {
+        vision.exposure as i32
+    })
+}
+
+pub unsafe fn vision_set_exposure(port: u8, exposure: u8) -> i32 {
+    smart(port, PROS_ERR_, SimDevice::as_vision, |vision| {
+        vision.exposure = exposure.min(150);
+        1
+    })
+}
+
+pub unsafe fn vision_set_led(port: u8, rgb: i32) -> i32 {
+    smart(port, PROS_ERR_, SimDevice::as_vision, |vision| {
+        vision.led = Some(rgb as u32);
+        1
+    })
+}
+
+pub unsafe fn vision_clear_led(port: u8) -> i32 {
+    smart(port, PROS_ERR_, SimDevice::as_vision, |vision| {
+        vision.led = None;
+        1
+    })
+}
+
+// RTOS
+
+pub unsafe fn micros() -> u64 {
+    super::micros()
+}
+
+pub unsafe fn rtos_suspend_all() {
+    rtos::suspend_all()
+}
+
+pub unsafe fn rtos_resume_all() -> i32 {
+    rtos::resume_all();
+    0
+}
+
+pub unsafe fn task_create(
+    function: task_fn_t,
+    parameters: *mut c_void,
+    prio: u32,
+    _stack_depth: u16,
+    name: *const c_char,
+) -> task_t {
+    rtos::create(function, parameters, prio, name)
+}
+
+pub unsafe fn task_delay(milliseconds: u32) {
+    rtos::delay(milliseconds)
+}
+
+pub unsafe fn task_delete(task: task_t) {
+    rtos::delete(task)
+}
+
+pub unsafe fn task_get_by_name(name: *const c_char) -> task_t {
+    rtos::find(name)
+}
+
+pub unsafe fn task_get_count() -> u32 {
+    rtos::count()
+}
+
+pub unsafe fn task_get_current() -> task_t {
+    rtos::current_handle()
+}
+
+pub unsafe fn task_get_name(task: task_t) -> *mut c_char {
+    rtos::name(task)
+}
+
+pub unsafe fn task_get_priority(task: task_t) -> u32 {
+    rtos::priority(task)
+}
+
+pub unsafe fn task_get_state(task: task_t) -> task_state_e_t {
+    rtos::state(task)
+}
+
+pub unsafe fn task_notify(task: task_t) -> u32 {
+    rtos::notify(task)
+}
+
+pub unsafe fn task_notify_take(clear_on_exit: bool, timeout: u32) -> u32 {
+    rtos::notify_take(clear_on_exit, timeout)
+}
+
+pub unsafe fn task_set_priority(task: task_t, prio: u32) {
+    rtos::set_priority(task, prio)
+}
+
+/// Threads' stack use is not measured, so this reports the default stack
+/// depth.
+pub unsafe fn uxTaskGetStackHighWaterMark(_task: task_t) -> u32 {
+    TASK_STACK_DEPTH_DEFAULT
+}
+
+pub unsafe fn uxTaskGetSystemState(
+    status: *mut crate::rtos::TaskStatus,
+    size: u32,
+    _total_run_time: *mut u32,
+) -> u32 {
+    rtos::system_state(status, size)
+}
+
+pub unsafe fn mutex_recursive_create() -> mutex_t {
+    rtos::mutex_create()
+}
+
+pub unsafe fn mutex_delete(mutex: mutex_t) {
+    rtos::mutex_delete(mutex)
+}
+
+pub unsafe fn mutex_recursive_take(mutex: mutex_t, timeout: u32) -> bool {
+    rtos::mutex_take(mutex, timeout)
+}
+
+pub unsafe fn mutex_recursive_give(mutex: mutex_t) -> bool {
+    rtos::mutex_give(mutex)
+}
+
+pub unsafe fn sem_create(max_count: u32, init_count: u32) -> sem_t {
+    rtos::sem_create(max_count, init_count)
+}
+
+pub unsafe fn sem_delete(sem: sem_t) {
+    rtos::sem_delete(sem)
+}
+
+pub unsafe fn sem_get_count(sem: sem_t) -> u32 {
+    rtos::sem_get_count(sem)
+}
+
+pub unsafe fn sem_post(sem: sem_t) -> bool {
+    rtos::sem_post(sem)
+}
+
+pub unsafe fn sem_wait(sem: sem_t, timeout: u32) -> bool {
+    rtos::sem_wait(sem, timeout)
+}
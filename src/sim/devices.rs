@@ -0,0 +1,588 @@
+use alloc::{collections::VecDeque, string::String, vec::Vec};
+
+use crate::{bindings, smart_port::DeviceType};
+
+/// A device plugged into a smart port.
+#[derive(Clone, Debug)]
+pub enum SimDevice {
+    /// A V5 Smart Motor.
+    Motor(SimMotor),
+    /// A V5 Rotation Sensor.
+    Rotation(SimRotation),
+    /// A V5 Distance Sensor.
+    Distance(SimDistance),
+    /// A V5 Inertial Sensor.
+    Imu(SimImu),
+    /// A V5 GPS Sensor.
+    Gps(SimGps),
+    /// A V5 Vision Sensor.
+    Vision(SimVision),
+    /// A V5 3-Wire Expander.
+    Adi(SimAdi),
+    /// A smart port in generic serial mode.
+    Serial(SimSerial),
+    /// A device which is detected but cannot be used, such as a radio.
+    Other(DeviceType),
+}
+
+impl SimDevice {
+    pub(crate) fn device_type(&self) -> bindings::v5_device_e_t {
+        match self {
+            SimDevice::Motor(_) => bindings::v5_device_e_E_DEVICE_MOTOR,
+            SimDevice::Rotation(_) => bindings::v5_device_e_E_DEVICE_ROTATION,
+            SimDevice::Distance(_) => bindings::v5_device_e_E_DEVICE_DISTANCE,
+            SimDevice::Imu(_) => bindings::v5_device_e_E_DEVICE_IMU,
+            SimDevice::Gps(_) => bindings::v5_device_e_E_DEVICE_GPS,
+            SimDevice::Vision(_) => bindings::v5_device_e_E_DEVICE_VISION,
+            SimDevice::Adi(_) => bindings::v5_device_e_E_DEVICE_ADI,
+            SimDevice::Serial(_) => bindings::v5_device_e_E_DEVICE_GENERIC,
+            SimDevice::Other(device_type) => match device_type {
+                DeviceType::None => bindings::v5_device_e_E_DEVICE_NONE,
+                DeviceType::Motor => bindings::v5_device_e_E_DEVICE_MOTOR,
+                DeviceType::Rotation => bindings::v5_device_e_E_DEVICE_ROTATION,
+                DeviceType::Imu => bindings::v5_device_e_E_DEVICE_IMU,
+                DeviceType::Distance => bindings::v5_device_e_E_DEVICE_DISTANCE,
+                DeviceType::Radio => bindings::v5_device_e_E_DEVICE_RADIO,
+                DeviceType::Vision => bindings::v5_device_e_E_DEVICE_VISION,
+                DeviceType::Adi => bindings::v5_device_e_E_DEVICE_ADI,
+                DeviceType::Optical => bindings::v5_device_e_E_DEVICE_OPTICAL,
+                DeviceType::Gps => bindings::v5_device_e_E_DEVICE_GPS,
+                DeviceType::Serial => bindings::v5_device_e_E_DEVICE_GENERIC,
+                DeviceType::Undefined => bindings::v5_device_e_E_DEVICE_UNDEFINED,
+                DeviceType::Unknown(t) => *t,
+            },
+        }
+    }
+
+    pub(crate) fn as_motor(&mut self) -> Option<&mut SimMotor> {
+        match self {
+            SimDevice::Motor(motor) => Some(motor),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_rotation(&mut self) -> Option<&mut SimRotation> {
+        match self {
+            SimDevice::Rotation(rotation) => Some(rotation),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_distance(&mut self) -> Option<&mut SimDistance> {
+        match self {
+            SimDevice::Distance(distance) => Some(distance),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_imu(&mut self) -> Option<&mut SimImu> {
+        match self {
+            SimDevice::Imu(imu) => Some(imu),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_gps(&mut self) -> Option<&mut SimGps> {
+        match self {
+            SimDevice::Gps(gps) => Some(gps),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_vision(&mut self) -> Option<&mut SimVision> {
+        match self {
+            SimDevice::Vision(vision) => Some(vision),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_adi(&mut self) -> Option<&mut SimAdi> {
+        match self {
+            SimDevice::Adi(adi) => Some(adi),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_serial(&mut self) -> Option<&mut SimSerial> {
+        match self {
+            SimDevice::Serial(serial) => Some(serial),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum MotorCommand {
+    /// Output voltage, in millivolts.
+    Voltage(i32),
+    /// Target velocity, in RPM.
+    Velocity(i32),
+    /// Target position, in degrees, and maximum velocity, in RPM.
+    Position(f64, i32),
+}
+
+/// A simulated V5 Smart Motor. The motor reaches the velocity it is commanded
+/// immediately, up to the maximum velocity of its gearset, and positions and
+/// velocities are in the direction set by the program.
+#[derive(Clone, Debug)]
+pub struct SimMotor {
+    /// The position, in degrees.
+    pub position: f64,
+    /// The velocity, in RPM.
+    pub velocity: f64,
+    /// Whether the motor is stalled (e.g., against a hard stop). A stalled
+    /// motor does not move, whatever it is commanded.
+    pub stalled: bool,
+    /// The current draw, in milliamps.
+    pub current: i32,
+    /// The temperature, in degrees Celsius.
+    pub temperature: f64,
+    /// The torque, in newton-metres.
+    pub torque: f64,
+    /// The efficiency, as a percentage.
+    pub efficiency: f64,
+    pub(crate) command: MotorCommand,
+    pub(crate) gearset: bindings::motor_gearset_e_t,
+    pub(crate) units: bindings::motor_encoder_units_e_t,
+    pub(crate) brake_mode: bindings::motor_brake_mode_e_t,
+    pub(crate) reversed: bool,
+    pub(crate) current_limit: i32,
+    pub(crate) voltage_limit: i32,
+    updated: Option<u64>,
+}
+
+impl SimMotor {
+    /// Gets the voltage applied to the motor, in millivolts.
+    pub fn voltage(&self) -> i32 {
+        match self.command {
+            MotorCommand::Voltage(voltage) => match self.voltage_limit {
+                0 => voltage.clamp(-12000, 12000),
+                limit => voltage.clamp(-limit, limit),
+            },
+            _ => (self.velocity / self.max_velocity() * 12000.0) as i32,
+        }
+    }
+
+    /// Gets the velocity which the motor is commanded to reach, in RPM.
+    pub fn target_velocity(&self) -> f64 {
+        match self.command {
+            MotorCommand::Voltage(_) => self.voltage() as f64 / 12000.0 * self.max_velocity(),
+            MotorCommand::Velocity(velocity) => velocity as f64,
+            MotorCommand::Position(target, velocity) => {
+                if target > self.position {
+                    velocity.abs() as f64
+                } else if target < self.position {
+                    -velocity.abs() as f64
+                } else {
+                    0.0
+                }
+            }
+        }
+        .clamp(-self.max_velocity(), self.max_velocity())
+    }
+
+    #[inline]
+    /// Checks whether the program has reversed the motor.
+    pub fn is_reversed(&self) -> bool {
+        self.reversed
+    }
+
+    pub(crate) fn max_velocity(&self) -> f64 {
+        match self.gearset {
+            bindings::motor_gearset_e_E_MOTOR_GEARSET_06 => 600.0,
+            bindings::motor_gearset_e_E_MOTOR_GEARSET_36 => 100.0,
+            _ => 200.0,
+        }
+    }
+
+    /// Converts a position in degrees to the motor's encoder units.
+    pub(crate) fn to_units(&self, degrees: f64) -> f64 {
+        match self.units {
+            bindings::motor_encoder_units_e_E_MOTOR_ENCODER_ROTATIONS => degrees / 360.0,
+            bindings::motor_encoder_units_e_E_MOTOR_ENCODER_COUNTS => {
+                degrees / 360.0 * (180000.0 / self.max_velocity())
+            }
+            _ => degrees,
+        }
+    }
+
+    /// Converts a position in the motor's encoder units to degrees.
+    pub(crate) fn to_degrees(&self, position: f64) -> f64 {
+        position / self.to_units(1.0)
+    }
+
+    /// Advances the motor to the given time, in microseconds.
+    pub(crate) fn update(&mut self, now: u64) {
+        let dt = match self.updated.replace(now) {
+            Some(updated) => now.saturating_sub(updated) as f64 / 1_000_000.0,
+            None => 0.0,
+        };
+        self.velocity = if self.stalled {
+            0.0
+        } else {
+            self.target_velocity()
+        };
+        let step = self.velocity * 6.0 * dt;
+        match self.command {
+            MotorCommand::Position(target, _) if (target - self.position).abs() <= step.abs() => {
+                self.position = target;
+                self.velocity = 0.0;
+            }
+            _ => self.position += step,
+        }
+    }
+}
+
+impl Default for SimMotor {
+    fn default() -> Self {
+        Self {
+            position: 0.0,
+            velocity: 0.0,
+            stalled: false,
+            current: 0,
+            temperature: 25.0,
+            torque: 0.0,
+            efficiency: 0.0,
+            command: MotorCommand::Voltage(0),
+            gearset: bindings::motor_gearset_e_E_MOTOR_GEARSET_18,
+            units: bindings::motor_encoder_units_e_E_MOTOR_ENCODER_DEGREES,
+            brake_mode: bindings::motor_brake_mode_e_E_MOTOR_BRAKE_COAST,
+            reversed: false,
+            current_limit: 2500,
+            voltage_limit: 0,
+            updated: None,
+        }
+    }
+}
+
+/// A simulated V5 Rotation Sensor.
+#[derive(Clone, Debug, Default)]
+pub struct SimRotation {
+    /// The position, in centidegrees.
+    pub position: i32,
+    /// The velocity, in centidegrees per second.
+    pub velocity: i32,
+    pub(crate) reversed: bool,
+}
+
+impl SimRotation {
+    #[inline]
+    /// Gets the angle, in centidegrees from 0 to 35999.
+    pub fn angle(&self) -> i32 {
+        self.position.rem_euclid(36000)
+    }
+
+    #[inline]
+    /// Checks whether the program has reversed the sensor.
+    pub fn is_reversed(&self) -> bool {
+        self.reversed
+    }
+}
+
+/// A simulated V5 Distance Sensor.
+#[derive(Clone, Debug)]
+pub struct SimDistance {
+    /// The distance to the object, in millimetres.
+    pub distance: i32,
+    /// The confidence in the distance, from 0 to 63.
+    pub confidence: i32,
+    /// The apparent size of the object, from 0 to 400.
+    pub object_size: i32,
+    /// The velocity of the object, in metres per second.
+    pub object_velocity: f64,
+}
+
+impl Default for SimDistance {
+    fn default() -> Self {
+        Self {
+            distance: 9999,
+            confidence: 0,
+            object_size: 0,
+            object_velocity: 0.0,
+        }
+    }
+}
+
+/// A simulated V5 Inertial Sensor.
+#[derive(Clone, Debug, Default)]
+pub struct SimImu {
+    /// The total rotation, in degrees, which is not bounded.
+    pub rotation: f64,
+    /// The pitch, in degrees.
+    pub pitch: f64,
+    /// The roll, in degrees.
+    pub roll: f64,
+    /// The rotation rates about the x, y and z axes, in degrees per second.
+    pub gyro_rate: [f64; 3],
+    /// The accelerations along the x, y and z axes, in g.
+    pub accel: [f64; 3],
+    /// Whether the sensor is calibrating. Readings fail while it is.
+    pub calibrating: bool,
+    /// The data refresh interval in milliseconds set by the program, or 0 if
+    /// none has been set.
+    pub data_rate: u32,
+}
+
+impl SimImu {
+    #[inline]
+    /// Gets the heading, in degrees from 0 to 360.
+    pub fn heading(&self) -> f64 {
+        self.rotation.rem_euclid(360.0)
+    }
+
+    #[inline]
+    /// Gets the yaw, in degrees from -180 to 180.
+    pub fn yaw(&self) -> f64 {
+        (self.rotation + 180.0).rem_euclid(360.0) - 180.0
+    }
+}
+
+/// A simulated V5 GPS Sensor. Positions are in metres from the centre of the
+/// field.
+#[derive(Clone, Debug, Default)]
+pub struct SimGps {
+    /// The x position of the robot.
+    pub x: f64,
+    /// The y position of the robot.
+    pub y: f64,
+    /// The total rotation of the robot, in degrees, which is not bounded.
+    pub rotation: f64,
+    /// The pitch, in degrees.
+    pub pitch: f64,
+    /// The roll, in degrees.
+    pub roll: f64,
+    /// The root mean square error of the position, in metres.
+    pub error: f64,
+    /// The offset of the sensor from the centre of the robot set by the
+    /// program.
+    pub offset: (f64, f64),
+    /// The data refresh interval in milliseconds set by the program, or 0 if
+    /// none has been set.
+    pub data_rate: u32,
+}
+
+/// A simulated V5 Vision Sensor.
+#[derive(Clone, Debug, Default)]
+pub struct SimVision {
+    /// The objects in view, in any order.
+    pub objects: Vec<SimVisionObject>,
+    /// Whether the program has set the zero point to the centre of the field
+    /// of view.
+    pub centered: bool,
+    /// The exposure setting set by the program.
+    pub exposure: u8,
+    /// The LED colour set by the program, as a 24-bit RGB value.
+    pub led: Option<u32>,
+}
+
+/// An object in view of a simulated vision sensor.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SimVisionObject {
+    /// The signature of the object, from 1 to 7.
+    pub signature: u16,
+    /// The left edge of the object, in pixels from the left of the field of
+    /// view.
+    pub left: i16,
+    /// The top edge of the object, in pixels from the top of the field of
+    /// view.
+    pub top: i16,
+    /// The width of the object, in pixels.
+    pub width: i16,
+    /// The height of the object, in pixels.
+    pub height: i16,
+}
+
+/// A simulated set of 8 ADI ports, on the brain or a 3-wire expander.
+#[derive(Clone, Debug, Default)]
+pub struct SimAdi {
+    /// The value on each port, from A to H: from 0 to 4095 for analog inputs,
+    /// 0 or 1 for digital inputs and outputs, the count for the top port of an
+    /// encoder, the distance in millimetres for the output port of an
+    /// ultrasonic sensor, and the angle in tenths of a degree for a gyro.
+    pub values: [i32; 8],
+    pub(crate) configs: [Option<bindings::adi_port_config_e_t>; 8],
+    pub(crate) offsets: [i32; 8],
+}
+
+/// A simulated device in generic serial mode.
+#[derive(Clone, Debug, Default)]
+pub struct SimSerial {
+    /// The bytes waiting to be read by the program.
+    pub input: VecDeque<u8>,
+    /// The bytes written by the program.
+    pub output: Vec<u8>,
+    /// The baud rate set by the program.
+    pub baudrate: i32,
+}
+
+/// A simulated controller.
+#[derive(Clone, Debug)]
+pub struct SimController {
+    /// Whether the controller is connected.
+    pub connected: bool,
+    /// The position of the left joystick, from -127 to 127.
+    pub left: (i8, i8),
+    /// The position of the right joystick, from -127 to 127.
+    pub right: (i8, i8),
+    /// The buttons which are pressed.
+    pub pressed: Vec<SimButton>,
+    /// The battery level.
+    pub battery_level: i32,
+    /// The battery capacity.
+    pub battery_capacity: i32,
+    /// The text shown on each line of the controller screen.
+    pub lines: [String; 3],
+    /// The last rumble pattern sent to the controller.
+    pub rumble: String,
+}
+
+impl Default for SimController {
+    fn default() -> Self {
+        Self {
+            connected: false,
+            left: (0, 0),
+            right: (0, 0),
+            pressed: Vec::new(),
+            battery_level: 100,
+            battery_capacity: 100,
+            lines: Default::default(),
+            rumble: String::new(),
+        }
+    }
+}
+
+/// A button on a [`SimController`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum SimButton {
+    L1,
+    L2,
+    R1,
+    R2,
+    Up,
+    Down,
+    Left,
+    Right,
+    X,
+    Y,
+    A,
+    B,
+}
+
+impl SimButton {
+    pub(crate) fn from_raw(button: bindings::controller_digital_e_t) -> Option<Self> {
+        Some(match button {
+            bindings::controller_digital_e_t_E_CONTROLLER_DIGITAL_L1 => Self::L1,
+            bindings::controller_digital_e_t_E_CONTROLLER_DIGITAL_L2 => Self::L2,
+            bindings::controller_digital_e_t_E_CONTROLLER_DIGITAL_R1 => Self::R1,
+            bindings::controller_digital_e_t_E_CONTROLLER_DIGITAL_R2 => Self::R2,
+            bindings::controller_digital_e_t_E_CONTROLLER_DIGITAL_UP => Self::Up,
+            bindings::controller_digital_e_t_E_CONTROLLER_DIGITAL_DOWN => Self::Down,
+            bindings::controller_digital_e_t_E_CONTROLLER_DIGITAL_LEFT => Self::Left,
+            bindings::controller_digital_e_t_E_CONTROLLER_DIGITAL_RIGHT => Self::Right,
+            bindings::controller_digital_e_t_E_CONTROLLER_DIGITAL_X => Self::X,
+            bindings::controller_digital_e_t_E_CONTROLLER_DIGITAL_Y => Self::Y,
+            bindings::controller_digital_e_t_E_CONTROLLER_DIGITAL_A => Self::A,
+            bindings::controller_digital_e_t_E_CONTROLLER_DIGITAL_B => Self::B,
+            _ => return None,
+        })
+    }
+}
+
+/// The simulated battery.
+#[derive(Clone, Debug)]
+pub struct SimBattery {
+    /// The voltage, in millivolts.
+    pub voltage: i32,
+    /// The current, in milliamps.
+    pub current: i32,
+    /// The remaining capacity, as a percentage.
+    pub capacity: f64,
+}
+
+impl Default for SimBattery {
+    fn default() -> Self {
+        Self {
+            voltage: 12800,
+            current: 0,
+            capacity: 100.0,
+        }
+    }
+}
+
+/// The simulated brain screen, which records text and images rather than
+/// pixels.
+#[derive(Clone, Debug)]
+pub struct SimScreen {
+    /// The text drawn on the screen, in the order it was drawn. Text drawn at
+    /// the same position as earlier text replaces it.
+    pub text: Vec<SimText>,
+    /// The images drawn on the screen, in the order they were drawn. An image
+    /// drawn at the same position as an earlier image replaces it.
+    pub images: Vec<SimImage>,
+    /// The lines drawn on the screen, in the order they were drawn.
+    pub lines: Vec<SimLine>,
+    /// The lines of the LLEMU emulated LCD.
+    pub lcd: [String; 8],
+    /// The point being touched, if any.
+    pub touch: Option<(i16, i16)>,
+    pub(crate) pen: u32,
+    pub(crate) lcd_initialized: bool,
+}
+
+impl Default for SimScreen {
+    fn default() -> Self {
+        Self {
+            text: Vec::new(),
+            images: Vec::new(),
+            lines: Vec::new(),
+            lcd: Default::default(),
+            touch: None,
+            pen: 0x00FF_FFFF,
+            lcd_initialized: false,
+        }
+    }
+}
+
+/// Text drawn on the [`SimScreen`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SimText {
+    /// The x-coordinate of the top-left corner, in pixels.
+    pub x: i16,
+    /// The y-coordinate of the top-left corner, in pixels.
+    pub y: i16,
+    /// The colour, as `0xRRGGBB`.
+    pub color: u32,
+    /// The text.
+    pub text: String,
+}
+
+/// An image drawn on the [`SimScreen`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SimImage {
+    /// The x-coordinate of the top-left corner, in pixels.
+    pub x: i16,
+    /// The y-coordinate of the top-left corner, in pixels.
+    pub y: i16,
+    /// The width, in pixels.
+    pub width: i16,
+    /// The height, in pixels.
+    pub height: i16,
+    /// The colour of each pixel, as `0xRRGGBB`, row by row.
+    pub pixels: Vec<u32>,
+}
+
+/// A line drawn on the [`SimScreen`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SimLine {
+    /// The x-coordinate of the start of the line, in pixels.
+    pub x0: i16,
+    /// The y-coordinate of the start of the line, in pixels.
+    pub y0: i16,
+    /// The x-coordinate of the end of the line, in pixels.
+    pub x1: i16,
+    /// The y-coordinate of the end of the line, in pixels.
+    pub y1: i16,
+    /// The colour, as `0xRRGGBB`.
+    pub color: u32,
+}
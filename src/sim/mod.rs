@@ -0,0 +1,259 @@
+//! # Host-Side Simulation.
+//!
+//! With the `sim` feature enabled, the PROS bindings are replaced by a
+//! pure-Rust implementation on top of the standard library, so that robot
+//! logic and state machines can be run and unit-tested on a desktop without a
+//! V5 brain:
+//!
+//! ```text
+//! cargo test --features sim --target x86_64-unknown-linux-gnu
+//! ```
+//!
+//! Tasks run on threads, and mutexes, semaphores, notifications and delays
+//! behave as they do under FreeRTOS, except that task priorities and stack
+//! depths are ignored. Time is taken from the host clock.
+//!
+//! Devices are simulated: this module provides functions to plug them into
+//! smart ports, script their readings and inspect their outputs. Accessing a
+//! device of a given type through one of these functions plugs a default one
+//! into the port first if necessary. [Motors](SimMotor) follow their commands
+//! at the maximum velocity of their gearset; other sensors report whatever
+//! values are set. Files under `/usd/` are read from and written to the
+//! directory set with [`set_sd_root()`].
+//!
+//! The simulated world is shared by the whole program, so tests which use it
+//! should call [`reset()`] first and should be run with `--test-threads=1`.
+//!
+//! Panics unwind as usual on the host; the tasks and
+//! [trace journal](crate::trace) are printed after the first one, but hooks
+//! registered with [`panic::set_hook()`](crate::panic::set_hook()) are not run.
+//!
+//! # Example
+//!
+//! ```
+//! // In a test:
+//! sim::reset();
+//! sim::distance(4, |d| d.distance = 30);
+//! let mut intake = Intake::new(Peripherals::take().unwrap());
+//! intake.update().unwrap();
+//! assert_eq!(sim::motor(8, |m| m.voltage()), 0);
+//! ```
+
+use std::{
+    path::PathBuf,
+    sync::{Mutex, MutexGuard},
+    time::Instant,
+};
+
+use spin::Once;
+
+use crate::{controller::ControllerId, peripherals::Peripherals, robot::CompetitionStatus};
+
+pub(crate) mod bindings;
+mod devices;
+mod rtos;
+
+pub use devices::*;
+
+/// The number of smart ports on the brain.
+const NUM_SMART_PORTS: u8 = 21;
+
+static WORLD: Once<Mutex<World>> = Once::new();
+static START: Once<Instant> = Once::new();
+
+/// The state of everything outside of the program.
+#[derive(Default)]
+pub(crate) struct World {
+    pub(crate) ports: [Option<SimDevice>; NUM_SMART_PORTS as usize],
+    pub(crate) internal_adi: SimAdi,
+    pub(crate) controllers: [SimController; 2],
+    pub(crate) battery: SimBattery,
+    pub(crate) competition: CompetitionStatus,
+    pub(crate) screen: SimScreen,
+    pub(crate) sd_root: Option<PathBuf>,
+}
+
+pub(crate) fn world() -> MutexGuard<'static, World> {
+    WORLD
+        .call_once(|| {
+            START.call_once(Instant::now);
+            crate::panic::install_sim_hook();
+            Mutex::new(World::default())
+        })
+        .lock()
+        // A panic while the world was locked cannot leave it inconsistent.
+        .unwrap_or_else(|err| err.into_inner())
+}
+
+/// Gets the number of microseconds since the program started.
+pub(crate) fn micros() -> u64 {
+    START.call_once(Instant::now).elapsed().as_micros() as u64
+}
+
+/// Resets the simulated world: unplugs every device, releases the controller
+/// inputs, disconnects competition control, clears the screen and ejects the
+/// SD card, and allows the [`Peripherals`] to be taken again. Tasks and time
+/// are not affected.
+pub fn reset() {
+    *world() = World::default();
+    Peripherals::release();
+}
+
+/// Plugs a device into a smart port, replacing any device already there.
+///
+/// # Panics
+///
+/// This function panics if `port` is not a valid smart port.
+pub fn plug(port: u8, device: SimDevice) {
+    *slot(&mut world(), port) = Some(device);
+}
+
+/// Unplugs the device in a smart port, returning it if there was one.
+///
+/// # Panics
+///
+/// This function panics if `port` is not a valid smart port.
+pub fn unplug(port: u8) -> Option<SimDevice> {
+    slot(&mut world(), port).take()
+}
+
+/// Accesses the motor in a smart port, plugging in a new one if necessary.
+/// Its position and velocity are brought up to date first.
+///
+/// # Panics
+///
+/// This function panics if `port` is not a valid smart port.
+pub fn motor<T>(port: u8, f: impl FnOnce(&mut SimMotor) -> T) -> T {
+    device(port, SimDevice::Motor, SimDevice::as_motor, |motor| {
+        motor.update(micros());
+        f(motor)
+    })
+}
+
+/// Accesses the rotation sensor in a smart port, plugging in a new one if
+/// necessary.
+///
+/// # Panics
+///
+/// This function panics if `port` is not a valid smart port.
+pub fn rotation<T>(port: u8, f: impl FnOnce(&mut SimRotation) -> T) -> T {
+    device(port, SimDevice::Rotation, SimDevice::as_rotation, f)
+}
+
+/// Accesses the distance sensor in a smart port, plugging in a new one if
+/// necessary.
+///
+/// # Panics
+///
+/// This function panics if `port` is not a valid smart port.
+pub fn distance<T>(port: u8, f: impl FnOnce(&mut SimDistance) -> T) -> T {
+    device(port, SimDevice::Distance, SimDevice::as_distance, f)
+}
+
+/// Accesses the inertial sensor in a smart port, plugging in a new one if
+/// necessary.
+///
+/// # Panics
+///
+/// This function panics if `port` is not a valid smart port.
+pub fn imu<T>(port: u8, f: impl FnOnce(&mut SimImu) -> T) -> T {
+    device(port, SimDevice::Imu, SimDevice::as_imu, f)
+}
+
+/// Accesses the GPS sensor in a smart port, plugging in a new one if
+/// necessary.
+///
+/// # Panics
+///
+/// This function panics if `port` is not a valid smart port.
+pub fn gps<T>(port: u8, f: impl FnOnce(&mut SimGps) -> T) -> T {
+    device(port, SimDevice::Gps, SimDevice::as_gps, f)
+}
+
+/// Accesses the vision sensor in a smart port, plugging in a new one if
+/// necessary.
+///
+/// # Panics
+///
+/// This function panics if `port` is not a valid smart port.
+pub fn vision<T>(port: u8, f: impl FnOnce(&mut SimVision) -> T) -> T {
+    device(port, SimDevice::Vision, SimDevice::as_vision, f)
+}
+
+/// Accesses a generic serial device in a smart port, plugging in a new one if
+/// necessary.
+///
+/// # Panics
+///
+/// This function panics if `port` is not a valid smart port.
+pub fn serial<T>(port: u8, f: impl FnOnce(&mut SimSerial) -> T) -> T {
+    device(port, SimDevice::Serial, SimDevice::as_serial, f)
+}
+
+/// Accesses the ADI ports of the brain, if `expander_port` is
+/// [`INTERNAL_ADI_PORT`](crate::error::DevicePort::INTERNAL_ADI_PORT), or
+/// otherwise of the 3-wire expander in the given smart port, plugging in a new
+/// one if necessary.
+///
+/// # Panics
+///
+/// This function panics if `expander_port` is not a valid smart port.
+pub fn adi<T>(expander_port: u8, f: impl FnOnce(&mut SimAdi) -> T) -> T {
+    if expander_port == bindings::INTERNAL_ADI_PORT as u8 {
+        f(&mut world().internal_adi)
+    } else {
+        device(expander_port, SimDevice::Adi, SimDevice::as_adi, f)
+    }
+}
+
+/// Accesses a controller.
+pub fn controller<T>(id: ControllerId, f: impl FnOnce(&mut SimController) -> T) -> T {
+    let id: bindings::controller_id_e_t = id.into();
+    f(&mut world().controllers[id as usize])
+}
+
+/// Accesses the battery.
+pub fn battery<T>(f: impl FnOnce(&mut SimBattery) -> T) -> T {
+    f(&mut world().battery)
+}
+
+/// Accesses the brain screen.
+pub fn screen<T>(f: impl FnOnce(&mut SimScreen) -> T) -> T {
+    f(&mut world().screen)
+}
+
+#[inline]
+/// Sets the competition state reported by
+/// [`CompetitionStatus::get()`](crate::robot::CompetitionStatus::get()).
+pub fn set_competition(status: CompetitionStatus) {
+    world().competition = status;
+}
+
+/// Inserts an SD card whose contents are the given host directory, or ejects
+/// it if `root` is `None`.
+pub fn set_sd_root(root: Option<PathBuf>) {
+    world().sd_root = root;
+}
+
+fn slot(world: &mut World, port: u8) -> &mut Option<SimDevice> {
+    assert!(
+        (1..=NUM_SMART_PORTS).contains(&port),
+        "invalid smart port: {}",
+        port
+    );
+    &mut world.ports[port as usize - 1]
+}
+
+fn device<D: Default, T>(
+    port: u8,
+    wrap: fn(D) -> SimDevice,
+    select: fn(&mut SimDevice) -> Option<&mut D>,
+    f: impl FnOnce(&mut D) -> T,
+) -> T {
+    let mut world = world();
+    let slot = slot(&mut world, port);
+    if slot.as_mut().and_then(select).is_none() {
+        *slot = Some(wrap(D::default()));
+    }
+    f(slot.as_mut().and_then(select).unwrap())
+}
@@ -0,0 +1,431 @@
+//! Tasks, mutexes and semaphores on top of standard library threads.
+//!
+//! Blocking operations wait in short slices, so that a task deleted while it
+//! is blocked stops at its next opportunity. A deleted task's thread is parked
+//! forever rather than unwound, since unwinding across the `extern "C"` entry
+//! point of a task is not allowed.
+
+use alloc::{borrow::ToOwned, boxed::Box, sync::Arc, vec::Vec};
+use core::{
+    sync::atomic::{AtomicBool, AtomicU32, Ordering},
+    time::Duration,
+};
+use std::{
+    cell::RefCell,
+    ffi::{CStr, CString},
+    sync::{Condvar, Mutex, MutexGuard},
+    thread,
+    time::Instant,
+};
+
+use libc::{c_char, c_void};
+use spin::Once;
+
+use super::bindings;
+use crate::rtos::{TaskStatus, TIMEOUT_MAX};
+
+/// The longest a blocked task waits before checking whether it was deleted.
+const SLICE: Duration = Duration::from_millis(10);
+
+static TASKS: Once<Mutex<Vec<Arc<TaskData>>>> = Once::new();
+
+std::thread_local! {
+    static CURRENT: RefCell<Option<Arc<TaskData>>> = const { RefCell::new(None) };
+}
+
+struct TaskData {
+    name: CString,
+    priority: AtomicU32,
+    state: AtomicU32,
+    deleted: AtomicBool,
+    notifications: Mutex<u32>,
+    notified: Condvar,
+}
+
+impl TaskData {
+    fn new(name: CString, priority: u32) -> Arc<Self> {
+        let task = Arc::new(Self {
+            name,
+            priority: AtomicU32::new(priority),
+            state: AtomicU32::new(bindings::task_state_e_t_E_TASK_STATE_READY),
+            deleted: AtomicBool::new(false),
+            notifications: Mutex::new(0),
+            notified: Condvar::new(),
+        });
+        lock(tasks()).push(task.clone());
+        task
+    }
+
+    fn handle(self: &Arc<Self>) -> bindings::task_t {
+        Arc::as_ptr(self) as bindings::task_t
+    }
+}
+
+fn tasks() -> &'static Mutex<Vec<Arc<TaskData>>> {
+    TASKS.call_once(|| Mutex::new(Vec::new()))
+}
+
+/// Locks a standard mutex, ignoring poisoning: the simulated kernel never
+/// panics while holding one.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Gets the task running on the current thread, registering the thread as a
+/// task if it was not created by [`create()`].
+fn current() -> Arc<TaskData> {
+    CURRENT.with(|current| {
+        current
+            .borrow_mut()
+            .get_or_insert_with(|| {
+                let name = thread::current().name().unwrap_or("main").replace('\0', "");
+                TaskData::new(
+                    CString::new(name).unwrap_or_default(),
+                    bindings::TASK_PRIORITY_DEFAULT,
+                )
+            })
+            .clone()
+    })
+}
+
+/// Gets the task with the given handle. Tasks are never freed, so any handle
+/// returned by this module remains valid.
+unsafe fn get(task: bindings::task_t) -> &'static TaskData {
+    if task.is_null() {
+        // Leak a reference to the current task, which is kept alive by the
+        // task list regardless.
+        &*Arc::as_ptr(&current())
+    } else {
+        &*(task as *const TaskData)
+    }
+}
+
+/// Blocks until `ready` returns a value or the timeout elapses, in
+/// milliseconds.
+fn block<T, R>(
+    mutex: &Mutex<T>,
+    condvar: &Condvar,
+    timeout: u32,
+    mut ready: impl FnMut(&mut T) -> Option<R>,
+) -> Option<R> {
+    let task = current();
+    let deadline =
+        (timeout != TIMEOUT_MAX).then(|| Instant::now() + Duration::from_millis(timeout as u64));
+    let mut guard = lock(mutex);
+    loop {
+        if let Some(result) = ready(&mut guard) {
+            task.state.store(
+                bindings::task_state_e_t_E_TASK_STATE_READY,
+                Ordering::SeqCst,
+            );
+            return Some(result);
+        }
+        let slice = match deadline {
+            Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                Some(remaining) if !remaining.is_zero() => remaining.min(SLICE),
+                _ => {
+                    task.state.store(
+                        bindings::task_state_e_t_E_TASK_STATE_READY,
+                        Ordering::SeqCst,
+                    );
+                    return None;
+                }
+            },
+            None => SLICE,
+        };
+        task.state.store(
+            bindings::task_state_e_t_E_TASK_STATE_BLOCKED,
+            Ordering::SeqCst,
+        );
+        guard = condvar
+            .wait_timeout(guard, slice)
+            .unwrap_or_else(|err| err.into_inner())
+            .0;
+        if task.deleted.load(Ordering::SeqCst) {
+            drop(guard);
+            park_forever();
+        }
+    }
+}
+
+fn park_forever() -> ! {
+    loop {
+        thread::park();
+    }
+}
+
+pub(crate) unsafe fn create(
+    function: bindings::task_fn_t,
+    parameters: *mut c_void,
+    priority: u32,
+    name: *const c_char,
+) -> bindings::task_t {
+    let function = match function {
+        Some(function) => function,
+        None => {
+            bindings::set_errno(libc::EINVAL);
+            return core::ptr::null_mut();
+        }
+    };
+    let name = CStr::from_ptr(name).to_owned();
+    let thread_name = name.to_string_lossy().into_owned();
+    let task = TaskData::new(name, priority);
+    let handle = task.handle();
+    // Raw pointers cannot be sent between threads.
+    let parameters = parameters as usize;
+    let result = thread::Builder::new().name(thread_name).spawn(move || {
+        CURRENT.with(|current| *current.borrow_mut() = Some(task.clone()));
+        function(parameters as *mut c_void);
+        task.state.store(
+            bindings::task_state_e_t_E_TASK_STATE_DELETED,
+            Ordering::SeqCst,
+        );
+    });
+    match result {
+        Ok(_) => handle,
+        Err(_) => {
+            get(handle).state.store(
+                bindings::task_state_e_t_E_TASK_STATE_DELETED,
+                Ordering::SeqCst,
+            );
+            bindings::set_errno(libc::ENOMEM);
+            core::ptr::null_mut()
+        }
+    }
+}
+
+pub(crate) fn delay(milliseconds: u32) {
+    let task = current();
+    let deadline = Instant::now() + Duration::from_millis(milliseconds as u64);
+    task.state.store(
+        bindings::task_state_e_t_E_TASK_STATE_BLOCKED,
+        Ordering::SeqCst,
+    );
+    while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+        if remaining.is_zero() {
+            break;
+        }
+        thread::sleep(remaining.min(SLICE));
+        if task.deleted.load(Ordering::SeqCst) {
+            park_forever();
+        }
+    }
+    task.state.store(
+        bindings::task_state_e_t_E_TASK_STATE_READY,
+        Ordering::SeqCst,
+    );
+}
+
+pub(crate) unsafe fn delete(task: bindings::task_t) {
+    let data = get(task);
+    data.deleted.store(true, Ordering::SeqCst);
+    data.state.store(
+        bindings::task_state_e_t_E_TASK_STATE_DELETED,
+        Ordering::SeqCst,
+    );
+    data.notified.notify_all();
+    if core::ptr::eq(data, Arc::as_ptr(&current())) {
+        park_forever();
+    }
+}
+
+pub(crate) fn current_handle() -> bindings::task_t {
+    current().handle()
+}
+
+pub(crate) unsafe fn find(name: *const c_char) -> bindings::task_t {
+    let name = CStr::from_ptr(name);
+    lock(tasks())
+        .iter()
+        .find(|task| task.name.as_c_str() == name && !is_deleted(task))
+        .map_or(core::ptr::null_mut(), TaskData::handle)
+}
+
+pub(crate) fn count() -> u32 {
+    lock(tasks())
+        .iter()
+        .filter(|task| !is_deleted(task))
+        .count() as u32
+}
+
+fn is_deleted(task: &TaskData) -> bool {
+    task.state.load(Ordering::SeqCst) == bindings::task_state_e_t_E_TASK_STATE_DELETED
+}
+
+/// Fills in the state of every task which has not been deleted, or nothing if
+/// there are more than `size` tasks. Processor time is not measured, so every
+/// task's run-time counter is zero.
+pub(crate) unsafe fn system_state(status: *mut TaskStatus, size: u32) -> u32 {
+    let tasks = lock(tasks());
+    let live: Vec<&Arc<TaskData>> = tasks.iter().filter(|task| !is_deleted(task)).collect();
+    if live.len() > size as usize {
+        return 0;
+    }
+    for (i, task) in live.iter().enumerate() {
+        let priority = task.priority.load(Ordering::SeqCst);
+        status.add(i).write(TaskStatus {
+            handle: task.handle(),
+            name: task.name.as_ptr(),
+            number: i as u32,
+            state: task.state.load(Ordering::SeqCst) as u8,
+            current_priority: priority,
+            base_priority: priority,
+            run_time: 0,
+            stack_base: core::ptr::null_mut(),
+            stack_high_water_mark: 0,
+        });
+    }
+    live.len() as u32
+}
+
+pub(crate) unsafe fn name(task: bindings::task_t) -> *mut c_char {
+    get(task).name.as_ptr() as *mut c_char
+}
+
+pub(crate) unsafe fn priority(task: bindings::task_t) -> u32 {
+    get(task).priority.load(Ordering::SeqCst)
+}
+
+pub(crate) unsafe fn set_priority(task: bindings::task_t, priority: u32) {
+    get(task).priority.store(priority, Ordering::SeqCst);
+}
+
+pub(crate) unsafe fn state(task: bindings::task_t) -> bindings::task_state_e_t {
+    let data = get(task);
+    if !is_deleted(data) && core::ptr::eq(data, Arc::as_ptr(&current())) {
+        bindings::task_state_e_t_E_TASK_STATE_RUNNING
+    } else {
+        data.state.load(Ordering::SeqCst)
+    }
+}
+
+pub(crate) unsafe fn notify(task: bindings::task_t) -> u32 {
+    let data = get(task);
+    *lock(&data.notifications) += 1;
+    data.notified.notify_all();
+    1
+}
+
+pub(crate) fn notify_take(clear_on_exit: bool, timeout: u32) -> u32 {
+    let task = current();
+    block(&task.notifications, &task.notified, timeout, |count| {
+        (*count > 0).then(|| {
+            let value = *count;
+            *count = if clear_on_exit { 0 } else { value - 1 };
+            value
+        })
+    })
+    .unwrap_or(0)
+}
+
+/// A recursive mutex, which records the task holding it.
+struct RecursiveMutex {
+    /// The handle of the task holding the mutex, and how many times it has
+    /// taken it.
+    state: Mutex<(usize, u32)>,
+    released: Condvar,
+}
+
+impl RecursiveMutex {
+    fn new() -> Self {
+        Self {
+            state: Mutex::new((0, 0)),
+            released: Condvar::new(),
+        }
+    }
+
+    fn take(&self, timeout: u32) -> bool {
+        let me = current().handle() as usize;
+        block(&self.state, &self.released, timeout, |(owner, count)| {
+            (*owner == 0 || *owner == me).then(|| {
+                *owner = me;
+                *count += 1;
+            })
+        })
+        .is_some()
+    }
+
+    fn give(&self) -> bool {
+        let me = current().handle() as usize;
+        let mut state = lock(&self.state);
+        if state.0 != me {
+            return false;
+        }
+        state.1 -= 1;
+        if state.1 == 0 {
+            state.0 = 0;
+            self.released.notify_one();
+        }
+        true
+    }
+}
+
+pub(crate) fn mutex_create() -> bindings::mutex_t {
+    Box::into_raw(Box::new(RecursiveMutex::new())) as bindings::mutex_t
+}
+
+pub(crate) unsafe fn mutex_delete(mutex: bindings::mutex_t) {
+    drop(Box::from_raw(mutex as *mut RecursiveMutex));
+}
+
+pub(crate) unsafe fn mutex_take(mutex: bindings::mutex_t, timeout: u32) -> bool {
+    (*(mutex as *const RecursiveMutex)).take(timeout)
+}
+
+pub(crate) unsafe fn mutex_give(mutex: bindings::mutex_t) -> bool {
+    (*(mutex as *const RecursiveMutex)).give()
+}
+
+/// The lock held while the scheduler is suspended. Simulated tasks run in
+/// parallel, so suspending the scheduler only excludes other tasks which
+/// suspend it too.
+static SCHEDULER: Once<RecursiveMutex> = Once::new();
+
+pub(crate) fn suspend_all() {
+    SCHEDULER.call_once(RecursiveMutex::new).take(TIMEOUT_MAX);
+}
+
+pub(crate) fn resume_all() {
+    SCHEDULER.call_once(RecursiveMutex::new).give();
+}
+
+/// A counting semaphore.
+struct Semaphore {
+    /// The current and maximum counts.
+    state: Mutex<(u32, u32)>,
+    posted: Condvar,
+}
+
+pub(crate) fn sem_create(max_count: u32, init_count: u32) -> bindings::sem_t {
+    Box::into_raw(Box::new(Semaphore {
+        state: Mutex::new((init_count, max_count)),
+        posted: Condvar::new(),
+    })) as bindings::sem_t
+}
+
+pub(crate) unsafe fn sem_delete(sem: bindings::sem_t) {
+    drop(Box::from_raw(sem as *mut Semaphore));
+}
+
+pub(crate) unsafe fn sem_get_count(sem: bindings::sem_t) -> u32 {
+    lock(&(*(sem as *const Semaphore)).state).0
+}
+
+pub(crate) unsafe fn sem_post(sem: bindings::sem_t) -> bool {
+    let sem = &*(sem as *const Semaphore);
+    let mut state = lock(&sem.state);
+    if state.0 >= state.1 {
+        return false;
+    }
+    state.0 += 1;
+    sem.posted.notify_one();
+    true
+}
+
+pub(crate) unsafe fn sem_wait(sem: bindings::sem_t, timeout: u32) -> bool {
+    let sem = &*(sem as *const Semaphore);
+    block(&sem.state, &sem.posted, timeout, |(count, _)| {
+        (*count > 0).then(|| *count -= 1)
+    })
+    .is_some()
+}
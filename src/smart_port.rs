@@ -5,10 +5,12 @@ use crate::{
     bindings,
     distance::DistanceSensor,
     error::Error,
+    gps::GpsSensor,
     imu::InertialSensor,
     motor::{EncoderUnits, Gearset, Motor, MotorError},
     rotation::{RotationSensor, RotationSensorError},
     serial::Serial,
+    vision::VisionSensor,
 };
 use core::convert::{TryFrom, TryInto};
 
@@ -72,6 +74,17 @@ impl SmartPort {
         self.into()
     }
 
+    /// Converts a `SmartPort` into a [`GpsSensor`](crate::gps::GpsSensor).
+    pub fn into_gps(self) -> GpsSensor {
+        self.into()
+    }
+
+    /// Converts a `SmartPort` into a
+    /// [`VisionSensor`](crate::vision::VisionSensor).
+    pub fn into_vision(self) -> VisionSensor {
+        self.into()
+    }
+
     /// Converts a `SmartPort` into a
     /// [`RotationSensor`](crate::rotation::RotationSensor).
     #[inline]
@@ -115,6 +128,18 @@ impl From<SmartPort> for InertialSensor {
     }
 }
 
+impl From<SmartPort> for GpsSensor {
+    fn from(port: SmartPort) -> Self {
+        unsafe { GpsSensor::new(port.port) }
+    }
+}
+
+impl From<SmartPort> for VisionSensor {
+    fn from(port: SmartPort) -> Self {
+        unsafe { VisionSensor::new(port.port) }
+    }
+}
+
 impl TryFrom<(SmartPort, bool)> for RotationSensor {
     type Error = RotationSensorError;
 
@@ -154,6 +179,9 @@ pub enum DeviceType {
     /// V5 Optical Sensor.
     Optical,
 
+    /// V5 GPS Sensor.
+    Gps,
+
     /// Generic serial mode.
     Serial,
 
@@ -176,6 +204,7 @@ impl From<bindings::v5_device_e_t> for DeviceType {
             bindings::v5_device_e_E_DEVICE_VISION => Self::Vision,
             bindings::v5_device_e_E_DEVICE_ADI => Self::Adi,
             bindings::v5_device_e_E_DEVICE_OPTICAL => Self::Optical,
+            bindings::v5_device_e_E_DEVICE_GPS => Self::Gps,
             bindings::v5_device_e_E_DEVICE_GENERIC => Self::Serial,
             bindings::v5_device_e_E_DEVICE_UNDEFINED => Self::Undefined,
             _ => Self::Unknown(t),
@@ -0,0 +1,228 @@
+//! # Task Starvation Detection.
+//!
+//! A periodic task which stops running on time, because a higher-priority
+//! task is hogging the processor or because it is stuck waiting for a lock held
+//! by a lower-priority task, often goes unnoticed until the robot misbehaves
+//! in a match. A [`StarvationMonitor`] catches this: each monitored task calls
+//! [`Heartbeat::beat()`] once per period, and the monitor flags any task which
+//! has missed more than a given number of periods in a row.
+//!
+//! When a task is flagged, the monitor lists the tasks, including those created
+//! by PROS, which used the most processor time since its previous check, on
+//! standard error and through the [`log`] crate. A starved task is usually
+//! being pre-empted by one of the first tasks in this list. The starvation is
+//! then reported to the [error escalation policy](crate::escalation) for the
+//! `"starvation"` subsystem, so that it can, for example, raise an alert.
+//!
+//! Monitoring is opt-in: nothing is checked unless a monitor is created.
+//!
+//! # Example
+//!
+//! ```
+//! let monitor = StarvationMonitor::new(Duration::from_millis(50));
+//! let heartbeat = monitor.register("intake", Duration::from_millis(10), 5);
+//!
+//! let mut l = Loop::new(Duration::from_millis(10));
+//! loop {
+//!     heartbeat.beat();
+//!     // ...
+//!     l.delay();
+//! }
+//! ```
+
+use alloc::{
+    format,
+    string::String,
+    sync::{Arc, Weak},
+    vec::Vec,
+};
+use core::{cmp::Reverse, mem, time::Duration};
+
+use crate::{
+    bindings,
+    error::Error,
+    escalation,
+    io::eprintln,
+    rtos::{time_since_start, Instant, Loop, Mutex, Task},
+    trace,
+};
+
+/// The maximum number of busy tasks listed when a task is flagged.
+const MAX_LISTED_TASKS: usize = 5;
+
+/// Checks registered periodic tasks from a background task, and flags those
+/// which stop running on time. See the [module-level documentation](self).
+///
+/// The monitor's task runs at the highest priority, so that it is not starved
+/// itself, and exits when this is dropped.
+pub struct StarvationMonitor(Arc<Mutex<Vec<Weak<Mutex<HeartbeatData>>>>>);
+
+impl StarvationMonitor {
+    #[inline]
+    /// Creates a new monitor which checks the registered tasks every
+    /// `check_period`, and spawns its task. Panics on failure; see
+    /// [`StarvationMonitor::try_new()`].
+    pub fn new(check_period: Duration) -> Self {
+        Self::try_new(check_period)
+            .unwrap_or_else(|err| panic!("failed to create starvation monitor: {:?}", err))
+    }
+
+    /// Creates a new monitor which checks the registered tasks every
+    /// `check_period`, and spawns its task.
+    pub fn try_new(check_period: Duration) -> Result<Self, Error> {
+        let data = Arc::new(Mutex::try_new(Vec::new())?);
+        let weak: Weak<Mutex<Vec<Weak<Mutex<HeartbeatData>>>>> = Arc::downgrade(&data);
+        Task::spawn_ext(
+            "starvation",
+            bindings::TASK_PRIORITY_MAX,
+            Task::DEFAULT_STACK_DEPTH,
+            move || {
+                let mut l = Loop::new(check_period);
+                let mut run_times = Task::run_times();
+                while let Some(data) = weak.upgrade() {
+                    let now = time_since_start();
+                    let mut flagged = Vec::new();
+                    data.lock().retain(|heartbeat| match heartbeat.upgrade() {
+                        Some(heartbeat) => {
+                            if let Some(starvation) = heartbeat.lock().check(now) {
+                                flagged.push(starvation);
+                            }
+                            true
+                        }
+                        None => false,
+                    });
+                    drop(data);
+                    let previous = mem::replace(&mut run_times, Task::run_times());
+                    if !flagged.is_empty() {
+                        dump_busy_tasks(&previous, &run_times);
+                    }
+                    for starvation in flagged {
+                        trace::record("task starved", &[starvation.missed as f64]);
+                        escalation::report("starvation", &starvation);
+                    }
+                    l.delay();
+                }
+            },
+        )?;
+        Ok(Self(data))
+    }
+
+    /// Registers a periodic task named `name`, which is expected to run every
+    /// `period`, and which is flagged once it has missed more than `max_missed`
+    /// periods in a row. The task should call [`Heartbeat::beat()`] on the
+    /// returned heartbeat once per period; it is no longer monitored once the
+    /// heartbeat is dropped.
+    pub fn register(&self, name: &str, period: Duration, max_missed: u32) -> Heartbeat {
+        let heartbeat = Arc::new(Mutex::new(HeartbeatData {
+            name: name.into(),
+            period,
+            max_missed,
+            last: time_since_start(),
+            starved: false,
+        }));
+        self.0.lock().push(Arc::downgrade(&heartbeat));
+        Heartbeat(heartbeat)
+    }
+
+    /// Gets the names of the registered tasks which are currently flagged as
+    /// starved, i.e., which have not run since they were flagged.
+    pub fn starved(&self) -> Vec<String> {
+        self.0
+            .lock()
+            .iter()
+            .filter_map(Weak::upgrade)
+            .filter_map(|heartbeat| {
+                let heartbeat = heartbeat.lock();
+                heartbeat.starved.then(|| heartbeat.name.clone())
+            })
+            .collect()
+    }
+}
+
+/// Signals to a [`StarvationMonitor`] that a registered task is still running.
+/// See [`StarvationMonitor::register()`].
+pub struct Heartbeat(Arc<Mutex<HeartbeatData>>);
+
+impl Heartbeat {
+    /// Records that the task has run, clearing any starvation flag.
+    pub fn beat(&self) {
+        let mut lock = self.0.lock();
+        lock.last = time_since_start();
+        lock.starved = false;
+    }
+}
+
+/// A task which was flagged by a [`StarvationMonitor`], as reported to the
+/// [error escalation policy](crate::escalation).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Starvation {
+    /// The name with which the task was registered.
+    pub name: String,
+    /// The number of periods the task had missed when it was flagged.
+    pub missed: u32,
+}
+
+struct HeartbeatData {
+    name: String,
+    period: Duration,
+    max_missed: u32,
+    last: Instant,
+    starved: bool,
+}
+
+impl HeartbeatData {
+    /// Checks whether the task has newly missed too many periods.
+    fn check(&mut self, now: Instant) -> Option<Starvation> {
+        if self.starved {
+            return None;
+        }
+        let elapsed = now.checked_sub_instant(self.last)?;
+        let missed = (elapsed.as_micros() / self.period.as_micros().max(1)) as u32;
+        if missed <= self.max_missed {
+            return None;
+        }
+        self.starved = true;
+        Some(Starvation {
+            name: self.name.clone(),
+            missed,
+        })
+    }
+}
+
+/// Prints the tasks which used the most processor time between two snapshots
+/// of the tasks' run-time counters, busiest first.
+fn dump_busy_tasks(previous: &[(Task, u32)], current: &[(Task, u32)]) {
+    let monitor = Task::current();
+    let mut tasks: Vec<(&Task, u32)> = current
+        .iter()
+        .filter(|(task, _)| *task != monitor)
+        .map(|(task, run_time)| {
+            let before = previous
+                .iter()
+                .find(|(other, _)| other == task)
+                .map_or(0, |(_, run_time)| *run_time);
+            (task, run_time.wrapping_sub(before))
+        })
+        .collect();
+    let total = tasks
+        .iter()
+        .map(|(_, used)| *used as u64)
+        .sum::<u64>()
+        .max(1);
+    tasks.sort_by_key(|(_, used)| Reverse(*used));
+    let lines = tasks.iter().take(MAX_LISTED_TASKS).map(|(task, used)| {
+        format!(
+            "  {:<20} {:>3}% ({} ticks) priority {}",
+            task.name(),
+            *used as u64 * 100 / total,
+            used,
+            task.priority()
+        )
+    });
+    eprintln!("task starvation detected; busiest tasks since the last check:");
+    log::warn!("task starvation detected; busiest tasks since the last check:");
+    for line in lines {
+        eprintln!("{}", line);
+        log::warn!("{}", line);
+    }
+}
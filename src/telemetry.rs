@@ -0,0 +1,271 @@
+//! # Binary Telemetry.
+//!
+//! Sends typed packets to a laptop dashboard over USB or a smart port serial
+//! connection. Each packet is serialized with [`postcard`], prefixed with its
+//! [`Packet::ID`], followed by a CRC-16 checksum, and COBS-encoded into a
+//! zero-terminated frame, so the receiver can resynchronize after any lost or
+//! corrupted bytes.
+//!
+//! # Example
+//!
+//! ```
+//! #[derive(Serialize)]
+//! struct Drive {
+//!     left: f64,
+//!     right: f64,
+//! }
+//!
+//! impl Packet for Drive {
+//!     const ID: u8 = 1;
+//!     const NAME: &'static str = "drive";
+//! }
+//!
+//! let mut telemetry = Telemetry::new(TelemetryOutput::Stdout);
+//! telemetry.register::<Drive>(Duration::from_millis(50))?;
+//! telemetry.send_registry()?;
+//!
+//! // In a control loop, sends at most every 50ms:
+//! telemetry.send(&Drive { left, right })?;
+//! ```
+
+use alloc::{collections::BTreeMap, format, vec::Vec};
+use core::{ptr, time::Duration};
+
+use serde::Serialize;
+
+use crate::{
+    bindings,
+    error::Error,
+    io,
+    rtos::{time_since_start, Instant},
+    serial::Serial,
+};
+
+/// The packet ID reserved for the registry packet sent by
+/// [`Telemetry::send_registry()`].
+pub const REGISTRY_ID: u8 = 0;
+
+/// A type which can be sent as a telemetry packet.
+pub trait Packet: Serialize {
+    /// The identifier of the packet type. Must be unique among the packets
+    /// registered with a [`Telemetry`] instance, and must not be
+    /// [`REGISTRY_ID`].
+    const ID: u8;
+
+    /// The name of the packet type, as shown on the dashboard.
+    const NAME: &'static str;
+}
+
+/// The destination of telemetry frames.
+pub enum TelemetryOutput {
+    /// The USB serial connection (standard output).
+    ///
+    /// PROS stream framing is disabled so that frames are sent unmodified;
+    /// text printed to standard output is still sent, between frames.
+    Stdout,
+    /// A smart port configured as a generic serial port.
+    Serial(Serial),
+}
+
+struct Registration {
+    name: &'static str,
+    interval: Duration,
+    last: Option<Instant>,
+}
+
+/// Sends rate-limited telemetry packets of registered types.
+pub struct Telemetry {
+    output: TelemetryOutput,
+    packets: BTreeMap<u8, Registration>,
+}
+
+impl Telemetry {
+    /// Creates a new telemetry sender with no registered packet types.
+    pub fn new(output: TelemetryOutput) -> Self {
+        if let TelemetryOutput::Stdout = output {
+            unsafe {
+                bindings::serctl(bindings::SERCTL_DISABLE_COBS, ptr::null_mut());
+            }
+        }
+        Self {
+            output,
+            packets: BTreeMap::new(),
+        }
+    }
+
+    /// Registers a packet type, which will be sent at most once every
+    /// `interval`. An interval of zero disables rate limiting.
+    pub fn register<P: Packet>(&mut self, interval: Duration) -> Result<(), TelemetryError> {
+        if P::ID == REGISTRY_ID || self.packets.contains_key(&P::ID) {
+            return Err(TelemetryError::DuplicateId(P::ID));
+        }
+        self.packets.insert(
+            P::ID,
+            Registration {
+                name: P::NAME,
+                interval,
+                last: None,
+            },
+        );
+        Ok(())
+    }
+
+    /// Sends the registry packet, which lists the ID and name of each
+    /// registered packet type, so that the dashboard can label incoming data.
+    pub fn send_registry(&mut self) -> Result<bool, TelemetryError> {
+        let registry: Vec<(u8, &str)> = self
+            .packets
+            .iter()
+            .map(|(id, registration)| (*id, registration.name))
+            .collect();
+        let payload = postcard::to_allocvec(&registry)?;
+        self.send_frame(REGISTRY_ID, &payload)
+    }
+
+    /// Sends a packet, unless one of the same type was sent too recently.
+    /// Returns whether the packet was sent; packets are also dropped when the
+    /// output buffer of a serial port is full.
+    pub fn send<P: Packet>(&mut self, packet: &P) -> Result<bool, TelemetryError> {
+        let now = time_since_start();
+        let registration = self
+            .packets
+            .get_mut(&P::ID)
+            .ok_or(TelemetryError::Unregistered(P::ID))?;
+        if matches!(registration.last, Some(last) if now < last + registration.interval) {
+            return Ok(false);
+        }
+        registration.last = Some(now);
+        let payload = postcard::to_allocvec(packet)?;
+        self.send_frame(P::ID, &payload)
+    }
+
+    fn send_frame(&mut self, id: u8, payload: &[u8]) -> Result<bool, TelemetryError> {
+        let mut data = Vec::with_capacity(payload.len() + 1);
+        data.push(id);
+        data.extend_from_slice(payload);
+        let frame = encode_frame(&data);
+        match &mut self.output {
+            TelemetryOutput::Stdout => io::write_raw(&frame).map_err(TelemetryError::Serial)?,
+            TelemetryOutput::Serial(serial) => {
+                if serial.get_write_free().map_err(TelemetryError::Serial)? < frame.len() {
+                    return Ok(false);
+                }
+                serial.write(&frame).map_err(TelemetryError::Serial)?;
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Represents possible errors for telemetry operations.
+#[derive(Debug)]
+pub enum TelemetryError {
+    /// The packet type has not been registered.
+    Unregistered(u8),
+    /// A packet type with the same ID has already been registered, or the ID
+    /// is reserved.
+    DuplicateId(u8),
+    /// The packet could not be serialized.
+    Serialize(postcard::Error),
+    /// An error occurred writing to the serial port or standard output.
+    Serial(Error),
+}
+
+impl From<postcard::Error> for TelemetryError {
+    #[inline]
+    fn from(err: postcard::Error) -> Self {
+        Self::Serialize(err)
+    }
+}
+
+impl From<TelemetryError> for Error {
+    fn from(err: TelemetryError) -> Self {
+        match err {
+            TelemetryError::Unregistered(id) => {
+                Error::Custom(format!("telemetry packet {} not registered", id))
+            }
+            TelemetryError::DuplicateId(id) => {
+                Error::Custom(format!("telemetry packet id {} already in use", id))
+            }
+            TelemetryError::Serialize(err) => Error::Custom(format!("{:?}", err)),
+            TelemetryError::Serial(err) => err,
+        }
+    }
+}
+
+/// Computes the CRC-16/CCITT-FALSE checksum of the data.
+fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0xffff, |crc, &byte| {
+        (0..8).fold(crc ^ ((byte as u16) << 8), |crc, _| {
+            if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
+
+/// Encodes the data with consistent overhead byte stuffing, appending the
+/// zero byte which terminates the frame.
+fn cobs_encode(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + data.len() / 254 + 2);
+    let mut code_index = 0;
+    out.push(0);
+    for &byte in data {
+        if byte != 0 {
+            out.push(byte);
+        }
+        if byte == 0 || out.len() - code_index == 0xff {
+            out[code_index] = (out.len() - code_index) as u8;
+            code_index = out.len();
+            out.push(0);
+        }
+    }
+    out[code_index] = (out.len() - code_index) as u8;
+    out.push(0);
+    out
+}
+
+/// Decodes a frame encoded with [`cobs_encode()`], without its terminating
+/// zero byte. Returns `None` if the frame is malformed.
+fn cobs_decode(frame: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(frame.len());
+    let mut index = 0;
+    while index < frame.len() {
+        let code = frame[index] as usize;
+        if code == 0 || index + code > frame.len() {
+            return None;
+        }
+        out.extend_from_slice(&frame[index + 1..index + code]);
+        index += code;
+        if code < 0xff && index < frame.len() {
+            out.push(0);
+        }
+    }
+    Some(out)
+}
+
+/// Frames a packet for sending: appends its CRC-16 checksum and COBS-encodes
+/// it.
+pub(crate) fn encode_frame(data: &[u8]) -> Vec<u8> {
+    let mut data = data.to_vec();
+    data.extend_from_slice(&crc16(&data).to_le_bytes());
+    cobs_encode(&data)
+}
+
+/// Decodes a frame received without its terminating zero byte and checks its
+/// CRC-16 checksum, returning the packet. Returns `None` if the frame is
+/// malformed or corrupted.
+pub(crate) fn decode_frame(frame: &[u8]) -> Option<Vec<u8>> {
+    let mut data = cobs_decode(frame)?;
+    if data.len() < 2 {
+        return None;
+    }
+    let crc = data.split_off(data.len() - 2);
+    if crc16(&data).to_le_bytes() == crc[..] {
+        Some(data)
+    } else {
+        None
+    }
+}
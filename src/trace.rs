@@ -0,0 +1,193 @@
+//! # Trace Journal.
+//!
+//! The [`trace!`](crate::trace!) macro records an event in a fixed-size
+//! in-memory ring buffer. Recording an event only stores a timestamp, a static
+//! message and up to [`MAX_VALUES`] numeric values, without any formatting, so
+//! it is cheap enough to use inside control loops. Once the buffer is full,
+//! the oldest events are overwritten.
+//!
+//! The journal can be printed with [`dump()`], saved to the SD card with
+//! [`save()`], or inspected with the `trace` [REPL](crate::repl) command:
+//!
+//! ```text
+//! trace                    # prints the journal
+//! trace save /usd/t.txt    # saves the journal to a file
+//! trace clear              # clears the journal
+//! ```
+//!
+//! If any events have been recorded, the journal is also printed when a panic
+//! occurs, and saved to the file set with [`save_on_panic()`].
+//!
+//! # Example
+//!
+//! ```
+//! trace::save_on_panic("/usd/trace.txt");
+//!
+//! loop {
+//!     let (left, right) = drive.voltages();
+//!     trace!("drive", left, right);
+//!     if intake.is_jammed() {
+//!         trace!("intake jammed");
+//!     }
+//!     // ...
+//! }
+//! ```
+
+use alloc::{collections::VecDeque, string::String, vec::Vec};
+use core::fmt::{self, Display, Formatter, Write};
+
+use spin::Once;
+
+use crate::{
+    error::Error,
+    io::{eprintln, println},
+    repl,
+    rtos::{time_since_start, Instant, Mutex},
+    sd::{File, SdError},
+};
+
+/// The number of events held in the journal.
+const CAPACITY: usize = 512;
+
+/// The maximum number of values recorded with each event.
+pub const MAX_VALUES: usize = 3;
+
+static JOURNAL: Once<Mutex<VecDeque<TraceEvent>>> = Once::new();
+static PANIC_PATH: Once<Mutex<Option<&'static str>>> = Once::new();
+
+fn journal() -> &'static Mutex<VecDeque<TraceEvent>> {
+    JOURNAL.call_once(|| {
+        repl::register("trace", |args| match args {
+            [] => {
+                dump();
+                Ok(())
+            }
+            ["save", path] => Ok(save(path)?),
+            ["clear"] => {
+                clear();
+                Ok(())
+            }
+            _ => Err(Error::Custom("usage: trace [save <path> | clear]".into())),
+        });
+        Mutex::new(VecDeque::with_capacity(CAPACITY))
+    })
+}
+
+/// An event recorded in the journal.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TraceEvent {
+    /// The time at which the event was recorded.
+    pub time: Instant,
+    /// The message describing the event.
+    pub message: &'static str,
+    values: [f64; MAX_VALUES],
+    len: u8,
+}
+
+impl TraceEvent {
+    #[inline]
+    /// Gets the values recorded with the event.
+    pub fn values(&self) -> &[f64] {
+        &self.values[..self.len as usize]
+    }
+}
+
+impl Display for TraceEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:>6}.{:06} {}",
+            self.time.as_secs(),
+            self.time.subsec_micros(),
+            self.message
+        )?;
+        for value in self.values() {
+            write!(f, " {}", value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Records an event in the journal. Values beyond the first [`MAX_VALUES`]
+/// are ignored. Usually called through [`trace!`](crate::trace!).
+pub fn record(message: &'static str, values: &[f64]) {
+    let len = values.len().min(MAX_VALUES);
+    let mut event = TraceEvent {
+        time: time_since_start(),
+        message,
+        values: [0.0; MAX_VALUES],
+        len: len as u8,
+    };
+    event.values[..len].copy_from_slice(&values[..len]);
+
+    let mut journal = journal().lock();
+    if journal.len() == CAPACITY {
+        journal.pop_front();
+    }
+    journal.push_back(event);
+}
+
+/// Gets every event in the journal, oldest first.
+pub fn events() -> Vec<TraceEvent> {
+    journal().lock().iter().copied().collect()
+}
+
+/// Removes every event from the journal.
+pub fn clear() {
+    journal().lock().clear();
+}
+
+/// Prints every event in the journal to standard output.
+pub fn dump() {
+    for event in events() {
+        println!("{}", event);
+    }
+}
+
+/// Writes every event in the journal to a new file at the given path,
+/// replacing any existing file.
+pub fn save(path: &str) -> Result<(), SdError> {
+    let mut file = File::create(path)?;
+    file.write_all(to_text(&events()).as_bytes())?;
+    file.flush()
+}
+
+/// Sets a file to which the journal is saved if a panic occurs.
+pub fn save_on_panic(path: &'static str) {
+    *PANIC_PATH.call_once(|| Mutex::new(None)).lock() = Some(path);
+}
+
+/// Prints the journal to standard error and saves it to the panic file, if
+/// any. Does nothing if the journal is empty or currently locked.
+pub(crate) fn dump_on_panic() {
+    let events: Vec<TraceEvent> = match JOURNAL.get().and_then(|journal| journal.poll()) {
+        Some(journal) if !journal.is_empty() => journal.iter().copied().collect(),
+        _ => return,
+    };
+    eprintln!("trace journal ({} events):", events.len());
+    for event in &events {
+        eprintln!("{}", event);
+    }
+    let path = PANIC_PATH
+        .get()
+        .and_then(Mutex::poll)
+        .and_then(|path| *path);
+    if let Some(path) = path {
+        let result = File::create(path).and_then(|mut file| {
+            file.write_all(to_text(&events).as_bytes())?;
+            file.flush()
+        });
+        if let Err(err) = result {
+            eprintln!("failed to save trace journal: {:?}", err);
+        }
+    }
+}
+
+fn to_text(events: &[TraceEvent]) -> String {
+    let mut text = String::new();
+    for event in events {
+        // Writing to a string cannot fail.
+        writeln!(text, "{}", event).ok();
+    }
+    text
+}
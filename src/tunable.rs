@@ -0,0 +1,161 @@
+//! # Live Tuning.
+//!
+//! A [`Tunable`] is a named value, such as a PID gain or a threshold, which
+//! can be changed while the program is running. Every tunable is registered
+//! with the `tune` [REPL](crate::repl) command:
+//!
+//! ```text
+//! tune               # lists all tunables and their values
+//! tune kp            # prints the value of `kp`
+//! tune kp 0.35       # sets `kp` to 0.35
+//! ```
+//!
+//! Values can also be listed and changed programmatically with [`values()`]
+//! and [`set()`], e.g. from an on-screen editor.
+//!
+//! # Example
+//!
+//! ```
+//! let kp = Tunable::new("kp", 0.5);
+//! repl::start()?;
+//!
+//! loop {
+//!     let output = kp.get() * error;
+//!     // ...
+//! }
+//! ```
+
+use alloc::{
+    boxed::Box,
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    sync::{Arc, Weak},
+    vec::Vec,
+};
+use core::{fmt::Display, str::FromStr};
+
+use spin::Once;
+
+use crate::{error::Error, io::println, repl, rtos::Mutex};
+
+pub(crate) trait Entry: Send + Sync {
+    fn get(&self) -> Option<String>;
+
+    fn set(&self, value: &str) -> Option<Result<(), Error>>;
+}
+
+impl<T: FromStr + Display + Send> Entry for Weak<Mutex<T>> {
+    fn get(&self) -> Option<String> {
+        Some(self.upgrade()?.lock().to_string())
+    }
+
+    fn set(&self, value: &str) -> Option<Result<(), Error>> {
+        let tunable = self.upgrade()?;
+        Some(match value.parse() {
+            Ok(value) => {
+                *tunable.lock() = value;
+                Ok(())
+            }
+            Err(_) => Err(Error::Custom(format!("invalid value: {}", value))),
+        })
+    }
+}
+
+static REGISTRY: Once<Mutex<BTreeMap<String, Box<dyn Entry>>>> = Once::new();
+
+fn registry() -> &'static Mutex<BTreeMap<String, Box<dyn Entry>>> {
+    REGISTRY.call_once(|| {
+        repl::register("tune", |args| match args {
+            [] => {
+                for (name, value) in values() {
+                    println!("{} = {}", name, value);
+                }
+                Ok(())
+            }
+            [name] => {
+                println!("{} = {}", name, get(name)?);
+                Ok(())
+            }
+            [name, value] => set(name, value),
+            _ => Err(Error::Custom("usage: tune [name [value]]".into())),
+        });
+        Mutex::new(BTreeMap::new())
+    })
+}
+
+/// Registers an entry under the given name, replacing any existing entry with
+/// the same name.
+pub(crate) fn register(name: &str, entry: Box<dyn Entry>) {
+    registry().lock().insert(name.into(), entry);
+}
+
+/// A value which can be changed at runtime by name. Clones refer to the same
+/// underlying value.
+///
+/// The value is unregistered once every clone has been dropped.
+pub struct Tunable<T>(Arc<Mutex<T>>);
+
+impl<T: FromStr + Display + Clone + Send + 'static> Tunable<T> {
+    /// Creates a new tunable value, registering it under the given name.
+    /// Replaces any existing tunable with the same name.
+    pub fn new(name: &str, initial: T) -> Self {
+        let value = Arc::new(Mutex::new(initial));
+        register(name, Box::new(Arc::downgrade(&value)));
+        Self(value)
+    }
+
+    #[inline]
+    /// Gets the current value.
+    pub fn get(&self) -> T {
+        self.0.lock().clone()
+    }
+
+    #[inline]
+    /// Sets the current value.
+    pub fn set(&self, value: T) {
+        *self.0.lock() = value;
+    }
+}
+
+impl<T> Clone for Tunable<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+/// Gets the current value of the tunable with the given name, formatted as a
+/// string.
+pub fn get(name: &str) -> Result<String, Error> {
+    registry()
+        .lock()
+        .get(name)
+        .and_then(|entry| entry.get())
+        .ok_or_else(|| not_found(name))
+}
+
+/// Parses and sets the value of the tunable with the given name.
+pub fn set(name: &str, value: &str) -> Result<(), Error> {
+    registry()
+        .lock()
+        .get(name)
+        .and_then(|entry| entry.set(value))
+        .unwrap_or_else(|| Err(not_found(name)))
+}
+
+/// Gets the name and current value of every registered tunable, in
+/// alphabetical order.
+pub fn values() -> Vec<(String, String)> {
+    let mut registry = registry().lock();
+    // Forget any tunables which have since been dropped.
+    registry.retain(|_, entry| entry.get().is_some());
+    registry
+        .iter()
+        .filter_map(|(name, entry)| Some((name.clone(), entry.get()?)))
+        .collect()
+}
+
+fn not_found(name: &str) -> Error {
+    Error::Custom(format!("no tunable named {}", name))
+}
@@ -0,0 +1,385 @@
+//! Units of measure.
+//!
+//! Each unit is a lightweight wrapper around an `f64`, so that quantities in
+//! different units cannot be mixed up accidentally. Conversions between
+//! compatible units are provided via [`From`].
+
+use core::{
+    fmt::{self, Display, Formatter},
+    iter::Sum,
+    ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+};
+
+#[cfg(not(feature = "sim"))]
+use num_traits::Float;
+
+use crate::motor::{EncoderUnits, Gearset};
+
+macro_rules! unit {
+    ($(#[$attr:meta])* $name:ident, $suffix:literal) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+        pub struct $name(pub f64);
+
+        impl $name {
+            #[inline]
+            /// Returns the absolute value of the quantity.
+            pub fn abs(self) -> Self {
+                Self(self.0.abs())
+            }
+
+            #[inline]
+            /// Returns the smaller of two quantities.
+            pub fn min(self, other: Self) -> Self {
+                Self(self.0.min(other.0))
+            }
+
+            #[inline]
+            /// Returns the larger of two quantities.
+            pub fn max(self, other: Self) -> Self {
+                Self(self.0.max(other.0))
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                Display::fmt(&self.0, f)?;
+                f.write_str($suffix)
+            }
+        }
+
+        impl Add for $name {
+            type Output = Self;
+
+            #[inline]
+            fn add(self, rhs: Self) -> Self {
+                Self(self.0 + rhs.0)
+            }
+        }
+
+        impl Sub for $name {
+            type Output = Self;
+
+            #[inline]
+            fn sub(self, rhs: Self) -> Self {
+                Self(self.0 - rhs.0)
+            }
+        }
+
+        impl Neg for $name {
+            type Output = Self;
+
+            #[inline]
+            fn neg(self) -> Self {
+                Self(-self.0)
+            }
+        }
+
+        impl Mul<f64> for $name {
+            type Output = Self;
+
+            #[inline]
+            fn mul(self, rhs: f64) -> Self {
+                Self(self.0 * rhs)
+            }
+        }
+
+        impl Mul<$name> for f64 {
+            type Output = $name;
+
+            #[inline]
+            fn mul(self, rhs: $name) -> $name {
+                $name(self * rhs.0)
+            }
+        }
+
+        impl Div<f64> for $name {
+            type Output = Self;
+
+            #[inline]
+            fn div(self, rhs: f64) -> Self {
+                Self(self.0 / rhs)
+            }
+        }
+
+        impl Div for $name {
+            type Output = f64;
+
+            #[inline]
+            fn div(self, rhs: Self) -> f64 {
+                self.0 / rhs.0
+            }
+        }
+
+        impl AddAssign for $name {
+            #[inline]
+            fn add_assign(&mut self, rhs: Self) {
+                self.0 += rhs.0;
+            }
+        }
+
+        impl SubAssign for $name {
+            #[inline]
+            fn sub_assign(&mut self, rhs: Self) {
+                self.0 -= rhs.0;
+            }
+        }
+
+        impl MulAssign<f64> for $name {
+            #[inline]
+            fn mul_assign(&mut self, rhs: f64) {
+                self.0 *= rhs;
+            }
+        }
+
+        impl DivAssign<f64> for $name {
+            #[inline]
+            fn div_assign(&mut self, rhs: f64) {
+                self.0 /= rhs;
+            }
+        }
+
+        impl Sum for $name {
+            fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+                Self(iter.map(|v| v.0).sum())
+            }
+        }
+    };
+}
+
+unit!(
+    /// An angle in degrees.
+    Degrees,
+    "°"
+);
+unit!(
+    /// An angle in radians.
+    Radians,
+    "rad"
+);
+unit!(
+    /// A distance in millimetres.
+    Millimeters,
+    "mm"
+);
+unit!(
+    /// A distance in inches.
+    Inches,
+    "in"
+);
+unit!(
+    /// A linear speed in metres per second.
+    MetersPerSecond,
+    "m/s"
+);
+unit!(
+    /// A linear acceleration in metres per second squared.
+    MetersPerSecondSquared,
+    "m/s²"
+);
+unit!(
+    /// An angular speed in revolutions per minute.
+    Rpm,
+    "rpm"
+);
+
+const MILLIMETERS_PER_INCH: f64 = 25.4;
+
+impl Degrees {
+    #[inline]
+    /// Wraps the angle into the range (-180, 180].
+    pub fn wrap(self) -> Self {
+        let wrapped = self.0 % 360.0;
+        Self(if wrapped > 180.0 {
+            wrapped - 360.0
+        } else if wrapped <= -180.0 {
+            wrapped + 360.0
+        } else {
+            wrapped
+        })
+    }
+
+    #[inline]
+    /// Wraps the angle into the range [0, 360).
+    pub fn wrap_positive(self) -> Self {
+        let wrapped = self.0 % 360.0;
+        Self(if wrapped < 0.0 {
+            wrapped + 360.0
+        } else {
+            wrapped
+        })
+    }
+
+    #[inline]
+    /// Computes the shortest signed angle from this angle to `target`, bounded
+    /// by (-180, 180]. Positive values are clockwise, like headings.
+    pub fn angle_to(self, target: impl Into<Self>) -> Self {
+        (target.into() - self).wrap()
+    }
+}
+
+impl Radians {
+    #[inline]
+    /// Computes the sine of the angle.
+    pub fn sin(self) -> f64 {
+        self.0.sin()
+    }
+
+    #[inline]
+    /// Computes the cosine of the angle.
+    pub fn cos(self) -> f64 {
+        self.0.cos()
+    }
+
+    #[inline]
+    /// Computes the sine and cosine of the angle.
+    pub fn sin_cos(self) -> (f64, f64) {
+        self.0.sin_cos()
+    }
+}
+
+impl From<Degrees> for Radians {
+    #[inline]
+    fn from(angle: Degrees) -> Self {
+        Self(angle.0.to_radians())
+    }
+}
+
+impl From<Radians> for Degrees {
+    #[inline]
+    fn from(angle: Radians) -> Self {
+        Self(angle.0.to_degrees())
+    }
+}
+
+impl From<Inches> for Millimeters {
+    #[inline]
+    fn from(distance: Inches) -> Self {
+        Self(distance.0 * MILLIMETERS_PER_INCH)
+    }
+}
+
+impl From<Millimeters> for Inches {
+    #[inline]
+    fn from(distance: Millimeters) -> Self {
+        Self(distance.0 / MILLIMETERS_PER_INCH)
+    }
+}
+
+/// Converts between motor encoder readings and the distance or speed of a
+/// wheel driven by the motor.
+///
+/// # Example
+///
+/// ```
+/// // 3.25" wheels driven by 600RPM motors through a 36:48 gear ratio.
+/// let conversions = Conversions::new(Inches(3.25))
+///     .gearset(Gearset::SixToOne)
+///     .gear_ratio(36.0 / 48.0);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Conversions {
+    wheel_diameter: Millimeters,
+    gear_ratio: f64,
+    gearset: Gearset,
+    encoder_units: EncoderUnits,
+}
+
+impl Conversions {
+    #[inline]
+    /// Creates a new set of conversions for a wheel of the given diameter,
+    /// driven directly by a motor with a [`Gearset::EighteenToOne`] cartridge
+    /// measuring in [`EncoderUnits::Degrees`].
+    pub fn new(wheel_diameter: impl Into<Millimeters>) -> Self {
+        Self {
+            wheel_diameter: wheel_diameter.into(),
+            gear_ratio: 1.0,
+            gearset: Gearset::EighteenToOne,
+            encoder_units: EncoderUnits::Degrees,
+        }
+    }
+
+    #[inline]
+    /// Sets the external gear ratio, as the number of wheel revolutions per
+    /// revolution of the motor (i.e., driving teeth / driven teeth).
+    pub fn gear_ratio(mut self, ratio: f64) -> Self {
+        self.gear_ratio = ratio;
+        self
+    }
+
+    #[inline]
+    /// Sets the gear cartridge of the motor.
+    pub fn gearset(mut self, gearset: Gearset) -> Self {
+        self.gearset = gearset;
+        self
+    }
+
+    #[inline]
+    /// Sets the encoder units that the motor is configured to report.
+    pub fn encoder_units(mut self, units: EncoderUnits) -> Self {
+        self.encoder_units = units;
+        self
+    }
+
+    #[inline]
+    /// Gets the diameter of the wheel.
+    pub fn wheel_diameter(&self) -> Millimeters {
+        self.wheel_diameter
+    }
+
+    /// Gets the number of encoder units per revolution of the wheel.
+    pub fn ticks_per_rev(&self) -> f64 {
+        let motor = match (self.encoder_units, self.gearset) {
+            (EncoderUnits::EncoderTicks, Gearset::SixToOne) => 300.0,
+            (EncoderUnits::EncoderTicks, Gearset::EighteenToOne) => 900.0,
+            (EncoderUnits::EncoderTicks, Gearset::ThirtySixToOne) => 1800.0,
+            (EncoderUnits::Degrees, _) => 360.0,
+            (EncoderUnits::Rotations, _) => 1.0,
+        };
+        motor / self.gear_ratio
+    }
+
+    #[inline]
+    /// Converts an encoder reading to the distance travelled by the wheel.
+    pub fn ticks_to_distance(&self, ticks: f64) -> Millimeters {
+        self.circumference() * (ticks / self.ticks_per_rev())
+    }
+
+    #[inline]
+    /// Converts a distance travelled by the wheel to an encoder reading.
+    pub fn distance_to_ticks(&self, distance: impl Into<Millimeters>) -> f64 {
+        distance.into() / self.circumference() * self.ticks_per_rev()
+    }
+
+    #[inline]
+    /// Converts a motor velocity to the linear speed of the wheel.
+    pub fn rpm_to_speed(&self, rpm: Rpm) -> MetersPerSecond {
+        MetersPerSecond(rpm.0 * self.gear_ratio * self.circumference().0 / 60000.0)
+    }
+
+    #[inline]
+    /// Converts a linear speed of the wheel to a motor velocity.
+    pub fn speed_to_rpm(&self, speed: MetersPerSecond) -> Rpm {
+        Rpm(speed.0 * 60000.0 / (self.gear_ratio * self.circumference().0))
+    }
+
+    /// Gets the free speed of the motor cartridge.
+    pub fn max_rpm(&self) -> Rpm {
+        Rpm(match self.gearset {
+            Gearset::SixToOne => 600.0,
+            Gearset::EighteenToOne => 200.0,
+            Gearset::ThirtySixToOne => 100.0,
+        })
+    }
+
+    #[inline]
+    /// Gets the linear speed of the wheel at the free speed of the motor.
+    pub fn max_speed(&self) -> MetersPerSecond {
+        self.rpm_to_speed(self.max_rpm())
+    }
+
+    #[inline]
+    fn circumference(&self) -> Millimeters {
+        self.wheel_diameter * core::f64::consts::PI
+    }
+}
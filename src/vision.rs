@@ -0,0 +1,282 @@
+//! # Vision Sensor API.
+//!
+//! Colour signatures are configured and saved to the sensor with the VEX
+//! Vision Utility; the sensor then reports the objects matching each
+//! signature, from largest to smallest.
+//!
+//! A [`VisionTracker`] follows the largest object of one signature, smoothing
+//! its position and reporting its offset from the centre of the sensor's
+//! field of view, ready to be fed to a
+//! [`FeedbackController`](crate::control::FeedbackController) for aiming.
+//!
+//! # Example
+//!
+//! ```
+//! let tracker = VisionTracker::new(vision, 1, 0.5)?;
+//! let mut pid = Pid::new(6000.0, 0.0, 400.0);
+//! loop {
+//!     let turn = match tracker.read()? {
+//!         Some(target) => pid.step(0.0, -target.x),
+//!         None => 0.0,
+//!     };
+//!     drivetrain.left().move_voltage(turn as i32)?;
+//!     drivetrain.right().move_voltage(-turn as i32)?;
+//!     Task::delay(Duration::from_millis(20));
+//! }
+//! ```
+
+use crate::{
+    bindings, diagnostics,
+    error::{get_errno, DeviceError, DevicePort},
+    filter::{Ema, Filter},
+    rtos::{DataSource, Mutex},
+};
+
+/// The width of the sensor's field of view, in pixels.
+pub const VISION_FOV_WIDTH: i16 = 316;
+
+/// The height of the sensor's field of view, in pixels.
+pub const VISION_FOV_HEIGHT: i16 = 212;
+
+/// The signature reported by PROS when an object cannot be read.
+const VISION_OBJECT_ERR_SIG: u16 = 255;
+
+/// A struct which represents a V5 smart port configured as a vision sensor.
+pub struct VisionSensor {
+    port: u8,
+}
+
+impl VisionSensor {
+    /// Constructs a new vision sensor.
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it allows the user to create multiple
+    /// mutable references to the same vision sensor. You likely want to
+    /// implement [`Robot::new()`](crate::robot::Robot::new()) instead.
+    pub unsafe fn new(port: u8) -> VisionSensor {
+        VisionSensor { port }
+    }
+
+    /// Gets the number of objects currently detected by the sensor.
+    pub fn get_object_count(&self) -> Result<usize, VisionSensorError> {
+        match unsafe { bindings::vision_get_object_count(self.port) } {
+            bindings::PROS_ERR_ => Err(self.error()),
+            x => Ok(x as usize),
+        }
+    }
+
+    /// Gets the `size_id`th largest object detected by the sensor (starting
+    /// from 0), or `None` if there are not that many objects.
+    pub fn get_by_size(&self, size_id: u32) -> Result<Option<VisionObject>, VisionSensorError> {
+        self.object(unsafe { bindings::vision_get_by_size(self.port, size_id) })
+    }
+
+    /// Gets the `size_id`th largest object of the given signature (from 1 to
+    /// 7) detected by the sensor (starting from 0), or `None` if there are not
+    /// that many such objects.
+    pub fn get_by_sig(
+        &self,
+        size_id: u32,
+        signature: u8,
+    ) -> Result<Option<VisionObject>, VisionSensorError> {
+        self.object(unsafe { bindings::vision_get_by_sig(self.port, size_id, signature as u32) })
+    }
+
+    /// Sets the origin of the coordinates reported by the sensor.
+    pub fn set_zero_point(&mut self, zero_point: VisionZeroPoint) -> Result<(), VisionSensorError> {
+        let zero_point = match zero_point {
+            VisionZeroPoint::TopLeft => bindings::vision_zero_E_VISION_ZERO_TOPLEFT,
+            VisionZeroPoint::Center => bindings::vision_zero_E_VISION_ZERO_CENTER,
+        };
+        match unsafe { bindings::vision_set_zero_point(self.port, zero_point) } {
+            bindings::PROS_ERR_ => Err(self.error()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Gets the exposure setting of the sensor, from 0 to 150.
+    pub fn get_exposure(&self) -> Result<u8, VisionSensorError> {
+        match unsafe { bindings::vision_get_exposure(self.port) } {
+            bindings::PROS_ERR_ => Err(self.error()),
+            x => Ok(x as u8),
+        }
+    }
+
+    /// Sets the exposure setting of the sensor, from 0 to 150.
+    pub fn set_exposure(&mut self, exposure: u8) -> Result<(), VisionSensorError> {
+        match unsafe { bindings::vision_set_exposure(self.port, exposure) } {
+            bindings::PROS_ERR_ => Err(self.error()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Sets the colour of the sensor's LED, as a 24-bit RGB value.
+    pub fn set_led(&mut self, rgb: u32) -> Result<(), VisionSensorError> {
+        match unsafe { bindings::vision_set_led(self.port, rgb as i32) } {
+            bindings::PROS_ERR_ => Err(self.error()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the sensor's LED to its default behaviour, showing the colour of
+    /// the most prominent detected object.
+    pub fn clear_led(&mut self) -> Result<(), VisionSensorError> {
+        match unsafe { bindings::vision_clear_led(self.port) } {
+            bindings::PROS_ERR_ => Err(self.error()),
+            _ => Ok(()),
+        }
+    }
+
+    fn object(
+        &self,
+        object: bindings::vision_object_s_t,
+    ) -> Result<Option<VisionObject>, VisionSensorError> {
+        if object.signature != VISION_OBJECT_ERR_SIG {
+            Ok(Some(VisionObject::from(object)))
+        } else if get_errno() == libc::EDOM {
+            Ok(None)
+        } else {
+            Err(self.error())
+        }
+    }
+
+    fn error(&self) -> DeviceError {
+        diagnostics::record_error(DeviceError::from_errno(
+            DevicePort::Smart(self.port),
+            "a vision sensor",
+        ))
+    }
+}
+
+/// Represents the origin of the coordinates reported by a vision sensor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VisionZeroPoint {
+    /// The top left of the field of view, which is the default.
+    TopLeft,
+    /// The centre of the field of view.
+    Center,
+}
+
+/// Represents an object detected by a vision sensor. Coordinates are in pixels
+/// relative to the [zero point](VisionZeroPoint), with y increasing
+/// downwards.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VisionObject {
+    /// The signature of the object, from 1 to 7, or the colour code.
+    pub signature: u16,
+    /// The left edge of the object.
+    pub left: i16,
+    /// The top edge of the object.
+    pub top: i16,
+    /// The width of the object.
+    pub width: i16,
+    /// The height of the object.
+    pub height: i16,
+    /// The x coordinate of the centre of the object.
+    pub x_middle: i16,
+    /// The y coordinate of the centre of the object.
+    pub y_middle: i16,
+    /// The angle of a colour code object, in tenths of a degree.
+    pub angle: u16,
+}
+
+impl From<bindings::vision_object_s_t> for VisionObject {
+    fn from(object: bindings::vision_object_s_t) -> Self {
+        Self {
+            signature: object.signature,
+            left: object.left_coord,
+            top: object.top_coord,
+            width: object.width,
+            height: object.height,
+            x_middle: object.x_middle_coord,
+            y_middle: object.y_middle_coord,
+            angle: object.angle,
+        }
+    }
+}
+
+/// Represents possible errors for vision sensor operations. This is an alias
+/// of [`DeviceError`].
+pub type VisionSensorError = DeviceError;
+
+/// Tracks the largest object of one signature seen by a vision sensor.
+///
+/// The position of the object is smoothed with an exponential moving average,
+/// which is reset whenever the object is lost.
+pub struct VisionTracker {
+    sensor: VisionSensor,
+    signature: u8,
+    filters: Mutex<(Ema, Ema)>,
+}
+
+impl VisionTracker {
+    /// Creates a new tracker for objects of the given signature, smoothing
+    /// their position with the given factor (see [`Ema::new()`]).
+    ///
+    /// This sets the sensor's zero point to
+    /// [`VisionZeroPoint::Center`].
+    pub fn new(
+        mut sensor: VisionSensor,
+        signature: u8,
+        smoothing: f64,
+    ) -> Result<Self, VisionSensorError> {
+        sensor.set_zero_point(VisionZeroPoint::Center)?;
+        Ok(Self {
+            sensor,
+            signature,
+            filters: Mutex::new((Ema::new(smoothing), Ema::new(smoothing))),
+        })
+    }
+
+    #[inline]
+    /// Gets the underlying sensor.
+    pub fn sensor(&mut self) -> &mut VisionSensor {
+        &mut self.sensor
+    }
+
+    #[inline]
+    /// Gets the signature being tracked.
+    pub fn signature(&self) -> u8 {
+        self.signature
+    }
+}
+
+impl DataSource for VisionTracker {
+    type Data = Option<VisionTarget>;
+
+    type Error = VisionSensorError;
+
+    fn read(&self) -> Result<Self::Data, Self::Error> {
+        let mut filters = self.filters.lock();
+        match self.sensor.get_by_sig(0, self.signature)? {
+            Some(object) => Ok(Some(VisionTarget {
+                x: filters
+                    .0
+                    .filter(object.x_middle as f64 / (VISION_FOV_WIDTH / 2) as f64),
+                y: filters
+                    .1
+                    .filter(object.y_middle as f64 / (VISION_FOV_HEIGHT / 2) as f64),
+                object,
+            })),
+            None => {
+                filters.0.reset();
+                filters.1.reset();
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Represents the object followed by a [`VisionTracker`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VisionTarget {
+    /// The smoothed horizontal offset of the object from the centre of the
+    /// field of view, from -1 at the left edge to 1 at the right edge.
+    pub x: f64,
+    /// The smoothed vertical offset of the object from the centre of the field
+    /// of view, from -1 at the top edge to 1 at the bottom edge.
+    pub y: f64,
+    /// The latest unsmoothed reading of the object.
+    pub object: VisionObject,
+}